@@ -0,0 +1,14 @@
+-- Holochain Bridge
+-- Links API songs to their catalog entries on the DHT
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        IF NOT EXISTS (SELECT FROM information_schema.columns
+                       WHERE table_name = 'songs' AND column_name = 'dht_action_hash') THEN
+            ALTER TABLE songs ADD COLUMN dht_action_hash VARCHAR(64);
+        END IF;
+    END IF;
+END $$;
+
+COMMENT ON COLUMN songs.dht_action_hash IS 'ActionHash of the catalog Song entry (base64, uhCkk...)';
@@ -0,0 +1,29 @@
+-- Song Moderation
+-- Soft-delete/hide status on songs plus an audit trail of moderator actions
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        IF NOT EXISTS (SELECT FROM information_schema.columns
+                       WHERE table_name = 'songs' AND column_name = 'status') THEN
+            ALTER TABLE songs ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'active';
+            ALTER TABLE songs ADD CONSTRAINT valid_song_status
+                CHECK (status IN ('active', 'hidden', 'removed'));
+            CREATE INDEX idx_songs_status ON songs(status);
+        END IF;
+    END IF;
+END $$;
+
+-- Moderation audit log - one row per status change
+CREATE TABLE IF NOT EXISTS song_moderation_log (
+    id SERIAL PRIMARY KEY,
+    song_id UUID NOT NULL,
+    previous_status VARCHAR(16) NOT NULL,
+    new_status VARCHAR(16) NOT NULL,
+    reason TEXT NOT NULL,
+    actor_address VARCHAR(42) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_song_moderation_song ON song_moderation_log(song_id);
+CREATE INDEX IF NOT EXISTS idx_song_moderation_actor ON song_moderation_log(actor_address);
@@ -0,0 +1,16 @@
+-- Song Genres
+-- Lowercased genre tags for filtering and genre-level analytics
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        IF NOT EXISTS (SELECT FROM information_schema.columns
+                       WHERE table_name = 'songs' AND column_name = 'genres') THEN
+            ALTER TABLE songs ADD COLUMN genres TEXT[] NOT NULL DEFAULT '{}';
+            CREATE INDEX idx_songs_genres ON songs USING GIN (genres);
+        END IF;
+    END IF;
+END $$;
+
+-- Window queries in genre analytics filter plays by song and time
+CREATE INDEX IF NOT EXISTS idx_plays_song_timestamp ON plays(song_id, timestamp);
@@ -0,0 +1,24 @@
+-- Cover-Art Thumbnails
+-- WebP renditions of cover images, keyed by source CID and size
+
+CREATE TABLE IF NOT EXISTS image_thumbnails (
+    source_cid VARCHAR(100) NOT NULL,
+    size INTEGER NOT NULL,
+    thumb_cid VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    PRIMARY KEY (source_cid, size),
+    CONSTRAINT valid_thumbnail_size CHECK (size IN (128, 512))
+);
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        IF NOT EXISTS (SELECT FROM information_schema.columns
+                       WHERE table_name = 'songs' AND column_name = 'cover_cid') THEN
+            ALTER TABLE songs ADD COLUMN cover_cid VARCHAR(100);
+            ALTER TABLE songs ADD COLUMN cover_thumb_128_cid VARCHAR(100);
+            ALTER TABLE songs ADD COLUMN cover_thumb_512_cid VARCHAR(100);
+        END IF;
+    END IF;
+END $$;
@@ -0,0 +1,15 @@
+-- Payment Finality
+-- Chain head as last seen by the indexer, used to derive per-payment
+-- confirmation depth and finality as the chain advances
+
+CREATE TABLE IF NOT EXISTS indexer_chain_state (
+    id SMALLINT PRIMARY KEY DEFAULT 1,
+    head_block BIGINT NOT NULL,
+    -- Highest block the indexer treats as confirmed (head - confirmations)
+    safe_block BIGINT NOT NULL,
+    -- Highest block considered final (head - finality blocks)
+    finalized_block BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT single_row CHECK (id = 1)
+);
@@ -0,0 +1,61 @@
+-- Artist Stats
+-- Denormalized per-artist totals maintained by a trigger on songs, so
+-- artist analytics read one row instead of summing every song.
+--
+-- The trigger applies the row's own delta (NEW - OLD), so totals change
+-- in the same transaction as the song update and roll back with it.
+
+CREATE TABLE IF NOT EXISTS artist_stats (
+    -- Lowercased artist address
+    artist_address TEXT PRIMARY KEY,
+    total_songs BIGINT NOT NULL DEFAULT 0,
+    total_plays BIGINT NOT NULL DEFAULT 0,
+    total_earnings NUMERIC NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE OR REPLACE FUNCTION apply_artist_stats_delta(
+    p_artist TEXT,
+    p_songs BIGINT,
+    p_plays BIGINT,
+    p_earnings NUMERIC
+) RETURNS VOID AS $$
+BEGIN
+    IF p_songs = 0 AND p_plays = 0 AND p_earnings = 0 THEN
+        RETURN;
+    END IF;
+
+    -- Single atomic upsert; concurrent plays serialize on the row lock
+    INSERT INTO artist_stats (artist_address, total_songs, total_plays, total_earnings)
+    VALUES (LOWER(p_artist), p_songs, p_plays, p_earnings)
+    ON CONFLICT (artist_address) DO UPDATE SET
+        total_songs = artist_stats.total_songs + EXCLUDED.total_songs,
+        total_plays = artist_stats.total_plays + EXCLUDED.total_plays,
+        total_earnings = artist_stats.total_earnings + EXCLUDED.total_earnings,
+        updated_at = NOW();
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION songs_artist_stats_trigger() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM apply_artist_stats_delta(
+            OLD.artist_address, -1, -COALESCE(OLD.plays, 0), -COALESCE(OLD.earnings, 0));
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM apply_artist_stats_delta(
+            NEW.artist_address, 1, COALESCE(NEW.plays, 0), COALESCE(NEW.earnings, 0));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        DROP TRIGGER IF EXISTS songs_artist_stats ON songs;
+        CREATE TRIGGER songs_artist_stats
+            AFTER INSERT OR DELETE OR UPDATE OF plays, earnings, artist_address ON songs
+            FOR EACH ROW EXECUTE FUNCTION songs_artist_stats_trigger();
+    END IF;
+END $$;
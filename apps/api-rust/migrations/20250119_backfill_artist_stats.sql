@@ -0,0 +1,20 @@
+-- Artist Stats Backfill
+-- Recompute artist_stats from songs. Safe to re-run: totals are rebuilt,
+-- not added to, and songs are locked against writes while it runs.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        LOCK TABLE songs IN SHARE MODE;
+
+        DELETE FROM artist_stats;
+
+        INSERT INTO artist_stats (artist_address, total_songs, total_plays, total_earnings)
+        SELECT LOWER(artist_address),
+               COUNT(*),
+               COALESCE(SUM(plays), 0),
+               COALESCE(SUM(earnings), 0)
+        FROM songs
+        GROUP BY LOWER(artist_address);
+    END IF;
+END $$;
@@ -0,0 +1,40 @@
+-- Chain Event Log
+-- Append-only log of decoded contract events. The indexer only writes
+-- here; payments, song registrations and future read models are
+-- projections rebuilt from this log without re-reading the chain.
+
+CREATE TABLE IF NOT EXISTS events (
+    id BIGSERIAL PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    block_timestamp TIMESTAMP WITH TIME ZONE NOT NULL,
+    tx_hash VARCHAR(66) NOT NULL,
+    log_index INTEGER NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    data JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    -- A transaction can emit several events; the log index tells them apart
+    CONSTRAINT unique_event_log UNIQUE (tx_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_block ON events(block_number);
+CREATE INDEX IF NOT EXISTS idx_events_type ON events(event_type, id);
+
+-- Per-projection position in the event log (last applied events.id)
+CREATE TABLE IF NOT EXISTS projection_cursors (
+    name VARCHAR(50) PRIMARY KEY,
+    last_event_id BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Payments are keyed by (tx_hash, log_index) so multiple payments in one
+-- transaction are all kept
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT FROM information_schema.columns
+                   WHERE table_name = 'payments' AND column_name = 'log_index') THEN
+        ALTER TABLE payments ADD COLUMN log_index INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE payments DROP CONSTRAINT IF EXISTS payments_tx_hash_key;
+        ALTER TABLE payments ADD CONSTRAINT unique_payment_log UNIQUE (tx_hash, log_index);
+    END IF;
+END $$;
@@ -0,0 +1,19 @@
+-- Play Exclusions
+-- Plays whose song was deleted or removed by moderation are flagged
+-- rather than deleted, so the history survives but earnings and
+-- analytics can skip them. Set by the orphaned-plays reconciliation.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'plays') THEN
+        IF NOT EXISTS (SELECT FROM information_schema.columns
+                       WHERE table_name = 'plays' AND column_name = 'excluded_reason') THEN
+            ALTER TABLE plays ADD COLUMN excluded_reason VARCHAR(16);
+            ALTER TABLE plays ADD COLUMN excluded_at TIMESTAMP WITH TIME ZONE;
+            ALTER TABLE plays ADD CONSTRAINT valid_play_exclusion
+                CHECK (excluded_reason IN ('song_missing', 'song_removed'));
+            CREATE INDEX idx_plays_excluded ON plays(excluded_reason)
+                WHERE excluded_reason IS NOT NULL;
+        END IF;
+    END IF;
+END $$;
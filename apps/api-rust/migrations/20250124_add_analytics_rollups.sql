@@ -0,0 +1,34 @@
+-- Analytics Rollups
+-- Per-day play aggregates so analytics read history from one row per day
+-- instead of scanning raw plays. Days are rebuilt whole, so re-running a
+-- day's rollup replaces its rows rather than adding to them.
+
+CREATE TABLE IF NOT EXISTS daily_song_stats (
+    day DATE NOT NULL,
+    song_id UUID NOT NULL,
+    plays BIGINT NOT NULL DEFAULT 0,
+    earnings NUMERIC NOT NULL DEFAULT 0,
+    unique_listeners BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (song_id, day)
+);
+
+CREATE TABLE IF NOT EXISTS daily_artist_stats (
+    day DATE NOT NULL,
+    -- Lowercased artist address
+    artist_address TEXT NOT NULL,
+    plays BIGINT NOT NULL DEFAULT 0,
+    earnings NUMERIC NOT NULL DEFAULT 0,
+    unique_listeners BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (artist_address, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_daily_song_stats_day ON daily_song_stats(day);
+CREATE INDEX IF NOT EXISTS idx_daily_artist_stats_day ON daily_artist_stats(day);
+
+-- Days whose rollups are complete; later days are read from raw plays
+CREATE TABLE IF NOT EXISTS analytics_rollup_days (
+    day DATE PRIMARY KEY,
+    rolled_up_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
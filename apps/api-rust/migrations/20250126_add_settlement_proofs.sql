@@ -0,0 +1,18 @@
+-- Settlement Proofs
+-- Result of checking a settlement batch's payout transaction on-chain,
+-- one row per batch (replaced when the batch's tx_hash changes)
+
+CREATE TABLE IF NOT EXISTS settlement_proofs (
+    -- Holochain ActionHash of the SettlementBatch
+    batch_hash TEXT PRIMARY KEY,
+    tx_hash VARCHAR(66) NOT NULL,
+    block_number BIGINT,
+    -- Lowercased artist payment address the batch promised to pay
+    recipient VARCHAR(42) NOT NULL,
+    expected_amount_wei VARCHAR(78) NOT NULL,
+    paid_amount_wei VARCHAR(78) NOT NULL,
+    verified BOOLEAN NOT NULL,
+    checked_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_settlement_proofs_recipient ON settlement_proofs(recipient);
@@ -0,0 +1,23 @@
+-- Pinning Queue
+-- Song CIDs that couldn't be pinned when the song was created (IPFS
+-- unreachable); a background worker retries them with backoff
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        ALTER TABLE songs ADD COLUMN IF NOT EXISTS pinning_pending BOOLEAN NOT NULL DEFAULT FALSE;
+
+        CREATE TABLE IF NOT EXISTS pinning_queue (
+            song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+            cid VARCHAR(100) NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+            PRIMARY KEY (song_id, cid)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_pinning_queue_due ON pinning_queue(next_attempt_at);
+    END IF;
+END $$;
@@ -0,0 +1,44 @@
+-- Play Anomalies
+-- Songs whose play rate spiked far above their trailing baseline, driven
+-- mostly by brand-new listeners (a play-farming signature). Flagged songs
+-- can have earnings paused: their plays are still counted but held with
+-- excluded_reason = 'earnings_paused' until an admin resolves the flag.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        ALTER TABLE songs ADD COLUMN IF NOT EXISTS earnings_paused BOOLEAN NOT NULL DEFAULT FALSE;
+
+        CREATE TABLE IF NOT EXISTS play_anomalies (
+            id BIGSERIAL PRIMARY KEY,
+            song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+            window_start TIMESTAMP WITH TIME ZONE NOT NULL,
+            window_end TIMESTAMP WITH TIME ZONE NOT NULL,
+            plays BIGINT NOT NULL,
+            -- Plays per window over the trailing baseline
+            baseline_mean DOUBLE PRECISION NOT NULL,
+            baseline_stddev DOUBLE PRECISION NOT NULL,
+            z_score DOUBLE PRECISION NOT NULL,
+            -- Share of window plays from listeners first seen recently
+            new_listener_share DOUBLE PRECISION NOT NULL,
+            earnings_paused BOOLEAN NOT NULL DEFAULT FALSE,
+            status VARCHAR(16) NOT NULL DEFAULT 'open',
+            detected_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            resolved_at TIMESTAMP WITH TIME ZONE,
+            resolved_by VARCHAR(42),
+
+            CONSTRAINT valid_anomaly_status CHECK (status IN ('open', 'dismissed', 'confirmed'))
+        );
+
+        -- At most one open flag per song
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_play_anomalies_open
+            ON play_anomalies(song_id) WHERE status = 'open';
+        CREATE INDEX IF NOT EXISTS idx_play_anomalies_detected ON play_anomalies(detected_at DESC);
+    END IF;
+
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'plays') THEN
+        ALTER TABLE plays DROP CONSTRAINT IF EXISTS valid_play_exclusion;
+        ALTER TABLE plays ADD CONSTRAINT valid_play_exclusion
+            CHECK (excluded_reason IN ('song_missing', 'song_removed', 'earnings_paused'));
+    END IF;
+END $$;
@@ -0,0 +1,13 @@
+-- Play Settlement
+-- Marks plays covered by an on-chain payment, so a listener's outstanding
+-- debt is their paying plays with no settled_at yet. Set by the payments
+-- projection when the listener pays for the song.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'plays') THEN
+        ALTER TABLE plays ADD COLUMN IF NOT EXISTS settled_at TIMESTAMP WITH TIME ZONE;
+        CREATE INDEX IF NOT EXISTS idx_plays_unsettled
+            ON plays(LOWER(listener_address)) WHERE settled_at IS NULL;
+    END IF;
+END $$;
@@ -0,0 +1,28 @@
+-- FX Rates
+-- Fiat value of one xDAI over time, for display and accounting only
+-- (amounts are always stored in xDAI/wei). payment_fx_rates keeps the
+-- rates in force when each payment was made, so statements converted
+-- at settlement time never change afterwards.
+
+CREATE TABLE IF NOT EXISTS fx_rates (
+    id BIGSERIAL PRIMARY KEY,
+    currency VARCHAR(8) NOT NULL,
+    -- Units of currency per xDAI
+    rate DOUBLE PRECISION NOT NULL CHECK (rate > 0),
+    fetched_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_fx_rates_currency_time ON fx_rates(currency, fetched_at DESC);
+
+-- Not tied to payments by foreign key: rebuilding the payments projection
+-- must not lose the rates recorded when the payments were first seen
+CREATE TABLE IF NOT EXISTS payment_fx_rates (
+    tx_hash VARCHAR(66) NOT NULL,
+    log_index INTEGER NOT NULL,
+    currency VARCHAR(8) NOT NULL,
+    rate DOUBLE PRECISION NOT NULL,
+    -- When the rate was fetched (at or before the payment)
+    rate_at TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    PRIMARY KEY (tx_hash, log_index, currency)
+);
@@ -0,0 +1,20 @@
+-- Live play events: every new play sends NOTIFY play_recorded with its id,
+-- so the API can push it to artist dashboards over SSE. Notifications are
+-- delivered when the recording transaction commits.
+
+CREATE OR REPLACE FUNCTION notify_play_recorded() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('play_recorded', NEW.id::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'plays') THEN
+        DROP TRIGGER IF EXISTS plays_notify_recorded ON plays;
+        CREATE TRIGGER plays_notify_recorded
+            AFTER INSERT ON plays
+            FOR EACH ROW EXECUTE FUNCTION notify_play_recorded();
+    END IF;
+END $$;
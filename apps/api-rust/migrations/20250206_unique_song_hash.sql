@@ -0,0 +1,11 @@
+-- Idempotent Song Registration
+-- song_hash is derived from the audio CID and canonical metadata, so
+-- re-submitting the same song resolves to the existing row. The unique
+-- index backs `ON CONFLICT (song_hash)` in create_song.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_songs_song_hash ON songs (song_hash);
+    END IF;
+END $$;
@@ -0,0 +1,11 @@
+-- Song Previews
+-- Short faded clip of a song, streamed in place of NFT-gated, staking-gated
+-- and download songs to listeners without access.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        ALTER TABLE songs ADD COLUMN IF NOT EXISTS preview_cid TEXT;
+        CREATE INDEX IF NOT EXISTS idx_songs_ipfs_hash ON songs(ipfs_hash);
+    END IF;
+END $$;
@@ -0,0 +1,23 @@
+-- Play Archive
+-- Raw plays past the retention window move to plays_archive once their
+-- day is rolled up. Only finished plays move: unsettled paying plays and
+-- plays held by an anomaly stay in plays until settled or released.
+-- plays_with_archive is what history readers (rollups, statements,
+-- all-time counts) query, so archiving never changes their totals.
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'plays') THEN
+        -- Same columns as plays, so rows move with INSERT ... SELECT *
+        CREATE TABLE IF NOT EXISTS plays_archive (LIKE plays INCLUDING DEFAULTS);
+        CREATE INDEX IF NOT EXISTS idx_plays_archive_song_timestamp
+            ON plays_archive(song_id, timestamp);
+        CREATE INDEX IF NOT EXISTS idx_plays_archive_listener
+            ON plays_archive(listener_address, timestamp);
+
+        CREATE OR REPLACE VIEW plays_with_archive AS
+            SELECT * FROM plays
+            UNION ALL
+            SELECT * FROM plays_archive;
+    END IF;
+END $$;
@@ -0,0 +1,16 @@
+-- Settlement Submissions
+-- One row per batch sent to its settlement chain. submit_settlement claims
+-- the row before sending, so a batch is never paid twice, and resumes from
+-- the recorded tx_hash if marking the batch submitted failed.
+
+CREATE TABLE IF NOT EXISTS settlement_submissions (
+    -- Holochain ActionHash of the SettlementBatch
+    batch_hash TEXT PRIMARY KEY,
+    -- claimed: being sent; sent: tx_hash recorded; marked: batch Submitted
+    state VARCHAR(16) NOT NULL DEFAULT 'claimed'
+        CHECK (state IN ('claimed', 'sent', 'marked')),
+    tx_hash VARCHAR(66),
+    chain_id BIGINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
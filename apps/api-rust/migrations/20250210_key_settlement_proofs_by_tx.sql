@@ -0,0 +1,14 @@
+-- Settlement Proofs per transaction
+-- Proofs are kept per (batch, transaction), so a batch whose tx_hash
+-- changes is checked again, and a transaction may verify one batch only.
+
+-- Earlier proofs counted FLOW transfers to the artist rather than the
+-- verifier's BatchSettled event; check them again
+UPDATE settlement_proofs SET verified = FALSE;
+UPDATE settlement_proofs SET tx_hash = LOWER(tx_hash);
+
+ALTER TABLE settlement_proofs DROP CONSTRAINT IF EXISTS settlement_proofs_pkey;
+ALTER TABLE settlement_proofs ADD PRIMARY KEY (batch_hash, tx_hash);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_settlement_proofs_verified_tx
+    ON settlement_proofs(tx_hash) WHERE verified;
@@ -0,0 +1,16 @@
+-- Catalog Sync
+-- When a song was last looked up in the catalog, so the dht_action_hash
+-- backfill checks the songs it tried longest ago first
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        IF NOT EXISTS (SELECT FROM information_schema.columns
+                       WHERE table_name = 'songs' AND column_name = 'dht_checked_at') THEN
+            ALTER TABLE songs ADD COLUMN dht_checked_at TIMESTAMP WITH TIME ZONE;
+        END IF;
+
+        CREATE INDEX IF NOT EXISTS idx_songs_unlinked
+            ON songs (dht_checked_at NULLS FIRST) WHERE dht_action_hash IS NULL;
+    END IF;
+END $$;
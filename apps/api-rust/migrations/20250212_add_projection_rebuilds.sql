@@ -0,0 +1,9 @@
+-- Projection Rebuilds
+-- Rebuild requests (`INDEXER_REBUILD_PROJECTIONS` entries) that have
+-- completed, so a restart with the variable still set doesn't wipe and
+-- replay the projection again
+
+CREATE TABLE IF NOT EXISTS projection_rebuilds (
+    request VARCHAR(100) PRIMARY KEY,
+    completed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
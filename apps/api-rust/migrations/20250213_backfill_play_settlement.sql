@@ -0,0 +1,45 @@
+-- Backfill Play Settlement
+-- Settle plays for payments projected before the payments projection
+-- matched plays to the amount paid. Each payment settles the listener's
+-- oldest unsettled paying plays of the song from before it, while their
+-- running total fits in the amount; plays already settled at the payment's
+-- time count against it (see `settle_plays` in services/projections.rs).
+
+DO $$
+DECLARE
+    payment RECORD;
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'plays')
+       AND EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'songs') THEN
+        FOR payment IN
+            SELECT song_id, listener_address, amount_wei, timestamp
+            FROM payments
+            ORDER BY timestamp, tx_hash, log_index
+        LOOP
+            WITH budget AS (
+                SELECT payment.amount_wei::numeric / 1e18 - COALESCE(SUM(p.amount), 0)
+                       AS remaining
+                FROM plays p
+                JOIN songs s ON s.id = p.song_id
+                WHERE s.song_id = payment.song_id
+                  AND LOWER(p.listener_address) = LOWER(payment.listener_address)
+                  AND p.settled_at = payment.timestamp
+            ),
+            candidates AS (
+                SELECT p.id, SUM(p.amount) OVER (ORDER BY p.timestamp, p.id) AS running
+                FROM plays p
+                JOIN songs s ON s.id = p.song_id
+                WHERE s.song_id = payment.song_id
+                  AND LOWER(p.listener_address) = LOWER(payment.listener_address)
+                  AND p.timestamp <= payment.timestamp
+                  AND p.settled_at IS NULL
+                  AND p.amount > 0
+                  AND (p.excluded_reason IS NULL OR p.excluded_reason = 'earnings_paused')
+            )
+            UPDATE plays
+            SET settled_at = payment.timestamp
+            FROM candidates c, budget b
+            WHERE plays.id = c.id AND c.running <= b.remaining;
+        END LOOP;
+    END IF;
+END $$;
@@ -0,0 +1,15 @@
+-- Play Anomalies
+-- At most one flag per song and window, so a dismissed flag isn't raised
+-- again by the next detection run over the same window
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'play_anomalies') THEN
+        DELETE FROM play_anomalies a
+        USING play_anomalies b
+        WHERE a.song_id = b.song_id AND a.window_start = b.window_start AND a.id > b.id;
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_play_anomalies_window
+            ON play_anomalies(song_id, window_start);
+    END IF;
+END $$;
@@ -0,0 +1,13 @@
+-- Settlement Submissions
+-- The signed transaction is recorded before it is broadcast, so a send
+-- that fails after reaching the chain keeps the claim and a retry resends
+-- the same transaction instead of paying again
+
+ALTER TABLE settlement_submissions ADD COLUMN IF NOT EXISTS raw_tx TEXT;
+ALTER TABLE settlement_submissions ADD COLUMN IF NOT EXISTS nonce BIGINT;
+
+-- claimed: being signed; signed: tx_hash and raw_tx recorded, maybe
+-- broadcast; sent: broadcast; marked: batch Submitted
+ALTER TABLE settlement_submissions DROP CONSTRAINT IF EXISTS settlement_submissions_state_check;
+ALTER TABLE settlement_submissions ADD CONSTRAINT settlement_submissions_state_check
+    CHECK (state IN ('claimed', 'signed', 'sent', 'marked'));
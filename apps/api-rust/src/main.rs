@@ -5,23 +5,44 @@
 //! future Holochain integration.
 
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
-    http::StatusCode,
+    http::{header, HeaderValue, Method, StatusCode},
     Json,
     extract::State,
 };
 use ethers::types::Address;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use std::time::Duration;
+use tokio::sync::watch;
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod routes;
 mod services;
 mod models;
 
-use services::indexer::{IndexerConfig, spawn_indexer};
+use services::anomalies::{AnomalyConfig, spawn_anomaly_detector};
+use services::auth::AuthService;
+use services::blockchain::BlockchainService;
+use services::catalog_sync::spawn_catalog_backfill;
+use services::fx::{FxConfig, spawn_fx_refresh};
+use services::gateways::GatewayService;
+use services::cdn::CdnRouter;
+use services::holochain::HolochainService;
+use services::indexer::{IndexerConfig, IndexerMonitor, spawn_indexer};
+use services::live::{PlayFeed, spawn_play_listener};
+use services::pinning::{PinConfig, spawn_pin_retries};
+use services::rollups::{RollupConfig, spawn_rollups};
+use services::retention::{RetentionConfig, spawn_play_archiver};
+use services::transcode::{TranscodeService, DEFAULT_FFMPEG_TIMEOUT_SECS};
+use services::upload_limiter::{UploadLimiter, UploadMetrics};
+use services::upload_progress::UploadTracker;
 
 /// Application state shared across handlers
 #[derive(Clone)]
@@ -29,6 +50,36 @@ pub struct AppState {
     pub db_pool: sqlx::PgPool,
     pub redis: redis::Client,
     pub ipfs_client: ipfs_api_backend_hyper::IpfsClient,
+    /// Holochain conductor bridge (None until HOLOCHAIN_GATEWAY_URL is set)
+    pub holochain: Option<Arc<HolochainService>>,
+    /// Chain reads for payout checks and live strategy parameters (None until ROUTER_ADDRESS is set)
+    pub blockchain: Option<Arc<BlockchainService>>,
+    /// Upload transcoder (None until FFMPEG_PATH is set)
+    pub transcoder: Option<Arc<TranscodeService>>,
+    /// Wallet sign-in and session tokens
+    pub auth: Arc<AuthService>,
+    /// IPFS gateway pool with failover
+    pub gateways: Arc<GatewayService>,
+    /// Community CDN routing for streams (None until HOLOCHAIN_GATEWAY_URL is set)
+    pub cdn: Option<Arc<CdnRouter>>,
+    /// Upload concurrency and in-flight byte limits
+    pub upload_limiter: Arc<UploadLimiter>,
+    /// Live progress for uploads started with an `upload_id`
+    pub upload_progress: Arc<UploadTracker>,
+    /// Newly recorded plays for artist live feeds
+    pub play_feed: Arc<PlayFeed>,
+    /// Event indexer progress (None until ROUTER_ADDRESS is set)
+    pub indexer: Option<Arc<IndexerMonitor>>,
+    /// IPFS chunker spec for uploads (see `IPFS_CHUNK_SIZE`)
+    pub ipfs_chunker: String,
+    /// Pin timeout and retry schedule for song content
+    pub pinning: PinConfig,
+    /// Raw play retention window (see `PLAY_RETENTION_DAYS`)
+    pub retention: RetentionConfig,
+    /// Largest share a non-artist split recipient may take (see `SPLIT_MAX_RECIPIENT_BPS`)
+    pub split_max_recipient_bps: u32,
+    /// Owed total at which listeners are prompted to settle (see `SETTLEMENT_NUDGE_THRESHOLD`)
+    pub settlement_threshold: f64,
 }
 
 /// Health check response
@@ -37,6 +88,9 @@ struct HealthResponse {
     status: String,
     version: String,
     services: ServiceStatus,
+    uploads: UploadMetrics,
+    /// Song CIDs waiting for IPFS to come back (None if unknown)
+    pinning_backlog: Option<i64>,
 }
 
 #[derive(Serialize)]
@@ -66,6 +120,13 @@ async fn main() -> anyhow::Result<()> {
     let db_pool = sqlx::PgPool::connect(&database_url).await?;
     tracing::info!("Connected to PostgreSQL");
 
+    // `mycelix-music-api backfill-rollups` fills analytics rollups and exits
+    if std::env::args().nth(1).as_deref() == Some("backfill-rollups") {
+        let days = services::rollups::backfill(&db_pool).await?;
+        tracing::info!("Rolled up {} days of analytics", days);
+        return Ok(());
+    }
+
     // Redis connection
     let redis_url = std::env::var("REDIS_URL")
         .unwrap_or_else(|_| "redis://localhost:6379".into());
@@ -78,6 +139,60 @@ async fn main() -> anyhow::Result<()> {
     let ipfs_client = ipfs_api_backend_hyper::IpfsClient::from_str(&ipfs_url)?;
     tracing::info!("Connected to IPFS");
 
+    // Holochain conductor bridge (optional during DHT migration)
+    let holochain = match std::env::var("HOLOCHAIN_GATEWAY_URL") {
+        Ok(url) => {
+            tracing::info!("Holochain bridge enabled via {}", url);
+            Some(Arc::new(HolochainService::new(&url)))
+        }
+        Err(_) => {
+            tracing::info!("Holochain bridge disabled (HOLOCHAIN_GATEWAY_URL not set)");
+            None
+        }
+    };
+
+    // Chain RPC for settlement verification and live strategies (optional)
+    let blockchain = match std::env::var("ROUTER_ADDRESS") {
+        Ok(router_address) => {
+            let rpc_url = std::env::var("RPC_URL")
+                .unwrap_or_else(|_| "http://localhost:8545".into());
+            let mut blockchain = BlockchainService::new(&rpc_url, &router_address)?;
+            match (
+                std::env::var("SETTLEMENT_RPC_URLS"),
+                std::env::var("SETTLEMENT_SIGNER_KEY"),
+            ) {
+                (Ok(rpc_urls), Ok(signer_key)) => {
+                    blockchain = blockchain.with_settlement_chains(&rpc_urls, &signer_key)?;
+                }
+                _ => tracing::info!(
+                    "Settlement submission disabled (SETTLEMENT_RPC_URLS/SETTLEMENT_SIGNER_KEY not set)"
+                ),
+            }
+            Some(Arc::new(blockchain))
+        }
+        Err(_) => {
+            tracing::info!("Chain reads disabled (ROUTER_ADDRESS not set)");
+            None
+        }
+    };
+
+    // ffmpeg transcoding for streaming renditions (optional)
+    let ffmpeg_timeout_secs = std::env::var("FFMPEG_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_FFMPEG_TIMEOUT_SECS);
+    let transcoder = std::env::var("FFMPEG_PATH").ok().map(|path| {
+        Arc::new(TranscodeService::new(&path, Duration::from_secs(ffmpeg_timeout_secs)))
+    });
+    if transcoder.is_none() {
+        tracing::info!("Upload transcoding disabled (FFMPEG_PATH not set)");
+    }
+
+    // Shutdown signal shared by the HTTP server and background tasks
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut indexer_handle = None;
+    let mut indexer = None;
+
     // Start event indexer (if configured)
     if let Ok(router_address) = std::env::var("ROUTER_ADDRESS") {
         if let Ok(router_addr) = router_address.parse::<Address>() {
@@ -89,12 +204,26 @@ async fn main() -> anyhow::Result<()> {
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(0);
 
+            let finality_blocks = std::env::var("INDEXER_FINALITY_BLOCKS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(64);
+
+            let block_time_secs = std::env::var("INDEXER_BLOCK_TIME_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(5);
+
             let indexer_config = IndexerConfig {
                 rpc_url,
                 router_address: router_addr,
                 start_block,
                 poll_interval_secs: 12, // ~1 block on Gnosis
                 confirmations: 3,
+                finality_blocks,
+                rebuild_projections: std::env::var("INDEXER_REBUILD_PROJECTIONS")
+                    .unwrap_or_default(),
+                block_time_secs,
             };
 
             tracing::info!(
@@ -103,50 +232,222 @@ async fn main() -> anyhow::Result<()> {
                 start_block
             );
 
-            spawn_indexer(indexer_config, db_pool.clone());
+            let monitor = Arc::new(IndexerMonitor::new(&indexer_config));
+            indexer_handle = Some(spawn_indexer(
+                indexer_config,
+                db_pool.clone(),
+                monitor.clone(),
+                shutdown_rx.clone(),
+            ));
+            indexer = Some(monitor);
         }
     } else {
         tracing::info!("Event indexer disabled (ROUTER_ADDRESS not set)");
     }
 
+    // Daily analytics rollups (ANALYTICS_ROLLUP_INTERVAL_SECS=0 disables)
+    let rollup_config = RollupConfig::from_env();
+    let rollup_handle = if rollup_config.interval_secs > 0 {
+        Some(spawn_rollups(rollup_config, db_pool.clone(), shutdown_rx.clone()))
+    } else {
+        tracing::info!("Analytics rollups disabled (ANALYTICS_ROLLUP_INTERVAL_SECS=0)");
+        None
+    };
+
+    // Raw play archival (PLAY_RETENTION_DAYS unset or 0 disables)
+    let retention_config = RetentionConfig::from_env();
+    let archive_handle = match retention_config.retention_days {
+        Some(days) if retention_config.interval_secs > 0 => Some(spawn_play_archiver(
+            days,
+            retention_config.interval_secs,
+            db_pool.clone(),
+            shutdown_rx.clone(),
+        )),
+        _ => {
+            tracing::info!("Play archival disabled (PLAY_RETENTION_DAYS not set)");
+            None
+        }
+    };
+
+    // Play-farming detection (ANOMALY_INTERVAL_SECS=0 disables)
+    let anomaly_config = AnomalyConfig::from_env();
+    let anomaly_handle = if anomaly_config.interval_secs > 0 {
+        Some(spawn_anomaly_detector(anomaly_config, db_pool.clone(), shutdown_rx.clone()))
+    } else {
+        tracing::info!("Play anomaly detection disabled (ANOMALY_INTERVAL_SECS=0)");
+        None
+    };
+
+    // Fiat display rates (FX_REFRESH_SECS=0 disables)
+    let fx_config = FxConfig::from_env();
+    let fx_handle = if fx_config.interval_secs > 0 && !fx_config.currencies.is_empty() {
+        Some(spawn_fx_refresh(fx_config, db_pool.clone(), shutdown_rx.clone()))
+    } else {
+        tracing::info!("FX rate refresh disabled (FX_REFRESH_SECS=0)");
+        None
+    };
+
+    // Retry pins queued while IPFS was unreachable
+    let pin_config = PinConfig::from_env();
+    let pin_handle = spawn_pin_retries(
+        pin_config.clone(),
+        db_pool.clone(),
+        ipfs_client.clone(),
+        shutdown_rx.clone(),
+    );
+
+    // Link songs to their catalog entries once artists publish them
+    // (CATALOG_BACKFILL_INTERVAL_SECS=0 disables)
+    let catalog_backfill_secs = std::env::var("CATALOG_BACKFILL_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(300);
+    let catalog_handle = match &holochain {
+        Some(holochain) if catalog_backfill_secs > 0 => Some(spawn_catalog_backfill(
+            catalog_backfill_secs,
+            db_pool.clone(),
+            holochain.clone(),
+            shutdown_rx.clone(),
+        )),
+        _ => {
+            tracing::info!("Catalog backfill disabled");
+            None
+        }
+    };
+
+    // Relay new plays to artist live feeds
+    let play_feed = Arc::new(PlayFeed::new(shutdown_rx.clone()));
+    let play_handle = spawn_play_listener(
+        play_feed.clone(),
+        db_pool.clone(),
+        shutdown_rx.clone(),
+    );
+
     // Create app state
+    let gateways = Arc::new(GatewayService::from_env(redis.clone()));
+    let cdn = holochain
+        .as_ref()
+        .map(|holochain| Arc::new(CdnRouter::from_env(holochain.clone())));
+
     let state = Arc::new(AppState {
         db_pool,
         redis,
         ipfs_client,
+        holochain,
+        blockchain,
+        transcoder,
+        auth: Arc::new(AuthService::from_env()),
+        gateways,
+        cdn,
+        upload_limiter: Arc::new(UploadLimiter::from_env()),
+        upload_progress: Arc::new(UploadTracker::new(shutdown_rx.clone())),
+        play_feed,
+        indexer,
+        ipfs_chunker: services::ipfs::chunker_from_env(),
+        pinning: pin_config,
+        retention: retention_config,
+        split_max_recipient_bps: routes::strategies::max_recipient_bps_from_env(),
+        settlement_threshold: routes::listeners::settlement_threshold_from_env(),
     });
 
+    // Routes that act for, or only answer to, the signed-in wallet
+    let protected = Router::new()
+        .route("/api/songs", post(routes::songs::create_song))
+        .route("/api/songs/:id/play", post(routes::songs::record_play))
+        .route("/api/upload", post(routes::uploads::upload_file))
+        .route("/api/listeners/:address/library", get(routes::listeners::get_listener_library))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            routes::auth::require_auth,
+        ));
+
+    // Moderation and reconciliation require an address listed in ADMIN_ADDRESSES
+    let admin = Router::new()
+        .route("/api/admin/songs/:id/moderate", post(routes::admin::moderate_song))
+        .route(
+            "/api/admin/reconcile/orphaned-plays",
+            post(routes::admin::reconcile_orphaned_plays),
+        )
+        .route("/api/admin/archive-plays", post(routes::admin::archive_plays))
+        .route("/api/admin/anomalies", get(routes::admin::list_anomalies))
+        .route("/api/admin/anomalies/:id/resolve", post(routes::admin::resolve_anomaly))
+        .route(
+            "/api/admin/settlements/:batch_hash/submit",
+            post(routes::settlements::submit_settlement),
+        )
+        .route_layer(middleware::from_fn(routes::auth::require_admin))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            routes::auth::require_auth,
+        ));
+
     // Build router
     let app = Router::new()
         // Health & Status
         .route("/health", get(health_check))
         .route("/", get(root))
 
+        // Auth
+        .route("/api/auth/nonce", post(routes::auth::request_nonce))
+        .route("/api/auth/verify", post(routes::auth::verify))
+
         // Songs
         .route("/api/songs", get(routes::songs::list_songs))
-        .route("/api/songs", post(routes::songs::create_song))
+        .route("/api/songs/by-category/:category", get(routes::songs::get_songs_by_category))
+        .route("/api/songs/batch", post(routes::songs::get_songs_batch))
         .route("/api/songs/:id", get(routes::songs::get_song))
-        .route("/api/songs/:id/play", post(routes::songs::record_play))
+        .route("/api/songs/:id/reconcile", get(routes::songs::reconcile_song))
+        .route("/api/songs/:id/attestations", get(routes::songs::get_song_attestations))
+        .route("/api/songs/:id/price", get(routes::songs::get_song_price))
 
         // Artists
         .route("/api/artists/:address", get(routes::artists::get_artist))
         .route("/api/artists/:address/songs", get(routes::artists::get_artist_songs))
+        .route("/api/artists/:address/forecast", get(routes::artists::get_artist_forecast))
+        .route("/api/artists/:address/earnings", get(routes::artists::get_artist_earnings))
+        .route("/api/artists/:address/statement", get(routes::statements::get_artist_statement))
+        .route("/api/artists/:address/live", get(routes::live::get_artist_live))
+
+        // Listeners
+        .route("/api/listeners/:address/owed", get(routes::listeners::get_listener_owed))
+
+        // Profiles
+        .route("/api/profiles/:identifier", get(routes::profiles::get_profile))
 
         // Analytics
         .route("/api/analytics/artist/:address", get(routes::analytics::artist_analytics))
         .route("/api/analytics/song/:id", get(routes::analytics::song_analytics))
         .route("/api/analytics/top-songs", get(routes::analytics::top_songs))
+        .route("/api/analytics/genres", get(routes::analytics::genre_analytics))
 
-        // Uploads
-        .route("/api/upload", post(routes::uploads::upload_file))
+        // Payments
+        .route("/api/payments", get(routes::payments::list_payments))
+        .route("/api/indexer/status", get(routes::indexer::get_indexer_status))
+
+        // Settlements
+        .route("/api/settlements/:batch_hash/verify", get(routes::settlements::verify_settlement))
+        .route("/api/plays/:play_hash/receipt", get(routes::settlements::get_play_receipt))
+
+        // Streaming
+        .route("/api/stream/:cid", get(routes::stream::stream_cid))
+
+        // Uploads (progress ids are unguessable; EventSource can't send auth headers)
+        .route("/api/upload/:upload_id/progress", get(routes::uploads::upload_progress))
+
+        // Images
+        .route("/api/images/:cid/thumb", get(routes::images::get_thumbnail))
 
         // Economic Strategies
         .route("/api/strategies", get(routes::strategies::list_strategies))
         .route("/api/strategies/:id/preview", post(routes::strategies::preview_splits))
 
+        // Authenticated routes
+        .merge(protected)
+        .merge(admin)
+
         // Middleware
         .layer(TraceLayer::new_for_http())
-        .layer(CorsLayer::permissive())
+        .layer(cors_layer())
         .with_state(state);
 
     // Start server
@@ -157,11 +458,135 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("   Vision: Default choice for the entire music industry");
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal(shutdown_tx))
+        .await?;
+
+    // Let the indexer finish its current range and save the checkpoint
+    if let Some(handle) = indexer_handle {
+        tracing::info!("Waiting for indexer to finish in-flight work");
+        if let Err(e) = handle.await {
+            tracing::error!("Indexer task failed during shutdown: {:?}", e);
+        }
+    }
+    if let Some(handle) = rollup_handle {
+        if let Err(e) = handle.await {
+            tracing::error!("Rollup task failed during shutdown: {:?}", e);
+        }
+    }
+    if let Some(handle) = archive_handle {
+        if let Err(e) = handle.await {
+            tracing::error!("Play archival task failed during shutdown: {:?}", e);
+        }
+    }
+    if let Some(handle) = anomaly_handle {
+        if let Err(e) = handle.await {
+            tracing::error!("Anomaly detector failed during shutdown: {:?}", e);
+        }
+    }
+    if let Some(handle) = fx_handle {
+        if let Err(e) = handle.await {
+            tracing::error!("FX refresh task failed during shutdown: {:?}", e);
+        }
+    }
+    if let Some(handle) = catalog_handle {
+        if let Err(e) = handle.await {
+            tracing::error!("Catalog backfill failed during shutdown: {:?}", e);
+        }
+    }
+    if let Err(e) = pin_handle.await {
+        tracing::error!("Pin retry task failed during shutdown: {:?}", e);
+    }
+    if let Err(e) = play_handle.await {
+        tracing::error!("Play listener failed during shutdown: {:?}", e);
+    }
 
+    tracing::info!("Shutdown complete");
     Ok(())
 }
 
+/// Resolve on Ctrl+C or SIGTERM, then notify background tasks
+async fn shutdown_signal(shutdown_tx: watch::Sender<bool>) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received");
+    let _ = shutdown_tx.send(true);
+}
+
+/// Build the CORS layer from the environment
+///
+/// - `DEV_MODE=true`: permissive (any origin, method, header)
+/// - `ALLOWED_ORIGINS`: comma-separated origins (none allowed if unset)
+/// - `ALLOWED_METHODS`: comma-separated methods (default GET,POST,OPTIONS)
+/// - `CORS_ALLOW_CREDENTIALS=true`: allow cookies/authorization headers
+fn cors_layer() -> CorsLayer {
+    let dev_mode = env_flag("DEV_MODE");
+    if dev_mode {
+        tracing::warn!("DEV_MODE enabled: CORS is permissive");
+        return CorsLayer::permissive();
+    }
+
+    let origins: Vec<HeaderValue> = std::env::var("ALLOWED_ORIGINS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .filter_map(|o| match o.parse::<HeaderValue>() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS origin: {}", o);
+                None
+            }
+        })
+        .collect();
+
+    if origins.is_empty() {
+        tracing::warn!("ALLOWED_ORIGINS not set: cross-origin requests will be rejected");
+    } else {
+        tracing::info!("CORS allowed origins: {:?}", origins);
+    }
+
+    let methods: Vec<Method> = std::env::var("ALLOWED_METHODS")
+        .unwrap_or_else(|_| "GET,POST,OPTIONS".into())
+        .split(',')
+        .map(str::trim)
+        .filter_map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods(methods)
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+        .allow_credentials(env_flag("CORS_ALLOW_CREDENTIALS"))
+}
+
+/// Read a boolean environment flag ("1"/"true"/"yes")
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
 /// Root endpoint
 async fn root() -> Json<serde_json::Value> {
     Json(serde_json::json!({
@@ -192,19 +617,28 @@ async fn health_check(State(state): State<Arc<AppState>>) -> Json<HealthResponse
         .map(|_| true)
         .unwrap_or(false);
 
-    // IPFS check (simple version query)
-    let ipfs_ok = state.ipfs_client
-        .version()
-        .await
-        .is_ok();
+    // IPFS check (simple version query); bounded so an unreachable node
+    // can't stall the health check
+    let ipfs_ok = matches!(
+        tokio::time::timeout(state.pinning.timeout, state.ipfs_client.version()).await,
+        Ok(Ok(_))
+    );
+
+    let pinning_backlog = if db_ok {
+        services::pinning::backlog(&state.db_pool).await.ok()
+    } else {
+        None
+    };
 
     Json(HealthResponse {
-        status: if db_ok && redis_ok { "healthy".into() } else { "degraded".into() },
+        status: if db_ok && redis_ok && ipfs_ok { "healthy".into() } else { "degraded".into() },
         version: env!("CARGO_PKG_VERSION").into(),
         services: ServiceStatus {
             database: db_ok,
             redis: redis_ok,
             ipfs: ipfs_ok,
         },
+        uploads: state.upload_limiter.metrics(),
+        pinning_backlog,
     })
 }
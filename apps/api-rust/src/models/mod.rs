@@ -2,7 +2,13 @@
 //!
 //! Shared data structures for the Mycelix Music platform
 
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 /// Song entity
@@ -54,7 +60,7 @@ pub struct StrategyConfig {
 }
 
 /// Payment model types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PaymentModel {
     PayPerStream,
@@ -70,6 +76,44 @@ pub enum PaymentModel {
     StakingGated,
 }
 
+impl PaymentModel {
+    /// Parse a snake_case payment model name (e.g. "pay_per_stream")
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+
+    /// Models that restrict the full track to listeners with access
+    pub fn is_gated(self) -> bool {
+        matches!(self, Self::NftGated | Self::StakingGated | Self::Download)
+    }
+}
+
+/// Song moderation status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongStatus {
+    /// Publicly listed
+    Active,
+    /// Visible only to the owning artist and admins
+    Hidden,
+    /// Taken down; visible only to admins
+    Removed,
+}
+
+impl SongStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SongStatus::Active => "active",
+            SongStatus::Hidden => "hidden",
+            SongStatus::Removed => "removed",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
 /// Revenue split configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Split {
@@ -107,3 +151,73 @@ impl ApiError {
         Self::new("internal_error", "An unexpected error occurred", 500)
     }
 }
+
+/// Field-keyed validation errors, returned as 422 Unprocessable Entity
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationErrors {
+    pub fields: BTreeMap<String, String>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error for a field (first error per field wins)
+    pub fn add(&mut self, field: &str, message: &str) {
+        self.fields
+            .entry(field.to_string())
+            .or_insert_with(|| message.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Convert into a result, failing if any errors were recorded
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": "validation_failed",
+            "message": "Request body failed validation",
+            "status_code": 422,
+            "fields": self.fields,
+        });
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+    }
+}
+
+/// Check for a 0x-prefixed, 42-char hex Ethereum address
+/// (same rule as the balances/trust zome validation)
+pub fn is_valid_eth_address(address: &str) -> bool {
+    address.len() == 42
+        && address.starts_with("0x")
+        && address[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Check for a plausible IPFS CID (v0 base58 "Qm..." or v1 base32 "b...")
+pub fn is_valid_cid(cid: &str) -> bool {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    if cid.starts_with("Qm") {
+        return cid.len() == 46 && cid.chars().all(|c| BASE58.contains(c));
+    }
+
+    if let Some(rest) = cid.strip_prefix('b') {
+        return cid.len() >= 59
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c));
+    }
+
+    false
+}
@@ -0,0 +1,473 @@
+//! Admin Routes - Moderation and data reconciliation
+//!
+//! All routes here sit behind `require_auth` + `require_admin`.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{SongStatus, ValidationErrors};
+use crate::routes::auth::AuthUser;
+use crate::services::retention::{self, ArchiveReport, MIN_RETENTION_DAYS};
+use crate::services::rollups;
+use crate::AppState;
+
+/// Maximum moderation reason length (characters)
+const MAX_REASON_LEN: usize = 1000;
+
+/// Moderate song request
+#[derive(Debug, Deserialize)]
+pub struct ModerateSongRequest {
+    /// active, hidden or removed
+    pub status: String,
+    pub reason: String,
+}
+
+/// Audit record of a moderation action
+#[derive(Debug, Serialize)]
+pub struct ModerationAction {
+    pub id: i32,
+    pub song_id: Uuid,
+    pub previous_status: String,
+    pub new_status: String,
+    pub reason: String,
+    pub actor_address: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Change a song's moderation status and record the action
+pub async fn moderate_song(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ModerateSongRequest>,
+) -> Result<Json<ModerationAction>, Response> {
+    let mut errors = ValidationErrors::new();
+    let status = SongStatus::parse(&req.status);
+    if status.is_none() {
+        errors.add("status", "Must be one of: active, hidden, removed");
+    }
+    let reason = req.reason.trim();
+    if reason.is_empty() {
+        errors.add("reason", "Reason is required");
+    } else if reason.chars().count() > MAX_REASON_LEN {
+        errors.add("reason", &format!("Reason cannot exceed {} characters", MAX_REASON_LEN));
+    }
+    errors.into_result().map_err(IntoResponse::into_response)?;
+    let new_status = status.expect("validated above").as_str();
+
+    let internal = |context: &str, e: sqlx::Error| {
+        tracing::error!("Failed to {}: {}", context, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|e| internal("begin moderation transaction", e))?;
+
+    let previous_status: String =
+        sqlx::query_scalar("SELECT status FROM songs WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| internal("load song status", e))?
+            .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    sqlx::query("UPDATE songs SET status = $2 WHERE id = $1")
+        .bind(id)
+        .bind(new_status)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal("update song status", e))?;
+
+    let (log_id, created_at) = sqlx::query_as::<_, (i32, chrono::DateTime<chrono::Utc>)>(
+        r#"
+        INSERT INTO song_moderation_log (song_id, previous_status, new_status, reason, actor_address)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, created_at
+        "#,
+    )
+    .bind(id)
+    .bind(&previous_status)
+    .bind(new_status)
+    .bind(reason)
+    .bind(&user.address)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| internal("record moderation action", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| internal("commit moderation", e))?;
+
+    tracing::info!(
+        "Moderated song {}: {} -> {} by {} ({})",
+        id,
+        previous_status,
+        new_status,
+        user.address,
+        reason
+    );
+
+    Ok(Json(ModerationAction {
+        id: log_id,
+        song_id: id,
+        previous_status,
+        new_status: new_status.to_string(),
+        reason: reason.to_string(),
+        actor_address: user.address,
+        created_at,
+    }))
+}
+
+/// Counts from an orphaned-plays reconciliation run
+#[derive(Debug, Serialize)]
+pub struct OrphanedPlaysReport {
+    /// Plays whose song row no longer exists
+    pub song_missing: u64,
+    /// Plays of songs with status `removed`
+    pub song_removed: u64,
+    /// Previously flagged plays whose song is back (e.g. un-removed)
+    pub restored: u64,
+}
+
+/// Tables holding plays; archived plays are flagged like live ones so
+/// statements over old periods stay consistent
+const PLAY_TABLES: [&str; 2] = ["plays", "plays_archive"];
+
+/// Flag plays of deleted or removed songs so they're excluded from
+/// earnings and analytics, and unflag plays of songs that were restored
+///
+/// Plays are never deleted, so the run is safe to repeat.
+pub async fn reconcile_orphaned_plays(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<OrphanedPlaysReport>, StatusCode> {
+    let internal = |context: &str, e: sqlx::Error| {
+        tracing::error!("Failed to {}: {}", context, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|e| internal("begin reconciliation transaction", e))?;
+
+    let mut song_missing = 0;
+    let mut song_removed = 0;
+    let mut restored = 0;
+    for table in PLAY_TABLES {
+        // Deleting a song nulls plays.song_id (fk_plays_song ON DELETE SET NULL)
+        song_missing += sqlx::query(&format!(
+            r#"
+            UPDATE {} p
+            SET excluded_reason = 'song_missing', excluded_at = NOW()
+            WHERE p.excluded_reason IS DISTINCT FROM 'song_missing'
+              AND NOT EXISTS (SELECT 1 FROM songs s WHERE s.id = p.song_id)
+            "#,
+            table
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal("flag plays of missing songs", e))?
+        .rows_affected();
+
+        song_removed += sqlx::query(&format!(
+            r#"
+            UPDATE {} p
+            SET excluded_reason = 'song_removed', excluded_at = NOW()
+            FROM songs s
+            WHERE s.id = p.song_id
+              AND s.status = 'removed'
+              AND p.excluded_reason IS DISTINCT FROM 'song_removed'
+            "#,
+            table
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal("flag plays of removed songs", e))?
+        .rows_affected();
+
+        restored += sqlx::query(&format!(
+            r#"
+            UPDATE {} p
+            SET excluded_reason = NULL, excluded_at = NULL
+            FROM songs s
+            WHERE s.id = p.song_id
+              AND s.status <> 'removed'
+              AND p.excluded_reason IN ('song_missing', 'song_removed')
+            "#,
+            table
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal("unflag plays of restored songs", e))?
+        .rows_affected();
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| internal("commit reconciliation", e))?;
+
+    tracing::info!(
+        "Reconciled orphaned plays by {}: {} missing, {} removed, {} restored",
+        user.address,
+        song_missing,
+        song_removed,
+        restored
+    );
+
+    Ok(Json(OrphanedPlaysReport {
+        song_missing,
+        song_removed,
+        restored,
+    }))
+}
+
+/// Query params for a manual archival run
+#[derive(Debug, Deserialize)]
+pub struct ArchivePlaysQuery {
+    /// Overrides `PLAY_RETENTION_DAYS` for this run (at least 90)
+    pub retention_days: Option<i64>,
+}
+
+/// Roll up and archive raw plays past the retention window now
+pub async fn archive_plays(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<ArchivePlaysQuery>,
+) -> Result<Json<ArchiveReport>, Response> {
+    let retention_days = match params.retention_days.or(state.retention.retention_days) {
+        Some(days) if days >= MIN_RETENTION_DAYS => days,
+        days => {
+            let mut errors = ValidationErrors::new();
+            let message = match days {
+                Some(_) => format!("Must be at least {} days", MIN_RETENTION_DAYS),
+                None => "Required when PLAY_RETENTION_DAYS is not configured".to_string(),
+            };
+            errors.add("retention_days", &message);
+            return Err(errors.into_response());
+        }
+    };
+
+    let report = retention::archive_plays(&state.db_pool, retention_days)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to archive plays: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    tracing::info!(
+        "Archived {} plays before {} by {}",
+        report.archived,
+        report.cutoff,
+        user.address
+    );
+    Ok(Json(report))
+}
+
+/// Query params for listing play anomalies
+#[derive(Debug, Deserialize)]
+pub struct ListAnomaliesQuery {
+    /// open (default), dismissed, confirmed or all
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// A flagged play-rate spike
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PlayAnomaly {
+    pub id: i64,
+    pub song_id: Uuid,
+    pub title: String,
+    pub artist_address: String,
+    pub window_start: chrono::DateTime<chrono::Utc>,
+    pub window_end: chrono::DateTime<chrono::Utc>,
+    pub plays: i64,
+    pub baseline_mean: f64,
+    pub baseline_stddev: f64,
+    pub z_score: f64,
+    pub new_listener_share: f64,
+    pub earnings_paused: bool,
+    pub status: String,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub resolved_by: Option<String>,
+}
+
+/// List play anomalies, newest first
+pub async fn list_anomalies(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ListAnomaliesQuery>,
+) -> Result<Json<Vec<PlayAnomaly>>, Response> {
+    let status = params.status.as_deref().unwrap_or("open");
+    if !matches!(status, "open" | "dismissed" | "confirmed" | "all") {
+        let mut errors = ValidationErrors::new();
+        errors.add("status", "Must be one of: open, dismissed, confirmed, all");
+        return Err(errors.into_response());
+    }
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+
+    let anomalies = sqlx::query_as::<_, PlayAnomaly>(
+        r#"
+        SELECT a.id, a.song_id, s.title, s.artist_address, a.window_start, a.window_end,
+               a.plays, a.baseline_mean, a.baseline_stddev, a.z_score, a.new_listener_share,
+               a.earnings_paused, a.status, a.detected_at, a.resolved_at, a.resolved_by
+        FROM play_anomalies a
+        JOIN songs s ON s.id = a.song_id
+        WHERE $1 = 'all' OR a.status = $1
+        ORDER BY a.detected_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(status)
+    .bind(limit)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list play anomalies: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    Ok(Json(anomalies))
+}
+
+/// Resolve anomaly request
+#[derive(Debug, Deserialize)]
+pub struct ResolveAnomalyRequest {
+    /// dismissed (plays were legitimate) or confirmed (farming)
+    pub status: String,
+}
+
+/// Outcome of resolving an anomaly
+#[derive(Debug, Serialize)]
+pub struct AnomalyResolution {
+    pub id: i64,
+    pub status: String,
+    /// Held plays released back into earnings (dismissed only)
+    pub released_plays: u64,
+}
+
+/// Resolve an open anomaly and resume the song's earnings
+///
+/// Dismissing releases plays held while earnings were paused; confirming
+/// leaves them excluded.
+pub async fn resolve_anomaly(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+    Json(req): Json<ResolveAnomalyRequest>,
+) -> Result<Json<AnomalyResolution>, Response> {
+    let dismissed = match req.status.as_str() {
+        "dismissed" => true,
+        "confirmed" => false,
+        _ => {
+            let mut errors = ValidationErrors::new();
+            errors.add("status", "Must be one of: dismissed, confirmed");
+            return Err(errors.into_response());
+        }
+    };
+
+    let internal = |context: &str, e: sqlx::Error| {
+        tracing::error!("Failed to {}: {}", context, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|e| internal("begin anomaly resolution", e))?;
+
+    let song_id: Uuid = sqlx::query_scalar(
+        r#"
+        UPDATE play_anomalies
+        SET status = $2, resolved_at = NOW(), resolved_by = $3
+        WHERE id = $1 AND status = 'open'
+        RETURNING song_id
+        "#,
+    )
+    .bind(id)
+    .bind(&req.status)
+    .bind(&user.address)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| internal("resolve anomaly", e))?
+    .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    // Held plays are released as one earnings delta so artist_stats follows
+    let mut released_days = Vec::new();
+    let mut released_plays = 0;
+    if dismissed {
+        let released = sqlx::query_as::<_, (chrono::NaiveDate, i64, f64)>(
+            r#"
+            WITH released AS (
+                UPDATE plays
+                SET excluded_reason = NULL, excluded_at = NULL
+                WHERE song_id = $1 AND excluded_reason = 'earnings_paused'
+                RETURNING timestamp, amount
+            )
+            SELECT timestamp::date, COUNT(*), COALESCE(SUM(amount), 0)::float8
+            FROM released
+            GROUP BY timestamp::date
+            "#,
+        )
+        .bind(song_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| internal("release held plays", e))?;
+
+        let amount: f64 = released.iter().map(|(_, _, amount)| amount).sum();
+        released_plays = released.iter().map(|(_, count, _)| *count as u64).sum();
+        released_days = released.into_iter().map(|(day, _, _)| day).collect();
+
+        sqlx::query("UPDATE songs SET earnings = earnings + $2 WHERE id = $1")
+            .bind(song_id)
+            .bind(amount)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| internal("credit released earnings", e))?;
+    }
+
+    sqlx::query("UPDATE songs SET earnings_paused = FALSE WHERE id = $1")
+        .bind(song_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal("resume song earnings", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| internal("commit anomaly resolution", e))?;
+
+    // Days already rolled up must be rebuilt to include released plays
+    let today = chrono::Utc::now().date_naive();
+    for day in released_days.into_iter().filter(|day| *day < today) {
+        if let Err(e) = rollups::rollup_day(&state.db_pool, day).await {
+            tracing::warn!("Failed to rebuild rollup for {}: {:?}", day, e);
+        }
+    }
+
+    tracing::info!(
+        "Resolved anomaly {} for song {} as {} by {} ({} plays released)",
+        id,
+        song_id,
+        req.status,
+        user.address,
+        released_plays
+    );
+
+    Ok(Json(AnomalyResolution {
+        id,
+        status: req.status,
+        released_plays,
+    }))
+}
@@ -9,8 +9,13 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::services::holochain::SongStats;
+use crate::services::rollups::{artist_daily_stats, song_daily_stats, DailyStats};
 use crate::AppState;
 
+/// Days covered by `plays_by_day`
+const DAILY_SERIES_DAYS: i32 = 30;
+
 #[derive(Debug, Serialize)]
 pub struct ArtistAnalytics {
     pub address: String,
@@ -20,6 +25,7 @@ pub struct ArtistAnalytics {
     pub top_songs: Vec<SongSummary>,
     pub earnings_by_strategy: Vec<StrategyEarnings>,
     pub recent_plays: Vec<RecentPlay>,
+    pub plays_by_day: Vec<DailyPlays>,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,6 +58,9 @@ pub struct SongAnalytics {
     pub total_plays: i64,
     pub total_earnings: f64,
     pub unique_listeners: i64,
+    /// Distinct listeners who completed the song, from the plays zome
+    /// (None without the Holochain bridge or a DHT counterpart)
+    pub completed_unique_listeners: Option<u64>,
     pub avg_tip: f64,
     pub plays_by_day: Vec<DailyPlays>,
 }
@@ -61,6 +70,18 @@ pub struct DailyPlays {
     pub date: String,
     pub plays: i64,
     pub earnings: f64,
+    pub unique_listeners: i64,
+}
+
+impl From<DailyStats> for DailyPlays {
+    fn from(stats: DailyStats) -> Self {
+        Self {
+            date: stats.day.to_string(),
+            plays: stats.plays,
+            earnings: stats.earnings,
+            unique_listeners: stats.unique_listeners,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -74,20 +95,19 @@ pub async fn artist_analytics(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
 ) -> Result<Json<ArtistAnalytics>, StatusCode> {
-    // Get totals
+    // Get totals (maintained by the songs trigger; no row = no songs yet)
     let totals = sqlx::query_as::<_, (f64, i64)>(
         r#"
-        SELECT
-            COALESCE(SUM(earnings), 0)::float8 as total_earnings,
-            COALESCE(SUM(plays), 0) as total_plays
-        FROM songs
-        WHERE artist_address = $1
+        SELECT total_earnings::float8, total_plays
+        FROM artist_stats
+        WHERE artist_address = LOWER($1)
         "#,
     )
     .bind(&address)
-    .fetch_one(&state.db_pool)
+    .fetch_optional(&state.db_pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .unwrap_or((0.0, 0));
 
     // Get top songs
     let top_songs = sqlx::query_as::<_, (Uuid, String, i64, f64)>(
@@ -128,6 +148,17 @@ pub async fn artist_analytics(
     })
     .collect();
 
+    // History from daily rollups, today from raw plays
+    let plays_by_day = artist_daily_stats(&state.db_pool, &address, DAILY_SERIES_DAYS)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get artist daily stats: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(DailyPlays::from)
+        .collect();
+
     let avg = if totals.1 > 0 {
         totals.0 / totals.1 as f64
     } else {
@@ -142,6 +173,7 @@ pub async fn artist_analytics(
         top_songs,
         earnings_by_strategy,
         recent_plays: vec![], // TODO: Implement
+        plays_by_day,
     }))
 }
 
@@ -150,9 +182,9 @@ pub async fn song_analytics(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<SongAnalytics>, StatusCode> {
-    let song = sqlx::query_as::<_, (String, i64, f64)>(
+    let song = sqlx::query_as::<_, (String, i64, f64, Option<String>)>(
         r#"
-        SELECT title, plays, earnings::float8
+        SELECT title, plays, earnings::float8, dht_action_hash
         FROM songs
         WHERE id = $1
         "#,
@@ -165,21 +197,50 @@ pub async fn song_analytics(
 
     // Get unique listeners
     let unique_listeners: i64 = sqlx::query_scalar(
-        "SELECT COUNT(DISTINCT listener_address) FROM plays WHERE song_id = $1",
+        "SELECT COUNT(DISTINCT listener_address) FROM plays_with_archive WHERE song_id = $1",
     )
     .bind(id)
     .fetch_one(&state.db_pool)
     .await
     .unwrap_or(0);
 
+    // Completion isn't kept in the DB; the plays zome tracks reach
+    let completed_unique_listeners = match (&state.holochain, &song.3) {
+        (Some(holochain), Some(dht_action_hash)) => {
+            match holochain
+                .call_zome::<_, SongStats>("plays", "get_song_stats", dht_action_hash)
+                .await
+            {
+                Ok(stats) => Some(stats.completed_unique_listeners),
+                Err(e) => {
+                    tracing::warn!("Failed to fetch song stats from DHT: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    // History from daily rollups, today from raw plays
+    let plays_by_day = song_daily_stats(&state.db_pool, id, DAILY_SERIES_DAYS)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get song daily stats: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(DailyPlays::from)
+        .collect();
+
     Ok(Json(SongAnalytics {
         id,
         title: song.0,
         total_plays: song.1,
         total_earnings: song.2,
         unique_listeners,
+        completed_unique_listeners,
         avg_tip: if song.1 > 0 { song.2 / song.1 as f64 } else { 0.0 },
-        plays_by_day: vec![], // TODO: Implement time series
+        plays_by_day,
     }))
 }
 
@@ -194,6 +255,7 @@ pub async fn top_songs(
         r#"
         SELECT id, title, plays, earnings::float8
         FROM songs
+        WHERE status = 'active'
         ORDER BY plays DESC
         LIMIT $1
         "#,
@@ -208,3 +270,120 @@ pub async fn top_songs(
 
     Ok(Json(songs))
 }
+
+/// Per-genre totals for a period, compared with the period before it
+#[derive(Debug, Serialize)]
+pub struct GenreAnalytics {
+    pub genre: String,
+    pub plays: i64,
+    pub earnings: f64,
+    pub song_count: i64,
+    pub unique_artists: i64,
+    pub previous_plays: i64,
+    pub previous_earnings: f64,
+    /// Relative play growth vs the prior period (None if it had no plays)
+    pub play_growth: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenreAnalyticsQuery {
+    pub period: Option<String>, // "day", "week", "month", "year"
+    pub sort: Option<String>,   // "plays", "earnings", "songs", "artists", "growth"
+    pub limit: Option<usize>,
+}
+
+/// Map a period name to a Postgres interval
+fn period_interval(period: &str) -> Option<&'static str> {
+    match period {
+        "day" => Some("1 day"),
+        "week" => Some("7 days"),
+        "month" => Some("1 month"),
+        "year" => Some("1 year"),
+        _ => None,
+    }
+}
+
+/// Get genre-level analytics
+pub async fn genre_analytics(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GenreAnalyticsQuery>,
+) -> Result<Json<Vec<GenreAnalytics>>, StatusCode> {
+    let interval = period_interval(params.period.as_deref().unwrap_or("month"))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let limit = params.limit.unwrap_or(50).min(200);
+
+    let rows = sqlx::query_as::<_, (String, i64, i64, i64, f64, i64, f64)>(
+        r#"
+        WITH song_genres AS (
+            SELECT id, artist_address, UNNEST(genres) AS genre
+            FROM songs
+            WHERE status = 'active'
+        ),
+        windowed AS (
+            SELECT sg.genre,
+                   COUNT(*) FILTER (WHERE p.timestamp >= NOW() - $1::interval) AS plays,
+                   COALESCE(SUM(p.amount) FILTER (WHERE p.timestamp >= NOW() - $1::interval), 0)::float8 AS earnings,
+                   COUNT(*) FILTER (WHERE p.timestamp < NOW() - $1::interval) AS previous_plays,
+                   COALESCE(SUM(p.amount) FILTER (WHERE p.timestamp < NOW() - $1::interval), 0)::float8 AS previous_earnings
+            FROM song_genres sg
+            JOIN plays_with_archive p ON p.song_id = sg.id
+            WHERE p.timestamp >= NOW() - 2 * $1::interval
+              AND p.excluded_reason IS NULL
+            GROUP BY sg.genre
+        )
+        SELECT sg.genre,
+               COUNT(DISTINCT sg.id) AS song_count,
+               COUNT(DISTINCT sg.artist_address) AS unique_artists,
+               COALESCE(MAX(w.plays), 0) AS plays,
+               COALESCE(MAX(w.earnings), 0)::float8 AS earnings,
+               COALESCE(MAX(w.previous_plays), 0) AS previous_plays,
+               COALESCE(MAX(w.previous_earnings), 0)::float8 AS previous_earnings
+        FROM song_genres sg
+        LEFT JOIN windowed w ON w.genre = sg.genre
+        GROUP BY sg.genre
+        "#,
+    )
+    .bind(interval)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get genre analytics: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut genres: Vec<GenreAnalytics> = rows
+        .into_iter()
+        .map(
+            |(genre, song_count, unique_artists, plays, earnings, previous_plays, previous_earnings)| {
+                GenreAnalytics {
+                    genre,
+                    plays,
+                    earnings,
+                    song_count,
+                    unique_artists,
+                    previous_plays,
+                    previous_earnings,
+                    play_growth: (previous_plays > 0)
+                        .then(|| (plays - previous_plays) as f64 / previous_plays as f64),
+                }
+            },
+        )
+        .collect();
+
+    match params.sort.as_deref().unwrap_or("plays") {
+        "plays" => genres.sort_by(|a, b| b.plays.cmp(&a.plays)),
+        "earnings" => genres.sort_by(|a, b| b.earnings.total_cmp(&a.earnings)),
+        "songs" => genres.sort_by(|a, b| b.song_count.cmp(&a.song_count)),
+        "artists" => genres.sort_by(|a, b| b.unique_artists.cmp(&a.unique_artists)),
+        // Fastest growing first; genres with no prior plays sort last
+        "growth" => genres.sort_by(|a, b| {
+            b.play_growth
+                .unwrap_or(f64::NEG_INFINITY)
+                .total_cmp(&a.play_growth.unwrap_or(f64::NEG_INFINITY))
+        }),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    }
+    genres.truncate(limit);
+
+    Ok(Json(genres))
+}
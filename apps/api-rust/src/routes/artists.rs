@@ -1,14 +1,19 @@
 //! Artist Routes - Artist profiles and catalog
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
+use crate::models::{is_valid_eth_address, ValidationErrors};
+use crate::services::fx;
 use crate::AppState;
+use super::auth::authenticate;
 use super::songs::Song;
 
 #[derive(Debug, Serialize)]
@@ -27,25 +32,23 @@ pub async fn get_artist(
 ) -> Result<Json<ArtistProfile>, StatusCode> {
     let stats = sqlx::query_as::<_, (i64, i64, f64)>(
         r#"
-        SELECT
-            COUNT(*) as total_songs,
-            COALESCE(SUM(plays), 0) as total_plays,
-            COALESCE(SUM(earnings), 0)::float8 as total_earnings
-        FROM songs
-        WHERE artist_address = $1
+        SELECT total_songs, total_plays, total_earnings::float8
+        FROM artist_stats
+        WHERE artist_address = LOWER($1)
         "#,
     )
     .bind(&address)
-    .fetch_one(&state.db_pool)
+    .fetch_optional(&state.db_pool)
     .await
     .map_err(|e| {
         tracing::error!("Failed to get artist stats: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    })?
+    .unwrap_or((0, 0, 0.0));
 
     let strategies: Vec<String> = sqlx::query_scalar(
         r#"
-        SELECT DISTINCT strategy_id FROM songs WHERE artist_address = $1
+        SELECT DISTINCT strategy_id FROM songs WHERE LOWER(artist_address) = LOWER($1)
         "#,
     )
     .bind(&address)
@@ -65,21 +68,27 @@ pub async fn get_artist(
     }))
 }
 
-/// Get songs by artist
+/// Get songs by artist (hidden songs included only for the artist themselves)
 pub async fn get_artist_songs(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(address): Path<String>,
 ) -> Result<Json<Vec<Song>>, StatusCode> {
+    let is_owner = authenticate(&state.auth, &headers).map_or(false, |u| u.is(&address));
+
     let songs = sqlx::query_as::<_, Song>(
         r#"
         SELECT id, song_hash, title, artist_address, ipfs_hash,
-               strategy_id, payment_model, plays, earnings::float8 as earnings, created_at
+               strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+               cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
         FROM songs
-        WHERE artist_address = $1
+        WHERE LOWER(artist_address) = LOWER($1)
+          AND (status = 'active' OR ($2 AND status = 'hidden'))
         ORDER BY created_at DESC
         "#,
     )
     .bind(&address)
+    .bind(is_owner)
     .fetch_all(&state.db_pool)
     .await
     .map_err(|e| {
@@ -89,3 +98,326 @@ pub async fn get_artist_songs(
 
     Ok(Json(songs))
 }
+
+/// Days of play history the forecast is fitted on
+const FORECAST_WINDOW_DAYS: i64 = 30;
+
+/// Days projected forward
+const FORECAST_HORIZON_DAYS: i64 = 30;
+
+/// Days with plays needed before a forecast is attempted
+const MIN_ACTIVE_DAYS: usize = 7;
+
+/// Play velocity for one strategy
+#[derive(Debug, Serialize)]
+pub struct StrategyVelocity {
+    pub strategy_id: String,
+    pub plays_7d: i64,
+    pub plays_30d: i64,
+    pub plays_per_day_7d: f64,
+    pub plays_per_day_30d: f64,
+    /// Average earnings per play over the last 30 days
+    pub earnings_per_play: f64,
+}
+
+/// Projected earnings for the next 30 days
+#[derive(Debug, Serialize)]
+pub struct EarningsProjection {
+    pub projected_earnings: f64,
+    /// Approximate 95% interval from the spread of daily earnings
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    /// Fitted change in daily earnings per day
+    pub daily_trend: f64,
+    /// "low", "medium" or "high", from the interval width
+    pub confidence: &'static str,
+}
+
+/// Earnings forecast for an artist (an estimate, not a guarantee)
+#[derive(Debug, Serialize)]
+pub struct ArtistForecast {
+    pub address: String,
+    pub is_estimate: bool,
+    pub window_days: i64,
+    pub horizon_days: i64,
+    pub plays_per_day_7d: f64,
+    pub plays_per_day_30d: f64,
+    pub strategies: Vec<StrategyVelocity>,
+    /// None when there isn't enough history to fit a trend
+    pub projection: Option<EarningsProjection>,
+    pub note: String,
+}
+
+/// Forecast an artist's next 30 days of earnings from recent play velocity
+///
+/// Fits a linear trend to the last 30 days of daily earnings (each strategy's
+/// plays valued at its observed per-play rate) and extrapolates it.
+pub async fn get_artist_forecast(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<ArtistForecast>, StatusCode> {
+    // (days ago, strategy, plays, earnings), day 0 = today
+    let rows = sqlx::query_as::<_, (i32, String, i64, f64)>(
+        r#"
+        SELECT (CURRENT_DATE - p.timestamp::date)::int AS days_ago,
+               s.strategy_id,
+               COUNT(*) AS plays,
+               COALESCE(SUM(p.amount), 0)::float8 AS earnings
+        FROM plays p
+        JOIN songs s ON s.id = p.song_id
+        WHERE LOWER(s.artist_address) = LOWER($1)
+          AND p.timestamp >= CURRENT_DATE - ($2::int - 1)
+          AND p.excluded_reason IS NULL
+        GROUP BY days_ago, s.strategy_id
+        "#,
+    )
+    .bind(&address)
+    .bind(FORECAST_WINDOW_DAYS as i32)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get artist play history: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let rows: Vec<(usize, String, i64, f64)> = rows
+        .into_iter()
+        .map(|(days_ago, strategy_id, plays, earnings)| {
+            let days_ago = days_ago.clamp(0, FORECAST_WINDOW_DAYS as i32 - 1) as usize;
+            (days_ago, strategy_id, plays, earnings)
+        })
+        .collect();
+
+    let mut daily_plays = vec![0i64; FORECAST_WINDOW_DAYS as usize];
+    let mut by_strategy: BTreeMap<&str, (i64, i64, f64)> = BTreeMap::new();
+    for (days_ago, strategy_id, plays, earnings) in &rows {
+        daily_plays[*days_ago] += plays;
+
+        let totals = by_strategy.entry(strategy_id.as_str()).or_default();
+        if *days_ago < 7 {
+            totals.0 += plays;
+        }
+        totals.1 += plays;
+        totals.2 += earnings;
+    }
+
+    let strategies: Vec<StrategyVelocity> = by_strategy
+        .iter()
+        .map(|(strategy_id, &(plays_7d, plays_30d, earnings))| StrategyVelocity {
+            strategy_id: strategy_id.to_string(),
+            plays_7d,
+            plays_30d,
+            plays_per_day_7d: plays_7d as f64 / 7.0,
+            plays_per_day_30d: plays_30d as f64 / FORECAST_WINDOW_DAYS as f64,
+            earnings_per_play: if plays_30d > 0 { earnings / plays_30d as f64 } else { 0.0 },
+        })
+        .collect();
+
+    // Value each day's plays at its strategy's per-play rate
+    let mut daily_earnings = vec![0.0; FORECAST_WINDOW_DAYS as usize];
+    for (days_ago, strategy_id, plays, _) in &rows {
+        let rate = strategies
+            .iter()
+            .find(|s| &s.strategy_id == strategy_id)
+            .map_or(0.0, |s| s.earnings_per_play);
+        daily_earnings[*days_ago] += *plays as f64 * rate;
+    }
+
+    let plays_7d: i64 = daily_plays[..7].iter().sum();
+    let plays_30d: i64 = daily_plays.iter().sum();
+
+    let active_days = daily_plays.iter().filter(|&&plays| plays > 0).count();
+    let (projection, note) = if active_days < MIN_ACTIVE_DAYS {
+        (
+            None,
+            format!(
+                "Not enough play history to forecast ({} of {} days with plays needed)",
+                active_days, MIN_ACTIVE_DAYS
+            ),
+        )
+    } else {
+        // Oldest day first so the slope reads forward in time
+        daily_earnings.reverse();
+        (
+            Some(project_earnings(&daily_earnings, FORECAST_HORIZON_DAYS as usize)),
+            "Estimate from a linear trend over the last 30 days; actual earnings will vary"
+                .to_string(),
+        )
+    };
+
+    Ok(Json(ArtistForecast {
+        address,
+        is_estimate: true,
+        window_days: FORECAST_WINDOW_DAYS,
+        horizon_days: FORECAST_HORIZON_DAYS,
+        plays_per_day_7d: plays_7d as f64 / 7.0,
+        plays_per_day_30d: plays_30d as f64 / FORECAST_WINDOW_DAYS as f64,
+        strategies,
+        projection,
+        note,
+    }))
+}
+
+/// Least-squares fit over daily earnings (oldest first), summed over the
+/// next `horizon` days
+fn project_earnings(daily: &[f64], horizon: usize) -> EarningsProjection {
+    let n = daily.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = daily.iter().sum::<f64>() / n;
+
+    let (mut sxx, mut sxy) = (0.0, 0.0);
+    for (x, y) in daily.iter().enumerate() {
+        let dx = x as f64 - mean_x;
+        sxx += dx * dx;
+        sxy += dx * (y - mean_y);
+    }
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    let intercept = mean_y - slope * mean_x;
+
+    // Residual variance of a single day around the trend
+    let sse: f64 = daily
+        .iter()
+        .enumerate()
+        .map(|(x, y)| (y - (intercept + slope * x as f64)).powi(2))
+        .sum();
+    let residual_var = if n > 2.0 { sse / (n - 2.0) } else { 0.0 };
+
+    // Earnings can't go negative, so a declining trend bottoms out at zero
+    let projected: f64 = (daily.len()..daily.len() + horizon)
+        .map(|x| (intercept + slope * x as f64).max(0.0))
+        .sum();
+
+    // Treat future days as independent around the trend
+    let margin = 1.96 * (residual_var * horizon as f64).sqrt();
+    let relative_width = if projected > 0.0 { margin / projected } else { f64::INFINITY };
+
+    EarningsProjection {
+        projected_earnings: projected,
+        lower_bound: (projected - margin).max(0.0),
+        upper_bound: projected + margin,
+        daily_trend: slope,
+        confidence: if relative_width < 0.25 {
+            "high"
+        } else if relative_width < 0.75 {
+            "medium"
+        } else {
+            "low"
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EarningsQuery {
+    /// ISO 4217 code, or XDAI (default USD)
+    pub currency: Option<String>,
+}
+
+/// An artist's earnings in a display currency
+///
+/// xDAI figures are canonical; converted figures are for display only.
+#[derive(Debug, Serialize)]
+pub struct ArtistEarnings {
+    pub address: String,
+    pub currency: String,
+    /// Latest rate (units of `currency` per xDAI)
+    pub rate: f64,
+    pub rate_at: chrono::DateTime<chrono::Utc>,
+    /// Lifetime earnings in xDAI
+    pub total_earnings: f64,
+    /// `total_earnings` at the latest rate
+    pub total_earnings_converted: f64,
+    /// On-chain payments for the artist's songs, in xDAI
+    pub settled: f64,
+    /// `settled` at the rates in force when each payment was made
+    pub settled_converted: f64,
+    /// Payments older than any recorded rate, converted at the latest one
+    pub payments_at_latest_rate: i64,
+}
+
+/// Get an artist's earnings in a display currency
+pub async fn get_artist_earnings(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    Query(params): Query<EarningsQuery>,
+) -> Result<Json<ArtistEarnings>, Response> {
+    let currency = params.currency.as_deref().unwrap_or("USD").to_uppercase();
+    let mut errors = ValidationErrors::new();
+    if !is_valid_eth_address(&address) {
+        errors.add("address", "Must be a 0x-prefixed 42-character address");
+    }
+    if currency.len() < 3
+        || currency.len() > 8
+        || !currency.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        errors.add("currency", "Must be an ISO 4217 code such as EUR, or XDAI");
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_response());
+    }
+
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to get artist earnings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    let latest = match fx::latest_rate(&state.db_pool, &currency)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(latest) => latest,
+        None => {
+            errors.add("currency", &format!("No exchange rate recorded for {}", currency));
+            return Err(errors.into_response());
+        }
+    };
+
+    let total_earnings: f64 = sqlx::query_scalar(
+        "SELECT total_earnings::float8 FROM artist_stats WHERE artist_address = LOWER($1)",
+    )
+    .bind(&address)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(internal_error)?
+    .unwrap_or(0.0);
+
+    let (settled, settled_converted, payments_at_latest_rate) =
+        sqlx::query_as::<_, (f64, f64, i64)>(
+            r#"
+            SELECT COALESCE(SUM(pay.amount), 0)::float8,
+                   COALESCE(SUM(pay.amount * COALESCE(r.rate, $3)), 0)::float8,
+                   COUNT(*) FILTER (WHERE r.rate IS NULL)
+            FROM (
+                SELECT p.tx_hash, p.log_index,
+                       (CAST(p.amount_wei AS NUMERIC) / 1e18)::float8 AS amount
+                FROM payments p
+                JOIN songs s ON s.song_id = p.song_id
+                WHERE LOWER(s.artist_address) = LOWER($1)
+            ) pay
+            LEFT JOIN payment_fx_rates r
+                ON r.tx_hash = pay.tx_hash AND r.log_index = pay.log_index AND r.currency = $2
+            "#,
+        )
+        .bind(&address)
+        .bind(&currency)
+        .bind(latest.rate)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(ArtistEarnings {
+        address: address.to_lowercase(),
+        total_earnings_converted: total_earnings * latest.rate,
+        total_earnings,
+        settled,
+        settled_converted,
+        // xDAI needs no conversion
+        payments_at_latest_rate: if currency == fx::NATIVE_CURRENCY {
+            0
+        } else {
+            payments_at_latest_rate
+        },
+        currency,
+        rate: latest.rate,
+        rate_at: latest.fetched_at,
+    }))
+}
@@ -0,0 +1,196 @@
+//! Auth Routes - Wallet sign-in and request authentication
+//!
+//! `POST /api/auth/nonce` issues a message to sign, `POST /api/auth/verify`
+//! exchanges the signature for a session token. `require_auth` guards
+//! mutating routes and exposes the caller as an `AuthUser` extension;
+//! `require_admin` additionally requires the admin role.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use ethers::types::Address;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::services::auth::{normalize_address, AuthService, NONCE_TTL_SECS};
+use crate::AppState;
+
+/// Authenticated caller, inserted into request extensions by `require_auth`
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    /// Lowercase 0x-prefixed address
+    pub address: String,
+    /// Listed in `ADMIN_ADDRESSES`
+    pub is_admin: bool,
+}
+
+impl AuthUser {
+    /// Whether a client-supplied address refers to this user
+    pub fn is(&self, address: &str) -> bool {
+        self.address.eq_ignore_ascii_case(address)
+    }
+}
+
+/// Nonce request
+#[derive(Debug, Deserialize)]
+pub struct NonceRequest {
+    pub address: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NonceResponse {
+    pub nonce: String,
+    /// Exact message the wallet must sign
+    pub message: String,
+    pub expires_in: u64,
+}
+
+/// Signature verification request
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    pub address: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyResponse {
+    pub token: String,
+    pub address: String,
+    pub expires_at: u64,
+}
+
+fn nonce_key(address: &str) -> String {
+    format!("auth:nonce:{}", address)
+}
+
+/// Issue a sign-in message for an address
+pub async fn request_nonce(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<NonceRequest>,
+) -> Result<Json<NonceResponse>, StatusCode> {
+    let address: Address = req.address.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let address = normalize_address(&address);
+
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let message = state.auth.sign_in_message(&address, &nonce);
+
+    let mut conn = state
+        .redis
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to connect to Redis: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // One outstanding message per address; a new request replaces the old one
+    conn.set_ex::<_, _, ()>(nonce_key(&address), &message, NONCE_TTL_SECS)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store auth nonce: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(NonceResponse {
+        nonce,
+        message,
+        expires_in: NONCE_TTL_SECS,
+    }))
+}
+
+/// Verify a signed sign-in message and issue a session token
+pub async fn verify(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<VerifyRequest>,
+) -> Result<Json<VerifyResponse>, StatusCode> {
+    let address: Address = req.address.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let normalized = normalize_address(&address);
+
+    let mut conn = state
+        .redis
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to connect to Redis: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Consume the message so a signature can only be exchanged once
+    let message: Option<String> = conn.get_del(nonce_key(&normalized)).await.map_err(|e| {
+        tracing::error!("Failed to load auth nonce: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let message = message.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let signer = AuthService::recover_signer(&message, &req.signature).map_err(|e| {
+        tracing::warn!("Rejected sign-in signature for {}: {}", normalized, e);
+        StatusCode::UNAUTHORIZED
+    })?;
+    if signer != address {
+        tracing::warn!("Sign-in signer mismatch: expected {}, got {:?}", normalized, signer);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let (token, expires_at) = state.auth.issue_token(&address).map_err(|e| {
+        tracing::error!("Failed to issue session token: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tracing::info!("Signed in {}", normalized);
+    Ok(Json(VerifyResponse {
+        token,
+        address: normalized,
+        expires_at,
+    }))
+}
+
+/// Resolve the caller from an `Authorization: Bearer <token>` header
+///
+/// Public routes use this directly to tailor results to a signed-in viewer.
+pub fn authenticate(auth: &AuthService, headers: &HeaderMap) -> Option<AuthUser> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))?;
+
+    let claims = auth
+        .verify_token(token)
+        .map_err(|e| tracing::debug!("Rejected request: {}", e))
+        .ok()?;
+
+    Some(AuthUser {
+        is_admin: auth.is_admin(&claims.sub),
+        address: claims.sub,
+    })
+}
+
+/// Require a valid `Authorization: Bearer <token>` header
+pub async fn require_auth(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let user = authenticate(&state.auth, req.headers()).ok_or(StatusCode::UNAUTHORIZED)?;
+    req.extensions_mut().insert(user);
+    Ok(next.run(req).await)
+}
+
+/// Require an authenticated admin (layer inside `require_auth`)
+pub async fn require_admin(req: Request, next: Next) -> Result<Response, StatusCode> {
+    let user = req
+        .extensions()
+        .get::<AuthUser>()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if !user.is_admin {
+        tracing::warn!("Non-admin {} denied access to {}", user.address, req.uri());
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(req).await)
+}
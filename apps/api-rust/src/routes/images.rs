@@ -0,0 +1,191 @@
+//! Image Routes - Cover-art thumbnails
+//!
+//! Thumbnails are generated on upload and otherwise lazily on first
+//! request, stored on IPFS, and indexed by (source CID, size).
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
+};
+use futures::TryStreamExt;
+use ipfs_api_backend_hyper::IpfsApi;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::is_valid_cid;
+use crate::services::thumbnail::{generate_thumbnail, THUMBNAIL_CONTENT_TYPE, THUMBNAIL_SIZES};
+use crate::AppState;
+
+/// Largest source image fetched from IPFS for on-demand thumbnails (10MB)
+const MAX_SOURCE_IMAGE_SIZE: usize = 10 * 1024 * 1024;
+
+/// Longest an IPFS fetch for a thumbnail may take
+const IPFS_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A stored thumbnail rendition
+#[derive(Debug, Clone, Serialize)]
+pub struct Thumbnail {
+    pub size: u32,
+    pub ipfs_hash: String,
+    pub gateway_url: String,
+    pub gateway_urls: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbQuery {
+    pub size: Option<u32>,
+}
+
+/// Serve a thumbnail of an image, generating and caching it on first request
+///
+/// Only songs' cover art is thumbnailed on demand, so the endpoint can't be
+/// used to make the server fetch and decode arbitrary CIDs.
+pub async fn get_thumbnail(
+    State(state): State<Arc<AppState>>,
+    Path(cid): Path<String>,
+    Query(params): Query<ThumbQuery>,
+) -> Result<Response, StatusCode> {
+    let size = params.size.unwrap_or(THUMBNAIL_SIZES[0]);
+    if !THUMBNAIL_SIZES.contains(&size) || !is_valid_cid(&cid) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let existing: Option<String> = sqlx::query_scalar(
+        "SELECT thumb_cid FROM image_thumbnails WHERE source_cid = $1 AND size = $2",
+    )
+    .bind(&cid)
+    .bind(size as i32)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up thumbnail: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let data = match existing {
+        Some(thumb_cid) => cat(&state, &thumb_cid, MAX_SOURCE_IMAGE_SIZE).await.map_err(|e| {
+            tracing::error!("Failed to fetch thumbnail {}: {}", thumb_cid, e);
+            StatusCode::BAD_GATEWAY
+        })?,
+        None => {
+            let is_cover: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM songs WHERE cover_cid = $1)")
+                    .bind(&cid)
+                    .fetch_one(&state.db_pool)
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("Failed to look up cover {}: {}", cid, e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+            if !is_cover {
+                return Err(StatusCode::NOT_FOUND);
+            }
+
+            let source = cat(&state, &cid, MAX_SOURCE_IMAGE_SIZE).await.map_err(|e| {
+                tracing::warn!("Failed to fetch source image {}: {}", cid, e);
+                StatusCode::NOT_FOUND
+            })?;
+            let data = generate_thumbnail(source, size).await.map_err(|e| {
+                tracing::warn!("Failed to generate thumbnail for {}: {}", cid, e);
+                StatusCode::UNPROCESSABLE_ENTITY
+            })?;
+            // Serving still succeeds if caching fails; the next request retries
+            if let Err(e) = store_thumbnail(&state, &cid, size, data.clone()).await {
+                tracing::warn!("Failed to cache thumbnail for {}: {}", cid, e);
+            }
+            data
+        }
+    };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, THUMBNAIL_CONTENT_TYPE)
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .body(Body::from(data))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Generate and store every thumbnail size for a freshly uploaded image
+///
+/// Failures are logged and skipped; missing sizes are filled in lazily.
+pub async fn create_thumbnails(state: &AppState, source_cid: &str, data: &[u8]) -> Vec<Thumbnail> {
+    let mut thumbnails = Vec::new();
+    for &size in THUMBNAIL_SIZES {
+        let rendered = match generate_thumbnail(data.to_vec(), size).await {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                tracing::warn!("Failed to generate {}px thumbnail for {}: {}", size, source_cid, e);
+                continue;
+            }
+        };
+        match store_thumbnail(state, source_cid, size, rendered).await {
+            Ok(thumbnail) => thumbnails.push(thumbnail),
+            Err(e) => tracing::warn!("Failed to store {}px thumbnail for {}: {}", size, source_cid, e),
+        }
+    }
+    thumbnails
+}
+
+/// Look up stored thumbnails for an image, keyed by size
+pub async fn find_thumbnails(state: &AppState, source_cid: &str) -> sqlx::Result<Vec<(u32, String)>> {
+    let rows = sqlx::query_as::<_, (i32, String)>(
+        "SELECT size, thumb_cid FROM image_thumbnails WHERE source_cid = $1 ORDER BY size",
+    )
+    .bind(source_cid)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|(size, cid)| (size as u32, cid)).collect())
+}
+
+/// Upload a rendered thumbnail to IPFS and index it
+async fn store_thumbnail(
+    state: &AppState,
+    source_cid: &str,
+    size: u32,
+    data: Vec<u8>,
+) -> anyhow::Result<Thumbnail> {
+    let added = state.ipfs_client.add(std::io::Cursor::new(data)).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO image_thumbnails (source_cid, size, thumb_cid)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (source_cid, size) DO NOTHING
+        "#,
+    )
+    .bind(source_cid)
+    .bind(size as i32)
+    .bind(&added.hash)
+    .execute(&state.db_pool)
+    .await?;
+
+    let urls = state.gateways.urls(&added.hash).await;
+    Ok(Thumbnail {
+        size,
+        gateway_url: urls.first().cloned().unwrap_or_default(),
+        gateway_urls: urls,
+        ipfs_hash: added.hash,
+    })
+}
+
+/// Fetch a file from IPFS, refusing anything larger than `max_size` or
+/// slower than `IPFS_FETCH_TIMEOUT`
+async fn cat(state: &AppState, cid: &str, max_size: usize) -> anyhow::Result<Vec<u8>> {
+    let fetch = async {
+        let mut stream = state.ipfs_client.cat(cid);
+        let mut data = Vec::new();
+        while let Some(chunk) = stream.try_next().await? {
+            data.extend_from_slice(&chunk);
+            if data.len() > max_size {
+                anyhow::bail!("{} exceeds {} bytes", cid, max_size);
+            }
+        }
+        Ok::<_, anyhow::Error>(data)
+    };
+    tokio::time::timeout(IPFS_FETCH_TIMEOUT, fetch)
+        .await
+        .map_err(|_| anyhow::anyhow!("Fetching {} timed out", cid))?
+}
@@ -0,0 +1,17 @@
+//! Indexer Routes - Event indexer progress for monitoring
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::sync::Arc;
+
+use crate::services::indexer::IndexerStatus;
+use crate::AppState;
+
+/// How far the indexer is behind the chain head, and whether it is alive
+///
+/// 503 when the indexer isn't configured (`ROUTER_ADDRESS` unset).
+pub async fn get_indexer_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<IndexerStatus>, StatusCode> {
+    let indexer = state.indexer.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok(Json(indexer.status(chrono::Utc::now())))
+}
@@ -0,0 +1,259 @@
+//! Listener Routes - Outstanding debt and purchased downloads
+//!
+//! A listener owes for paying plays that no on-chain payment has settled
+//! yet (`plays.settled_at`, set by the payments projection). This mirrors
+//! the plays zome's `get_my_balance_owed`, which only answers for the
+//! conductor's own agent. Their library is every song they bought a
+//! download of on-chain.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::models::{is_valid_eth_address, ValidationErrors};
+use crate::routes::auth::AuthUser;
+use crate::routes::profiles::lookup_profile;
+use crate::services::blockchain::PaymentType;
+use crate::AppState;
+
+/// Default owed total that prompts a listener to settle
+const DEFAULT_SETTLEMENT_THRESHOLD: f64 = 0.1;
+
+/// Owed total that prompts a listener to settle, from
+/// `SETTLEMENT_NUDGE_THRESHOLD` (0 never prompts)
+pub fn settlement_threshold_from_env() -> f64 {
+    std::env::var("SETTLEMENT_NUDGE_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|threshold| threshold.is_finite() && *threshold >= 0.0)
+        .unwrap_or(DEFAULT_SETTLEMENT_THRESHOLD)
+}
+
+/// What a listener owes one artist
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ArtistOwed {
+    pub artist_address: String,
+    /// Artist's display name, or an abbreviated address if unknown
+    #[sqlx(default)]
+    pub display_name: String,
+    pub amount_owed: f64,
+    pub play_count: i64,
+}
+
+/// A listener's unsettled plays, by artist
+#[derive(Debug, Serialize)]
+pub struct ListenerOwed {
+    pub listener_address: String,
+    pub total_owed: f64,
+    pub play_count: i64,
+    /// `total_owed` reached the settlement threshold; prompt to settle
+    pub needs_settlement: bool,
+    pub settlement_threshold: f64,
+    /// Largest debt first
+    pub by_artist: Vec<ArtistOwed>,
+}
+
+/// Get what a listener owes artists for unsettled plays
+pub async fn get_listener_owed(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<ListenerOwed>, Response> {
+    if !is_valid_eth_address(&address) {
+        let mut errors = ValidationErrors::new();
+        errors.add("address", "Must be a 0x-prefixed 42-character address");
+        return Err(errors.into_response());
+    }
+
+    // Plays of deleted or removed songs are written off; plays held while
+    // a song's earnings are paused are still owed
+    let mut by_artist = sqlx::query_as::<_, ArtistOwed>(
+        r#"
+        SELECT LOWER(s.artist_address) AS artist_address,
+               COALESCE(SUM(p.amount), 0)::float8 AS amount_owed,
+               COUNT(*) AS play_count
+        FROM plays p
+        JOIN songs s ON s.id = p.song_id
+        WHERE LOWER(p.listener_address) = LOWER($1)
+          AND p.settled_at IS NULL
+          AND p.amount > 0
+          AND (p.excluded_reason IS NULL OR p.excluded_reason = 'earnings_paused')
+        GROUP BY LOWER(s.artist_address)
+        ORDER BY amount_owed DESC, artist_address
+        "#,
+    )
+    .bind(&address)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get listener debt: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let profiles = join_all(
+        by_artist
+            .iter()
+            .map(|artist| lookup_profile(&state, &artist.artist_address)),
+    )
+    .await;
+    for (artist, profile) in by_artist.iter_mut().zip(profiles) {
+        artist.display_name = profile
+            .map(|profile| profile.display_name)
+            .unwrap_or_else(|| artist.artist_address.clone());
+    }
+
+    let total_owed: f64 = by_artist.iter().map(|artist| artist.amount_owed).sum();
+    let threshold = state.settlement_threshold;
+    Ok(Json(ListenerOwed {
+        listener_address: address.to_lowercase(),
+        total_owed,
+        play_count: by_artist.iter().map(|artist| artist.play_count).sum(),
+        needs_settlement: threshold > 0.0 && total_owed >= threshold,
+        settlement_threshold: threshold,
+        by_artist,
+    }))
+}
+
+/// Library filters (all optional)
+#[derive(Debug, Deserialize)]
+pub struct LibraryQuery {
+    /// Only songs by this artist
+    pub artist: Option<String>,
+    /// Bought on or after this day (UTC)
+    pub from: Option<NaiveDate>,
+    /// Bought on or before this day (UTC)
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PurchaseRow {
+    song_id: uuid::Uuid,
+    title: String,
+    artist_address: String,
+    ipfs_hash: String,
+    purchased_at: chrono::DateTime<chrono::Utc>,
+    tx_hash: String,
+}
+
+/// A purchased song with a fresh download link
+#[derive(Debug, Serialize)]
+pub struct LibraryItem {
+    pub song_id: uuid::Uuid,
+    pub title: String,
+    pub artist_address: String,
+    /// Artist's display name, or the address if unknown
+    pub artist_display_name: String,
+    /// First download purchase of the song
+    pub purchased_at: chrono::DateTime<chrono::Utc>,
+    pub tx_hash: String,
+    /// Lossless original
+    pub ipfs_hash: String,
+    /// Signed link to the original through the streaming endpoint
+    pub download_url: String,
+    /// When `download_url` stops working (unix seconds)
+    pub download_expires_at: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListenerLibrary {
+    pub listener_address: String,
+    /// Most recent purchase first
+    pub songs: Vec<LibraryItem>,
+}
+
+/// Get the songs a listener bought downloads of (owner only)
+pub async fn get_listener_library(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(address): Path<String>,
+    Query(params): Query<LibraryQuery>,
+) -> Result<Json<ListenerLibrary>, Response> {
+    let mut errors = ValidationErrors::new();
+    if !is_valid_eth_address(&address) {
+        errors.add("address", "Must be a 0x-prefixed 42-character address");
+    }
+    if let Some(artist) = &params.artist {
+        if !is_valid_eth_address(artist) {
+            errors.add("artist", "Must be a 0x-prefixed 42-character address");
+        }
+    }
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if to < from {
+            errors.add("to", "Period must end on or after it starts");
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_response());
+    }
+    if !user.is(&address) {
+        tracing::warn!("{} tried to read the library of {}", user.address, address);
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+
+    // Taken-down songs can no longer be downloaded
+    let rows = sqlx::query_as::<_, PurchaseRow>(
+        r#"
+        SELECT DISTINCT ON (s.id)
+               s.id AS song_id, s.title, LOWER(s.artist_address) AS artist_address, s.ipfs_hash,
+               p.timestamp AS purchased_at, p.tx_hash
+        FROM payments p
+        JOIN songs s ON s.song_id = p.song_id
+        WHERE LOWER(p.listener_address) = LOWER($1)
+          AND p.payment_type = $2
+          AND s.status <> 'removed'
+          AND ($3::text IS NULL OR LOWER(s.artist_address) = LOWER($3))
+          AND ($4::date IS NULL OR p.timestamp >= $4)
+          AND ($5::date IS NULL OR p.timestamp < $5 + 1)
+        ORDER BY s.id, p.timestamp
+        "#,
+    )
+    .bind(&address)
+    .bind(PaymentType::Download as i16)
+    .bind(&params.artist)
+    .bind(params.from)
+    .bind(params.to)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get listener library: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let profiles = join_all(rows.iter().map(|row| lookup_profile(&state, &row.artist_address))).await;
+
+    let mut songs = Vec::with_capacity(rows.len());
+    for (row, profile) in rows.into_iter().zip(profiles) {
+        let (token, expires_at) = state
+            .auth
+            .issue_download_token(&user.address, &row.ipfs_hash)
+            .map_err(|e| {
+                tracing::error!("Failed to sign download link: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?;
+        songs.push(LibraryItem {
+            artist_display_name: profile
+                .map(|profile| profile.display_name)
+                .unwrap_or_else(|| row.artist_address.clone()),
+            download_url: format!("/api/stream/{}?token={}", row.ipfs_hash, token),
+            download_expires_at: expires_at,
+            song_id: row.song_id,
+            title: row.title,
+            artist_address: row.artist_address,
+            purchased_at: row.purchased_at,
+            tx_hash: row.tx_hash,
+            ipfs_hash: row.ipfs_hash,
+        });
+    }
+    songs.sort_by(|a, b| b.purchased_at.cmp(&a.purchased_at));
+
+    Ok(Json(ListenerLibrary {
+        listener_address: address.to_lowercase(),
+        songs,
+    }))
+}
@@ -0,0 +1,147 @@
+//! Live Routes - Real-time play feed for artist dashboards
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::stream;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+
+use crate::models::{is_valid_eth_address, ValidationErrors};
+use crate::services::live::{plays_since, LivePlay};
+use crate::AppState;
+
+/// Most plays replayed at once when resuming or catching up
+const REPLAY_LIMIT: i64 = 500;
+
+struct LiveStream {
+    state: Arc<AppState>,
+    artist: String,
+    receiver: broadcast::Receiver<LivePlay>,
+    shutdown: watch::Receiver<bool>,
+    /// Plays loaded from the DB, sent before live ones
+    backlog: VecDeque<LivePlay>,
+    /// Highest id sent or queued; live copies at or below it are dupes
+    sent_through: i64,
+}
+
+impl LiveStream {
+    /// Queue the artist's plays after `after_id`; false if the DB failed
+    async fn replay(&mut self, after_id: i64) -> bool {
+        match plays_since(&self.state.db_pool, &self.artist, after_id, REPLAY_LIMIT).await {
+            Ok(plays) => {
+                if let Some(last) = plays.last() {
+                    self.sent_through = self.sent_through.max(last.id);
+                }
+                self.backlog.extend(plays);
+                true
+            }
+            Err(e) => {
+                tracing::error!("Failed to replay live plays: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Next play to send, or None once the stream should end
+    async fn next_play(&mut self) -> Option<LivePlay> {
+        if let Some(play) = self.backlog.pop_front() {
+            return Some(play);
+        }
+
+        loop {
+            if *self.shutdown.borrow() {
+                return None;
+            }
+            tokio::select! {
+                received = self.receiver.recv() => match received {
+                    Ok(play) => {
+                        if play.artist_address == self.artist && play.id > self.sent_through {
+                            self.sent_through = play.id;
+                            return Some(play);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        // Fell behind the feed; pick the missed plays up from the DB
+                        let after_id = self.sent_through;
+                        if !self.replay(after_id).await {
+                            return None;
+                        }
+                        if let Some(play) = self.backlog.pop_front() {
+                            return Some(play);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                changed = self.shutdown.changed() => {
+                    if changed.is_err() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Stream an artist's plays as server-sent `play` events
+///
+/// Each event's id is the play id. Reconnecting clients send it back as
+/// `Last-Event-ID` and first receive the plays recorded since (up to 500).
+pub async fn get_artist_live(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let mut errors = ValidationErrors::new();
+    if !is_valid_eth_address(&address) {
+        errors.add("address", "Must be a 0x-prefixed 42-character address");
+    }
+    let last_event_id = match headers.get("last-event-id") {
+        Some(value) => match value.to_str().ok().and_then(|v| v.trim().parse::<i64>().ok()) {
+            Some(id) => Some(id),
+            None => {
+                errors.add("Last-Event-ID", "Must be the id of a previous play event");
+                None
+            }
+        },
+        None => None,
+    };
+    if !errors.is_empty() {
+        return errors.into_response();
+    }
+
+    // Subscribe before replaying so nothing recorded in between is lost
+    let mut live = LiveStream {
+        receiver: state.play_feed.subscribe(),
+        shutdown: state.play_feed.shutdown(),
+        state: state.clone(),
+        artist: address.to_lowercase(),
+        backlog: VecDeque::new(),
+        sent_through: 0,
+    };
+    if let Some(after_id) = last_event_id {
+        if !live.replay(after_id).await {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    // Dropping the stream on disconnect drops the subscription with it
+    let events = stream::unfold(live, |mut live| async move {
+        let play = live.next_play().await?;
+        let event = Event::default()
+            .id(play.id.to_string())
+            .event("play")
+            .json_data(&play)
+            .ok()?;
+        Some((Ok::<_, Infallible>(event), live))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
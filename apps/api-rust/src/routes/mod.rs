@@ -1,9 +1,20 @@
 //! API Route Handlers
 //!
-//! Organized by domain: songs, artists, analytics, uploads, strategies
+//! Organized by domain: auth, admin, songs, artists, listeners, live, analytics, uploads, stream, images, payments, indexer, settlements, statements, strategies, profiles
 
+pub mod auth;
+pub mod admin;
 pub mod songs;
 pub mod artists;
+pub mod listeners;
+pub mod live;
 pub mod analytics;
 pub mod uploads;
+pub mod stream;
+pub mod images;
+pub mod payments;
+pub mod indexer;
+pub mod settlements;
+pub mod statements;
 pub mod strategies;
+pub mod profiles;
@@ -0,0 +1,127 @@
+//! Payment Routes - Indexed on-chain payments
+//!
+//! Payments are written by the event indexer. Confirmation depth and
+//! finality are derived from the chain head it last observed, so they
+//! advance with the chain without rewriting payment rows.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::AppState;
+
+/// Settlement stage of an indexed payment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    /// Not yet past the confirmation depth (or chain head unknown)
+    Pending,
+    /// Past the indexer's confirmation depth; likely final
+    Confirmed,
+    /// Past the finality depth
+    Finalized,
+}
+
+/// Indexed payment with chain-relative finality
+#[derive(Debug, Serialize)]
+pub struct PaymentRecord {
+    pub tx_hash: String,
+    pub block_number: i64,
+    pub song_id: String,
+    pub listener_address: String,
+    pub amount_wei: String,
+    pub payment_type: i16,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Blocks on top of this payment's block (0 if the head is unknown)
+    pub confirmation_depth: i64,
+    pub finalized: bool,
+    pub status: PaymentStatus,
+}
+
+/// Payment row joined with the indexer's chain state
+#[derive(Debug, sqlx::FromRow)]
+struct PaymentRow {
+    tx_hash: String,
+    block_number: i64,
+    song_id: String,
+    listener_address: String,
+    amount_wei: String,
+    payment_type: i16,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    head_block: Option<i64>,
+    safe_block: Option<i64>,
+    finalized_block: Option<i64>,
+}
+
+impl From<PaymentRow> for PaymentRecord {
+    fn from(row: PaymentRow) -> Self {
+        let finalized = row.finalized_block.map_or(false, |f| row.block_number <= f);
+        let status = if finalized {
+            PaymentStatus::Finalized
+        } else if row.safe_block.map_or(false, |s| row.block_number <= s) {
+            PaymentStatus::Confirmed
+        } else {
+            PaymentStatus::Pending
+        };
+
+        Self {
+            confirmation_depth: row.head_block.map_or(0, |h| (h - row.block_number).max(0)),
+            tx_hash: row.tx_hash,
+            block_number: row.block_number,
+            song_id: row.song_id,
+            listener_address: row.listener_address,
+            amount_wei: row.amount_wei,
+            payment_type: row.payment_type,
+            timestamp: row.timestamp,
+            finalized,
+            status,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPaymentsQuery {
+    pub listener: Option<String>,
+    pub song_id: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// List indexed payments, newest first
+pub async fn list_payments(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ListPaymentsQuery>,
+) -> Result<Json<Vec<PaymentRecord>>, StatusCode> {
+    let limit = params.limit.unwrap_or(50).min(100);
+    let offset = params.offset.unwrap_or(0);
+
+    let rows = sqlx::query_as::<_, PaymentRow>(
+        r#"
+        SELECT p.tx_hash, p.block_number, p.song_id, p.listener_address,
+               p.amount_wei, p.payment_type, p.timestamp,
+               c.head_block, c.safe_block, c.finalized_block
+        FROM payments p
+        LEFT JOIN indexer_chain_state c ON c.id = 1
+        WHERE ($1::text IS NULL OR LOWER(p.listener_address) = LOWER($1))
+          AND ($2::text IS NULL OR p.song_id = $2)
+        ORDER BY p.block_number DESC, p.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(&params.listener)
+    .bind(params.song_id.as_deref().map(|s| s.trim_start_matches("0x").to_lowercase()))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list payments: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows.into_iter().map(PaymentRecord::from).collect()))
+}
@@ -0,0 +1,247 @@
+//! Profile Routes - Identity resolution for UI rendering
+//!
+//! Resolves an Ethereum address or Holochain agent key to a display
+//! profile: the catalog `ArtistProfile` merged with trust verification.
+//! Unknown identities get a minimal default profile instead of a 404.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use ethers::types::Address;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::services::auth::normalize_address;
+use crate::services::holochain::HolochainService;
+use crate::AppState;
+
+/// Cache lifetime for resolved profiles (seconds)
+const PROFILE_TTL_SECS: u64 = 3600;
+
+/// Cache lifetime for unknown identities, so new profiles show up quickly
+const UNKNOWN_PROFILE_TTL_SECS: u64 = 300;
+
+/// Agent keys are base64url HoloHashes with a `u` multibase prefix
+const AGENT_KEY_PREFIX: &str = "uhCAk";
+
+/// Artist profile as returned by `catalog::get_artist_profile`
+#[derive(Debug, Deserialize)]
+struct CatalogProfile {
+    name: String,
+    bio: String,
+    avatar_cid: Option<String>,
+    payment_address: String,
+}
+
+/// Verification as returned by `trust::get_verification_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verification {
+    pub tier: String,
+    pub trust_score: u32,
+    pub vouch_count: u32,
+}
+
+/// Display profile for an identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub identifier: String,
+    pub eth_address: Option<String>,
+    pub agent_key: Option<String>,
+    pub display_name: String,
+    pub bio: String,
+    pub avatar_cid: Option<String>,
+    pub avatar_url: Option<String>,
+    pub verified: bool,
+    pub verification: Option<Verification>,
+    /// False when no catalog profile exists and defaults were filled in
+    pub known: bool,
+}
+
+/// The kind of identity a request names
+enum Identity {
+    EthAddress(String),
+    AgentKey(String),
+}
+
+impl Identity {
+    fn parse(identifier: &str) -> Option<Self> {
+        if identifier.starts_with(AGENT_KEY_PREFIX) {
+            return Some(Self::AgentKey(identifier.to_string()));
+        }
+        identifier
+            .parse::<Address>()
+            .ok()
+            .map(|address| Self::EthAddress(normalize_address(&address)))
+    }
+
+    /// Canonical form, used for cache keys
+    fn key(&self) -> &str {
+        match self {
+            Self::EthAddress(address) | Self::AgentKey(address) => address,
+        }
+    }
+}
+
+fn profile_key(identity: &Identity) -> String {
+    format!("profile:{}", identity.key())
+}
+
+/// Resolve an eth address or agent key to a display profile
+pub async fn get_profile(
+    State(state): State<Arc<AppState>>,
+    Path(identifier): Path<String>,
+) -> Result<Json<Profile>, StatusCode> {
+    lookup_profile(&state, &identifier)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::BAD_REQUEST)
+}
+
+/// Display profile for an identifier, from the cache or the DHT
+///
+/// Returns None only when the identifier is neither an eth address nor
+/// an agent key.
+pub async fn lookup_profile(state: &AppState, identifier: &str) -> Option<Profile> {
+    let identity = Identity::parse(identifier)?;
+    let key = profile_key(&identity);
+
+    let mut conn = state.redis.get_multiplexed_async_connection().await.ok();
+    if let Some(conn) = conn.as_mut() {
+        let cached: Option<String> = conn.get(&key).await.unwrap_or(None);
+        if let Some(profile) = cached.and_then(|json| serde_json::from_str(&json).ok()) {
+            return Some(profile);
+        }
+    }
+
+    let mut profile = match state.holochain.as_ref() {
+        Some(holochain) => match resolve(holochain, &identity).await {
+            Ok(profile) => profile,
+            // Don't cache a conductor outage as an unknown identity
+            Err(e) => {
+                tracing::warn!("Failed to resolve profile {}: {}", identifier, e);
+                return Some(default_profile(&identity));
+            }
+        },
+        None => default_profile(&identity),
+    };
+
+    if let Some(cid) = &profile.avatar_cid {
+        profile.avatar_url = state.gateways.urls(cid).await.into_iter().next();
+    }
+
+    if let Some(conn) = conn.as_mut() {
+        let ttl = if profile.known {
+            PROFILE_TTL_SECS
+        } else {
+            UNKNOWN_PROFILE_TTL_SECS
+        };
+        if let Ok(json) = serde_json::to_string(&profile) {
+            if let Err(e) = conn.set_ex::<_, _, ()>(&key, json, ttl).await {
+                tracing::debug!("Failed to cache profile {}: {}", identifier, e);
+            }
+        }
+    }
+
+    Some(profile)
+}
+
+/// Look the identity up in the catalog and trust zomes
+async fn resolve(holochain: &HolochainService, identity: &Identity) -> anyhow::Result<Profile> {
+    let agent = match identity {
+        Identity::AgentKey(agent) => Some(agent.clone()),
+        Identity::EthAddress(address) => {
+            holochain
+                .call_zome("catalog", "get_agent_by_payment_address", address)
+                .await?
+        }
+    };
+    let agent = match agent {
+        Some(agent) => agent,
+        None => return Ok(default_profile(identity)),
+    };
+
+    let catalog: Option<CatalogProfile> = holochain
+        .call_zome("catalog", "get_artist_profile", &agent)
+        .await?;
+    let verification: Option<Verification> = holochain
+        .call_zome("trust", "get_verification_status", &agent)
+        .await?;
+
+    let mut profile = default_profile(identity);
+    profile.agent_key = Some(agent);
+    profile.verified = verification
+        .as_ref()
+        .map_or(false, |v| v.tier != "Unverified");
+    profile.verification = verification;
+
+    if let Some(catalog) = catalog {
+        if !catalog.name.is_empty() {
+            profile.display_name = catalog.name;
+        }
+        profile.bio = catalog.bio;
+        profile.avatar_cid = catalog.avatar_cid;
+        if profile.eth_address.is_none() && !catalog.payment_address.is_empty() {
+            profile.eth_address = Some(catalog.payment_address);
+        }
+        profile.known = true;
+    }
+
+    Ok(profile)
+}
+
+/// Minimal profile for identities with nothing on the DHT
+fn default_profile(identity: &Identity) -> Profile {
+    let (eth_address, agent_key) = match identity {
+        Identity::EthAddress(address) => (Some(address.clone()), None),
+        Identity::AgentKey(agent) => (None, Some(agent.clone())),
+    };
+
+    Profile {
+        identifier: identity.key().to_string(),
+        display_name: short_name(identity.key()),
+        eth_address,
+        agent_key,
+        bio: String::new(),
+        avatar_cid: None,
+        avatar_url: None,
+        verified: false,
+        verification: None,
+        known: false,
+    }
+}
+
+/// Abbreviated identifier for display, e.g. "0x1234…abcd"
+///
+/// Counts characters, not bytes: only the prefix of an agent key is
+/// checked, so the rest may be anything.
+fn short_name(identifier: &str) -> String {
+    let chars: Vec<char> = identifier.chars().collect();
+    if chars.len() <= 12 {
+        return identifier.to_string();
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_names_split_on_characters() {
+        assert_eq!(
+            short_name("0x1234567890abcdef1234567890abcdef12345678"),
+            "0x1234…5678"
+        );
+        assert_eq!(short_name("0x1234"), "0x1234");
+
+        // Multibyte characters straddling both byte cut points
+        let id = format!("{}éééééééé€€€€", AGENT_KEY_PREFIX);
+        assert!(Identity::parse(&id).is_some());
+        assert_eq!(short_name(&id), format!("{}é…€€€€", AGENT_KEY_PREFIX));
+    }
+}
@@ -0,0 +1,808 @@
+//! Settlement Routes - Trustless payout verification
+//!
+//! Checks that a settled batch's transaction really settled it: the
+//! target's verifier must have emitted `BatchSettled` for the batch, to
+//! the artist's payment address, for the batch's `total_amount` and merkle
+//! root. Verified results are stored in `settlement_proofs` per batch and
+//! transaction, since a mined payout never changes; a transaction verifies
+//! at most one batch.
+//! Play receipts prove a single play is in a batch's merkle root. Admins
+//! submit pending batches to the chain each one targets.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use ethers::types::{Address, Bytes, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::services::auth::normalize_address;
+use crate::services::blockchain::{BlockchainService, SignedSettlement};
+use crate::services::settlement::{
+    BatchPayout, SettlementChain, SettlementPayload, SettlementTarget, SignatureScheme,
+};
+use crate::AppState;
+
+/// Action hashes are base64url HoloHashes with a `u` multibase prefix
+const ACTION_HASH_PREFIX: &str = "uhCkk";
+
+/// Settlement batch as returned by `plays::get_settlement`
+#[derive(Debug, Deserialize)]
+struct SettlementBatch {
+    artist: String,
+    total_amount: u64,
+    merkle_root: Vec<u8>,
+    status: String,
+    #[serde(default)]
+    settlement_target: SettlementTarget,
+    tx_hash: Option<String>,
+}
+
+/// Artist profile as returned by `catalog::get_artist_profile`
+#[derive(Debug, Deserialize)]
+struct CatalogProfile {
+    payment_address: String,
+}
+
+/// Outcome of checking a batch against the chain
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// The verifier settled the batch to the artist for exactly its total
+    Verified,
+    /// The batch has no settlement transaction yet
+    Unsettled,
+    /// The artist has no payment address to check against
+    NoPaymentAddress,
+    /// The transaction isn't mined (or doesn't exist)
+    TxNotFound,
+    /// The transaction reverted
+    TxFailed,
+    /// The batch's target has no verifier contract to check events from
+    NoVerifier,
+    /// The transaction didn't settle this batch
+    NoSettlementEvent,
+    /// The transaction settled this batch for a different amount,
+    /// recipient or merkle root
+    AmountMismatch,
+    /// The transaction already verified another batch
+    TxAlreadyUsed,
+}
+
+/// Result of verifying a settlement batch
+#[derive(Debug, Serialize)]
+pub struct SettlementVerification {
+    pub batch_hash: String,
+    pub status: VerificationStatus,
+    pub verified: bool,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<u64>,
+    /// The artist's payment address
+    pub recipient: Option<String>,
+    /// Batch `total_amount` (wei)
+    pub expected_amount_wei: String,
+    /// Amount the transaction's settlement of this batch paid `recipient` (wei)
+    pub paid_amount_wei: Option<String>,
+    pub checked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Stored proof row
+#[derive(Debug, sqlx::FromRow)]
+struct ProofRow {
+    block_number: Option<i64>,
+    recipient: String,
+    expected_amount_wei: String,
+    paid_amount_wei: String,
+    checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Verify a settlement batch's payout on-chain
+pub async fn verify_settlement(
+    State(state): State<Arc<AppState>>,
+    Path(batch_hash): Path<String>,
+) -> Result<Json<SettlementVerification>, StatusCode> {
+    if !batch_hash.starts_with(ACTION_HASH_PREFIX) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let blockchain = state
+        .blockchain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let batch: SettlementBatch = holochain
+        .call_zome::<_, Option<SettlementBatch>>("plays", "get_settlement", &batch_hash)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get settlement {}: {}", batch_hash, e);
+            StatusCode::BAD_GATEWAY
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut result = SettlementVerification {
+        batch_hash: batch_hash.clone(),
+        status: VerificationStatus::Unsettled,
+        verified: false,
+        tx_hash: batch.tx_hash.clone(),
+        block_number: None,
+        recipient: None,
+        expected_amount_wei: batch.total_amount.to_string(),
+        paid_amount_wei: None,
+        checked_at: None,
+    };
+
+    let tx_hash = match batch.tx_hash.as_deref().and_then(|h| h.parse::<H256>().ok()) {
+        Some(tx_hash) => tx_hash,
+        None => return Ok(Json(result)),
+    };
+    // Stored lowercased, so one transaction can't pass as two
+    let tx_hash_hex = format!("{:?}", tx_hash);
+    result.tx_hash = Some(tx_hash_hex.clone());
+
+    // Proofs are kept per (batch, transaction): a batch whose tx_hash
+    // changed is checked again
+    if let Some(proof) = stored_proof(&state, &batch_hash, &tx_hash_hex).await? {
+        result.status = VerificationStatus::Verified;
+        result.verified = true;
+        result.block_number = proof.block_number.map(|n| n as u64);
+        result.recipient = Some(proof.recipient);
+        result.expected_amount_wei = proof.expected_amount_wei;
+        result.paid_amount_wei = Some(proof.paid_amount_wei);
+        result.checked_at = Some(proof.checked_at);
+        return Ok(Json(result));
+    }
+
+    // One transaction pays one batch
+    if tx_verified_for_other_batch(&state, &batch_hash, &tx_hash_hex).await? {
+        result.status = VerificationStatus::TxAlreadyUsed;
+        return Ok(Json(result));
+    }
+
+    let profile: Option<CatalogProfile> = holochain
+        .call_zome("catalog", "get_artist_profile", &batch.artist)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get profile for {}: {}", batch.artist, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+    let recipient = match profile.and_then(|p| p.payment_address.parse::<Address>().ok()) {
+        Some(recipient) => recipient,
+        None => {
+            result.status = VerificationStatus::NoPaymentAddress;
+            return Ok(Json(result));
+        }
+    };
+    result.recipient = Some(normalize_address(&recipient));
+
+    let verifier = match batch.settlement_target.verifier.parse::<Address>() {
+        Ok(verifier) => verifier,
+        Err(_) => {
+            result.status = VerificationStatus::NoVerifier;
+            return Ok(Json(result));
+        }
+    };
+    let payout = BatchPayout::new(&batch_hash, recipient, batch.total_amount, &batch.merkle_root)
+        .map_err(|e| {
+            tracing::warn!("Settlement {} can't be checked: {}", batch_hash, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    let receipt = blockchain
+        .get_settlement_receipt(batch.settlement_target.chain_id, verifier, tx_hash)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get receipt for {:?}: {}", tx_hash, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+    let receipt = match receipt {
+        Some(receipt) => receipt,
+        None => {
+            result.status = VerificationStatus::TxNotFound;
+            return Ok(Json(result));
+        }
+    };
+
+    // Only the verifier's settlement of this batch counts, to this
+    // recipient and under this batch's root
+    let events: Vec<_> = receipt
+        .settlements
+        .iter()
+        .filter(|settled| settled.batch_id == payout.batch_id)
+        .collect();
+    let paid = events
+        .iter()
+        .filter(|settled| {
+            settled.recipient == payout.recipient && settled.merkle_root == payout.merkle_root
+        })
+        .fold(U256::zero(), |total, settled| total + settled.amount);
+
+    result.block_number = receipt.block_number;
+    result.paid_amount_wei = Some(paid.to_string());
+    result.checked_at = Some(chrono::Utc::now());
+    result.status = if !receipt.succeeded {
+        VerificationStatus::TxFailed
+    } else if events.is_empty() {
+        VerificationStatus::NoSettlementEvent
+    } else if paid != payout.amount {
+        VerificationStatus::AmountMismatch
+    } else {
+        VerificationStatus::Verified
+    };
+    result.verified = result.status == VerificationStatus::Verified;
+
+    // The answer is still useful if it can't be stored, unless another
+    // batch claimed the transaction first
+    match store_proof(&state, &result).await {
+        Ok(()) => {}
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            result.status = VerificationStatus::TxAlreadyUsed;
+            result.verified = false;
+        }
+        Err(e) => tracing::warn!("Failed to store settlement proof for {}: {}", batch_hash, e),
+    }
+
+    Ok(Json(result))
+}
+
+/// Mirror of `plays::SettlementProof`
+#[derive(Debug, Deserialize)]
+struct ZomeSettlementProof {
+    merkle_algorithm: serde_json::Value,
+    merkle_root: Vec<u8>,
+    leaf: Vec<u8>,
+    index: u64,
+    proof: Vec<Vec<u8>>,
+}
+
+/// Mirror of `plays::PlayReceipt`
+#[derive(Debug, Deserialize)]
+struct ZomePlayReceipt {
+    play_hash: String,
+    play: serde_json::Value,
+    batch_hash: String,
+    artist: String,
+    batch_status: serde_json::Value,
+    tx_hash: Option<String>,
+    proof: ZomeSettlementProof,
+}
+
+/// A play's inclusion proof, hex-encoded as submitted on-chain
+#[derive(Debug, Serialize)]
+pub struct PlayInclusionProof {
+    /// Tree construction behind the root (`SortedPairs` or `Sequential`)
+    pub merkle_algorithm: serde_json::Value,
+    pub merkle_root: String,
+    pub leaf: String,
+    /// Position of the play in the batch (orders `Sequential` pairs)
+    pub index: u64,
+    /// Sibling hashes from the leaf up to the root
+    pub proof: Vec<String>,
+}
+
+/// Receipt proving a play was counted in a settlement
+///
+/// Rebuild `proof.merkle_root` from `leaf` and `proof`, then check it is
+/// the root the `tx_hash` settlement submitted.
+#[derive(Debug, Serialize)]
+pub struct PlayReceipt {
+    pub play_hash: String,
+    /// The play's settled `PlayRecord`
+    pub play: serde_json::Value,
+    pub batch_hash: String,
+    pub artist: String,
+    pub batch_status: serde_json::Value,
+    /// Settlement transaction, once submitted
+    pub tx_hash: Option<String>,
+    pub proof: PlayInclusionProof,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Receipt tying one play to its settlement batch and on-chain payout
+///
+/// 404 until the play is settled by a batch.
+pub async fn get_play_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(play_hash): Path<String>,
+) -> Result<Json<PlayReceipt>, StatusCode> {
+    if !play_hash.starts_with(ACTION_HASH_PREFIX) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let receipt: ZomePlayReceipt = holochain
+        .call_zome::<_, Option<ZomePlayReceipt>>("plays", "get_play_receipt", &play_hash)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get receipt for play {}: {}", play_hash, e);
+            StatusCode::BAD_GATEWAY
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let proof = receipt.proof;
+    Ok(Json(PlayReceipt {
+        play_hash: receipt.play_hash,
+        play: receipt.play,
+        batch_hash: receipt.batch_hash,
+        artist: receipt.artist,
+        batch_status: receipt.batch_status,
+        tx_hash: receipt.tx_hash,
+        proof: PlayInclusionProof {
+            merkle_algorithm: proof.merkle_algorithm,
+            merkle_root: to_hex(&proof.merkle_root),
+            leaf: to_hex(&proof.leaf),
+            index: proof.index,
+            proof: proof.proof.iter().map(|sibling| to_hex(sibling)).collect(),
+        },
+    }))
+}
+
+/// A previously verified proof of a batch's transaction, if any
+async fn stored_proof(
+    state: &AppState,
+    batch_hash: &str,
+    tx_hash: &str,
+) -> Result<Option<ProofRow>, StatusCode> {
+    sqlx::query_as::<_, ProofRow>(
+        r#"
+        SELECT block_number, recipient, expected_amount_wei, paid_amount_wei, checked_at
+        FROM settlement_proofs
+        WHERE batch_hash = $1 AND tx_hash = $2 AND verified
+        "#,
+    )
+    .bind(batch_hash)
+    .bind(tx_hash)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load settlement proof: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Whether a transaction already verified another batch's payout
+async fn tx_verified_for_other_batch(
+    state: &AppState,
+    batch_hash: &str,
+    tx_hash: &str,
+) -> Result<bool, StatusCode> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM settlement_proofs
+            WHERE tx_hash = $1 AND batch_hash <> $2 AND verified
+        )
+        "#,
+    )
+    .bind(tx_hash)
+    .bind(batch_hash)
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check settlement transaction {}: {}", tx_hash, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Record the latest on-chain check of a batch's transaction
+///
+/// Fails with a unique violation if the transaction already verified
+/// another batch.
+async fn store_proof(state: &AppState, result: &SettlementVerification) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO settlement_proofs (
+            batch_hash, tx_hash, block_number, recipient, expected_amount_wei,
+            paid_amount_wei, verified, checked_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        ON CONFLICT (batch_hash, tx_hash) DO UPDATE SET
+            block_number = EXCLUDED.block_number,
+            recipient = EXCLUDED.recipient,
+            expected_amount_wei = EXCLUDED.expected_amount_wei,
+            paid_amount_wei = EXCLUDED.paid_amount_wei,
+            verified = EXCLUDED.verified,
+            checked_at = NOW()
+        "#,
+    )
+    .bind(&result.batch_hash)
+    .bind(&result.tx_hash)
+    .bind(result.block_number.map(|n| n as i64))
+    .bind(&result.recipient)
+    .bind(&result.expected_amount_wei)
+    .bind(&result.paid_amount_wei)
+    .bind(result.verified)
+    .execute(&state.db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Mirror of `plays::UpdateSettlementStatusInput`
+#[derive(Debug, Serialize)]
+struct SettlementStatusUpdate<'a> {
+    batch_hash: &'a str,
+    status: &'a str,
+    tx_hash: Option<String>,
+}
+
+/// A batch payout sent to its target chain
+#[derive(Debug, Serialize)]
+pub struct SubmittedSettlement {
+    pub batch_hash: String,
+    pub chain: SettlementChain,
+    pub chain_id: u64,
+    pub verifier: String,
+    pub signature_scheme: SignatureScheme,
+    pub tx_hash: String,
+}
+
+/// Submit a pending batch's payout to the chain it targets
+///
+/// The payout is signed in the target's format and sent with that chain's
+/// settlement RPC, then the batch is marked `Submitted` with the
+/// transaction hash. The batch is claimed in `settlement_submissions`
+/// before anything is signed, and the signed transaction is recorded
+/// before it is broadcast, so it is never paid twice: a send that fails
+/// keeps the claim, and a repeat request resends the same transaction
+/// (or, once it was sent, only retries marking the batch). The claim is
+/// released only when nothing was broadcast, or the transaction was
+/// dropped: the settler's nonce was spent by another one. `409` unless the
+/// batch is pending (or while another submission of it is in flight),
+/// `422` if the artist has no payment address or the target has no
+/// verifier.
+pub async fn submit_settlement(
+    State(state): State<Arc<AppState>>,
+    Path(batch_hash): Path<String>,
+) -> Result<Json<SubmittedSettlement>, StatusCode> {
+    if !batch_hash.starts_with(ACTION_HASH_PREFIX) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let blockchain = state
+        .blockchain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let batch: SettlementBatch = holochain
+        .call_zome::<_, Option<SettlementBatch>>("plays", "get_settlement", &batch_hash)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get settlement {}: {}", batch_hash, e);
+            StatusCode::BAD_GATEWAY
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if batch.status != "Pending" {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let profile: Option<CatalogProfile> = holochain
+        .call_zome("catalog", "get_artist_profile", &batch.artist)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get profile for {}: {}", batch.artist, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+    let recipient = profile
+        .and_then(|p| p.payment_address.parse::<Address>().ok())
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let target = batch.settlement_target;
+    let payload = BatchPayout::new(&batch_hash, recipient, batch.total_amount, &batch.merkle_root)
+        .and_then(|payout| SettlementPayload::new(&target, payout))
+        .map_err(|e| {
+            tracing::warn!("Cannot settle {}: {}", batch_hash, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    let tx_hash = match claim_submission(&state, &batch_hash, target.chain_id).await? {
+        SubmissionClaim::Claimed => {
+            let signed = match blockchain.sign_settlement(&payload).await {
+                Ok(signed) => signed,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to sign settlement {} for chain {}: {}",
+                        batch_hash,
+                        target.chain_id,
+                        e
+                    );
+                    release_submission(&state, &batch_hash, "claimed", None).await;
+                    return Err(StatusCode::BAD_GATEWAY);
+                }
+            };
+            if let Err(status) = record_signed(&state, &batch_hash, &signed).await {
+                release_submission(&state, &batch_hash, "claimed", None).await;
+                return Err(status);
+            }
+            // From here the payout may be out, so the claim is kept
+            let tx_hash = format!("{:?}", signed.tx_hash);
+            broadcast(&state, blockchain, &batch_hash, target.chain_id, &tx_hash, signed.raw)
+                .await?;
+            tx_hash
+        }
+        SubmissionClaim::Signed(signed) => {
+            resume_signed(&state, blockchain, &batch_hash, &payload, signed).await?
+        }
+        SubmissionClaim::Sent(tx_hash) => {
+            tracing::info!("Settlement {} already sent as {}, marking it", batch_hash, tx_hash);
+            tx_hash
+        }
+        SubmissionClaim::InFlight => return Err(StatusCode::CONFLICT),
+    };
+
+    // The payout is on its way; a retry resumes here from the recorded
+    // transaction instead of sending it again
+    let update = SettlementStatusUpdate {
+        batch_hash: &batch_hash,
+        status: "Submitted",
+        tx_hash: Some(tx_hash.clone()),
+    };
+    let updated: anyhow::Result<serde_json::Value> = holochain
+        .call_zome("plays", "update_settlement_status", &update)
+        .await;
+    if let Err(e) = updated {
+        tracing::error!(
+            "Settlement {} sent as {} but not marked submitted: {}",
+            batch_hash,
+            tx_hash,
+            e
+        );
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+    record_submission(&state, &batch_hash, "marked", None).await?;
+
+    Ok(Json(SubmittedSettlement {
+        batch_hash,
+        chain: target.chain,
+        chain_id: target.chain_id,
+        verifier: target.verifier,
+        signature_scheme: payload.scheme,
+        tx_hash,
+    }))
+}
+
+/// Broadcast a recorded settlement transaction and record it sent
+///
+/// A failed send keeps the claim: the transaction may have reached the
+/// chain, and a retry resends the same one.
+async fn broadcast(
+    state: &AppState,
+    blockchain: &BlockchainService,
+    batch_hash: &str,
+    chain_id: u64,
+    tx_hash: &str,
+    raw: Bytes,
+) -> Result<(), StatusCode> {
+    if let Err(e) = blockchain.broadcast_settlement(chain_id, raw).await {
+        tracing::error!(
+            "Failed to send settlement {} to chain {} as {}, retry resends it: {}",
+            batch_hash,
+            chain_id,
+            tx_hash,
+            e
+        );
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+    record_submission(state, batch_hash, "sent", None).await
+}
+
+/// Pick up a signed transaction whose broadcast may not have happened
+///
+/// Mined: it was sent. Not mined but its nonce spent: it was dropped and
+/// never will be, so the claim is released (`409`) and the next request
+/// signs afresh. Otherwise it is resent as is. The nonce is read before
+/// the receipt, so a transaction mined in between isn't taken for dropped.
+async fn resume_signed(
+    state: &AppState,
+    blockchain: &BlockchainService,
+    batch_hash: &str,
+    payload: &SettlementPayload,
+    signed: StoredTransaction,
+) -> Result<String, StatusCode> {
+    let chain_error = |e: anyhow::Error| {
+        tracing::error!("Failed to check settlement {} on-chain: {}", batch_hash, e);
+        StatusCode::BAD_GATEWAY
+    };
+    let tx_hash: H256 = signed.tx_hash.parse().map_err(|_| {
+        tracing::error!("Settlement {} has a malformed tx hash {}", batch_hash, signed.tx_hash);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let nonce_spent = blockchain
+        .settlement_nonce_spent(payload.chain_id, signed.nonce)
+        .await
+        .map_err(chain_error)?;
+    let mined = blockchain
+        .get_settlement_receipt(payload.chain_id, payload.verifier, tx_hash)
+        .await
+        .map_err(chain_error)?
+        .is_some();
+
+    if mined {
+        record_submission(state, batch_hash, "sent", None).await?;
+    } else if nonce_spent {
+        tracing::warn!(
+            "Settlement {} transaction {} was dropped, releasing the claim",
+            batch_hash,
+            signed.tx_hash
+        );
+        release_submission(state, batch_hash, "signed", Some(&signed.tx_hash)).await;
+        return Err(StatusCode::CONFLICT);
+    } else {
+        let raw: Bytes = signed.raw_tx.parse().map_err(|_| {
+            tracing::error!("Settlement {} has a malformed raw transaction", batch_hash);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        broadcast(state, blockchain, batch_hash, payload.chain_id, &signed.tx_hash, raw).await?;
+    }
+    Ok(signed.tx_hash)
+}
+
+/// Where an earlier submission of a batch left off
+enum SubmissionClaim {
+    /// Nothing was signed before; this request now holds the batch
+    Claimed,
+    /// This transaction was signed and recorded but may not have been
+    /// broadcast
+    Signed(StoredTransaction),
+    /// The payout was sent as this transaction
+    Sent(String),
+    /// Another request holds the batch and hasn't signed it yet
+    InFlight,
+}
+
+/// A signed settlement transaction as recorded in `settlement_submissions`
+struct StoredTransaction {
+    tx_hash: String,
+    raw_tx: String,
+    nonce: u64,
+}
+
+/// Claim a batch for submission, or find where an earlier one stopped
+async fn claim_submission(
+    state: &AppState,
+    batch_hash: &str,
+    chain_id: u64,
+) -> Result<SubmissionClaim, StatusCode> {
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("Failed to claim settlement {}: {}", batch_hash, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO settlement_submissions (batch_hash, chain_id)
+        VALUES ($1, $2)
+        ON CONFLICT (batch_hash) DO NOTHING
+        "#,
+    )
+    .bind(batch_hash)
+    .bind(chain_id as i64)
+    .execute(&state.db_pool)
+    .await
+    .map_err(db_error)?;
+    if inserted.rows_affected() == 1 {
+        return Ok(SubmissionClaim::Claimed);
+    }
+
+    let (submission_state, tx_hash, raw_tx, nonce) =
+        sqlx::query_as::<_, (String, Option<String>, Option<String>, Option<i64>)>(
+            r#"
+            SELECT state, tx_hash, raw_tx, nonce
+            FROM settlement_submissions
+            WHERE batch_hash = $1
+            "#,
+        )
+        .bind(batch_hash)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(db_error)?;
+
+    let nonce = nonce.and_then(|nonce| u64::try_from(nonce).ok());
+    Ok(match (submission_state.as_str(), tx_hash, raw_tx, nonce) {
+        ("sent" | "marked", Some(tx_hash), _, _) => SubmissionClaim::Sent(tx_hash),
+        ("signed", Some(tx_hash), Some(raw_tx), Some(nonce)) => {
+            SubmissionClaim::Signed(StoredTransaction { tx_hash, raw_tx, nonce })
+        }
+        _ => SubmissionClaim::InFlight,
+    })
+}
+
+/// Record a claimed batch's signed transaction, before it is broadcast
+async fn record_signed(
+    state: &AppState,
+    batch_hash: &str,
+    signed: &SignedSettlement,
+) -> Result<(), StatusCode> {
+    let nonce = i64::try_from(signed.nonce).map_err(|_| {
+        tracing::error!("Settlement {} nonce {} is out of range", batch_hash, signed.nonce);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    sqlx::query(
+        r#"
+        UPDATE settlement_submissions
+        SET state = 'signed', tx_hash = $2, raw_tx = $3, nonce = $4, updated_at = NOW()
+        WHERE batch_hash = $1 AND state = 'claimed'
+        "#,
+    )
+    .bind(batch_hash)
+    .bind(format!("{:?}", signed.tx_hash))
+    .bind(to_hex(&signed.raw))
+    .bind(nonce)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record signed settlement {}: {}", batch_hash, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(())
+}
+
+/// Move a claimed batch along, recording its transaction once sent
+async fn record_submission(
+    state: &AppState,
+    batch_hash: &str,
+    submission_state: &str,
+    tx_hash: Option<&str>,
+) -> Result<(), StatusCode> {
+    sqlx::query(
+        r#"
+        UPDATE settlement_submissions
+        SET state = $2, tx_hash = COALESCE($3, tx_hash), updated_at = NOW()
+        WHERE batch_hash = $1
+        "#,
+    )
+    .bind(batch_hash)
+    .bind(submission_state)
+    .bind(tx_hash)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(
+            "Failed to record settlement {} as {}: {}",
+            batch_hash,
+            submission_state,
+            e
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(())
+}
+
+/// Drop a claim whose payout was never broadcast (or was dropped), so the
+/// batch can be retried; only while still in `submission_state` with
+/// `tx_hash`
+async fn release_submission(
+    state: &AppState,
+    batch_hash: &str,
+    submission_state: &str,
+    tx_hash: Option<&str>,
+) {
+    let released = sqlx::query(
+        r#"
+        DELETE FROM settlement_submissions
+        WHERE batch_hash = $1 AND state = $2 AND tx_hash IS NOT DISTINCT FROM $3
+        "#,
+    )
+    .bind(batch_hash)
+    .bind(submission_state)
+    .bind(tx_hash)
+    .execute(&state.db_pool)
+    .await;
+    if let Err(e) = released {
+        tracing::error!("Failed to release settlement claim {}: {}", batch_hash, e);
+    }
+}
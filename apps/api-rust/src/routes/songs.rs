@@ -4,17 +4,42 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::models::{is_valid_cid, is_valid_eth_address, PaymentModel, SongStatus, ValidationErrors};
+use crate::routes::auth::{authenticate, AuthUser};
+use crate::routes::images::find_thumbnails;
+use crate::routes::strategies;
+use crate::services::catalog_sync;
+use crate::services::fx;
+use crate::services::holochain::SongStats;
+use crate::services::pinning;
 use crate::AppState;
 
+/// Maximum song title length (characters)
+const MAX_TITLE_LEN: usize = 200;
+
+/// Maximum genres per song
+const MAX_GENRES: usize = 10;
+
+/// Maximum genre name length (characters)
+const MAX_GENRE_LEN: usize = 50;
+
+/// Most songs one batch request may fetch
+const MAX_BATCH_SONGS: usize = 100;
+
+/// Play-count drift between DB and DHT above which reconcile logs a warning
+const RECONCILE_DRIFT_THRESHOLD: i64 = 5;
+
 /// Song model
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Song {
     pub id: Uuid,
     pub song_hash: String,
@@ -25,18 +50,159 @@ pub struct Song {
     pub payment_model: String,
     pub plays: i64,
     pub earnings: f64,
+    /// Lowercased genre tags
+    pub genres: Vec<String>,
+    /// Full-resolution cover art
+    pub cover_cid: Option<String>,
+    /// 128px WebP cover thumbnail
+    pub cover_thumb_128_cid: Option<String>,
+    /// 512px WebP cover thumbnail
+    pub cover_thumb_512_cid: Option<String>,
+    /// Free preview clip, streamed instead of gated songs to listeners without access
+    pub preview_cid: Option<String>,
+    /// Moderation status (active, hidden, removed)
+    pub status: String,
+    /// Content not pinned yet because IPFS was unreachable; retried in the background
+    pub pinning_pending: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl Song {
+    /// Whether a (possibly anonymous) viewer may see this song
+    ///
+    /// Hidden songs are visible to their artist; removed songs only to admins.
+    pub fn visible_to(&self, viewer: Option<&AuthUser>) -> bool {
+        match SongStatus::parse(&self.status) {
+            Some(SongStatus::Active) => true,
+            Some(SongStatus::Hidden) => {
+                viewer.map_or(false, |v| v.is_admin || v.is(&self.artist_address))
+            }
+            _ => viewer.map_or(false, |v| v.is_admin),
+        }
+    }
+}
+
 /// Create song request
 #[derive(Debug, Deserialize)]
 pub struct CreateSongRequest {
     pub title: String,
-    pub artist_address: String,
+    /// Optional; must match the authenticated address if given
+    #[serde(default)]
+    pub artist_address: Option<String>,
     pub ipfs_hash: String,
     pub strategy_id: String,
     pub payment_model: String,
     pub splits: Vec<Split>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    /// Cover art CID (upload via `/api/upload` to get thumbnails)
+    #[serde(default)]
+    pub cover_cid: Option<String>,
+    /// Preview clip CID (upload with `?preview=true` to generate one)
+    #[serde(default)]
+    pub preview_cid: Option<String>,
+}
+
+impl CreateSongRequest {
+    /// Trimmed, lowercased, de-duplicated genres
+    pub fn normalized_genres(&self) -> Vec<String> {
+        let mut genres: Vec<String> = Vec::new();
+        for genre in &self.genres {
+            let genre = genre.trim().to_lowercase();
+            if !genre.is_empty() && !genres.contains(&genre) {
+                genres.push(genre);
+            }
+        }
+        genres
+    }
+
+    /// Content-derived song hash: SHA-256 over the audio CID and canonical
+    /// metadata, so re-submitting the same song yields the same hash
+    pub fn content_hash(&self, artist_address: &str) -> String {
+        // json! objects serialize with sorted keys
+        let canonical = serde_json::json!({
+            "artist_address": artist_address.to_lowercase(),
+            "ipfs_hash": self.ipfs_hash,
+            "title": self.title.trim(),
+            "strategy_id": self.strategy_id,
+            "payment_model": self.payment_model,
+            "genres": self.normalized_genres(),
+            "cover_cid": self.cover_cid,
+        });
+        format!("0x{}", hex::encode(sha2::Sha256::digest(canonical.to_string().as_bytes())))
+    }
+
+    /// Validate all fields, collecting every failure keyed by field name
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let title = self.title.trim();
+        if title.is_empty() {
+            errors.add("title", "Title cannot be empty");
+        } else if title.chars().count() > MAX_TITLE_LEN {
+            errors.add("title", &format!("Title cannot exceed {} characters", MAX_TITLE_LEN));
+        }
+
+        if let Some(artist_address) = &self.artist_address {
+            if !is_valid_eth_address(artist_address) {
+                errors.add("artist_address", "Must be a 0x-prefixed 42-character address");
+            }
+        }
+
+        if !is_valid_cid(&self.ipfs_hash) {
+            errors.add("ipfs_hash", "Must be a valid IPFS CID");
+        }
+
+        if let Some(cover_cid) = &self.cover_cid {
+            if !is_valid_cid(cover_cid) {
+                errors.add("cover_cid", "Must be a valid IPFS CID");
+            }
+        }
+
+        if let Some(preview_cid) = &self.preview_cid {
+            if !is_valid_cid(preview_cid) {
+                errors.add("preview_cid", "Must be a valid IPFS CID");
+            }
+        }
+
+        if PaymentModel::parse(&self.payment_model).is_none() {
+            errors.add("payment_model", "Unknown payment model");
+        }
+
+        if self.genres.len() > MAX_GENRES {
+            errors.add("genres", &format!("At most {} genres allowed", MAX_GENRES));
+        }
+        for (i, genre) in self.genres.iter().enumerate() {
+            let genre = genre.trim();
+            if genre.is_empty() {
+                errors.add(&format!("genres[{}]", i), "Genre cannot be empty");
+            } else if genre.chars().count() > MAX_GENRE_LEN {
+                errors.add(
+                    &format!("genres[{}]", i),
+                    &format!("Genre cannot exceed {} characters", MAX_GENRE_LEN),
+                );
+            }
+        }
+
+        for (i, split) in self.splits.iter().enumerate() {
+            if !is_valid_eth_address(&split.recipient) {
+                errors.add(
+                    &format!("splits[{}].recipient", i),
+                    "Must be a 0x-prefixed 42-character address",
+                );
+            }
+        }
+
+        let total_bps: u64 = self.splits.iter().map(|s| s.basis_points as u64).sum();
+        if total_bps != 10000 {
+            errors.add(
+                "splits",
+                &format!("Split basis points must sum to 10000 (got {})", total_bps),
+            );
+        }
+
+        errors.into_result()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -59,7 +225,9 @@ pub struct ListSongsQuery {
 /// Record play request
 #[derive(Debug, Deserialize)]
 pub struct RecordPlayRequest {
-    pub listener_address: String,
+    /// Optional; must match the authenticated address if given
+    #[serde(default)]
+    pub listener_address: Option<String>,
     pub amount: f64,
     pub payment_type: String,
     pub signature: String,
@@ -67,24 +235,34 @@ pub struct RecordPlayRequest {
 }
 
 /// List songs with optional filters
+///
+/// Only active songs are public; a signed-in artist also sees their hidden ones.
 pub async fn list_songs(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Query(params): Query<ListSongsQuery>,
 ) -> Result<Json<Vec<Song>>, StatusCode> {
     let limit = params.limit.unwrap_or(50).min(100);
     let offset = params.offset.unwrap_or(0);
+    let viewer = authenticate(&state.auth, &headers).map(|u| u.address);
 
     let songs = sqlx::query_as::<_, Song>(
         r#"
         SELECT id, song_hash, title, artist_address, ipfs_hash,
-               strategy_id, payment_model, plays, earnings::float8 as earnings, created_at
+               strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+               cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
         FROM songs
+        WHERE (status = 'active'
+               OR (status = 'hidden' AND LOWER(artist_address) = $3))
+          AND ($4::text IS NULL OR genres @> ARRAY[$4::text])
         ORDER BY created_at DESC
         LIMIT $1 OFFSET $2
         "#,
     )
     .bind(limit)
     .bind(offset)
+    .bind(viewer)
+    .bind(params.genre.map(|g| g.trim().to_lowercase()))
     .fetch_all(&state.db_pool)
     .await
     .map_err(|e| {
@@ -95,15 +273,80 @@ pub async fn list_songs(
     Ok(Json(songs))
 }
 
+/// Query params for browsing songs by category
+#[derive(Debug, Deserialize)]
+pub struct CategorySongsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// List songs using any strategy in an economic category (e.g.
+/// `community` for free listening, `direct-payment` for pay-per-stream)
+///
+/// Unknown categories get a 404 listing the valid ones. Visibility matches
+/// `list_songs`.
+pub async fn get_songs_by_category(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(category): Path<String>,
+    Query(params): Query<CategorySongsQuery>,
+) -> Result<Json<Vec<Song>>, Response> {
+    let strategy_ids = match strategies::category_strategy_ids(&category) {
+        Some(ids) => ids,
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "error": format!("Unknown category '{}'", category),
+                    "categories": strategies::categories(),
+                })),
+            )
+                .into_response())
+        }
+    };
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let offset = params.offset.unwrap_or(0).max(0);
+    let viewer = authenticate(&state.auth, &headers).map(|u| u.address);
+
+    let songs = sqlx::query_as::<_, Song>(
+        r#"
+        SELECT id, song_hash, title, artist_address, ipfs_hash,
+               strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+               cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
+        FROM songs
+        WHERE (status = 'active'
+               OR (status = 'hidden' AND LOWER(artist_address) = $3))
+          AND strategy_id = ANY($4)
+        ORDER BY created_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .bind(viewer)
+    .bind(&strategy_ids)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list songs by category: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    Ok(Json(songs))
+}
+
 /// Get a single song by ID
 pub async fn get_song(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Song>, StatusCode> {
     let song = sqlx::query_as::<_, Song>(
         r#"
         SELECT id, song_hash, title, artist_address, ipfs_hash,
-               strategy_id, payment_model, plays, earnings::float8 as earnings, created_at
+               strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+               cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
         FROM songs
         WHERE id = $1
         "#,
@@ -117,39 +360,185 @@ pub async fn get_song(
     })?
     .ok_or(StatusCode::NOT_FOUND)?;
 
+    // Moderated songs look missing to anyone not allowed to see them
+    if !song.visible_to(authenticate(&state.auth, &headers).as_ref()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
     Ok(Json(song))
 }
 
+/// Batch song request
+#[derive(Debug, Deserialize)]
+pub struct BatchSongsRequest {
+    pub ids: Vec<Uuid>,
+}
+
+/// Songs found for a batch request
+#[derive(Debug, Serialize)]
+pub struct BatchSongsResponse {
+    /// In request order; an id requested twice appears twice
+    pub songs: Vec<Song>,
+    /// Requested ids that don't exist or aren't visible to the caller
+    pub not_found: Vec<Uuid>,
+}
+
+/// Get many songs by ID in one query, for hydrating playlists and queues
+///
+/// Unknown ids are listed in `not_found` instead of failing the request.
+/// Visibility matches `get_song`.
+pub async fn get_songs_batch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<BatchSongsRequest>,
+) -> Result<Json<BatchSongsResponse>, Response> {
+    if req.ids.len() > MAX_BATCH_SONGS {
+        let mut errors = ValidationErrors::new();
+        errors.add("ids", &format!("At most {} songs per batch", MAX_BATCH_SONGS));
+        return Err(errors.into_response());
+    }
+
+    let mut found = sqlx::query_as::<_, Song>(
+        r#"
+        SELECT id, song_hash, title, artist_address, ipfs_hash,
+               strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+               cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
+        FROM songs
+        WHERE id = ANY($1)
+        "#,
+    )
+    .bind(&req.ids)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get song batch: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let viewer = authenticate(&state.auth, &headers);
+    found.retain(|song| song.visible_to(viewer.as_ref()));
+    let found: HashMap<Uuid, Song> = found.into_iter().map(|song| (song.id, song)).collect();
+
+    let mut songs = Vec::with_capacity(req.ids.len());
+    let mut not_found = Vec::new();
+    for id in &req.ids {
+        match found.get(id) {
+            Some(song) => songs.push(song.clone()),
+            None => not_found.push(*id),
+        }
+    }
+
+    Ok(Json(BatchSongsResponse { songs, not_found }))
+}
+
 /// Create a new song
+///
+/// Idempotent: identical content returns the existing song (200) instead of
+/// a duplicate entry.
 pub async fn create_song(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
     Json(req): Json<CreateSongRequest>,
-) -> Result<Json<Song>, StatusCode> {
+) -> Result<Json<Song>, Response> {
+    req.validate().map_err(IntoResponse::into_response)?;
+
+    if let Some(artist_address) = &req.artist_address {
+        if !user.is(artist_address) {
+            tracing::warn!("{} tried to create a song as {}", user.address, artist_address);
+            return Err(StatusCode::FORBIDDEN.into_response());
+        }
+    }
+
     let id = Uuid::new_v4();
-    let song_hash = format!("0x{}", hex::encode(sha2::Sha256::digest(id.as_bytes())));
+    let song_hash = req.content_hash(&user.address);
 
-    let song = sqlx::query_as::<_, Song>(
+    // Thumbnails generated at upload time; missing ones are served lazily
+    let mut cover_thumbs = Vec::new();
+    if let Some(cover_cid) = &req.cover_cid {
+        cover_thumbs = find_thumbnails(&state, cover_cid).await.map_err(|e| {
+            tracing::error!("Failed to look up cover thumbnails: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+    }
+    let thumb = |size: u32| {
+        cover_thumbs
+            .iter()
+            .find(|(s, _)| *s == size)
+            .map(|(_, cid)| cid.clone())
+    };
+
+    let inserted = sqlx::query_as::<_, Song>(
         r#"
-        INSERT INTO songs (id, song_hash, title, artist_address, ipfs_hash, strategy_id, payment_model, plays, earnings)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, 0, 0)
+        INSERT INTO songs (id, song_hash, title, artist_address, ipfs_hash, strategy_id, payment_model, genres,
+                           cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, plays, earnings)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, 0, 0)
+        ON CONFLICT (song_hash) DO NOTHING
         RETURNING id, song_hash, title, artist_address, ipfs_hash,
-                  strategy_id, payment_model, plays, earnings::float8 as earnings, created_at
+                  strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+                  cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
         "#,
     )
     .bind(id)
     .bind(&song_hash)
     .bind(&req.title)
-    .bind(&req.artist_address)
+    .bind(&user.address)
     .bind(&req.ipfs_hash)
     .bind(&req.strategy_id)
     .bind(&req.payment_model)
-    .fetch_one(&state.db_pool)
+    .bind(req.normalized_genres())
+    .bind(&req.cover_cid)
+    .bind(thumb(128))
+    .bind(thumb(512))
+    .bind(&req.preview_cid)
+    .fetch_optional(&state.db_pool)
     .await
     .map_err(|e| {
         tracing::error!("Failed to create song: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
     })?;
 
+    let mut song = match inserted {
+        Some(song) => song,
+        None => {
+            // Already registered; its pins were handled the first time
+            let existing = sqlx::query_as::<_, Song>(
+                r#"
+                SELECT id, song_hash, title, artist_address, ipfs_hash,
+                       strategy_id, payment_model, plays, earnings::float8 as earnings, genres,
+                       cover_cid, cover_thumb_128_cid, cover_thumb_512_cid, preview_cid, status, pinning_pending, created_at
+                FROM songs
+                WHERE song_hash = $1
+                "#,
+            )
+            .bind(&song_hash)
+            .fetch_one(&state.db_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load existing song: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?;
+            tracing::info!("Song already registered: {} ({})", existing.title, existing.id);
+            return Ok(Json(existing));
+        }
+    };
+
+    // An IPFS outage queues the pins instead of failing the song
+    let mut cids = vec![req.ipfs_hash.as_str()];
+    cids.extend(req.cover_cid.as_deref());
+    cids.extend(req.preview_cid.as_deref());
+    match pinning::pin_or_queue(&state.db_pool, &state.ipfs_client, &state.pinning, id, &cids).await {
+        Ok(pending) => song.pinning_pending = pending,
+        Err(e) => tracing::error!("Failed to queue pins for song {}: {}", id, e),
+    }
+
+    // Artists who published to the catalog first are linked right away;
+    // the rest are picked up by the catalog backfill
+    if let Some(holochain) = &state.holochain {
+        if let Err(e) = catalog_sync::link_song(&state.db_pool, holochain, &song_hash).await {
+            tracing::warn!("Failed to link song {} to the catalog: {}", id, e);
+        }
+    }
+
     tracing::info!("Created song: {} by {}", song.title, song.artist_address);
     Ok(Json(song))
 }
@@ -157,61 +546,433 @@ pub async fn create_song(
 /// Record a play (streaming event)
 pub async fn record_play(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
     Json(req): Json<RecordPlayRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
+    if let Some(listener_address) = &req.listener_address {
+        if !user.is(listener_address) {
+            tracing::warn!("{} tried to record a play as {}", user.address, listener_address);
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
     // TODO: Verify signature
     // TODO: Process payment via smart contract
     // TODO: For now, just record the play in DB
 
-    // Update play count and earnings
-    let result = sqlx::query(
+    // Counters, artist_stats (via trigger) and the play row commit together
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin play transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Update play count and earnings (held back while a flagged song is paused)
+    let earnings_paused: bool = sqlx::query_scalar(
         r#"
         UPDATE songs
-        SET plays = plays + 1, earnings = earnings + $2
-        WHERE id = $1
+        SET plays = plays + 1,
+            earnings = earnings + CASE WHEN earnings_paused THEN 0 ELSE $2 END
+        WHERE id = $1 AND status = 'active'
+        RETURNING earnings_paused
         "#,
     )
     .bind(id)
     .bind(req.amount)
-    .execute(&state.db_pool)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!("Failed to record play: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if result.rows_affected() == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
 
     // Insert play record
     sqlx::query(
         r#"
-        INSERT INTO plays (song_id, listener_address, amount, payment_type, timestamp)
-        VALUES ($1, $2, $3, $4, NOW())
+        INSERT INTO plays (song_id, listener_address, amount, payment_type, timestamp,
+                           excluded_reason, excluded_at)
+        VALUES ($1, $2, $3, $4, NOW(),
+                CASE WHEN $5 THEN 'earnings_paused' END, CASE WHEN $5 THEN NOW() END)
         "#,
     )
     .bind(id)
-    .bind(&req.listener_address)
+    .bind(&user.address)
     .bind(req.amount)
     .bind(&req.payment_type)
-    .execute(&state.db_pool)
+    .bind(earnings_paused)
+    .execute(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!("Failed to insert play record: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    tracing::info!("Recorded play for song {} by {}", id, req.listener_address);
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit play: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tracing::info!("Recorded play for song {} by {}", id, user.address);
 
     Ok(Json(serde_json::json!({
         "success": true,
         "song_id": id,
-        "listener": req.listener_address,
+        "listener": user.address,
         "amount": req.amount,
         "message": "Play recorded. Artist paid instantly!"
     })))
 }
 
+/// DB vs DHT play reconciliation report
+#[derive(Debug, Serialize)]
+pub struct ReconcileResponse {
+    pub song_id: Uuid,
+    pub dht_action_hash: String,
+    pub db_plays: i64,
+    pub db_earnings: f64,
+    pub dht_plays: u64,
+    pub dht_earnings_wei: u64,
+    pub dht_unique_listeners: u64,
+    /// DB plays minus DHT plays (positive = DB ahead)
+    pub plays_delta: i64,
+    pub in_sync: bool,
+}
+
+/// Compare a song's DB counters against the plays zome (diagnostic)
+pub async fn reconcile_song(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReconcileResponse>, StatusCode> {
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let (db_plays, db_earnings, song_hash, dht_action_hash) =
+        sqlx::query_as::<_, (i64, f64, String, Option<String>)>(
+            r#"
+            SELECT plays, earnings::float8, song_hash, dht_action_hash
+            FROM songs
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get song for reconcile: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Songs the artist hasn't published to the catalog have no DHT counterpart yet
+    let dht_action_hash = match dht_action_hash {
+        Some(dht_action_hash) => dht_action_hash,
+        None => catalog_sync::link_song(&state.db_pool, holochain, &song_hash)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to link song {} to the catalog: {}", id, e);
+                StatusCode::BAD_GATEWAY
+            })?
+            .ok_or(StatusCode::CONFLICT)?,
+    };
+
+    let stats: SongStats = holochain
+        .call_zome("plays", "get_song_stats", &dht_action_hash)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch song stats from DHT: {}", e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    let plays_delta = db_plays - stats.total_plays as i64;
+    if plays_delta.abs() > RECONCILE_DRIFT_THRESHOLD {
+        tracing::warn!(
+            "Play count drift for song {}: db={} dht={} (delta {})",
+            id,
+            db_plays,
+            stats.total_plays,
+            plays_delta
+        );
+    }
+
+    Ok(Json(ReconcileResponse {
+        song_id: id,
+        dht_action_hash,
+        db_plays,
+        db_earnings,
+        dht_plays: stats.total_plays,
+        dht_earnings_wei: stats.total_earnings,
+        dht_unique_listeners: stats.unique_listeners,
+        plays_delta,
+        in_sync: plays_delta == 0,
+    }))
+}
+
+/// Largest attestation page the plays zome returns
+const MAX_ATTESTATIONS_PAGE: usize = 200;
+
+/// Query params for a song's attestations
+#[derive(Debug, Deserialize)]
+pub struct AttestationsQuery {
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Mirror of `plays::PlaysCursor`
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkCursor {
+    created_at: i64,
+    link_hash: String,
+}
+
+impl LinkCursor {
+    /// Parse the `{created_at}:{link_hash}` form used in URLs
+    fn parse(cursor: &str) -> Option<Self> {
+        let (created_at, link_hash) = cursor.split_once(':')?;
+        Some(Self {
+            created_at: created_at.parse().ok()?,
+            link_hash: link_hash.to_string(),
+        })
+    }
+
+    fn to_param(&self) -> String {
+        format!("{}:{}", self.created_at, self.link_hash)
+    }
+}
+
+/// Mirror of `plays::GetSongAttestationsInput`
+#[derive(Debug, Serialize)]
+struct AttestationsInput {
+    song_hash: String,
+    cursor: Option<LinkCursor>,
+    limit: Option<usize>,
+}
+
+/// A play attestation checked by the plays zome
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditedAttestation {
+    pub attestation_hash: String,
+    /// The `PlayAttestation` entry
+    pub attestation: serde_json::Value,
+    /// Listener agent key the signature was checked against
+    pub listener: String,
+    pub signature_valid: bool,
+    /// Song, artist and amount match the play as recorded
+    pub matches_play: bool,
+    /// The referenced play's latest `PlayRecord`, if found
+    pub play: Option<serde_json::Value>,
+}
+
+/// Mirror of `plays::SongAttestationsPage`
+#[derive(Debug, Deserialize)]
+struct AttestationsPage {
+    attestations: Vec<AuditedAttestation>,
+    next_cursor: Option<LinkCursor>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SongAttestations {
+    pub song_id: Uuid,
+    pub dht_action_hash: String,
+    pub attestations: Vec<AuditedAttestation>,
+    /// Pass as `cursor` for the next page; null on the last
+    pub next_cursor: Option<String>,
+}
+
+/// A song's play attestations with each signature verified (audit trail)
+///
+/// Separates listens the listener signed a receipt for from ones that were
+/// merely recorded, for dispute resolution.
+pub async fn get_song_attestations(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AttestationsQuery>,
+) -> Result<Json<SongAttestations>, Response> {
+    let mut errors = ValidationErrors::new();
+    let cursor = match query.cursor.as_deref() {
+        Some(cursor) => match LinkCursor::parse(cursor) {
+            Some(cursor) => Some(cursor),
+            None => {
+                errors.add("cursor", "Must be the next_cursor of a previous page");
+                None
+            }
+        },
+        None => None,
+    };
+    if let Some(limit) = query.limit {
+        if limit == 0 || limit > MAX_ATTESTATIONS_PAGE {
+            errors.add("limit", "Must be between 1 and 200");
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_response());
+    }
+
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or_else(|| StatusCode::SERVICE_UNAVAILABLE.into_response())?;
+
+    let dht_action_hash = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT dht_action_hash FROM songs WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get song for attestations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?
+    .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?
+    // Songs created before the bridge have no DHT counterpart yet
+    .ok_or_else(|| StatusCode::CONFLICT.into_response())?;
+
+    let input = AttestationsInput {
+        song_hash: dht_action_hash.clone(),
+        cursor,
+        limit: query.limit,
+    };
+    let page: AttestationsPage = holochain
+        .call_zome("plays", "get_attestations_for_song", &input)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get attestations for song {}: {}", id, e);
+            StatusCode::BAD_GATEWAY.into_response()
+        })?;
+
+    Ok(Json(SongAttestations {
+        song_id: id,
+        dht_action_hash,
+        attestations: page.attestations,
+        next_cursor: page.next_cursor.map(|cursor| cursor.to_param()),
+    }))
+}
+
+/// Query params for a play price preview
+#[derive(Debug, Deserialize)]
+pub struct PriceQuery {
+    /// Share of the song listened, 0.0 to 1.0 (default 1.0)
+    pub completion: Option<f64>,
+    /// Currency for `fiat_estimate` (default USD)
+    pub currency: Option<String>,
+}
+
+/// Mirror of `plays::PreviewPlayInput`
+#[derive(Debug, Serialize)]
+struct PreviewPlayInput {
+    song_hash: String,
+    completion_bps: u32,
+}
+
+/// Mirror of `plays::PlayPreview`
+#[derive(Debug, Deserialize)]
+struct PlayPreview {
+    strategy_id: String,
+    song_duration: u32,
+    duration_listened: u32,
+    qualifies: bool,
+    amount_wei: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayPrice {
+    pub song_id: Uuid,
+    pub strategy_id: String,
+    pub completion: f64,
+    pub duration_listened: u32,
+    pub song_duration: u32,
+    /// Whether the play would reach the listen threshold
+    pub qualifies: bool,
+    /// Expected charge (wei of xDAI)
+    pub amount_wei: String,
+    /// Expected charge in xDAI
+    pub amount: f64,
+    pub currency: String,
+    /// `amount` at the latest rate; null when no rate is recorded
+    pub fiat_estimate: Option<f64>,
+    pub rate_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Preview what listening to part of a song would cost
+///
+/// Priced by the plays zome with the same code that charges plays, so the
+/// preview can't drift from the charge. Gift-economy songs and plays below
+/// the listen threshold cost 0.
+pub async fn get_song_price(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<PriceQuery>,
+) -> Result<Json<PlayPrice>, Response> {
+    let completion = query.completion.unwrap_or(1.0);
+    let currency = query.currency.as_deref().unwrap_or("USD").to_uppercase();
+    let mut errors = ValidationErrors::new();
+    if !(0.0..=1.0).contains(&completion) {
+        errors.add("completion", "Must be between 0.0 and 1.0");
+    }
+    if currency.len() < 3
+        || currency.len() > 8
+        || !currency.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        errors.add("currency", "Must be an ISO 4217 code such as EUR, or XDAI");
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_response());
+    }
+
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or_else(|| StatusCode::SERVICE_UNAVAILABLE.into_response())?;
+
+    let dht_action_hash = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT dht_action_hash FROM songs WHERE id = $1 AND status = 'active'",
+    )
+    .bind(id)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get song for price: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?
+    .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?
+    // Songs created before the bridge have no DHT counterpart yet
+    .ok_or_else(|| StatusCode::CONFLICT.into_response())?;
+
+    let input = PreviewPlayInput {
+        song_hash: dht_action_hash,
+        completion_bps: (completion * 10_000.0).round() as u32,
+    };
+    let preview: PlayPreview = holochain
+        .call_zome::<_, Option<PlayPreview>>("plays", "preview_play_amount", &input)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to preview play price for song {}: {}", id, e);
+            StatusCode::BAD_GATEWAY.into_response()
+        })?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    let rate = fx::latest_rate(&state.db_pool, &currency).await.map_err(|e| {
+        tracing::error!("Failed to get FX rate for {}: {}", currency, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let amount = preview.amount_wei as f64 / 1e18;
+
+    Ok(Json(PlayPrice {
+        song_id: id,
+        strategy_id: preview.strategy_id,
+        completion,
+        duration_listened: preview.duration_listened,
+        song_duration: preview.song_duration,
+        qualifies: preview.qualifies,
+        amount_wei: preview.amount_wei.to_string(),
+        amount,
+        currency,
+        fiat_estimate: rate.as_ref().map(|rate| amount * rate.rate),
+        rate_at: rate.map(|rate| rate.fetched_at),
+    }))
+}
+
 use sha2::Digest;
@@ -0,0 +1,339 @@
+//! Royalty Statements - Per-period accounting for artists
+//!
+//! Aggregates an artist's plays over a date range by song and strategy,
+//! with gross earnings, protocol fees and net payout, and lists the
+//! on-chain payments for their songs in the same range. Line items, the
+//! per-strategy breakdown and the totals are all derived from the same
+//! rows in integer wei, so they always add up exactly.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::models::{is_valid_eth_address, ValidationErrors};
+use crate::routes::strategies::strategy_fee_bps;
+use crate::AppState;
+
+/// Longest period one statement may cover (days, inclusive)
+const MAX_STATEMENT_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    /// First day of the period (inclusive, UTC)
+    pub from: NaiveDate,
+    /// Last day of the period (inclusive, UTC)
+    pub to: NaiveDate,
+    /// "json" (default) or "csv"
+    pub format: Option<String>,
+}
+
+/// Serialize wei as a decimal string, like `amount_wei` elsewhere, so
+/// clients don't lose precision
+fn wei_string<S: serde::Serializer>(wei: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(wei)
+}
+
+/// Earnings for one song under one strategy (wei)
+#[derive(Debug, Serialize)]
+pub struct StatementLine {
+    pub song_id: uuid::Uuid,
+    pub title: String,
+    pub strategy_id: String,
+    pub plays: i64,
+    #[serde(serialize_with = "wei_string")]
+    pub gross_wei: u128,
+    pub protocol_fee_bps: u32,
+    #[serde(serialize_with = "wei_string")]
+    pub protocol_fee_wei: u128,
+    #[serde(serialize_with = "wei_string")]
+    pub net_wei: u128,
+}
+
+/// Earnings summed over a strategy or the whole statement (wei)
+#[derive(Debug, Default, Serialize)]
+pub struct StatementTotals {
+    pub plays: i64,
+    #[serde(serialize_with = "wei_string")]
+    pub gross_wei: u128,
+    #[serde(serialize_with = "wei_string")]
+    pub protocol_fee_wei: u128,
+    #[serde(serialize_with = "wei_string")]
+    pub net_wei: u128,
+}
+
+impl StatementTotals {
+    fn add(&mut self, line: &StatementLine) {
+        self.plays += line.plays;
+        self.gross_wei += line.gross_wei;
+        self.protocol_fee_wei += line.protocol_fee_wei;
+        self.net_wei += line.net_wei;
+    }
+}
+
+/// An on-chain payment for one of the artist's songs
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SettlementReference {
+    pub tx_hash: String,
+    pub block_number: i64,
+    /// On-chain song id
+    pub song_id: String,
+    pub amount_wei: String,
+    /// `amount_wei` in whole FLOW
+    pub amount: f64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Royalty statement for one artist and period
+#[derive(Debug, Serialize)]
+pub struct RoyaltyStatement {
+    pub artist_address: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub lines: Vec<StatementLine>,
+    pub by_strategy: BTreeMap<String, StatementTotals>,
+    pub totals: StatementTotals,
+    pub settlements: Vec<SettlementReference>,
+    /// Sum of `settlements` (wei)
+    #[serde(serialize_with = "wei_string")]
+    pub settled_on_chain_wei: u128,
+}
+
+/// Play totals for one song over the period
+#[derive(Debug, sqlx::FromRow)]
+struct SongEarningsRow {
+    song_id: uuid::Uuid,
+    title: String,
+    strategy_id: String,
+    plays: i64,
+    /// Whole wei, as text (NUMERIC has no exact Rust mapping here)
+    gross_wei: String,
+}
+
+/// Generate an artist's royalty statement for a period
+pub async fn get_artist_statement(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    Query(params): Query<StatementQuery>,
+) -> Result<Response, Response> {
+    let csv = validate(&address, &params).map_err(IntoResponse::into_response)?;
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to build royalty statement: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    // Excluded plays (deleted or removed songs) earn nothing; archived plays
+    // still count, so old statements never change
+    let rows = sqlx::query_as::<_, SongEarningsRow>(
+        r#"
+        SELECT s.id AS song_id, s.title, s.strategy_id,
+               COUNT(*) AS plays,
+               TRUNC(COALESCE(SUM(p.amount), 0) * 1e18)::text AS gross_wei
+        FROM plays_with_archive p
+        JOIN songs s ON s.id = p.song_id
+        WHERE LOWER(s.artist_address) = LOWER($1)
+          AND p.timestamp >= $2 AND p.timestamp < $3 + 1
+          AND p.excluded_reason IS NULL
+        GROUP BY s.id, s.title, s.strategy_id
+        ORDER BY s.title, s.id
+        "#,
+    )
+    .bind(&address)
+    .bind(params.from)
+    .bind(params.to)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(internal_error)?;
+
+    let settlements = sqlx::query_as::<_, SettlementReference>(
+        r#"
+        SELECT p.tx_hash, p.block_number, p.song_id, p.amount_wei,
+               (CAST(p.amount_wei AS NUMERIC) / 1e18)::float8 AS amount,
+               p.timestamp
+        FROM payments p
+        JOIN songs s ON s.song_id = p.song_id
+        WHERE LOWER(s.artist_address) = LOWER($1)
+          AND p.timestamp >= $2 AND p.timestamp < $3 + 1
+        ORDER BY p.timestamp, p.tx_hash
+        "#,
+    )
+    .bind(&address)
+    .bind(params.from)
+    .bind(params.to)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(internal_error)?;
+
+    // Fees at the rates settlements deduct, one lookup per strategy
+    let mut fee_rates: BTreeMap<String, u32> = BTreeMap::new();
+    for row in &rows {
+        if !fee_rates.contains_key(&row.strategy_id) {
+            let fee_bps = strategy_fee_bps(&state, &row.strategy_id)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            fee_rates.insert(row.strategy_id.clone(), fee_bps);
+        }
+    }
+
+    // Fees round down, as settlement batches compute them
+    let mut lines = Vec::with_capacity(rows.len());
+    for row in rows {
+        let gross_wei = parse_wei(&row.gross_wei)?;
+        let fee_bps = fee_rates[&row.strategy_id];
+        let protocol_fee_wei = gross_wei * fee_bps as u128 / 10_000;
+        lines.push(StatementLine {
+            song_id: row.song_id,
+            title: row.title,
+            strategy_id: row.strategy_id,
+            plays: row.plays,
+            gross_wei,
+            protocol_fee_bps: fee_bps,
+            protocol_fee_wei,
+            net_wei: gross_wei - protocol_fee_wei,
+        });
+    }
+    let mut settled_on_chain_wei = 0;
+    for settlement in &settlements {
+        settled_on_chain_wei += parse_wei(&settlement.amount_wei)?;
+    }
+
+    let mut by_strategy: BTreeMap<String, StatementTotals> = BTreeMap::new();
+    let mut totals = StatementTotals::default();
+    for line in &lines {
+        by_strategy.entry(line.strategy_id.clone()).or_default().add(line);
+        totals.add(line);
+    }
+
+    let statement = RoyaltyStatement {
+        artist_address: address.to_lowercase(),
+        from: params.from,
+        to: params.to,
+        generated_at: chrono::Utc::now(),
+        settled_on_chain_wei,
+        lines,
+        by_strategy,
+        totals,
+        settlements,
+    };
+
+    if csv {
+        let filename = format!(
+            "royalty-statement-{}-{}-{}.csv",
+            statement.artist_address, statement.from, statement.to
+        );
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", filename),
+                ),
+            ],
+            to_csv(&statement),
+        )
+            .into_response());
+    }
+
+    Ok(Json(statement).into_response())
+}
+
+/// Parse a wei amount from the database
+fn parse_wei(wei: &str) -> Result<u128, Response> {
+    wei.parse().map_err(|e| {
+        tracing::error!("Invalid wei amount {} in royalty statement: {}", wei, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })
+}
+
+/// Check the address and period; returns whether CSV was requested
+fn validate(address: &str, params: &StatementQuery) -> Result<bool, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    if !is_valid_eth_address(address) {
+        errors.add("address", "Must be a 0x-prefixed 42-character address");
+    }
+
+    if params.to < params.from {
+        errors.add("to", "Period must end on or after it starts");
+    } else if (params.to - params.from).num_days() + 1 > MAX_STATEMENT_DAYS {
+        errors.add(
+            "to",
+            &format!("A statement can cover at most {} days", MAX_STATEMENT_DAYS),
+        );
+    }
+
+    let csv = match params.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => {
+            errors.add("format", "Format must be json or csv");
+            false
+        }
+    };
+
+    errors.into_result().map(|()| csv)
+}
+
+/// Render a statement as CSV: line items and a total row, then a blank
+/// line and the on-chain settlements
+fn to_csv(statement: &RoyaltyStatement) -> String {
+    let mut out = String::from(
+        "song_id,title,strategy_id,plays,gross_wei,protocol_fee_bps,protocol_fee_wei,net_wei\n",
+    );
+    for line in &statement.lines {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            line.song_id,
+            csv_field(&line.title),
+            csv_field(&line.strategy_id),
+            line.plays,
+            line.gross_wei,
+            line.protocol_fee_bps,
+            line.protocol_fee_wei,
+            line.net_wei
+        ));
+    }
+    let totals = &statement.totals;
+    out.push_str(&format!(
+        "TOTAL,,,{},{},,{},{}\n",
+        totals.plays, totals.gross_wei, totals.protocol_fee_wei, totals.net_wei
+    ));
+
+    out.push_str("\ntx_hash,block_number,song_id,timestamp,amount_wei,amount\n");
+    for settlement in &statement.settlements {
+        out.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            settlement.tx_hash,
+            settlement.block_number,
+            settlement.song_id,
+            settlement.timestamp.to_rfc3339(),
+            settlement.amount_wei,
+            settlement.amount
+        ));
+    }
+    out
+}
+
+/// Quote a free-text CSV field
+///
+/// A leading `'` stops spreadsheets from running text that starts like
+/// a formula (`=`, `+`, `-`, `@`).
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -1,19 +1,61 @@
 //! Economic Strategies Routes
 //!
 //! Manage and preview economic strategies for artists
+//!
+//! The catalog's static metadata mirrors the Solidity contracts; when the
+//! chain is reachable it's merged with the router's live parameters so
+//! artists only pick strategies the chain will honor.
 
 use axum::{
     extract::{Path, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
+use ethers::types::U256;
+use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::models::ValidationErrors;
+use crate::services::blockchain::BlockchainService;
 use crate::AppState;
 
+const CATALOG_CACHE_KEY: &str = "strategies:catalog";
+
+/// Live catalog cache lifetime; fee and registry changes are rare
+const CATALOG_TTL_SECS: u64 = 300;
+
+/// Role every split must include; the only role allowed above the cap
+pub const PRIMARY_ROLE: &str = "artist";
+
+/// Roles a split recipient may have
+pub const KNOWN_ROLES: &[&str] = &[
+    PRIMARY_ROLE,
+    "featured_artist",
+    "producer",
+    "songwriter",
+    "composer",
+    "mixer",
+    "label",
+    "manager",
+    "collaborator",
+];
+
+/// Default cap for a single non-artist recipient (50%)
+const DEFAULT_MAX_RECIPIENT_BPS: u32 = 5000;
+
+/// Cap for a single non-artist recipient, from `SPLIT_MAX_RECIPIENT_BPS`
+pub fn max_recipient_bps_from_env() -> u32 {
+    std::env::var("SPLIT_MAX_RECIPIENT_BPS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_MAX_RECIPIENT_BPS)
+        .min(10000)
+}
+
 /// Available economic strategy
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EconomicStrategy {
     pub id: String,
     pub name: String,
@@ -24,6 +66,12 @@ pub struct EconomicStrategy {
     pub supports_free_listening: bool,
     pub supports_tips: bool,
     pub supports_subscriptions: bool,
+    /// Registered strategy contract (None without live data or if unregistered)
+    pub contract_address: Option<String>,
+    /// Whether the chain will honor this strategy; assumed true without live data
+    pub available: bool,
+    /// Whether fee, minimum and availability were read from the chain
+    pub live: bool,
 }
 
 /// Split preview request
@@ -33,13 +81,106 @@ pub struct PreviewSplitsRequest {
     pub splits: Vec<SplitConfig>,
 }
 
+/// One recipient's share, given as either `basis_points` or `percentage`
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SplitConfig {
     pub recipient: String,
-    pub basis_points: u32,
+    #[serde(default)]
+    pub basis_points: Option<u32>,
+    /// 0-100 with at most two decimals (12.5 = 1250 bps)
+    #[serde(default)]
+    pub percentage: Option<f64>,
     pub role: String,
 }
 
+impl PreviewSplitsRequest {
+    /// Validate the request and resolve each split to basis points
+    pub fn validate(&self, max_recipient_bps: u32) -> Result<Vec<u32>, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if !self.amount.is_finite() || self.amount < 0.0 {
+            errors.add("amount", "Amount must be a non-negative number");
+        }
+
+        let mut resolved = Vec::with_capacity(self.splits.len());
+        for (i, split) in self.splits.iter().enumerate() {
+            let role = split.role.as_str();
+            if !KNOWN_ROLES.contains(&role) {
+                errors.add(
+                    &format!("splits[{}].role", i),
+                    &format!("Unknown role '{}' (expected one of: {})", role, KNOWN_ROLES.join(", ")),
+                );
+            }
+
+            let bps = match (split.basis_points, split.percentage) {
+                (Some(bps), None) => bps,
+                (None, Some(percentage)) => match percentage_to_bps(percentage) {
+                    Some(bps) => bps,
+                    None => {
+                        errors.add(
+                            &format!("splits[{}].percentage", i),
+                            "Percentage must be between 0 and 100 with at most two decimals",
+                        );
+                        0
+                    }
+                },
+                (Some(_), Some(_)) => {
+                    errors.add(
+                        &format!("splits[{}]", i),
+                        "Give either basis_points or percentage, not both",
+                    );
+                    0
+                }
+                (None, None) => {
+                    errors.add(&format!("splits[{}]", i), "Missing basis_points or percentage");
+                    0
+                }
+            };
+
+            if role != PRIMARY_ROLE && bps > max_recipient_bps {
+                errors.add(
+                    &format!("splits[{}]", i),
+                    &format!(
+                        "A '{}' share cannot exceed {} basis points (got {})",
+                        role, max_recipient_bps, bps
+                    ),
+                );
+            }
+            resolved.push(bps);
+        }
+
+        if !self.splits.iter().any(|s| s.role == PRIMARY_ROLE) {
+            errors.add(
+                "splits",
+                &format!("Splits must include a recipient with the '{}' role", PRIMARY_ROLE),
+            );
+        }
+
+        let total_bps: u64 = resolved.iter().map(|&bps| bps as u64).sum();
+        if total_bps != 10000 {
+            errors.add(
+                "splits",
+                &format!("Split basis points must sum to 10000 (got {})", total_bps),
+            );
+        }
+
+        errors.into_result().map(|()| resolved)
+    }
+}
+
+/// Convert a percentage to basis points; None if out of range or finer
+/// than 0.01%
+fn percentage_to_bps(percentage: f64) -> Option<u32> {
+    if !(0.0..=100.0).contains(&percentage) {
+        return None;
+    }
+    let bps = (percentage * 100.0).round();
+    if (percentage * 100.0 - bps).abs() > 1e-6 {
+        return None;
+    }
+    Some(bps as u32)
+}
+
 /// Split preview response
 #[derive(Debug, Serialize)]
 pub struct PreviewSplitsResponse {
@@ -57,11 +198,104 @@ pub struct Distribution {
     pub percentage: f64,
 }
 
-/// List all available strategies
+/// List all strategies, with live on-chain parameters when available
 pub async fn list_strategies(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
 ) -> Json<Vec<EconomicStrategy>> {
-    // These mirror the Solidity contracts
+    let blockchain = match state.blockchain.as_ref() {
+        Some(blockchain) => blockchain,
+        None => return Json(static_catalog()),
+    };
+
+    let mut conn = state.redis.get_multiplexed_async_connection().await.ok();
+    if let Some(conn) = conn.as_mut() {
+        let cached: Option<String> = conn.get(CATALOG_CACHE_KEY).await.unwrap_or(None);
+        if let Some(catalog) = cached.and_then(|json| serde_json::from_str(&json).ok()) {
+            return Json(catalog);
+        }
+    }
+
+    let catalog = match live_catalog(blockchain).await {
+        Ok(catalog) => catalog,
+        // Don't cache an RPC outage
+        Err(e) => {
+            tracing::warn!("Failed to read strategies from chain, using static catalog: {}", e);
+            return Json(static_catalog());
+        }
+    };
+
+    if let Some(conn) = conn.as_mut() {
+        if let Ok(json) = serde_json::to_string(&catalog) {
+            let _: Result<(), _> = conn.set_ex(CATALOG_CACHE_KEY, json, CATALOG_TTL_SECS).await;
+        }
+    }
+
+    Json(catalog)
+}
+
+/// Static catalog merged with the router's fee and each strategy's
+/// registration, deployment and minimum payment
+async fn live_catalog(blockchain: &BlockchainService) -> anyhow::Result<Vec<EconomicStrategy>> {
+    let protocol_fee_bps = blockchain.get_protocol_fee_bps().await?;
+
+    let lookups = static_catalog().into_iter().map(|mut strategy| async move {
+        strategy.live = true;
+        strategy.available = false;
+        strategy.default_protocol_fee_bps = protocol_fee_bps;
+
+        let address = match blockchain.get_registered_strategy(&strategy.id).await? {
+            Some(address) => address,
+            None => return Ok(strategy),
+        };
+        strategy.contract_address = Some(format!("{:?}", address));
+
+        if !blockchain.has_code(address).await? {
+            return Ok(strategy);
+        }
+        // A reverting minimum means the contract can't take payments
+        if let Ok(min_payment) = blockchain.get_min_payment(address).await {
+            strategy.min_payment = wei_to_flow(min_payment);
+            strategy.available = true;
+        }
+        Ok::<_, anyhow::Error>(strategy)
+    });
+
+    futures::future::try_join_all(lookups).await
+}
+
+/// Wei to whole FLOW (18 decimals), for display
+fn wei_to_flow(wei: U256) -> f64 {
+    ethers::utils::format_units(wei, 18)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0.0)
+}
+
+/// Mirror of `plays::StrategyFeeRate`
+#[derive(Debug, Deserialize)]
+struct StrategyFeeRate {
+    effective_fee_bps: u32,
+}
+
+/// Protocol fee the plays zome deducts from a strategy's settlements
+/// (basis points), the rate batch validation enforces
+pub async fn strategy_fee_bps(state: &AppState, strategy_id: &str) -> Result<u32, StatusCode> {
+    let holochain = state
+        .holochain
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let rate: StrategyFeeRate = holochain
+        .call_zome("plays", "get_strategy_fee", &strategy_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get fee for strategy {}: {}", strategy_id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+    Ok(rate.effective_fee_bps)
+}
+
+/// Strategy metadata mirroring the Solidity contracts
+fn static_catalog() -> Vec<EconomicStrategy> {
     let strategies = vec![
         EconomicStrategy {
             id: "pay-per-stream-v1".into(),
@@ -73,6 +307,9 @@ pub async fn list_strategies(
             supports_free_listening: false,
             supports_tips: true,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "gift-economy-v1".into(),
@@ -84,6 +321,9 @@ pub async fn list_strategies(
             supports_free_listening: true,
             supports_tips: true,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "subscription-v1".into(),
@@ -95,6 +335,9 @@ pub async fn list_strategies(
             supports_free_listening: false,
             supports_tips: true,
             supports_subscriptions: true,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "patronage-v1".into(),
@@ -106,6 +349,9 @@ pub async fn list_strategies(
             supports_free_listening: true,
             supports_tips: false,
             supports_subscriptions: true,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "nft-gated-v1".into(),
@@ -117,6 +363,9 @@ pub async fn list_strategies(
             supports_free_listening: true,
             supports_tips: true,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "pay-what-you-want-v1".into(),
@@ -128,6 +377,9 @@ pub async fn list_strategies(
             supports_free_listening: true,
             supports_tips: true,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "auction-v1".into(),
@@ -139,6 +391,9 @@ pub async fn list_strategies(
             supports_free_listening: false,
             supports_tips: false,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "freemium-v1".into(),
@@ -150,6 +405,9 @@ pub async fn list_strategies(
             supports_free_listening: true,
             supports_tips: true,
             supports_subscriptions: true,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "time-barter-v1".into(),
@@ -161,6 +419,9 @@ pub async fn list_strategies(
             supports_free_listening: false,
             supports_tips: false,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "download-v1".into(),
@@ -172,6 +433,9 @@ pub async fn list_strategies(
             supports_free_listening: false,
             supports_tips: false,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
         EconomicStrategy {
             id: "staking-gated-v1".into(),
@@ -183,48 +447,71 @@ pub async fn list_strategies(
             supports_free_listening: true,
             supports_tips: true,
             supports_subscriptions: false,
+            contract_address: None,
+            available: true,
+            live: false,
         },
     ];
 
-    Json(strategies)
+    strategies
+}
+
+/// Ids of the strategies in an economic category (None if unknown)
+///
+/// Built from the catalog `list_strategies` serves, so the two agree.
+pub fn category_strategy_ids(category: &str) -> Option<Vec<String>> {
+    let ids: Vec<String> = static_catalog()
+        .into_iter()
+        .filter(|strategy| strategy.category == category)
+        .map(|strategy| strategy.id)
+        .collect();
+    (!ids.is_empty()).then_some(ids)
+}
+
+/// Every economic category in the catalog, sorted
+pub fn categories() -> Vec<String> {
+    let mut categories: Vec<String> = static_catalog()
+        .into_iter()
+        .map(|strategy| strategy.category)
+        .collect();
+    categories.sort();
+    categories.dedup();
+    categories
 }
 
 /// Preview how splits would work for a given amount
+///
+/// The fee is the one settlements of the strategy actually deduct, from
+/// the plays zome.
 pub async fn preview_splits(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Path(strategy_id): Path<String>,
     Json(req): Json<PreviewSplitsRequest>,
-) -> Result<Json<PreviewSplitsResponse>, StatusCode> {
-    // Get protocol fee for strategy (simplified)
-    let protocol_fee_bps: u32 = match strategy_id.as_str() {
-        "pay-per-stream-v1" => 100,
-        "gift-economy-v1" => 100,
-        "subscription-v1" => 200,
-        "nft-gated-v1" => 250,
-        "auction-v1" => 500,
-        _ => 100,
-    };
+) -> Result<Json<PreviewSplitsResponse>, Response> {
+    let split_bps = req
+        .validate(state.split_max_recipient_bps)
+        .map_err(IntoResponse::into_response)?;
+
+    let protocol_fee_bps = strategy_fee_bps(&state, &strategy_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
 
     let gross_amount = req.amount;
     let protocol_fee = gross_amount * (protocol_fee_bps as f64 / 10000.0);
     let net_amount = gross_amount - protocol_fee;
 
     // Calculate distributions
-    let total_bps: u32 = req.splits.iter().map(|s| s.basis_points).sum();
-    if total_bps != 10000 {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     let distributions: Vec<Distribution> = req
         .splits
         .iter()
-        .map(|split| {
-            let amount = net_amount * (split.basis_points as f64 / 10000.0);
+        .zip(split_bps)
+        .map(|(split, bps)| {
+            let amount = net_amount * (bps as f64 / 10000.0);
             Distribution {
                 recipient: split.recipient.clone(),
                 role: split.role.clone(),
                 amount,
-                percentage: split.basis_points as f64 / 100.0,
+                percentage: bps as f64 / 100.0,
             }
         })
         .collect();
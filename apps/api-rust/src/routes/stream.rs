@@ -0,0 +1,212 @@
+//! Stream Routes - Audio playback through CDN nodes and the IPFS gateway pool
+//!
+//! Streams from the region's best community CDN nodes when the conductor
+//! bridge is configured, moving to the next node if one errors or stalls
+//! mid-transfer. Otherwise proxies content from the most reliable gateway,
+//! failing over to the next one if a gateway errors or is slow to respond.
+//! Listeners without access to a gated song get its preview clip instead
+//! of the full track.
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::models::{is_valid_cid, PaymentModel};
+use crate::routes::auth::{authenticate, AuthUser};
+use crate::services::catalog_sync;
+use crate::AppState;
+
+/// Upstream headers passed through to the client
+const FORWARDED_HEADERS: &[header::HeaderName] = &[
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_RANGE,
+    header::ACCEPT_RANGES,
+    header::ETAG,
+];
+
+/// Stream query params
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    /// Signed download token from the listener's library
+    pub token: Option<String>,
+}
+
+/// Marks a response that carries a preview clip instead of the requested CID
+const PREVIEW_HEADER: &str = "x-preview";
+
+/// What to serve for a gated song's full track
+enum GatedPlayback {
+    /// The viewer has access; serve it, but not from shared caches
+    Full,
+    /// Serve this preview clip instead
+    Preview(String),
+}
+
+/// How to serve `cid` if it is the full track of a gated song with a preview
+///
+/// The artist, admins and listeners with an on-chain payment for the song
+/// have access. Gated songs without a preview are served as before.
+async fn gated_playback(
+    state: &AppState,
+    cid: &str,
+    viewer: Option<&AuthUser>,
+) -> sqlx::Result<Option<GatedPlayback>> {
+    let songs = sqlx::query_as::<_, (String, Option<String>, String, bool)>(
+        r#"
+        SELECT s.payment_model, s.preview_cid, s.artist_address,
+               EXISTS (
+                   SELECT 1 FROM payments p
+                   WHERE p.song_id = s.song_id AND LOWER(p.listener_address) = LOWER($2)
+               )
+        FROM songs s
+        WHERE s.ipfs_hash = $1 AND s.preview_cid IS NOT NULL
+        "#,
+    )
+    .bind(cid)
+    .bind(viewer.map(|v| v.address.as_str()))
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let gated = songs.into_iter().find(|(payment_model, ..)| {
+        PaymentModel::parse(payment_model).map_or(false, PaymentModel::is_gated)
+    });
+    Ok(gated.map(|(_, preview_cid, artist_address, paid)| {
+        let has_access = paid || viewer.map_or(false, |v| v.is_admin || v.is(&artist_address));
+        match preview_cid {
+            Some(preview_cid) if !has_access => GatedPlayback::Preview(preview_cid),
+            _ => GatedPlayback::Full,
+        }
+    }))
+}
+
+/// Stream a CID, honouring `Range` for seeking
+///
+/// A valid download `token` for the CID serves the full original as an
+/// attachment, whatever the song's gating.
+pub async fn stream_cid(
+    State(state): State<Arc<AppState>>,
+    Path(cid): Path<String>,
+    Query(params): Query<StreamQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !is_valid_cid(&cid) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let download = match &params.token {
+        Some(token) => match state.auth.verify_download_token(token, &cid) {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::debug!("Rejected download of {}: {}", cid, e);
+                return Err(StatusCode::FORBIDDEN);
+            }
+        },
+        None => false,
+    };
+
+    let viewer = authenticate(&state.auth, &headers);
+    let gated = if download {
+        Some(GatedPlayback::Full)
+    } else {
+        gated_playback(&state, &cid, viewer.as_ref()).await.map_err(|e| {
+            tracing::error!("Failed to check access for {}: {}", cid, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    };
+    let is_gated = gated.is_some();
+    let (cid, cache_control, is_preview) = match gated {
+        None => (cid, "public, max-age=31536000, immutable", false),
+        Some(GatedPlayback::Full) => (cid, "private, max-age=31536000, immutable", false),
+        Some(GatedPlayback::Preview(preview_cid)) => (preview_cid, "private, no-cache", true),
+    };
+
+    let range = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+
+    // Community CDN nodes first, then the gateway pool
+    let cdn_stream = match &state.cdn {
+        Some(cdn) => cdn.open(&cid, range, catalog_song_hash(&state, &cid).await).await,
+        None => None,
+    };
+    let (status, upstream_headers, body) = match cdn_stream {
+        Some(stream) => {
+            let first = stream.response().ok_or(StatusCode::BAD_GATEWAY)?;
+            let (status, upstream_headers) = (first.status(), first.headers().clone());
+            (status, upstream_headers, Body::from_stream(stream.into_body()))
+        }
+        None => {
+            let upstream = state.gateways.fetch(&cid, range).await.map_err(|e| {
+                tracing::error!("Failed to stream {}: {}", cid, e);
+                StatusCode::BAD_GATEWAY
+            })?;
+            let (status, upstream_headers) = (upstream.status(), upstream.headers().clone());
+            (status, upstream_headers, Body::from_stream(upstream.bytes_stream()))
+        }
+    };
+
+    let mut response = Response::builder()
+        .status(status.as_u16())
+        .header(header::CACHE_CONTROL, cache_control);
+    if is_gated {
+        // Whether the full track is served depends on who is asking
+        response = response.header(header::VARY, "Authorization");
+    }
+    if is_preview {
+        response = response.header(PREVIEW_HEADER, "true");
+    }
+    if download {
+        response = response.header(header::CONTENT_DISPOSITION, "attachment");
+    }
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = upstream_headers.get(name.as_str()) {
+            response = response.header(name, value.as_bytes());
+        }
+    }
+
+    response
+        .body(body)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Catalog action hash of the song a CID belongs to, for CDN quality reports
+///
+/// Songs without a `dht_action_hash` yet are looked up in the catalog (see
+/// `catalog_sync`). None if the song isn't on the DHT, since reports must
+/// name a song that exists there.
+async fn catalog_song_hash(state: &AppState, cid: &str) -> Option<String> {
+    let row = sqlx::query_as::<_, (String, Option<String>)>(
+        r#"
+        SELECT song_hash, dht_action_hash FROM songs
+        WHERE ipfs_hash = $1 OR preview_cid = $1
+        LIMIT 1
+        "#,
+    )
+    .bind(cid)
+    .fetch_optional(&state.db_pool)
+    .await;
+
+    let (song_hash, dht_action_hash) = match row {
+        Ok(row) => row?,
+        Err(e) => {
+            tracing::warn!("Failed to look up catalog hash for {}: {}", cid, e);
+            return None;
+        }
+    };
+    if dht_action_hash.is_some() {
+        return dht_action_hash;
+    }
+
+    let holochain = state.holochain.as_ref()?;
+    match catalog_sync::link_song(&state.db_pool, holochain, &song_hash).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            tracing::debug!("Failed to resolve catalog hash for {}: {}", cid, e);
+            None
+        }
+    }
+}
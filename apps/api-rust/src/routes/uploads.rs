@@ -1,29 +1,96 @@
 //! Upload Routes - IPFS file uploads
 //!
-//! Handles music file uploads to IPFS with Web3.Storage
+//! Handles music file and cover-art uploads to IPFS with Web3.Storage.
+//! Uploads started with an `upload_id` report progress over SSE.
 
 use axum::{
-    extract::{Multipart, State},
-    http::StatusCode,
-    Json,
+    body::Bytes,
+    extract::{Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
 };
-use serde::Serialize;
+use futures::{stream, Stream};
+use ipfs_api_backend_hyper::{request, response::AddResponse, IpfsApi};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
+use uuid::Uuid;
 
+use crate::routes::auth::AuthUser;
+use crate::routes::images::{create_thumbnails, Thumbnail};
+use crate::services::thumbnail::is_image;
+use crate::services::transcode::{
+    TranscodeService, ALLOWED_BITRATES, DEFAULT_PREVIEW_SECS, MAX_PREVIEW_SECS, STREAMING_CONTENT_TYPE,
+};
+use crate::services::upload_limiter::RETRY_AFTER_SECS;
+use crate::services::upload_progress::{UploadStage, STALE_AFTER};
 use crate::AppState;
 
 #[derive(Debug, Serialize)]
 pub struct UploadResponse {
     pub success: bool,
+    /// Original file (served for download purchases)
+    pub ipfs_hash: String,
+    pub size: u64,
+    pub content_type: String,
+    /// Preferred gateway URL (first of `gateway_urls`)
+    pub gateway_url: String,
+    /// All configured gateway URLs, most reliable first
+    pub gateway_urls: Vec<String>,
+    /// Rendition to stream (the original if not transcoded)
+    pub streaming: StreamingRendition,
+    /// WebP thumbnails (cover-art uploads only)
+    pub thumbnails: Vec<Thumbnail>,
+    /// Preview clip, if requested and generated (pass as the song's `preview_cid`)
+    pub preview: Option<PreviewClip>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewClip {
+    pub ipfs_hash: String,
+    pub size: u64,
+    pub content_type: String,
+    pub duration_secs: u32,
+    pub gateway_url: String,
+    pub gateway_urls: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamingRendition {
     pub ipfs_hash: String,
     pub size: u64,
     pub content_type: String,
     pub gateway_url: String,
+    pub gateway_urls: Vec<String>,
+    /// Whether this is a transcoded rendition rather than the original
+    pub transcoded: bool,
+}
+
+/// Upload query params
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// Produce a streaming rendition for lossless uploads
+    pub transcode: Option<bool>,
+    /// Streaming bitrate in kbps (128 or 256, default 256)
+    pub bitrate_kbps: Option<u32>,
+    /// Client-chosen id to follow at `/api/upload/:upload_id/progress`
+    pub upload_id: Option<Uuid>,
+    /// Also cut a faded preview clip from the start of an audio upload
+    pub preview: Option<bool>,
+    /// Preview length in seconds (default 30, at most 60)
+    pub preview_secs: Option<u32>,
 }
 
 /// Maximum file size (100MB)
 const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
 
+/// Maximum cover-art size (10MB)
+const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
+
 /// Allowed MIME types
 const ALLOWED_TYPES: &[&str] = &[
     "audio/mpeg",
@@ -36,12 +103,64 @@ const ALLOWED_TYPES: &[&str] = &[
     "audio/aac",
 ];
 
+/// Upload failure
+pub enum UploadError {
+    Status(StatusCode),
+    /// Upload slots or the in-flight byte budget are exhausted
+    Busy,
+}
+
+impl From<StatusCode> for UploadError {
+    fn from(status: StatusCode) -> Self {
+        Self::Status(status)
+    }
+}
+
+impl IntoResponse for UploadError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Status(status) => status.into_response(),
+            Self::Busy => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+            )
+                .into_response(),
+        }
+    }
+}
+
 /// Upload file to IPFS
 pub async fn upload_file(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<UploadQuery>,
+    headers: HeaderMap,
     mut multipart: Multipart,
-) -> Result<Json<UploadResponse>, StatusCode> {
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
+) -> Result<Json<UploadResponse>, UploadError> {
+    let bitrate_kbps = params.bitrate_kbps.unwrap_or(256);
+    if !ALLOWED_BITRATES.contains(&bitrate_kbps) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    let preview_secs = params.preview_secs.unwrap_or(DEFAULT_PREVIEW_SECS);
+    if preview_secs == 0 || preview_secs > MAX_PREVIEW_SECS {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    // Dropped on any early return, which reports the upload as failed
+    let expected_bytes = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    let mut progress = params
+        .upload_id
+        .map(|id| state.upload_progress.start(&id.to_string(), expected_bytes));
+
+    let mut permit = state.upload_limiter.try_acquire().ok_or_else(|| {
+        tracing::warn!("Rejected upload: concurrency limit reached");
+        UploadError::Busy
+    })?;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
         tracing::error!("Failed to read multipart field: {}", e);
         StatusCode::BAD_REQUEST
     })? {
@@ -56,52 +175,237 @@ pub async fn upload_file(
             .unwrap_or_else(|| "application/octet-stream".to_string());
 
         // Validate content type
-        if !ALLOWED_TYPES.contains(&content_type.as_str()) {
+        let is_cover = is_image(&content_type);
+        if !ALLOWED_TYPES.contains(&content_type.as_str()) && !is_cover {
             tracing::warn!("Rejected upload with content type: {}", content_type);
-            return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE.into());
         }
 
-        // Read file data
-        let data = field.bytes().await.map_err(|e| {
+        // Read file data, enforcing the size limit and byte budget as it arrives
+        let max_size = if is_cover { MAX_IMAGE_SIZE } else { MAX_FILE_SIZE };
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(|e| {
             tracing::error!("Failed to read file data: {}", e);
             StatusCode::BAD_REQUEST
-        })?;
-
-        // Check file size
-        if data.len() as u64 > MAX_FILE_SIZE {
-            tracing::warn!("Rejected upload: file too large ({} bytes)", data.len());
-            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        })? {
+            let received = (data.len() + chunk.len()) as u64;
+            if received > max_size {
+                tracing::warn!("Rejected upload: file too large (over {} bytes)", max_size);
+                return Err(StatusCode::PAYLOAD_TOO_LARGE.into());
+            }
+            if !permit.reserve(chunk.len() as u64) {
+                tracing::warn!("Rejected upload: in-flight byte budget exhausted");
+                state.upload_limiter.record_rejection();
+                return Err(UploadError::Busy);
+            }
+            data.extend_from_slice(&chunk);
+            if let Some(progress) = progress.as_mut() {
+                progress.received(chunk.len() as u64);
+            }
         }
 
-        // Upload to IPFS
-        let cursor = std::io::Cursor::new(data.to_vec());
-        let response = state
-            .ipfs_client
-            .add(cursor)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to upload to IPFS: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+        // Upload to IPFS; the rest of the handler shares the buffer
+        let data = Bytes::from(data);
+        if let Some(progress) = progress.as_mut() {
+            progress.stage(UploadStage::Storing);
+        }
+        let response = add_to_ipfs(&state, data.clone()).await.map_err(|e| {
+            tracing::error!("Failed to upload to IPFS: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
         let ipfs_hash = response.hash;
         let size = data.len() as u64;
 
         tracing::info!(
-            "Uploaded file to IPFS: {} ({} bytes, {})",
+            "Uploaded file to IPFS: {} ({} bytes, {}) by {}",
             ipfs_hash,
             size,
-            content_type
+            content_type,
+            user.address
         );
 
+        if let Some(progress) = progress.as_mut() {
+            progress.stage(UploadStage::Processing);
+        }
+
+        let gateways = state.gateways.ranked().await;
+        let original_urls = gateway_urls(&gateways, &ipfs_hash);
+
+        // Optional streaming rendition; any failure falls back to the original
+        let mut streaming = StreamingRendition {
+            ipfs_hash: ipfs_hash.clone(),
+            size,
+            content_type: content_type.clone(),
+            gateway_url: original_urls.first().cloned().unwrap_or_default(),
+            gateway_urls: original_urls.clone(),
+            transcoded: false,
+        };
+
+        if params.transcode.unwrap_or(false) && TranscodeService::should_transcode(&content_type) {
+            match &state.transcoder {
+                Some(transcoder) => match transcoder.to_streaming_aac(&data, bitrate_kbps).await {
+                    Ok(rendition) => {
+                        let rendition_size = rendition.len() as u64;
+                        match add_to_ipfs(&state, rendition.into()).await {
+                            Ok(added) => {
+                                tracing::info!(
+                                    "Uploaded streaming rendition: {} ({} bytes, {}kbps)",
+                                    added.hash,
+                                    rendition_size,
+                                    bitrate_kbps
+                                );
+                                let urls = gateway_urls(&gateways, &added.hash);
+                                streaming = StreamingRendition {
+                                    gateway_url: urls.first().cloned().unwrap_or_default(),
+                                    gateway_urls: urls,
+                                    ipfs_hash: added.hash,
+                                    size: rendition_size,
+                                    content_type: STREAMING_CONTENT_TYPE.to_string(),
+                                    transcoded: true,
+                                };
+                            }
+                            Err(e) => {
+                                tracing::warn!("Failed to upload rendition, using original: {}", e)
+                            }
+                        }
+                    }
+                    Err(e) => tracing::warn!("Transcode failed, using original: {}", e),
+                },
+                None => tracing::warn!("Transcode requested but FFMPEG_PATH is not configured"),
+            }
+        }
+
+        let preview = if params.preview.unwrap_or(false) && !is_cover {
+            create_preview(&state, &gateways, &data, preview_secs).await
+        } else {
+            None
+        };
+
+        let thumbnails = if is_cover {
+            create_thumbnails(&state, &ipfs_hash, &data).await
+        } else {
+            Vec::new()
+        };
+
+        if let Some(progress) = progress {
+            progress.done();
+        }
+
         return Ok(Json(UploadResponse {
             success: true,
-            ipfs_hash: ipfs_hash.clone(),
+            gateway_url: original_urls.first().cloned().unwrap_or_default(),
+            gateway_urls: original_urls,
+            ipfs_hash,
             size,
             content_type,
-            gateway_url: format!("https://w3s.link/ipfs/{}", ipfs_hash),
+            streaming,
+            thumbnails,
+            preview,
         }));
     }
 
-    Err(StatusCode::BAD_REQUEST)
+    Err(StatusCode::BAD_REQUEST.into())
+}
+
+/// Cut and store a preview clip; None (upload continues) on any failure
+async fn create_preview(
+    state: &AppState,
+    gateways: &[String],
+    data: &[u8],
+    duration_secs: u32,
+) -> Option<PreviewClip> {
+    let transcoder = match &state.transcoder {
+        Some(transcoder) => transcoder,
+        None => {
+            tracing::warn!("Preview requested but FFMPEG_PATH is not configured");
+            return None;
+        }
+    };
+    let clip = match transcoder.preview_clip(data, duration_secs).await {
+        Ok(clip) => clip,
+        Err(e) => {
+            tracing::warn!("Preview clip failed, skipping preview: {}", e);
+            return None;
+        }
+    };
+
+    let size = clip.len() as u64;
+    match add_to_ipfs(state, clip.into()).await {
+        Ok(added) => {
+            tracing::info!("Uploaded preview clip: {} ({} bytes, {}s)", added.hash, size, duration_secs);
+            let urls = gateway_urls(gateways, &added.hash);
+            Some(PreviewClip {
+                gateway_url: urls.first().cloned().unwrap_or_default(),
+                gateway_urls: urls,
+                ipfs_hash: added.hash,
+                size,
+                content_type: STREAMING_CONTENT_TYPE.to_string(),
+                duration_secs,
+            })
+        }
+        Err(e) => {
+            tracing::warn!("Failed to upload preview clip, skipping preview: {}", e);
+            None
+        }
+    }
+}
+
+/// URLs for a hash on each gateway, in the given order
+pub fn gateway_urls(gateways: &[String], ipfs_hash: &str) -> Vec<String> {
+    gateways
+        .iter()
+        .map(|gateway| format!("{}/{}", gateway, ipfs_hash))
+        .collect()
+}
+
+/// Add a file to IPFS with the configured chunker
+///
+/// Takes `Bytes` so callers can keep using the buffer without copying it.
+async fn add_to_ipfs(
+    state: &AppState,
+    data: Bytes,
+) -> Result<AddResponse, ipfs_api_backend_hyper::Error> {
+    let options = request::Add {
+        chunker: Some(&state.ipfs_chunker),
+        ..Default::default()
+    };
+    state
+        .ipfs_client
+        .add_with_options(std::io::Cursor::new(data), options)
+        .await
+}
+
+/// Stream an upload's progress as server-sent `progress` events
+///
+/// The stream ends after the `done` or `failed` event, on shutdown, or
+/// when no update arrives for ten minutes.
+pub async fn upload_progress(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<Uuid>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.upload_progress.subscribe(&upload_id.to_string());
+    let shutdown = state.upload_progress.shutdown();
+
+    let initial = (Some(receiver), shutdown, true);
+    let events = stream::unfold(initial, |(receiver, mut shutdown, first)| async move {
+        let mut receiver = receiver?;
+        if *shutdown.borrow() {
+            return None;
+        }
+        if !first {
+            tokio::select! {
+                // Errors once the channel is pruned
+                changed = receiver.changed() => changed.ok()?,
+                _ = shutdown.changed() => return None,
+                _ = tokio::time::sleep(STALE_AFTER) => return None,
+            }
+        }
+        let progress = receiver.borrow_and_update().clone();
+        let event = Event::default().event("progress").json_data(&progress).ok()?;
+        let next = if progress.is_finished() { None } else { Some(receiver) };
+        Some((Ok(event), (next, shutdown, false)))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
 }
@@ -0,0 +1,238 @@
+//! Play Anomalies - Rate-of-change detection for play farming
+//!
+//! Compares each busy song's plays in the latest completed window with its
+//! trailing baseline of same-sized windows. A song is flagged when the window is
+//! `z_threshold` standard deviations above the baseline and most of its
+//! plays come from listeners first seen within `new_listener_days`.
+//! Listener trust scores live on the DHT, so listener age stands in for
+//! trust here.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, DurationRound, Utc};
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Detection thresholds and schedule
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    pub interval_secs: u64,
+    /// Length of the window checked on each run
+    pub window_minutes: i64,
+    /// Trailing period the baseline is measured over
+    pub baseline_hours: i64,
+    /// Standard deviations above the baseline mean that count as a spike
+    pub z_threshold: f64,
+    /// Windows with fewer plays are never flagged
+    pub min_plays: i64,
+    /// Listeners whose first play is this recent count as new
+    pub new_listener_days: i64,
+    /// Share of window plays from new listeners needed to flag (0-1)
+    pub new_listener_share: f64,
+    /// Hold a flagged song's earnings until an admin resolves the flag
+    pub pause_earnings: bool,
+}
+
+impl AnomalyConfig {
+    /// Configure from `ANOMALY_*` variables
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            interval_secs: var("ANOMALY_INTERVAL_SECS", 900),
+            window_minutes: var("ANOMALY_WINDOW_MINUTES", 60i64).max(1),
+            baseline_hours: var("ANOMALY_BASELINE_HOURS", 168i64).max(1),
+            z_threshold: var("ANOMALY_Z_THRESHOLD", 4.0),
+            min_plays: var("ANOMALY_MIN_PLAYS", 50),
+            new_listener_days: var("ANOMALY_NEW_LISTENER_DAYS", 7),
+            new_listener_share: var("ANOMALY_NEW_LISTENER_SHARE", 0.5),
+            pause_earnings: std::env::var("ANOMALY_PAUSE_EARNINGS")
+                .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// A song's latest window against its baseline
+#[derive(Debug, sqlx::FromRow)]
+struct SongVelocity {
+    song_id: Uuid,
+    plays: i64,
+    new_listener_plays: i64,
+    /// Sum and sum of squares of per-window plays over the baseline
+    baseline_total: i64,
+    baseline_sum_squares: i64,
+}
+
+/// Mean and standard deviation of per-window plays, counting empty windows
+fn baseline_stats(total: i64, sum_squares: i64, windows: i64) -> (f64, f64) {
+    let n = windows.max(1) as f64;
+    let mean = total as f64 / n;
+    let variance = (sum_squares as f64 / n - mean * mean).max(0.0);
+    (mean, variance.sqrt())
+}
+
+/// Check the latest completed window; returns the number of songs flagged
+///
+/// Windows are aligned to multiples of their length, so every run until
+/// the next one completes checks the same window, and a flag an admin
+/// dismissed isn't raised again for it.
+pub async fn detect(pool: &PgPool, config: &AnomalyConfig) -> Result<usize> {
+    let window = ChronoDuration::minutes(config.window_minutes);
+    let window_end: DateTime<Utc> = Utc::now().duration_trunc(window)?;
+    let window_start = window_end - window;
+    let baseline_start = window_start - ChronoDuration::hours(config.baseline_hours);
+    let baseline_windows = config.baseline_hours * 60 / config.window_minutes;
+
+    let candidates = sqlx::query_as::<_, SongVelocity>(
+        r#"
+        WITH current AS (
+            SELECT p.song_id,
+                   COUNT(*) AS plays,
+                   COUNT(*) FILTER (WHERE NOT EXISTS (
+                       SELECT 1 FROM plays_with_archive older
+                       WHERE older.listener_address = p.listener_address
+                         AND older.timestamp < $2 - make_interval(days => $5)
+                   )) AS new_listener_plays
+            FROM plays p
+            WHERE p.timestamp >= $2 AND p.timestamp < $3
+              AND p.song_id IS NOT NULL
+            GROUP BY p.song_id
+            HAVING COUNT(*) >= $6
+        ),
+        buckets AS (
+            SELECT p.song_id,
+                   FLOOR(EXTRACT(EPOCH FROM ($2 - p.timestamp)) / ($4 * 60)) AS bucket,
+                   COUNT(*) AS plays
+            FROM plays p
+            JOIN current c ON c.song_id = p.song_id
+            WHERE p.timestamp >= $1 AND p.timestamp < $2
+            GROUP BY p.song_id, bucket
+        )
+        SELECT c.song_id, c.plays, c.new_listener_plays,
+               COALESCE(SUM(b.plays), 0)::bigint AS baseline_total,
+               COALESCE(SUM(b.plays * b.plays), 0)::bigint AS baseline_sum_squares
+        FROM current c
+        LEFT JOIN buckets b ON b.song_id = c.song_id
+        WHERE NOT EXISTS (
+            SELECT 1 FROM play_anomalies a WHERE a.song_id = c.song_id AND a.status = 'open'
+        )
+        GROUP BY c.song_id, c.plays, c.new_listener_plays
+        "#,
+    )
+    .bind(baseline_start)
+    .bind(window_start)
+    .bind(window_end)
+    .bind(config.window_minutes as f64)
+    .bind(config.new_listener_days as i32)
+    .bind(config.min_plays)
+    .fetch_all(pool)
+    .await?;
+
+    let mut flagged = 0;
+    for song in candidates {
+        let (mean, stddev) =
+            baseline_stats(song.baseline_total, song.baseline_sum_squares, baseline_windows);
+        // A flat baseline has no spread; treat it as at least one play
+        let z_score = (song.plays as f64 - mean) / stddev.max(1.0);
+        let new_share = song.new_listener_plays as f64 / song.plays as f64;
+        if z_score < config.z_threshold || new_share < config.new_listener_share {
+            continue;
+        }
+
+        let mut tx = pool.begin().await?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO play_anomalies (
+                song_id, window_start, window_end, plays, baseline_mean, baseline_stddev,
+                z_score, new_listener_share, earnings_paused
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(song.song_id)
+        .bind(window_start)
+        .bind(window_end)
+        .bind(song.plays)
+        .bind(mean)
+        .bind(stddev)
+        .bind(z_score)
+        .bind(new_share)
+        .bind(config.pause_earnings)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if inserted > 0 && config.pause_earnings {
+            sqlx::query("UPDATE songs SET earnings_paused = TRUE WHERE id = $1")
+                .bind(song.song_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        if inserted > 0 {
+            warn!(
+                "Flagged song {}: {} plays vs baseline {:.1} ± {:.1} (z = {:.1}), {:.0}% new listeners",
+                song.song_id,
+                song.plays,
+                mean,
+                stddev,
+                z_score,
+                new_share * 100.0
+            );
+            flagged += 1;
+        }
+    }
+
+    Ok(flagged)
+}
+
+/// Run detection on the configured interval until shutdown
+pub fn spawn_anomaly_detector(
+    config: AnomalyConfig,
+    pool: PgPool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while !*shutdown.borrow() {
+            if let Err(e) = detect(&pool, &config).await {
+                error!("Play anomaly detection failed: {:?}", e);
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(config.interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_counts_empty_windows() {
+        // 10 plays in one of four windows: mean 2.5, spread from the zeros
+        let (mean, stddev) = baseline_stats(10, 100, 4);
+        assert_eq!(mean, 2.5);
+        assert!((stddev - 18.75f64.sqrt()).abs() < 1e-9);
+
+        // No history at all
+        assert_eq!(baseline_stats(0, 0, 168), (0.0, 0.0));
+    }
+}
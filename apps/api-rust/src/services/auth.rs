@@ -0,0 +1,184 @@
+//! Auth Service - Wallet sign-in and session tokens
+//!
+//! SIWE-style login: the client requests a nonce, signs the returned
+//! message with its wallet, and exchanges the signature for a
+//! short-lived JWT bound to the recovered address.
+
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, Signature};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// How long a sign-in nonce stays valid (seconds)
+pub const NONCE_TTL_SECS: u64 = 300;
+
+/// Default session token lifetime (seconds)
+const DEFAULT_TOKEN_TTL_SECS: u64 = 3600;
+
+/// Default download link lifetime (seconds)
+const DEFAULT_DOWNLOAD_TTL_SECS: u64 = 3600;
+
+/// Audience of download tokens, so they are never accepted as sessions
+const DOWNLOAD_AUDIENCE: &str = "download";
+
+/// JWT claims for an authenticated wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// Lowercase 0x-prefixed address
+    pub sub: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+/// JWT claims for a signed download link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadClaims {
+    /// Listener the link was issued to
+    pub sub: String,
+    /// Content the link unlocks
+    pub cid: String,
+    pub aud: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+/// Wallet sign-in and token issuing
+pub struct AuthService {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    token_ttl_secs: u64,
+    download_ttl_secs: u64,
+    domain: String,
+    chain_id: u64,
+    /// Lowercase addresses granted the admin role
+    admins: HashSet<String>,
+}
+
+impl AuthService {
+    pub fn new(secret: &[u8], domain: &str, chain_id: u64) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            token_ttl_secs: DEFAULT_TOKEN_TTL_SECS,
+            download_ttl_secs: DEFAULT_DOWNLOAD_TTL_SECS,
+            domain: domain.to_string(),
+            chain_id,
+            admins: HashSet::new(),
+        }
+    }
+
+    /// Configure from `JWT_SECRET`, `AUTH_DOMAIN`, `CHAIN_ID`, `JWT_TTL_SECS`,
+    /// `DOWNLOAD_LINK_TTL_SECS` and `ADMIN_ADDRESSES` (comma-separated)
+    pub fn from_env() -> Self {
+        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| {
+            tracing::warn!("JWT_SECRET not set: using a random secret (sessions reset on restart)");
+            format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+        });
+        let domain = std::env::var("AUTH_DOMAIN").unwrap_or_else(|_| "localhost:3000".into());
+        let chain_id = std::env::var("CHAIN_ID")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(100);
+
+        let mut service = Self::new(secret.as_bytes(), &domain, chain_id);
+        if let Some(ttl) = std::env::var("JWT_TTL_SECS").ok().and_then(|s| s.parse().ok()) {
+            service.token_ttl_secs = ttl;
+        }
+        if let Some(ttl) = std::env::var("DOWNLOAD_LINK_TTL_SECS").ok().and_then(|s| s.parse().ok()) {
+            service.download_ttl_secs = ttl;
+        }
+        service.admins = std::env::var("ADMIN_ADDRESSES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|a| a.trim().to_lowercase())
+            .filter(|a| !a.is_empty())
+            .collect();
+        service
+    }
+
+    pub fn token_ttl_secs(&self) -> u64 {
+        self.token_ttl_secs
+    }
+
+    /// Whether an address holds the admin role
+    pub fn is_admin(&self, address: &str) -> bool {
+        self.admins.contains(&address.to_lowercase())
+    }
+
+    /// Build the message the wallet is asked to sign
+    pub fn sign_in_message(&self, address: &str, nonce: &str) -> String {
+        format!(
+            "{domain} wants you to sign in with your Ethereum account:\n\
+             {address}\n\n\
+             Sign in to Mycelix Music\n\n\
+             URI: https://{domain}\n\
+             Version: 1\n\
+             Chain ID: {chain_id}\n\
+             Nonce: {nonce}\n\
+             Issued At: {issued_at}",
+            domain = self.domain,
+            address = address,
+            chain_id = self.chain_id,
+            nonce = nonce,
+            issued_at = chrono::Utc::now().to_rfc3339(),
+        )
+    }
+
+    /// Recover the signer of an EIP-191 personal message
+    pub fn recover_signer(message: &str, signature: &str) -> Result<Address> {
+        let signature: Signature = signature.parse()?;
+        Ok(signature.recover(message)?)
+    }
+
+    /// Issue a session token for an address
+    pub fn issue_token(&self, address: &Address) -> Result<(String, u64)> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let claims = Claims {
+            sub: normalize_address(address),
+            iat: now,
+            exp: now + self.token_ttl_secs,
+        };
+        let token = encode(&Header::default(), &claims, &self.encoding_key)?;
+        Ok((token, claims.exp))
+    }
+
+    /// Verify a session token and return its claims
+    pub fn verify_token(&self, token: &str) -> Result<Claims> {
+        decode::<Claims>(token, &self.decoding_key, &Validation::default())
+            .map(|data| data.claims)
+            .map_err(|e| anyhow!("Invalid session token: {}", e))
+    }
+
+    /// Issue a time-limited download token for one CID
+    pub fn issue_download_token(&self, listener: &str, cid: &str) -> Result<(String, u64)> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let claims = DownloadClaims {
+            sub: listener.to_lowercase(),
+            cid: cid.to_string(),
+            aud: DOWNLOAD_AUDIENCE.to_string(),
+            iat: now,
+            exp: now + self.download_ttl_secs,
+        };
+        let token = encode(&Header::default(), &claims, &self.encoding_key)?;
+        Ok((token, claims.exp))
+    }
+
+    /// Verify a download token for a CID and return its claims
+    pub fn verify_download_token(&self, token: &str, cid: &str) -> Result<DownloadClaims> {
+        let mut validation = Validation::default();
+        validation.set_audience(&[DOWNLOAD_AUDIENCE]);
+        let claims = decode::<DownloadClaims>(token, &self.decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| anyhow!("Invalid download token: {}", e))?;
+        if claims.cid != cid {
+            return Err(anyhow!("Download token is for different content"));
+        }
+        Ok(claims)
+    }
+}
+
+/// Canonical lowercase 0x-prefixed form used for comparisons
+pub fn normalize_address(address: &Address) -> String {
+    format!("{:?}", address)
+}
@@ -1,16 +1,62 @@
 //! Blockchain Service - Smart contract interactions
 //!
 //! Handles all interactions with the EconomicStrategyRouter
-//! and payment processing on Gnosis Chain.
+//! and payment processing on Gnosis Chain. Settlement batches are
+//! submitted to their target chain's verifier, through that chain's RPC
+//! and the settler wallet.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::services::settlement::SettlementPayload;
+
+/// Settler wallet on one chain's RPC
+type SettlementClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// Event a settlement verifier emits for each batch it pays out
+const BATCH_SETTLED_EVENT: &str = "BatchSettled(bytes32,address,uint256,bytes32)";
+
+/// A `BatchSettled` event decoded from a transaction receipt
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchSettlement {
+    /// keccak256 of the batch's action hash (see `BatchPayout`)
+    pub batch_id: H256,
+    pub recipient: Address,
+    pub amount: U256,
+    pub merkle_root: H256,
+}
+
+/// A settlement transaction signed for its chain but not yet broadcast
+///
+/// Its hash is fixed once signed, so it can be recorded before sending and
+/// the same bytes resent if the send's outcome is unknown.
+#[derive(Debug, Clone)]
+pub struct SignedSettlement {
+    pub tx_hash: H256,
+    /// RLP-encoded signed transaction
+    pub raw: Bytes,
+    /// Settler wallet nonce the transaction spends
+    pub nonce: u64,
+}
+
+/// The parts of a mined settlement transaction needed to check a payout
+#[derive(Debug, Clone)]
+pub struct SettlementReceipt {
+    pub succeeded: bool,
+    pub block_number: Option<u64>,
+    /// Settlements the verifier emitted in the transaction
+    pub settlements: Vec<BatchSettlement>,
+}
+
 /// Blockchain service for contract interactions
 pub struct BlockchainService {
     provider: Arc<Provider<Http>>,
     router_address: Address,
+    /// Settlement submission clients by chain id
+    settlement_clients: HashMap<u64, Arc<SettlementClient>>,
 }
 
 impl BlockchainService {
@@ -21,9 +67,157 @@ impl BlockchainService {
         Ok(Self {
             provider: Arc::new(provider),
             router_address,
+            settlement_clients: HashMap::new(),
         })
     }
 
+    /// Enable settlement submission on each `chain_id=rpc_url` in a
+    /// comma-separated list, signing with the settler's private key
+    pub fn with_settlement_chains(mut self, rpc_urls: &str, signer_key: &str) -> Result<Self> {
+        let wallet: LocalWallet = signer_key.parse()?;
+        for entry in rpc_urls.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (chain_id, rpc_url) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected chain_id=rpc_url, got {}", entry))?;
+            let chain_id: u64 = chain_id.trim().parse()?;
+            let provider = Provider::<Http>::try_from(rpc_url.trim())?;
+            let client = SignerMiddleware::new(provider, wallet.clone().with_chain_id(chain_id));
+            self.settlement_clients.insert(chain_id, Arc::new(client));
+        }
+        Ok(self)
+    }
+
+    /// Sign a settlement payload and the transaction submitting it to its
+    /// chain's verifier, without sending anything
+    pub async fn sign_settlement(&self, payload: &SettlementPayload) -> Result<SignedSettlement> {
+        let client = self.settlement_client(payload.chain_id)?;
+
+        // The digest already carries the target's signature format
+        let signature = client.signer().sign_hash(payload.digest)?;
+        let mut tx: TypedTransaction = TransactionRequest::new()
+            .to(payload.verifier)
+            .data(payload.calldata(&signature.to_vec()))
+            .into();
+        client.fill_transaction(&mut tx, None).await?;
+        let nonce = tx
+            .nonce()
+            .copied()
+            .ok_or_else(|| anyhow!("Settlement transaction has no nonce"))?;
+        let nonce =
+            u64::try_from(nonce).map_err(|_| anyhow!("Nonce {} doesn't fit in u64", nonce))?;
+
+        let tx_signature = client.signer().sign_transaction(&tx).await?;
+        Ok(SignedSettlement {
+            tx_hash: tx.hash(&tx_signature),
+            raw: tx.rlp_signed(&tx_signature),
+            nonce,
+        })
+    }
+
+    /// Broadcast a signed settlement transaction (again, if need be: a
+    /// resend of the same bytes can't pay twice)
+    pub async fn broadcast_settlement(&self, chain_id: u64, raw: Bytes) -> Result<H256> {
+        let pending = self.settlement_client(chain_id)?.send_raw_transaction(raw).await?;
+        Ok(pending.tx_hash())
+    }
+
+    /// Whether the settler wallet has mined a transaction with `nonce` on
+    /// a chain; if so, a signed settlement with that nonce that isn't
+    /// itself mined never will be
+    pub async fn settlement_nonce_spent(&self, chain_id: u64, nonce: u64) -> Result<bool> {
+        let client = self.settlement_client(chain_id)?;
+        let mined = client
+            .get_transaction_count(client.address(), Some(BlockNumber::Latest.into()))
+            .await?;
+        Ok(mined > U256::from(nonce))
+    }
+
+    fn settlement_client(&self, chain_id: u64) -> Result<&Arc<SettlementClient>> {
+        self.settlement_clients
+            .get(&chain_id)
+            .ok_or_else(|| anyhow!("No settlement RPC configured for chain {}", chain_id))
+    }
+
+    /// Fetch a settlement transaction's receipt from its target chain and
+    /// decode the `BatchSettled` events `verifier` emitted; None if the
+    /// transaction isn't mined (or doesn't exist)
+    ///
+    /// Uses the chain's settlement RPC when configured, else `RPC_URL`.
+    pub async fn get_settlement_receipt(
+        &self,
+        chain_id: u64,
+        verifier: Address,
+        tx_hash: H256,
+    ) -> Result<Option<SettlementReceipt>> {
+        let receipt = match self.settlement_clients.get(&chain_id) {
+            Some(client) => client.get_transaction_receipt(tx_hash).await?,
+            None => self.provider.get_transaction_receipt(tx_hash).await?,
+        };
+        let receipt = match receipt {
+            Some(receipt) => receipt,
+            None => return Ok(None),
+        };
+
+        let settlements = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == verifier)
+            .filter_map(decode_batch_settled)
+            .collect();
+
+        Ok(Some(SettlementReceipt {
+            succeeded: receipt.status == Some(U64::one()),
+            block_number: receipt.block_number.map(|n| n.as_u64()),
+            settlements,
+        }))
+    }
+
+    /// Router's current protocol fee
+    pub async fn get_protocol_fee_bps(&self) -> Result<u32> {
+        let output = self
+            .call(self.router_address, ethers::utils::id("protocolFeeBps()").to_vec())
+            .await?;
+        let fee = decode_word(&output)?;
+        u32::try_from(fee).map_err(|_| anyhow!("Protocol fee {} doesn't fit in u32", fee))
+    }
+
+    /// Contract registered for a strategy id (e.g. "pay-per-stream-v1");
+    /// None if the router doesn't know it
+    pub async fn get_registered_strategy(&self, strategy_id: &str) -> Result<Option<Address>> {
+        let mut data = ethers::utils::id("registeredStrategies(bytes32)").to_vec();
+        data.extend_from_slice(&ethers::utils::keccak256(strategy_id.as_bytes()));
+
+        let output = self.call(self.router_address, data).await?;
+        let word = decode_word(&output)?;
+        if word.is_zero() {
+            return Ok(None);
+        }
+        let mut bytes = [0u8; 32];
+        word.to_big_endian(&mut bytes);
+        Ok(Some(Address::from_slice(&bytes[12..])))
+    }
+
+    /// Whether a contract is deployed at an address
+    pub async fn has_code(&self, address: Address) -> Result<bool> {
+        Ok(!self.provider.get_code(address, None).await?.is_empty())
+    }
+
+    /// A strategy's minimum stream payment (wei)
+    pub async fn get_min_payment(&self, strategy: Address) -> Result<U256> {
+        let mut data = ethers::utils::id("getMinPayment(bytes32,uint8)").to_vec();
+        // Strategy-wide minimum: zero song id, PaymentType::Stream
+        data.extend_from_slice(&[0u8; 64]);
+
+        let output = self.call(strategy, data).await?;
+        decode_word(&output)
+    }
+
+    /// Read-only contract call
+    async fn call(&self, to: Address, data: Vec<u8>) -> Result<Bytes> {
+        let tx: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+        Ok(self.provider.call(&tx, None).await?)
+    }
+
     /// Get current block number
     pub async fn get_block_number(&self) -> Result<u64> {
         Ok(self.provider.get_block_number().await?.as_u64())
@@ -75,3 +269,27 @@ impl From<PaymentType> for u8 {
         pt as u8
     }
 }
+
+/// First 32-byte word of a call's output
+fn decode_word(output: &Bytes) -> Result<U256> {
+    if output.len() < 32 {
+        return Err(anyhow::anyhow!("Short call output ({} bytes)", output.len()));
+    }
+    Ok(U256::from_big_endian(&output[0..32]))
+}
+
+/// Decode a verifier's `BatchSettled` event
+///
+/// topics: [event_sig, batch_id, recipient]; data: [amount, merkle_root]
+fn decode_batch_settled(log: &Log) -> Option<BatchSettlement> {
+    let signature = H256(ethers::utils::keccak256(BATCH_SETTLED_EVENT));
+    if log.topics.len() != 3 || log.topics[0] != signature || log.data.0.len() < 64 {
+        return None;
+    }
+    Some(BatchSettlement {
+        batch_id: log.topics[1],
+        recipient: Address::from_slice(&log.topics[2].0[12..]),
+        amount: U256::from_big_endian(&log.data.0[0..32]),
+        merkle_root: H256::from_slice(&log.data.0[32..64]),
+    })
+}
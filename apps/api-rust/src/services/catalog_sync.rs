@@ -0,0 +1,108 @@
+//! Catalog Sync - Link API songs to their catalog entries on the DHT
+//!
+//! Artists publish a song to the catalog from their own cell, under the
+//! `song_hash` the API gave it, so a song's `dht_action_hash` can only be
+//! filled in once the catalog knows that hash. Song creation tries right
+//! away; a background pass backfills songs still missing one, least
+//! recently checked first.
+
+use anyhow::Result;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, warn};
+
+use crate::services::holochain::HolochainService;
+
+/// Songs checked per backfill pass
+const BACKFILL_BATCH_SIZE: i64 = 100;
+
+/// Resolve a song's catalog action hash by its `song_hash` and store it
+///
+/// None if the catalog doesn't have the song (yet).
+pub async fn link_song(
+    pool: &PgPool,
+    holochain: &HolochainService,
+    song_hash: &str,
+) -> Result<Option<String>> {
+    let resolved: Option<String> = holochain
+        .call_zome("catalog", "get_song_hash_by_content_hash", song_hash)
+        .await?;
+
+    match &resolved {
+        Some(action_hash) => {
+            sqlx::query(
+                r#"
+                UPDATE songs SET dht_action_hash = $1, dht_checked_at = NOW()
+                WHERE song_hash = $2 AND dht_action_hash IS NULL
+                "#,
+            )
+            .bind(action_hash)
+            .bind(song_hash)
+            .execute(pool)
+            .await?;
+        }
+        None => {
+            sqlx::query("UPDATE songs SET dht_checked_at = NOW() WHERE song_hash = $1")
+                .bind(song_hash)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(resolved)
+}
+
+/// Check the songs without a catalog hash that were checked longest ago;
+/// returns how many were linked
+async fn backfill(pool: &PgPool, holochain: &HolochainService) -> Result<usize> {
+    let song_hashes = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT song_hash FROM songs
+        WHERE dht_action_hash IS NULL
+        ORDER BY dht_checked_at NULLS FIRST, created_at
+        LIMIT $1
+        "#,
+    )
+    .bind(BACKFILL_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut linked = 0;
+    for song_hash in &song_hashes {
+        match link_song(pool, holochain, song_hash).await {
+            Ok(Some(_)) => linked += 1,
+            Ok(None) => {}
+            Err(e) => warn!("Failed to link song {} to the catalog: {}", song_hash, e),
+        }
+    }
+    Ok(linked)
+}
+
+/// Run backfill passes every `interval_secs` until shutdown
+pub fn spawn_catalog_backfill(
+    interval_secs: u64,
+    pool: PgPool,
+    holochain: Arc<HolochainService>,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while !*shutdown.borrow() {
+            match backfill(&pool, &holochain).await {
+                Ok(0) => {}
+                Ok(linked) => info!("Linked {} songs to their catalog entries", linked),
+                Err(e) => error!("Catalog backfill pass failed: {:?}", e),
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
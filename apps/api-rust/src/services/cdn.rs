@@ -0,0 +1,412 @@
+//! CDN Routing - Stream through community CDN nodes with failover
+//!
+//! Ranks the trust zome's CDN nodes (`get_all_cdn_nodes`) for the region
+//! by PoGQ score and streams from the first that answers. Only gateways on
+//! `CDN_TRUSTED_GATEWAYS` are used: a node's `gateway_url` is
+//! self-reported, and content fetched from it isn't checked against its
+//! CID, so an unlisted gateway could point the API at internal hosts or
+//! serve other bytes. If a node errors or stalls
+//! mid-transfer, the rest of the content is requested from the next node
+//! with a `Range` starting where the last one stopped. Each outcome is
+//! sent back as a `ServiceQualityReport`, so real streams feed CDN
+//! reputation. Without a conductor or usable nodes, callers fall back to
+//! the IPFS gateway pool.
+
+use anyhow::{anyhow, Result};
+use axum::body::Bytes;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::services::holochain::HolochainService;
+
+/// Default wait for a node's headers, or for the next chunk mid-stream (ms)
+const DEFAULT_STALL_TIMEOUT_MS: u64 = 5000;
+
+/// Region asked for when `CDN_REGION` is unset (every region)
+const DEFAULT_REGION: &str = "global";
+
+/// Nodes tried per stream before falling back to the gateway pool
+const MAX_CANDIDATES: usize = 5;
+
+/// The fields of `trust_integrity::CdnNodeReputation` routing needs
+#[derive(Debug, Clone, Deserialize)]
+pub struct CdnNode {
+    /// Node agent key
+    pub node: String,
+    pub region: String,
+    pub pogq_score: f64,
+    pub gateway_url: Option<String>,
+}
+
+/// A node whose gateway passed `trusted_gateway`
+#[derive(Debug, Clone)]
+struct Candidate {
+    node: String,
+    gateway: reqwest::Url,
+}
+
+/// A node's gateway URL, if it is an https URL on a trusted host
+///
+/// `trusted` holds lowercase host names (optionally `host:port`).
+pub fn trusted_gateway(gateway_url: &str, trusted: &[String]) -> Option<reqwest::Url> {
+    let url = reqwest::Url::parse(gateway_url).ok()?;
+    if url.scheme() != "https" || !url.username().is_empty() || url.password().is_some() {
+        return None;
+    }
+    let host = url.host_str()?.to_ascii_lowercase();
+    let authority = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host,
+    };
+    trusted.contains(&authority).then_some(url)
+}
+
+/// Mirror of `trust_coordinator::SubmitQualityReportInput`
+#[derive(Debug, Serialize)]
+struct QualityReport<'a> {
+    node: &'a str,
+    song_hash: &'a str,
+    latency_ms: u32,
+    success: bool,
+    error_code: Option<String>,
+}
+
+/// CDN node selection and outcome reporting
+pub struct CdnRouter {
+    holochain: Arc<HolochainService>,
+    http: reqwest::Client,
+    region: String,
+    /// Gateway hosts nodes may be reached through
+    trusted_gateways: Vec<String>,
+    stall_timeout: Duration,
+}
+
+impl CdnRouter {
+    /// Configure from `CDN_REGION`, `CDN_TRUSTED_GATEWAYS` and
+    /// `CDN_STALL_TIMEOUT_MS`
+    pub fn from_env(holochain: Arc<HolochainService>) -> Self {
+        let region = std::env::var("CDN_REGION")
+            .ok()
+            .filter(|region| !region.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
+        let stall_timeout_ms = std::env::var("CDN_STALL_TIMEOUT_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_STALL_TIMEOUT_MS);
+        let trusted_gateways = std::env::var("CDN_TRUSTED_GATEWAYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|host| host.trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+
+        // A trusted gateway mustn't bounce requests somewhere else
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            holochain,
+            http,
+            region,
+            trusted_gateways,
+            stall_timeout: Duration::from_millis(stall_timeout_ms),
+        }
+    }
+
+    /// The region's nodes on trusted gateways, best PoGQ score first
+    ///
+    /// Nodes are filtered before taking the best `MAX_CANDIDATES`, so
+    /// unusable top-ranked nodes don't crowd out usable ones.
+    async fn candidates(&self) -> Result<Vec<Candidate>> {
+        if self.trusted_gateways.is_empty() {
+            return Ok(Vec::new());
+        }
+        let nodes: Vec<CdnNode> = self
+            .holochain
+            .call_zome("trust", "get_all_cdn_nodes", &())
+            .await?;
+
+        let mut usable: Vec<(f64, Candidate)> = nodes
+            .into_iter()
+            .filter(|n| self.region == DEFAULT_REGION || n.region == self.region)
+            .filter_map(|n| {
+                let gateway = trusted_gateway(n.gateway_url.as_deref()?, &self.trusted_gateways)?;
+                Some((n.pogq_score, Candidate { node: n.node, gateway }))
+            })
+            .collect();
+        usable.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(usable.into_iter().take(MAX_CANDIDATES).map(|(_, c)| c).collect())
+    }
+
+    /// Request `cid` from one node, waiting at most the stall timeout for headers
+    async fn request(
+        &self,
+        node: &Candidate,
+        cid: &str,
+        range: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let gateway = node.gateway.as_str().trim_end_matches('/');
+        let mut request = self.http.get(format!("{}/{}", gateway, cid));
+        if let Some(range) = range {
+            request = request.header(reqwest::header::RANGE, range);
+        }
+
+        let response = tokio::time::timeout(self.stall_timeout, request.send())
+            .await
+            .map_err(|_| anyhow!("timed out"))??;
+        if !response.status().is_success() {
+            return Err(anyhow!("returned {}", response.status()));
+        }
+        Ok(response)
+    }
+
+    /// Send a quality report for a node (best effort, in the background)
+    fn report(&self, node: &str, song_hash: Option<&str>, latency: Duration, error: Option<&str>) {
+        // Reports are about a catalog song; CIDs unknown to the DHT can't be reported
+        let song_hash = match song_hash {
+            Some(song_hash) => song_hash.to_string(),
+            None => return,
+        };
+        let holochain = self.holochain.clone();
+        let node = node.to_string();
+        let error_code = error.map(str::to_string);
+        let latency_ms = latency.as_millis().min(u32::MAX as u128) as u32;
+
+        tokio::spawn(async move {
+            let report = QualityReport {
+                node: &node,
+                song_hash: &song_hash,
+                latency_ms,
+                success: error_code.is_none(),
+                error_code,
+            };
+            let result: Result<serde_json::Value> = holochain
+                .call_zome("trust", "submit_quality_report", &report)
+                .await;
+            if let Err(e) = result {
+                tracing::debug!("Failed to report CDN node {}: {}", node, e);
+            }
+        });
+    }
+
+    /// Start streaming `cid` from the best responding node
+    ///
+    /// Returns None if there are no usable nodes or none answered, so the
+    /// caller can fall back to the gateway pool.
+    pub async fn open(
+        self: &Arc<Self>,
+        cid: &str,
+        range: Option<&str>,
+        song_hash: Option<String>,
+    ) -> Option<CdnStream> {
+        let candidates = match self.candidates().await {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                tracing::warn!("Failed to get CDN nodes for {}: {}", self.region, e);
+                return None;
+            }
+        };
+
+        let mut stream = CdnStream {
+            router: self.clone(),
+            cid: cid.to_string(),
+            range: range.map(str::to_string),
+            song_hash,
+            candidates,
+            next: 0,
+            node: None,
+            response: None,
+            body: None,
+            sent: 0,
+            started: Instant::now(),
+            done: false,
+        };
+        stream.connect(range).await.then_some(stream)
+    }
+}
+
+/// A stream from CDN nodes that moves to the next node on failure
+pub struct CdnStream {
+    router: Arc<CdnRouter>,
+    cid: String,
+    /// Client's original `Range`, if any
+    range: Option<String>,
+    song_hash: Option<String>,
+    candidates: Vec<Candidate>,
+    /// Index of the next candidate to try
+    next: usize,
+    /// Node currently streaming
+    node: Option<String>,
+    /// First response, until the caller takes its headers
+    response: Option<reqwest::Response>,
+    body: Option<BoxStream<'static, reqwest::Result<Bytes>>>,
+    /// Body bytes forwarded so far
+    sent: u64,
+    started: Instant,
+    done: bool,
+}
+
+impl CdnStream {
+    /// Try candidates in order until one answers; failures are reported
+    async fn connect(&mut self, range: Option<&str>) -> bool {
+        let resuming = self.sent > 0;
+        while let Some(candidate) = self.candidates.get(self.next).cloned() {
+            self.next += 1;
+            let started = Instant::now();
+            match self.router.request(&candidate, &self.cid, range).await {
+                // A resumed request must continue the body, not restart it
+                Ok(response) if resuming && response.status() != reqwest::StatusCode::PARTIAL_CONTENT => {
+                    self.fail(&candidate.node, started, "range_unsupported");
+                }
+                Ok(response) => {
+                    self.node = Some(candidate.node);
+                    self.started = started;
+                    if resuming {
+                        self.body = Some(response.bytes_stream().boxed());
+                    } else {
+                        self.response = Some(response);
+                    }
+                    return true;
+                }
+                Err(e) => {
+                    tracing::warn!("CDN node {} failed for {}: {}", candidate.node, self.cid, e);
+                    self.fail(&candidate.node, started, "request_failed");
+                }
+            }
+        }
+        false
+    }
+
+    fn fail(&self, node: &str, started: Instant, error: &str) {
+        self.router
+            .report(node, self.song_hash.as_deref(), started.elapsed(), Some(error));
+    }
+
+    /// Status and headers of the first response
+    pub fn response(&self) -> Option<&reqwest::Response> {
+        self.response.as_ref()
+    }
+
+    /// Stream the body, failing over between nodes
+    pub fn into_body(mut self) -> impl Stream<Item = std::io::Result<Bytes>> {
+        if let Some(response) = self.response.take() {
+            self.body = Some(response.bytes_stream().boxed());
+        }
+        futures::stream::unfold(self, |mut stream| async move {
+            let item = stream.next_chunk().await?;
+            Some((item, stream))
+        })
+    }
+
+    async fn next_chunk(&mut self) -> Option<std::io::Result<Bytes>> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let body = self.body.as_mut()?;
+            let error = match tokio::time::timeout(self.router.stall_timeout, body.next()).await {
+                Ok(Some(Ok(chunk))) => {
+                    self.sent += chunk.len() as u64;
+                    return Some(Ok(chunk));
+                }
+                Ok(None) => {
+                    if let Some(node) = self.node.take() {
+                        self.router
+                            .report(&node, self.song_hash.as_deref(), self.started.elapsed(), None);
+                    }
+                    self.done = true;
+                    return None;
+                }
+                Ok(Some(Err(e))) => format!("{}", e),
+                Err(_) => "stalled".to_string(),
+            };
+
+            let node = self.node.take().unwrap_or_default();
+            tracing::warn!("CDN node {} failed mid-stream for {}: {}", node, self.cid, error);
+            self.fail(&node, self.started, "stream_interrupted");
+
+            // Pick up where the failed node stopped
+            let resumed = match resume_range(self.range.as_deref(), self.sent) {
+                Some(range) => self.connect(Some(&range)).await,
+                None => false,
+            };
+            if !resumed {
+                self.done = true;
+                return Some(Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    format!("all CDN nodes failed for {}", self.cid),
+                )));
+            }
+        }
+    }
+}
+
+/// `Range` requesting the rest of `original` after `sent` bytes
+///
+/// Only `bytes=start-[end]` ranges can be resumed; suffix and multi-part
+/// ranges return None.
+pub fn resume_range(original: Option<&str>, sent: u64) -> Option<String> {
+    let (start, end) = match original {
+        None => (0, None),
+        Some(range) => {
+            let spec = range.trim().strip_prefix("bytes=")?;
+            if spec.contains(',') {
+                return None;
+            }
+            let (start, end) = spec.split_once('-')?;
+            let start: u64 = start.trim().parse().ok()?;
+            let end = match end.trim() {
+                "" => None,
+                end => Some(end.parse::<u64>().ok()?),
+            };
+            (start, end)
+        }
+    };
+
+    let resume_at = start.checked_add(sent)?;
+    match end {
+        Some(end) if resume_at > end => None,
+        Some(end) => Some(format!("bytes={}-{}", resume_at, end)),
+        None => Some(format!("bytes={}-", resume_at)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_https_gateways_on_trusted_hosts_are_used() {
+        let trusted = vec!["cdn.example.org".to_string(), "edge.example.org:8443".to_string()];
+        assert!(trusted_gateway("https://cdn.example.org/ipfs", &trusted).is_some());
+        assert!(trusted_gateway("https://CDN.example.org", &trusted).is_some());
+        assert!(trusted_gateway("https://edge.example.org:8443/ipfs", &trusted).is_some());
+
+        assert!(trusted_gateway("http://cdn.example.org/ipfs", &trusted).is_none());
+        assert!(trusted_gateway("https://edge.example.org/ipfs", &trusted).is_none());
+        assert!(trusted_gateway("https://169.254.169.254/latest", &trusted).is_none());
+        assert!(trusted_gateway("https://localhost:5001", &trusted).is_none());
+        assert!(trusted_gateway("https://user@cdn.example.org", &trusted).is_none());
+        assert!(trusted_gateway("file:///etc/passwd", &trusted).is_none());
+    }
+
+    #[test]
+    fn resumes_after_the_bytes_already_sent() {
+        assert_eq!(resume_range(None, 1000).as_deref(), Some("bytes=1000-"));
+        assert_eq!(resume_range(Some("bytes=500-"), 100).as_deref(), Some("bytes=600-"));
+        assert_eq!(resume_range(Some("bytes=0-999"), 400).as_deref(), Some("bytes=400-999"));
+        // Everything was sent
+        assert_eq!(resume_range(Some("bytes=0-999"), 1000), None);
+    }
+
+    #[test]
+    fn suffix_and_multipart_ranges_cannot_resume() {
+        assert_eq!(resume_range(Some("bytes=-500"), 10), None);
+        assert_eq!(resume_range(Some("bytes=0-10,20-30"), 5), None);
+        assert_eq!(resume_range(Some("items=0-10"), 5), None);
+    }
+}
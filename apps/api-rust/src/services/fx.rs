@@ -0,0 +1,183 @@
+//! FX Rates - xDAI to fiat for display and accounting
+//!
+//! Wei of xDAI stays the canonical unit everywhere; fiat is only a view.
+//! A background job records the rate for each configured currency in
+//! `fx_rates`, and the payments projection snapshots the rates in force
+//! when a payment was made into `payment_fx_rates`, so historical figures
+//! never move with the market.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+/// The native unit; its rate is always 1
+pub const NATIVE_CURRENCY: &str = "XDAI";
+
+/// Where and how often rates are fetched
+#[derive(Debug, Clone)]
+pub struct FxConfig {
+    pub interval_secs: u64,
+    /// CoinGecko-style `simple/price` endpoint
+    pub url: String,
+    /// Price-feed id of the native token
+    pub asset_id: String,
+    /// ISO 4217 codes, upper case
+    pub currencies: Vec<String>,
+}
+
+impl FxConfig {
+    /// Configure from `FX_REFRESH_SECS`, `FX_RATES_URL`, `FX_ASSET_ID` and
+    /// `FX_CURRENCIES`
+    pub fn from_env() -> Self {
+        let interval_secs = std::env::var("FX_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600);
+        let url = std::env::var("FX_RATES_URL")
+            .unwrap_or_else(|_| "https://api.coingecko.com/api/v3/simple/price".into());
+        let asset_id = std::env::var("FX_ASSET_ID").unwrap_or_else(|_| "xdai".into());
+        let currencies = std::env::var("FX_CURRENCIES")
+            .unwrap_or_else(|_| "USD,EUR,GBP".into())
+            .split(',')
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .collect();
+
+        Self {
+            interval_secs,
+            url,
+            asset_id,
+            currencies,
+        }
+    }
+}
+
+/// A stored rate: units of `currency` per xDAI
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FxRate {
+    pub currency: String,
+    pub rate: f64,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Pull `{asset: {currency: rate}}` out of a price-feed response
+fn parse_rates(body: &Value, asset_id: &str, currencies: &[String]) -> Vec<(String, f64)> {
+    currencies
+        .iter()
+        .filter_map(|currency| {
+            body.get(asset_id)?
+                .get(currency.to_lowercase())?
+                .as_f64()
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| (currency.clone(), rate))
+        })
+        .collect()
+}
+
+/// Fetch current rates and store them; returns how many were recorded
+pub async fn refresh(pool: &PgPool, http: &reqwest::Client, config: &FxConfig) -> Result<usize> {
+    let body: Value = http
+        .get(&config.url)
+        .query(&[
+            ("ids", config.asset_id.clone()),
+            ("vs_currencies", config.currencies.join(",").to_lowercase()),
+        ])
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let rates = parse_rates(&body, &config.asset_id, &config.currencies);
+    if rates.is_empty() {
+        return Err(anyhow!("price feed returned no usable rates"));
+    }
+
+    let mut tx = pool.begin().await?;
+    for (currency, rate) in &rates {
+        sqlx::query("INSERT INTO fx_rates (currency, rate, fetched_at) VALUES ($1, $2, NOW())")
+            .bind(currency)
+            .bind(rate)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(rates.len())
+}
+
+/// The most recent rate for a currency (1 for xDAI itself)
+pub async fn latest_rate(pool: &PgPool, currency: &str) -> sqlx::Result<Option<FxRate>> {
+    if currency == NATIVE_CURRENCY {
+        return Ok(Some(FxRate {
+            currency: NATIVE_CURRENCY.to_string(),
+            rate: 1.0,
+            fetched_at: chrono::Utc::now(),
+        }));
+    }
+
+    sqlx::query_as::<_, FxRate>(
+        r#"
+        SELECT currency, rate, fetched_at
+        FROM fx_rates
+        WHERE currency = $1
+        ORDER BY fetched_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(currency)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Refresh rates on the configured interval until shutdown
+pub fn spawn_fx_refresh(
+    config: FxConfig,
+    pool: PgPool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let http = reqwest::Client::new();
+        while !*shutdown.borrow() {
+            match refresh(&pool, &http, &config).await {
+                Ok(count) => info!("Recorded {} FX rates", count),
+                Err(e) => error!("FX rate refresh failed: {:?}", e),
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(config.interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_configured_currencies_only() {
+        let body = serde_json::json!({
+            "xdai": { "usd": 1.001, "eur": 0.92, "jpy": 149.0, "gbp": 0.0 }
+        });
+        let currencies: Vec<String> =
+            ["USD", "EUR", "GBP", "CHF"].iter().map(|c| c.to_string()).collect();
+
+        // Zero and missing rates are skipped rather than stored
+        assert_eq!(
+            parse_rates(&body, "xdai", &currencies),
+            vec![("USD".to_string(), 1.001), ("EUR".to_string(), 0.92)]
+        );
+        assert!(parse_rates(&body, "dai", &currencies).is_empty());
+    }
+}
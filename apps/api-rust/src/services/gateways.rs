@@ -0,0 +1,210 @@
+//! Gateway Service - IPFS gateway list with failover
+//!
+//! Keeps an ordered list of public IPFS gateways, tracks per-gateway
+//! success rates in Redis, and fetches content from the most reliable
+//! gateway first, failing over to the next on error or timeout.
+
+use anyhow::{anyhow, Result};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+
+/// Default gateways when `IPFS_GATEWAYS` is not set
+const DEFAULT_GATEWAYS: &[&str] = &["https://w3s.link/ipfs", "https://ipfs.io/ipfs"];
+
+/// Default time to wait for a gateway to start responding (ms)
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Length of a reliability window (s); rates count the current and
+/// previous window, so older outcomes stop weighing on a gateway
+const STATS_WINDOW_SECS: u64 = 600;
+
+/// Redis hash holding success/failure counters for a gateway in a window
+fn stats_key(gateway: &str, window: u64) -> String {
+    format!("ipfs:gateway:{}:{}", gateway, window)
+}
+
+/// Index of the reliability window containing now
+fn current_window() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    now / STATS_WINDOW_SECS
+}
+
+/// Whether a gateway response says the gateway itself failed
+///
+/// A 404 or other 4xx is about the request or the content, not the
+/// gateway, so it fails over without counting against it.
+fn is_gateway_failure(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+}
+
+/// Ordered IPFS gateways with reliability tracking
+pub struct GatewayService {
+    gateways: Vec<String>,
+    http: reqwest::Client,
+    redis: redis::Client,
+    /// Shared by every stats read and write, opened on first use
+    conn: OnceCell<ConnectionManager>,
+    timeout: Duration,
+}
+
+impl GatewayService {
+    pub fn new(gateways: Vec<String>, redis: redis::Client, timeout: Duration) -> Self {
+        Self {
+            gateways: gateways
+                .into_iter()
+                .map(|g| g.trim_end_matches('/').to_string())
+                .collect(),
+            http: reqwest::Client::new(),
+            redis,
+            conn: OnceCell::new(),
+            timeout,
+        }
+    }
+
+    /// Configure from `IPFS_GATEWAYS` (comma-separated, in preference order,
+    /// falling back to `IPFS_GATEWAY`) and `IPFS_GATEWAY_TIMEOUT_MS`
+    pub fn from_env(redis: redis::Client) -> Self {
+        let configured = std::env::var("IPFS_GATEWAYS")
+            .or_else(|_| std::env::var("IPFS_GATEWAY"))
+            .unwrap_or_default();
+        let mut gateways: Vec<String> = configured
+            .split(',')
+            .map(str::trim)
+            .filter(|g| !g.is_empty())
+            .map(String::from)
+            .collect();
+        if gateways.is_empty() {
+            gateways = DEFAULT_GATEWAYS.iter().map(|g| g.to_string()).collect();
+        }
+
+        let timeout_ms = std::env::var("IPFS_GATEWAY_TIMEOUT_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_MS);
+
+        Self::new(gateways, redis, Duration::from_millis(timeout_ms))
+    }
+
+    /// Gateways ordered by observed success rate (config order breaks ties)
+    ///
+    /// Falls back to config order if Redis is unavailable.
+    pub async fn ranked(&self) -> Vec<String> {
+        let rates = match self.success_rates().await {
+            Ok(rates) => rates,
+            Err(e) => {
+                tracing::warn!("Failed to load gateway stats, using configured order: {}", e);
+                return self.gateways.clone();
+            }
+        };
+
+        let mut ranked = self.gateways.clone();
+        // Stable sort keeps configured order among equally reliable gateways
+        ranked.sort_by(|a, b| rates[b].total_cmp(&rates[a]));
+        ranked
+    }
+
+    /// Gateway URLs for a CID, most reliable first
+    pub async fn urls(&self, cid: &str) -> Vec<String> {
+        self.ranked()
+            .await
+            .into_iter()
+            .map(|gateway| format!("{}/{}", gateway, cid))
+            .collect()
+    }
+
+    /// Request a CID from each gateway in turn until one responds successfully
+    ///
+    /// `range` is forwarded so clients can seek. The timeout only bounds the
+    /// wait for response headers; the body is streamed by the caller.
+    pub async fn fetch(&self, cid: &str, range: Option<&str>) -> Result<reqwest::Response> {
+        for gateway in self.ranked().await {
+            let url = format!("{}/{}", gateway, cid);
+            let mut request = self.http.get(&url);
+            if let Some(range) = range {
+                request = request.header(reqwest::header::RANGE, range);
+            }
+
+            // Only server errors, timeouts and unreachable gateways count
+            // as failures; anything else just moves on to the next gateway
+            match tokio::time::timeout(self.timeout, request.send()).await {
+                Ok(Ok(response)) if response.status().is_success() => {
+                    self.record(&gateway, true).await;
+                    return Ok(response);
+                }
+                Ok(Ok(response)) => {
+                    tracing::warn!("Gateway {} returned {} for {}", gateway, response.status(), cid);
+                    if is_gateway_failure(response.status()) {
+                        self.record(&gateway, false).await;
+                    }
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Gateway {} failed for {}: {}", gateway, cid, e);
+                    if e.is_timeout() || e.is_connect() {
+                        self.record(&gateway, false).await;
+                    }
+                }
+                Err(_) => {
+                    tracing::warn!("Gateway {} timed out for {}", gateway, cid);
+                    self.record(&gateway, false).await;
+                }
+            }
+        }
+
+        Err(anyhow!("All IPFS gateways failed for {}", cid))
+    }
+
+    /// The shared Redis connection (reconnects by itself after a drop)
+    async fn connection(&self) -> Result<ConnectionManager> {
+        let conn = self
+            .conn
+            .get_or_try_init(|| self.redis.get_connection_manager())
+            .await?;
+        Ok(conn.clone())
+    }
+
+    /// Laplace-smoothed success rate per gateway over the current and
+    /// previous window (unknown gateways = 0.5)
+    async fn success_rates(&self) -> Result<HashMap<String, f64>> {
+        let mut conn = self.connection().await?;
+        let window = current_window();
+        let mut rates = HashMap::new();
+
+        for gateway in &self.gateways {
+            let (mut success, mut failure) = (0u64, 0u64);
+            for window in [window.saturating_sub(1), window] {
+                let stats: HashMap<String, u64> = conn.hgetall(stats_key(gateway, window)).await?;
+                success += stats.get("success").copied().unwrap_or(0);
+                failure += stats.get("failure").copied().unwrap_or(0);
+            }
+            let (success, failure) = (success as f64, failure as f64);
+            rates.insert(gateway.clone(), (success + 1.0) / (success + failure + 2.0));
+        }
+
+        Ok(rates)
+    }
+
+    /// Record the outcome of a gateway request (best effort)
+    ///
+    /// A window's counters expire once no rate reads them any more.
+    async fn record(&self, gateway: &str, success: bool) {
+        let field = if success { "success" } else { "failure" };
+        let key = stats_key(gateway, current_window());
+        let result: Result<()> = async {
+            let mut conn = self.connection().await?;
+            conn.hincr::<_, _, _, ()>(&key, field, 1).await?;
+            conn.expire::<_, ()>(&key, (2 * STATS_WINDOW_SECS) as i64).await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::debug!("Failed to record gateway stats for {}: {}", gateway, e);
+        }
+    }
+}
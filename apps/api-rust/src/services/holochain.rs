@@ -0,0 +1,69 @@
+//! Holochain Service - Conductor bridge
+//!
+//! Calls zome functions on the mycelix-music DNA through an HTTP
+//! gateway in front of the Holochain conductor. Used while the API
+//! migrates from the DB-centric model to the DHT as source of truth.
+
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Play statistics as returned by `plays::get_song_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SongStats {
+    pub total_plays: u64,
+    pub total_earnings: u64,
+    pub unique_listeners: u64,
+    /// Unique listeners whose best play reached the DNA's completion threshold
+    #[serde(default)]
+    pub completed_unique_listeners: u64,
+    pub avg_completion: f64,
+}
+
+/// Holochain conductor bridge
+pub struct HolochainService {
+    http: reqwest::Client,
+    gateway_url: String,
+}
+
+impl HolochainService {
+    pub fn new(gateway_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            gateway_url: gateway_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Call a zome function and decode its JSON result
+    pub async fn call_zome<I, O>(&self, zome: &str, fn_name: &str, payload: &I) -> Result<O>
+    where
+        I: Serialize + ?Sized,
+        O: DeserializeOwned,
+    {
+        let url = format!("{}/call/{}/{}", self.gateway_url, zome, fn_name);
+        let response = self.http.post(&url).json(payload).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "Zome call {}/{} failed ({}): {}",
+                zome,
+                fn_name,
+                status,
+                body
+            ));
+        }
+
+        Ok(response.json::<O>().await?)
+    }
+
+    /// Check the gateway is reachable
+    pub async fn ping(&self) -> bool {
+        self.http
+            .get(format!("{}/health", self.gateway_url))
+            .send()
+            .await
+            .map(|r| r.status().is_success())
+            .unwrap_or(false)
+    }
+}
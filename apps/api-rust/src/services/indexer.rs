@@ -3,13 +3,22 @@
 //! Listens to smart contract events and syncs them to the database.
 //! This enables the API to serve real-time payment and play data.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use ethers::prelude::*;
-use sqlx::PgPool;
-use std::sync::Arc;
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 use tracing::{error, info, warn};
 
+use super::projections::{
+    event_types, rebuild_projections, run_projections, PaymentProcessedData, SongRegisteredData,
+};
+
 /// Contract event signatures (keccak256 hashes)
 mod event_signatures {
     use ethers::types::H256;
@@ -53,6 +62,17 @@ pub struct SongRegisteredEvent {
     pub artist: Address,
 }
 
+/// Decoded event ready for the event log
+#[derive(Debug, Clone)]
+struct ChainEvent {
+    block_number: u64,
+    block_timestamp: u64,
+    tx_hash: H256,
+    log_index: u64,
+    event_type: &'static str,
+    data: serde_json::Value,
+}
+
 /// Event indexer configuration
 #[derive(Clone)]
 pub struct IndexerConfig {
@@ -61,6 +81,13 @@ pub struct IndexerConfig {
     pub start_block: u64,
     pub poll_interval_secs: u64,
     pub confirmations: u64,
+    /// Depth after which payments are reported as finalized
+    pub finality_blocks: u64,
+    /// Projections to rebuild from the event log on startup (comma-separated);
+    /// each entry runs once
+    pub rebuild_projections: String,
+    /// Average block time, for estimating lag in seconds
+    pub block_time_secs: u64,
 }
 
 impl Default for IndexerConfig {
@@ -71,6 +98,132 @@ impl Default for IndexerConfig {
             start_block: 0,
             poll_interval_secs: 12, // ~1 block on Gnosis
             confirmations: 3,
+            finality_blocks: 64,
+            rebuild_projections: String::new(),
+            block_time_secs: 5, // Gnosis
+        }
+    }
+}
+
+/// Missed polls after which a running indexer is reported as not alive
+const STALLED_AFTER_POLLS: u64 = 5;
+
+/// The indexer's most recent failure
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexerError {
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Indexer progress as reported by `GET /api/indexer/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexerStatus {
+    pub router_address: String,
+    pub last_indexed_block: Option<u64>,
+    pub chain_head: Option<u64>,
+    /// Blocks between the head and the last indexed block, including the
+    /// confirmations the indexer deliberately waits for
+    pub lag_blocks: Option<u64>,
+    /// `lag_blocks` at the configured block time
+    pub lag_seconds: Option<u64>,
+    /// The task is running and has polled recently
+    pub alive: bool,
+    /// End of the last cycle that indexed without error
+    pub last_indexed_at: Option<DateTime<Utc>>,
+    pub last_error: Option<IndexerError>,
+}
+
+#[derive(Debug, Default)]
+struct IndexerProgress {
+    running: bool,
+    /// Task start or end of the last cycle, successful or not
+    heartbeat: Option<DateTime<Utc>>,
+    last_indexed_block: Option<u64>,
+    chain_head: Option<u64>,
+    last_indexed_at: Option<DateTime<Utc>>,
+    last_error: Option<IndexerError>,
+}
+
+/// Live indexer state, updated by the task and read by the status route
+pub struct IndexerMonitor {
+    router_address: Address,
+    block_time_secs: u64,
+    stalled_after: chrono::Duration,
+    progress: Mutex<IndexerProgress>,
+}
+
+impl IndexerMonitor {
+    pub fn new(config: &IndexerConfig) -> Self {
+        let stalled_after = config.poll_interval_secs.max(1) * STALLED_AFTER_POLLS;
+        Self {
+            router_address: config.router_address,
+            block_time_secs: config.block_time_secs,
+            stalled_after: chrono::Duration::seconds(stalled_after as i64),
+            progress: Mutex::new(IndexerProgress::default()),
+        }
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut IndexerProgress)) {
+        apply(&mut self.progress.lock().expect("indexer monitor lock poisoned"));
+    }
+
+    fn started(&self, last_indexed_block: u64) {
+        self.update(|progress| {
+            progress.running = true;
+            progress.heartbeat = Some(Utc::now());
+            progress.last_indexed_block = Some(last_indexed_block);
+        });
+    }
+
+    fn saw_head(&self, chain_head: u64) {
+        self.update(|progress| progress.chain_head = Some(chain_head));
+    }
+
+    fn indexed(&self, last_indexed_block: u64) {
+        self.update(|progress| {
+            let now = Utc::now();
+            progress.heartbeat = Some(now);
+            progress.last_indexed_block = Some(last_indexed_block);
+            progress.last_indexed_at = Some(now);
+        });
+    }
+
+    fn failed(&self, error: &anyhow::Error) {
+        self.update(|progress| {
+            let now = Utc::now();
+            progress.heartbeat = Some(now);
+            progress.last_error = Some(IndexerError {
+                message: format!("{:#}", error),
+                at: now,
+            });
+        });
+    }
+
+    fn stopped(&self) {
+        self.update(|progress| progress.running = false);
+    }
+
+    /// Current status; `now` decides whether the task has stalled
+    pub fn status(&self, now: DateTime<Utc>) -> IndexerStatus {
+        let progress = self.progress.lock().expect("indexer monitor lock poisoned");
+        let lag_blocks = match (progress.chain_head, progress.last_indexed_block) {
+            (Some(head), Some(indexed)) => Some(head.saturating_sub(indexed)),
+            _ => None,
+        };
+        let alive = progress.running
+            && progress
+                .heartbeat
+                .map_or(false, |heartbeat| now - heartbeat <= self.stalled_after);
+
+        IndexerStatus {
+            router_address: format!("{:?}", self.router_address),
+            last_indexed_block: progress.last_indexed_block,
+            chain_head: progress.chain_head,
+            lag_blocks,
+            lag_seconds: lag_blocks.map(|blocks| blocks.saturating_mul(self.block_time_secs)),
+            alive,
+            last_indexed_at: progress.last_indexed_at,
+            last_error: progress.last_error.clone(),
         }
     }
 }
@@ -81,11 +234,16 @@ pub struct EventIndexer {
     config: IndexerConfig,
     db_pool: PgPool,
     last_indexed_block: u64,
+    monitor: Arc<IndexerMonitor>,
 }
 
 impl EventIndexer {
     /// Create a new event indexer
-    pub async fn new(config: IndexerConfig, db_pool: PgPool) -> Result<Self> {
+    pub async fn new(
+        config: IndexerConfig,
+        db_pool: PgPool,
+        monitor: Arc<IndexerMonitor>,
+    ) -> Result<Self> {
         let provider = Provider::<Http>::try_from(&config.rpc_url)?;
 
         // Get last indexed block from database or use config start
@@ -103,38 +261,70 @@ impl EventIndexer {
             config,
             db_pool,
             last_indexed_block,
+            monitor,
         })
     }
 
     /// Start the indexer loop
-    pub async fn run(&mut self) -> Result<()> {
+    ///
+    /// Runs until `shutdown` flips to true. Shutdown is only observed
+    /// between cycles, so the current block range always finishes and
+    /// its checkpoint is saved before the loop exits.
+    pub async fn run(&mut self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
         info!(
             "Starting event indexer from block {} for router {:?}",
             self.last_indexed_block, self.config.router_address
         );
+        self.monitor.started(self.last_indexed_block);
+
+        if !self.config.rebuild_projections.is_empty() {
+            rebuild_projections(&self.db_pool, &self.config.rebuild_projections).await?;
+        }
 
-        loop {
+        while !*shutdown.borrow() {
             match self.index_new_blocks().await {
                 Ok(count) => {
                     if count > 0 {
                         info!("Indexed {} new events", count);
                     }
+                    self.monitor.indexed(self.last_indexed_block);
                 }
                 Err(e) => {
                     error!("Indexer error: {:?}", e);
+                    self.monitor.failed(&e);
                 }
             }
 
-            sleep(Duration::from_secs(self.config.poll_interval_secs)).await;
+            tokio::select! {
+                _ = sleep(Duration::from_secs(self.config.poll_interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    // Sender dropped: treat as shutdown
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
         }
+
+        info!("Indexer stopped cleanly at block {}", self.last_indexed_block);
+        Ok(())
     }
 
     /// Index events from new blocks
+    ///
+    /// Decoded events and the checkpoint are written to the event log in
+    /// one transaction; read models are then projected from the log.
     async fn index_new_blocks(&mut self) -> Result<usize> {
         let current_block = self.provider.get_block_number().await?.as_u64();
+        self.monitor.saw_head(current_block);
         let safe_block = current_block.saturating_sub(self.config.confirmations);
 
+        // Payment depth/finality is derived from the head, so record it every cycle
+        self.save_chain_head(current_block, safe_block).await?;
+
         if safe_block <= self.last_indexed_block {
+            // Still catch projections up (e.g. after a rebuild or a failed cycle)
+            run_projections(&self.db_pool).await?;
             return Ok(0);
         }
 
@@ -149,165 +339,138 @@ impl EventIndexer {
             .to_block(to_block);
 
         let logs = self.provider.get_logs(&filter).await?;
-        let mut event_count = 0;
+        let mut block_timestamps: HashMap<u64, u64> = HashMap::new();
+        let mut events = Vec::new();
 
         for log in logs {
-            match self.process_log(&log).await {
-                Ok(true) => event_count += 1,
-                Ok(false) => {} // Unknown event type
+            match self.decode_log(&log, &mut block_timestamps).await {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => {} // Unknown event type
                 Err(e) => {
-                    warn!("Failed to process log: {:?}", e);
+                    warn!("Failed to decode log: {:?}", e);
                 }
             }
         }
 
+        let mut tx = self.db_pool.begin().await?;
+        for event in &events {
+            sqlx::query(
+                r#"
+                INSERT INTO events (
+                    block_number, block_timestamp, tx_hash, log_index, event_type, data
+                )
+                VALUES ($1, to_timestamp($2), $3, $4, $5, $6)
+                ON CONFLICT (tx_hash, log_index) DO NOTHING
+                "#,
+            )
+            .bind(event.block_number as i64)
+            .bind(event.block_timestamp as f64)
+            .bind(format!("{:?}", event.tx_hash))
+            .bind(event.log_index as i32)
+            .bind(event.event_type)
+            .bind(&event.data)
+            .execute(&mut *tx)
+            .await?;
+        }
+        self.save_checkpoint(&mut tx, to_block).await?;
+        tx.commit().await?;
+
         // Update last indexed block
         self.last_indexed_block = to_block;
-        self.save_checkpoint(to_block).await?;
 
-        Ok(event_count)
+        run_projections(&self.db_pool).await?;
+
+        Ok(events.len())
     }
 
-    /// Process a single log entry
-    async fn process_log(&self, log: &Log) -> Result<bool> {
+    /// Decode a single log entry into an event log row (None if unknown)
+    async fn decode_log(
+        &self,
+        log: &Log,
+        block_timestamps: &mut HashMap<u64, u64>,
+    ) -> Result<Option<ChainEvent>> {
         if log.topics.is_empty() {
-            return Ok(false);
+            return Ok(None);
         }
 
         let event_sig = log.topics[0];
-
-        if event_sig == event_signatures::PAYMENT_PROCESSED {
-            self.process_payment_event(log).await?;
-            return Ok(true);
-        }
-
-        if event_sig == event_signatures::SONG_REGISTERED {
-            self.process_song_registered_event(log).await?;
-            return Ok(true);
-        }
-
-        Ok(false)
-    }
-
-    /// Process a PaymentProcessed event
-    async fn process_payment_event(&self, log: &Log) -> Result<()> {
-        // Parse event data from log
-        // topics: [event_sig, songId, listener]
-        // data: [amount, paymentType]
-
-        let song_id: [u8; 32] = log.topics.get(1)
-            .map(|h| h.0)
-            .unwrap_or_default();
-
-        let listener = log.topics.get(2)
-            .map(|h| Address::from_slice(&h.0[12..]))
-            .unwrap_or_default();
-
-        // Decode amount and payment type from data
-        let data = &log.data.0;
-        let amount = if data.len() >= 32 {
-            U256::from_big_endian(&data[0..32])
-        } else {
-            U256::zero()
-        };
-
-        let payment_type = if data.len() >= 64 {
-            data[63]
+        let (event_type, data) = if event_sig == event_signatures::PAYMENT_PROCESSED {
+            (
+                event_types::PAYMENT_PROCESSED,
+                serde_json::to_value(decode_payment(log))?,
+            )
+        } else if event_sig == event_signatures::SONG_REGISTERED {
+            (
+                event_types::SONG_REGISTERED,
+                serde_json::to_value(decode_song_registered(log))?,
+            )
         } else {
-            0
+            return Ok(None);
         };
 
         let block_number = log.block_number.map(|b| b.as_u64()).unwrap_or(0);
-        let tx_hash = log.transaction_hash.unwrap_or_default();
-
-        // Get block timestamp
-        let timestamp = if let Some(block) = self.provider.get_block(block_number).await? {
-            block.timestamp.as_u64()
-        } else {
-            0
+        let block_timestamp = match block_timestamps.get(&block_number) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let timestamp = match self.provider.get_block(block_number).await? {
+                    Some(block) => u64::try_from(block.timestamp).map_err(|_| {
+                        anyhow!("Block {} timestamp {} out of range", block_number, block.timestamp)
+                    })?,
+                    None => 0,
+                };
+                block_timestamps.insert(block_number, timestamp);
+                timestamp
+            }
         };
 
-        // Store in database
-        sqlx::query!(
-            r#"
-            INSERT INTO payments (
-                tx_hash, block_number, song_id, listener_address,
-                amount_wei, payment_type, timestamp, created_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7), NOW())
-            ON CONFLICT (tx_hash) DO NOTHING
-            "#,
-            format!("{:?}", tx_hash),
-            block_number as i64,
-            hex::encode(song_id),
-            format!("{:?}", listener),
-            amount.to_string(),
-            payment_type as i16,
-            timestamp as f64,
-        )
-        .execute(&self.db_pool)
-        .await?;
+        let log_index = match log.log_index {
+            Some(index) => u64::try_from(index)
+                .map_err(|_| anyhow!("Log index {} overflows u64", index))?,
+            None => 0,
+        };
 
-        info!(
-            "Indexed payment: song={}, listener={:?}, amount={}, type={}",
-            hex::encode(&song_id[..8]),
-            listener,
-            amount,
-            payment_type
-        );
+        info!("Indexed {} in block {}", event_type, block_number);
 
-        Ok(())
+        Ok(Some(ChainEvent {
+            block_number,
+            block_timestamp,
+            tx_hash: log.transaction_hash.unwrap_or_default(),
+            log_index,
+            event_type,
+            data,
+        }))
     }
 
-    /// Process a SongRegistered event
-    async fn process_song_registered_event(&self, log: &Log) -> Result<()> {
-        let song_id: [u8; 32] = log.topics.get(1)
-            .map(|h| h.0)
-            .unwrap_or_default();
-
-        let strategy_id: [u8; 32] = log.topics.get(2)
-            .map(|h| h.0)
-            .unwrap_or_default();
+    /// Record the current chain head for confirmation depth queries
+    async fn save_chain_head(&self, head_block: u64, safe_block: u64) -> Result<()> {
+        let finalized_block = head_block.saturating_sub(self.config.finality_blocks);
 
-        let artist = log.topics.get(3)
-            .map(|h| Address::from_slice(&h.0[12..]))
-            .unwrap_or_default();
-
-        let block_number = log.block_number.map(|b| b.as_u64()).unwrap_or(0);
-        let tx_hash = log.transaction_hash.unwrap_or_default();
-
-        // Update song record with on-chain registration
         sqlx::query!(
             r#"
-            UPDATE songs
-            SET
-                strategy_id = $1,
-                registered_on_chain = true,
-                registration_tx = $2,
-                registration_block = $3,
+            INSERT INTO indexer_chain_state (id, head_block, safe_block, finalized_block, updated_at)
+            VALUES (1, $1, $2, $3, NOW())
+            ON CONFLICT (id) DO UPDATE
+            SET head_block = EXCLUDED.head_block,
+                safe_block = EXCLUDED.safe_block,
+                finalized_block = EXCLUDED.finalized_block,
                 updated_at = NOW()
-            WHERE song_id = $4
             "#,
-            hex::encode(strategy_id),
-            format!("{:?}", tx_hash),
-            block_number as i64,
-            hex::encode(song_id),
+            head_block as i64,
+            safe_block as i64,
+            finalized_block as i64,
         )
         .execute(&self.db_pool)
         .await?;
 
-        info!(
-            "Indexed song registration: song={}, artist={:?}, strategy={}",
-            hex::encode(&song_id[..8]),
-            artist,
-            hex::encode(&strategy_id[..8])
-        );
-
         Ok(())
     }
 
-    /// Save indexer checkpoint
-    async fn save_checkpoint(&self, block_number: u64) -> Result<()> {
+    /// Save indexer checkpoint alongside the events it covers
+    async fn save_checkpoint(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        block_number: u64,
+    ) -> Result<()> {
         sqlx::query!(
             r#"
             INSERT INTO indexed_events (event_type, block_number, created_at)
@@ -315,27 +478,94 @@ impl EventIndexer {
             "#,
             block_number as i64,
         )
-        .execute(&self.db_pool)
+        .execute(&mut **tx)
         .await?;
 
         Ok(())
     }
 }
 
+/// Decode PaymentProcessed
+///
+/// topics: [event_sig, songId, listener]; data: [amount, paymentType]
+fn decode_payment(log: &Log) -> PaymentProcessedData {
+    let song_id: [u8; 32] = log.topics.get(1)
+        .map(|h| h.0)
+        .unwrap_or_default();
+
+    let listener = log.topics.get(2)
+        .map(|h| Address::from_slice(&h.0[12..]))
+        .unwrap_or_default();
+
+    let data = &log.data.0;
+    let amount = if data.len() >= 32 {
+        U256::from_big_endian(&data[0..32])
+    } else {
+        U256::zero()
+    };
+
+    let payment_type = if data.len() >= 64 {
+        data[63]
+    } else {
+        0
+    };
+
+    PaymentProcessedData {
+        song_id: hex::encode(song_id),
+        listener: format!("{:?}", listener),
+        amount_wei: amount.to_string(),
+        payment_type,
+    }
+}
+
+/// Decode SongRegistered
+///
+/// topics: [event_sig, songId, strategyId, artist]
+fn decode_song_registered(log: &Log) -> SongRegisteredData {
+    let song_id: [u8; 32] = log.topics.get(1)
+        .map(|h| h.0)
+        .unwrap_or_default();
+
+    let strategy_id: [u8; 32] = log.topics.get(2)
+        .map(|h| h.0)
+        .unwrap_or_default();
+
+    let artist = log.topics.get(3)
+        .map(|h| Address::from_slice(&h.0[12..]))
+        .unwrap_or_default();
+
+    SongRegisteredData {
+        song_id: hex::encode(song_id),
+        strategy_id: hex::encode(strategy_id),
+        artist: format!("{:?}", artist),
+    }
+}
+
 /// Start the indexer as a background task
-pub fn spawn_indexer(config: IndexerConfig, db_pool: PgPool) {
+///
+/// Await the returned handle after signalling `shutdown` to let
+/// in-flight work finish.
+pub fn spawn_indexer(
+    config: IndexerConfig,
+    db_pool: PgPool,
+    monitor: Arc<IndexerMonitor>,
+    shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
     tokio::spawn(async move {
-        match EventIndexer::new(config, db_pool).await {
+        match EventIndexer::new(config, db_pool, monitor.clone()).await {
             Ok(mut indexer) => {
-                if let Err(e) = indexer.run().await {
+                if let Err(e) = indexer.run(shutdown).await {
                     error!("Indexer failed: {:?}", e);
+                    monitor.failed(&e);
                 }
             }
             Err(e) => {
                 error!("Failed to create indexer: {:?}", e);
+                monitor.failed(&e);
             }
         }
-    });
+        monitor.stopped();
+    })
 }
 
 #[cfg(test)]
@@ -347,4 +577,48 @@ mod tests {
         assert_eq!(0u8, 0); // Stream
         assert_eq!(1u8, 1); // Download
     }
+
+    fn monitor() -> IndexerMonitor {
+        IndexerMonitor::new(&IndexerConfig {
+            poll_interval_secs: 12,
+            block_time_secs: 5,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn status_reports_lag_behind_the_head() {
+        let monitor = monitor();
+        let status = monitor.status(Utc::now());
+        assert!(!status.alive);
+        assert_eq!(status.lag_blocks, None);
+
+        monitor.started(1_000);
+        monitor.saw_head(1_010);
+        let status = monitor.status(Utc::now());
+        assert!(status.alive);
+        assert_eq!(status.last_indexed_block, Some(1_000));
+        assert_eq!(status.lag_blocks, Some(10));
+        assert_eq!(status.lag_seconds, Some(50));
+
+        monitor.indexed(1_007);
+        assert_eq!(monitor.status(Utc::now()).lag_blocks, Some(3));
+    }
+
+    #[test]
+    fn quiet_or_stopped_indexer_is_not_alive() {
+        let monitor = monitor();
+        monitor.started(0);
+        monitor.failed(&anyhow::anyhow!("rpc unreachable"));
+        let status = monitor.status(Utc::now());
+        // Failing cycles still count as polling
+        assert!(status.alive);
+        assert_eq!(status.last_error.unwrap().message, "rpc unreachable");
+
+        let later = Utc::now() + chrono::Duration::seconds(61);
+        assert!(!monitor.status(later).alive);
+
+        monitor.stopped();
+        assert!(!monitor.status(Utc::now()).alive);
+    }
 }
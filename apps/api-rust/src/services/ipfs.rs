@@ -5,18 +5,38 @@
 
 use anyhow::Result;
 
+/// Default IPFS chunk size (the go-ipfs default, 256KiB)
+const DEFAULT_CHUNK_SIZE: u64 = 256 * 1024;
+
+/// Largest chunk IPFS will accept as a single block (1MiB)
+const MAX_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Chunker spec for `add` from `IPFS_CHUNK_SIZE` (bytes), e.g. "size-262144"
+///
+/// Every upload uses the same chunk size so identical content always
+/// produces identical blocks and deduplicates across uploads.
+pub fn chunker_from_env() -> String {
+    let size = std::env::var("IPFS_CHUNK_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+        .clamp(1024, MAX_CHUNK_SIZE);
+    format!("size-{}", size)
+}
+
 /// IPFS service for file storage
 pub struct IpfsService {
     client: ipfs_api_backend_hyper::IpfsClient,
-    gateway_url: String,
+    /// Gateway base URLs in preference order (see `GatewayService` for failover)
+    gateway_urls: Vec<String>,
 }
 
 impl IpfsService {
-    pub fn new(api_url: &str, gateway_url: &str) -> Result<Self> {
+    pub fn new(api_url: &str, gateway_urls: &[String]) -> Result<Self> {
         let client = ipfs_api_backend_hyper::IpfsClient::from_str(api_url)?;
         Ok(Self {
             client,
-            gateway_url: gateway_url.to_string(),
+            gateway_urls: gateway_urls.to_vec(),
         })
     }
 
@@ -27,9 +47,17 @@ impl IpfsService {
         Ok(response.hash)
     }
 
-    /// Get gateway URL for a hash
+    /// Get the preferred gateway URL for a hash
     pub fn gateway_url(&self, hash: &str) -> String {
-        format!("{}/ipfs/{}", self.gateway_url, hash)
+        self.gateway_urls(hash).into_iter().next().unwrap_or_default()
+    }
+
+    /// Get URLs for a hash on every configured gateway
+    pub fn gateway_urls(&self, hash: &str) -> Vec<String> {
+        self.gateway_urls
+            .iter()
+            .map(|gateway| format!("{}/ipfs/{}", gateway.trim_end_matches('/'), hash))
+            .collect()
     }
 
     /// Pin a hash to ensure persistence
@@ -0,0 +1,150 @@
+//! Live Plays - Real-time play events for artist dashboards
+//!
+//! A trigger on `plays` sends `NOTIFY play_recorded` with each new row's
+//! id. One listener per API instance loads the play once and fans it out
+//! over a broadcast channel; SSE subscribers keep their artist's plays.
+//! Notifications missed while the listener reconnects are recovered by
+//! clients resuming from their last event id.
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::error;
+use uuid::Uuid;
+
+/// Postgres channel the plays trigger notifies
+pub const PLAY_CHANNEL: &str = "play_recorded";
+
+/// Plays buffered per subscriber before it lags and catches up from the DB
+const FEED_CAPACITY: usize = 1024;
+
+/// Delay before reconnecting a failed listener
+const RECONNECT_SECS: u64 = 5;
+
+/// A recorded play as pushed to the artist's dashboard
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LivePlay {
+    /// Play row id, also the SSE event id
+    pub id: i64,
+    #[serde(skip)]
+    pub artist_address: String,
+    pub song_id: Uuid,
+    pub song_title: String,
+    pub amount: f64,
+    /// Held by an open play anomaly; `amount` isn't credited yet
+    pub earnings_paused: bool,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Broadcast of newly recorded plays
+pub struct PlayFeed {
+    sender: broadcast::Sender<LivePlay>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl PlayFeed {
+    pub fn new(shutdown: watch::Receiver<bool>) -> Self {
+        let (sender, _) = broadcast::channel(FEED_CAPACITY);
+        Self { sender, shutdown }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LivePlay> {
+        self.sender.subscribe()
+    }
+
+    /// Shutdown signal, so open streams end and don't hold up the server
+    pub fn shutdown(&self) -> watch::Receiver<bool> {
+        self.shutdown.clone()
+    }
+}
+
+const LIVE_PLAY_SELECT: &str = r#"
+    SELECT p.id::bigint AS id, LOWER(s.artist_address) AS artist_address,
+           s.id AS song_id, s.title AS song_title,
+           COALESCE(p.amount, 0)::float8 AS amount,
+           COALESCE(p.excluded_reason = 'earnings_paused', FALSE) AS earnings_paused,
+           p.timestamp
+    FROM plays p
+    JOIN songs s ON s.id = p.song_id
+"#;
+
+/// An artist's plays recorded after `after_id`, oldest first
+pub async fn plays_since(
+    pool: &PgPool,
+    artist_address: &str,
+    after_id: i64,
+    limit: i64,
+) -> sqlx::Result<Vec<LivePlay>> {
+    sqlx::query_as::<_, LivePlay>(&format!(
+        "{} WHERE LOWER(s.artist_address) = LOWER($1) AND p.id > $2 ORDER BY p.id LIMIT $3",
+        LIVE_PLAY_SELECT
+    ))
+    .bind(artist_address)
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+async fn load_play(pool: &PgPool, id: i64) -> sqlx::Result<Option<LivePlay>> {
+    sqlx::query_as::<_, LivePlay>(&format!("{} WHERE p.id = $1", LIVE_PLAY_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Relay notifications to the feed until shutdown or a connection error
+async fn listen(feed: &PlayFeed, pool: &PgPool, shutdown: &mut watch::Receiver<bool>) -> Result<()> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(PLAY_CHANNEL).await?;
+
+    loop {
+        tokio::select! {
+            notification = listener.recv() => {
+                let id = match notification?.payload().parse::<i64>() {
+                    Ok(id) => id,
+                    Err(_) => continue,
+                };
+                // Plays of unknown songs have no artist to notify
+                if let Some(play) = load_play(pool, id).await? {
+                    // Nobody subscribed is fine
+                    let _ = feed.sender.send(play);
+                }
+            }
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Listen for recorded plays until shutdown, reconnecting on failure
+pub fn spawn_play_listener(
+    feed: Arc<PlayFeed>,
+    pool: PgPool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while !*shutdown.borrow() {
+            if let Err(e) = listen(&feed, &pool, &mut shutdown).await {
+                error!("Play listener failed: {:?}", e);
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(RECONNECT_SECS)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
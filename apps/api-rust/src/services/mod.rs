@@ -3,6 +3,23 @@
 //! Core services for Mycelix Music platform
 
 pub mod ipfs;
+pub mod pinning;
 pub mod blockchain;
+pub mod settlement;
 pub mod cache;
 pub mod indexer;
+pub mod projections;
+pub mod rollups;
+pub mod retention;
+pub mod anomalies;
+pub mod fx;
+pub mod live;
+pub mod holochain;
+pub mod catalog_sync;
+pub mod transcode;
+pub mod auth;
+pub mod thumbnail;
+pub mod gateways;
+pub mod cdn;
+pub mod upload_limiter;
+pub mod upload_progress;
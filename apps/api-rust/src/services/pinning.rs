@@ -0,0 +1,202 @@
+//! Pinning - Keep song content pinned even when IPFS is flaky
+//!
+//! Song creation pins the audio and cover CIDs right away. If IPFS is
+//! unreachable the CIDs go into `pinning_queue` and the song is marked
+//! `pinning_pending`; a background worker retries with backoff and clears
+//! the flag once every CID for the song is pinned.
+
+use anyhow::{anyhow, Result};
+use ipfs_api_backend_hyper::{IpfsApi, IpfsClient};
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Longest wait between retries of one CID
+const MAX_BACKOFF_SECS: i64 = 3600;
+
+/// Queue rows retried per pass
+const RETRY_BATCH_SIZE: i64 = 50;
+
+/// Pinning timeouts and retry schedule
+#[derive(Debug, Clone)]
+pub struct PinConfig {
+    /// How long a pin may take before IPFS is treated as unavailable
+    pub timeout: Duration,
+    pub retry_interval_secs: u64,
+}
+
+impl PinConfig {
+    /// Configure from `IPFS_PIN_TIMEOUT_SECS` and `IPFS_PIN_RETRY_INTERVAL_SECS`
+    pub fn from_env() -> Self {
+        let timeout_secs = std::env::var("IPFS_PIN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10);
+        let retry_interval_secs = std::env::var("IPFS_PIN_RETRY_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        Self {
+            timeout: Duration::from_secs(timeout_secs),
+            retry_interval_secs,
+        }
+    }
+}
+
+/// Pin a CID recursively, failing if IPFS doesn't answer in time
+pub async fn pin(ipfs: &IpfsClient, cid: &str, limit: Duration) -> Result<()> {
+    match timeout(limit, ipfs.pin_add(cid, true)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(anyhow!("{}", e)),
+        Err(_) => Err(anyhow!("timed out after {:?}", limit)),
+    }
+}
+
+/// Pin a new song's CIDs, queueing any that fail; returns whether the
+/// song is left with pinning pending
+pub async fn pin_or_queue(
+    pool: &PgPool,
+    ipfs: &IpfsClient,
+    config: &PinConfig,
+    song_id: Uuid,
+    cids: &[&str],
+) -> Result<bool> {
+    let mut failed = Vec::new();
+    for cid in cids {
+        if let Err(e) = pin(ipfs, cid, config.timeout).await {
+            warn!("Failed to pin {} for song {}, queueing retry: {}", cid, song_id, e);
+            failed.push((*cid, e.to_string()));
+        }
+    }
+    if failed.is_empty() {
+        return Ok(false);
+    }
+
+    let mut tx = pool.begin().await?;
+    for (cid, error) in failed {
+        sqlx::query(
+            r#"
+            INSERT INTO pinning_queue (song_id, cid, last_error)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (song_id, cid) DO NOTHING
+            "#,
+        )
+        .bind(song_id)
+        .bind(cid)
+        .bind(error)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("UPDATE songs SET pinning_pending = TRUE WHERE id = $1")
+        .bind(song_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// CIDs waiting to be pinned
+pub async fn backlog(pool: &PgPool) -> sqlx::Result<i64> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM pinning_queue")
+        .fetch_one(pool)
+        .await
+}
+
+#[derive(sqlx::FromRow)]
+struct QueuedPin {
+    song_id: Uuid,
+    cid: String,
+    attempts: i32,
+}
+
+/// Retry every due CID once
+async fn retry_due(pool: &PgPool, ipfs: &IpfsClient, config: &PinConfig) -> Result<()> {
+    let due = sqlx::query_as::<_, QueuedPin>(
+        r#"
+        SELECT song_id, cid, attempts
+        FROM pinning_queue
+        WHERE next_attempt_at <= NOW()
+        ORDER BY next_attempt_at
+        LIMIT $1
+        "#,
+    )
+    .bind(RETRY_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    for queued in due {
+        match pin(ipfs, &queued.cid, config.timeout).await {
+            Ok(()) => {
+                let mut tx = pool.begin().await?;
+                sqlx::query("DELETE FROM pinning_queue WHERE song_id = $1 AND cid = $2")
+                    .bind(queued.song_id)
+                    .bind(&queued.cid)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query(
+                    r#"
+                    UPDATE songs SET pinning_pending = FALSE
+                    WHERE id = $1
+                      AND NOT EXISTS (SELECT 1 FROM pinning_queue WHERE song_id = $1)
+                    "#,
+                )
+                .bind(queued.song_id)
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+                info!("Pinned {} for song {}", queued.cid, queued.song_id);
+            }
+            Err(e) => {
+                // 1, 2, 4 ... minutes, capped
+                let backoff = (60i64 << queued.attempts.min(10)).min(MAX_BACKOFF_SECS);
+                sqlx::query(
+                    r#"
+                    UPDATE pinning_queue
+                    SET attempts = attempts + 1,
+                        last_error = $3,
+                        next_attempt_at = NOW() + make_interval(secs => $4)
+                    WHERE song_id = $1 AND cid = $2
+                    "#,
+                )
+                .bind(queued.song_id)
+                .bind(&queued.cid)
+                .bind(e.to_string())
+                .bind(backoff as f64)
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Retry queued pins on the configured interval until shutdown
+pub fn spawn_pin_retries(
+    config: PinConfig,
+    pool: PgPool,
+    ipfs: IpfsClient,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while !*shutdown.borrow() {
+            if let Err(e) = retry_due(&pool, &ipfs, &config).await {
+                error!("Pin retry pass failed: {:?}", e);
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(config.retry_interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,373 @@
+//! Event Log Projections
+//!
+//! Builds read models (payments, song registrations) from the append-only
+//! `events` log written by the indexer. Each projection keeps its own
+//! cursor, so a new projection can be added, or an existing one rebuilt,
+//! by replaying the stored log instead of re-indexing the chain.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{info, warn};
+
+/// Event type names stored in `events.event_type`
+pub mod event_types {
+    pub const PAYMENT_PROCESSED: &str = "PaymentProcessed";
+    pub const SONG_REGISTERED: &str = "SongRegistered";
+}
+
+/// Decoded PaymentProcessed data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentProcessedData {
+    /// Hex-encoded bytes32, no 0x prefix
+    pub song_id: String,
+    pub listener: String,
+    pub amount_wei: String,
+    pub payment_type: u8,
+}
+
+/// Decoded SongRegistered data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SongRegisteredData {
+    /// Hex-encoded bytes32, no 0x prefix
+    pub song_id: String,
+    pub strategy_id: String,
+    pub artist: String,
+}
+
+/// Read models built from the event log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Payments,
+    SongRegistrations,
+}
+
+/// Every projection, applied in this order after each indexing cycle
+pub const PROJECTIONS: &[Projection] = &[Projection::Payments, Projection::SongRegistrations];
+
+/// Events applied per transaction
+const BATCH_SIZE: i64 = 500;
+
+/// Stored event row
+#[derive(Debug, sqlx::FromRow)]
+struct EventRow {
+    id: i64,
+    block_number: i64,
+    block_timestamp: chrono::DateTime<chrono::Utc>,
+    tx_hash: String,
+    log_index: i32,
+    data: serde_json::Value,
+}
+
+impl Projection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Payments => "payments",
+            Self::SongRegistrations => "song_registrations",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        PROJECTIONS.iter().copied().find(|p| p.name() == name)
+    }
+
+    /// Event types this projection consumes
+    fn event_type(&self) -> &'static str {
+        match self {
+            Self::Payments => event_types::PAYMENT_PROCESSED,
+            Self::SongRegistrations => event_types::SONG_REGISTERED,
+        }
+    }
+
+    /// Apply pending events to this projection; returns the number applied
+    pub async fn run(&self, pool: &PgPool) -> Result<usize> {
+        let mut applied = 0;
+        loop {
+            let mut tx = pool.begin().await?;
+
+            sqlx::query(
+                "INSERT INTO projection_cursors (name) VALUES ($1) ON CONFLICT (name) DO NOTHING",
+            )
+            .bind(self.name())
+            .execute(&mut *tx)
+            .await?;
+
+            // Row lock keeps concurrent runners from applying the same batch
+            let cursor: i64 = sqlx::query_scalar(
+                "SELECT last_event_id FROM projection_cursors WHERE name = $1 FOR UPDATE",
+            )
+            .bind(self.name())
+            .fetch_one(&mut *tx)
+            .await?;
+
+            let events = sqlx::query_as::<_, EventRow>(
+                r#"
+                SELECT id, block_number, block_timestamp, tx_hash, log_index, data
+                FROM events
+                WHERE id > $1 AND event_type = $2
+                ORDER BY id
+                LIMIT $3
+                "#,
+            )
+            .bind(cursor)
+            .bind(self.event_type())
+            .bind(BATCH_SIZE)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let last = match events.last() {
+                Some(event) => event.id,
+                None => {
+                    tx.commit().await?;
+                    return Ok(applied);
+                }
+            };
+
+            for event in &events {
+                self.apply(&mut tx, event).await?;
+            }
+
+            sqlx::query(
+                "UPDATE projection_cursors SET last_event_id = $2, updated_at = NOW() WHERE name = $1",
+            )
+            .bind(self.name())
+            .bind(last)
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            applied += events.len();
+        }
+    }
+
+    /// Clear the rows this projection built from the log and replay it
+    /// from the start
+    ///
+    /// Rows the log doesn't cover, such as payments indexed before it
+    /// existed, are kept.
+    pub async fn rebuild(&self, pool: &PgPool) -> Result<usize> {
+        let mut tx = pool.begin().await?;
+        match self {
+            Self::Payments => {
+                sqlx::query(
+                    r#"
+                    DELETE FROM payments p
+                    USING events e
+                    WHERE e.tx_hash = p.tx_hash AND e.log_index = p.log_index
+                      AND e.event_type = $1
+                    "#,
+                )
+                .bind(self.event_type())
+                .execute(&mut *tx)
+                .await?;
+            }
+            Self::SongRegistrations => {
+                sqlx::query(
+                    r#"
+                    UPDATE songs
+                    SET registered_on_chain = false, registration_tx = NULL,
+                        registration_block = NULL
+                    WHERE registered_on_chain
+                      AND registration_tx IN (SELECT tx_hash FROM events WHERE event_type = $1)
+                    "#,
+                )
+                .bind(self.event_type())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO projection_cursors (name, last_event_id) VALUES ($1, 0)
+            ON CONFLICT (name) DO UPDATE SET last_event_id = 0, updated_at = NOW()
+            "#,
+        )
+        .bind(self.name())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let applied = self.run(pool).await?;
+        info!("Rebuilt {} projection from {} events", self.name(), applied);
+        Ok(applied)
+    }
+
+    /// Decode event data; malformed events are skipped rather than
+    /// blocking the projection
+    fn decode<T: serde::de::DeserializeOwned>(&self, event: &EventRow) -> Option<T> {
+        match serde_json::from_value(event.data.clone()) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                warn!("{} projection skipped event {}: {}", self.name(), event.id, e);
+                None
+            }
+        }
+    }
+
+    async fn apply(&self, tx: &mut Transaction<'_, Postgres>, event: &EventRow) -> Result<()> {
+        match self {
+            Self::Payments => {
+                let data: PaymentProcessedData = match self.decode(event) {
+                    Some(data) => data,
+                    None => return Ok(()),
+                };
+                sqlx::query(
+                    r#"
+                    INSERT INTO payments (
+                        tx_hash, log_index, block_number, song_id, listener_address,
+                        amount_wei, payment_type, timestamp, created_at
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+                    ON CONFLICT (tx_hash, log_index) DO NOTHING
+                    "#,
+                )
+                .bind(&event.tx_hash)
+                .bind(event.log_index)
+                .bind(event.block_number)
+                .bind(&data.song_id)
+                .bind(&data.listener)
+                .bind(&data.amount_wei)
+                .bind(data.payment_type as i16)
+                .bind(event.block_timestamp)
+                .execute(&mut **tx)
+                .await?;
+
+                // Fix the fiat value of the payment at the rates of the day
+                sqlx::query(
+                    r#"
+                    INSERT INTO payment_fx_rates (tx_hash, log_index, currency, rate, rate_at)
+                    SELECT DISTINCT ON (currency) $1, $2, currency, rate, fetched_at
+                    FROM fx_rates
+                    WHERE fetched_at <= $3
+                    ORDER BY currency, fetched_at DESC
+                    ON CONFLICT (tx_hash, log_index, currency) DO NOTHING
+                    "#,
+                )
+                .bind(&event.tx_hash)
+                .bind(event.log_index)
+                .bind(event.block_timestamp)
+                .execute(&mut **tx)
+                .await?;
+
+                settle_plays(tx, &data, event.block_timestamp).await?;
+            }
+            Self::SongRegistrations => {
+                let data: SongRegisteredData = match self.decode(event) {
+                    Some(data) => data,
+                    None => return Ok(()),
+                };
+                sqlx::query(
+                    r#"
+                    UPDATE songs
+                    SET
+                        strategy_id = $1,
+                        registered_on_chain = true,
+                        registration_tx = $2,
+                        registration_block = $3,
+                        updated_at = NOW()
+                    WHERE song_id = $4
+                    "#,
+                )
+                .bind(&data.strategy_id)
+                .bind(&event.tx_hash)
+                .bind(event.block_number)
+                .bind(&data.song_id)
+                .execute(&mut **tx)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mark the listener's plays of the song a payment covers as settled
+///
+/// Their oldest unsettled paying plays from before the payment are settled
+/// while their running total fits in the amount paid (plays are priced in
+/// xDAI, payments in wei). Plays already settled at the payment's time
+/// count against it, so replaying the payment settles nothing twice.
+/// `migrations/20250213_backfill_play_settlement.sql` applies the same
+/// rule to payments projected before.
+async fn settle_plays(
+    tx: &mut Transaction<'_, Postgres>,
+    payment: &PaymentProcessedData,
+    paid_at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH budget AS (
+            SELECT $4::numeric / 1e18 - COALESCE(SUM(p.amount), 0) AS remaining
+            FROM plays p
+            JOIN songs s ON s.id = p.song_id
+            WHERE s.song_id = $1
+              AND LOWER(p.listener_address) = LOWER($2)
+              AND p.settled_at = $3
+        ),
+        candidates AS (
+            SELECT p.id, SUM(p.amount) OVER (ORDER BY p.timestamp, p.id) AS running
+            FROM plays p
+            JOIN songs s ON s.id = p.song_id
+            WHERE s.song_id = $1
+              AND LOWER(p.listener_address) = LOWER($2)
+              AND p.timestamp <= $3
+              AND p.settled_at IS NULL
+              AND p.amount > 0
+              AND (p.excluded_reason IS NULL OR p.excluded_reason = 'earnings_paused')
+        )
+        UPDATE plays
+        SET settled_at = $3
+        FROM candidates c, budget b
+        WHERE plays.id = c.id AND c.running <= b.remaining
+        "#,
+    )
+    .bind(&payment.song_id)
+    .bind(&payment.listener)
+    .bind(paid_at)
+    .bind(&payment.amount_wei)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Bring every projection up to date with the log
+pub async fn run_projections(pool: &PgPool) -> Result<usize> {
+    let mut applied = 0;
+    for projection in PROJECTIONS {
+        applied += projection.run(pool).await?;
+    }
+    Ok(applied)
+}
+
+/// Rebuild projections named in a comma-separated list (e.g. "payments")
+///
+/// Each entry runs once: its completion is recorded in
+/// `projection_rebuilds`, and later startups with the same entry skip it.
+/// An entry may carry a label (e.g. "payments@2") to request another
+/// rebuild of the same projection.
+pub async fn rebuild_projections(pool: &PgPool, names: &str) -> Result<()> {
+    for request in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let name = request.split('@').next().unwrap_or(request);
+        let projection = match Projection::parse(name) {
+            Some(projection) => projection,
+            None => bail!("Unknown projection: {}", name),
+        };
+
+        let done: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM projection_rebuilds WHERE request = $1)",
+        )
+        .bind(request)
+        .fetch_one(pool)
+        .await?;
+        if done {
+            info!("Projection rebuild {} already done; skipping", request);
+            continue;
+        }
+
+        projection.rebuild(pool).await?;
+        sqlx::query("INSERT INTO projection_rebuilds (request) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(request)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! Play Retention
+//!
+//! Moves raw plays older than `PLAY_RETENTION_DAYS` into `plays_archive`
+//! after rolling their day up. Unsettled paying plays and plays held by an
+//! anomaly stay until they're settled or released. History readers query
+//! `plays_with_archive`, and song/artist earnings totals are kept on
+//! `songs`, so archiving changes no totals.
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+use crate::services::rollups::rollup_day;
+
+/// Shortest retention allowed; recent-window analytics (forecasts, anomaly
+/// baselines) read raw plays only
+pub const MIN_RETENTION_DAYS: i64 = 90;
+
+/// Plays that can leave `plays`: not held by an anomaly and not owed
+const ARCHIVABLE: &str = r#"
+    excluded_reason IS DISTINCT FROM 'earnings_paused'
+    AND (settled_at IS NOT NULL OR COALESCE(amount, 0) = 0 OR excluded_reason IS NOT NULL)
+"#;
+
+/// Retention window and archival schedule
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// Days of raw plays kept (None keeps everything)
+    pub retention_days: Option<i64>,
+    pub interval_secs: u64,
+}
+
+impl RetentionConfig {
+    /// Configure from `PLAY_RETENTION_DAYS` (unset or 0 disables archival,
+    /// otherwise at least 90) and `PLAY_ARCHIVE_INTERVAL_SECS`
+    pub fn from_env() -> Self {
+        let retention_days = std::env::var("PLAY_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .filter(|days| *days > 0)
+            .map(|days| days.max(MIN_RETENTION_DAYS));
+        let interval_secs = std::env::var("PLAY_ARCHIVE_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(86400);
+
+        Self {
+            retention_days,
+            interval_secs,
+        }
+    }
+}
+
+/// Result of an archival run
+#[derive(Debug, Serialize)]
+pub struct ArchiveReport {
+    /// Plays before this day were eligible
+    pub cutoff: NaiveDate,
+    /// Days rolled up and archived
+    pub days: usize,
+    /// Plays moved to `plays_archive`
+    pub archived: u64,
+}
+
+/// Move one day's archivable plays to `plays_archive`
+async fn archive_day(pool: &PgPool, day: NaiveDate) -> Result<u64> {
+    let moved = sqlx::query(&format!(
+        r#"
+        WITH moved AS (
+            DELETE FROM plays
+            WHERE timestamp >= $1 AND timestamp < $1 + 1 AND {}
+            RETURNING *
+        )
+        INSERT INTO plays_archive SELECT * FROM moved
+        "#,
+        ARCHIVABLE
+    ))
+    .bind(day)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(moved)
+}
+
+/// Archive plays older than `retention_days`, a day at a time
+///
+/// Each day is rolled up first; rollups read `plays_with_archive`, so
+/// rebuilding an archived day later gives the same rows.
+pub async fn archive_plays(pool: &PgPool, retention_days: i64) -> Result<ArchiveReport> {
+    let cutoff = Utc::now().date_naive() - ChronoDuration::days(retention_days);
+    let first: Option<NaiveDate> = sqlx::query_scalar(&format!(
+        "SELECT MIN(timestamp)::date FROM plays WHERE timestamp < $1 AND {}",
+        ARCHIVABLE
+    ))
+    .bind(cutoff)
+    .fetch_one(pool)
+    .await?;
+
+    let mut report = ArchiveReport {
+        cutoff,
+        days: 0,
+        archived: 0,
+    };
+    let mut day = match first {
+        Some(first) => first,
+        None => return Ok(report),
+    };
+    while day < cutoff {
+        rollup_day(pool, day).await?;
+        report.archived += archive_day(pool, day).await?;
+        report.days += 1;
+        day += ChronoDuration::days(1);
+    }
+
+    info!(
+        "Archived {} plays from {} days before {}",
+        report.archived, report.days, cutoff
+    );
+    Ok(report)
+}
+
+/// Archive on the configured interval until shutdown
+pub fn spawn_play_archiver(
+    retention_days: i64,
+    interval_secs: u64,
+    pool: PgPool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while !*shutdown.borrow() {
+            if let Err(e) = archive_plays(&pool, retention_days).await {
+                error!("Play archival failed: {:?}", e);
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,291 @@
+//! Analytics Rollups
+//!
+//! Aggregates raw `plays` into `daily_song_stats` and `daily_artist_stats`
+//! on a schedule. Each run rebuilds the last few completed days, so late
+//! or reconciled plays are picked up, and catches up on any days since
+//! the last rollup that were missed while the job wasn't running. Analytics read history from the
+//! rollups and only query raw plays for days not rolled up yet. Rollups
+//! read archived plays too, so rebuilding an archived day is safe.
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+/// Rollup schedule
+#[derive(Debug, Clone)]
+pub struct RollupConfig {
+    pub interval_secs: u64,
+    /// Completed days rebuilt on every run (1 = just yesterday)
+    pub lookback_days: i64,
+    /// Roll up all history on startup
+    pub backfill: bool,
+}
+
+impl RollupConfig {
+    /// Configure from `ANALYTICS_ROLLUP_INTERVAL_SECS`,
+    /// `ANALYTICS_ROLLUP_LOOKBACK_DAYS` and `ANALYTICS_ROLLUP_BACKFILL`
+    pub fn from_env() -> Self {
+        let interval_secs = std::env::var("ANALYTICS_ROLLUP_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600);
+        let lookback_days = std::env::var("ANALYTICS_ROLLUP_LOOKBACK_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(2)
+            .max(1);
+        let backfill = std::env::var("ANALYTICS_ROLLUP_BACKFILL")
+            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        Self {
+            interval_secs,
+            lookback_days,
+            backfill,
+        }
+    }
+}
+
+/// One day of a song's or artist's plays
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DailyStats {
+    pub day: NaiveDate,
+    pub plays: i64,
+    pub earnings: f64,
+    pub unique_listeners: i64,
+}
+
+/// Rebuild one day's rollups; safe to re-run
+pub async fn rollup_day(pool: &PgPool, day: NaiveDate) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM daily_song_stats WHERE day = $1")
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM daily_artist_stats WHERE day = $1")
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO daily_song_stats (day, song_id, plays, earnings, unique_listeners)
+        SELECT $1, p.song_id, COUNT(*), COALESCE(SUM(p.amount), 0),
+               COUNT(DISTINCT p.listener_address)
+        FROM plays_with_archive p
+        WHERE p.timestamp >= $1 AND p.timestamp < $1 + 1
+          AND p.song_id IS NOT NULL
+          AND p.excluded_reason IS NULL
+        GROUP BY p.song_id
+        "#,
+    )
+    .bind(day)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO daily_artist_stats (day, artist_address, plays, earnings, unique_listeners)
+        SELECT $1, LOWER(s.artist_address), COUNT(*), COALESCE(SUM(p.amount), 0),
+               COUNT(DISTINCT p.listener_address)
+        FROM plays_with_archive p
+        JOIN songs s ON s.id = p.song_id
+        WHERE p.timestamp >= $1 AND p.timestamp < $1 + 1
+          AND p.excluded_reason IS NULL
+        GROUP BY LOWER(s.artist_address)
+        "#,
+    )
+    .bind(day)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO analytics_rollup_days (day) VALUES ($1)
+        ON CONFLICT (day) DO UPDATE SET rolled_up_at = NOW()
+        "#,
+    )
+    .bind(day)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Roll up every completed day from the first play to yesterday;
+/// returns the number of days rolled up
+pub async fn backfill(pool: &PgPool) -> Result<usize> {
+    let first: Option<NaiveDate> =
+        sqlx::query_scalar("SELECT MIN(timestamp)::date FROM plays_with_archive")
+            .fetch_one(pool)
+            .await?;
+    let first = match first {
+        Some(first) => first,
+        None => return Ok(0),
+    };
+
+    let today = Utc::now().date_naive();
+    let mut day = first;
+    let mut count = 0;
+    while day < today {
+        rollup_day(pool, day).await?;
+        day += ChronoDuration::days(1);
+        count += 1;
+    }
+
+    info!("Backfilled analytics rollups for {} days from {}", count, first);
+    Ok(count)
+}
+
+/// Rebuild the last `lookback_days` completed days, starting earlier if
+/// the last rolled-up day is older than that
+async fn rollup_recent(pool: &PgPool, lookback_days: i64) -> Result<()> {
+    let today = Utc::now().date_naive();
+    let last: Option<NaiveDate> = sqlx::query_scalar("SELECT MAX(day) FROM analytics_rollup_days")
+        .fetch_one(pool)
+        .await?;
+    let start = catch_up_start(last, today, lookback_days);
+
+    let mut day = start;
+    while day < today {
+        rollup_day(pool, day).await?;
+        day += ChronoDuration::days(1);
+    }
+    if (today - start).num_days() > lookback_days {
+        info!("Caught up analytics rollups from {}", start);
+    }
+    Ok(())
+}
+
+/// First day a run rebuilds: the start of the lookback window, or the day
+/// after the last rollup if that is earlier
+fn catch_up_start(last: Option<NaiveDate>, today: NaiveDate, lookback_days: i64) -> NaiveDate {
+    let window = today - ChronoDuration::days(lookback_days);
+    match last {
+        Some(last) => window.min(last + ChronoDuration::days(1)),
+        None => window,
+    }
+}
+
+/// Run rollups on the configured interval until shutdown
+pub fn spawn_rollups(
+    config: RollupConfig,
+    pool: PgPool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if config.backfill {
+            if let Err(e) = backfill(&pool).await {
+                error!("Analytics rollup backfill failed: {:?}", e);
+            }
+        }
+
+        while !*shutdown.borrow() {
+            if let Err(e) = rollup_recent(&pool, config.lookback_days).await {
+                error!("Analytics rollup failed: {:?}", e);
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_secs(config.interval_secs)) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Daily stats for a song over the last `days` days: rolled-up days from
+/// `daily_song_stats`, later days (at least today) from raw plays
+pub async fn song_daily_stats(
+    pool: &PgPool,
+    song_id: uuid::Uuid,
+    days: i32,
+) -> sqlx::Result<Vec<DailyStats>> {
+    sqlx::query_as::<_, DailyStats>(
+        r#"
+        WITH boundary AS (
+            SELECT LEAST(CURRENT_DATE, COALESCE(MAX(day) + 1, CURRENT_DATE)) AS day
+            FROM analytics_rollup_days
+        )
+        SELECT r.day, r.plays, r.earnings::float8 AS earnings, r.unique_listeners
+        FROM daily_song_stats r, boundary b
+        WHERE r.song_id = $1 AND r.day >= CURRENT_DATE - $2 AND r.day < b.day
+        UNION ALL
+        SELECT p.timestamp::date AS day, COUNT(*) AS plays,
+               COALESCE(SUM(p.amount), 0)::float8 AS earnings,
+               COUNT(DISTINCT p.listener_address) AS unique_listeners
+        FROM plays p, boundary b
+        WHERE p.song_id = $1
+          AND p.timestamp >= GREATEST(b.day, CURRENT_DATE - $2)
+          AND p.excluded_reason IS NULL
+        GROUP BY p.timestamp::date
+        ORDER BY day
+        "#,
+    )
+    .bind(song_id)
+    .bind(days)
+    .fetch_all(pool)
+    .await
+}
+
+/// Daily stats for an artist over the last `days` days, split between
+/// rollups and raw plays like `song_daily_stats`
+pub async fn artist_daily_stats(
+    pool: &PgPool,
+    address: &str,
+    days: i32,
+) -> sqlx::Result<Vec<DailyStats>> {
+    sqlx::query_as::<_, DailyStats>(
+        r#"
+        WITH boundary AS (
+            SELECT LEAST(CURRENT_DATE, COALESCE(MAX(day) + 1, CURRENT_DATE)) AS day
+            FROM analytics_rollup_days
+        )
+        SELECT r.day, r.plays, r.earnings::float8 AS earnings, r.unique_listeners
+        FROM daily_artist_stats r, boundary b
+        WHERE r.artist_address = LOWER($1) AND r.day >= CURRENT_DATE - $2 AND r.day < b.day
+        UNION ALL
+        SELECT p.timestamp::date AS day, COUNT(*) AS plays,
+               COALESCE(SUM(p.amount), 0)::float8 AS earnings,
+               COUNT(DISTINCT p.listener_address) AS unique_listeners
+        FROM plays p
+        JOIN songs s ON s.id = p.song_id, boundary b
+        WHERE LOWER(s.artist_address) = LOWER($1)
+          AND p.timestamp >= GREATEST(b.day, CURRENT_DATE - $2)
+          AND p.excluded_reason IS NULL
+        GROUP BY p.timestamp::date
+        ORDER BY day
+        "#,
+    )
+    .bind(address)
+    .bind(days)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_catch_up_from_the_last_rolled_up_day() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+
+        // Up to date, or nothing rolled up yet: just the lookback window
+        assert_eq!(catch_up_start(Some(day(9)), today, 2), day(8));
+        assert_eq!(catch_up_start(None, today, 2), day(8));
+        // Down since the 3rd: start from the 4th
+        assert_eq!(catch_up_start(Some(day(3)), today, 2), day(4));
+    }
+}
@@ -0,0 +1,285 @@
+//! Settlement Payloads - Batch payouts formatted for their target chain
+//!
+//! Each settlement batch records the `SettlementTarget` (chain family,
+//! chain id and verifier contract) it settles on. Every verifier takes the
+//! same `settleBatch` call, but checks the settler's signature over the
+//! payout in its chain family's format: Gnosis verifiers recover an
+//! EIP-191 signed message, while Optimism and Base verifiers use EIP-712
+//! typed data. Both bind the chain id and verifier, so a signed payout
+//! can't be replayed on another target. `BlockchainService` submits the
+//! payload with the target chain's provider and signer.
+
+use anyhow::{anyhow, Result};
+use ethers::abi::{encode, Token};
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::{hash_message, id, keccak256};
+use serde::{Deserialize, Serialize};
+
+/// EIP-712 domain name and version the verifier contracts use
+const EIP712_NAME: &str = "MycelixSettlement";
+const EIP712_VERSION: &str = "1";
+
+const EIP712_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+
+const SETTLEMENT_TYPE: &str =
+    "Settlement(bytes32 batchId,address recipient,uint256 amount,bytes32 merkleRoot)";
+
+/// Mirror of `plays_integrity::SettlementChain`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementChain {
+    #[default]
+    Gnosis,
+    Optimism,
+    Base,
+}
+
+/// How a verifier expects the settler's signature over a payout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureScheme {
+    /// `personal_sign` over the hash of the ABI-encoded payout
+    Eip191,
+    /// Typed `Settlement` data under the verifier's domain
+    Eip712,
+}
+
+impl SettlementChain {
+    pub fn signature_scheme(self) -> SignatureScheme {
+        match self {
+            Self::Gnosis => SignatureScheme::Eip191,
+            Self::Optimism | Self::Base => SignatureScheme::Eip712,
+        }
+    }
+}
+
+/// Mirror of `plays_integrity::SettlementTarget`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementTarget {
+    pub chain: SettlementChain,
+    pub chain_id: u64,
+    /// Empty until a verifier is deployed
+    #[serde(default)]
+    pub verifier: String,
+}
+
+impl Default for SettlementTarget {
+    /// Gnosis Chain, for batches from before targets were recorded
+    fn default() -> Self {
+        Self {
+            chain: SettlementChain::Gnosis,
+            chain_id: 100,
+            verifier: String::new(),
+        }
+    }
+}
+
+/// What a settlement pays out, as the verifier sees it
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchPayout {
+    /// keccak256 of the batch's action hash
+    pub batch_id: H256,
+    /// The artist's payment address
+    pub recipient: Address,
+    /// Batch `total_amount` (wei)
+    pub amount: U256,
+    pub merkle_root: H256,
+}
+
+impl BatchPayout {
+    pub fn new(
+        batch_hash: &str,
+        recipient: Address,
+        amount: u64,
+        merkle_root: &[u8],
+    ) -> Result<Self> {
+        if merkle_root.len() != 32 {
+            return Err(anyhow!("Merkle root must be 32 bytes, not {}", merkle_root.len()));
+        }
+        Ok(Self {
+            batch_id: H256(keccak256(batch_hash.as_bytes())),
+            recipient,
+            amount: U256::from(amount),
+            merkle_root: H256::from_slice(merkle_root),
+        })
+    }
+}
+
+/// A payout formatted for one target: the digest the settler signs and
+/// the call that submits it
+#[derive(Debug, Clone)]
+pub struct SettlementPayload {
+    pub chain_id: u64,
+    pub verifier: Address,
+    pub scheme: SignatureScheme,
+    pub digest: H256,
+    pub payout: BatchPayout,
+}
+
+impl SettlementPayload {
+    /// Format a payout for a target; fails if it has no verifier
+    pub fn new(target: &SettlementTarget, payout: BatchPayout) -> Result<Self> {
+        let verifier: Address = target.verifier.parse().map_err(|_| {
+            anyhow!("No settlement verifier configured for chain {}", target.chain_id)
+        })?;
+        let scheme = target.chain.signature_scheme();
+        let digest = match scheme {
+            SignatureScheme::Eip191 => eip191_digest(target.chain_id, verifier, &payout),
+            SignatureScheme::Eip712 => eip712_digest(target.chain_id, verifier, &payout),
+        };
+
+        Ok(Self {
+            chain_id: target.chain_id,
+            verifier,
+            scheme,
+            digest,
+            payout,
+        })
+    }
+
+    /// `settleBatch(batchId, recipient, amount, merkleRoot, signature)` calldata
+    pub fn calldata(&self, signature: &[u8]) -> Bytes {
+        let mut data = id("settleBatch(bytes32,address,uint256,bytes32,bytes)").to_vec();
+        data.extend(encode(&[
+            Token::FixedBytes(self.payout.batch_id.as_bytes().to_vec()),
+            Token::Address(self.payout.recipient),
+            Token::Uint(self.payout.amount),
+            Token::FixedBytes(self.payout.merkle_root.as_bytes().to_vec()),
+            Token::Bytes(signature.to_vec()),
+        ]));
+        data.into()
+    }
+}
+
+fn eip191_digest(chain_id: u64, verifier: Address, payout: &BatchPayout) -> H256 {
+    let message = keccak256(encode(&[
+        Token::Uint(U256::from(chain_id)),
+        Token::Address(verifier),
+        Token::FixedBytes(payout.batch_id.as_bytes().to_vec()),
+        Token::Address(payout.recipient),
+        Token::Uint(payout.amount),
+        Token::FixedBytes(payout.merkle_root.as_bytes().to_vec()),
+    ]));
+    hash_message(message)
+}
+
+fn eip712_digest(chain_id: u64, verifier: Address, payout: &BatchPayout) -> H256 {
+    let domain_separator = keccak256(encode(&[
+        Token::FixedBytes(keccak256(EIP712_DOMAIN_TYPE).to_vec()),
+        Token::FixedBytes(keccak256(EIP712_NAME).to_vec()),
+        Token::FixedBytes(keccak256(EIP712_VERSION).to_vec()),
+        Token::Uint(U256::from(chain_id)),
+        Token::Address(verifier),
+    ]));
+    let struct_hash = keccak256(encode(&[
+        Token::FixedBytes(keccak256(SETTLEMENT_TYPE).to_vec()),
+        Token::FixedBytes(payout.batch_id.as_bytes().to_vec()),
+        Token::Address(payout.recipient),
+        Token::Uint(payout.amount),
+        Token::FixedBytes(payout.merkle_root.as_bytes().to_vec()),
+    ]));
+
+    let mut typed = vec![0x19, 0x01];
+    typed.extend_from_slice(&domain_separator);
+    typed.extend_from_slice(&struct_hash);
+    H256(keccak256(typed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{decode, ParamType};
+
+    const VERIFIER: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn target(chain: SettlementChain, chain_id: u64) -> SettlementTarget {
+        SettlementTarget {
+            chain,
+            chain_id,
+            verifier: VERIFIER.to_string(),
+        }
+    }
+
+    fn payout() -> BatchPayout {
+        BatchPayout::new("uhCkkBatch", Address::repeat_byte(0x11), 1_000_000, &[0x22; 32]).unwrap()
+    }
+
+    fn payload(chain: SettlementChain, chain_id: u64) -> SettlementPayload {
+        SettlementPayload::new(&target(chain, chain_id), payout()).unwrap()
+    }
+
+    #[test]
+    fn gnosis_signs_a_personal_message() {
+        let payload = payload(SettlementChain::Gnosis, 100);
+        assert_eq!(payload.scheme, SignatureScheme::Eip191);
+
+        let payout = payout();
+        let message = keccak256(encode(&[
+            Token::Uint(100.into()),
+            Token::Address(VERIFIER.parse().unwrap()),
+            Token::FixedBytes(payout.batch_id.as_bytes().to_vec()),
+            Token::Address(payout.recipient),
+            Token::Uint(payout.amount),
+            Token::FixedBytes(vec![0x22; 32]),
+        ]));
+        assert_eq!(payload.digest, hash_message(message));
+    }
+
+    #[test]
+    fn rollups_sign_typed_data_bound_to_their_chain() {
+        let optimism = payload(SettlementChain::Optimism, 10);
+        let base = payload(SettlementChain::Base, 8453);
+        assert_eq!(optimism.scheme, SignatureScheme::Eip712);
+        assert_eq!(base.scheme, SignatureScheme::Eip712);
+        assert_ne!(optimism.digest, base.digest);
+
+        // Same chain id, different scheme
+        let gnosis = payload(SettlementChain::Gnosis, 10);
+        assert_ne!(gnosis.digest, optimism.digest);
+
+        let elsewhere = SettlementTarget {
+            verifier: "0x00000000000000000000000000000000000000bb".to_string(),
+            ..target(SettlementChain::Optimism, 10)
+        };
+        let moved = SettlementPayload::new(&elsewhere, payout()).unwrap();
+        assert_ne!(moved.digest, optimism.digest);
+    }
+
+    #[test]
+    fn calldata_carries_the_payout_and_signature() {
+        for (chain, chain_id) in [
+            (SettlementChain::Gnosis, 100),
+            (SettlementChain::Optimism, 10),
+            (SettlementChain::Base, 8453),
+        ] {
+            let calldata = payload(chain, chain_id).calldata(&[0x33; 65]);
+            assert_eq!(
+                &calldata[..4],
+                &id("settleBatch(bytes32,address,uint256,bytes32,bytes)")[..]
+            );
+
+            let tokens = decode(
+                &[
+                    ParamType::FixedBytes(32),
+                    ParamType::Address,
+                    ParamType::Uint(256),
+                    ParamType::FixedBytes(32),
+                    ParamType::Bytes,
+                ],
+                &calldata[4..],
+            )
+            .unwrap();
+            assert_eq!(tokens[1], Token::Address(Address::repeat_byte(0x11)));
+            assert_eq!(tokens[2], Token::Uint(1_000_000.into()));
+            assert_eq!(tokens[4], Token::Bytes(vec![0x33; 65]));
+        }
+    }
+
+    #[test]
+    fn targets_need_a_verifier_and_payouts_a_full_root() {
+        let undeployed = SettlementTarget::default();
+        assert!(SettlementPayload::new(&undeployed, payout()).is_err());
+        assert!(BatchPayout::new("uhCkkBatch", Address::zero(), 1, &[0x22; 39]).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Thumbnail Service - Cover-art renditions
+//!
+//! Downscales cover images to small WebP thumbnails so list views
+//! don't pull full-resolution artwork.
+
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+
+/// Content type of generated thumbnails
+pub const THUMBNAIL_CONTENT_TYPE: &str = "image/webp";
+
+/// Thumbnail sizes (longest edge, px); requests outside this list are rejected
+pub const THUMBNAIL_SIZES: &[u32] = &[128, 512];
+
+/// Widest or tallest source image decoded (px)
+const MAX_SOURCE_DIMENSION: u32 = 8192;
+
+/// Most memory the decoder may allocate for a source image (128MB)
+const MAX_DECODE_ALLOC: u64 = 128 * 1024 * 1024;
+
+/// Image types accepted as cover art
+pub const IMAGE_TYPES: &[&str] = &["image/jpeg", "image/png", "image/webp"];
+
+/// Whether a content type is cover art
+pub fn is_image(content_type: &str) -> bool {
+    IMAGE_TYPES.contains(&content_type)
+}
+
+/// Render a WebP thumbnail whose longest edge is `size` pixels
+///
+/// Decoding and resizing are CPU-bound, so this runs on the blocking pool.
+pub async fn generate_thumbnail(data: Vec<u8>, size: u32) -> Result<Vec<u8>> {
+    if !THUMBNAIL_SIZES.contains(&size) {
+        return Err(anyhow!("Unsupported thumbnail size: {}", size));
+    }
+
+    tokio::task::spawn_blocking(move || render(&data, size)).await?
+}
+
+fn render(data: &[u8], size: u32) -> Result<Vec<u8>> {
+    // A small file can declare huge dimensions; refuse before allocating
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    let mut reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    reader.limits(limits);
+    let image = reader.decode()?;
+
+    // Never upscale small covers
+    let thumb = if image.width() > size || image.height() > size {
+        image.thumbnail(size, size)
+    } else {
+        image
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(thumb.to_rgba8()).write_to(&mut out, ImageFormat::WebP)?;
+    Ok(out.into_inner())
+}
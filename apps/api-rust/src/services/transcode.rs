@@ -0,0 +1,124 @@
+//! Transcode Service - Web-friendly streaming renditions
+//!
+//! Shells out to ffmpeg to turn lossless uploads (WAV/FLAC) into a
+//! compact AAC rendition for browser streaming. The original file is
+//! kept for download purchases. Also cuts short faded preview clips for
+//! gated songs.
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Content type of the streaming rendition
+pub const STREAMING_CONTENT_TYPE: &str = "audio/mp4";
+
+/// Supported streaming bitrates (kbps)
+pub const ALLOWED_BITRATES: &[u32] = &[128, 256];
+
+/// Default preview clip length (seconds)
+pub const DEFAULT_PREVIEW_SECS: u32 = 30;
+
+/// Longest preview clip allowed (seconds)
+pub const MAX_PREVIEW_SECS: u32 = 60;
+
+/// Preview clip bitrate (kbps)
+const PREVIEW_BITRATE_KBPS: u32 = 128;
+
+/// Lossless types that benefit from a streaming rendition
+const LOSSLESS_TYPES: &[&str] = &["audio/wav", "audio/x-wav", "audio/flac", "audio/x-flac"];
+
+/// Default limit on one ffmpeg run (seconds)
+pub const DEFAULT_FFMPEG_TIMEOUT_SECS: u64 = 300;
+
+/// ffmpeg-backed transcoder
+pub struct TranscodeService {
+    ffmpeg_path: String,
+    /// Longest an ffmpeg run may take before it is killed
+    timeout: Duration,
+}
+
+impl TranscodeService {
+    pub fn new(ffmpeg_path: &str, timeout: Duration) -> Self {
+        Self {
+            ffmpeg_path: ffmpeg_path.to_string(),
+            timeout,
+        }
+    }
+
+    /// Whether a content type should get a streaming rendition
+    pub fn should_transcode(content_type: &str) -> bool {
+        LOSSLESS_TYPES.contains(&content_type)
+    }
+
+    /// Transcode audio to AAC (MP4 container, faststart for streaming)
+    pub async fn to_streaming_aac(&self, input: &[u8], bitrate_kbps: u32) -> Result<Vec<u8>> {
+        self.to_aac(input, bitrate_kbps, &[]).await
+    }
+
+    /// Cut the first `duration_secs` into an AAC clip that fades in and out
+    pub async fn preview_clip(&self, input: &[u8], duration_secs: u32) -> Result<Vec<u8>> {
+        let duration = duration_secs as f32;
+        let fade = (duration / 4.0).min(2.0);
+        let filter = format!(
+            "afade=t=in:st=0:d={fade},afade=t=out:st={start}:d={fade}",
+            fade = fade,
+            start = duration - fade
+        );
+        let args = ["-t".to_string(), duration_secs.to_string(), "-af".to_string(), filter];
+        self.to_aac(input, PREVIEW_BITRATE_KBPS, &args).await
+    }
+
+    /// Run ffmpeg to AAC with extra output options
+    async fn to_aac(&self, input: &[u8], bitrate_kbps: u32, extra_args: &[String]) -> Result<Vec<u8>> {
+        let id = uuid::Uuid::new_v4();
+        let in_path = temp_path(&format!("mycelix-{}-in", id));
+        let out_path = temp_path(&format!("mycelix-{}-out.m4a", id));
+
+        tokio::fs::write(&in_path, input).await?;
+
+        // A malformed upload can keep ffmpeg busy indefinitely; on timeout
+        // the wait is dropped and kill_on_drop kills the child
+        let run = Command::new(&self.ffmpeg_path)
+            .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+            .arg(&in_path)
+            .args(extra_args)
+            .args(["-vn", "-c:a", "aac", "-b:a"])
+            .arg(format!("{}k", bitrate_kbps))
+            .args(["-movflags", "+faststart"])
+            .arg(&out_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let result = match run {
+            Ok(child) => tokio::time::timeout(self.timeout, child.wait_with_output()).await,
+            Err(e) => Ok(Err(e)),
+        };
+
+        let output = match result {
+            Ok(Ok(output)) if output.status.success() => {
+                tokio::fs::read(&out_path).await.map_err(Into::into)
+            }
+            Ok(Ok(output)) => Err(anyhow!(
+                "ffmpeg exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+            Ok(Err(e)) => Err(anyhow!("Failed to run ffmpeg: {}", e)),
+            Err(_) => Err(anyhow!("ffmpeg timed out after {}s", self.timeout.as_secs())),
+        };
+
+        // Always clean up temp files
+        let _ = tokio::fs::remove_file(&in_path).await;
+        let _ = tokio::fs::remove_file(&out_path).await;
+
+        output
+    }
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(name)
+}
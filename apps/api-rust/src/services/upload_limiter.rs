@@ -0,0 +1,128 @@
+//! Upload Limiter - Backpressure for the upload endpoint
+//!
+//! Caps simultaneous uploads with a semaphore and bounds the total bytes
+//! buffered across all in-flight uploads. Callers that can't get a slot
+//! or byte budget are told to retry later instead of queueing.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default maximum simultaneous uploads
+const DEFAULT_MAX_CONCURRENT: usize = 8;
+
+/// Default in-flight byte budget (512MB)
+const DEFAULT_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Suggested client backoff when the limiter is saturated (seconds)
+pub const RETRY_AFTER_SECS: u64 = 5;
+
+/// Snapshot of limiter usage for tuning
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadMetrics {
+    pub in_flight: usize,
+    pub max_concurrent: usize,
+    pub bytes_in_flight: u64,
+    pub max_bytes: u64,
+    pub rejected_total: u64,
+}
+
+/// Concurrency and byte-budget limiter shared by all uploads
+pub struct UploadLimiter {
+    slots: Arc<Semaphore>,
+    max_concurrent: usize,
+    bytes_in_flight: Arc<AtomicU64>,
+    max_bytes: u64,
+    rejected_total: AtomicU64,
+}
+
+impl UploadLimiter {
+    pub fn new(max_concurrent: usize, max_bytes: u64) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            bytes_in_flight: Arc::new(AtomicU64::new(0)),
+            max_bytes,
+            rejected_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Configure from `UPLOAD_MAX_CONCURRENT` and `UPLOAD_MAX_INFLIGHT_BYTES`
+    pub fn from_env() -> Self {
+        let max_concurrent = std::env::var("UPLOAD_MAX_CONCURRENT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CONCURRENT);
+        let max_bytes = std::env::var("UPLOAD_MAX_INFLIGHT_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BYTES);
+
+        Self::new(max_concurrent, max_bytes)
+    }
+
+    /// Claim an upload slot without waiting
+    pub fn try_acquire(&self) -> Option<UploadPermit> {
+        match self.slots.clone().try_acquire_owned() {
+            Ok(slot) => Some(UploadPermit {
+                _slot: slot,
+                bytes_in_flight: self.bytes_in_flight.clone(),
+                max_bytes: self.max_bytes,
+                reserved: 0,
+            }),
+            Err(_) => {
+                self.rejected_total.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Count a rejection that happened after a slot was granted
+    pub fn record_rejection(&self) {
+        self.rejected_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> UploadMetrics {
+        UploadMetrics {
+            in_flight: self.max_concurrent - self.slots.available_permits(),
+            max_concurrent: self.max_concurrent,
+            bytes_in_flight: self.bytes_in_flight.load(Ordering::Relaxed),
+            max_bytes: self.max_bytes,
+            rejected_total: self.rejected_total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// An upload slot plus the bytes it has reserved; both are released on drop
+pub struct UploadPermit {
+    _slot: OwnedSemaphorePermit,
+    bytes_in_flight: Arc<AtomicU64>,
+    max_bytes: u64,
+    reserved: u64,
+}
+
+impl UploadPermit {
+    /// Reserve more of the global byte budget; false if it would be exceeded
+    pub fn reserve(&mut self, bytes: u64) -> bool {
+        let reserved = self
+            .bytes_in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                current
+                    .checked_add(bytes)
+                    .filter(|total| *total <= self.max_bytes)
+            })
+            .is_ok();
+
+        if reserved {
+            self.reserved += bytes;
+        }
+        reserved
+    }
+}
+
+impl Drop for UploadPermit {
+    fn drop(&mut self) {
+        self.bytes_in_flight.fetch_sub(self.reserved, Ordering::AcqRel);
+    }
+}
@@ -0,0 +1,183 @@
+//! Upload Progress - Live byte counts for in-flight uploads
+//!
+//! Clients pick an upload id, open the progress stream for it and then
+//! start the upload with the same id. Either side may arrive first; the
+//! channel is created on demand and keeps its final state for a minute so
+//! late subscribers still see how the upload ended. Channels nobody
+//! publishes to are dropped after ten minutes.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Channels nobody published to within this long are pruned, and progress
+/// streams that saw no update for this long end
+pub const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// How long a finished upload's final state is kept for late subscribers
+const FINISHED_TTL: Duration = Duration::from_secs(60);
+
+/// Received bytes between updates, so large files don't flood subscribers
+const PUBLISH_EVERY_BYTES: u64 = 256 * 1024;
+
+/// Where an upload is in the pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadStage {
+    /// Waiting for the upload request to start
+    Pending,
+    /// Reading the multipart body
+    Receiving,
+    /// Adding the file to IPFS
+    Storing,
+    /// Transcoding and thumbnails
+    Processing,
+    Done,
+    Failed,
+}
+
+/// Progress snapshot sent to subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub stage: UploadStage,
+    pub bytes_received: u64,
+    /// From the request's Content-Length, so it includes multipart framing
+    pub expected_bytes: Option<u64>,
+}
+
+impl UploadProgress {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.stage, UploadStage::Done | UploadStage::Failed)
+    }
+}
+
+struct Channel {
+    sender: watch::Sender<UploadProgress>,
+    touched_at: Instant,
+}
+
+/// Registry of progress channels keyed by client-chosen upload id
+pub struct UploadTracker {
+    channels: Mutex<HashMap<String, Channel>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl UploadTracker {
+    pub fn new(shutdown: watch::Receiver<bool>) -> Self {
+        Self {
+            channels: Mutex::new(HashMap::new()),
+            shutdown,
+        }
+    }
+
+    /// Shutdown signal for progress streams, so they end with the server
+    pub fn shutdown(&self) -> watch::Receiver<bool> {
+        self.shutdown.clone()
+    }
+
+    /// Subscribe to an upload's progress, creating its channel if needed
+    pub fn subscribe(&self, upload_id: &str) -> watch::Receiver<UploadProgress> {
+        let mut channels = self.channels.lock().expect("upload tracker lock poisoned");
+        prune(&mut channels);
+        channels
+            .entry(upload_id.to_string())
+            .or_insert_with(new_channel)
+            .sender
+            .subscribe()
+    }
+
+    /// Start reporting for an upload
+    pub fn start<'a>(&'a self, upload_id: &str, expected_bytes: Option<u64>) -> UploadReporter<'a> {
+        prune(&mut self.channels.lock().expect("upload tracker lock poisoned"));
+        let reporter = UploadReporter {
+            tracker: self,
+            upload_id: upload_id.to_string(),
+            progress: UploadProgress {
+                stage: UploadStage::Receiving,
+                bytes_received: 0,
+                expected_bytes,
+            },
+            unpublished_bytes: 0,
+        };
+        reporter.tracker.publish(&reporter.upload_id, &reporter.progress);
+        reporter
+    }
+
+    fn publish(&self, upload_id: &str, progress: &UploadProgress) {
+        let mut channels = self.channels.lock().expect("upload tracker lock poisoned");
+        prune(&mut channels);
+        let channel = channels
+            .entry(upload_id.to_string())
+            .or_insert_with(new_channel);
+        channel.touched_at = Instant::now();
+        channel.sender.send_replace(progress.clone());
+    }
+}
+
+/// Drop finished and abandoned channels; subscribers still waiting on them
+/// see the stream end
+fn prune(channels: &mut HashMap<String, Channel>) {
+    channels.retain(|_, c| {
+        let ttl = if c.sender.borrow().is_finished() {
+            FINISHED_TTL
+        } else {
+            STALE_AFTER
+        };
+        c.touched_at.elapsed() < ttl
+    });
+}
+
+fn new_channel() -> Channel {
+    let (sender, _) = watch::channel(UploadProgress {
+        stage: UploadStage::Pending,
+        bytes_received: 0,
+        expected_bytes: None,
+    });
+    Channel {
+        sender,
+        touched_at: Instant::now(),
+    }
+}
+
+/// Publishes progress for one upload; reports `Failed` if dropped
+/// before `done`
+pub struct UploadReporter<'a> {
+    tracker: &'a UploadTracker,
+    upload_id: String,
+    progress: UploadProgress,
+    unpublished_bytes: u64,
+}
+
+impl UploadReporter<'_> {
+    pub fn received(&mut self, bytes: u64) {
+        self.progress.bytes_received += bytes;
+        self.unpublished_bytes += bytes;
+        if self.unpublished_bytes >= PUBLISH_EVERY_BYTES {
+            self.publish();
+        }
+    }
+
+    pub fn stage(&mut self, stage: UploadStage) {
+        self.progress.stage = stage;
+        self.publish();
+    }
+
+    pub fn done(mut self) {
+        self.stage(UploadStage::Done);
+    }
+
+    fn publish(&mut self) {
+        self.unpublished_bytes = 0;
+        self.tracker.publish(&self.upload_id, &self.progress);
+    }
+}
+
+impl Drop for UploadReporter<'_> {
+    fn drop(&mut self) {
+        if !self.progress.is_finished() {
+            self.stage(UploadStage::Failed);
+        }
+    }
+}
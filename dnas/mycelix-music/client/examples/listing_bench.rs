@@ -0,0 +1,190 @@
+//! Catalog listing benchmark
+//!
+//! Times `get_all_songs`, which fetches each page's songs with one batched
+//! `get`, against fetching the same songs one `get_song` call at a time
+//! (the N+1 pattern the listings used before). Seeds the catalog up to
+//! `BENCH_SONGS` songs first.
+//!
+//! Needs a running conductor with the DNA installed:
+//!
+//! ```text
+//! MYCELIX_ADMIN_URL=ws://localhost:4444 MYCELIX_APP_URL=ws://localhost:8888 \
+//!     MYCELIX_APP_ID=mycelix-music BENCH_SONGS=300 cargo run --release --example listing_bench
+//! ```
+//!
+//! The per-song baseline also pays a websocket round trip per call, so it
+//! overstates the in-zome cost of N+1 fetches; compare the two on the same
+//! conductor rather than reading either as an absolute number.
+
+use holochain_client::{AdminWebsocket, AppWebsocket, AuthorizeSigningCredentialsPayload, CellInfo};
+use mycelix_client::catalog::Song;
+use mycelix_client::{
+    ActionHash, AgentPubKey, ClientAgentSigner, ClientConfig, MycelixClient, PageInput, Timestamp,
+    DEFAULT_ROLE_NAME,
+};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Timed runs of each approach; the median is reported
+const RUNS: usize = 5;
+
+/// Listing page size (the zome's default)
+const PAGE_SIZE: usize = 100;
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let admin_url = env_or("MYCELIX_ADMIN_URL", "ws://localhost:4444");
+    let app_url = env_or("MYCELIX_APP_URL", "ws://localhost:8888");
+    let app_id = env_or("MYCELIX_APP_ID", "mycelix-music");
+    let songs: usize = env_or("BENCH_SONGS", "300").parse()?;
+
+    let (client, artist) = connect(&admin_url, &app_url, &app_id).await?;
+
+    let existing = list_all(&client).await?.len();
+    for i in existing..songs {
+        client.create_song(&bench_song(&artist, i)).await?;
+    }
+    let hashes = action_hashes(&client, &list_all(&client).await?).await?;
+    println!("Catalog holds {} songs", hashes.len());
+
+    let batched = median(RUNS, || async {
+        list_all(&client).await?;
+        Ok(())
+    })
+    .await?;
+    let sequential = median(RUNS, || async {
+        for hash in &hashes {
+            client.get_song(hash).await?;
+        }
+        Ok(())
+    })
+    .await?;
+
+    let per_song = |total: Duration| total / hashes.len().max(1) as u32;
+    println!(
+        "get_all_songs (batched, {} per page): {:?} total, {:?} per song",
+        PAGE_SIZE,
+        batched,
+        per_song(batched)
+    );
+    println!(
+        "get_song per song (N+1):              {:?} total, {:?} per song",
+        sequential,
+        per_song(sequential)
+    );
+    Ok(())
+}
+
+/// Authorize a signing key for the app's cell and open a client with it
+async fn connect(
+    admin_url: &str,
+    app_url: &str,
+    app_id: &str,
+) -> Result<(MycelixClient, AgentPubKey), Box<dyn Error>> {
+    let mut app = AppWebsocket::connect(app_url.to_string()).await?;
+    let info = app
+        .app_info(app_id.to_string())
+        .await
+        .map_err(|e| format!("app_info failed: {:?}", e))?
+        .ok_or_else(|| format!("App {} is not installed", app_id))?;
+    let cell_id = match info.cell_info.get(DEFAULT_ROLE_NAME).and_then(|cells| cells.first()) {
+        Some(CellInfo::Provisioned(cell)) => cell.cell_id.clone(),
+        _ => return Err(format!("No provisioned {} cell", DEFAULT_ROLE_NAME).into()),
+    };
+
+    let mut admin = AdminWebsocket::connect(admin_url.to_string()).await?;
+    let credentials = admin
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await?;
+    let signer = ClientAgentSigner::default();
+    signer.add_credentials(cell_id.clone(), credentials);
+
+    let client = MycelixClient::connect(
+        ClientConfig::new(app_url, app_id),
+        Arc::new(Box::new(signer)),
+    )
+    .await?;
+    Ok((client, cell_id.agent_pubkey().clone()))
+}
+
+/// Page through every listed song
+async fn list_all(client: &MycelixClient) -> Result<Vec<Song>, Box<dyn Error>> {
+    let mut songs = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = client
+            .get_all_songs(&PageInput {
+                cursor,
+                limit: Some(PAGE_SIZE),
+            })
+            .await?;
+        songs.extend(page.items);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(songs),
+        }
+    }
+}
+
+/// The catalog action hash of each song, for the per-song baseline
+async fn action_hashes(
+    client: &MycelixClient,
+    songs: &[Song],
+) -> Result<Vec<ActionHash>, Box<dyn Error>> {
+    let mut hashes = Vec::with_capacity(songs.len());
+    for song in songs {
+        let hash = client
+            .get_song_hash_by_content_hash(&song.song_hash)
+            .await?
+            .ok_or_else(|| format!("Listed song {} has no index", song.song_hash))?;
+        hashes.push(hash);
+    }
+    Ok(hashes)
+}
+
+/// Median wall time of `runs` runs of `run`
+async fn median<F, Fut>(runs: usize, run: F) -> Result<Duration, Box<dyn Error>>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let mut times = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let started = Instant::now();
+        run().await?;
+        times.push(started.elapsed());
+    }
+    times.sort();
+    Ok(times[runs / 2])
+}
+
+fn bench_song(artist: &AgentPubKey, i: usize) -> Song {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or(0);
+    Song {
+        song_hash: format!("bench-song-{}", i),
+        title: format!("Bench Song {}", i),
+        artist: artist.clone(),
+        ipfs_cid: format!("bafybench{}", i),
+        cover_cid: None,
+        duration_seconds: 180,
+        genres: vec!["bench".to_string()],
+        strategy_id: "pay_per_stream".to_string(),
+        released_at: Timestamp::from_micros(now),
+        metadata: String::new(),
+        min_play_override: None,
+        isrc: None,
+        earnings_frozen: false,
+        earnings_freeze: None,
+    }
+}
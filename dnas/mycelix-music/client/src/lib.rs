@@ -0,0 +1,283 @@
+//! Mycelix Client - Typed zome calls for Rust consumers
+//!
+//! `MycelixClient` has one method per coordinator extern, taking and
+//! returning the zomes' own input and output types, so a renamed extern
+//! or a changed struct fails to compile instead of failing at runtime.
+//! The types are re-exported by zome (`mycelix_client::plays::RecordPlayInput`).
+//!
+//! Calls go over a `holochain_client` app websocket, signed with the
+//! signer the client was created with (see `ClientAgentSigner`). Every
+//! call has a timeout. A connection that drops is reopened on the next
+//! call with exponential backoff; the failed call itself is not retried,
+//! since the conductor may already have run it.
+
+use hdk::prelude::{ExternIO, FunctionName, ZomeName};
+use holochain_client::{AgentSigner, AppAgentWebsocket, ConductorApiError, ZomeCallTarget};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+pub use hdk::prelude::{ActionHash, AgentPubKey, Timestamp};
+pub use holochain_client::ClientAgentSigner;
+pub use pagination::{Page, PageCursor, PageInput};
+
+mod zomes;
+
+pub mod catalog {
+    //! Catalog zome types
+    pub use ::catalog::{
+        AlbumWithSongs, CatalogSignal, FreezeSongEarningsInput, GetNewReleasesInput,
+        GetSongsByArtistInput, GetSongsByGenreInput, RescheduleSongInput, ScheduledSong,
+    };
+    pub use catalog_integrity::{
+        payment_address_claim_message, Album, ArtistOnboarding, ArtistProfile, EarningsFreeze,
+        FreezeAuthority, PaymentAddressClaim, Song, SongMetadata,
+    };
+}
+
+pub mod plays {
+    //! Plays zome types
+    pub use ::plays::{
+        AmendPlayInput, BalanceOwed, BatchOutcome, GetAllPendingSettlementsInput,
+        GetSongAttestationsInput, GetUnsettledPlaysPageInput, PendingSettlement, PlayPreview,
+        PlayRate, PlayReceipt, PreviewPlayInput, RecordPlayInput, RecordPlayOutput,
+        RewardSummary, SettleAllOutput, SettlementEstimate, SettlementProof,
+        SettlementProofInput, SongAttestationsPage, SongStats, StrategyFeeRate,
+        UnsettledPlaysPage, UpdateSettlementStatusInput, VerifyProofInput,
+    };
+    pub use plays_integrity::{
+        MerkleAlgorithm, PlayAttestation, PlayRecord, PlaySignalMode, SettlementBatch,
+        SettlementChain, SettlementStatus, SettlementTarget,
+    };
+}
+
+pub mod balances {
+    //! Balances zome types
+    pub use ::balances::{
+        AccountReconciliation, ArtistStats, ConfirmDepositInput, ExecuteTendTransferInput,
+        ExecuteTransferInput, FilteredTransfers, GetProtocolRevenueInput, GetSpendingSummaryInput,
+        GetTransfersFilteredInput, ProtocolRevenue, ReconcileAccountInput, RecordDepositInput,
+        SetSpendingLimitsInput, SpendingAllowance, SpendingSummary, TendBalance,
+    };
+    pub use balances_integrity::{
+        ArtistAccount, BalanceDrift, CashoutRequest, CashoutStatus, ListenerAccount, TendAccount,
+        TendTransfer, Transfer, TransferReason,
+    };
+}
+
+pub mod trust {
+    //! Trust zome types
+    pub use ::trust::{
+        CreateTrustClaimInput, GetPersonalizedNodesInput, ListenerReputationSummary,
+        QualityReportResult, RankedCdnNode, RegisterCdnNodeInput,
+        ReportByzantineInput, ReportQuorum, SubmitQualityReportInput,
+    };
+    pub use trust_integrity::{
+        ByzantineBehavior, ByzantineReport, CdnNodeReputation, ReportStatus, ServiceChallenge,
+        SignedServiceResult, TrustClaim, TrustClaimType, VerificationStatus, VerificationTier,
+    };
+}
+
+/// Role the mycelix-music DNA is installed under
+pub const DEFAULT_ROLE_NAME: &str = "mycelix-music";
+
+/// Why a zome call failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientError {
+    /// The app websocket couldn't be (re)opened
+    Connect(String),
+    /// The call didn't return within `call_timeout`
+    Timeout { function: String, after: Duration },
+    /// The input or output didn't (de)serialize as the extern's types
+    Serialization(String),
+    /// The conductor or the zome returned an error
+    Conductor(String),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connect(reason) => write!(f, "Connect: {}", reason),
+            Self::Timeout { function, after } => {
+                write!(f, "Timeout: {} took longer than {:?}", function, after)
+            }
+            Self::Serialization(reason) => write!(f, "Serialization: {}", reason),
+            Self::Conductor(reason) => write!(f, "Conductor: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// How a dropped connection is reopened
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Connection attempts before a call fails with `ClientError::Connect`
+    pub max_attempts: u32,
+    /// Wait before the second attempt; doubles after each failure
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Wait before connection attempt `attempt` (0 is the first, no wait)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        if attempt == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 1u32.checked_shl(attempt - 1).unwrap_or(u32::MAX);
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Where the app runs and how patient to be with it
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// App interface websocket, e.g. `ws://localhost:8888`
+    pub url: String,
+    pub app_id: String,
+    pub role_name: String,
+    pub call_timeout: Duration,
+    pub reconnect: ReconnectPolicy,
+}
+
+impl ClientConfig {
+    pub fn new(url: impl Into<String>, app_id: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            app_id: app_id.into(),
+            role_name: DEFAULT_ROLE_NAME.to_string(),
+            call_timeout: Duration::from_secs(30),
+            reconnect: ReconnectPolicy::default(),
+        }
+    }
+}
+
+/// Typed client for the mycelix-music DNA
+pub struct MycelixClient {
+    config: ClientConfig,
+    signer: Arc<Box<dyn AgentSigner + Send + Sync>>,
+    /// None after the connection dropped, until the next call reopens it
+    connection: Mutex<Option<AppAgentWebsocket>>,
+}
+
+impl MycelixClient {
+    /// Connect to the app, retrying per the config's reconnect policy
+    pub async fn connect(
+        config: ClientConfig,
+        signer: Arc<Box<dyn AgentSigner + Send + Sync>>,
+    ) -> Result<Self, ClientError> {
+        let client = Self {
+            config,
+            signer,
+            connection: Mutex::new(None),
+        };
+        client.connection().await?;
+        Ok(client)
+    }
+
+    /// The open connection, reopening it if it dropped
+    async fn connection(&self) -> Result<AppAgentWebsocket, ClientError> {
+        let mut connection = self.connection.lock().await;
+        if let Some(open) = connection.as_ref() {
+            return Ok(open.clone());
+        }
+
+        let policy = &self.config.reconnect;
+        let mut last_error = String::new();
+        for attempt in 0..policy.max_attempts.max(1) {
+            tokio::time::sleep(policy.delay(attempt)).await;
+            let connecting = AppAgentWebsocket::connect(
+                self.config.url.clone(),
+                self.config.app_id.clone(),
+                self.signer.clone(),
+            );
+            match tokio::time::timeout(self.config.call_timeout, connecting).await {
+                Ok(Ok(open)) => {
+                    *connection = Some(open.clone());
+                    return Ok(open);
+                }
+                Ok(Err(e)) => last_error = e.to_string(),
+                Err(_) => last_error = format!("timed out after {:?}", self.config.call_timeout),
+            }
+        }
+        Err(ClientError::Connect(format!(
+            "{} after {} attempts: {}",
+            self.config.url,
+            policy.max_attempts.max(1),
+            last_error
+        )))
+    }
+
+    /// Call any extern by name; prefer the typed methods
+    pub async fn call<I, O>(&self, zome: &str, function: &str, input: &I) -> Result<O, ClientError>
+    where
+        I: Serialize + std::fmt::Debug,
+        O: DeserializeOwned + std::fmt::Debug,
+    {
+        let payload =
+            ExternIO::encode(input).map_err(|e| ClientError::Serialization(e.to_string()))?;
+        let mut connection = self.connection().await?;
+        let calling = connection.call_zome(
+            ZomeCallTarget::RoleName(self.config.role_name.clone()),
+            ZomeName::from(zome),
+            FunctionName::from(function),
+            payload,
+        );
+
+        let output = match tokio::time::timeout(self.config.call_timeout, calling).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(ConductorApiError::WebsocketError(e))) => {
+                // Reopened by the next call
+                self.connection.lock().await.take();
+                return Err(ClientError::Connect(format!("{:?}", e)));
+            }
+            Ok(Err(e)) => return Err(ClientError::Conductor(format!("{:?}", e))),
+            Err(_) => {
+                return Err(ClientError::Timeout {
+                    function: format!("{}::{}", zome, function),
+                    after: self.config.call_timeout,
+                })
+            }
+        };
+        output
+            .decode()
+            .map_err(|e| ClientError::Serialization(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnects_back_off_up_to_the_cap() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(0), Duration::ZERO);
+        assert_eq!(policy.delay(1), Duration::from_millis(250));
+        assert_eq!(policy.delay(2), Duration::from_millis(500));
+        assert_eq!(policy.delay(5), Duration::from_secs(4));
+        assert_eq!(policy.delay(6), Duration::from_secs(5));
+        assert_eq!(policy.delay(40), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn errors_start_with_their_kind() {
+        let timeout = ClientError::Timeout {
+            function: "plays::record_play".to_string(),
+            after: Duration::from_secs(30),
+        };
+        assert_eq!(timeout.to_string(), "Timeout: plays::record_play took longer than 30s");
+    }
+}
@@ -0,0 +1,149 @@
+//! One typed method per coordinator extern (`init` and signal handlers aside)
+
+use hdk::prelude::{ActionHash, AgentPubKey};
+use std::collections::HashMap;
+
+use crate::balances::*;
+use crate::catalog::*;
+use crate::plays::*;
+use crate::trust::*;
+use crate::{ClientError, MycelixClient, Page, PageInput};
+
+/// `fn name() -> Out;` sends `()`, `fn name(In) -> Out;` sends a `&In`
+macro_rules! zome_fns {
+    ($zome:literal { $($fns:tt)* }) => {
+        impl MycelixClient {
+            zome_fns!(@fns $zome; $($fns)*);
+        }
+    };
+    (@fns $zome:literal;) => {};
+    (@fns $zome:literal; fn $name:ident() -> $output:ty; $($rest:tt)*) => {
+        #[doc = concat!("Calls `", $zome, "::", stringify!($name), "`")]
+        pub async fn $name(&self) -> Result<$output, ClientError> {
+            self.call($zome, stringify!($name), &()).await
+        }
+        zome_fns!(@fns $zome; $($rest)*);
+    };
+    (@fns $zome:literal; fn $name:ident($input:ty) -> $output:ty; $($rest:tt)*) => {
+        #[doc = concat!("Calls `", $zome, "::", stringify!($name), "`")]
+        pub async fn $name(&self, input: &$input) -> Result<$output, ClientError> {
+            self.call($zome, stringify!($name), input).await
+        }
+        zome_fns!(@fns $zome; $($rest)*);
+    };
+}
+
+zome_fns!("catalog" {
+    fn get_allowed_strategies() -> Vec<String>;
+    fn create_song(Song) -> ActionHash;
+    fn get_scheduled_songs() -> Vec<ScheduledSong>;
+    fn publish_due_songs() -> Vec<ActionHash>;
+    fn reschedule_song(RescheduleSongInput) -> ActionHash;
+    fn is_onboarded(AgentPubKey) -> bool;
+    fn get_song(ActionHash) -> Option<Song>;
+    fn freeze_song_earnings(FreezeSongEarningsInput) -> ActionHash;
+    fn unfreeze_song_earnings(ActionHash) -> ActionHash;
+    fn get_song_metadata(ActionHash) -> Option<SongMetadata>;
+    fn get_song_by_content_hash(String) -> Option<Song>;
+    fn get_song_hash_by_content_hash(String) -> Option<ActionHash>;
+    fn get_song_by_isrc(String) -> Option<Song>;
+    fn get_songs_by_artist(GetSongsByArtistInput) -> Page<Song>;
+    fn get_all_songs(PageInput) -> Page<Song>;
+    fn get_songs_by_genre(GetSongsByGenreInput) -> Page<Song>;
+    fn get_new_releases(GetNewReleasesInput) -> Vec<Song>;
+    fn create_album(Album) -> ActionHash;
+    fn get_album_with_songs(ActionHash) -> Option<AlbumWithSongs>;
+    fn set_artist_profile(ArtistProfile) -> ActionHash;
+    fn get_artist_profile(AgentPubKey) -> Option<ArtistProfile>;
+    fn get_my_profile() -> Option<ArtistProfile>;
+    fn apply_verification(ActionHash) -> bool;
+    fn claim_payment_address(PaymentAddressClaim) -> ();
+    fn get_agent_by_payment_address(String) -> Option<AgentPubKey>;
+    fn follow_artist(AgentPubKey) -> ();
+    fn unfollow_artist(AgentPubKey) -> ();
+    fn get_followers(AgentPubKey) -> Vec<AgentPubKey>;
+    fn search_songs(String) -> Vec<Song>;
+});
+
+zome_fns!("plays" {
+    fn record_play(RecordPlayInput) -> RecordPlayOutput;
+    fn set_play_signal_mode(PlaySignalMode) -> ActionHash;
+    fn flush_play_signals() -> u32;
+    fn broadcast_now_playing(ActionHash) -> ();
+    fn get_live_listeners(ActionHash) -> u32;
+    fn amend_play(AmendPlayInput) -> PlayRecord;
+    fn verify_play_receipt(PlayAttestation) -> bool;
+    fn get_attestations_for_song(GetSongAttestationsInput) -> SongAttestationsPage;
+    fn get_my_rewards() -> RewardSummary;
+    fn get_play_rate(String) -> PlayRate;
+    fn preview_play_amount(PreviewPlayInput) -> Option<PlayPreview>;
+    fn get_strategy_fee(String) -> StrategyFeeRate;
+    fn get_my_unsettled_plays() -> Vec<PlayRecord>;
+    fn get_my_unsettled_plays_page(GetUnsettledPlaysPageInput) -> UnsettledPlaysPage;
+    fn get_my_balance_owed() -> BalanceOwed;
+    fn settle_all_debts() -> SettleAllOutput;
+    fn estimate_settlement(AgentPubKey) -> SettlementEstimate;
+    fn create_settlement_batch(AgentPubKey) -> BatchOutcome;
+    fn create_bounded_settlement_batches(AgentPubKey) -> Vec<ActionHash>;
+    fn mark_plays_settled(ActionHash) -> u32;
+    fn get_settlement(ActionHash) -> Option<SettlementBatch>;
+    fn get_settlement_proof(SettlementProofInput) -> Option<SettlementProof>;
+    fn verify_merkle_proof(VerifyProofInput) -> bool;
+    fn get_play_receipt(ActionHash) -> Option<PlayReceipt>;
+    fn get_pending_settlements(AgentPubKey) -> Vec<SettlementBatch>;
+    fn get_all_pending_settlements(GetAllPendingSettlementsInput) -> Vec<PendingSettlement>;
+    fn update_settlement_status(UpdateSettlementStatusInput) -> ActionHash;
+    fn get_song_stats(ActionHash) -> SongStats;
+    fn refresh_song_stats(ActionHash) -> SongStats;
+});
+
+zome_fns!("balances" {
+    fn get_or_create_listener_account(String) -> ListenerAccount;
+    fn get_or_create_artist_account(String) -> ArtistAccount;
+    fn record_deposit(RecordDepositInput) -> ActionHash;
+    fn confirm_deposit(ConfirmDepositInput) -> ActionHash;
+    fn reconcile_account(ReconcileAccountInput) -> AccountReconciliation;
+    fn set_spending_limits(SetSpendingLimitsInput) -> ListenerAccount;
+    fn get_spending_allowance() -> Option<SpendingAllowance>;
+    fn request_cashout(u64) -> ActionHash;
+    fn execute_transfer(ExecuteTransferInput) -> ActionHash;
+    fn transfer_artist_account(AgentPubKey) -> ActionHash;
+    fn accept_artist_account_transfer(AgentPubKey) -> ArtistAccount;
+    fn get_my_listener_balance() -> Option<ListenerAccount>;
+    fn get_my_artist_balance() -> Option<ArtistAccount>;
+    fn get_artist_stats(AgentPubKey) -> Option<ArtistStats>;
+    fn get_my_cashouts(PageInput) -> Page<CashoutRequest>;
+    fn get_my_transfers(PageInput) -> Page<Transfer>;
+    fn get_transfers_filtered(GetTransfersFilteredInput) -> FilteredTransfers;
+    fn get_protocol_revenue(GetProtocolRevenueInput) -> ProtocolRevenue;
+    fn get_my_spending_summary(GetSpendingSummaryInput) -> SpendingSummary;
+    fn get_or_create_tend_account() -> TendAccount;
+    fn get_my_tend_balance() -> TendBalance;
+    fn execute_tend_transfer(ExecuteTendTransferInput) -> ActionHash;
+    fn claim_tend_credits() -> TendAccount;
+    fn get_my_tend_transfers() -> Vec<TendTransfer>;
+});
+
+zome_fns!("trust" {
+    fn create_trust_claim(CreateTrustClaimInput) -> ActionHash;
+    fn get_trust_claims(AgentPubKey) -> Vec<TrustClaim>;
+    fn get_my_trust_claims() -> Vec<TrustClaim>;
+    fn revoke_trust_claim(ActionHash) -> ActionHash;
+    fn get_verification_status(AgentPubKey) -> Option<VerificationStatus>;
+    fn get_verification_status_hash(AgentPubKey) -> Option<ActionHash>;
+    fn get_verification_statuses(Vec<AgentPubKey>) -> HashMap<AgentPubKey, VerificationStatus>;
+    fn register_cdn_node(RegisterCdnNodeInput) -> ActionHash;
+    fn get_cdn_reputation(AgentPubKey) -> Option<CdnNodeReputation>;
+    fn get_all_cdn_nodes() -> Vec<CdnNodeReputation>;
+    fn submit_quality_report(SubmitQualityReportInput) -> ActionHash;
+    fn submit_quality_reports_batch(Vec<SubmitQualityReportInput>) -> Vec<QualityReportResult>;
+    fn sign_service_challenge(SignedServiceResult) -> Vec<u8>;
+    fn report_byzantine_behavior(ReportByzantineInput) -> ActionHash;
+    fn get_reports_against(AgentPubKey) -> Vec<ByzantineReport>;
+    fn get_report_quorum(AgentPubKey) -> ReportQuorum;
+    fn record_listener_play(ActionHash) -> ActionHash;
+    fn get_listener_reputation(AgentPubKey) -> ListenerReputationSummary;
+    fn is_high_trust_listener(AgentPubKey) -> bool;
+    fn get_best_nodes_for_region(String) -> Vec<CdnNodeReputation>;
+    fn get_best_nodes_for_me(GetPersonalizedNodesInput) -> Vec<RankedCdnNode>;
+});
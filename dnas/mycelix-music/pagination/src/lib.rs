@@ -0,0 +1,161 @@
+//! Pagination - Cursor paging shared by the listing externs
+//!
+//! Listings page through a base's links oldest first. A page is cut after
+//! `limit` links and carries a `PageCursor` naming the last link in it, so
+//! the next call resumes after that link even if newer links were created
+//! in between. Clients treat the cursor as opaque and pass it back as is.
+
+use hdi::prelude::*;
+
+/// Page size when a listing's `limit` is unset
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Largest page any listing returns, whatever `limit` asks for
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Continuation point for paging through a listing
+///
+/// Opaque to clients: pass back the `next_cursor` of the previous page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PageCursor {
+    /// Create time of the last link examined
+    pub created_at: Timestamp,
+    /// Breaks ties between links created in the same microsecond
+    pub link_hash: ActionHash,
+}
+
+/// Cursor and size of the page to fetch (both optional)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PageInput {
+    /// None starts from the oldest item
+    #[serde(default)]
+    pub cursor: Option<PageCursor>,
+    /// Defaults to 100, capped at 500
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One page of a listing
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// None once the last page has been returned
+    pub next_cursor: Option<PageCursor>,
+}
+
+/// A requested page size, defaulted and clamped to 1..=`max`
+pub fn page_limit(limit: Option<usize>, default: usize, max: usize) -> usize {
+    limit.unwrap_or(default).clamp(1, max)
+}
+
+/// The items after `cursor` ordered by `key`, at most `limit` of them, and
+/// the cursor for the next page (None when this is the last)
+pub fn paginate<T>(
+    mut items: Vec<T>,
+    cursor: Option<&PageCursor>,
+    limit: usize,
+    key: impl Fn(&T) -> (Timestamp, &ActionHash),
+) -> (Vec<T>, Option<PageCursor>) {
+    items.sort_by(|a, b| key(a).cmp(&key(b)));
+
+    let start = match cursor {
+        Some(cursor) => {
+            items.partition_point(|item| key(item) <= (cursor.created_at, &cursor.link_hash))
+        }
+        None => 0,
+    };
+    let mut page: Vec<T> = items.into_iter().skip(start).take(limit + 1).collect();
+    let has_more = page.len() > limit;
+    page.truncate(limit);
+
+    let next_cursor = match page.last() {
+        Some(last) if has_more => {
+            let (created_at, link_hash) = key(last);
+            Some(PageCursor {
+                created_at,
+                link_hash: link_hash.clone(),
+            })
+        }
+        _ => None,
+    };
+    (page, next_cursor)
+}
+
+/// The links after `cursor`, oldest first, and the cursor for the next page
+pub fn paginate_links(
+    links: Vec<Link>,
+    cursor: Option<&PageCursor>,
+    limit: usize,
+) -> (Vec<Link>, Option<PageCursor>) {
+    paginate(links, cursor, limit, |link| (link.timestamp, &link.create_link_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(count: u8) -> Vec<(Timestamp, ActionHash)> {
+        (0..count)
+            .map(|i| {
+                let created_at = Timestamp::from_micros(i as i64 / 2);
+                (created_at, ActionHash::from_raw_36(vec![i; 36]))
+            })
+            .collect()
+    }
+
+    fn page(
+        items: Vec<(Timestamp, ActionHash)>,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> (Vec<(Timestamp, ActionHash)>, Option<PageCursor>) {
+        paginate(items, cursor, limit, |(at, hash)| (*at, hash))
+    }
+
+    #[test]
+    fn pages_through_every_item_once() {
+        let all = items(7);
+        let mut shuffled = all.clone();
+        shuffled.reverse();
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (items, next) = page(shuffled.clone(), cursor.as_ref(), 3);
+            seen.extend(items);
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen, all);
+    }
+
+    #[test]
+    fn last_page_has_no_cursor() {
+        let (items, next) = page(items(3), None, 3);
+        assert_eq!(items.len(), 3);
+        assert_eq!(next, None);
+
+        let (items, next) = page(Vec::new(), None, 3);
+        assert!(items.is_empty());
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn newer_items_do_not_shift_later_pages() {
+        let all = items(6);
+        let (first, cursor) = page(all[..4].to_vec(), None, 2);
+        assert_eq!(first, all[..2].to_vec());
+
+        // Items created after the first page was read come after it
+        let (second, _) = page(all.clone(), cursor.as_ref(), 2);
+        assert_eq!(second, all[2..4].to_vec());
+    }
+
+    #[test]
+    fn limits_are_defaulted_and_capped() {
+        assert_eq!(page_limit(None, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE), 100);
+        assert_eq!(page_limit(Some(10_000), DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE), 500);
+        assert_eq!(page_limit(Some(0), DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE), 1);
+    }
+}
@@ -5,6 +5,18 @@
 
 use balances_integrity::*;
 use hdk::prelude::*;
+use pagination::{page_limit, paginate_links, Page, PageInput, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use std::collections::{HashMap, HashSet};
+
+/// Fail init if the DNA properties are malformed
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    if let Err(reason) = parse_balances_properties(dna_info()?.modifiers.properties) {
+        error!("{}", reason);
+        return Ok(InitCallbackResult::Fail(reason));
+    }
+    Ok(InitCallbackResult::Pass)
+}
 
 /// Create or get listener account
 #[hdk_extern]
@@ -19,11 +31,20 @@ pub fn get_or_create_listener_account(eth_address: String) -> ExternResult<Liste
     // Create new account
     let now = sys_time()?;
     let account = ListenerAccount {
+        schema_version: ACCOUNT_SCHEMA_VERSION,
         owner: my_agent.clone(),
         eth_address,
         balance: 0,
         total_deposited: 0,
         total_spent: 0,
+        daily_limit: None,
+        monthly_limit: None,
+        spent_in_day: 0,
+        day_window_start: now,
+        spent_in_month: 0,
+        month_window_start: now,
+        last_deposit: None,
+        last_transfer: None,
         created_at: now,
         updated_at: now,
     };
@@ -45,6 +66,13 @@ pub fn get_or_create_listener_account(eth_address: String) -> ExternResult<Liste
 
 /// Get listener account
 fn get_listener_account(agent: AgentPubKey) -> ExternResult<Option<ListenerAccount>> {
+    Ok(latest_listener_account(&agent)?.map(|(_, account)| account))
+}
+
+/// Latest version of an agent's listener account, with its action hash
+fn latest_listener_account(
+    agent: &AgentPubKey,
+) -> ExternResult<Option<(ActionHash, ListenerAccount)>> {
     let account_path = Path::from(format!("listener_account/{}", agent));
     let links = get_links(
         GetLinksInputBuilder::try_new(
@@ -56,11 +84,8 @@ fn get_listener_account(agent: AgentPubKey) -> ExternResult<Option<ListenerAccou
 
     if let Some(link) = links.last() {
         if let Some(action_hash) = link.target.clone().into_action_hash() {
-            if let Some(record) = get(action_hash, GetOptions::default())? {
-                return Ok(record
-                    .entry()
-                    .to_app_option()
-                    .map_err(|e| wasm_error!(e))?);
+            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
+                return Ok(read_listener_account(&record)?.map(|account| (action_hash, account)));
             }
         }
     }
@@ -68,6 +93,15 @@ fn get_listener_account(agent: AgentPubKey) -> ExternResult<Option<ListenerAccou
     Ok(None)
 }
 
+/// Decode a ListenerAccount of any schema version, upgraded to the current one
+fn read_listener_account(record: &Record) -> ExternResult<Option<ListenerAccount>> {
+    Ok(record
+        .entry()
+        .to_app_option::<ListenerAccount>()
+        .map_err(|e| wasm_error!(e))?
+        .map(ListenerAccount::upgraded))
+}
+
 /// Create or get artist account
 #[hdk_extern]
 pub fn get_or_create_artist_account(eth_address: String) -> ExternResult<ArtistAccount> {
@@ -81,11 +115,19 @@ pub fn get_or_create_artist_account(eth_address: String) -> ExternResult<ArtistA
     // Create new account
     let now = sys_time()?;
     let account = ArtistAccount {
+        schema_version: ACCOUNT_SCHEMA_VERSION,
         owner: my_agent.clone(),
         eth_address,
         pending_balance: 0,
         total_earned: 0,
         total_cashed_out: 0,
+        total_plays: 0,
+        total_play_earnings: 0,
+        migrated_to: None,
+        migrated_at: None,
+        migrated_from: None,
+        last_transfer: None,
+        last_cashout: None,
         created_at: now,
         updated_at: now,
     };
@@ -107,6 +149,11 @@ pub fn get_or_create_artist_account(eth_address: String) -> ExternResult<ArtistA
 
 /// Get artist account
 fn get_artist_account(agent: AgentPubKey) -> ExternResult<Option<ArtistAccount>> {
+    Ok(latest_artist_account(&agent)?.map(|(_, account)| account))
+}
+
+/// Latest version of an agent's artist account, with its action hash
+fn latest_artist_account(agent: &AgentPubKey) -> ExternResult<Option<(ActionHash, ArtistAccount)>> {
     let account_path = Path::from(format!("artist_account/{}", agent));
     let links = get_links(
         GetLinksInputBuilder::try_new(
@@ -118,11 +165,8 @@ fn get_artist_account(agent: AgentPubKey) -> ExternResult<Option<ArtistAccount>>
 
     if let Some(link) = links.last() {
         if let Some(action_hash) = link.target.clone().into_action_hash() {
-            if let Some(record) = get(action_hash, GetOptions::default())? {
-                return Ok(record
-                    .entry()
-                    .to_app_option()
-                    .map_err(|e| wasm_error!(e))?);
+            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
+                return Ok(read_artist_account(&record)?.map(|account| (action_hash, account)));
             }
         }
     }
@@ -130,6 +174,15 @@ fn get_artist_account(agent: AgentPubKey) -> ExternResult<Option<ArtistAccount>>
     Ok(None)
 }
 
+/// Decode an ArtistAccount of any schema version, upgraded to the current one
+fn read_artist_account(record: &Record) -> ExternResult<Option<ArtistAccount>> {
+    Ok(record
+        .entry()
+        .to_app_option::<ArtistAccount>()
+        .map_err(|e| wasm_error!(e))?
+        .map(ArtistAccount::upgraded))
+}
+
 /// Record a deposit (after on-chain verification)
 #[hdk_extern]
 pub fn record_deposit(input: RecordDepositInput) -> ExternResult<ActionHash> {
@@ -142,6 +195,7 @@ pub fn record_deposit(input: RecordDepositInput) -> ExternResult<ActionHash> {
         block_number: input.block_number,
         deposited_at: sys_time()?,
         verified: false, // Will be verified by oracle
+        confirmed_head: None,
     };
 
     let action_hash = create_entry(&EntryTypes::Deposit(deposit))?;
@@ -156,9 +210,10 @@ pub fn record_deposit(input: RecordDepositInput) -> ExternResult<ActionHash> {
         (),
     )?;
 
-    // Update account balance (will be verified later)
-    // In production, this would wait for oracle verification
-    update_listener_balance(my_agent, input.amount as i64)?;
+    // With an oracle configured, the credit waits for `confirm_deposit`
+    if balances_properties()?.deposit_oracle.is_none() {
+        credit_listener(my_agent, input.amount, action_hash.clone())?;
+    }
 
     Ok(action_hash)
 }
@@ -170,9 +225,226 @@ pub struct RecordDepositInput {
     pub block_number: u64,
 }
 
-/// Update listener balance (internal)
-fn update_listener_balance(agent: AgentPubKey, delta: i64) -> ExternResult<()> {
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConfirmDepositInput {
+    pub deposit_hash: ActionHash,
+    /// Current chain head block as seen by the oracle
+    pub chain_head: u64,
+}
+
+/// Confirm a deposit once it is `min_deposit_confirmations` deep and credit
+/// the listener (deposit oracle only)
+///
+/// The oracle asserts the chain head it checked against; it is stored on
+/// the deposit so the confirmation depth can be audited.
+#[hdk_extern]
+pub fn confirm_deposit(input: ConfirmDepositInput) -> ExternResult<ActionHash> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let properties = balances_properties()?;
+    if properties.deposit_oracle.as_ref() != Some(&my_agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the deposit oracle can confirm deposits".to_string()
+        )));
+    }
+
+    let (latest_hash, mut deposit) = latest_deposit(input.deposit_hash)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Deposit not found".to_string())))?;
+    if deposit.verified {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Deposit is already confirmed".to_string()
+        )));
+    }
+    check_deposit_finality(
+        deposit.block_number,
+        input.chain_head,
+        properties.min_deposit_confirmations,
+    )
+    .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    deposit.verified = true;
+    deposit.confirmed_head = Some(input.chain_head);
+    let listener = deposit.listener.clone();
+    let amount = deposit.amount;
+    let confirmed_hash = update_entry(latest_hash, &EntryTypes::Deposit(deposit))?;
+
+    credit_listener(listener, amount, confirmed_hash.clone())?;
+
+    Ok(confirmed_hash)
+}
+
+/// Latest version of a deposit and its action hash
+fn latest_deposit(deposit_hash: ActionHash) -> ExternResult<Option<(ActionHash, Deposit)>> {
+    let details = match get_details(deposit_hash, GetOptions::default())? {
+        Some(Details::Record(details)) => details,
+        _ => return Ok(None),
+    };
+
+    let record = match details
+        .updates
+        .iter()
+        .max_by_key(|update| update.action().timestamp())
+    {
+        Some(update) => match get(update.action_address().clone(), GetOptions::default())? {
+            Some(record) => record,
+            None => return Ok(None),
+        },
+        None => details.record,
+    };
+
+    Ok(record
+        .entry()
+        .to_app_option::<Deposit>()
+        .map_err(|e| wasm_error!(e))?
+        .map(|deposit| (record.action_address().clone(), deposit)))
+}
+
+/// Credit a listener's deposit to their account
+///
+/// `deposit_hash` is the deposit version that backs the credit: the
+/// recorded deposit, or its confirmation when an oracle is configured.
+fn credit_listener(agent: AgentPubKey, amount: u64, deposit_hash: ActionHash) -> ExternResult<()> {
+    let (action_hash, mut account) =
+        latest_listener_account(&agent)?.ok_or_else(|| BalancesError::AccountNotFound {
+            kind: "listener",
+            agent: agent.clone(),
+        })?;
+    account.credit(amount)?;
+    account.last_deposit = Some(deposit_hash);
+    account.updated_at = sys_time()?;
+    write_listener_account(&agent, action_hash, account)
+}
+
+/// Check a debit fits the listener's spending limits in the current windows
+fn check_spending_limits(account: &mut ListenerAccount, amount: u64) -> ExternResult<()> {
+    account.roll_spending_windows(sys_time()?);
+    if account.daily_remaining().map_or(false, |r| amount > r) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Daily spending limit exceeded".to_string()
+        )));
+    }
+    if account.monthly_remaining().map_or(false, |r| amount > r) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Monthly spending limit exceeded".to_string()
+        )));
+    }
+    Ok(())
+}
+
+/// Write a new listener account version and point the agent at it
+fn write_listener_account(
+    agent: &AgentPubKey,
+    original: ActionHash,
+    account: ListenerAccount,
+) -> ExternResult<()> {
+    let new_hash = update_entry(original, &EntryTypes::ListenerAccount(account))?;
     let account_path = Path::from(format!("listener_account/{}", agent));
+    create_link(
+        account_path.path_entry_hash()?,
+        new_hash,
+        LinkTypes::AgentToListenerAccount,
+        (),
+    )?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReconcileAccountInput {
+    pub agent: AgentPubKey,
+    /// Rewrite a drifted account to match its history (balance auditors only)
+    #[serde(default)]
+    pub correct: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AccountReconciliation {
+    pub agent: AgentPubKey,
+    /// Balance the account recorded
+    pub recorded_balance: u64,
+    /// Totals the deposit and transfer history accounts for
+    pub expected: ListenerLedger,
+    /// Recorded minus expected; all zero when the account is consistent
+    pub drift: BalanceDrift,
+    /// Whether a corrected account version was written
+    pub corrected: bool,
+}
+
+/// Recompute a listener's balance from their deposits and transfers and
+/// report how far the account has drifted from it
+///
+/// With `correct`, a drifted account is rewritten to the expected balance
+/// and totals. Only a `balance_auditors` agent may correct, and validation
+/// rejects balance changes from anyone else that deposits and spending
+/// don't explain.
+#[hdk_extern]
+pub fn reconcile_account(input: ReconcileAccountInput) -> ExternResult<AccountReconciliation> {
+    let properties = balances_properties()?;
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    if input.correct && !properties.balance_auditors.contains(&my_agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only balance auditors can correct accounts".to_string()
+        )));
+    }
+
+    let (action_hash, mut account) =
+        latest_listener_account(&input.agent)?.ok_or_else(|| BalancesError::AccountNotFound {
+            kind: "listener",
+            agent: input.agent.clone(),
+        })?;
+    let expected = ListenerLedger::replay(
+        &input.agent,
+        &agent_deposits(&input.agent)?,
+        &agent_transfers(&input.agent)?,
+        properties.deposit_oracle.is_some(),
+    )?;
+    let drift = BalanceDrift::between(&account, &expected);
+    let recorded_balance = account.balance;
+
+    let corrected = input.correct && !drift.is_zero();
+    if corrected {
+        account.balance = expected.balance().ok_or_else(|| {
+            wasm_error!(WasmErrorInner::Guest(
+                "History spends more than was deposited; cannot correct".to_string()
+            ))
+        })?;
+        account.total_deposited = expected.total_deposited;
+        account.total_spent = expected.total_spent;
+        account.updated_at = sys_time()?;
+        write_listener_account(&input.agent, action_hash, account)?;
+    }
+
+    Ok(AccountReconciliation {
+        agent: input.agent,
+        recorded_balance,
+        expected,
+        drift,
+        corrected,
+    })
+}
+
+/// Latest version of each deposit an agent recorded
+fn agent_deposits(agent: &AgentPubKey) -> ExternResult<Vec<Deposit>> {
+    let deposits_path = Path::from(format!("deposits/{}", agent));
+    let links = get_links(
+        GetLinksInputBuilder::try_new(deposits_path.path_entry_hash()?, LinkTypes::AgentToDeposits)?
+            .build(),
+    )?;
+
+    let mut deposits = Vec::new();
+    for link in links {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some((_, deposit)) = latest_deposit(action_hash)? {
+                deposits.push(deposit);
+            }
+        }
+    }
+    Ok(deposits)
+}
+
+/// Set or clear my spending limits (None = unlimited)
+#[hdk_extern]
+pub fn set_spending_limits(input: SetSpendingLimitsInput) -> ExternResult<ListenerAccount> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let account_path = Path::from(format!("listener_account/{}", my_agent));
     let links = get_links(
         GetLinksInputBuilder::try_new(
             account_path.path_entry_hash()?,
@@ -181,78 +453,111 @@ fn update_listener_balance(agent: AgentPubKey, delta: i64) -> ExternResult<()> {
         .build(),
     )?;
 
-    if let Some(link) = links.last() {
-        if let Some(action_hash) = link.target.clone().into_action_hash() {
-            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
-                if let Some(mut account) = record
-                    .entry()
-                    .to_app_option::<ListenerAccount>()
-                    .map_err(|e| wasm_error!(e))?
-                {
-                    // Update balance
-                    if delta >= 0 {
-                        account.balance += delta as u64;
-                        account.total_deposited += delta as u64;
-                    } else {
-                        let abs_delta = (-delta) as u64;
-                        if account.balance >= abs_delta {
-                            account.balance -= abs_delta;
-                            account.total_spent += abs_delta;
-                        } else {
-                            return Err(wasm_error!(WasmErrorInner::Guest(
-                                "Insufficient balance".to_string()
-                            )));
-                        }
-                    }
-                    account.updated_at = sys_time()?;
-
-                    // Create updated entry
-                    let new_hash =
-                        update_entry(action_hash, &EntryTypes::ListenerAccount(account))?;
-
-                    // Update link
-                    create_link(
-                        account_path.path_entry_hash()?,
-                        new_hash,
-                        LinkTypes::AgentToListenerAccount,
-                        (),
-                    )?;
-                }
-            }
-        }
-    }
+    let action_hash = links
+        .last()
+        .and_then(|link| link.target.clone().into_action_hash())
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("No listener account found".to_string())))?;
 
-    Ok(())
+    let record = get(action_hash.clone(), GetOptions::default())?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("No listener account found".to_string())))?;
+    let mut account = read_listener_account(&record)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Invalid listener account".to_string())))?;
+
+    let now = sys_time()?;
+    account.roll_spending_windows(now);
+    account.daily_limit = input.daily_limit;
+    account.monthly_limit = input.monthly_limit;
+    account.updated_at = now;
+
+    let new_hash = update_entry(action_hash, &EntryTypes::ListenerAccount(account.clone()))?;
+    create_link(
+        account_path.path_entry_hash()?,
+        new_hash,
+        LinkTypes::AgentToListenerAccount,
+        (),
+    )?;
+
+    Ok(account)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetSpendingLimitsInput {
+    pub daily_limit: Option<u64>,
+    pub monthly_limit: Option<u64>,
+}
+
+/// Remaining spending allowance in the current windows
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpendingAllowance {
+    pub daily_limit: Option<u64>,
+    pub daily_remaining: Option<u64>,
+    pub monthly_limit: Option<u64>,
+    pub monthly_remaining: Option<u64>,
+}
+
+/// Get my remaining spending allowance
+#[hdk_extern]
+pub fn get_spending_allowance(_: ()) -> ExternResult<Option<SpendingAllowance>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?;
+
+    Ok(get_listener_account(my_agent)?.map(|mut account| {
+        account.roll_spending_windows(now);
+        SpendingAllowance {
+            daily_limit: account.daily_limit,
+            daily_remaining: account.daily_remaining(),
+            monthly_limit: account.monthly_limit,
+            monthly_remaining: account.monthly_remaining(),
+        }
+    }))
 }
 
 /// Request a cashout (artist)
+///
+/// The cashout cites my latest account version, which must cover it, and
+/// that version is then debited citing the cashout, so the same earnings
+/// can't be cashed out twice.
 #[hdk_extern]
 pub fn request_cashout(amount: u64) -> ExternResult<ActionHash> {
     let my_agent = agent_info()?.agent_initial_pubkey;
 
     // Get artist account
-    let account = get_artist_account(my_agent.clone())?
-        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("No artist account found".to_string())))?;
+    let (account_hash, account) = latest_artist_account(&my_agent)?.ok_or_else(|| {
+        BalancesError::AccountNotFound {
+            kind: "artist",
+            agent: my_agent.clone(),
+        }
+    })?;
 
-    // Check balance
-    if account.pending_balance < amount {
-        return Err(wasm_error!(WasmErrorInner::Guest(
-            "Insufficient pending balance".to_string()
-        )));
+    if let Some(successor) = &account.migrated_to {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Artist account has been handed to {}",
+            successor
+        ))));
     }
 
+    // Check balance before anything is written
+    let mut debited = account.clone();
+    debited.debit(amount)?;
+
+    let now = sys_time()?;
     let cashout = CashoutRequest {
         artist: my_agent.clone(),
         amount,
         eth_address: account.eth_address.clone(),
-        requested_at: sys_time()?,
+        requested_at: now,
         status: CashoutStatus::Pending,
         tx_hash: None,
         completed_at: None,
+        account: Some(account_hash.clone()),
     };
 
     let action_hash = create_entry(&EntryTypes::CashoutRequest(cashout))?;
 
+    debited.last_cashout = Some(action_hash.clone());
+    debited.updated_at = now;
+    write_artist_account(&my_agent, account_hash, debited)?;
+
     // Link to agent
     let cashouts_path = Path::from(format!("cashouts/{}", my_agent));
     cashouts_path.ensure()?;
@@ -267,17 +572,47 @@ pub fn request_cashout(amount: u64) -> ExternResult<ActionHash> {
 }
 
 /// Execute transfer from listener to artist (internal, called by plays zome)
+///
+/// Idempotent on `reference`: if I already made the same transfer for the
+/// reference its hash is returned and no balances move, so a settlement
+/// driver can safely retry after a crash. Both accounts must exist (a
+/// rotated artist key pays its successor) and both sides are applied
+/// before anything is written, so a failed transfer records nothing.
 #[hdk_extern]
 pub fn execute_transfer(input: ExecuteTransferInput) -> ExternResult<ActionHash> {
-    // Create transfer record
     let transfer = Transfer {
         from: input.from.clone(),
         to: input.to.clone(),
         amount: input.amount,
-        reason: input.reason,
-        reference: input.reference,
+        reason: input.reason.clone(),
+        reference: input.reference.clone(),
+        play_count: input.play_count,
         transferred_at: sys_time()?,
     };
+    if let Some(existing) = get_transfer_by_reference(&transfer)? {
+        return Ok(existing);
+    }
+
+    // Play counters only move with settlements
+    let settled_plays = if input.reason == TransferReason::PlaySettlement {
+        input.play_count
+    } else {
+        0
+    };
+    let listener = latest_listener_account(&input.from)?;
+    let artist = current_artist_account(&input.to)?;
+    let mut spender = listener.as_ref().map(|(_, account)| account.clone());
+    if let Some(account) = spender.as_mut() {
+        check_spending_limits(account, input.amount)?;
+    }
+    let (mut debited, mut credited) = apply_transfer(
+        &input.from,
+        spender.as_ref(),
+        &input.to,
+        artist.as_ref().map(|(_, _, account)| account),
+        input.amount,
+        settled_plays,
+    )?;
 
     let action_hash = create_entry(&EntryTypes::Transfer(transfer))?;
 
@@ -300,11 +635,21 @@ pub fn execute_transfer(input: ExecuteTransferInput) -> ExternResult<ActionHash>
         (),
     )?;
 
-    // Debit listener
-    update_listener_balance(input.from, -(input.amount as i64))?;
+    // Index by reference for retry detection
+    if let Some(reference) = input.reference.clone() {
+        create_link(reference, action_hash.clone(), LinkTypes::ReferenceToTransfer, ())?;
+    }
 
-    // Credit artist
-    update_artist_balance(input.to, input.amount as i64)?;
+    // Debit listener, credit artist; apply_transfer found both accounts
+    let now = sys_time()?;
+    if let (Some((listener_hash, _)), Some((artist_owner, artist_hash, _))) = (listener, artist) {
+        debited.last_transfer = Some(action_hash.clone());
+        debited.updated_at = now;
+        write_listener_account(&input.from, listener_hash, debited)?;
+        credited.last_transfer = Some(action_hash.clone());
+        credited.updated_at = now;
+        write_artist_account(&artist_owner, artist_hash, credited)?;
+    }
 
     Ok(action_hash)
 }
@@ -315,57 +660,212 @@ pub struct ExecuteTransferInput {
     pub to: AgentPubKey,
     pub amount: u64,
     pub reason: TransferReason,
+    /// Idempotency key (e.g. the settlement batch hash)
     pub reference: Option<ActionHash>,
+    /// Plays covered (required for PlaySettlement)
+    #[serde(default)]
+    pub play_count: u64,
 }
 
-/// Update artist balance (internal)
-fn update_artist_balance(agent: AgentPubKey, delta: i64) -> ExternResult<()> {
-    let account_path = Path::from(format!("artist_account/{}", agent));
+/// The transfer I already made for a transfer's reference and reason
+///
+/// Transfers linked from the reference by anyone else are ignored, and
+/// one of mine on different terms is an error.
+fn get_transfer_by_reference(transfer: &Transfer) -> ExternResult<Option<ActionHash>> {
+    let reference = match &transfer.reference {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
     let links = get_links(
-        GetLinksInputBuilder::try_new(
-            account_path.path_entry_hash()?,
-            LinkTypes::AgentToArtistAccount,
-        )?
-        .build(),
+        GetLinksInputBuilder::try_new(reference.clone(), LinkTypes::ReferenceToTransfer)?.build(),
     )?;
 
-    if let Some(link) = links.last() {
-        if let Some(action_hash) = link.target.clone().into_action_hash() {
+    let mut recorded = Vec::new();
+    for link in links {
+        if let Some(action_hash) = link.target.into_action_hash() {
             if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
-                if let Some(mut account) = record
+                if let Some(existing) = record
                     .entry()
-                    .to_app_option::<ArtistAccount>()
+                    .to_app_option::<Transfer>()
                     .map_err(|e| wasm_error!(e))?
                 {
-                    // Update balance
-                    if delta >= 0 {
-                        account.pending_balance += delta as u64;
-                        account.total_earned += delta as u64;
-                    } else {
-                        let abs_delta = (-delta) as u64;
-                        if account.pending_balance >= abs_delta {
-                            account.pending_balance -= abs_delta;
-                            account.total_cashed_out += abs_delta;
-                        }
-                    }
-                    account.updated_at = sys_time()?;
-
-                    // Create updated entry
-                    let new_hash =
-                        update_entry(action_hash, &EntryTypes::ArtistAccount(account))?;
-
-                    // Update link
-                    create_link(
-                        account_path.path_entry_hash()?,
-                        new_hash,
-                        LinkTypes::AgentToArtistAccount,
-                        (),
-                    )?;
+                    recorded.push((action_hash, record.action().author().clone(), existing));
                 }
             }
         }
     }
 
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    match_retried_transfer(&recorded, &my_agent, transfer)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))
+}
+
+/// An agent's artist account, following key rotations to the successor
+/// that now takes its credits; returns the owner with the account
+fn current_artist_account(
+    agent: &AgentPubKey,
+) -> ExternResult<Option<(AgentPubKey, ActionHash, ArtistAccount)>> {
+    let mut owner = agent.clone();
+    loop {
+        let (action_hash, account) = match latest_artist_account(&owner)? {
+            Some(latest) => latest,
+            None => return Ok(None),
+        };
+        match (account.migrated_at, &account.migrated_to) {
+            (Some(_), Some(successor)) => owner = successor.clone(),
+            _ => return Ok(Some((owner, action_hash, account))),
+        }
+    }
+}
+
+/// Write a new artist account version and point the owner at it
+fn write_artist_account(
+    owner: &AgentPubKey,
+    original: ActionHash,
+    account: ArtistAccount,
+) -> ExternResult<()> {
+    let new_hash = update_entry(original, &EntryTypes::ArtistAccount(account))?;
+    let account_path = Path::from(format!("artist_account/{}", owner));
+    create_link(
+        account_path.path_entry_hash()?,
+        new_hash,
+        LinkTypes::AgentToArtistAccount,
+        (),
+    )?;
+    Ok(())
+}
+
+/// Hand my artist account to a new agent key (key rotation, step 1)
+///
+/// Nothing moves until the new key calls `accept_artist_account_transfer`;
+/// until then the account keeps earning. The handover can't be re-pointed
+/// or undone, and no cashouts can be requested from then on.
+#[hdk_extern]
+pub fn transfer_artist_account(new_owner: AgentPubKey) -> ExternResult<ActionHash> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    if new_owner == my_agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Cannot transfer an account to its current owner".to_string()
+        )));
+    }
+
+    let (action_hash, mut account) = latest_artist_account(&my_agent)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("No artist account found".to_string())))?;
+    if account.migrated_to.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Artist account has already been handed over".to_string()
+        )));
+    }
+
+    account.migrated_to = Some(new_owner);
+    account.updated_at = sys_time()?;
+    let new_hash = update_entry(action_hash, &EntryTypes::ArtistAccount(account))?;
+
+    let account_path = Path::from(format!("artist_account/{}", my_agent));
+    create_link(
+        account_path.path_entry_hash()?,
+        new_hash.clone(),
+        LinkTypes::AgentToArtistAccount,
+        (),
+    )?;
+
+    Ok(new_hash)
+}
+
+/// Take over an artist account handed to my key (key rotation, step 2)
+///
+/// Freezes the old account's latest version, then creates my successor
+/// account citing that freeze with the balance and counters it held, and
+/// links the old key's transfer and cashout history to me. Later credits
+/// to the old key land here.
+#[hdk_extern]
+pub fn accept_artist_account_transfer(previous_owner: AgentPubKey) -> ExternResult<ArtistAccount> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    if get_artist_account(my_agent.clone())?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "This key already has an artist account".to_string()
+        )));
+    }
+
+    let (old_hash, mut old) = latest_artist_account(&previous_owner)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("No artist account found".to_string())))?;
+    if old.migrated_to.as_ref() != Some(&my_agent) || old.migrated_at.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Artist account is not awaiting transfer to this key".to_string()
+        )));
+    }
+
+    // Freeze the old account with its balance handed over
+    let now = sys_time()?;
+    let handed_over = old.clone();
+    old.pending_balance = 0;
+    old.migrated_at = Some(now);
+    old.updated_at = now;
+    let frozen_hash = update_entry(old_hash, &EntryTypes::ArtistAccount(old))?;
+    let old_path = Path::from(format!("artist_account/{}", previous_owner));
+    create_link(
+        old_path.path_entry_hash()?,
+        frozen_hash.clone(),
+        LinkTypes::AgentToArtistAccount,
+        (),
+    )?;
+
+    let successor = ArtistAccount {
+        schema_version: ACCOUNT_SCHEMA_VERSION,
+        owner: my_agent.clone(),
+        eth_address: handed_over.eth_address,
+        pending_balance: handed_over.pending_balance,
+        total_earned: handed_over.total_earned,
+        total_cashed_out: handed_over.total_cashed_out,
+        total_plays: handed_over.total_plays,
+        total_play_earnings: handed_over.total_play_earnings,
+        migrated_to: None,
+        migrated_at: None,
+        migrated_from: Some(frozen_hash),
+        last_transfer: None,
+        last_cashout: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let successor_hash = create_entry(&EntryTypes::ArtistAccount(successor.clone()))?;
+
+    let account_path = Path::from(format!("artist_account/{}", my_agent));
+    account_path.ensure()?;
+    create_link(
+        account_path.path_entry_hash()?,
+        successor_hash,
+        LinkTypes::AgentToArtistAccount,
+        (),
+    )?;
+
+    // Carry the old key's history over
+    copy_agent_links("transfers", &previous_owner, &my_agent, LinkTypes::AgentToTransfers)?;
+    copy_agent_links("cashouts", &previous_owner, &my_agent, LinkTypes::AgentToCashouts)?;
+
+    Ok(successor)
+}
+
+/// Link everything under `{prefix}/{from}` from `{prefix}/{to}` as well
+fn copy_agent_links(
+    prefix: &str,
+    from: &AgentPubKey,
+    to: &AgentPubKey,
+    link_type: LinkTypes,
+) -> ExternResult<()> {
+    let from_path = Path::from(format!("{}/{}", prefix, from));
+    let links = get_links(
+        GetLinksInputBuilder::try_new(from_path.path_entry_hash()?, link_type)?.build(),
+    )?;
+    if links.is_empty() {
+        return Ok(());
+    }
+
+    let to_path = Path::from(format!("{}/{}", prefix, to));
+    to_path.ensure()?;
+    for link in links {
+        create_link(to_path.path_entry_hash()?, link.target, link_type, ())?;
+    }
+
     Ok(())
 }
 
@@ -383,63 +883,684 @@ pub fn get_my_artist_balance(_: ()) -> ExternResult<Option<ArtistAccount>> {
     get_artist_account(my_agent)
 }
 
-/// Get my cashout history
+/// Cached lifetime play figures for an artist
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArtistStats {
+    pub artist: AgentPubKey,
+    pub total_plays: u64,
+    pub total_play_earnings: u64,
+    pub total_earned: u64,
+    pub updated_at: Timestamp,
+}
+
+/// Get an artist's lifetime play stats without rescanning plays
 #[hdk_extern]
-pub fn get_my_cashouts(_: ()) -> ExternResult<Vec<CashoutRequest>> {
+pub fn get_artist_stats(artist: AgentPubKey) -> ExternResult<Option<ArtistStats>> {
+    Ok(get_artist_account(artist.clone())?.map(|account| ArtistStats {
+        artist,
+        total_plays: account.total_plays,
+        total_play_earnings: account.total_play_earnings,
+        total_earned: account.total_earned,
+        updated_at: account.updated_at,
+    }))
+}
+
+/// One page of the entries an agent's index path links to, oldest first
+fn linked_page<T>(path: Path, link_type: LinkTypes, page: &PageInput) -> ExternResult<Page<T>>
+where
+    T: TryFrom<SerializedBytes, Error = SerializedBytesError>,
+{
+    let links =
+        get_links(GetLinksInputBuilder::try_new(path.path_entry_hash()?, link_type)?.build())?;
+    let limit = page_limit(page.limit, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE);
+    let (links, next_cursor) = paginate_links(links, page.cursor.as_ref(), limit);
+
+    // One host call for the whole page
+    let inputs: Vec<GetInput> = links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .map(|hash| GetInput::new(hash.into(), GetOptions::default()))
+        .collect();
+    let records = HDK.with(|h| h.borrow().get(inputs))?;
+
+    let mut items = Vec::new();
+    for record in records.into_iter().flatten() {
+        if let Some(item) = record.entry().to_app_option::<T>().map_err(|e| wasm_error!(e))? {
+            items.push(item);
+        }
+    }
+    Ok(Page { items, next_cursor })
+}
+
+/// Get my cashout history, oldest first (paginated)
+#[hdk_extern]
+pub fn get_my_cashouts(page: PageInput) -> ExternResult<Page<CashoutRequest>> {
     let my_agent = agent_info()?.agent_initial_pubkey;
     let cashouts_path = Path::from(format!("cashouts/{}", my_agent));
+    linked_page(cashouts_path, LinkTypes::AgentToCashouts, &page)
+}
+
+/// Get my transfer history, oldest first (paginated)
+///
+/// See `get_transfers_filtered` to filter by reason or time.
+#[hdk_extern]
+pub fn get_my_transfers(page: PageInput) -> ExternResult<Page<Transfer>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let transfers_path = Path::from(format!("transfers/{}", my_agent));
+    linked_page(transfers_path, LinkTypes::AgentToTransfers, &page)
+}
+
+/// Filters for my transfer history (all optional)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetTransfersFilteredInput {
+    #[serde(default)]
+    pub reason: Option<TransferReason>,
+    /// Inclusive lower bound on `transferred_at`
+    #[serde(default)]
+    pub from: Option<Timestamp>,
+    /// Exclusive upper bound on `transferred_at`
+    #[serde(default)]
+    pub to: Option<Timestamp>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// Totals for one transfer reason across all matching transfers
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransferReasonSubtotal {
+    pub reason: TransferReason,
+    pub count: u64,
+    /// Amount I received (in wei)
+    pub received: u64,
+    /// Amount I sent (in wei)
+    pub sent: u64,
+}
+
+/// A page of matching transfers plus subtotals over every match
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FilteredTransfers {
+    /// Oldest first by `transferred_at`
+    pub transfers: Vec<Transfer>,
+    /// Matches before pagination
+    pub total: usize,
+    pub subtotals: Vec<TransferReasonSubtotal>,
+}
+
+/// Get my transfers filtered by reason and time, with per-reason subtotals
+#[hdk_extern]
+pub fn get_transfers_filtered(input: GetTransfersFilteredInput) -> ExternResult<FilteredTransfers> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+
+    let mut transfers: Vec<Transfer> = agent_transfers(&my_agent)?
+        .into_iter()
+        .filter(|transfer| transfer_matches(transfer, &input))
+        .collect();
+    transfers.sort_by_key(|t| t.transferred_at);
+
+    let mut subtotals: Vec<TransferReasonSubtotal> = Vec::new();
+    for transfer in &transfers {
+        let index = match subtotals.iter().position(|s| s.reason == transfer.reason) {
+            Some(index) => index,
+            None => {
+                subtotals.push(TransferReasonSubtotal {
+                    reason: transfer.reason.clone(),
+                    count: 0,
+                    received: 0,
+                    sent: 0,
+                });
+                subtotals.len() - 1
+            }
+        };
+        let subtotal = &mut subtotals[index];
+        subtotal.count += 1;
+        if transfer.to == my_agent {
+            subtotal.received += transfer.amount;
+        }
+        if transfer.from == my_agent {
+            subtotal.sent += transfer.amount;
+        }
+    }
+
+    let total = transfers.len();
+    let transfers = transfers
+        .into_iter()
+        .skip(input.offset)
+        .take(input.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(FilteredTransfers {
+        transfers,
+        total,
+        subtotals,
+    })
+}
+
+/// Transfers an agent sent or received
+fn agent_transfers(agent: &AgentPubKey) -> ExternResult<Vec<Transfer>> {
+    let transfers_path = Path::from(format!("transfers/{}", agent));
 
     let links = get_links(
-        GetLinksInputBuilder::try_new(cashouts_path.path_entry_hash()?, LinkTypes::AgentToCashouts)?
-            .build(),
+        GetLinksInputBuilder::try_new(
+            transfers_path.path_entry_hash()?,
+            LinkTypes::AgentToTransfers,
+        )?
+        .build(),
     )?;
 
-    let mut cashouts = Vec::new();
+    let mut transfers = Vec::new();
     for link in links {
         if let Some(action_hash) = link.target.into_action_hash() {
             if let Some(record) = get(action_hash, GetOptions::default())? {
-                if let Some(cashout) = record
+                if let Some(transfer) = record
                     .entry()
-                    .to_app_option::<CashoutRequest>()
+                    .to_app_option::<Transfer>()
                     .map_err(|e| wasm_error!(e))?
                 {
-                    cashouts.push(cashout);
+                    transfers.push(transfer);
                 }
             }
         }
     }
 
-    Ok(cashouts)
+    Ok(transfers)
+}
+
+/// Time range for protocol revenue (both bounds optional)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetProtocolRevenueInput {
+    /// Inclusive lower bound on `transferred_at`
+    #[serde(default)]
+    pub from: Option<Timestamp>,
+    /// Exclusive upper bound on `transferred_at`
+    #[serde(default)]
+    pub to: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProtocolRevenue {
+    /// Protocol fees collected by the platform agent (in wei)
+    pub total_fees: u64,
+    pub fee_count: u64,
+}
+
+/// Sum the protocol fees paid to the platform agent in a time range
+#[hdk_extern]
+pub fn get_protocol_revenue(input: GetProtocolRevenueInput) -> ExternResult<ProtocolRevenue> {
+    let platform = match balances_properties()?.platform_agent {
+        Some(platform) => platform,
+        None => {
+            return Ok(ProtocolRevenue {
+                total_fees: 0,
+                fee_count: 0,
+            })
+        }
+    };
+
+    let filter = GetTransfersFilteredInput {
+        reason: Some(TransferReason::ProtocolFee),
+        from: input.from,
+        to: input.to,
+        ..Default::default()
+    };
+
+    let mut revenue = ProtocolRevenue {
+        total_fees: 0,
+        fee_count: 0,
+    };
+    for transfer in agent_transfers(&platform)? {
+        if transfer.to == platform && transfer_matches(&transfer, &filter) {
+            revenue.total_fees += transfer.amount;
+            revenue.fee_count += 1;
+        }
+    }
+
+    Ok(revenue)
+}
+
+fn transfer_matches(transfer: &Transfer, filter: &GetTransfersFilteredInput) -> bool {
+    filter.reason.as_ref().map_or(true, |r| transfer.reason == *r)
+        && filter.from.map_or(true, |from| transfer.transferred_at >= from)
+        && filter.to.map_or(true, |to| transfer.transferred_at < to)
+}
+
+/// Period for a spending summary (both bounds optional)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetSpendingSummaryInput {
+    /// Inclusive lower bound on `transferred_at`
+    #[serde(default)]
+    pub from: Option<Timestamp>,
+    /// Exclusive upper bound on `transferred_at`
+    #[serde(default)]
+    pub to: Option<Timestamp>,
+}
+
+/// What I paid one artist in the period
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArtistSpend {
+    pub artist: AgentPubKey,
+    /// Artist profile name (None if the artist has no profile)
+    pub display_name: Option<String>,
+    /// Amount sent (in wei)
+    pub amount: u64,
+    pub transfer_count: u64,
+    /// Plays covered by settlements to this artist
+    pub play_count: u64,
+}
+
+/// What I paid for one transfer reason in the period
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReasonSpend {
+    pub reason: TransferReason,
+    /// Amount sent (in wei)
+    pub amount: u64,
+    pub transfer_count: u64,
 }
 
-/// Get my transfer history
+/// Where my money went over a period
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpendingSummary {
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    /// Everything I sent, protocol fees included (in wei)
+    pub total_spent: u64,
+    /// Plays covered by my settlements
+    pub play_count: u64,
+    /// Largest first; protocol fees go to the platform, so only appear by reason
+    pub by_artist: Vec<ArtistSpend>,
+    /// Largest first
+    pub by_reason: Vec<ReasonSpend>,
+}
+
+/// Mirror of the display fields of `catalog::ArtistProfile`
+#[derive(Serialize, Deserialize, Debug)]
+struct CatalogArtistProfile {
+    name: String,
+}
+
+/// An artist's profile name, or None if they have none or catalog fails
+fn artist_display_name(artist: &AgentPubKey) -> ExternResult<Option<String>> {
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("catalog"),
+        FunctionName::from("get_artist_profile"),
+        None,
+        artist.clone(),
+    )? {
+        ZomeCallResponse::Ok(result) => {
+            let profile: Option<CatalogArtistProfile> = result.decode().map_err(|e| wasm_error!(e))?;
+            Ok(profile.map(|profile| profile.name))
+        }
+        other => {
+            debug!("Failed to get artist profile for {}: {:?}", artist, other);
+            Ok(None)
+        }
+    }
+}
+
+/// Summarise what I spent over a period, by artist and by reason
 #[hdk_extern]
-pub fn get_my_transfers(_: ()) -> ExternResult<Vec<Transfer>> {
+pub fn get_my_spending_summary(input: GetSpendingSummaryInput) -> ExternResult<SpendingSummary> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let filter = GetTransfersFilteredInput {
+        from: input.from,
+        to: input.to,
+        ..Default::default()
+    };
+
+    let mut summary = SpendingSummary {
+        from: input.from,
+        to: input.to,
+        total_spent: 0,
+        play_count: 0,
+        by_artist: Vec::new(),
+        by_reason: Vec::new(),
+    };
+    for transfer in agent_transfers(&my_agent)? {
+        if transfer.from != my_agent || !transfer_matches(&transfer, &filter) {
+            continue;
+        }
+        summary.total_spent += transfer.amount;
+        summary.play_count += transfer.play_count;
+
+        match summary.by_reason.iter_mut().find(|r| r.reason == transfer.reason) {
+            Some(reason) => {
+                reason.amount += transfer.amount;
+                reason.transfer_count += 1;
+            }
+            None => summary.by_reason.push(ReasonSpend {
+                reason: transfer.reason.clone(),
+                amount: transfer.amount,
+                transfer_count: 1,
+            }),
+        }
+
+        if transfer.reason == TransferReason::ProtocolFee {
+            continue;
+        }
+        match summary.by_artist.iter_mut().find(|a| a.artist == transfer.to) {
+            Some(artist) => {
+                artist.amount += transfer.amount;
+                artist.transfer_count += 1;
+                artist.play_count += transfer.play_count;
+            }
+            None => summary.by_artist.push(ArtistSpend {
+                artist: transfer.to.clone(),
+                display_name: None,
+                amount: transfer.amount,
+                transfer_count: 1,
+                play_count: transfer.play_count,
+            }),
+        }
+    }
+
+    for artist in &mut summary.by_artist {
+        artist.display_name = artist_display_name(&artist.artist)?;
+    }
+    summary.by_artist.sort_by(|a, b| b.amount.cmp(&a.amount));
+    summary.by_reason.sort_by(|a, b| b.amount.cmp(&a.amount));
+
+    Ok(summary)
+}
+
+/// Create or get my TEND account
+#[hdk_extern]
+pub fn get_or_create_tend_account(_: ()) -> ExternResult<TendAccount> {
     let my_agent = agent_info()?.agent_initial_pubkey;
-    let transfers_path = Path::from(format!("transfers/{}", my_agent));
 
+    if let Some((_, account)) = get_tend_account(&my_agent)? {
+        return Ok(account);
+    }
+
+    let now = sys_time()?;
+    let account = TendAccount {
+        owner: my_agent.clone(),
+        balance: 0,
+        total_earned: 0,
+        total_spent: 0,
+        last_transfer: None,
+        sender_debit: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let action_hash = create_entry(&EntryTypes::TendAccount(account.clone()))?;
+
+    let account_path = Path::from(format!("tend_account/{}", my_agent));
+    account_path.ensure()?;
+    create_link(
+        account_path.path_entry_hash()?,
+        action_hash,
+        LinkTypes::AgentToTendAccount,
+        (),
+    )?;
+
+    Ok(account)
+}
+
+/// Latest TEND account entry for an agent, with its action hash
+fn get_tend_account(agent: &AgentPubKey) -> ExternResult<Option<(ActionHash, TendAccount)>> {
+    let account_path = Path::from(format!("tend_account/{}", agent));
     let links = get_links(
         GetLinksInputBuilder::try_new(
-            transfers_path.path_entry_hash()?,
-            LinkTypes::AgentToTransfers,
+            account_path.path_entry_hash()?,
+            LinkTypes::AgentToTendAccount,
         )?
         .build(),
     )?;
 
+    if let Some(link) = links.last() {
+        if let Some(action_hash) = link.target.clone().into_action_hash() {
+            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
+                return Ok(record
+                    .entry()
+                    .to_app_option::<TendAccount>()
+                    .map_err(|e| wasm_error!(e))?
+                    .map(|account| (action_hash, account)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TendBalance {
+    /// TEND seconds (negative = in credit)
+    pub balance: i64,
+    /// TEND sent to me that `claim_tend_credits` hasn't applied yet
+    pub pending: u64,
+    /// TEND that can still be spent, including the credit limit and
+    /// pending credits
+    pub available: u64,
+    pub credit_limit: u64,
+    pub total_earned: u64,
+    pub total_spent: u64,
+}
+
+/// Get my TEND balance (zero if I have no TEND account yet)
+#[hdk_extern]
+pub fn get_my_tend_balance(_: ()) -> ExternResult<TendBalance> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let credit_limit = balances_properties()?.tend_credit_limit;
+
+    let (balance, total_earned, total_spent) = match get_tend_account(&my_agent)? {
+        Some((_, account)) => (account.balance, account.total_earned, account.total_spent),
+        None => (0, 0, 0),
+    };
+    let pending = pending_tend_credits(&my_agent)?
+        .iter()
+        .fold(0u64, |total, (_, transfer, _)| total.saturating_add(transfer.amount));
+
+    Ok(TendBalance {
+        balance,
+        pending,
+        available: (balance as i128 + credit_limit as i128 + pending as i128)
+            .clamp(0, u64::MAX as i128) as u64,
+        credit_limit,
+        total_earned,
+        total_spent,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExecuteTendTransferInput {
+    pub to: AgentPubKey,
+    /// TEND seconds
+    pub amount: u64,
+    /// Idempotency key (e.g. the play record hash)
+    pub reference: Option<ActionHash>,
+}
+
+/// Pay TEND from my account to another agent (called by the plays zome
+/// for time-barter plays)
+///
+/// TEND never touches the wei accounts. Only the owner writes a TEND
+/// account, so this records the transfer and debits me; the recipient
+/// picks the credit up with `claim_tend_credits`, whether or not they
+/// have opened an account yet, citing my debit from the tag of their link
+/// to the transfer. Retries with the same reference are no-ops.
+#[hdk_extern]
+pub fn execute_tend_transfer(input: ExecuteTendTransferInput) -> ExternResult<ActionHash> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+
+    let my_transfers = tend_transfer_links(&my_agent)?;
+    if let Some(reference) = &input.reference {
+        for (action_hash, transfer) in read_tend_transfers(my_transfers)? {
+            if transfer.from == my_agent && transfer.reference.as_ref() == Some(reference) {
+                return Ok(action_hash);
+            }
+        }
+    }
+
+    // Credits sent to me count towards what I can spend
+    claim_tend_credits(())?;
+    let (my_hash, mut mine) = get_tend_account(&my_agent)?.ok_or_else(|| {
+        wasm_error!(WasmErrorInner::Guest("TEND account not found".to_string()))
+    })?;
+
+    // Check the debit before anything is written
+    let credit_limit = balances_properties()?.tend_credit_limit;
+    mine.debit(input.amount, credit_limit)?;
+
+    let now = sys_time()?;
+    let transfer = TendTransfer {
+        from: my_agent.clone(),
+        to: input.to.clone(),
+        amount: input.amount,
+        reference: input.reference,
+        transferred_at: now,
+    };
+    let action_hash = create_entry(&EntryTypes::TendTransfer(transfer))?;
+
+    mine.last_transfer = Some(action_hash.clone());
+    mine.updated_at = now;
+    let debit = update_tend_account(&my_agent, my_hash, mine)?;
+
+    let tag = TendTransferTag { debit }.to_link_tag()?;
+    for (agent, tag) in [(&my_agent, LinkTag::new(vec![])), (&input.to, tag)] {
+        let path = Path::from(format!("tend_transfers/{}", agent));
+        path.ensure()?;
+        create_link(
+            path.path_entry_hash()?,
+            action_hash.clone(),
+            LinkTypes::AgentToTendTransfers,
+            tag,
+        )?;
+    }
+
+    Ok(action_hash)
+}
+
+/// Apply the TEND other agents have sent me to my account, oldest first,
+/// opening the account if I don't have one
+///
+/// Each credit is its own account version citing the transfer it applies.
+#[hdk_extern]
+pub fn claim_tend_credits(_: ()) -> ExternResult<TendAccount> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let mut account = get_or_create_tend_account(())?;
+
+    for (transfer_hash, transfer, debit) in pending_tend_credits(&my_agent)? {
+        let (account_hash, mut latest) = get_tend_account(&my_agent)?.ok_or_else(|| {
+            wasm_error!(WasmErrorInner::Guest("TEND account not found".to_string()))
+        })?;
+        latest.credit(transfer.amount)?;
+        latest.last_transfer = Some(transfer_hash);
+        latest.sender_debit = Some(debit);
+        latest.updated_at = sys_time()?;
+        update_tend_account(&my_agent, account_hash, latest.clone())?;
+        account = latest;
+    }
+
+    Ok(account)
+}
+
+/// TEND transfers to an agent not yet applied to their account, with the
+/// sender's debit of each, oldest first
+///
+/// Transfers whose link doesn't name the sender's debit can't be credited,
+/// so they're left out.
+fn pending_tend_credits(
+    agent: &AgentPubKey,
+) -> ExternResult<Vec<(ActionHash, TendTransfer, ActionHash)>> {
+    let applied = applied_tend_transfers()?;
+    let links = tend_transfer_links(agent)?;
+    let debits: HashMap<ActionHash, ActionHash> = links
+        .iter()
+        .filter_map(|link| {
+            let debit = TendTransferTag::from_link_tag(&link.tag)?.debit;
+            Some((link.target.clone().into_action_hash()?, debit))
+        })
+        .collect();
+    let mut pending: Vec<(ActionHash, TendTransfer, ActionHash)> =
+        read_tend_transfers(links)?
+            .into_iter()
+            .filter(|(hash, transfer)| &transfer.to == agent && !applied.contains(hash))
+            .filter_map(|(hash, transfer)| {
+                let debit = debits.get(&hash)?.clone();
+                Some((hash, transfer, debit))
+            })
+            .collect();
+    pending.sort_by_key(|(_, transfer, _)| transfer.transferred_at);
+    Ok(pending)
+}
+
+/// Transfers my TEND account versions have applied (scans my source chain)
+fn applied_tend_transfers() -> ExternResult<HashSet<ActionHash>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::TendAccount.try_into()?)
+            .include_entries(true),
+    )?;
+
+    let mut applied = HashSet::new();
+    for record in records {
+        if let Some(account) = record
+            .entry()
+            .to_app_option::<TendAccount>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            applied.extend(account.last_transfer);
+        }
+    }
+    Ok(applied)
+}
+
+/// Write a new TEND account version and point the agent at it; returns
+/// the new version's hash
+fn update_tend_account(
+    agent: &AgentPubKey,
+    original: ActionHash,
+    account: TendAccount,
+) -> ExternResult<ActionHash> {
+    let new_hash = update_entry(original, &EntryTypes::TendAccount(account))?;
+
+    let account_path = Path::from(format!("tend_account/{}", agent));
+    create_link(
+        account_path.path_entry_hash()?,
+        new_hash.clone(),
+        LinkTypes::AgentToTendAccount,
+        (),
+    )?;
+    Ok(new_hash)
+}
+
+fn tend_transfer_links(agent: &AgentPubKey) -> ExternResult<Vec<Link>> {
+    let path = Path::from(format!("tend_transfers/{}", agent));
+    get_links(
+        GetLinksInputBuilder::try_new(path.path_entry_hash()?, LinkTypes::AgentToTendTransfers)?
+            .build(),
+    )
+}
+
+fn read_tend_transfers(links: Vec<Link>) -> ExternResult<Vec<(ActionHash, TendTransfer)>> {
     let mut transfers = Vec::new();
     for link in links {
         if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(record) = get(action_hash, GetOptions::default())? {
+            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
                 if let Some(transfer) = record
                     .entry()
-                    .to_app_option::<Transfer>()
+                    .to_app_option::<TendTransfer>()
                     .map_err(|e| wasm_error!(e))?
                 {
-                    transfers.push(transfer);
+                    transfers.push((action_hash, transfer));
                 }
             }
         }
     }
+    Ok(transfers)
+}
 
+/// Get my TEND transfer history, oldest first
+#[hdk_extern]
+pub fn get_my_tend_transfers(_: ()) -> ExternResult<Vec<TendTransfer>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let mut transfers: Vec<TendTransfer> = read_tend_transfers(tend_transfer_links(&my_agent)?)?
+        .into_iter()
+        .map(|(_, transfer)| transfer)
+        .collect();
+    transfers.sort_by_key(|t| t.transferred_at);
     Ok(transfers)
 }
@@ -6,10 +6,23 @@
 
 use hdi::prelude::*;
 
+/// Current account entry schema version (listener and artist)
+///
+/// - v0: original layout (no `schema_version` field)
+/// - v1: adds spending limits (listener) and play counters (artist)
+/// - v2: adds key-rotation fields (artist)
+/// - v3: adds the last credited deposit (listener)
+/// - v4: adds the last credited transfer and paid-out cashout (artist)
+/// - v5: adds the last paid transfer (listener)
+pub const ACCOUNT_SCHEMA_VERSION: u8 = 5;
+
 /// Listener account - tracks pre-funded balance
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
 pub struct ListenerAccount {
+    /// Entry schema version (missing on v0 entries)
+    #[serde(default)]
+    pub schema_version: u8,
     /// Owner's agent pub key
     pub owner: AgentPubKey,
     /// Ethereum address for deposits/refunds
@@ -20,16 +33,177 @@ pub struct ListenerAccount {
     pub total_deposited: u64,
     /// Total spent on plays
     pub total_spent: u64,
+    /// Optional spending cap per 24h window (in wei)
+    #[serde(default)]
+    pub daily_limit: Option<u64>,
+    /// Optional spending cap per 30-day window (in wei)
+    #[serde(default)]
+    pub monthly_limit: Option<u64>,
+    /// Spent in the current daily window
+    #[serde(default)]
+    pub spent_in_day: u64,
+    /// Start of the current daily window
+    #[serde(default)]
+    pub day_window_start: Timestamp,
+    /// Spent in the current monthly window
+    #[serde(default)]
+    pub spent_in_month: u64,
+    /// Start of the current monthly window
+    #[serde(default)]
+    pub month_window_start: Timestamp,
+    /// Deposit most recently credited; a version that raises
+    /// `total_deposited` must name the deposit it credits here
+    #[serde(default)]
+    pub last_deposit: Option<ActionHash>,
+    /// Transfer most recently paid; a version that raises `total_spent`
+    /// must name the transfer it pays here
+    #[serde(default)]
+    pub last_transfer: Option<ActionHash>,
     /// Account creation timestamp
     pub created_at: Timestamp,
     /// Last activity timestamp
     pub updated_at: Timestamp,
 }
 
+/// Length of the daily spending window (microseconds)
+pub const DAY_WINDOW_MICROS: i64 = 24 * 60 * 60 * 1_000_000;
+
+/// Length of the monthly spending window (microseconds)
+pub const MONTH_WINDOW_MICROS: i64 = 30 * DAY_WINDOW_MICROS;
+
+impl ListenerAccount {
+    /// Upgrade an entry of any prior schema version in memory
+    pub fn upgraded(mut self) -> Self {
+        // v0 -> v1: spending limit fields default to unlimited/empty windows
+        // v2 -> v3: no deposit cited until the next credit
+        // v4 -> v5: no transfer cited until the next payment
+        self.schema_version = ACCOUNT_SCHEMA_VERSION;
+        self
+    }
+
+    /// Start new spending windows if the current ones have elapsed
+    pub fn roll_spending_windows(&mut self, now: Timestamp) {
+        if now.as_micros() - self.day_window_start.as_micros() >= DAY_WINDOW_MICROS {
+            self.spent_in_day = 0;
+            self.day_window_start = now;
+        }
+        if now.as_micros() - self.month_window_start.as_micros() >= MONTH_WINDOW_MICROS {
+            self.spent_in_month = 0;
+            self.month_window_start = now;
+        }
+    }
+
+    /// Remaining allowance in the daily window (None = unlimited)
+    pub fn daily_remaining(&self) -> Option<u64> {
+        self.daily_limit
+            .map(|limit| limit.saturating_sub(self.spent_in_day))
+    }
+
+    /// Remaining allowance in the monthly window (None = unlimited)
+    pub fn monthly_remaining(&self) -> Option<u64> {
+        self.monthly_limit
+            .map(|limit| limit.saturating_sub(self.spent_in_month))
+    }
+
+    /// Add a deposit; errors rather than wrapping if a total would overflow
+    pub fn credit(&mut self, amount: u64) -> Result<(), BalancesError> {
+        let balance = checked_credit(self.balance, amount, "balance")?;
+        let total_deposited = checked_credit(self.total_deposited, amount, "total_deposited")?;
+        self.balance = balance;
+        self.total_deposited = total_deposited;
+        Ok(())
+    }
+
+    /// Take a payment from the balance; errors if the balance is short or
+    /// a spending total would overflow. Spending limits are checked apart.
+    pub fn debit(&mut self, amount: u64) -> Result<(), BalancesError> {
+        let balance = self
+            .balance
+            .checked_sub(amount)
+            .ok_or(BalancesError::InsufficientBalance("balance"))?;
+        let total_spent = checked_credit(self.total_spent, amount, "total_spent")?;
+        let spent_in_day = checked_credit(self.spent_in_day, amount, "spent_in_day")?;
+        let spent_in_month = checked_credit(self.spent_in_month, amount, "spent_in_month")?;
+        self.balance = balance;
+        self.total_spent = total_spent;
+        self.spent_in_day = spent_in_day;
+        self.spent_in_month = spent_in_month;
+        Ok(())
+    }
+}
+
+/// `value + amount`, or an error naming the field that would overflow
+fn checked_credit(value: u64, amount: u64, field: &'static str) -> Result<u64, BalancesError> {
+    value.checked_add(amount).ok_or(BalancesError::Overflow(field))
+}
+
+/// Why a balance operation failed
+///
+/// Surfaced to callers as guest errors whose messages start with the
+/// variant name, so clients can match on them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BalancesError {
+    /// The agent has no account of this kind (listener, artist or TEND)
+    AccountNotFound {
+        kind: &'static str,
+        agent: AgentPubKey,
+    },
+    /// The named balance is too low
+    InsufficientBalance(&'static str),
+    /// Adding to the named total would overflow
+    Overflow(&'static str),
+}
+
+impl std::fmt::Display for BalancesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AccountNotFound { kind, agent } => {
+                write!(f, "AccountNotFound: no {} account for {}", kind, agent)
+            }
+            Self::InsufficientBalance(field) => write!(f, "InsufficientBalance: {} is too low", field),
+            Self::Overflow(field) => write!(f, "Overflow: amount would overflow {}", field),
+        }
+    }
+}
+
+impl From<BalancesError> for WasmError {
+    fn from(error: BalancesError) -> Self {
+        wasm_error!(WasmErrorInner::Guest(error.to_string()))
+    }
+}
+
+/// Apply a listener-to-artist transfer to copies of both accounts
+///
+/// Both accounts must exist, and the debit and credit must both succeed,
+/// before anything is returned to write, so a failure moves no funds.
+pub fn apply_transfer(
+    from: &AgentPubKey,
+    listener: Option<&ListenerAccount>,
+    to: &AgentPubKey,
+    artist: Option<&ArtistAccount>,
+    amount: u64,
+    settled_plays: u64,
+) -> Result<(ListenerAccount, ArtistAccount), BalancesError> {
+    let mut listener = listener.cloned().ok_or_else(|| BalancesError::AccountNotFound {
+        kind: "listener",
+        agent: from.clone(),
+    })?;
+    let mut artist = artist.cloned().ok_or_else(|| BalancesError::AccountNotFound {
+        kind: "artist",
+        agent: to.clone(),
+    })?;
+    listener.debit(amount)?;
+    artist.credit(amount, settled_plays)?;
+    Ok((listener, artist))
+}
+
 /// Artist account - tracks earnings awaiting cashout
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
 pub struct ArtistAccount {
+    /// Entry schema version (missing on v0 entries)
+    #[serde(default)]
+    pub schema_version: u8,
     /// Owner's agent pub key
     pub owner: AgentPubKey,
     /// Ethereum address for payouts
@@ -40,12 +214,192 @@ pub struct ArtistAccount {
     pub total_earned: u64,
     /// Total cashed out
     pub total_cashed_out: u64,
+    /// Plays paid for through settlements (running counter)
+    #[serde(default)]
+    pub total_plays: u64,
+    /// Earnings from play settlements only (excludes tips etc.)
+    #[serde(default)]
+    pub total_play_earnings: u64,
+    /// Key this account is being handed to (set once by the owner; no
+    /// cashouts from then on)
+    #[serde(default)]
+    pub migrated_to: Option<AgentPubKey>,
+    /// When `migrated_to` accepted; the account is frozen from then on
+    #[serde(default)]
+    pub migrated_at: Option<Timestamp>,
+    /// The frozen version of the previous key's account this one took
+    /// over from
+    #[serde(default)]
+    pub migrated_from: Option<ActionHash>,
+    /// Transfer most recently credited; a version that raises the earnings
+    /// totals must name the transfer it credits here
+    #[serde(default)]
+    pub last_transfer: Option<ActionHash>,
+    /// Cashout most recently paid out; a version that lowers the pending
+    /// balance must name the cashout it pays out here
+    #[serde(default)]
+    pub last_cashout: Option<ActionHash>,
     /// Account creation timestamp
     pub created_at: Timestamp,
     /// Last activity timestamp
     pub updated_at: Timestamp,
 }
 
+impl ArtistAccount {
+    /// Upgrade an entry of any prior schema version in memory
+    pub fn upgraded(mut self) -> Self {
+        // v0 -> v1: play counters start at zero
+        // v1 -> v2: accounts start unmigrated
+        // v3 -> v4: no transfer or cashout cited until the next one
+        self.schema_version = ACCOUNT_SCHEMA_VERSION;
+        self
+    }
+
+    /// Add earnings (`settled_plays` > 0 for a play settlement, which also
+    /// advances the play counters); errors rather than wrapping on overflow
+    pub fn credit(&mut self, amount: u64, settled_plays: u64) -> Result<(), BalancesError> {
+        let pending_balance = checked_credit(self.pending_balance, amount, "pending_balance")?;
+        let total_earned = checked_credit(self.total_earned, amount, "total_earned")?;
+        let (total_plays, total_play_earnings) = if settled_plays > 0 {
+            (
+                checked_credit(self.total_plays, settled_plays, "total_plays")?,
+                checked_credit(self.total_play_earnings, amount, "total_play_earnings")?,
+            )
+        } else {
+            (self.total_plays, self.total_play_earnings)
+        };
+        self.pending_balance = pending_balance;
+        self.total_earned = total_earned;
+        self.total_plays = total_plays;
+        self.total_play_earnings = total_play_earnings;
+        Ok(())
+    }
+
+    /// Move earnings out for a cashout; errors if the pending balance is short
+    pub fn debit(&mut self, amount: u64) -> Result<(), BalancesError> {
+        let pending_balance = self
+            .pending_balance
+            .checked_sub(amount)
+            .ok_or(BalancesError::InsufficientBalance("pending_balance"))?;
+        let total_cashed_out = checked_credit(self.total_cashed_out, amount, "total_cashed_out")?;
+        self.pending_balance = pending_balance;
+        self.total_cashed_out = total_cashed_out;
+        Ok(())
+    }
+}
+
+/// Check that a successor account carries over its predecessor exactly
+///
+/// `predecessor` is the version the freeze was made to: handed to the
+/// successor's owner and not already taken over. Balances and counters
+/// must match so nothing is minted or lost in the move.
+pub fn check_account_succession(
+    predecessor: &ArtistAccount,
+    successor: &ArtistAccount,
+) -> Result<(), String> {
+    if predecessor.migrated_to.as_ref() != Some(&successor.owner) {
+        return Err("Predecessor account was not handed to this owner".to_string());
+    }
+    if predecessor.migrated_at.is_some() {
+        return Err("Predecessor account has already been taken over".to_string());
+    }
+    let carried_over = successor.pending_balance == predecessor.pending_balance
+        && successor.total_earned == predecessor.total_earned
+        && successor.total_cashed_out == predecessor.total_cashed_out
+        && successor.total_plays == predecessor.total_plays
+        && successor.total_play_earnings == predecessor.total_play_earnings;
+    if !carried_over {
+        return Err("Successor account must carry over the predecessor's balances".to_string());
+    }
+    Ok(())
+}
+
+/// Check a freeze only takes over the account: the pending balance moves
+/// out and the acceptance time is set, nothing else changes
+pub fn check_account_freeze(
+    original: &ArtistAccount,
+    frozen: &ArtistAccount,
+) -> Result<(), String> {
+    let expected = ArtistAccount {
+        pending_balance: 0,
+        migrated_at: frozen.migrated_at,
+        updated_at: frozen.updated_at,
+        ..original.clone()
+    };
+    if frozen.migrated_at.is_none() || frozen != &expected {
+        return Err("Migrated account must hand over its pending balance unchanged".to_string());
+    }
+    Ok(())
+}
+
+/// Check an artist account update credits exactly the transfer it cites
+///
+/// The pending balance and lifetime earnings rise by the transfer's
+/// amount, and a play settlement also advances the play counters by its
+/// plays; nothing else about the account changes.
+pub fn check_artist_credit(
+    original: &ArtistAccount,
+    updated: &ArtistAccount,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    let settled_plays = if transfer.reason == TransferReason::PlaySettlement {
+        transfer.play_count
+    } else {
+        0
+    };
+    let mut expected = original.clone();
+    expected
+        .credit(transfer.amount, settled_plays)
+        .map_err(|e| e.to_string())?;
+    expected.last_transfer = updated.last_transfer.clone();
+    expected.updated_at = updated.updated_at;
+    if updated != &expected {
+        return Err("Artist credit must add exactly the cited transfer".to_string());
+    }
+    Ok(())
+}
+
+/// Check an artist account update pays out exactly the cashout it cites
+pub fn check_artist_debit(
+    original: &ArtistAccount,
+    updated: &ArtistAccount,
+    cashout: &CashoutRequest,
+) -> Result<(), String> {
+    if cashout.artist != updated.owner {
+        return Err("Cited cashout belongs to another artist".to_string());
+    }
+    let mut expected = original.clone();
+    expected.debit(cashout.amount).map_err(|e| e.to_string())?;
+    expected.last_cashout = updated.last_cashout.clone();
+    expected.updated_at = updated.updated_at;
+    if updated != &expected {
+        return Err("Artist debit must take out exactly the cited cashout".to_string());
+    }
+    Ok(())
+}
+
+/// Check a cashout is covered by the artist account version it cites
+///
+/// `cashed_out` is what the account had paid out before this cashout. The
+/// cited version must already show all of it, so an older version's
+/// balance can't be drawn on a second time.
+pub fn check_cashout_funds(
+    cashout: &CashoutRequest,
+    account: &ArtistAccount,
+    cashed_out: u64,
+) -> Result<(), String> {
+    if account.owner != cashout.artist {
+        return Err("Cashouts must draw on the artist's own account".to_string());
+    }
+    if account.total_cashed_out != cashed_out {
+        return Err("Cashouts must draw on an account version showing earlier cashouts".to_string());
+    }
+    if cashout.amount > account.pending_balance {
+        return Err("Cashout amount exceeds the account's pending balance".to_string());
+    }
+    Ok(())
+}
+
 /// Deposit record - when listener funds their account
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
@@ -62,6 +416,27 @@ pub struct Deposit {
     pub deposited_at: Timestamp,
     /// Verification status
     pub verified: bool,
+    /// Chain head the deposit oracle asserted when confirming it
+    #[serde(default)]
+    pub confirmed_head: Option<u64>,
+}
+
+/// Check a deposit is buried deep enough to confirm, given the chain head
+pub fn check_deposit_finality(
+    block_number: u64,
+    chain_head: u64,
+    min_confirmations: u64,
+) -> Result<(), String> {
+    let confirmations = chain_head
+        .checked_sub(block_number)
+        .ok_or_else(|| "Chain head is behind the deposit's block".to_string())?;
+    if confirmations < min_confirmations {
+        return Err(format!(
+            "Deposit has {} of {} required confirmations",
+            confirmations, min_confirmations
+        ));
+    }
+    Ok(())
 }
 
 /// Cashout request - artist requesting payout
@@ -82,6 +457,10 @@ pub struct CashoutRequest {
     pub tx_hash: Option<String>,
     /// Completion timestamp
     pub completed_at: Option<Timestamp>,
+    /// Artist account version the cashout draws on (missing on cashouts
+    /// made before cashouts debited the account)
+    #[serde(default)]
+    pub account: Option<ActionHash>,
 }
 
 /// Cashout status
@@ -112,12 +491,130 @@ pub struct Transfer {
     pub amount: u64,
     /// Reason (play settlement, tip, etc.)
     pub reason: TransferReason,
-    /// Reference (settlement batch hash, etc.)
+    /// Reference (settlement batch hash, etc.); doubles as an idempotency
+    /// key, so at most one transfer per reason may carry a given reference
     pub reference: Option<ActionHash>,
+    /// Plays covered by this transfer (PlaySettlement only)
+    #[serde(default)]
+    pub play_count: u64,
     /// Timestamp
     pub transferred_at: Timestamp,
 }
 
+/// Find an already-recorded transfer with the given reference and reason
+///
+/// Validation uses it to reject a second transfer for the same reference
+/// on an author's chain. Keyed on reason too, so a batch's protocol fee
+/// and its settlement can share a reference.
+pub fn find_transfer_by_reference(
+    recorded: &[(ActionHash, Transfer)],
+    reference: &ActionHash,
+    reason: &TransferReason,
+) -> Option<ActionHash> {
+    recorded
+        .iter()
+        .find(|(_, transfer)| {
+            transfer.reference.as_ref() == Some(reference) && &transfer.reason == reason
+        })
+        .map(|(hash, _)| hash.clone())
+}
+
+/// Match a transfer about to be made against those linked from its
+/// reference, each given with its author
+///
+/// Anyone can link a transfer to a reference, so only one written by
+/// `author` (whose chain validation keeps to one per reference and reason)
+/// counts, and it must move the same amount between the same parties.
+/// One that doesn't is an error rather than a retry.
+pub fn match_retried_transfer(
+    recorded: &[(ActionHash, AgentPubKey, Transfer)],
+    author: &AgentPubKey,
+    transfer: &Transfer,
+) -> Result<Option<ActionHash>, String> {
+    let reference = match &transfer.reference {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
+    let existing = recorded.iter().find(|(_, recorded_by, existing)| {
+        recorded_by == author
+            && existing.reference.as_ref() == Some(reference)
+            && existing.reason == transfer.reason
+    });
+    match existing {
+        None => Ok(None),
+        Some((hash, _, existing))
+            if existing.from == transfer.from
+                && existing.to == transfer.to
+                && existing.amount == transfer.amount
+                && existing.play_count == transfer.play_count =>
+        {
+            Ok(Some(hash.clone()))
+        }
+        Some((hash, _, _)) => Err(format!(
+            "Reference already paid by transfer {} on different terms",
+            hash
+        )),
+    }
+}
+
+/// Mirror of the plays `SettlementBatch` fields a transfer referencing the
+/// batch is checked against
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct SettledBatch {
+    pub artist: AgentPubKey,
+    pub total_amount: u64,
+    #[serde(default)]
+    pub protocol_fee: u64,
+    pub play_hashes: Vec<ActionHash>,
+}
+
+/// Check a protocol fee transfer against the batch it references, created
+/// by `settler`
+///
+/// The settler pays exactly the batch's fee, to the platform agent.
+pub fn check_fee_transfer(
+    transfer: &Transfer,
+    settler: &AgentPubKey,
+    batch: &SettledBatch,
+    platform_agent: Option<&AgentPubKey>,
+) -> Result<(), String> {
+    if &transfer.from != settler {
+        return Err("Protocol fees must be paid by the batch's settler".to_string());
+    }
+    if platform_agent != Some(&transfer.to) {
+        return Err("Protocol fees must be paid to the platform agent".to_string());
+    }
+    if transfer.amount != batch.protocol_fee {
+        return Err("Protocol fee transfer must match the batch's fee".to_string());
+    }
+    Ok(())
+}
+
+/// Check a play settlement transfer against the batch it references,
+/// created by `settler`
+///
+/// The settler pays the batch's artist its net total, for exactly the
+/// batch's plays, so artist play counters can't be inflated.
+pub fn check_settlement_transfer(
+    transfer: &Transfer,
+    settler: &AgentPubKey,
+    batch: &SettledBatch,
+) -> Result<(), String> {
+    if &transfer.from != settler {
+        return Err("Play settlements must be paid by the batch's settler".to_string());
+    }
+    if transfer.to != batch.artist {
+        return Err("Play settlements must be paid to the batch's artist".to_string());
+    }
+    if transfer.amount != batch.total_amount {
+        return Err("Play settlement transfer must match the batch's total".to_string());
+    }
+    if transfer.play_count != batch.play_hashes.len() as u64 {
+        return Err("Play settlement transfer must cover the batch's plays".to_string());
+    }
+    Ok(())
+}
+
 /// Transfer reason
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
@@ -132,6 +629,324 @@ pub enum TransferReason {
     Download,
     /// NFT access
     NftAccess,
+    /// Protocol fee taken from a settlement batch
+    ProtocolFee,
+}
+
+/// A listener's totals as their deposit and transfer history accounts for
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListenerLedger {
+    /// Deposits that have been credited
+    pub total_deposited: u64,
+    /// Transfers sent
+    pub total_spent: u64,
+}
+
+impl ListenerLedger {
+    /// Replay `owner`'s deposits and transfers
+    ///
+    /// A deposit counts once it is credited: on record without a deposit
+    /// oracle, on confirmation with one. Errors if a total would overflow.
+    pub fn replay(
+        owner: &AgentPubKey,
+        deposits: &[Deposit],
+        transfers: &[Transfer],
+        oracle_configured: bool,
+    ) -> Result<Self, BalancesError> {
+        let mut ledger = Self::default();
+        for deposit in deposits {
+            if &deposit.listener == owner && (deposit.verified || !oracle_configured) {
+                ledger.total_deposited =
+                    checked_credit(ledger.total_deposited, deposit.amount, "total_deposited")?;
+            }
+        }
+        for transfer in transfers.iter().filter(|transfer| &transfer.from == owner) {
+            ledger.total_spent =
+                checked_credit(ledger.total_spent, transfer.amount, "total_spent")?;
+        }
+        Ok(ledger)
+    }
+
+    /// The balance the history leaves, or None if it spends more than was
+    /// deposited
+    pub fn balance(&self) -> Option<u64> {
+        self.total_deposited.checked_sub(self.total_spent)
+    }
+}
+
+/// How far a listener account's totals are from its history (recorded
+/// minus expected, saturating at the bounds of i64)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BalanceDrift {
+    /// Positive when the account holds more than its history explains
+    pub balance: i64,
+    pub total_deposited: i64,
+    pub total_spent: i64,
+}
+
+impl BalanceDrift {
+    pub fn between(account: &ListenerAccount, ledger: &ListenerLedger) -> Self {
+        let expected_balance = ledger.total_deposited as i128 - ledger.total_spent as i128;
+        Self {
+            balance: saturating_i64(account.balance as i128 - expected_balance),
+            total_deposited: saturating_i64(
+                account.total_deposited as i128 - ledger.total_deposited as i128,
+            ),
+            total_spent: saturating_i64(account.total_spent as i128 - ledger.total_spent as i128),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+fn saturating_i64(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Check a listener account update only moves its balance by what it
+/// deposits and spends
+///
+/// Totals never go down, and the balance changes by exactly the new
+/// deposits less the new spending. Anything else rewrites the account's
+/// history and is left to balance auditors (see `reconcile_account`).
+pub fn check_listener_balance_change(
+    original: &ListenerAccount,
+    updated: &ListenerAccount,
+) -> Result<(), String> {
+    if updated.total_deposited < original.total_deposited
+        || updated.total_spent < original.total_spent
+    {
+        return Err("Listener account totals cannot decrease".to_string());
+    }
+    let deposited = (updated.total_deposited - original.total_deposited) as i128;
+    let spent = (updated.total_spent - original.total_spent) as i128;
+    if updated.balance as i128 - original.balance as i128 != deposited - spent {
+        return Err("Listener balance can only change by deposits and spending".to_string());
+    }
+    Ok(())
+}
+
+/// Check the deposit a listener account update cites explains the rise in
+/// `total_deposited`
+///
+/// The deposit must be the owner's, for exactly the amount credited, and
+/// confirmed when a deposit oracle is configured. That it is credited only
+/// once is checked against the crediting agent's chain.
+pub fn check_deposit_credit(
+    original: &ListenerAccount,
+    updated: &ListenerAccount,
+    deposit: &Deposit,
+    oracle_configured: bool,
+) -> Result<(), String> {
+    if deposit.listener != updated.owner {
+        return Err("Cited deposit belongs to another listener".to_string());
+    }
+    if oracle_configured && !deposit.verified {
+        return Err("Cited deposit has not been confirmed by the deposit oracle".to_string());
+    }
+    if updated.total_deposited.checked_sub(original.total_deposited) != Some(deposit.amount) {
+        return Err("Deposit credit must equal the cited deposit's amount".to_string());
+    }
+    Ok(())
+}
+
+/// Check a listener account update spends exactly the transfer it cites
+///
+/// The transfer must be paid by the owner, `total_spent` rises by exactly
+/// its amount, and no deposit is credited in the same version.
+pub fn check_listener_debit(
+    original: &ListenerAccount,
+    updated: &ListenerAccount,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    if transfer.from != updated.owner {
+        return Err("Cited transfer was paid by another listener".to_string());
+    }
+    if updated.total_deposited != original.total_deposited {
+        return Err("Listener debits cannot also credit a deposit".to_string());
+    }
+    if updated.total_spent.checked_sub(original.total_spent) != Some(transfer.amount) {
+        return Err("Listener debit must equal the cited transfer's amount".to_string());
+    }
+    Ok(())
+}
+
+/// TEND (time-barter credit) account
+///
+/// TEND is a mutual credit kept apart from the wei-denominated accounts:
+/// balances start at zero and may go negative down to the DNA's
+/// `tend_credit_limit`, so time is exchanged without anyone minting it.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct TendAccount {
+    pub owner: AgentPubKey,
+    /// Current balance in TEND seconds (negative = in credit)
+    pub balance: i64,
+    /// TEND received all-time
+    pub total_earned: u64,
+    /// TEND spent all-time
+    pub total_spent: u64,
+    /// Transfer most recently applied; every version that moves the
+    /// totals names the one transfer it applies here
+    #[serde(default)]
+    pub last_transfer: Option<ActionHash>,
+    /// On a credit, the sender's account version that debited the cited
+    /// transfer, so received TEND is always spent by someone
+    #[serde(default)]
+    pub sender_debit: Option<ActionHash>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+impl TendAccount {
+    /// Receive TEND; errors rather than wrapping on overflow
+    pub fn credit(&mut self, amount: u64) -> Result<(), BalancesError> {
+        let balance = i64::try_from(amount)
+            .ok()
+            .and_then(|amount| self.balance.checked_add(amount))
+            .ok_or(BalancesError::Overflow("balance"))?;
+        let total_earned = checked_credit(self.total_earned, amount, "total_earned")?;
+        self.balance = balance;
+        self.total_earned = total_earned;
+        Ok(())
+    }
+
+    /// Spend TEND, going no further below zero than `credit_limit`
+    pub fn debit(&mut self, amount: u64, credit_limit: u64) -> Result<(), BalancesError> {
+        let floor = -i64::try_from(credit_limit).unwrap_or(i64::MAX);
+        let balance = i64::try_from(amount)
+            .ok()
+            .and_then(|amount| self.balance.checked_sub(amount))
+            .filter(|balance| *balance >= floor)
+            .ok_or(BalancesError::InsufficientBalance("TEND balance"))?;
+        let total_spent = checked_credit(self.total_spent, amount, "total_spent")?;
+        self.balance = balance;
+        self.total_spent = total_spent;
+        Ok(())
+    }
+}
+
+/// Check a TEND account update applies exactly the transfer it cites
+///
+/// Receiving raises `total_earned` by a transfer to the owner, and
+/// spending raises `total_spent` by a transfer from them; one version
+/// never does both, and totals never go down.
+pub fn check_tend_change(
+    original: &TendAccount,
+    updated: &TendAccount,
+    transfer: &TendTransfer,
+) -> Result<(), String> {
+    let earned = updated.total_earned.checked_sub(original.total_earned);
+    let spent = updated.total_spent.checked_sub(original.total_spent);
+    match (earned, spent) {
+        (Some(earned), Some(0)) if earned > 0 => {
+            if transfer.to != updated.owner || transfer.amount != earned {
+                return Err("TEND credit must match a transfer to the owner".to_string());
+            }
+        }
+        (Some(0), Some(spent)) if spent > 0 => {
+            if transfer.from != updated.owner || transfer.amount != spent {
+                return Err("TEND debit must match a transfer from the owner".to_string());
+            }
+        }
+        _ => {
+            return Err("TEND account updates must apply exactly one transfer".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Transfer of TEND between agents, e.g. a listener paying for a
+/// time-barter play
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct TendTransfer {
+    pub from: AgentPubKey,
+    pub to: AgentPubKey,
+    /// TEND seconds
+    pub amount: u64,
+    /// Reference (e.g. the play record); at most one transfer per reference
+    pub reference: Option<ActionHash>,
+    pub transferred_at: Timestamp,
+}
+
+/// Tag of the link from a TEND transfer's recipient to it: the sender's
+/// account version that debited it, which the recipient's credit cites
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct TendTransferTag {
+    pub debit: ActionHash,
+}
+
+impl TendTransferTag {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let bytes = SerializedBytes::try_from(self.clone()).map_err(|e| wasm_error!(e))?;
+        Ok(LinkTag::new(bytes.bytes().clone()))
+    }
+
+    pub fn from_link_tag(tag: &LinkTag) -> Option<Self> {
+        Self::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone()))).ok()
+    }
+}
+
+/// DNA properties read by the balances zome
+///
+/// `platform_agent` is the same property the plays zome pays protocol
+/// fees to.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BalancesProperties {
+    pub platform_agent: Option<AgentPubKey>,
+    /// How far below zero a TEND balance may go (TEND seconds)
+    pub tend_credit_limit: u64,
+    /// Agent that confirms deposits on-chain. When set, deposits are only
+    /// credited once it confirms them; when unset they credit on record.
+    pub deposit_oracle: Option<AgentPubKey>,
+    /// Blocks a deposit must be buried under before it can be confirmed
+    pub min_deposit_confirmations: u64,
+    /// Agents allowed to correct listener accounts that drifted from
+    /// their deposit and transfer history
+    pub balance_auditors: Vec<AgentPubKey>,
+}
+
+impl Default for BalancesProperties {
+    fn default() -> Self {
+        Self {
+            platform_agent: None,
+            // One hour of listening
+            tend_credit_limit: 3_600,
+            deposit_oracle: None,
+            // Same depth the API indexer waits for
+            min_deposit_confirmations: 3,
+            balance_auditors: Vec::new(),
+        }
+    }
+}
+
+/// Parse balances settings from DNA properties; defaults if none are set
+///
+/// Malformed properties are an error rather than the defaults, so a typo in
+/// a limit or the platform agent can't go unnoticed.
+pub fn parse_balances_properties(
+    properties: SerializedBytes,
+) -> Result<BalancesProperties, String> {
+    if properties_unset(&properties) {
+        return Ok(BalancesProperties::default());
+    }
+    BalancesProperties::try_from(properties)
+        .map_err(|e| format!("Malformed balances DNA properties: {}", e))
+}
+
+/// Read balances settings from DNA properties
+pub fn balances_properties() -> ExternResult<BalancesProperties> {
+    parse_balances_properties(dna_info()?.modifiers.properties)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))
+}
+
+/// Whether the DNA manifest sets no properties (empty, or msgpack nil)
+fn properties_unset(properties: &SerializedBytes) -> bool {
+    matches!(properties.bytes().as_slice(), [] | [0xc0])
 }
 
 /// Link types
@@ -147,6 +962,12 @@ pub enum LinkTypes {
     AgentToCashouts,
     /// Agent -> Transfers (as sender or recipient)
     AgentToTransfers,
+    /// Transfer reference (e.g. settlement batch) -> Transfer
+    ReferenceToTransfer,
+    /// Agent -> Their TEND account
+    AgentToTendAccount,
+    /// Agent -> TEND transfers (as sender or recipient)
+    AgentToTendTransfers,
 }
 
 /// Entry types
@@ -158,6 +979,8 @@ pub enum EntryTypes {
     Deposit(Deposit),
     CashoutRequest(CashoutRequest),
     Transfer(Transfer),
+    TendAccount(TendAccount),
+    TendTransfer(TendTransfer),
 }
 
 /// Validation
@@ -165,6 +988,30 @@ pub enum EntryTypes {
 pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
     match op.flattened::<EntryTypes, LinkTypes>()? {
         FlatOp::StoreEntry(store_entry) => match store_entry {
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::ListenerAccount(account),
+                action,
+                original_action_hash,
+                original_entry_hash: _,
+            } => validate_update_listener_account(account, action, original_action_hash),
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::TendAccount(account),
+                action,
+                original_action_hash,
+                original_entry_hash: _,
+            } => validate_update_tend_account(account, action, original_action_hash),
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::ArtistAccount(account),
+                action,
+                original_action_hash,
+                original_entry_hash: _,
+            } => validate_update_artist_account(account, action, original_action_hash),
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::Deposit(deposit),
+                action,
+                original_action_hash,
+                original_entry_hash: _,
+            } => validate_update_deposit(deposit, action, original_action_hash),
             OpEntry::CreateEntry { app_entry, action } => match app_entry {
                 EntryTypes::ListenerAccount(account) => {
                     validate_listener_account(account, action)
@@ -175,13 +1022,60 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                 EntryTypes::Deposit(deposit) => validate_deposit(deposit, action),
                 EntryTypes::CashoutRequest(cashout) => validate_cashout(cashout, action),
                 EntryTypes::Transfer(transfer) => validate_transfer(transfer, action),
+                EntryTypes::TendAccount(account) => validate_tend_account(account, action),
+                EntryTypes::TendTransfer(transfer) => validate_tend_transfer(transfer, action),
             },
             _ => Ok(ValidateCallbackResult::Valid),
         },
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::ReferenceToTransfer,
+            base_address,
+            target_address,
+            tag: _,
+            action,
+        } => validate_reference_link(base_address, target_address, action),
         _ => Ok(ValidateCallbackResult::Valid),
     }
 }
 
+/// Reference links index a transfer under its own reference, made by the
+/// transfer's author
+fn validate_reference_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Reference links must point to a transfer".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(target)?;
+    let transfer = match record
+        .entry()
+        .to_app_option::<Transfer>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(transfer) => transfer,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Reference links must point to a transfer".to_string(),
+            ))
+        }
+    };
+    if record.action().author() != &action.author
+        || base_address.into_action_hash() != transfer.reference
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Reference links must be made by the transfer's author from its reference".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
 fn validate_listener_account(
     account: ListenerAccount,
     action: Create,
@@ -203,87 +1097,1625 @@ fn validate_listener_account(
     Ok(ValidateCallbackResult::Valid)
 }
 
-fn validate_artist_account(
-    account: ArtistAccount,
-    action: Create,
+fn validate_update_listener_account(
+    account: ListenerAccount,
+    action: Update,
+    original_action_hash: ActionHash,
 ) -> ExternResult<ValidateCallbackResult> {
-    // Owner must match author
-    if account.owner != action.author {
+    let original: Option<ListenerAccount> = must_get_valid_record(original_action_hash)?
+        .entry()
+        .to_app_option::<ListenerAccount>()
+        .map_err(|e| wasm_error!(e))?
+        .map(ListenerAccount::upgraded);
+
+    let original = match original {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not a listener account".to_string(),
+            ))
+        }
+    };
+
+    if account.owner != original.owner {
         return Ok(ValidateCallbackResult::Invalid(
-            "Account owner must match action author".to_string(),
+            "Listener account owner cannot change".to_string(),
         ));
     }
 
-    // Must have valid Ethereum address
-    if !account.eth_address.starts_with("0x") || account.eth_address.len() != 42 {
+    // Only the owner can change their own spending limits
+    let limits_changed = account.daily_limit != original.daily_limit
+        || account.monthly_limit != original.monthly_limit;
+    if limits_changed && action.author != account.owner {
         return Ok(ValidateCallbackResult::Invalid(
-            "Invalid Ethereum address format".to_string(),
+            "Only the account owner can change spending limits".to_string(),
         ));
     }
 
+    // Corrections may move the balance freely, but only auditors make them
+    let properties = balances_properties()?;
+    if properties.balance_auditors.contains(&action.author) {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+    if let Err(reason) = check_listener_balance_change(&original, &account) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    // Spending is the only way the balance goes down
+    if account.total_spent > original.total_spent {
+        return validate_listener_debit(&original, &account, &action);
+    }
+    if account.total_deposited > original.total_deposited {
+        return validate_deposit_credit(&original, &account, &action, &properties);
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
 
-fn validate_deposit(deposit: Deposit, _action: Create) -> ExternResult<ValidateCallbackResult> {
-    // Deposit must have a transaction hash
-    if deposit.tx_hash.is_empty() {
+/// Check a listener account debit is the owner paying a transfer of theirs
+/// that hasn't been paid before
+fn validate_listener_debit(
+    original: &ListenerAccount,
+    account: &ListenerAccount,
+    action: &Update,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.author != account.owner {
         return Ok(ValidateCallbackResult::Invalid(
-            "Deposit must have a transaction hash".to_string(),
+            "Only the account owner can spend from a listener account".to_string(),
+        ));
+    }
+    let transfer_hash = match &account.last_transfer {
+        Some(hash) if account.last_transfer != original.last_transfer => hash.clone(),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Listener debits must cite the transfer they pay".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(transfer_hash.clone())?;
+    let transfer = match record
+        .entry()
+        .to_app_option::<Transfer>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(transfer) => transfer,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Cited entry is not a transfer".to_string(),
+            ))
+        }
+    };
+    if record.action().author() != &account.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Listener debits must pay a transfer the owner made".to_string(),
         ));
     }
+    if let Err(reason) = check_listener_debit(original, account, &transfer) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
 
-    // Amount must be positive
-    if deposit.amount == 0 {
+    let already_paid = prior_entries::<ListenerAccount>(
+        &action.author,
+        &action.prev_action,
+        UnitEntryTypes::ListenerAccount,
+    )?
+    .iter()
+    .any(|(_, prior)| prior.last_transfer.as_ref() == Some(&transfer_hash));
+    if already_paid {
         return Ok(ValidateCallbackResult::Invalid(
-            "Deposit amount must be greater than 0".to_string(),
+            "Transfer has already been paid".to_string(),
         ));
     }
 
     Ok(ValidateCallbackResult::Valid)
 }
 
-fn validate_cashout(
-    cashout: CashoutRequest,
+/// Check a listener account credit cites a deposit that backs it and that
+/// hasn't been credited before
+///
+/// Only one agent may credit a given deposit (the deposit oracle when one
+/// is configured, the listener otherwise), so scanning that agent's chain
+/// for an earlier version citing it is enough to stop a second credit.
+fn validate_deposit_credit(
+    original: &ListenerAccount,
+    account: &ListenerAccount,
+    action: &Update,
+    properties: &BalancesProperties,
+) -> ExternResult<ValidateCallbackResult> {
+    let deposit_hash = match &account.last_deposit {
+        Some(hash) if account.last_deposit != original.last_deposit => hash.clone(),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Deposit credits must cite the deposit they credit".to_string(),
+            ))
+        }
+    };
+
+    let crediting_agent = properties.deposit_oracle.as_ref().unwrap_or(&account.owner);
+    if &action.author != crediting_agent {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposits are credited by the deposit oracle, or by the listener without one"
+                .to_string(),
+        ));
+    }
+
+    let deposit = match must_get_valid_record(deposit_hash.clone())?
+        .entry()
+        .to_app_option::<Deposit>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(deposit) => deposit,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Cited deposit is not a deposit".to_string(),
+            ))
+        }
+    };
+    if let Err(reason) =
+        check_deposit_credit(original, account, &deposit, properties.deposit_oracle.is_some())
+    {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    let already_credited = prior_entries::<ListenerAccount>(
+        &action.author,
+        &action.prev_action,
+        UnitEntryTypes::ListenerAccount,
+    )?
+    .iter()
+    .any(|(_, prior)| {
+        prior.owner == account.owner && prior.last_deposit.as_ref() == Some(&deposit_hash)
+    });
+    if already_credited {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposit has already been credited".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_artist_account(
+    account: ArtistAccount,
     action: Create,
 ) -> ExternResult<ValidateCallbackResult> {
-    // Artist must match author
-    if cashout.artist != action.author {
+    // Owner must match author
+    if account.owner != action.author {
         return Ok(ValidateCallbackResult::Invalid(
-            "Cashout artist must match action author".to_string(),
+            "Account owner must match action author".to_string(),
         ));
     }
 
-    // Amount must be positive
-    if cashout.amount == 0 {
+    // Must have valid Ethereum address
+    if !account.eth_address.starts_with("0x") || account.eth_address.len() != 42 {
         return Ok(ValidateCallbackResult::Invalid(
-            "Cashout amount must be greater than 0".to_string(),
+            "Invalid Ethereum address format".to_string(),
         ));
     }
 
-    // New cashouts must be pending
-    if cashout.status != CashoutStatus::Pending {
+    if account.migrated_to.is_some() || account.migrated_at.is_some() {
         return Ok(ValidateCallbackResult::Invalid(
-            "New cashout requests must have Pending status".to_string(),
+            "New artist accounts cannot start migrated".to_string(),
         ));
     }
 
+    // One artist account per key, so a handover yields one successor
+    let has_account = prior_entries::<ArtistAccount>(
+        &action.author,
+        &action.prev_action,
+        UnitEntryTypes::ArtistAccount,
+    )?
+    .iter()
+    .any(|(_, prior)| prior.owner == action.author);
+    if has_account {
+        return Ok(ValidateCallbackResult::Invalid(
+            "This key already has an artist account".to_string(),
+        ));
+    }
+
+    // A successor may start with balances only by taking over its predecessor
+    match &account.migrated_from {
+        Some(frozen_hash) => {
+            return validate_account_succession(&account, frozen_hash.clone(), &action);
+        }
+        None => {
+            if account.pending_balance != 0
+                || account.total_earned != 0
+                || account.total_cashed_out != 0
+                || account.total_plays != 0
+                || account.total_play_earnings != 0
+            {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "New artist accounts must start at zero".to_string(),
+                ));
+            }
+        }
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
 
-fn validate_transfer(transfer: Transfer, _action: Create) -> ExternResult<ValidateCallbackResult> {
-    // Amount must be positive
-    if transfer.amount == 0 {
+/// Check a successor cites the freeze its owner made of the predecessor,
+/// and carries over the version that freeze was made to
+fn validate_account_succession(
+    account: &ArtistAccount,
+    frozen_hash: ActionHash,
+    action: &Create,
+) -> ExternResult<ValidateCallbackResult> {
+    let frozen_record = must_get_valid_record(frozen_hash)?;
+    let frozen_from = match frozen_record.action() {
+        Action::Update(update) if update.author == action.author => {
+            update.original_action_address.clone()
+        }
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Successor must cite the freeze it made of the predecessor".to_string(),
+            ))
+        }
+    };
+    let frozen = frozen_record
+        .entry()
+        .to_app_option::<ArtistAccount>()
+        .map_err(|e| wasm_error!(e))?
+        .map(ArtistAccount::upgraded);
+    let predecessor = read_artist_record(frozen_from)?;
+    let (frozen, predecessor) = match (frozen, predecessor) {
+        (Some(frozen), Some(predecessor)) if frozen.migrated_at.is_some() => (frozen, predecessor),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Predecessor is not a frozen artist account".to_string(),
+            ))
+        }
+    };
+    if frozen.migrated_to.as_ref() != Some(&account.owner) {
         return Ok(ValidateCallbackResult::Invalid(
-            "Transfer amount must be greater than 0".to_string(),
+            "Predecessor account was not handed to this owner".to_string(),
         ));
     }
+    if let Err(reason) = check_account_succession(&predecessor, account) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
 
-    // From and to must be different
-    if transfer.from == transfer.to {
+/// Whether `owner` has handed their artist account over (scans their chain)
+fn has_handed_over(owner: &AgentPubKey, until: &ActionHash) -> ExternResult<bool> {
+    Ok(
+        prior_entries::<ArtistAccount>(owner, until, UnitEntryTypes::ArtistAccount)?
+            .iter()
+            .any(|(_, prior)| &prior.owner == owner && prior.migrated_to.is_some()),
+    )
+}
+
+fn validate_update_artist_account(
+    account: ArtistAccount,
+    action: Update,
+    original_action_hash: ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let original = match read_artist_record(original_action_hash.clone())? {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not an artist account".to_string(),
+            ))
+        }
+    };
+
+    if account.owner != original.owner || account.migrated_from != original.migrated_from {
         return Ok(ValidateCallbackResult::Invalid(
-            "Cannot transfer to self".to_string(),
+            "Artist account owner cannot change".to_string(),
         ));
     }
 
-    Ok(ValidateCallbackResult::Valid)
+    // Taken-over accounts are frozen; credits go to the successor
+    if original.migrated_at.is_some() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Artist account has been migrated to a new key".to_string(),
+        ));
+    }
+
+    // Only the owner hands the account over, once: re-pointing a stale
+    // version at another key would let two successors claim it
+    if account.migrated_to != original.migrated_to {
+        if action.author != account.owner {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the account owner can transfer the account".to_string(),
+            ));
+        }
+        if original.migrated_to.is_some()
+            || has_handed_over(&action.author, &action.prev_action)?
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Artist account has already been handed over".to_string(),
+            ));
+        }
+    }
+
+    // The takeover is signed by the new owner and empties the old account
+    if account.migrated_at.is_some() {
+        if account.migrated_to.as_ref() != Some(&action.author)
+            || account.migrated_to != original.migrated_to
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only the new owner can accept an account transfer".to_string(),
+            ));
+        }
+        if let Err(reason) = check_account_freeze(&original, &account) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+        return Ok(ValidateCallbackResult::Valid);
+    }
+
+    // Earnings only move by a cited transfer, credited by its sender, or a
+    // cited cashout, paid out by the owner
+    if account.last_transfer != original.last_transfer {
+        return validate_artist_credit(&original, &account, &action);
+    }
+    if account.last_cashout != original.last_cashout {
+        return validate_artist_debit(&original, &account, &action, &original_action_hash);
+    }
+
+    if action.author != account.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the account owner can update an artist account".to_string(),
+        ));
+    }
+    let unchanged = ArtistAccount {
+        eth_address: account.eth_address.clone(),
+        migrated_to: account.migrated_to.clone(),
+        updated_at: account.updated_at,
+        ..original
+    };
+    if account != unchanged {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Artist balances only change by a cited transfer or cashout".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Check an artist account credit cites a transfer paid to the account
+/// that hasn't been credited before
+///
+/// Only the transfer's author may credit it, so scanning their chain for
+/// an earlier version citing it is enough to stop a second credit.
+fn validate_artist_credit(
+    original: &ArtistAccount,
+    account: &ArtistAccount,
+    action: &Update,
+) -> ExternResult<ValidateCallbackResult> {
+    let transfer_hash = match &account.last_transfer {
+        Some(hash) => hash.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Artist credits must cite the transfer they credit".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(transfer_hash.clone())?;
+    let transfer = match record
+        .entry()
+        .to_app_option::<Transfer>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(transfer) => transfer,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Cited entry is not a transfer".to_string(),
+            ))
+        }
+    };
+    if record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Artist credits must be written by the transfer's author".to_string(),
+        ));
+    }
+    if !account_keys(account)?.contains(&transfer.to) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cited transfer was not paid to this account".to_string(),
+        ));
+    }
+    if is_batch_transfer(&transfer) {
+        if let invalid @ ValidateCallbackResult::Invalid(_) = validate_batch_transfer(&transfer)? {
+            return Ok(invalid);
+        }
+    }
+    if let Err(reason) = check_artist_credit(original, account, &transfer) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    let already_credited = prior_entries::<ArtistAccount>(
+        &action.author,
+        &action.prev_action,
+        UnitEntryTypes::ArtistAccount,
+    )?
+    .iter()
+    .any(|(_, prior)| prior.last_transfer.as_ref() == Some(&transfer_hash));
+    if already_credited {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Transfer has already been credited".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Check an artist account debit is the owner paying out one of their
+/// cashouts, drawn on the version being updated and not paid out before
+fn validate_artist_debit(
+    original: &ArtistAccount,
+    account: &ArtistAccount,
+    action: &Update,
+    original_action_hash: &ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.author != account.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the account owner can pay out a cashout".to_string(),
+        ));
+    }
+    let cashout_hash = match &account.last_cashout {
+        Some(hash) => hash.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Artist debits must cite the cashout they pay out".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(cashout_hash.clone())?;
+    let cashout = match record
+        .entry()
+        .to_app_option::<CashoutRequest>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(cashout) => cashout,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Cited entry is not a cashout request".to_string(),
+            ))
+        }
+    };
+    if record.action().author() != &account.owner
+        || cashout.account.as_ref() != Some(original_action_hash)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Artist debits must pay out the owner's cashout drawn on this version".to_string(),
+        ));
+    }
+    if let Err(reason) = check_artist_debit(original, account, &cashout) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    let already_paid = prior_entries::<ArtistAccount>(
+        &action.author,
+        &action.prev_action,
+        UnitEntryTypes::ArtistAccount,
+    )?
+    .iter()
+    .any(|(_, prior)| prior.last_cashout.as_ref() == Some(&cashout_hash));
+    if already_paid {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cashout has already been paid out".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Keys an artist account has been held under: its owner, then each
+/// predecessor it took over from
+fn account_keys(account: &ArtistAccount) -> ExternResult<Vec<AgentPubKey>> {
+    let mut keys = vec![account.owner.clone()];
+    let mut migrated_from = account.migrated_from.clone();
+    while let Some(frozen_hash) = migrated_from {
+        match read_artist_record(frozen_hash)? {
+            Some(frozen) => {
+                keys.push(frozen.owner);
+                migrated_from = frozen.migrated_from;
+            }
+            None => break,
+        }
+    }
+    Ok(keys)
+}
+
+/// Decode an artist account record of any schema version
+fn read_artist_record(action_hash: ActionHash) -> ExternResult<Option<ArtistAccount>> {
+    Ok(must_get_valid_record(action_hash)?
+        .entry()
+        .to_app_option::<ArtistAccount>()
+        .map_err(|e| wasm_error!(e))?
+        .map(ArtistAccount::upgraded))
+}
+
+fn validate_deposit(deposit: Deposit, action: Create) -> ExternResult<ValidateCallbackResult> {
+    // Listeners record their own deposits
+    if deposit.listener != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposit listener must match action author".to_string(),
+        ));
+    }
+
+    // Deposit must have a transaction hash
+    if deposit.tx_hash.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposit must have a transaction hash".to_string(),
+        ));
+    }
+
+    // Amount must be positive
+    if deposit.amount == 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposit amount must be greater than 0".to_string(),
+        ));
+    }
+
+    // Only the oracle confirms, and only with an update
+    if deposit.verified || deposit.confirmed_head.is_some() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposits must be recorded unconfirmed".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_update_deposit(
+    deposit: Deposit,
+    action: Update,
+    original_action_hash: ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let original = match must_get_valid_record(original_action_hash)?
+        .entry()
+        .to_app_option::<Deposit>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not a deposit".to_string(),
+            ))
+        }
+    };
+
+    let properties = balances_properties()?;
+    if properties.deposit_oracle.as_ref() != Some(&action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the deposit oracle can confirm deposits".to_string(),
+        ));
+    }
+    if original.verified {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Deposit is already confirmed".to_string(),
+        ));
+    }
+
+    // Confirmation sets the verified flag and head, nothing else
+    let chain_head = match deposit.confirmed_head {
+        Some(chain_head) if deposit.verified => chain_head,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Deposit updates must confirm the deposit at a chain head".to_string(),
+            ))
+        }
+    };
+    let unchanged = Deposit {
+        verified: true,
+        confirmed_head: Some(chain_head),
+        ..original
+    };
+    if deposit != unchanged {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Confirming a deposit cannot change its details".to_string(),
+        ));
+    }
+
+    if let Err(reason) = check_deposit_finality(
+        deposit.block_number,
+        chain_head,
+        properties.min_deposit_confirmations,
+    ) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_cashout(
+    cashout: CashoutRequest,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    // Artist must match author
+    if cashout.artist != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cashout artist must match action author".to_string(),
+        ));
+    }
+
+    // Amount must be positive
+    if cashout.amount == 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cashout amount must be greater than 0".to_string(),
+        ));
+    }
+
+    // New cashouts must be pending
+    if cashout.status != CashoutStatus::Pending {
+        return Ok(ValidateCallbackResult::Invalid(
+            "New cashout requests must have Pending status".to_string(),
+        ));
+    }
+
+    // A handed-over balance belongs to the successor
+    if has_handed_over(&action.author, &action.prev_action)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Artist account has been handed to a new key".to_string(),
+        ));
+    }
+
+    // The cited account version must cover the amount after every earlier
+    // cashout drawn on the account
+    let account = match &cashout.account {
+        Some(account_hash) => read_artist_record(account_hash.clone())?,
+        None => None,
+    };
+    let account = match account {
+        Some(account) => account,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Cashouts must cite the artist account they draw on".to_string(),
+            ))
+        }
+    };
+    let carried_over = match &account.migrated_from {
+        Some(frozen_hash) => read_artist_record(frozen_hash.clone())?
+            .map_or(0, |frozen| frozen.total_cashed_out),
+        None => 0,
+    };
+    let cashed_out = prior_created::<CashoutRequest>(
+        &action.author,
+        &action.prev_action,
+        UnitEntryTypes::CashoutRequest,
+    )?
+    .iter()
+    .filter(|(_, prior)| prior.account.is_some())
+    .fold(carried_over, |total, (_, prior)| total.saturating_add(prior.amount));
+    if let Err(reason) = check_cashout_funds(&cashout, &account, cashed_out) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_transfer(transfer: Transfer, action: Create) -> ExternResult<ValidateCallbackResult> {
+    // Amount must be positive
+    if transfer.amount == 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Transfer amount must be greater than 0".to_string(),
+        ));
+    }
+
+    // From and to must be different
+    if transfer.from == transfer.to {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cannot transfer to self".to_string(),
+        ));
+    }
+
+    // Play counts only make sense for settlements, and settlements need them
+    match transfer.reason {
+        TransferReason::PlaySettlement if transfer.play_count == 0 => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Play settlement transfers must cover at least one play".to_string(),
+            ));
+        }
+        TransferReason::PlaySettlement => {}
+        _ if transfer.play_count != 0 => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only play settlement transfers can carry a play count".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    // Settlements and protocol fees are exactly what the referenced batch
+    // charged
+    if is_batch_transfer(&transfer) {
+        if transfer.from != action.author {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Batch transfers must be paid by the transfer's author".to_string(),
+            ));
+        }
+        if let invalid @ ValidateCallbackResult::Invalid(_) = validate_batch_transfer(&transfer)? {
+            return Ok(invalid);
+        }
+    }
+
+    // A reference can only be paid once (checked against the author's chain)
+    if let Some(reference) = &transfer.reference {
+        let prior = prior_transfers(&action.author, &action.prev_action)?;
+        if let Some(existing) = find_transfer_by_reference(&prior, reference, &transfer.reason) {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Reference already paid by transfer {}",
+                existing
+            )));
+        }
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Whether a transfer pays for a settlement batch it references
+fn is_batch_transfer(transfer: &Transfer) -> bool {
+    matches!(transfer.reason, TransferReason::PlaySettlement | TransferReason::ProtocolFee)
+}
+
+/// Check a settlement or protocol fee transfer against the batch it
+/// references
+fn validate_batch_transfer(transfer: &Transfer) -> ExternResult<ValidateCallbackResult> {
+    let batch_hash = match &transfer.reference {
+        Some(batch_hash) => batch_hash.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Batch transfers must reference their settlement batch".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(batch_hash)?;
+    let batch = match record.entry().to_app_option::<SettledBatch>() {
+        Ok(Some(batch)) => batch,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Batch transfers must reference a settlement batch".to_string(),
+            ))
+        }
+    };
+    let settler = record.action().author();
+    let checked = if transfer.reason == TransferReason::ProtocolFee {
+        let platform_agent = balances_properties()?.platform_agent;
+        check_fee_transfer(transfer, settler, &batch, platform_agent.as_ref())
+    } else {
+        check_settlement_transfer(transfer, settler, &batch)
+    };
+    if let Err(reason) = checked {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_tend_account(
+    account: TendAccount,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    if account.owner != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Account owner must match action author".to_string(),
+        ));
+    }
+
+    // TEND only moves through transfers, so accounts open empty
+    if account.balance != 0 || account.total_earned != 0 || account.total_spent != 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "New TEND accounts must start at zero".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_update_tend_account(
+    account: TendAccount,
+    action: Update,
+    original_action_hash: ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let original = match must_get_valid_record(original_action_hash)?
+        .entry()
+        .to_app_option::<TendAccount>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not a TEND account".to_string(),
+            ))
+        }
+    };
+
+    if account.owner != original.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "TEND account owner cannot change".to_string(),
+        ));
+    }
+
+    // Recipients claim their own credits, so the chain never forks
+    if action.author != account.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the account owner can update a TEND account".to_string(),
+        ));
+    }
+
+    // Balance must be exactly what the running totals say
+    if account.balance as i128 != account.total_earned as i128 - account.total_spent as i128 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "TEND balance must equal earned minus spent".to_string(),
+        ));
+    }
+
+    let totals_changed = account.total_earned != original.total_earned
+        || account.total_spent != original.total_spent;
+    if totals_changed {
+        let transfer_hash = match &account.last_transfer {
+            Some(hash) if account.last_transfer != original.last_transfer => hash.clone(),
+            _ => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "TEND account updates must cite the transfer they apply".to_string(),
+                ))
+            }
+        };
+        let transfer = match must_get_valid_record(transfer_hash.clone())?
+            .entry()
+            .to_app_option::<TendTransfer>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            Some(transfer) => transfer,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Cited entry is not a TEND transfer".to_string(),
+                ))
+            }
+        };
+        if let Err(reason) = check_tend_change(&original, &account, &transfer) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+
+        // The owner writes every version, so their chain shows past applications
+        let already_applied = prior_entries::<TendAccount>(
+            &action.author,
+            &action.prev_action,
+            UnitEntryTypes::TendAccount,
+        )?
+        .iter()
+        .any(|(_, prior)| prior.last_transfer.as_ref() == Some(&transfer_hash));
+        if already_applied {
+            return Ok(ValidateCallbackResult::Invalid(
+                "TEND transfer has already been applied".to_string(),
+            ));
+        }
+
+        if account.total_earned > original.total_earned {
+            if let invalid @ ValidateCallbackResult::Invalid(_) =
+                validate_sender_debit(&account, &transfer_hash, &transfer)?
+            {
+                return Ok(invalid);
+            }
+        }
+    }
+
+    let credit_limit = balances_properties()?.tend_credit_limit;
+    if account.balance < -(credit_limit as i64) {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "TEND balance cannot go below -{}",
+            credit_limit
+        )));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Check a TEND credit cites the sender's debit of the transfer it applies
+///
+/// A transfer on its own moves nothing, so without the debit a credit
+/// would mint TEND. The debit must be on the sender's chain and cite the
+/// transfer; having passed validation itself, it kept the sender within
+/// the credit limit.
+fn validate_sender_debit(
+    account: &TendAccount,
+    transfer_hash: &ActionHash,
+    transfer: &TendTransfer,
+) -> ExternResult<ValidateCallbackResult> {
+    let debit_hash = match &account.sender_debit {
+        Some(hash) => hash.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "TEND credits must cite the sender's debit".to_string(),
+            ))
+        }
+    };
+    if must_get_valid_record(debit_hash.clone())?.action().author() != &transfer.from {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cited TEND debit was not written by the sender".to_string(),
+        ));
+    }
+
+    let debited = prior_entries::<TendAccount>(
+        &transfer.from,
+        &debit_hash,
+        UnitEntryTypes::TendAccount,
+    )?
+    .iter()
+    .any(|(hash, version)| {
+        hash == &debit_hash
+            && version.owner == transfer.from
+            && version.last_transfer.as_ref() == Some(transfer_hash)
+    });
+    if !debited {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cited TEND debit does not apply this transfer".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_tend_transfer(
+    transfer: TendTransfer,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    if transfer.amount == 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "TEND transfer amount must be greater than 0".to_string(),
+        ));
+    }
+
+    // Only the payer can spend their TEND
+    if transfer.from != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "TEND transfers must be made by the sender".to_string(),
+        ));
+    }
+
+    if transfer.from == transfer.to {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cannot transfer TEND to self".to_string(),
+        ));
+    }
+
+    if let Some(reference) = &transfer.reference {
+        let already_paid = prior_entries::<TendTransfer>(
+            &action.author,
+            &action.prev_action,
+            UnitEntryTypes::TendTransfer,
+        )?
+        .iter()
+        .any(|(_, prior)| prior.reference.as_ref() == Some(reference));
+        if already_paid {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Reference already paid in TEND".to_string(),
+            ));
+        }
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Transfers created earlier on an author's chain, up to and including `until`
+fn prior_transfers(
+    author: &AgentPubKey,
+    until: &ActionHash,
+) -> ExternResult<Vec<(ActionHash, Transfer)>> {
+    prior_entries(author, until, UnitEntryTypes::Transfer)
+}
+
+/// Entries of one type created or updated on an author's chain, up to and
+/// including `until`
+fn prior_entries<T: TryFrom<Entry>>(
+    author: &AgentPubKey,
+    until: &ActionHash,
+    entry_type: UnitEntryTypes,
+) -> ExternResult<Vec<(ActionHash, T)>> {
+    chain_entries(author, until, entry_type, false)
+}
+
+/// Entries of one type created (not updated) on an author's chain, up to
+/// and including `until`
+fn prior_created<T: TryFrom<Entry>>(
+    author: &AgentPubKey,
+    until: &ActionHash,
+    entry_type: UnitEntryTypes,
+) -> ExternResult<Vec<(ActionHash, T)>> {
+    chain_entries(author, until, entry_type, true)
+}
+
+fn chain_entries<T: TryFrom<Entry>>(
+    author: &AgentPubKey,
+    until: &ActionHash,
+    entry_type: UnitEntryTypes,
+    creates_only: bool,
+) -> ExternResult<Vec<(ActionHash, T)>> {
+    let entry_def = ScopedEntryDefIndex::try_from(entry_type)?;
+    let activity = must_get_agent_activity(author.clone(), ChainFilter::new(until.clone()))?;
+
+    let mut entries = Vec::new();
+    for item in activity {
+        if creates_only && !matches!(item.action.hashed.content, Action::Create(_)) {
+            continue;
+        }
+        if let Some((entry_hash, entry_type)) = item.action.hashed.content.entry_data() {
+            let is_match = matches!(
+                entry_type,
+                EntryType::App(def)
+                    if def.zome_index == entry_def.zome_index
+                        && def.entry_index == entry_def.zome_type
+            );
+            if is_match {
+                let entry = must_get_entry(entry_hash.clone())?;
+                if let Ok(content) = T::try_from(entry.content) {
+                    entries.push((item.action.hashed.hash.clone(), content));
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artist_account(owner: u8, pending_balance: u64) -> ArtistAccount {
+        ArtistAccount {
+            schema_version: ACCOUNT_SCHEMA_VERSION,
+            owner: AgentPubKey::from_raw_36(vec![owner; 36]),
+            eth_address: format!("0x{}", "a".repeat(40)),
+            pending_balance,
+            total_earned: pending_balance + 500,
+            total_cashed_out: 500,
+            total_plays: 42,
+            total_play_earnings: pending_balance,
+            migrated_to: None,
+            migrated_at: None,
+            migrated_from: None,
+            last_transfer: None,
+            last_cashout: None,
+            created_at: Timestamp::from_micros(0),
+            updated_at: Timestamp::from_micros(0),
+        }
+    }
+
+    #[test]
+    fn successor_takes_over_an_account_handed_to_it() {
+        let mut old = artist_account(1, 2_000);
+        let mut new = artist_account(2, 2_000);
+        new.migrated_from = Some(ActionHash::from_raw_36(vec![3; 36]));
+
+        // Not handed over yet
+        assert!(check_account_succession(&old, &new).is_err());
+
+        old.migrated_to = Some(new.owner.clone());
+        assert_eq!(check_account_succession(&old, &new), Ok(()));
+
+        // Balances must carry over exactly
+        new.pending_balance = 3_000;
+        assert!(check_account_succession(&old, &new).is_err());
+    }
+
+    #[test]
+    fn freeze_only_moves_the_pending_balance_out() {
+        let mut handed_over = artist_account(1, 2_000);
+        handed_over.migrated_to = Some(AgentPubKey::from_raw_36(vec![2; 36]));
+
+        let mut frozen = handed_over.clone();
+        frozen.pending_balance = 0;
+        frozen.migrated_at = Some(Timestamp::from_micros(5));
+        assert_eq!(check_account_freeze(&handed_over, &frozen), Ok(()));
+
+        // Balance left behind, or lifetime totals rewritten on the way out
+        let mut kept = frozen.clone();
+        kept.pending_balance = 2_000;
+        assert!(check_account_freeze(&handed_over, &kept).is_err());
+        let mut rewritten = frozen.clone();
+        rewritten.total_earned += 1;
+        assert!(check_account_freeze(&handed_over, &rewritten).is_err());
+    }
+
+    #[test]
+    fn account_cannot_be_taken_over_twice_or_by_another_key() {
+        let mut old = artist_account(1, 2_000);
+        old.migrated_to = Some(AgentPubKey::from_raw_36(vec![2; 36]));
+
+        // Someone other than the chosen key
+        assert!(check_account_succession(&old, &artist_account(9, 2_000)).is_err());
+
+        // Already taken over
+        old.migrated_at = Some(Timestamp::from_micros(1));
+        assert!(check_account_succession(&old, &artist_account(2, 2_000)).is_err());
+    }
+
+    fn referenced_transfer(reference: Option<ActionHash>) -> Transfer {
+        Transfer {
+            from: AgentPubKey::from_raw_36(vec![1; 36]),
+            to: AgentPubKey::from_raw_36(vec![2; 36]),
+            amount: 1_000,
+            reason: SETTLEMENT,
+            reference,
+            play_count: 3,
+            transferred_at: Timestamp::from_micros(0),
+        }
+    }
+
+    const SETTLEMENT: TransferReason = TransferReason::PlaySettlement;
+
+    #[test]
+    fn retrying_with_same_reference_is_a_no_op() {
+        let batch = ActionHash::from_raw_36(vec![7; 36]);
+        let first_hash = ActionHash::from_raw_36(vec![9; 36]);
+        let settler = AgentPubKey::from_raw_36(vec![5; 36]);
+        let retry = referenced_transfer(Some(batch));
+        let mut recorded = Vec::new();
+
+        // First call: nothing recorded yet, so a transfer is created
+        assert_eq!(match_retried_transfer(&recorded, &settler, &retry), Ok(None));
+        recorded.push((first_hash.clone(), settler.clone(), retry.clone()));
+
+        // Second call: the existing transfer is returned instead
+        assert_eq!(match_retried_transfer(&recorded, &settler, &retry), Ok(Some(first_hash)));
+    }
+
+    #[test]
+    fn foreign_or_forged_transfers_do_not_stand_in_for_a_payment() {
+        let batch = ActionHash::from_raw_36(vec![7; 36]);
+        let settler = AgentPubKey::from_raw_36(vec![5; 36]);
+        let attacker = AgentPubKey::from_raw_36(vec![6; 36]);
+        let payment = referenced_transfer(Some(batch));
+
+        // An identical transfer written by someone else and linked to the
+        // reference is ignored, so the payment still goes ahead
+        let foreign = vec![(ActionHash::from_raw_36(vec![9; 36]), attacker, payment.clone())];
+        assert_eq!(match_retried_transfer(&foreign, &settler, &payment), Ok(None));
+
+        // The settler's own transfer on different terms is not a retry
+        let mut cheaper = payment.clone();
+        cheaper.amount = 1;
+        let forged = vec![(ActionHash::from_raw_36(vec![9; 36]), settler.clone(), cheaper)];
+        assert!(match_retried_transfer(&forged, &settler, &payment).is_err());
+        let mut elsewhere = payment.clone();
+        elsewhere.to = AgentPubKey::from_raw_36(vec![6; 36]);
+        let forged = vec![(ActionHash::from_raw_36(vec![9; 36]), settler.clone(), elsewhere)];
+        assert!(match_retried_transfer(&forged, &settler, &payment).is_err());
+
+        // Unreferenced transfers are never retries
+        let unreferenced = referenced_transfer(None);
+        assert_eq!(match_retried_transfer(&forged, &settler, &unreferenced), Ok(None));
+    }
+
+    #[test]
+    fn fee_and_settlement_can_share_a_reference() {
+        let batch = ActionHash::from_raw_36(vec![7; 36]);
+        let settlement_hash = ActionHash::from_raw_36(vec![9; 36]);
+        let recorded = vec![(settlement_hash.clone(), referenced_transfer(Some(batch.clone())))];
+
+        assert_eq!(
+            find_transfer_by_reference(&recorded, &batch, &TransferReason::ProtocolFee),
+            None
+        );
+        assert_eq!(
+            find_transfer_by_reference(&recorded, &batch, &SETTLEMENT),
+            Some(settlement_hash)
+        );
+    }
+
+    #[test]
+    fn protocol_fee_transfer_matches_its_batch() {
+        let settler = AgentPubKey::from_raw_36(vec![1; 36]);
+        let platform = AgentPubKey::from_raw_36(vec![2; 36]);
+        let batch = SettledBatch {
+            artist: AgentPubKey::from_raw_36(vec![3; 36]),
+            total_amount: 990,
+            protocol_fee: 10,
+            play_hashes: vec![ActionHash::from_raw_36(vec![4; 36])],
+        };
+        let fee = Transfer {
+            amount: 10,
+            reason: TransferReason::ProtocolFee,
+            play_count: 0,
+            ..referenced_transfer(Some(ActionHash::from_raw_36(vec![7; 36])))
+        };
+        assert!(check_fee_transfer(&fee, &settler, &batch, Some(&platform)).is_ok());
+
+        let inflated = Transfer { amount: 1_000, ..fee.clone() };
+        assert!(check_fee_transfer(&inflated, &settler, &batch, Some(&platform)).is_err());
+        // Someone else's batch, or no platform agent to pay
+        let other = AgentPubKey::from_raw_36(vec![5; 36]);
+        assert!(check_fee_transfer(&fee, &other, &batch, Some(&platform)).is_err());
+        assert!(check_fee_transfer(&fee, &settler, &batch, None).is_err());
+    }
+
+    #[test]
+    fn settlement_transfer_matches_its_batch() {
+        let settler = AgentPubKey::from_raw_36(vec![1; 36]);
+        let batch = SettledBatch {
+            artist: AgentPubKey::from_raw_36(vec![2; 36]),
+            total_amount: 1_000,
+            protocol_fee: 0,
+            play_hashes: vec![ActionHash::from_raw_36(vec![4; 36]); 3],
+        };
+        let settlement = referenced_transfer(Some(ActionHash::from_raw_36(vec![7; 36])));
+        assert!(check_settlement_transfer(&settlement, &settler, &batch).is_ok());
+
+        let inflated = Transfer { play_count: 300, ..settlement.clone() };
+        assert!(check_settlement_transfer(&inflated, &settler, &batch).is_err());
+        let overpaid = Transfer { amount: 2_000, ..settlement.clone() };
+        assert!(check_settlement_transfer(&overpaid, &settler, &batch).is_err());
+        let other = AgentPubKey::from_raw_36(vec![5; 36]);
+        let redirected = Transfer { to: other.clone(), ..settlement.clone() };
+        assert!(check_settlement_transfer(&redirected, &settler, &batch).is_err());
+        assert!(check_settlement_transfer(&settlement, &other, &batch).is_err());
+    }
+
+    #[test]
+    fn artist_credit_adds_exactly_the_cited_transfer() {
+        let original = artist_account(2, 2_000);
+        let settlement = referenced_transfer(Some(ActionHash::from_raw_36(vec![7; 36])));
+
+        let mut credited = original.clone();
+        credited.credit(1_000, 3).unwrap();
+        credited.last_transfer = Some(ActionHash::from_raw_36(vec![8; 36]));
+        assert_eq!(check_artist_credit(&original, &credited, &settlement), Ok(()));
+
+        // Balance or play counters inflated past the transfer
+        let mut inflated = credited.clone();
+        inflated.pending_balance += 1;
+        assert!(check_artist_credit(&original, &inflated, &settlement).is_err());
+        let mut padded = credited.clone();
+        padded.total_plays += 1;
+        assert!(check_artist_credit(&original, &padded, &settlement).is_err());
+
+        // A tip moves earnings but not the play counters
+        let tip = Transfer { reason: TransferReason::Tip, play_count: 0, ..settlement };
+        assert!(check_artist_credit(&original, &credited, &tip).is_err());
+    }
+
+    fn cashout(amount: u64, account: Option<ActionHash>) -> CashoutRequest {
+        CashoutRequest {
+            artist: AgentPubKey::from_raw_36(vec![2; 36]),
+            amount,
+            eth_address: format!("0x{}", "a".repeat(40)),
+            requested_at: Timestamp::from_micros(0),
+            status: CashoutStatus::Pending,
+            tx_hash: None,
+            completed_at: None,
+            account,
+        }
+    }
+
+    #[test]
+    fn cashouts_draw_only_on_a_current_covering_balance() {
+        let account = artist_account(2, 2_000);
+        let version = Some(ActionHash::from_raw_36(vec![9; 36]));
+
+        assert_eq!(check_cashout_funds(&cashout(2_000, version.clone()), &account, 500), Ok(()));
+        assert!(check_cashout_funds(&cashout(2_001, version.clone()), &account, 500).is_err());
+        // A version from before an earlier cashout was paid out
+        assert!(check_cashout_funds(&cashout(1_000, version.clone()), &account, 1_500).is_err());
+        // Someone else's account
+        let foreign = artist_account(3, 2_000);
+        assert!(check_cashout_funds(&cashout(1_000, version.clone()), &foreign, 500).is_err());
+
+        let mut debited = account.clone();
+        debited.debit(1_200).unwrap();
+        debited.last_cashout = Some(ActionHash::from_raw_36(vec![10; 36]));
+        let paid_out = cashout(1_200, version.clone());
+        assert_eq!(check_artist_debit(&account, &debited, &paid_out), Ok(()));
+        assert!(check_artist_debit(&account, &debited, &cashout(1_000, version)).is_err());
+    }
+
+    fn listener_account(balance: u64) -> ListenerAccount {
+        ListenerAccount {
+            schema_version: ACCOUNT_SCHEMA_VERSION,
+            owner: AgentPubKey::from_raw_36(vec![1; 36]),
+            eth_address: format!("0x{}", "b".repeat(40)),
+            balance,
+            total_deposited: balance,
+            total_spent: 0,
+            daily_limit: None,
+            monthly_limit: None,
+            spent_in_day: 0,
+            day_window_start: Timestamp::from_micros(0),
+            spent_in_month: 0,
+            month_window_start: Timestamp::from_micros(0),
+            last_deposit: None,
+            last_transfer: None,
+            created_at: Timestamp::from_micros(0),
+            updated_at: Timestamp::from_micros(0),
+        }
+    }
+
+    #[test]
+    fn deposit_near_u64_max_errors_instead_of_wrapping() {
+        let mut account = listener_account(1_000);
+        assert!(account.credit(u64::MAX - 10).is_err());
+        assert_eq!(account.balance, 1_000);
+        assert_eq!(account.total_deposited, 1_000);
+
+        assert_eq!(account.credit(500), Ok(()));
+        assert_eq!(account.balance, 1_500);
+        assert_eq!(account.total_deposited, 1_500);
+
+        let mut artist = artist_account(1, 2_000);
+        assert!(artist.credit(u64::MAX - 10, 1).is_err());
+        assert_eq!(artist.pending_balance, 2_000);
+        assert_eq!(artist.total_plays, 42);
+    }
+
+    #[test]
+    fn over_debit_errors_instead_of_wrapping() {
+        let mut account = listener_account(1_000);
+        assert_eq!(account.debit(1_001), Err(BalancesError::InsufficientBalance("balance")));
+        assert_eq!(account.balance, 1_000);
+        assert_eq!(account.total_spent, 0);
+
+        assert_eq!(account.debit(1_000), Ok(()));
+        assert_eq!(account.balance, 0);
+        assert_eq!(account.total_spent, 1_000);
+        assert_eq!(account.spent_in_day, 1_000);
+
+        let mut artist = artist_account(1, 2_000);
+        assert!(artist.debit(2_001).is_err());
+        assert_eq!(artist.pending_balance, 2_000);
+        assert_eq!(artist.total_cashed_out, 500);
+    }
+
+    #[test]
+    fn tend_stays_within_credit_limit() {
+        let mut account = TendAccount {
+            owner: AgentPubKey::from_raw_36(vec![1; 36]),
+            balance: 0,
+            total_earned: 0,
+            total_spent: 0,
+            last_transfer: None,
+            sender_debit: None,
+            created_at: Timestamp::from_micros(0),
+            updated_at: Timestamp::from_micros(0),
+        };
+        assert_eq!(account.debit(3_600, 3_600), Ok(()));
+        assert_eq!(account.balance, -3_600);
+        assert!(account.debit(1, 3_600).is_err());
+        assert!(account.debit(u64::MAX, u64::MAX).is_err());
+        assert!(account.credit(u64::MAX).is_err());
+        assert_eq!(account.balance, -3_600);
+    }
+
+    #[test]
+    fn tend_updates_apply_exactly_the_cited_transfer() {
+        let owner = AgentPubKey::from_raw_36(vec![1; 36]);
+        let other = AgentPubKey::from_raw_36(vec![2; 36]);
+        let original = TendAccount {
+            owner: owner.clone(),
+            balance: 0,
+            total_earned: 0,
+            total_spent: 0,
+            last_transfer: None,
+            sender_debit: None,
+            created_at: Timestamp::from_micros(0),
+            updated_at: Timestamp::from_micros(0),
+        };
+        let paid = |from: &AgentPubKey, to: &AgentPubKey, amount| TendTransfer {
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+            reference: None,
+            transferred_at: Timestamp::from_micros(0),
+        };
+
+        let mut credited = original.clone();
+        credited.credit(120).unwrap();
+        assert_eq!(check_tend_change(&original, &credited, &paid(&other, &owner, 120)), Ok(()));
+        // Minted: more than the transfer, or a transfer to someone else
+        assert!(check_tend_change(&original, &credited, &paid(&other, &owner, 60)).is_err());
+        assert!(check_tend_change(&original, &credited, &paid(&owner, &other, 120)).is_err());
+
+        let mut debited = original.clone();
+        debited.debit(60, 3_600).unwrap();
+        assert_eq!(check_tend_change(&original, &debited, &paid(&owner, &other, 60)), Ok(()));
+        assert!(check_tend_change(&original, &debited, &paid(&other, &owner, 60)).is_err());
+
+        // Two transfers folded into one version
+        let mut both = credited.clone();
+        both.debit(60, 3_600).unwrap();
+        assert!(check_tend_change(&original, &both, &paid(&other, &owner, 120)).is_err());
+    }
+
+    #[test]
+    fn tend_transfer_tag_round_trips() {
+        let tag = TendTransferTag {
+            debit: ActionHash::from_raw_36(vec![6; 36]),
+        };
+        assert_eq!(TendTransferTag::from_link_tag(&tag.to_link_tag().unwrap()), Some(tag));
+        assert_eq!(TendTransferTag::from_link_tag(&LinkTag::new(vec![])), None);
+    }
+
+    #[test]
+    fn transfer_to_account_less_artist_moves_nothing() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let artist = AgentPubKey::from_raw_36(vec![2; 36]);
+        let account = listener_account(1_000);
+
+        let error = apply_transfer(&listener, Some(&account), &artist, None, 400, 3).unwrap_err();
+        assert_eq!(
+            error,
+            BalancesError::AccountNotFound {
+                kind: "artist",
+                agent: artist.clone(),
+            }
+        );
+        assert!(error.to_string().starts_with("AccountNotFound: no artist account"));
+        // The listener's account is untouched
+        assert_eq!(account.balance, 1_000);
+        assert_eq!(account.total_spent, 0);
+
+        let error = apply_transfer(&listener, None, &artist, Some(&artist_account(1, 0)), 400, 3);
+        assert!(matches!(error, Err(BalancesError::AccountNotFound { kind: "listener", .. })));
+    }
+
+    #[test]
+    fn transfer_debits_and_credits_together() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let artist = AgentPubKey::from_raw_36(vec![2; 36]);
+        let (debited, credited) = apply_transfer(
+            &listener,
+            Some(&listener_account(1_000)),
+            &artist,
+            Some(&artist_account(1, 2_000)),
+            400,
+            3,
+        )
+        .unwrap();
+        assert_eq!(debited.balance, 600);
+        assert_eq!(credited.pending_balance, 2_400);
+        assert_eq!(credited.total_plays, 45);
+
+        let short = apply_transfer(
+            &listener,
+            Some(&listener_account(100)),
+            &artist,
+            Some(&artist_account(1, 2_000)),
+            400,
+            3,
+        );
+        assert_eq!(short.unwrap_err(), BalancesError::InsufficientBalance("balance"));
+    }
+
+    #[test]
+    fn deposit_needs_min_confirmations_below_the_head() {
+        assert_eq!(check_deposit_finality(100, 103, 3), Ok(()));
+        assert_eq!(check_deposit_finality(100, 200, 3), Ok(()));
+        assert!(check_deposit_finality(100, 102, 3).is_err());
+        assert!(check_deposit_finality(100, 99, 0).is_err());
+    }
+
+    fn deposit(amount: u64, verified: bool) -> Deposit {
+        Deposit {
+            listener: AgentPubKey::from_raw_36(vec![1; 36]),
+            amount,
+            tx_hash: format!("0x{}", "c".repeat(64)),
+            block_number: 100,
+            deposited_at: Timestamp::from_micros(0),
+            verified,
+            confirmed_head: None,
+        }
+    }
+
+    fn transfer(from: u8, to: u8, amount: u64) -> Transfer {
+        Transfer {
+            from: AgentPubKey::from_raw_36(vec![from; 36]),
+            to: AgentPubKey::from_raw_36(vec![to; 36]),
+            amount,
+            reason: TransferReason::Tip,
+            reference: None,
+            play_count: 0,
+            transferred_at: Timestamp::from_micros(0),
+        }
+    }
+
+    #[test]
+    fn ledger_replays_credited_deposits_and_sent_transfers() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let deposits = [deposit(1_000, true), deposit(500, false)];
+        // Transfers received don't spend the listener's balance
+        let transfers = [transfer(1, 2, 300), transfer(3, 1, 50)];
+
+        let ledger = ListenerLedger::replay(&listener, &deposits, &transfers, false).unwrap();
+        assert_eq!(ledger.total_deposited, 1_500);
+        assert_eq!(ledger.total_spent, 300);
+        assert_eq!(ledger.balance(), Some(1_200));
+
+        // With an oracle, unconfirmed deposits aren't credited yet
+        let ledger = ListenerLedger::replay(&listener, &deposits, &transfers, true).unwrap();
+        assert_eq!(ledger.total_deposited, 1_000);
+        assert_eq!(ledger.balance(), Some(700));
+
+        let overflowing = [deposit(u64::MAX, true), deposit(1, true)];
+        assert_eq!(
+            ListenerLedger::replay(&listener, &overflowing, &[], false),
+            Err(BalancesError::Overflow("total_deposited"))
+        );
+    }
+
+    #[test]
+    fn drift_is_recorded_minus_expected() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let ledger = ListenerLedger::replay(
+            &listener,
+            &[deposit(1_000, true)],
+            &[transfer(1, 2, 400)],
+            false,
+        )
+        .unwrap();
+
+        let mut account = listener_account(1_000);
+        account.debit(400).unwrap();
+        assert!(BalanceDrift::between(&account, &ledger).is_zero());
+
+        // Inject drift: a credit that no deposit accounts for
+        account.balance += 250;
+        let drift = BalanceDrift::between(&account, &ledger);
+        assert_eq!(drift.balance, 250);
+        assert_eq!(drift.total_deposited, 0);
+        assert!(!drift.is_zero());
+
+        // And a spend that no transfer accounts for
+        account.balance = 100;
+        account.total_spent = 900;
+        let drift = BalanceDrift::between(&account, &ledger);
+        assert_eq!(drift.balance, -500);
+        assert_eq!(drift.total_spent, 500);
+    }
+
+    #[test]
+    fn balance_moves_only_with_deposits_and_spending() {
+        let original = listener_account(1_000);
+
+        let mut updated = original.clone();
+        updated.credit(500).unwrap();
+        updated.debit(200).unwrap();
+        assert_eq!(check_listener_balance_change(&original, &updated), Ok(()));
+
+        // Balance bumped without a deposit
+        let mut drifted = original.clone();
+        drifted.balance += 1;
+        assert!(check_listener_balance_change(&original, &drifted).is_err());
+
+        // History rolled back
+        let mut rolled_back = original.clone();
+        rolled_back.total_deposited -= 100;
+        rolled_back.balance -= 100;
+        assert!(check_listener_balance_change(&original, &rolled_back).is_err());
+    }
+
+    #[test]
+    fn listener_debit_spends_exactly_the_owners_cited_transfer() {
+        let original = listener_account(1_000);
+        let mut debited = original.clone();
+        debited.debit(400).unwrap();
+
+        assert_eq!(check_listener_debit(&original, &debited, &transfer(1, 2, 400)), Ok(()));
+        // Drained by a transfer someone else paid, or for more than it moved
+        assert!(check_listener_debit(&original, &debited, &transfer(3, 2, 400)).is_err());
+        assert!(check_listener_debit(&original, &debited, &transfer(1, 2, 100)).is_err());
+
+        // A deposit slipped in alongside the payment
+        let mut topped_up = debited.clone();
+        topped_up.credit(400).unwrap();
+        assert!(check_listener_debit(&original, &topped_up, &transfer(1, 2, 400)).is_err());
+    }
+
+    #[test]
+    fn deposit_credit_must_match_a_confirmed_deposit_of_the_owner() {
+        let original = listener_account(1_000);
+        let mut updated = original.clone();
+        updated.credit(500).unwrap();
+
+        assert_eq!(check_deposit_credit(&original, &updated, &deposit(500, true), true), Ok(()));
+        // Unconfirmed deposits only count without an oracle
+        assert!(check_deposit_credit(&original, &updated, &deposit(500, false), true).is_err());
+        assert_eq!(check_deposit_credit(&original, &updated, &deposit(500, false), false), Ok(()));
+
+        // Credit larger than the deposit it cites
+        assert!(check_deposit_credit(&original, &updated, &deposit(400, true), true).is_err());
+
+        // Someone else's deposit
+        let mut foreign = deposit(500, true);
+        foreign.listener = AgentPubKey::from_raw_36(vec![9; 36]);
+        assert!(check_deposit_credit(&original, &updated, &foreign, true).is_err());
+    }
 }
@@ -5,20 +5,115 @@
 
 use catalog_integrity::*;
 use hdk::prelude::*;
+use pagination::{page_limit, paginate_links, Page, PageInput, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
+/// Grant unrestricted access to apply_verification so the trust zome on
+/// a voucher's cell can push verification changes to this artist, and to
+/// recv_remote_signal so followed artists can announce releases
+///
+/// Fails if the DNA properties are malformed.
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    if let Err(reason) = parse_catalog_properties(dna_info()?.modifiers.properties) {
+        error!("{}", reason);
+        return Ok(InitCallbackResult::Fail(reason));
+    }
+
+    let mut functions = std::collections::BTreeSet::new();
+    functions.insert((zome_info()?.name, "apply_verification".into()));
+    functions.insert((zome_info()?.name, "recv_remote_signal".into()));
+    create_cap_grant(CapGrantEntry {
+        tag: "verification".into(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+    Ok(InitCallbackResult::Pass)
+}
 
 /// Create a new song entry
+///
+/// A song whose `released_at` is still ahead is scheduled: it's indexed by
+/// content hash and ISRC right away, but only listed publicly once
+/// `publish_due_songs` runs after its release.
 #[hdk_extern]
 pub fn create_song(song: Song) -> ExternResult<ActionHash> {
+    check_duplicate_content(&song.song_hash)?;
+
+    let properties = catalog_properties()?;
+    properties
+        .check_song_limits(&song)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+    properties
+        .check_strategy(&song.strategy_id)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+    if let Some(isrc) = &song.isrc {
+        check_duplicate_isrc(isrc, properties.strict_isrc)?;
+    }
+    let now = sys_time()?;
+    check_release_date(song.released_at, now)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+    ensure_onboarded(&agent_info()?.agent_initial_pubkey)?;
+
     let action_hash = create_entry(&EntryTypes::Song(song.clone()))?;
 
+    // Index by content hash
+    let hash_path = song_by_hash_path(&song.song_hash);
+    hash_path.ensure()?;
+    create_link(
+        hash_path.path_entry_hash()?,
+        action_hash.clone(),
+        LinkTypes::SongByHash,
+        (),
+    )?;
+
+    // Index by ISRC
+    if let Some(isrc) = &song.isrc {
+        let isrc_path = isrc_path(isrc);
+        isrc_path.ensure()?;
+        create_link(
+            isrc_path.path_entry_hash()?,
+            action_hash.clone(),
+            LinkTypes::SongByIsrc,
+            (),
+        )?;
+    }
+
+    if is_scheduled(&song, now) {
+        let path = scheduled_path(&song.artist);
+        path.ensure()?;
+        create_link(
+            path.path_entry_hash()?,
+            action_hash.clone(),
+            LinkTypes::ArtistToScheduled,
+            (),
+        )?;
+    } else {
+        list_song(&action_hash, &action_hash, &song)?;
+    }
+
+    Ok(action_hash)
+}
+
+/// Link a released song into the public listings: the artist's songs, all
+/// songs, its release day and its genres
+///
+/// Links target the song's create action (`song_hash`) and are tagged with
+/// the `version` listed, whose release date validation checks.
+fn list_song(song_hash: &ActionHash, version: &ActionHash, song: &Song) -> ExternResult<()> {
+    let tag = ListingTag {
+        released_at: song.released_at.as_micros(),
+        version: version.clone(),
+    }
+    .to_link_tag()?;
+
     // Link from artist to song
     let artist_path = Path::from(format!("artists/{}", song.artist));
     artist_path.ensure()?;
     create_link(
         artist_path.path_entry_hash()?,
-        action_hash.clone(),
+        song_hash.clone(),
         LinkTypes::ArtistToSongs,
-        (),
+        tag.clone(),
     )?;
 
     // Link to all songs anchor
@@ -26,9 +121,19 @@ pub fn create_song(song: Song) -> ExternResult<ActionHash> {
     all_songs_path.ensure()?;
     create_link(
         all_songs_path.path_entry_hash()?,
-        action_hash.clone(),
+        song_hash.clone(),
         LinkTypes::AllSongs,
-        (),
+        tag.clone(),
+    )?;
+
+    // Index by release day, tagged with the exact time for ordering
+    let release_path = release_day_path(release_day(song.released_at));
+    release_path.ensure()?;
+    create_link(
+        release_path.path_entry_hash()?,
+        song_hash.clone(),
+        LinkTypes::ReleasedRecent,
+        tag.clone(),
     )?;
 
     // Link from each genre
@@ -37,91 +142,589 @@ pub fn create_song(song: Song) -> ExternResult<ActionHash> {
         genre_path.ensure()?;
         create_link(
             genre_path.path_entry_hash()?,
-            action_hash.clone(),
+            song_hash.clone(),
             LinkTypes::GenreToSongs,
-            (),
+            tag.clone(),
         )?;
     }
 
-    Ok(action_hash)
+    Ok(())
 }
 
-/// Get a song by its action hash
+/// Signal to a follower's client
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum CatalogSignal {
+    /// An artist I follow released a scheduled song
+    Released {
+        artist: AgentPubKey,
+        /// The song's create action
+        song_hash: ActionHash,
+        title: String,
+    },
+}
+
+/// Forward release announcements from followed artists to my client
+///
+/// Announcements are dropped unless the artist sent them and I follow them.
 #[hdk_extern]
-pub fn get_song(action_hash: ActionHash) -> ExternResult<Option<Song>> {
-    let record = get(action_hash, GetOptions::default())?;
-    match record {
-        Some(r) => Ok(r.entry().to_app_option().map_err(|e| wasm_error!(e))?),
-        None => Ok(None),
+pub fn recv_remote_signal(signal: CatalogSignal) -> ExternResult<()> {
+    let sender = call_info()?.provenance;
+    let me = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
+    match &signal {
+        CatalogSignal::Released { artist, .. } => {
+            if &sender != artist
+                || !follower_links(artist)?.iter().any(|link| link.target == me)
+            {
+                return Ok(());
+            }
+        }
     }
+    emit_signal(signal)
 }
 
-/// Get all songs by an artist
-#[hdk_extern]
-pub fn get_songs_by_artist(artist: AgentPubKey) -> ExternResult<Vec<Song>> {
-    let artist_path = Path::from(format!("artists/{}", artist));
-    let links = get_links(
-        GetLinksInputBuilder::try_new(artist_path.path_entry_hash()?, LinkTypes::ArtistToSongs)?
-            .build(),
-    )?;
+fn scheduled_links(artist: &AgentPubKey) -> ExternResult<Vec<Link>> {
+    get_links(
+        GetLinksInputBuilder::try_new(
+            scheduled_path(artist).path_entry_hash()?,
+            LinkTypes::ArtistToScheduled,
+        )?
+        .build(),
+    )
+}
 
-    let mut songs = Vec::new();
-    for link in links {
-        if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(song) = get_song(action_hash)? {
-                songs.push(song);
+/// A song I've scheduled and haven't published yet
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScheduledSong {
+    /// The song's create action, as returned by `create_song`
+    pub song_hash: ActionHash,
+    /// Latest version, with the current `released_at`
+    pub song: Song,
+    /// The release time has passed; the next `publish_due_songs` lists it
+    pub due: bool,
+}
+
+/// My scheduled songs and the latest version of each, soonest first
+fn my_scheduled_songs() -> ExternResult<Vec<(Link, ActionHash, Song)>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let mut scheduled = Vec::new();
+    for link in scheduled_links(&my_agent)? {
+        let song_hash = match link.target.clone().into_action_hash() {
+            Some(song_hash) => song_hash,
+            None => continue,
+        };
+        if let Some(record) = latest_song_record(song_hash)? {
+            if let Some(song) = record
+                .entry()
+                .to_app_option::<Song>()
+                .map_err(|e| wasm_error!(e))?
+            {
+                scheduled.push((link, record.action_address().clone(), song));
             }
         }
     }
-    Ok(songs)
+    scheduled.sort_by_key(|(_, _, song)| song.released_at);
+    Ok(scheduled)
+}
+
+/// My songs waiting for their release, soonest first
+#[hdk_extern]
+pub fn get_scheduled_songs(_: ()) -> ExternResult<Vec<ScheduledSong>> {
+    let now = sys_time()?;
+    Ok(my_scheduled_songs()?
+        .into_iter()
+        .filter_map(|(link, _, song)| {
+            Some(ScheduledSong {
+                song_hash: link.target.into_action_hash()?,
+                due: !is_scheduled(&song, now),
+                song,
+            })
+        })
+        .collect())
+}
+
+/// Publish my scheduled songs whose release time has passed
+///
+/// Meant to be called periodically on the artist's cell (by their client
+/// or a scheduler). Each due song is listed publicly as of its latest
+/// version, removed from my scheduled songs, and announced to my followers
+/// with a `CatalogSignal::Released`. Returns the published songs' create
+/// hashes.
+#[hdk_extern]
+pub fn publish_due_songs(_: ()) -> ExternResult<Vec<ActionHash>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?;
+
+    let mut published = Vec::new();
+    for (link, version, song) in my_scheduled_songs()? {
+        if is_scheduled(&song, now) {
+            continue;
+        }
+        let song_hash = match link.target.into_action_hash() {
+            Some(song_hash) => song_hash,
+            None => continue,
+        };
+
+        list_song(&song_hash, &version, &song)?;
+        delete_link(link.create_link_hash)?;
+
+        let followers = get_followers(my_agent.clone())?;
+        if !followers.is_empty() {
+            let signal = CatalogSignal::Released {
+                artist: my_agent.clone(),
+                song_hash: song_hash.clone(),
+                title: song.title.clone(),
+            };
+            // Best effort: offline followers just miss the announcement
+            let _ = send_remote_signal(signal, followers);
+        }
+        published.push(song_hash);
+    }
+
+    Ok(published)
 }
 
-/// Get all songs (paginated)
 #[derive(Serialize, Deserialize, Debug)]
-pub struct GetAllSongsInput {
-    pub limit: usize,
-    pub offset: usize,
+pub struct RescheduleSongInput {
+    /// The song's create action
+    pub song_hash: ActionHash,
+    pub released_at: Timestamp,
 }
 
+/// Move a scheduled song's release; only the artist can, and only before
+/// it's released. Returns the updated version.
 #[hdk_extern]
-pub fn get_all_songs(input: GetAllSongsInput) -> ExternResult<Vec<Song>> {
-    let all_songs_path = Path::from("all_songs");
-    let links = get_links(
-        GetLinksInputBuilder::try_new(all_songs_path.path_entry_hash()?, LinkTypes::AllSongs)?
+pub fn reschedule_song(input: RescheduleSongInput) -> ExternResult<ActionHash> {
+    let record = latest_song_record(input.song_hash)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Song not found".to_string())))?;
+    let song: Song = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Record is not a song".to_string())))?;
+    let rescheduled = Song {
+        released_at: input.released_at,
+        ..song.clone()
+    };
+
+    // Fail with validation's reason rather than a rejected commit
+    let me = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?;
+    check_song_update(&song, &rescheduled, &me, &catalog_properties()?.moderators)
+        .and_then(|()| check_release_change(&song, &rescheduled, now))
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    update_entry(record.action_address().clone(), &EntryTypes::Song(rescheduled))
+}
+
+/// Refuse (strict mode) or log an upload whose ISRC is already claimed
+///
+/// The same recording uploaded twice splits its plays and earnings, so
+/// labels can turn on `strict_isrc` to stop it. Only the uploader's own
+/// claims are refused: anyone can index any ISRC, and refusing on their
+/// claims would let a squatter block the label's upload.
+fn check_duplicate_isrc(isrc: &str, strict: bool) -> ExternResult<()> {
+    let links = isrc_links(isrc)?;
+    if links.is_empty() {
+        return Ok(());
+    }
+    let me = agent_info()?.agent_initial_pubkey;
+    if strict && links.iter().any(|link| link.author == me) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "You already have a song with ISRC {}",
+            isrc
+        ))));
+    }
+    warn!("Creating a second song with ISRC {}", isrc);
+    Ok(())
+}
+
+/// Refuse content the uploader already published, and log content another
+/// artist claims
+///
+/// Refusing on other artists' claims would let a squatter who indexes the
+/// content first block its real artist; lookups treat it as contested.
+fn check_duplicate_content(song_hash: &str) -> ExternResult<()> {
+    let links = song_hash_links(song_hash)?;
+    if links.is_empty() {
+        return Ok(());
+    }
+    let me = agent_info()?.agent_initial_pubkey;
+    if links.iter().any(|link| link.author == me) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "A song with content hash {} already exists",
+            song_hash
+        ))));
+    }
+    warn!("Content hash {} is already claimed by another artist", song_hash);
+    Ok(())
+}
+
+/// The song an index path resolves to, if one artist holds every claim
+///
+/// Index links are made by the song's artist (checked at validation) with
+/// a timestamp they choose, so "earliest" can't settle a claim between
+/// artists: a contested path resolves to nothing. Within one artist's
+/// claims their earliest song wins.
+fn sole_claim(links: Vec<Link>) -> Option<ActionHash> {
+    let first = links.iter().min_by_key(|link| link.timestamp)?;
+    if links.iter().any(|link| link.author != first.author) {
+        return None;
+    }
+    first.target.clone().into_action_hash()
+}
+
+fn isrc_links(isrc: &str) -> ExternResult<Vec<Link>> {
+    get_links(
+        GetLinksInputBuilder::try_new(isrc_path(isrc).path_entry_hash()?, LinkTypes::SongByIsrc)?
             .build(),
-    )?;
+    )
+}
 
-    let mut songs = Vec::new();
-    for link in links.into_iter().skip(input.offset).take(input.limit) {
-        if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(song) = get_song(action_hash)? {
-                songs.push(song);
-            }
+/// Apply the deployment's onboarding mode before an upload
+fn ensure_onboarded(agent: &AgentPubKey) -> ExternResult<()> {
+    let mode = catalog_properties()?.artist_onboarding;
+    if mode == ArtistOnboarding::Open || latest_profile(agent)?.is_some() {
+        return Ok(());
+    }
+
+    match mode {
+        ArtistOnboarding::ProfileRequired => Err(wasm_error!(WasmErrorInner::Guest(
+            "Create your artist profile first (set_artist_profile), then upload songs".to_string()
+        ))),
+        _ => {
+            // Placeholder name until the artist fills in their profile
+            let key = agent.to_string();
+            set_artist_profile(ArtistProfile {
+                name: format!("Artist {}", &key[key.len().saturating_sub(8)..]),
+                bio: String::new(),
+                avatar_cid: None,
+                payment_address: String::new(),
+                social_links: String::new(),
+                verified: false,
+                verification_tier: None,
+                verification_hash: None,
+            })?;
+            Ok(())
         }
     }
-    Ok(songs)
 }
 
-/// Get songs by genre
+/// Strategy ids this deployment offers, for populating client UIs
 #[hdk_extern]
-pub fn get_songs_by_genre(genre: String) -> ExternResult<Vec<Song>> {
-    let genre_path = Path::from(format!("genres/{}", genre.to_lowercase()));
-    let links = get_links(
-        GetLinksInputBuilder::try_new(genre_path.path_entry_hash()?, LinkTypes::GenreToSongs)?
-            .build(),
-    )?;
+pub fn get_allowed_strategies(_: ()) -> ExternResult<Vec<String>> {
+    Ok(catalog_properties()?.offered_strategies())
+}
+
+/// Whether an agent has created an artist profile
+#[hdk_extern]
+pub fn is_onboarded(agent: AgentPubKey) -> ExternResult<bool> {
+    Ok(latest_profile(&agent)?.is_some())
+}
+
+/// Get a song by its action hash, as of its latest update
+#[hdk_extern]
+pub fn get_song(action_hash: ActionHash) -> ExternResult<Option<Song>> {
+    match latest_song_record(action_hash)? {
+        Some(record) => Ok(record.entry().to_app_option().map_err(|e| wasm_error!(e))?),
+        None => Ok(None),
+    }
+}
+
+/// The newest record in a song's chain of updates
+///
+/// Each update is made to the version before it, so this follows the
+/// newest update of each version until one has none.
+fn latest_song_record(action_hash: ActionHash) -> ExternResult<Option<Record>> {
+    let mut details = match get_details(action_hash, GetOptions::default())? {
+        Some(Details::Record(details)) => details,
+        _ => return Ok(None),
+    };
+    loop {
+        let next = match details
+            .updates
+            .iter()
+            .max_by_key(|update| update.action().timestamp())
+        {
+            Some(update) => update.action_address().clone(),
+            None => return Ok(Some(details.record)),
+        };
+        details = match get_details(next, GetOptions::default())? {
+            Some(Details::Record(next)) => next,
+            _ => return Ok(None),
+        };
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FreezeSongEarningsInput {
+    pub song_hash: ActionHash,
+    pub reason: String,
+}
+
+/// Hold a song's earnings during a dispute (e.g. contested authorship)
+///
+/// The artist or a moderator (`moderators` DNA property) may freeze. Plays
+/// keep being recorded, but settlement leaves them unsettled until the same
+/// authority unfreezes the song.
+#[hdk_extern]
+pub fn freeze_song_earnings(input: FreezeSongEarningsInput) -> ExternResult<ActionHash> {
+    let (version, song, authority) = song_for_freeze(input.song_hash)?;
+    update_entry(
+        version,
+        &EntryTypes::Song(Song {
+            earnings_frozen: true,
+            earnings_freeze: Some(EarningsFreeze {
+                frozen_by: agent_info()?.agent_initial_pubkey,
+                authority,
+                reason: input.reason,
+                frozen_at: sys_time()?,
+            }),
+            ..song
+        }),
+    )
+}
+
+/// Release a song's held earnings; needs the authority that froze them
+#[hdk_extern]
+pub fn unfreeze_song_earnings(song_hash: ActionHash) -> ExternResult<ActionHash> {
+    let (version, song, authority) = song_for_freeze(song_hash)?;
+    match &song.earnings_freeze {
+        Some(freeze) if freeze.authority != authority => {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Only the {:?} authority that froze these earnings can unfreeze them",
+                freeze.authority
+            ))))
+        }
+        None if !song.earnings_frozen => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Song earnings are not frozen".to_string()
+            )))
+        }
+        _ => {}
+    }
+    update_entry(
+        version,
+        &EntryTypes::Song(Song {
+            earnings_frozen: false,
+            earnings_freeze: None,
+            ..song
+        }),
+    )
+}
+
+/// A song's latest version and my authority over its earnings
+fn song_for_freeze(song_hash: ActionHash) -> ExternResult<(ActionHash, Song, FreezeAuthority)> {
+    let record = latest_song_record(song_hash)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Song not found".to_string())))?;
+    let song: Song = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Record is not a song".to_string())))?;
+
+    let me = agent_info()?.agent_initial_pubkey;
+    let authority = song_update_authority(&song, &me, &catalog_properties()?.moderators)
+        .ok_or_else(|| {
+            wasm_error!(WasmErrorInner::Guest(
+                "Only the artist or a moderator can freeze a song's earnings".to_string()
+            ))
+        })?;
+    Ok((record.action_address().clone(), song, authority))
+}
+
+/// A song's parsed metadata (None if the song doesn't exist)
+#[hdk_extern]
+pub fn get_song_metadata(song_hash: ActionHash) -> ExternResult<Option<SongMetadata>> {
+    match get_song(song_hash)? {
+        Some(song) => song
+            .parsed_metadata()
+            .map(Some)
+            .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason))),
+        None => Ok(None),
+    }
+}
+
+/// Links from a content hash's index path
+fn song_hash_links(song_hash: &str) -> ExternResult<Vec<Link>> {
+    get_links(
+        GetLinksInputBuilder::try_new(
+            song_by_hash_path(song_hash).path_entry_hash()?,
+            LinkTypes::SongByHash,
+        )?
+        .build(),
+    )
+}
+
+/// Get a song by its content hash (e.g. from an IPFS reference)
+///
+/// None if no song, or songs by more than one artist, claim the content.
+#[hdk_extern]
+pub fn get_song_by_content_hash(song_hash: String) -> ExternResult<Option<Song>> {
+    match get_song_hash_by_content_hash(song_hash)? {
+        Some(action_hash) => get_song(action_hash),
+        None => Ok(None),
+    }
+}
+
+/// Action hash of the song `get_song_by_content_hash` returns, for
+/// off-DHT mirrors that key other zomes' calls on it
+#[hdk_extern]
+pub fn get_song_hash_by_content_hash(song_hash: String) -> ExternResult<Option<ActionHash>> {
+    Ok(sole_claim(song_hash_links(&song_hash)?))
+}
+
+/// Get a song by its ISRC, with or without hyphens
+///
+/// None if no song, or songs by more than one artist, claim the ISRC.
+#[hdk_extern]
+pub fn get_song_by_isrc(isrc: String) -> ExternResult<Option<Song>> {
+    match sole_claim(isrc_links(&isrc)?) {
+        Some(action_hash) => get_song(action_hash),
+        None => Ok(None),
+    }
+}
+
+/// Action hashes targeted by a set of links, in link order
+fn link_targets(links: Vec<Link>) -> Vec<ActionHash> {
+    links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .collect()
+}
+
+/// Fetch many songs in a single host call
+///
+/// Avoids N sequential DHT fetches in listing functions. Preserves
+/// input order and skips missing or non-song entries, logging targets
+/// that don't decode as songs rather than failing the whole page.
+fn get_songs_batch(hashes: Vec<ActionHash>) -> ExternResult<Vec<Song>> {
+    if hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let inputs: Vec<GetInput> = hashes
+        .iter()
+        .map(|hash| GetInput::new(hash.clone().into(), GetOptions::default()))
+        .collect();
+    let records = HDK.with(|h| h.borrow().get(inputs))?;
 
     let mut songs = Vec::new();
-    for link in links {
-        if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(song) = get_song(action_hash)? {
-                songs.push(song);
-            }
+    let found = hashes.iter().zip(records).filter_map(|(hash, record)| Some((hash, record?)));
+    for (hash, record) in found {
+        match record.entry().to_app_option::<Song>() {
+            Ok(Some(song)) => songs.push(song),
+            Ok(None) => {}
+            Err(e) => warn!("Skipping listed target {} that isn't a song: {:?}", hash, e),
         }
     }
     Ok(songs)
 }
 
+/// One page of the songs an index path links to, oldest link first
+fn songs_page(path: Path, link_type: LinkTypes, page: &PageInput) -> ExternResult<Page<Song>> {
+    let links =
+        get_links(GetLinksInputBuilder::try_new(path.path_entry_hash()?, link_type)?.build())?;
+    let limit = page_limit(page.limit, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE);
+    let (links, next_cursor) = paginate_links(links, page.cursor.as_ref(), limit);
+
+    Ok(Page {
+        items: get_songs_batch(link_targets(links))?,
+        next_cursor,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetSongsByArtistInput {
+    pub artist: AgentPubKey,
+    #[serde(default)]
+    pub page: PageInput,
+}
+
+/// Get an artist's songs, oldest upload first (paginated)
+#[hdk_extern]
+pub fn get_songs_by_artist(input: GetSongsByArtistInput) -> ExternResult<Page<Song>> {
+    let artist_path = Path::from(format!("artists/{}", input.artist));
+    songs_page(artist_path, LinkTypes::ArtistToSongs, &input.page)
+}
+
+/// Get all songs, oldest upload first (paginated)
+#[hdk_extern]
+pub fn get_all_songs(page: PageInput) -> ExternResult<Page<Song>> {
+    songs_page(Path::from("all_songs"), LinkTypes::AllSongs, &page)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetSongsByGenreInput {
+    /// Matched case-insensitively
+    pub genre: String,
+    #[serde(default)]
+    pub page: PageInput,
+}
+
+/// Get songs by genre, oldest upload first (paginated)
+#[hdk_extern]
+pub fn get_songs_by_genre(input: GetSongsByGenreInput) -> ExternResult<Page<Song>> {
+    let genre_path = Path::from(format!("genres/{}", input.genre.to_lowercase()));
+    songs_page(genre_path, LinkTypes::GenreToSongs, &input.page)
+}
+
+/// Offset paging, as new releases are merged from several day buckets
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetNewReleasesInput {
+    /// Capped at 500
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Days of release buckets `get_new_releases` reads back through
+const NEW_RELEASES_LOOKBACK_DAYS: i64 = 90;
+
+/// Get recently released songs, newest first (paginated)
+///
+/// Reads day buckets back from today, stopping once the page is filled or
+/// after 90 days. Songs scheduled for later aren't listed until released.
+#[hdk_extern]
+pub fn get_new_releases(input: GetNewReleasesInput) -> ExternResult<Vec<Song>> {
+    let now = sys_time()?;
+    let limit = input.limit.min(MAX_PAGE_SIZE);
+    let wanted = input.offset.saturating_add(limit);
+    let today = release_day(now);
+
+    let mut releases: Vec<(i64, ActionHash)> = Vec::new();
+    for day in (today - NEW_RELEASES_LOOKBACK_DAYS..=today).rev() {
+        if releases.len() >= wanted {
+            break;
+        }
+        let links = get_links(
+            GetLinksInputBuilder::try_new(
+                release_day_path(day).path_entry_hash()?,
+                LinkTypes::ReleasedRecent,
+            )?
+            .build(),
+        )?;
+
+        let mut bucket: Vec<(i64, ActionHash)> = links
+            .into_iter()
+            .filter_map(|link| {
+                let released_at = release_tag_time(&link.tag)?;
+                let target = link.target.into_action_hash()?;
+                Some((released_at, target))
+            })
+            .filter(|(released_at, _)| *released_at <= now.as_micros())
+            .collect();
+        bucket.sort_by(|a, b| b.0.cmp(&a.0));
+        releases.extend(bucket);
+    }
+
+    let page = releases
+        .into_iter()
+        .skip(input.offset)
+        .take(limit)
+        .map(|(_, hash)| hash)
+        .collect();
+    get_songs_batch(page)
+}
+
 /// Create an album
 #[hdk_extern]
 pub fn create_album(album: Album) -> ExternResult<ActionHash> {
@@ -175,42 +778,33 @@ pub fn get_album_with_songs(action_hash: ActionHash) -> ExternResult<Option<Albu
         GetLinksInputBuilder::try_new(action_hash, LinkTypes::AlbumToSongs)?.build(),
     )?;
 
-    let mut songs = Vec::new();
-    for link in links {
-        if let Some(song_hash) = link.target.into_action_hash() {
-            if let Some(song) = get_song(song_hash)? {
-                songs.push(song);
-            }
-        }
-    }
+    let songs = get_songs_batch(link_targets(links))?;
 
     Ok(Some(AlbumWithSongs { album, songs }))
 }
 
 /// Create or update artist profile
+///
+/// The profile's `payment_address` only resolves to me once the wallet has
+/// signed a claim for it (`claim_payment_address`).
 #[hdk_extern]
 pub fn set_artist_profile(profile: ArtistProfile) -> ExternResult<ActionHash> {
     let my_agent = agent_info()?.agent_initial_pubkey;
-    let profile_path = Path::from(format!("profile/{}", my_agent));
 
-    // Check if profile already exists
-    let existing_links = get_links(
-        GetLinksInputBuilder::try_new(profile_path.path_entry_hash()?, LinkTypes::AllArtists)?
-            .build(),
-    )?;
+    // Verification comes from the trust zome, never from the caller
+    let mut profile = profile;
+    apply_status(&mut profile, current_verification(&my_agent)?);
 
-    if let Some(link) = existing_links.first() {
-        if let Some(action_hash) = link.target.clone().into_action_hash() {
-            // Update existing profile
-            let updated_hash = update_entry(action_hash, &EntryTypes::ArtistProfile(profile))?;
-            return Ok(updated_hash);
-        }
+    if let Some((original_hash, _)) = latest_profile(&my_agent)? {
+        // Update existing profile
+        return update_entry(original_hash, &EntryTypes::ArtistProfile(profile));
     }
 
     // Create new profile
     let action_hash = create_entry(&EntryTypes::ArtistProfile(profile))?;
 
     // Link from profile path
+    let profile_path = Path::from(format!("profile/{}", my_agent));
     profile_path.ensure()?;
     create_link(
         profile_path.path_entry_hash()?,
@@ -232,28 +826,267 @@ pub fn set_artist_profile(profile: ArtistProfile) -> ExternResult<ActionHash> {
     Ok(action_hash)
 }
 
-/// Get artist profile by agent pub key
-#[hdk_extern]
-pub fn get_artist_profile(agent: AgentPubKey) -> ExternResult<Option<ArtistProfile>> {
+/// An agent's profile (following updates) with its original action hash
+fn latest_profile(agent: &AgentPubKey) -> ExternResult<Option<(ActionHash, ArtistProfile)>> {
     let profile_path = Path::from(format!("profile/{}", agent));
     let links = get_links(
         GetLinksInputBuilder::try_new(profile_path.path_entry_hash()?, LinkTypes::AllArtists)?
             .build(),
     )?;
 
-    if let Some(link) = links.first() {
-        if let Some(action_hash) = link.target.clone().into_action_hash() {
-            let record = get(action_hash, GetOptions::default())?;
-            return match record {
-                Some(r) => Ok(r.entry().to_app_option().map_err(|e| wasm_error!(e))?),
-                None => Ok(None),
-            };
+    let original_hash = match links.first().and_then(|l| l.target.clone().into_action_hash()) {
+        Some(hash) => hash,
+        None => return Ok(None),
+    };
+    let details = match get_details(original_hash.clone(), GetOptions::default())? {
+        Some(Details::Record(details)) => details,
+        _ => return Ok(None),
+    };
+
+    let record = match details
+        .updates
+        .iter()
+        .max_by_key(|update| update.action().timestamp())
+    {
+        Some(update) => match get(update.action_address().clone(), GetOptions::default())? {
+            Some(record) => record,
+            None => return Ok(None),
+        },
+        None => details.record,
+    };
+
+    Ok(record
+        .entry()
+        .to_app_option::<ArtistProfile>()
+        .map_err(|e| wasm_error!(e))?
+        .map(|profile| (original_hash, profile)))
+}
+
+/// Get artist profile by agent pub key
+#[hdk_extern]
+pub fn get_artist_profile(agent: AgentPubKey) -> ExternResult<Option<ArtistProfile>> {
+    Ok(latest_profile(&agent)?.map(|(_, profile)| profile))
+}
+
+/// A trust verification status record, decoded
+fn read_verification(status_hash: ActionHash) -> ExternResult<Option<TrustVerification>> {
+    match get(status_hash, GetOptions::default())? {
+        Some(record) => record
+            .entry()
+            .to_app_option::<TrustVerification>()
+            .map_err(|e| wasm_error!(e)),
+        None => Ok(None),
+    }
+}
+
+/// An agent's latest verification status from the trust zome
+fn current_verification(
+    agent: &AgentPubKey,
+) -> ExternResult<Option<(ActionHash, TrustVerification)>> {
+    let status_hash: Option<ActionHash> = match call(
+        CallTargetCell::Local,
+        ZomeName::from("trust"),
+        FunctionName::from("get_verification_status_hash"),
+        None,
+        agent.clone(),
+    )? {
+        ZomeCallResponse::Ok(response) => response.decode().map_err(|e| wasm_error!(e))?,
+        other => {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Failed to get verification status: {:?}",
+                other
+            ))))
+        }
+    };
+
+    match status_hash {
+        Some(hash) => Ok(read_verification(hash.clone())?.map(|status| (hash, status))),
+        None => Ok(None),
+    }
+}
+
+/// Copy a trust status onto a profile's verification fields
+fn apply_status(profile: &mut ArtistProfile, status: Option<(ActionHash, TrustVerification)>) {
+    match status {
+        Some((hash, status)) => {
+            profile.verified = status.tier != UNVERIFIED_TIER;
+            profile.verification_tier = Some(status.tier);
+            profile.verification_hash = Some(hash);
+        }
+        None => {
+            profile.verified = false;
+            profile.verification_tier = None;
+            profile.verification_hash = None;
+        }
+    }
+}
+
+/// Update my profile's verification from a trust status
+///
+/// Called by the trust zome when my tier changes, and only accepts my
+/// latest status. Returns false if the profile didn't change; without a
+/// profile nothing is written, since `set_artist_profile` applies the
+/// latest status when it's created.
+#[hdk_extern]
+pub fn apply_verification(status_hash: ActionHash) -> ExternResult<bool> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let status = read_verification(status_hash.clone())?.ok_or_else(|| {
+        wasm_error!(WasmErrorInner::Guest("Verification status not found".to_string()))
+    })?;
+    if status.artist != my_agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Verification status is for a different artist".to_string()
+        )));
+    }
+    // A stale status could roll the tier back
+    let latest_hash = current_verification(&my_agent)?.map(|(hash, _)| hash);
+    if latest_hash.as_ref() != Some(&status_hash) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Verification status is not the artist's latest".to_string()
+        )));
+    }
+
+    let (original_hash, profile) = match latest_profile(&my_agent)? {
+        Some(found) => found,
+        None => return Ok(false),
+    };
+
+    let mut updated = profile.clone();
+    apply_status(&mut updated, Some((status_hash, status)));
+    if updated.verified == profile.verified
+        && updated.verification_tier == profile.verification_tier
+    {
+        return Ok(false);
+    }
+
+    update_entry(original_hash, &EntryTypes::ArtistProfile(updated))?;
+    Ok(true)
+}
+
+/// Link a payment address to me, with the wallet's EIP-191 signature over
+/// `payment_address_claim_message(address, my agent)`; a no-op if I
+/// already hold a valid claim
+#[hdk_extern]
+pub fn claim_payment_address(claim: PaymentAddressClaim) -> ExternResult<()> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    // Fail with validation's reason rather than a rejected commit
+    check_payment_address_claim(&claim, &my_agent)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    let target = AnyLinkableHash::from(my_agent.clone());
+    let already_claimed = payment_address_links(&claim.address)?
+        .into_iter()
+        .any(|link| link.target == target && valid_claim(&link).is_some());
+    if already_claimed {
+        return Ok(());
+    }
+
+    let path = payment_address_path(&claim.address);
+    path.ensure()?;
+    create_link(
+        path.path_entry_hash()?,
+        target,
+        LinkTypes::PaymentAddressToAgent,
+        claim.to_link_tag()?,
+    )?;
+    Ok(())
+}
+
+/// The agent a payment address link claims the address for, if its tag
+/// carries a claim signed by the address
+fn valid_claim(link: &Link) -> Option<AgentPubKey> {
+    let agent = link.target.clone().into_agent_pub_key()?;
+    let claim = PaymentAddressClaim::from_link_tag(&link.tag)?;
+    check_payment_address_claim(&claim, &agent).ok()?;
+    Some(agent)
+}
+
+fn payment_address_links(address: &str) -> ExternResult<Vec<Link>> {
+    get_links(
+        GetLinksInputBuilder::try_new(
+            payment_address_path(address).path_entry_hash()?,
+            LinkTypes::PaymentAddressToAgent,
+        )?
+        .build(),
+    )
+}
+
+/// Resolve an Ethereum payment address to the agent whose profile uses it
+///
+/// Only claims signed by the address count (links from before claims were
+/// signed are ignored), and of those, agents whose current profile still
+/// lists it; the earliest such claim wins, as a wallet may sign for
+/// several of its owner's agents.
+#[hdk_extern]
+pub fn get_agent_by_payment_address(address: String) -> ExternResult<Option<AgentPubKey>> {
+    let mut links = payment_address_links(&address)?;
+    links.sort_by_key(|link| link.timestamp);
+
+    let address = address.to_lowercase();
+    for link in links {
+        let agent = match valid_claim(&link) {
+            Some(agent) => agent,
+            None => continue,
+        };
+        if let Some(profile) = get_artist_profile(agent.clone())? {
+            if profile.payment_address.to_lowercase() == address {
+                return Ok(Some(agent));
+            }
         }
     }
 
     Ok(None)
 }
 
+fn follower_links(artist: &AgentPubKey) -> ExternResult<Vec<Link>> {
+    get_links(
+        GetLinksInputBuilder::try_new(
+            followers_path(artist).path_entry_hash()?,
+            LinkTypes::ArtistToFollowers,
+        )?
+        .build(),
+    )
+}
+
+/// Follow an artist; a no-op if I already do
+#[hdk_extern]
+pub fn follow_artist(artist: AgentPubKey) -> ExternResult<()> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let me = AnyLinkableHash::from(my_agent.clone());
+    if follower_links(&artist)?.iter().any(|link| link.target == me) {
+        return Ok(());
+    }
+
+    let path = followers_path(&artist);
+    path.ensure()?;
+    create_link(path.path_entry_hash()?, my_agent, LinkTypes::ArtistToFollowers, ())?;
+    Ok(())
+}
+
+/// Stop following an artist
+#[hdk_extern]
+pub fn unfollow_artist(artist: AgentPubKey) -> ExternResult<()> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    for link in follower_links(&artist)? {
+        if link.author == my_agent {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    Ok(())
+}
+
+/// Agents following an artist
+#[hdk_extern]
+pub fn get_followers(artist: AgentPubKey) -> ExternResult<Vec<AgentPubKey>> {
+    let mut followers: Vec<AgentPubKey> = follower_links(&artist)?
+        .into_iter()
+        .filter_map(|link| link.target.into_agent_pub_key())
+        .collect();
+    followers.sort();
+    followers.dedup();
+    Ok(followers)
+}
+
 /// Get my profile
 #[hdk_extern]
 pub fn get_my_profile(_: ()) -> ExternResult<Option<ArtistProfile>> {
@@ -271,17 +1104,10 @@ pub fn search_songs(query: String) -> ExternResult<Vec<Song>> {
     )?;
 
     let query_lower = query.to_lowercase();
-    let mut matches = Vec::new();
-
-    for link in links {
-        if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(song) = get_song(action_hash)? {
-                if song.title.to_lowercase().contains(&query_lower) {
-                    matches.push(song);
-                }
-            }
-        }
-    }
+    let matches = get_songs_batch(link_targets(links))?
+        .into_iter()
+        .filter(|song| song.title.to_lowercase().contains(&query_lower))
+        .collect();
 
     Ok(matches)
 }
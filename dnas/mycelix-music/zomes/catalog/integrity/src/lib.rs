@@ -4,6 +4,7 @@
 //! Songs, albums, and artist profiles are stored here.
 
 use hdi::prelude::*;
+use std::collections::BTreeMap;
 
 /// Song entry - core content unit in Mycelix Music
 #[hdk_entry_helper]
@@ -29,6 +30,350 @@ pub struct Song {
     pub released_at: Timestamp,
     /// Additional metadata (JSON)
     pub metadata: String,
+    /// Seconds of listening that qualify a play, replacing the default
+    /// 30s/50% threshold (for interludes and pieces meant to be sampled)
+    #[serde(default)]
+    pub min_play_override: Option<u32>,
+    /// International Standard Recording Code (CC-XXX-YY-NNNNN), indexed
+    /// for label lookups and duplicate detection
+    #[serde(default)]
+    pub isrc: Option<String>,
+    /// Earnings are held during a dispute: plays are recorded but not
+    /// settled until the song is unfrozen
+    #[serde(default)]
+    pub earnings_frozen: bool,
+    /// Who froze the earnings and why, while frozen
+    #[serde(default)]
+    pub earnings_freeze: Option<EarningsFreeze>,
+}
+
+/// Who may freeze a song's earnings
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FreezeAuthority {
+    Artist,
+    /// An agent in the `moderators` DNA property
+    Moderator,
+}
+
+/// Record of a freeze on a song's earnings
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EarningsFreeze {
+    pub frozen_by: AgentPubKey,
+    pub authority: FreezeAuthority,
+    /// E.g. "Contested authorship, see dispute #12"
+    pub reason: String,
+    pub frozen_at: Timestamp,
+}
+
+/// The authority an agent updates a song with, if any
+///
+/// The artist acts as the artist even if they are also a moderator.
+pub fn song_update_authority(
+    song: &Song,
+    agent: &AgentPubKey,
+    moderators: &[AgentPubKey],
+) -> Option<FreezeAuthority> {
+    if &song.artist == agent {
+        Some(FreezeAuthority::Artist)
+    } else if moderators.contains(agent) {
+        Some(FreezeAuthority::Moderator)
+    } else {
+        None
+    }
+}
+
+/// Check who may make an update to a song and how it may change its
+/// earnings freeze
+///
+/// Moderators may only freeze or unfreeze. A freeze must record its author,
+/// their authority and a reason, and only the same authority can lift or
+/// change it, so an artist can't unfreeze earnings a moderator is holding.
+pub fn check_song_update(
+    previous: &Song,
+    next: &Song,
+    author: &AgentPubKey,
+    moderators: &[AgentPubKey],
+) -> Result<(), String> {
+    let authority = song_update_authority(previous, author, moderators)
+        .ok_or_else(|| "Only the artist or a moderator can update a song".to_string())?;
+
+    let freeze_changed = previous.earnings_frozen != next.earnings_frozen
+        || previous.earnings_freeze != next.earnings_freeze;
+    if authority == FreezeAuthority::Moderator {
+        let unchanged = Song {
+            earnings_frozen: previous.earnings_frozen,
+            earnings_freeze: previous.earnings_freeze.clone(),
+            ..next.clone()
+        };
+        if &unchanged != previous {
+            return Err("Moderators can only freeze or unfreeze a song's earnings".to_string());
+        }
+    }
+    if !freeze_changed {
+        return Ok(());
+    }
+
+    if let Some(current) = &previous.earnings_freeze {
+        if current.authority != authority {
+            return Err(format!(
+                "Only the {:?} authority that froze these earnings can change the freeze",
+                current.authority
+            ));
+        }
+    }
+    match (next.earnings_frozen, &next.earnings_freeze) {
+        (true, Some(freeze)) => {
+            if &freeze.frozen_by != author || freeze.authority != authority {
+                return Err("A freeze must record who made it and their authority".to_string());
+            }
+            if freeze.reason.trim().is_empty() {
+                return Err("A freeze must give a reason".to_string());
+            }
+            Ok(())
+        }
+        (true, None) => Err("A freeze must record who made it and why".to_string()),
+        (false, Some(_)) => Err("An unfrozen song cannot carry a freeze".to_string()),
+        (false, None) => Ok(()),
+    }
+}
+
+/// Check a song's play threshold override is within its duration
+pub fn check_min_play_override(song: &Song) -> Result<(), String> {
+    match song.min_play_override {
+        Some(0) => Err("Play threshold override must be at least 1 second".to_string()),
+        Some(secs) if secs > song.duration_seconds => Err(format!(
+            "Play threshold override ({}s) cannot exceed the song duration ({}s)",
+            secs, song.duration_seconds
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Well-known fields of a song's `metadata` JSON
+///
+/// Every field is optional. Fields this version doesn't know are kept in
+/// `extra`, so metadata written by newer clients survives a round trip.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SongMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm: Option<f64>,
+    /// Musical key, e.g. "F# minor"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// International Standard Recording Code, with or without hyphens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explicit: Option<bool>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Highest tempo accepted in song metadata
+pub const MAX_METADATA_BPM: f64 = 999.0;
+
+/// Longest musical key accepted in song metadata
+pub const MAX_METADATA_KEY_LEN: usize = 32;
+
+/// Metadata field names `required_song_metadata` may list
+pub const SONG_METADATA_FIELDS: [&str; 4] = ["bpm", "key", "isrc", "explicit"];
+
+impl SongMetadata {
+    /// Parse and check a song's `metadata` JSON; empty means no metadata
+    pub fn parse(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let metadata: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Song metadata must be a JSON object: {}", e))?;
+        metadata.check()?;
+        Ok(metadata)
+    }
+
+    /// Check the well-known fields hold sensible values
+    pub fn check(&self) -> Result<(), String> {
+        if let Some(bpm) = self.bpm {
+            if !(bpm > 0.0 && bpm <= MAX_METADATA_BPM) {
+                return Err(format!(
+                    "Metadata bpm must be above 0 and at most {}",
+                    MAX_METADATA_BPM
+                ));
+            }
+        }
+        if let Some(key) = &self.key {
+            if key.trim().is_empty() || key.len() > MAX_METADATA_KEY_LEN {
+                return Err(format!(
+                    "Metadata key must be 1 to {} characters",
+                    MAX_METADATA_KEY_LEN
+                ));
+            }
+        }
+        if let Some(isrc) = &self.isrc {
+            check_isrc(isrc)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a well-known field (see `SONG_METADATA_FIELDS`) is set
+    pub fn has_field(&self, field: &str) -> bool {
+        match field {
+            "bpm" => self.bpm.is_some(),
+            "key" => self.key.is_some(),
+            "isrc" => self.isrc.is_some(),
+            "explicit" => self.explicit.is_some(),
+            _ => self.extra.contains_key(field),
+        }
+    }
+}
+
+/// Check an ISRC: country (2 letters), registrant (3 alphanumerics), year
+/// (2 digits) and designation (5 digits), e.g. "US-RC1-76-07839"
+pub fn check_isrc(isrc: &str) -> Result<(), String> {
+    let code: Vec<char> = normalize_isrc(isrc).chars().collect();
+    let valid = code.len() == 12
+        && code[..2].iter().all(|c| c.is_ascii_uppercase())
+        && code[2..5]
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && code[5..].iter().all(|c| c.is_ascii_digit());
+    if !valid {
+        return Err(format!("Metadata isrc {} is not a valid ISRC", isrc));
+    }
+    Ok(())
+}
+
+/// An ISRC without hyphens, the form it is indexed by
+pub fn normalize_isrc(isrc: &str) -> String {
+    isrc.chars().filter(|c| *c != '-').collect()
+}
+
+impl Song {
+    /// The song's `metadata`, parsed
+    pub fn parsed_metadata(&self) -> Result<SongMetadata, String> {
+        SongMetadata::parse(&self.metadata)
+    }
+
+    /// Check the song's ISRC, which metadata may repeat but not contradict
+    pub fn check_isrc(&self, metadata: &SongMetadata) -> Result<(), String> {
+        let isrc = match &self.isrc {
+            Some(isrc) => isrc,
+            None => return Ok(()),
+        };
+        check_isrc(isrc)?;
+        match &metadata.isrc {
+            Some(other) if normalize_isrc(other) != normalize_isrc(isrc) => {
+                Err(format!("Metadata isrc {} does not match the song's ISRC {}", other, isrc))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the metadata an update sets, if it changed
+    ///
+    /// `required_song_metadata` only applies at creation, so a deployment
+    /// that adds a required field doesn't lock every existing song out of
+    /// updates; new metadata must still parse and agree with the ISRC.
+    pub fn check_metadata_change(&self, previous: &Song) -> Result<(), String> {
+        if self.metadata == previous.metadata {
+            return Ok(());
+        }
+        self.check_isrc(&self.parsed_metadata()?)
+    }
+}
+
+/// Microseconds per release-index bucket
+pub const RELEASE_DAY_MICROS: i64 = 86_400_000_000;
+
+/// How far ahead of its creation a song may be scheduled for release
+pub const MAX_RELEASE_LEAD_DAYS: i64 = 365;
+
+/// Day bucket (days since the epoch) a release falls into
+pub fn release_day(released_at: Timestamp) -> i64 {
+    released_at.as_micros().div_euclid(RELEASE_DAY_MICROS)
+}
+
+/// Tag of a public listing link (artist's songs, all songs, genre, release
+/// day)
+///
+/// Listings target a song's create action, its canonical hash, while a
+/// scheduled song is listed as of its latest version, which may have moved
+/// its release; `version` names that version for validation.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct ListingTag {
+    /// The version's `released_at` in microseconds, for ordering releases
+    pub released_at: i64,
+    pub version: ActionHash,
+}
+
+impl ListingTag {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let bytes = SerializedBytes::try_from(self.clone()).map_err(|e| wasm_error!(e))?;
+        Ok(LinkTag::new(bytes.bytes().clone()))
+    }
+
+    pub fn from_link_tag(tag: &LinkTag) -> Option<Self> {
+        Self::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone()))).ok()
+    }
+}
+
+/// Release time of a release index link: from its `ListingTag`, or the
+/// bare big-endian microseconds older links were tagged with
+pub fn release_tag_time(tag: &LinkTag) -> Option<i64> {
+    if let Some(listing) = ListingTag::from_link_tag(tag) {
+        return Some(listing.released_at);
+    }
+    let bytes: [u8; 8] = tag.0.as_slice().try_into().ok()?;
+    Some(i64::from_be_bytes(bytes))
+}
+
+/// Check a song isn't scheduled unreasonably far past `now`
+pub fn check_release_date(released_at: Timestamp, now: Timestamp) -> Result<(), String> {
+    let lead_days = (released_at.as_micros() - now.as_micros()) / RELEASE_DAY_MICROS;
+    if lead_days > MAX_RELEASE_LEAD_DAYS {
+        return Err(format!(
+            "Release date can be at most {} days in the future",
+            MAX_RELEASE_LEAD_DAYS
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a song is scheduled for release after `now`
+///
+/// Scheduled songs stay out of the public listings (all songs, genres, the
+/// artist's songs) until `publish_due_songs` lists them.
+pub fn is_scheduled(song: &Song, now: Timestamp) -> bool {
+    song.released_at > now
+}
+
+/// Check a change to a song's release date made at `now`
+///
+/// Only scheduled songs can be rescheduled, and not into the past; once a
+/// song is released its date is fixed. Who may update is checked by
+/// `check_song_update`.
+pub fn check_release_change(previous: &Song, next: &Song, now: Timestamp) -> Result<(), String> {
+    if previous.released_at == next.released_at {
+        return Ok(());
+    }
+    if !is_scheduled(previous, now) {
+        return Err("A released song's release date cannot be changed".to_string());
+    }
+    if next.released_at < now {
+        return Err("A song cannot be rescheduled into the past".to_string());
+    }
+    check_release_date(next.released_at, now)
+}
+
+/// Check a public listing link (all songs, genre, artist) made by
+/// `author` at `now`
+pub fn check_song_listing(song: &Song, author: &AgentPubKey, now: Timestamp) -> Result<(), String> {
+    if &song.artist != author {
+        return Err("Only the artist can list their song".to_string());
+    }
+    if is_scheduled(song, now) {
+        return Err("Scheduled songs cannot be listed before their release".to_string());
+    }
+    Ok(())
 }
 
 /// Album entry - collection of songs
@@ -65,6 +410,54 @@ pub struct ArtistProfile {
     pub social_links: String,
     /// Verified status (set by trust zome)
     pub verified: bool,
+    /// Trust tier name, e.g. "CommunityVerified" (set by trust zome)
+    #[serde(default)]
+    pub verification_tier: Option<String>,
+    /// Trust `VerificationStatus` backing `verified` and the tier
+    #[serde(default)]
+    pub verification_hash: Option<ActionHash>,
+}
+
+/// Tier name of agents with no verification
+pub const UNVERIFIED_TIER: &str = "Unverified";
+
+/// Mirror of `trust_integrity::VerificationStatus`, decoded from the
+/// record a profile's `verification_hash` points at
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct TrustVerification {
+    pub artist: AgentPubKey,
+    pub trust_score: u32,
+    /// Variant name of `trust_integrity::VerificationTier`
+    pub tier: String,
+    pub vouch_count: u32,
+    pub computed_at: Timestamp,
+}
+
+/// Check a profile's verification fields against the trust status they
+/// cite. Without a status the profile must be unverified.
+pub fn check_profile_verification(
+    profile: &ArtistProfile,
+    author: &AgentPubKey,
+    status: Option<&TrustVerification>,
+) -> Result<(), String> {
+    let status = match status {
+        Some(status) => status,
+        None if profile.verified || profile.verification_tier.is_some() => {
+            return Err("Verification must cite a trust verification status".to_string())
+        }
+        None => return Ok(()),
+    };
+
+    if &status.artist != author {
+        return Err("Verification status is for a different artist".to_string());
+    }
+    if profile.verification_tier.as_deref() != Some(status.tier.as_str()) {
+        return Err("Verification tier does not match the trust status".to_string());
+    }
+    if profile.verified != (status.tier != UNVERIFIED_TIER) {
+        return Err("Verified flag does not match the trust status".to_string());
+    }
+    Ok(())
 }
 
 /// Link types for the catalog
@@ -82,6 +475,292 @@ pub enum LinkTypes {
     AllSongs,
     /// All artists anchor
     AllArtists,
+    /// song_by_hash/{song_hash} -> Song
+    SongByHash,
+    /// profile_by_payment_address/{address} -> Agent claiming it
+    PaymentAddressToAgent,
+    /// followers/{artist} -> Agent following them
+    ArtistToFollowers,
+    /// released/{day} -> Songs released that day, tagged with `released_at`
+    ReleasedRecent,
+    /// isrc/{isrc} -> Songs claiming that ISRC
+    SongByIsrc,
+    /// scheduled/{artist} -> Songs waiting for their release
+    ArtistToScheduled,
+}
+
+/// Index path for looking a song up by its content hash
+pub fn song_by_hash_path(song_hash: &str) -> Path {
+    Path::from(format!("song_by_hash/{}", song_hash))
+}
+
+/// Index path for looking songs up by ISRC (hyphens removed)
+pub fn isrc_path(isrc: &str) -> Path {
+    Path::from(format!("isrc/{}", normalize_isrc(isrc)))
+}
+
+/// Index path for resolving an Ethereum payment address to an agent
+///
+/// Addresses are lowercased so checksummed and plain forms resolve alike.
+pub fn payment_address_path(address: &str) -> Path {
+    Path::from(format!("profile_by_payment_address/{}", address.to_lowercase()))
+}
+
+/// An agent's claim to an Ethereum payment address, carried in the tag of
+/// its `PaymentAddressToAgent` link
+///
+/// `signature` is the address's EIP-191 (`personal_sign`) signature over
+/// `payment_address_claim_message`, so only the wallet's owner can point
+/// the address at an agent.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct PaymentAddressClaim {
+    pub address: String,
+    /// 65 bytes: r, s and v (0/1 or 27/28)
+    pub signature: Vec<u8>,
+}
+
+impl PaymentAddressClaim {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let bytes = SerializedBytes::try_from(self.clone()).map_err(|e| wasm_error!(e))?;
+        Ok(LinkTag::new(bytes.bytes().clone()))
+    }
+
+    pub fn from_link_tag(tag: &LinkTag) -> Option<Self> {
+        Self::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone()))).ok()
+    }
+}
+
+/// Message a wallet signs to claim its address for `agent`
+pub fn payment_address_claim_message(address: &str, agent: &AgentPubKey) -> String {
+    format!(
+        "Mycelix Music: payment address {} belongs to agent {}",
+        address.to_lowercase(),
+        agent
+    )
+}
+
+/// Check that a claim's signature was made by its address for `agent`
+pub fn check_payment_address_claim(
+    claim: &PaymentAddressClaim,
+    agent: &AgentPubKey,
+) -> Result<(), String> {
+    let message = payment_address_claim_message(&claim.address, agent);
+    let signer = recover_eth_address(&message, &claim.signature)?;
+    if signer != claim.address.to_lowercase() {
+        return Err(format!(
+            "Payment address claim is signed by {}, not {}",
+            signer, claim.address
+        ));
+    }
+    Ok(())
+}
+
+/// Keccak-256 of `message` with the EIP-191 personal-message prefix, the
+/// digest a wallet's `personal_sign` signs
+pub fn eip191_digest(message: &str) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    Keccak256::digest(prefixed.as_bytes()).into()
+}
+
+/// Lowercase 0x address that made an EIP-191 signature over `message`
+pub fn recover_eth_address(message: &str, signature: &[u8]) -> Result<String, String> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+    use sha3::{Digest, Keccak256};
+
+    if signature.len() != 65 {
+        return Err("Signature must be 65 bytes".to_string());
+    }
+    let v = signature[64];
+    let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })
+        .ok_or_else(|| "Invalid signature recovery id".to_string())?;
+    let signature =
+        Signature::from_slice(&signature[..64]).map_err(|_| "Invalid signature".to_string())?;
+
+    let digest = eip191_digest(message);
+    let key = VerifyingKey::recover_from_prehash(&digest, &signature, recovery_id)
+        .map_err(|_| "Signature does not recover to a key".to_string())?;
+
+    let point = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    Ok(format!(
+        "0x{}",
+        hash[12..].iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    ))
+}
+
+/// Index path for an artist's followers
+pub fn followers_path(artist: &AgentPubKey) -> Path {
+    Path::from(format!("followers/{}", artist))
+}
+
+/// Index path for an artist's scheduled songs, until they're published
+pub fn scheduled_path(artist: &AgentPubKey) -> Path {
+    Path::from(format!("scheduled/{}", artist))
+}
+
+/// Index path for songs released on a day (see `release_day`)
+pub fn release_day_path(day: i64) -> Path {
+    Path::from(format!("released/{}", day))
+}
+
+/// How `create_song` treats agents without an artist profile
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtistOnboarding {
+    /// Anyone may upload (permissionless deployments)
+    #[default]
+    Open,
+    /// A minimal profile is created on the artist's first upload
+    AutoProfile,
+    /// Uploads are refused until the artist creates a profile
+    ProfileRequired,
+}
+
+/// Strategy ids the plays zome knows how to price
+pub const KNOWN_STRATEGY_IDS: [&str; 5] =
+    ["pay_per_stream", "patronage", "premium", "gift", "time_barter"];
+
+/// DNA properties read by the catalog
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CatalogProperties {
+    pub artist_onboarding: ArtistOnboarding,
+    /// Strategy ids this deployment offers (any strategy when unset)
+    pub allowed_strategies: Option<Vec<String>>,
+    /// Metadata fields every song must set (e.g. `["isrc", "explicit"]`)
+    pub required_song_metadata: Vec<String>,
+    /// Refuse uploads whose ISRC another of the uploader's songs already
+    /// claims, instead of only logging a warning
+    pub strict_isrc: bool,
+    /// Agents who may freeze and unfreeze any song's earnings in disputes
+    pub moderators: Vec<AgentPubKey>,
+    /// Longest song accepted (seconds)
+    pub max_song_duration_seconds: u32,
+    /// Largest `metadata` accepted (bytes)
+    pub max_song_metadata_bytes: usize,
+    /// Most genre tags a song may carry
+    pub max_song_genres: usize,
+}
+
+impl Default for CatalogProperties {
+    fn default() -> Self {
+        Self {
+            artist_onboarding: ArtistOnboarding::default(),
+            allowed_strategies: None,
+            required_song_metadata: Vec::new(),
+            strict_isrc: false,
+            moderators: Vec::new(),
+            // Six hours covers DJ mixes and full live sets
+            max_song_duration_seconds: 6 * 60 * 60,
+            max_song_metadata_bytes: 16 * 1024,
+            max_song_genres: 10,
+        }
+    }
+}
+
+impl CatalogProperties {
+    /// Strategies clients should offer: the allowlist, or every known one
+    pub fn offered_strategies(&self) -> Vec<String> {
+        match &self.allowed_strategies {
+            Some(allowed) => allowed.clone(),
+            None => KNOWN_STRATEGY_IDS.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+
+    /// Reject strategies outside the deployment's allowlist
+    pub fn check_strategy(&self, strategy_id: &str) -> Result<(), String> {
+        match &self.allowed_strategies {
+            Some(allowed) if !allowed.iter().any(|id| id == strategy_id) => Err(format!(
+                "Strategy {} is not offered on this deployment (allowed: {})",
+                strategy_id,
+                allowed.join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject songs over the deployment's duration, metadata size or genre
+    /// limits, so a bad client can't skew play math or bloat the DHT
+    pub fn check_song_limits(&self, song: &Song) -> Result<(), String> {
+        self.check_limits(None, song)
+    }
+
+    /// Check the limits an update to `previous` touches
+    ///
+    /// Only changed fields are checked, so songs created before a deployment
+    /// tightened its limits can still be edited.
+    pub fn check_song_limit_changes(&self, previous: &Song, song: &Song) -> Result<(), String> {
+        self.check_limits(Some(previous), song)
+    }
+
+    fn check_limits(&self, previous: Option<&Song>, song: &Song) -> Result<(), String> {
+        let duration_changed =
+            previous.map_or(true, |p| p.duration_seconds != song.duration_seconds);
+        let metadata_changed = previous.map_or(true, |p| p.metadata != song.metadata);
+        let genres_changed = previous.map_or(true, |p| p.genres != song.genres);
+
+        if duration_changed && song.duration_seconds > self.max_song_duration_seconds {
+            return Err(format!(
+                "Song duration of {}s exceeds the {}s limit",
+                song.duration_seconds, self.max_song_duration_seconds
+            ));
+        }
+        if metadata_changed && song.metadata.len() > self.max_song_metadata_bytes {
+            return Err(format!(
+                "Song metadata of {} bytes exceeds the {} byte limit",
+                song.metadata.len(),
+                self.max_song_metadata_bytes
+            ));
+        }
+        if genres_changed && song.genres.len() > self.max_song_genres {
+            return Err(format!(
+                "Song has {} genres, more than the limit of {}",
+                song.genres.len(),
+                self.max_song_genres
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse a song's metadata and check the deployment's required fields
+    pub fn check_song_metadata(&self, song: &Song) -> Result<SongMetadata, String> {
+        let metadata = song.parsed_metadata()?;
+        let missing: Vec<&str> = self
+            .required_song_metadata
+            .iter()
+            .filter(|field| !metadata.has_field(field))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("Song metadata must set {}", missing.join(", ")));
+        }
+        Ok(metadata)
+    }
+}
+
+/// Parse catalog settings from DNA properties; defaults if none are set
+///
+/// Malformed properties are an error rather than the defaults, so a typo in
+/// a limit or the onboarding mode can't go unnoticed.
+pub fn parse_catalog_properties(properties: SerializedBytes) -> Result<CatalogProperties, String> {
+    if properties_unset(&properties) {
+        return Ok(CatalogProperties::default());
+    }
+    CatalogProperties::try_from(properties)
+        .map_err(|e| format!("Malformed catalog DNA properties: {}", e))
+}
+
+/// Read catalog settings from DNA properties
+pub fn catalog_properties() -> ExternResult<CatalogProperties> {
+    parse_catalog_properties(dna_info()?.modifiers.properties)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))
+}
+
+/// Whether the DNA manifest sets no properties (empty, or msgpack nil)
+fn properties_unset(properties: &SerializedBytes) -> bool {
+    matches!(properties.bytes().as_slice(), [] | [0xc0])
 }
 
 /// Entry types for the catalog zome
@@ -123,18 +802,59 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
         },
         FlatOp::RegisterCreateLink {
             link_type,
-            base_address: _,
-            target_address: _,
-            tag: _,
-            action: _,
+            base_address,
+            target_address,
+            tag,
+            action,
         } => match link_type {
-            LinkTypes::ArtistToSongs => Ok(ValidateCallbackResult::Valid),
+            LinkTypes::ArtistToSongs | LinkTypes::GenreToSongs | LinkTypes::AllSongs => {
+                validate_listing_link(target_address, tag, action)
+            }
             LinkTypes::ArtistToAlbums => Ok(ValidateCallbackResult::Valid),
             LinkTypes::AlbumToSongs => Ok(ValidateCallbackResult::Valid),
-            LinkTypes::GenreToSongs => Ok(ValidateCallbackResult::Valid),
-            LinkTypes::AllSongs => Ok(ValidateCallbackResult::Valid),
             LinkTypes::AllArtists => Ok(ValidateCallbackResult::Valid),
+            LinkTypes::SongByHash => {
+                validate_song_by_hash_link(base_address, target_address, action)
+            }
+            LinkTypes::PaymentAddressToAgent => {
+                validate_payment_address_link(base_address, target_address, tag, action)
+            }
+            LinkTypes::ArtistToFollowers => validate_follower_link(target_address, action),
+            LinkTypes::ReleasedRecent => {
+                validate_release_link(base_address, target_address, tag, action)
+            }
+            LinkTypes::SongByIsrc => validate_isrc_link(base_address, target_address, action),
+            LinkTypes::ArtistToScheduled => {
+                validate_scheduled_link(base_address, target_address, action)
+            }
         },
+        FlatOp::RegisterDeleteLink {
+            link_type: LinkTypes::ArtistToFollowers,
+            original_action,
+            action,
+            ..
+        } => {
+            if action.author != original_action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the follower can unfollow".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+        // Listings, indexes and schedules are kept by the agent who made
+        // them; nobody else can unlist a song or drop it from a schedule
+        FlatOp::RegisterDeleteLink {
+            original_action,
+            action,
+            ..
+        } => {
+            if action.author != original_action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only a link's author can delete it".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
         _ => Ok(ValidateCallbackResult::Valid),
     }
 }
@@ -168,6 +888,378 @@ fn validate_create_song(song: Song, action: Create) -> ExternResult<ValidateCall
         ));
     }
 
+    if let Err(reason) = check_min_play_override(&song) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    let properties = catalog_properties()?;
+    if let Err(reason) = properties.check_song_limits(&song) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    if let Err(reason) = properties.check_strategy(&song.strategy_id) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    match properties.check_song_metadata(&song) {
+        Ok(metadata) => {
+            if let Err(reason) = song.check_isrc(&metadata) {
+                return Ok(ValidateCallbackResult::Invalid(reason));
+            }
+        }
+        Err(reason) => return Ok(ValidateCallbackResult::Invalid(reason)),
+    }
+
+    if let Err(reason) = check_release_date(song.released_at, action.timestamp) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // Earnings are frozen by a later update, never at upload
+    if song.earnings_frozen || song.earnings_freeze.is_some() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A new song's earnings cannot be frozen".to_string(),
+        ));
+    }
+
+    // Song must have a content hash
+    if song.song_hash.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Song must have a content hash".to_string(),
+        ));
+    }
+
+    // A content hash can only be claimed once per artist chain; content
+    // claimed across artists is contested (see get_song_by_content_hash)
+    if prior_song_hashes(&action.author, &action.prev_action)?.contains(&song.song_hash) {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "A song with content hash {} already exists",
+            song.song_hash
+        )));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Content hashes of songs created earlier on an author's chain
+fn prior_song_hashes(author: &AgentPubKey, until: &ActionHash) -> ExternResult<Vec<String>> {
+    let song_def = ScopedEntryDefIndex::try_from(UnitEntryTypes::Song)?;
+    let activity = must_get_agent_activity(author.clone(), ChainFilter::new(until.clone()))?;
+
+    let mut hashes = Vec::new();
+    for item in activity {
+        if let Action::Create(create) = &item.action.hashed.content {
+            let is_song = matches!(
+                &create.entry_type,
+                EntryType::App(def)
+                    if def.zome_index == song_def.zome_index
+                        && def.entry_index == song_def.zome_type
+            );
+            if is_song {
+                let entry = must_get_entry(create.entry_hash.clone())?;
+                if let Ok(song) = Song::try_from(entry.content) {
+                    hashes.push(song.song_hash);
+                }
+            }
+        }
+    }
+
+    Ok(hashes)
+}
+
+/// song_by_hash links must point from a song's own content-hash path,
+/// created by the song's artist
+fn validate_song_by_hash_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Song index link must target a song action".to_string(),
+            ))
+        }
+    };
+
+    let record = must_get_valid_record(target)?;
+    let song = match record
+        .entry()
+        .to_app_option::<Song>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(song) => song,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Song index link must target a song".to_string(),
+            ))
+        }
+    };
+
+    if song.artist != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the artist can index their song".to_string(),
+        ));
+    }
+
+    if AnyLinkableHash::from(song_by_hash_path(&song.song_hash).path_entry_hash()?) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Song index link base must match the song's content hash".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// ISRC index links must point from the song's own ISRC, created by its
+/// artist
+fn validate_isrc_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "ISRC index link must target a song action".to_string(),
+            ))
+        }
+    };
+
+    let song = match must_get_valid_record(target)?
+        .entry()
+        .to_app_option::<Song>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(song) => song,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "ISRC index link must target a song".to_string(),
+            ))
+        }
+    };
+
+    if song.artist != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the artist can index their song".to_string(),
+        ));
+    }
+
+    let isrc = match &song.isrc {
+        Some(isrc) => isrc,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "ISRC index link must target a song with an ISRC".to_string(),
+            ))
+        }
+    };
+    if AnyLinkableHash::from(isrc_path(isrc).path_entry_hash()?) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "ISRC index link base must match the song's ISRC".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Release index links must point from the song's release day, created
+/// by the song's artist
+fn validate_release_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    tag: LinkTag,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let (song, released_at) = match listed_song(target_address, &tag)? {
+        Ok(listed) => listed,
+        Err(reason) => return Ok(ValidateCallbackResult::Invalid(reason)),
+    };
+
+    if song.artist != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the artist can index their release".to_string(),
+        ));
+    }
+
+    if released_at != Some(song.released_at.as_micros()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Release index link must be tagged with the song's release time".to_string(),
+        ));
+    }
+
+    let day_path = release_day_path(release_day(song.released_at));
+    if AnyLinkableHash::from(day_path.path_entry_hash()?) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Release index link base must match the song's release day".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Songs are listed publicly by their artist, and only once released
+fn validate_listing_link(
+    target_address: AnyLinkableHash,
+    tag: LinkTag,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let (song, _) = match listed_song(target_address, &tag)? {
+        Ok(listed) => listed,
+        Err(reason) => return Ok(ValidateCallbackResult::Invalid(reason)),
+    };
+
+    Ok(match check_song_listing(&song, &action.author, action.timestamp) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// The song version a listing link was made for, and the release time in
+/// its tag, if any
+///
+/// The link targets the song's create action; a `ListingTag` names the
+/// update it was listed as, which must descend from that create. Links
+/// without one list the target itself.
+fn listed_song(
+    target_address: AnyLinkableHash,
+    tag: &LinkTag,
+) -> ExternResult<Result<(Song, Option<i64>), String>> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => return Ok(Err("Song listing link must target a song action".to_string())),
+    };
+
+    let (version, released_at) = match ListingTag::from_link_tag(tag) {
+        Some(listing) => {
+            if !is_song_version(&target, &listing.version)? {
+                return Ok(Err(
+                    "Song listing link must be tagged with a version of its song".to_string(),
+                ));
+            }
+            (listing.version, Some(listing.released_at))
+        }
+        None => (target, release_tag_time(tag)),
+    };
+
+    Ok(
+        match must_get_valid_record(version)?
+            .entry()
+            .to_app_option::<Song>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            Some(song) => Ok((song, released_at)),
+            None => Err("Song listing link must target a song".to_string()),
+        },
+    )
+}
+
+/// Whether `version` is the song created at `song_hash` or an update
+/// descending from it
+fn is_song_version(song_hash: &ActionHash, version: &ActionHash) -> ExternResult<bool> {
+    let mut current = version.clone();
+    loop {
+        if &current == song_hash {
+            return Ok(true);
+        }
+        match must_get_action(current)?.action() {
+            Action::Update(update) => current = update.original_action_address.clone(),
+            _ => return Ok(false),
+        }
+    }
+}
+
+/// Scheduled index links must point from the artist's own scheduled path
+/// to one of their songs
+fn validate_scheduled_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Scheduled index link must target a song action".to_string(),
+            ))
+        }
+    };
+
+    let song = match must_get_valid_record(target)?
+        .entry()
+        .to_app_option::<Song>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(song) => song,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Scheduled index link must target a song".to_string(),
+            ))
+        }
+    };
+
+    if song.artist != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the artist can schedule their song".to_string(),
+        ));
+    }
+
+    if AnyLinkableHash::from(scheduled_path(&song.artist).path_entry_hash()?) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Scheduled index link base must be the artist's scheduled path".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Agents can only claim payment addresses for themselves, from the
+/// address's index path, with the address's signature; whether the claim
+/// matches their profile is checked when resolving
+fn validate_payment_address_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    tag: LinkTag,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    if target_address != AnyLinkableHash::from(action.author.clone()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Payment address links must target their author".to_string(),
+        ));
+    }
+
+    let claim = match PaymentAddressClaim::from_link_tag(&tag) {
+        Some(claim) => claim,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Payment address links must carry a signed claim".to_string(),
+            ))
+        }
+    };
+    if AnyLinkableHash::from(payment_address_path(&claim.address).path_entry_hash()?)
+        != base_address
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Payment address link base must match the claimed address".to_string(),
+        ));
+    }
+
+    Ok(match check_payment_address_claim(&claim, &action.author) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// Agents can only follow as themselves
+fn validate_follower_link(
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    if target_address != AnyLinkableHash::from(action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Follower links must target their author".to_string(),
+        ));
+    }
     Ok(ValidateCallbackResult::Valid)
 }
 
@@ -190,25 +1282,103 @@ fn validate_create_album(album: Album, action: Create) -> ExternResult<ValidateC
 }
 
 fn validate_create_profile(
-    _profile: ArtistProfile,
-    _action: Create,
+    profile: ArtistProfile,
+    action: Create,
 ) -> ExternResult<ValidateCallbackResult> {
-    // Profiles can be created by anyone for themselves
-    Ok(ValidateCallbackResult::Valid)
+    // Profiles can be created by anyone for themselves, but only the
+    // trust zome's status can make them verified
+    validate_profile_verification(&profile, &action.author)
+}
+
+fn validate_profile_verification(
+    profile: &ArtistProfile,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let status = match &profile.verification_hash {
+        Some(hash) => {
+            let status = must_get_valid_record(hash.clone())?
+                .entry()
+                .to_app_option::<TrustVerification>()
+                .map_err(|e| wasm_error!(e))?;
+            match status {
+                Some(status) => Some(status),
+                None => {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "Verification hash must reference a trust verification status"
+                            .to_string(),
+                    ))
+                }
+            }
+        }
+        None => None,
+    };
+
+    Ok(match check_profile_verification(profile, author, status.as_ref()) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
 }
 
 fn validate_update_song(
-    _song: Song,
+    song: Song,
     action: Update,
     original_action_hash: ActionHash,
 ) -> ExternResult<ValidateCallbackResult> {
-    // Only the original author can update
-    let original_action = must_get_action(original_action_hash)?;
-    if original_action.action().author() != &action.author {
+    // Updates are made to the song's previous version
+    let original = match must_get_valid_record(original_action_hash)?
+        .entry()
+        .to_app_option::<Song>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not a song".to_string(),
+            ))
+        }
+    };
+
+    // The artist updates their song; moderators only freeze its earnings
+    let properties = catalog_properties()?;
+    if let Err(reason) = check_song_update(&original, &song, &action.author, &properties.moderators) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // The content hash is indexed, so it can't change
+    if original.song_hash != song.song_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A song's content hash cannot be changed".to_string(),
+        ));
+    }
+
+    // The release date can only move while the song is scheduled
+    if let Err(reason) = check_release_change(&original, &song, action.timestamp) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // And the ISRC
+    if original.isrc != song.isrc {
         return Ok(ValidateCallbackResult::Invalid(
-            "Only the original author can update a song".to_string(),
+            "A song's ISRC cannot be changed".to_string(),
         ));
     }
+
+    if let Err(reason) = check_min_play_override(&song) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    if let Err(reason) = properties.check_song_limit_changes(&original, &song) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    if let Err(reason) = properties.check_strategy(&song.strategy_id) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    if let Err(reason) = song.check_metadata_change(&original) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
 
@@ -227,7 +1397,7 @@ fn validate_update_album(
 }
 
 fn validate_update_profile(
-    _profile: ArtistProfile,
+    profile: ArtistProfile,
     action: Update,
     original_action_hash: ActionHash,
 ) -> ExternResult<ValidateCallbackResult> {
@@ -237,5 +1407,440 @@ fn validate_update_profile(
             "Only the original author can update their profile".to_string(),
         ));
     }
-    Ok(ValidateCallbackResult::Valid)
+    validate_profile_verification(&profile, &action.author)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(byte: u8) -> AgentPubKey {
+        AgentPubKey::from_raw_36(vec![byte; 36])
+    }
+
+    fn profile(verified: bool, tier: Option<&str>) -> ArtistProfile {
+        ArtistProfile {
+            name: "Artist".to_string(),
+            bio: String::new(),
+            avatar_cid: None,
+            payment_address: String::new(),
+            social_links: String::new(),
+            verified,
+            verification_tier: tier.map(str::to_string),
+            verification_hash: None,
+        }
+    }
+
+    fn status(artist: AgentPubKey, tier: &str) -> TrustVerification {
+        TrustVerification {
+            artist,
+            trust_score: 800,
+            tier: tier.to_string(),
+            vouch_count: 3,
+            computed_at: Timestamp::from_micros(0),
+        }
+    }
+
+    fn song(duration_seconds: u32, min_play_override: Option<u32>) -> Song {
+        Song {
+            song_hash: "hash".to_string(),
+            title: "Interlude".to_string(),
+            artist: agent(1),
+            ipfs_cid: "cid".to_string(),
+            cover_cid: None,
+            duration_seconds,
+            genres: Vec::new(),
+            strategy_id: "pay_per_stream".to_string(),
+            released_at: Timestamp::from_micros(0),
+            metadata: String::new(),
+            min_play_override,
+            isrc: None,
+            earnings_frozen: false,
+            earnings_freeze: None,
+        }
+    }
+
+    #[test]
+    fn play_threshold_override_must_fit_the_song() {
+        assert!(check_min_play_override(&song(20, None)).is_ok());
+        assert!(check_min_play_override(&song(20, Some(10))).is_ok());
+        assert!(check_min_play_override(&song(20, Some(20))).is_ok());
+        assert!(check_min_play_override(&song(20, Some(21))).is_err());
+        assert!(check_min_play_override(&song(20, Some(0))).is_err());
+    }
+
+    #[test]
+    fn release_date_cannot_be_far_in_the_future() {
+        let now = Timestamp::from_micros(1_700_000_000_000_000);
+        let days = |n: i64| Timestamp::from_micros(now.as_micros() + n * RELEASE_DAY_MICROS);
+        assert!(check_release_date(now, now).is_ok());
+        assert!(check_release_date(days(-1000), now).is_ok());
+        assert!(check_release_date(days(MAX_RELEASE_LEAD_DAYS), now).is_ok());
+        assert!(check_release_date(days(MAX_RELEASE_LEAD_DAYS + 1), now).is_err());
+    }
+
+    #[test]
+    fn only_scheduled_songs_can_be_rescheduled() {
+        let now = Timestamp::from_micros(1_700_000_000_000_000);
+        let at = |days: i64| Song {
+            released_at: Timestamp::from_micros(now.as_micros() + days * RELEASE_DAY_MICROS),
+            ..song(200, None)
+        };
+        assert!(check_release_change(&at(7), &at(14), now).is_ok());
+        // Pulled forward to release right away
+        assert!(check_release_change(&at(7), &at(0), now).is_ok());
+        assert!(check_release_change(&at(7), &at(-1), now).is_err());
+        assert!(check_release_change(&at(7), &at(MAX_RELEASE_LEAD_DAYS + 1), now).is_err());
+        // Released songs keep their date
+        assert!(check_release_change(&at(-1), &at(7), now).is_err());
+        assert!(check_release_change(&at(0), &at(7), now).is_err());
+        assert!(check_release_change(&at(-1), &at(-1), now).is_ok());
+    }
+
+    #[test]
+    fn scheduled_songs_are_not_listed_early() {
+        let now = Timestamp::from_micros(1_700_000_000_000_000);
+        let scheduled = Song {
+            released_at: Timestamp::from_micros(now.as_micros() + 1),
+            ..song(200, None)
+        };
+        assert!(is_scheduled(&scheduled, now));
+        assert!(check_song_listing(&scheduled, &agent(1), now).is_err());
+        assert!(check_song_listing(&scheduled, &agent(1), scheduled.released_at).is_ok());
+        // Only the artist lists their songs
+        assert!(check_song_listing(&song(200, None), &agent(2), now).is_err());
+    }
+
+    #[test]
+    fn release_days_split_at_midnight_utc() {
+        assert_eq!(release_day(Timestamp::from_micros(0)), 0);
+        assert_eq!(release_day(Timestamp::from_micros(RELEASE_DAY_MICROS - 1)), 0);
+        assert_eq!(release_day(Timestamp::from_micros(RELEASE_DAY_MICROS)), 1);
+        assert_eq!(release_day(Timestamp::from_micros(-1)), -1);
+    }
+
+    #[test]
+    fn unverified_profile_needs_no_status() {
+        assert!(check_profile_verification(&profile(false, None), &agent(1), None).is_ok());
+    }
+
+    #[test]
+    fn client_cannot_self_verify() {
+        assert!(check_profile_verification(&profile(true, None), &agent(1), None).is_err());
+        assert!(
+            check_profile_verification(&profile(false, Some("Trusted")), &agent(1), None).is_err()
+        );
+    }
+
+    #[test]
+    fn verification_must_match_the_cited_status() {
+        let community = status(agent(1), "CommunityVerified");
+        let verified = profile(true, Some("CommunityVerified"));
+        assert!(check_profile_verification(&verified, &agent(1), Some(&community)).is_ok());
+        // Someone else's status
+        assert!(check_profile_verification(&verified, &agent(2), Some(&community)).is_err());
+        // Inflated tier
+        let inflated = profile(true, Some("Trusted"));
+        assert!(check_profile_verification(&inflated, &agent(1), Some(&community)).is_err());
+        // Verified flag on an unverified status
+        let unverified = status(agent(1), UNVERIFIED_TIER);
+        let claimed = profile(true, Some(UNVERIFIED_TIER));
+        assert!(check_profile_verification(&claimed, &agent(1), Some(&unverified)).is_err());
+        let honest = profile(false, Some(UNVERIFIED_TIER));
+        assert!(check_profile_verification(&honest, &agent(1), Some(&unverified)).is_ok());
+    }
+
+    #[test]
+    fn strategy_allowlist_defaults_to_everything() {
+        let open = CatalogProperties::default();
+        assert!(open.check_strategy("premium").is_ok());
+        assert_eq!(open.offered_strategies().len(), KNOWN_STRATEGY_IDS.len());
+
+        let podcasts = CatalogProperties {
+            allowed_strategies: Some(vec!["gift".to_string(), "patronage".to_string()]),
+            ..Default::default()
+        };
+        assert!(podcasts.check_strategy("patronage").is_ok());
+        assert!(podcasts.check_strategy("premium").is_err());
+        assert_eq!(podcasts.offered_strategies(), vec!["gift", "patronage"]);
+    }
+
+    #[test]
+    fn metadata_keeps_unknown_fields() {
+        let metadata =
+            SongMetadata::parse(r#"{"bpm": 128, "explicit": false, "mood": "calm"}"#).unwrap();
+        assert_eq!(metadata.bpm, Some(128.0));
+        assert_eq!(metadata.explicit, Some(false));
+        assert_eq!(metadata.extra.get("mood"), Some(&serde_json::json!("calm")));
+        let round_trip = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(SongMetadata::parse(&round_trip).unwrap(), metadata);
+        // No metadata at all
+        assert_eq!(SongMetadata::parse("").unwrap(), SongMetadata::default());
+    }
+
+    #[test]
+    fn malformed_metadata_is_rejected() {
+        assert!(SongMetadata::parse("{bpm: 120").is_err());
+        assert!(SongMetadata::parse("[120]").is_err());
+        assert!(SongMetadata::parse(r#"{"bpm": "fast"}"#).is_err());
+        assert!(SongMetadata::parse(r#"{"bpm": 0}"#).is_err());
+        assert!(SongMetadata::parse(r#"{"key": ""}"#).is_err());
+    }
+
+    #[test]
+    fn isrc_may_be_hyphenated() {
+        assert!(check_isrc("USRC17607839").is_ok());
+        assert!(check_isrc("US-RC1-76-07839").is_ok());
+        assert!(check_isrc("US-RC1-76-0783").is_err());
+        assert!(check_isrc("1SRC17607839").is_err());
+        assert!(check_isrc("USRC1760783X").is_err());
+        assert!(check_isrc("us-rc1-76-07839").is_err());
+        assert!(check_isrc("US-RC1-76-078390").is_err());
+    }
+
+    #[test]
+    fn song_isrc_must_be_valid_and_agree_with_metadata() {
+        let mut labeled = song(200, None);
+        assert!(labeled.check_isrc(&SongMetadata::default()).is_ok());
+
+        labeled.isrc = Some("US-RC1-76-07839".to_string());
+        assert!(labeled.check_isrc(&SongMetadata::default()).is_ok());
+        let same = SongMetadata {
+            isrc: Some("USRC17607839".to_string()),
+            ..Default::default()
+        };
+        assert!(labeled.check_isrc(&same).is_ok());
+        let other = SongMetadata {
+            isrc: Some("GBAYE0601498".to_string()),
+            ..Default::default()
+        };
+        assert!(labeled.check_isrc(&other).is_err());
+
+        labeled.isrc = Some("USRC176".to_string());
+        assert!(labeled.check_isrc(&SongMetadata::default()).is_err());
+    }
+
+    fn frozen(song: &Song, by: AgentPubKey, authority: FreezeAuthority) -> Song {
+        Song {
+            earnings_frozen: true,
+            earnings_freeze: Some(EarningsFreeze {
+                frozen_by: by,
+                authority,
+                reason: "Contested authorship".to_string(),
+                frozen_at: Timestamp::from_micros(0),
+            }),
+            ..song.clone()
+        }
+    }
+
+    #[test]
+    fn artist_or_moderator_can_freeze_earnings() {
+        let moderators = vec![agent(9)];
+        let open = song(200, None);
+        let by_artist = frozen(&open, agent(1), FreezeAuthority::Artist);
+        assert!(check_song_update(&open, &by_artist, &agent(1), &moderators).is_ok());
+        let by_moderator = frozen(&open, agent(9), FreezeAuthority::Moderator);
+        assert!(check_song_update(&open, &by_moderator, &agent(9), &moderators).is_ok());
+
+        // Strangers can't, and a freeze must name its author and a reason
+        let by_stranger = frozen(&open, agent(5), FreezeAuthority::Moderator);
+        assert!(check_song_update(&open, &by_stranger, &agent(5), &moderators).is_err());
+        let impersonated = frozen(&open, agent(9), FreezeAuthority::Moderator);
+        assert!(check_song_update(&open, &impersonated, &agent(1), &moderators).is_err());
+        let mut unexplained = by_artist.clone();
+        unexplained.earnings_freeze.as_mut().unwrap().reason = " ".to_string();
+        assert!(check_song_update(&open, &unexplained, &agent(1), &moderators).is_err());
+        let mut unrecorded = open.clone();
+        unrecorded.earnings_frozen = true;
+        assert!(check_song_update(&open, &unrecorded, &agent(1), &moderators).is_err());
+    }
+
+    #[test]
+    fn unfreezing_needs_the_same_authority() {
+        let moderators = vec![agent(9)];
+        let open = song(200, None);
+        let held = frozen(&open, agent(9), FreezeAuthority::Moderator);
+        assert!(check_song_update(&held, &open, &agent(1), &moderators).is_err());
+        assert!(check_song_update(&held, &open, &agent(9), &moderators).is_ok());
+        // Any moderator, not only the one who froze it
+        let more = vec![agent(9), agent(8)];
+        assert!(check_song_update(&held, &open, &agent(8), &more).is_ok());
+
+        let held_by_artist = frozen(&open, agent(1), FreezeAuthority::Artist);
+        assert!(check_song_update(&held_by_artist, &open, &agent(9), &moderators).is_err());
+        assert!(check_song_update(&held_by_artist, &open, &agent(1), &moderators).is_ok());
+    }
+
+    #[test]
+    fn moderators_only_change_the_freeze() {
+        let moderators = vec![agent(9)];
+        let open = song(200, None);
+        let mut retitled = frozen(&open, agent(9), FreezeAuthority::Moderator);
+        retitled.title = "Mine now".to_string();
+        assert!(check_song_update(&open, &retitled, &agent(9), &moderators).is_err());
+
+        // The artist can keep editing while a moderator holds the earnings
+        let held = frozen(&open, agent(9), FreezeAuthority::Moderator);
+        let mut edited = held.clone();
+        edited.title = "Interlude (remastered)".to_string();
+        assert!(check_song_update(&held, &edited, &agent(1), &moderators).is_ok());
+    }
+
+    #[test]
+    fn payment_address_claims_must_be_signed_by_the_address() {
+        use k256::ecdsa::SigningKey;
+
+        // Private key 1's well-known address
+        let address = "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf";
+        let key = SigningKey::from_slice(&[[0u8; 31].as_slice(), &[1]].concat()).unwrap();
+        let sign = |message: &str| {
+            let digest = eip191_digest(message);
+            let (signature, recovery_id) = key.sign_prehash_recoverable(&digest).unwrap();
+            let mut bytes = signature.to_bytes().to_vec();
+            bytes.push(27 + recovery_id.to_byte());
+            bytes
+        };
+
+        let claim = PaymentAddressClaim {
+            address: address.to_string(),
+            signature: sign(&payment_address_claim_message(address, &agent(1))),
+        };
+        assert!(check_payment_address_claim(&claim, &agent(1)).is_ok());
+
+        // The signature binds the agent
+        assert!(check_payment_address_claim(&claim, &agent(2)).is_err());
+
+        // And another wallet can't claim the address
+        let forged = PaymentAddressClaim {
+            address: "0x0000000000000000000000000000000000000001".to_string(),
+            ..claim.clone()
+        };
+        assert!(check_payment_address_claim(&forged, &agent(1)).is_err());
+
+        let truncated = PaymentAddressClaim {
+            signature: claim.signature[..64].to_vec(),
+            ..claim
+        };
+        assert!(check_payment_address_claim(&truncated, &agent(1)).is_err());
+    }
+
+    #[test]
+    fn isrc_index_ignores_hyphens() {
+        assert_eq!(normalize_isrc("US-RC1-76-07839"), "USRC17607839");
+        assert_eq!(normalize_isrc("USRC17607839"), "USRC17607839");
+    }
+
+    #[test]
+    fn deployments_can_require_metadata_fields() {
+        let mut tagged = song(200, None);
+        tagged.metadata = r#"{"isrc": "USRC17607839"}"#.to_string();
+        assert!(CatalogProperties::default().check_song_metadata(&tagged).is_ok());
+
+        let label = CatalogProperties {
+            required_song_metadata: vec!["isrc".to_string(), "explicit".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            label.check_song_metadata(&tagged),
+            Err("Song metadata must set explicit".to_string())
+        );
+        tagged.metadata = r#"{"isrc": "USRC17607839", "explicit": true}"#.to_string();
+        assert!(label.check_song_metadata(&tagged).is_ok());
+    }
+
+    #[test]
+    fn updates_only_check_metadata_they_change() {
+        // Stored before the deployment required any fields; untouched it
+        // isn't re-checked, even if it wouldn't parse today
+        let mut stored = song(200, None);
+        stored.metadata = "legacy".to_string();
+        let mut retitled = stored.clone();
+        retitled.title = "Interlude (remastered)".to_string();
+        assert!(retitled.check_metadata_change(&stored).is_ok());
+
+        // Changed metadata must parse, but required fields aren't enforced
+        retitled.metadata = r#"{"bpm": 120}"#.to_string();
+        assert!(retitled.check_metadata_change(&stored).is_ok());
+        retitled.metadata = "{".to_string();
+        assert!(retitled.check_metadata_change(&stored).is_err());
+
+        retitled.isrc = Some("USRC17607839".to_string());
+        retitled.metadata = r#"{"isrc": "GBAYE0000351"}"#.to_string();
+        assert!(retitled.check_metadata_change(&stored).is_err());
+    }
+
+    #[test]
+    fn songs_must_fit_the_size_limits() {
+        let limits = CatalogProperties::default();
+        let mut long = song(6 * 60 * 60, None);
+        assert!(limits.check_song_limits(&long).is_ok());
+        long.duration_seconds += 1;
+        assert!(limits.check_song_limits(&long).is_err());
+
+        let mut tagged = song(200, None);
+        tagged.metadata = "x".repeat(16 * 1024);
+        assert!(limits.check_song_limits(&tagged).is_ok());
+        tagged.metadata.push('x');
+        assert!(limits.check_song_limits(&tagged).is_err());
+
+        let mut genres = song(200, None);
+        genres.genres = (0..10).map(|i| format!("genre-{}", i)).collect();
+        assert!(limits.check_song_limits(&genres).is_ok());
+        genres.genres.push("one-more".to_string());
+        assert!(limits.check_song_limits(&genres).is_err());
+
+        // Deployments can tighten them
+        let strict = CatalogProperties {
+            max_song_genres: 3,
+            ..Default::default()
+        };
+        genres.genres.truncate(4);
+        assert!(strict.check_song_limits(&genres).is_err());
+    }
+
+    #[test]
+    fn updates_only_check_the_limits_they_change() {
+        let strict = CatalogProperties {
+            max_song_genres: 3,
+            ..Default::default()
+        };
+        let mut stored = song(200, None);
+        stored.genres = (0..5).map(|i| format!("genre-{}", i)).collect();
+
+        // Created under looser limits, it can still be retitled
+        let mut retitled = stored.clone();
+        retitled.title = "Interlude (remastered)".to_string();
+        assert!(strict.check_song_limit_changes(&stored, &retitled).is_ok());
+
+        // But changed genres must fit
+        retitled.genres.pop();
+        assert!(strict.check_song_limit_changes(&stored, &retitled).is_err());
+        retitled.genres.truncate(3);
+        assert!(strict.check_song_limit_changes(&stored, &retitled).is_ok());
+    }
+
+    #[test]
+    fn malformed_properties_are_an_error() {
+        // No properties in the manifest: the defaults
+        let unset = SerializedBytes::try_from(()).unwrap();
+        assert_eq!(parse_catalog_properties(unset), Ok(CatalogProperties::default()));
+
+        let strict = CatalogProperties {
+            strict_isrc: true,
+            ..Default::default()
+        };
+        let set = SerializedBytes::try_from(strict.clone()).unwrap();
+        assert_eq!(parse_catalog_properties(set), Ok(strict));
+
+        // A mistyped value isn't quietly replaced by the defaults
+        #[derive(Serialize, Deserialize, SerializedBytes, Debug)]
+        struct Typo {
+            strict_isrc: String,
+        }
+        let typo = SerializedBytes::try_from(Typo {
+            strict_isrc: "yes".to_string(),
+        })
+        .unwrap();
+        assert!(parse_catalog_properties(typo).is_err());
+    }
 }
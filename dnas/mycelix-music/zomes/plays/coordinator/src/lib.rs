@@ -10,33 +10,137 @@
 //!
 //! Result: Artists get paid for EVERY play, listeners pay near-zero fees
 
+use balances_integrity::{ListenerAccount, TransferReason};
 use hdk::prelude::*;
+use pagination::{page_limit, paginate_links, PageCursor, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
 use plays_integrity::*;
+use std::collections::{BTreeMap, HashSet};
+
+/// Length of a play-signal aggregation window (microseconds)
+const SIGNAL_WINDOW_MICROS: i64 = 60 * 1_000_000;
+
+/// How long a now-playing heartbeat counts as live (microseconds)
+const PRESENCE_TTL_MICROS: i64 = 90 * 1_000_000;
+
+/// How far ahead of my clock a heartbeat may be and still count, so a
+/// listener can't stay live by dating their presence in the future
+const PRESENCE_MAX_SKEW_MICROS: i64 = 30 * 1_000_000;
+
+/// Grant unrestricted access to recv_remote_signal so listeners can
+/// notify artists of paying plays
+///
+/// Fails if the DNA properties are malformed.
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    if let Err(reason) = parse_plays_properties(dna_info()?.modifiers.properties) {
+        error!("{}", reason);
+        return Ok(InitCallbackResult::Fail(reason));
+    }
+
+    let mut functions = std::collections::BTreeSet::new();
+    functions.insert((zome_info()?.name, "recv_remote_signal".into()));
+    create_cap_grant(CapGrantEntry {
+        tag: "play_signals".into(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+    Ok(InitCallbackResult::Pass)
+}
 
 /// Record a song play - THIS IS FREE (just writes to local source chain)
+///
+/// The artist, strategy, length and threshold are the song's own, read
+/// from its latest version; only how long I listened comes from the input.
 #[hdk_extern]
-pub fn record_play(input: RecordPlayInput) -> ExternResult<ActionHash> {
+pub fn record_play(input: RecordPlayInput) -> ExternResult<RecordPlayOutput> {
     let my_agent = agent_info()?.agent_initial_pubkey;
 
+    let (song_version, song) = latest_song_version(input.song_hash.clone())?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Song not found".to_string())))?;
+    catalog_integrity::catalog_properties()?
+        .check_strategy(&song.strategy_id)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
     // Calculate amount owed based on strategy
-    let amount_owed = calculate_play_amount(&input.strategy_id, input.duration_listened, input.song_duration);
+    let properties = plays_properties()?;
+    let config = properties.strategy_config(&song.strategy_id);
+    let min_play_override = song.min_play_override;
+    let song_duration = song.duration_seconds;
+    let self_play = song.artist == my_agent;
+    let played_at = sys_time()?;
+    let repeat_listen = config.repeat_listen_bonus_bps.is_some()
+        && is_full_listen(input.duration_listened, song_duration)
+        && has_full_listen(&input.song_hash, played_at)?;
+    let nanowei = match self_play && properties.self_plays == SelfPlayPolicy::Unpaid {
+        true => 0,
+        false => play_amount_nanowei(
+            properties.base_rate_wei,
+            &song.strategy_id,
+            input.duration_listened,
+            song_duration,
+            min_play_override,
+            completion_bonus_bps(
+                &config,
+                input.duration_listened,
+                song_duration,
+                min_play_override,
+                repeat_listen,
+            ),
+        ),
+    };
+    // Fractions of a wei left over from my last play of this artist
+    let carries = properties.earnings_rounding == EarningsRounding::AccumulateRemainder
+        && nanowei > 0;
+    let carried = match carries {
+        true => earnings_remainder(&my_agent, &song.artist)?,
+        false => 0,
+    };
+    let (amount_owed, remainder) = properties.earnings_rounding.charge(nanowei, carried);
 
     let play = PlayRecord {
+        schema_version: PLAY_RECORD_SCHEMA_VERSION,
         song_hash: input.song_hash.clone(),
-        artist: input.artist.clone(),
-        played_at: sys_time()?,
+        artist: song.artist,
+        played_at,
         duration_listened: input.duration_listened,
-        song_duration: input.song_duration,
-        strategy_id: input.strategy_id,
+        song_duration,
+        min_play_override,
+        song_version: Some(song_version),
+        strategy_id: song.strategy_id,
         amount_owed,
         settled: false,
         settlement_hash: None,
     };
 
-    let action_hash = create_entry(&EntryTypes::PlayRecord(play))?;
+    // Fail with validation's reason rather than a rejected commit
+    check_self_play(&play, self_play, properties.self_plays)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    // Bootstrap the owed counter before this play lands on the chain so
+    // the one-off full walk can't count it twice
+    let mut owed = if amount_owed > 0 {
+        Some(current_owed_balance()?)
+    } else {
+        None
+    };
+
+    let action_hash = create_entry(&EntryTypes::PlayRecord(play.clone()))?;
+
+    if carries && remainder != carried {
+        save_earnings_remainder(&my_agent, &play.artist, remainder)?;
+    }
+
+    if let Some(owed) = owed.as_mut() {
+        let before = owed.total_amount;
+        owed.add_play(&play.artist, amount_owed);
+        // Live earnings ticker for the artist (paying plays only)
+        notify_artist(&play, owed)?;
+        save_owed_balance(owed)?;
+        nudge_settlement(owed, before, properties.settlement_nudge_wei)?;
+    }
 
     // Link from listener to their plays
-    let listener_path = Path::from(format!("listener_plays/{}", my_agent));
+    let listener_path = listener_plays_path(&my_agent);
     listener_path.ensure()?;
     create_link(
         listener_path.path_entry_hash()?,
@@ -47,312 +151,2683 @@ pub fn record_play(input: RecordPlayInput) -> ExternResult<ActionHash> {
 
     // Link from song to plays (for artist analytics)
     create_link(
-        input.song_hash,
+        input.song_hash.clone(),
         action_hash.clone(),
         LinkTypes::SongToPlays,
         (),
     )?;
 
-    Ok(action_hash)
+    // Listening history feeds my listener reputation; playing my own
+    // songs doesn't count
+    if play.artist != my_agent {
+        record_listener_play(&action_hash)?;
+    }
+
+    // Gift-economy plays earn the listener CGC instead of costing anything
+    let reward = if play.strategy_id == GIFT_STRATEGY_ID
+        && properties.gift_reward_cgc > 0
+        && is_qualifying_play(play.duration_listened, play.song_duration, play.min_play_override)
+    {
+        Some(accrue_reward(my_agent.clone(), action_hash.clone(), &play, properties.gift_reward_cgc)?)
+    } else {
+        None
+    };
+
+    // Time-barter plays are paid in TEND straight away
+    let tend_charged = if play.strategy_id == TIME_BARTER_STRATEGY_ID && play.artist != my_agent {
+        let amount = tend_play_amount(
+            play.duration_listened,
+            play.song_duration,
+            play.min_play_override,
+            properties.tend_per_minute,
+        );
+        if amount > 0 {
+            pay_tend(&play.artist, amount, &action_hash)?;
+        }
+        amount
+    } else {
+        0
+    };
+
+    // Optionally sign a portable receipt for this play
+    let receipt = if input.with_receipt {
+        Some(create_play_receipt(my_agent, action_hash.clone(), &play)?)
+    } else {
+        None
+    };
+
+    Ok(RecordPlayOutput {
+        play_hash: action_hash,
+        receipt,
+        reward,
+        tend_charged,
+    })
 }
 
+/// Mirror of `balances::ExecuteTendTransferInput`
 #[derive(Serialize, Deserialize, Debug)]
-pub struct RecordPlayInput {
-    pub song_hash: ActionHash,
-    pub artist: AgentPubKey,
-    pub duration_listened: u32,
-    pub song_duration: u32,
-    pub strategy_id: String,
+struct ExecuteTendTransferInput {
+    to: AgentPubKey,
+    amount: u64,
+    reference: Option<ActionHash>,
 }
 
-/// Calculate payment amount based on strategy
-fn calculate_play_amount(strategy_id: &str, duration_listened: u32, song_duration: u32) -> u64 {
-    // Base rate: 0.001 USD per full play (in wei: ~400000000000000 at $0.40/xDAI)
-    let base_rate: u64 = 400_000_000_000_000; // 0.0004 xDAI
-
-    // Calculate completion percentage
-    let completion = if song_duration > 0 {
-        (duration_listened as f64 / song_duration as f64).min(1.0)
-    } else {
-        0.0
+/// Debit my TEND account for a time-barter play via the balances zome
+fn pay_tend(artist: &AgentPubKey, amount: u64, play_hash: &ActionHash) -> ExternResult<()> {
+    let input = ExecuteTendTransferInput {
+        to: artist.clone(),
+        amount,
+        reference: Some(play_hash.clone()),
     };
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("balances"),
+        FunctionName::from("execute_tend_transfer"),
+        None,
+        input,
+    )? {
+        ZomeCallResponse::Ok(_) => Ok(()),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Failed to pay TEND: {:?}",
+            other
+        )))),
+    }
+}
 
-    // Only count plays over 30 seconds or 50% completion
-    if duration_listened < 30 && completion < 0.5 {
-        return 0;
+/// Fold a play into my listener reputation via the trust zome
+fn record_listener_play(play_hash: &ActionHash) -> ExternResult<()> {
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("trust"),
+        FunctionName::from("record_listener_play"),
+        None,
+        play_hash.clone(),
+    )? {
+        ZomeCallResponse::Ok(_) => Ok(()),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Failed to update listener reputation: {:?}",
+            other
+        )))),
     }
+}
 
-    // Apply strategy multiplier
-    let multiplier = match strategy_id {
-        "premium" => 2.0,
-        "patronage" => 1.5,
-        "gift" => 0.0, // Gift economy = free
-        "pay_per_stream" => 1.0,
-        _ => 1.0,
-    };
+/// Real-time play notification sent to the artist
+///
+/// Play signals never include the listener's identity; only `NowPlaying`,
+/// which the listener chose to broadcast, does.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum PlaySignal {
+    /// A single paying play
+    Play {
+        song_hash: ActionHash,
+        amount_owed: u64,
+        played_at: Timestamp,
+    },
+    /// Paying plays accrued since this listener's previous signal
+    Aggregate {
+        play_count: u64,
+        amount_owed: u64,
+        window_start: Timestamp,
+        window_end: Timestamp,
+    },
+    /// Ephemeral presence heartbeat: `listener` is playing `song_hash`
+    NowPlaying {
+        song_hash: ActionHash,
+        listener: AgentPubKey,
+        heartbeat_at: Timestamp,
+    },
+}
 
-    ((base_rate as f64) * completion * multiplier) as u64
+/// Signal to the listener's own client
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ListenerSignal {
+    /// What I owe reached `settlement_nudge_wei`; settle before it grows
+    SettlementDue {
+        total_owed: u64,
+        threshold: u64,
+        /// Zome function that settles everything owed
+        suggested_action: String,
+    },
 }
 
-/// Get my unsettled plays
+/// Prompt me to settle when what I owe has just reached the threshold
+fn nudge_settlement(owed: &OwedBalance, before: u64, threshold: u64) -> ExternResult<()> {
+    if owed.crossed_threshold(before, threshold) {
+        emit_signal(ListenerSignal::SettlementDue {
+            total_owed: owed.total_amount,
+            threshold,
+            suggested_action: "settle_all_debts".to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Forward play signals from listeners to the artist's client
+///
+/// Signals sent by my own agent are dropped, as are `Play` signals for
+/// songs that aren't mine and empty `Aggregate` ones. A `NowPlaying`
+/// heartbeat is dropped unless its sender is the listener it names; play
+/// and heartbeat times are clamped to my clock.
 #[hdk_extern]
-pub fn get_my_unsettled_plays(_: ()) -> ExternResult<Vec<PlayRecord>> {
+pub fn recv_remote_signal(signal: PlaySignal) -> ExternResult<()> {
+    let sender = call_info()?.provenance;
+    let me = agent_info()?.agent_initial_pubkey;
+    if sender == me {
+        return Ok(());
+    }
+    let now = sys_time()?;
+    let signal = match signal {
+        PlaySignal::Play {
+            song_hash,
+            amount_owed,
+            played_at,
+        } => {
+            if catalog_song(&song_hash)?.map(|song| song.artist) != Some(me) {
+                return Ok(());
+            }
+            PlaySignal::Play {
+                song_hash,
+                amount_owed,
+                played_at: played_at.min(now),
+            }
+        }
+        PlaySignal::Aggregate {
+            play_count,
+            window_start,
+            window_end,
+            ..
+        } if play_count == 0 || window_start > window_end => {
+            return Ok(());
+        }
+        PlaySignal::NowPlaying {
+            song_hash,
+            listener,
+            heartbeat_at,
+        } => {
+            if listener != sender {
+                return Ok(());
+            }
+            PlaySignal::NowPlaying {
+                song_hash,
+                listener,
+                heartbeat_at: heartbeat_at.min(now),
+            }
+        }
+        other => other,
+    };
+    emit_signal(signal)
+}
+
+/// Set how I (as an artist) receive play signals
+#[hdk_extern]
+pub fn set_play_signal_mode(mode: PlaySignalMode) -> ExternResult<ActionHash> {
     let my_agent = agent_info()?.agent_initial_pubkey;
-    let listener_path = Path::from(format!("listener_plays/{}", my_agent));
+    let preference = PlaySignalPreference {
+        artist: my_agent.clone(),
+        mode,
+    };
+
+    let action_hash = create_entry(&EntryTypes::PlaySignalPreference(preference))?;
+
+    let prefs_path = Path::from(format!("signal_prefs/{}", my_agent));
+    prefs_path.ensure()?;
+    create_link(
+        prefs_path.path_entry_hash()?,
+        action_hash.clone(),
+        LinkTypes::ArtistToSignalPreference,
+        (),
+    )?;
+
+    Ok(action_hash)
+}
 
+/// An artist's play-signal mode (PerPlay if never set)
+fn get_play_signal_mode(artist: &AgentPubKey) -> ExternResult<PlaySignalMode> {
+    let prefs_path = Path::from(format!("signal_prefs/{}", artist));
     let links = get_links(
-        GetLinksInputBuilder::try_new(listener_path.path_entry_hash()?, LinkTypes::ListenerToPlays)?
-            .build(),
+        GetLinksInputBuilder::try_new(
+            prefs_path.path_entry_hash()?,
+            LinkTypes::ArtistToSignalPreference,
+        )?
+        .build(),
     )?;
 
-    let mut unsettled = Vec::new();
-    for link in links {
+    if let Some(link) = links.into_iter().max_by_key(|l| l.timestamp) {
         if let Some(action_hash) = link.target.into_action_hash() {
             if let Some(record) = get(action_hash, GetOptions::default())? {
-                if let Some(play) = record
+                if let Some(preference) = record
                     .entry()
-                    .to_app_option::<PlayRecord>()
+                    .to_app_option::<PlaySignalPreference>()
                     .map_err(|e| wasm_error!(e))?
                 {
-                    if !play.settled {
-                        unsettled.push(play);
-                    }
+                    return Ok(preference.mode);
                 }
             }
         }
     }
 
-    Ok(unsettled)
+    Ok(PlaySignalMode::PerPlay)
 }
 
-/// Get total amount I owe (unsettled plays)
-#[hdk_extern]
-pub fn get_my_balance_owed(_: ()) -> ExternResult<BalanceOwed> {
-    let plays = get_my_unsettled_plays(())?;
-
-    let mut total_amount: u64 = 0;
-    let mut play_count: u64 = 0;
-    let mut by_artist: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
+/// Signal the artist about a paying play, honoring their preference
+///
+/// Per-minute counts are kept on my owed counter, which the caller saves.
+fn notify_artist(play: &PlayRecord, owed: &mut OwedBalance) -> ExternResult<()> {
+    let signal = match get_play_signal_mode(&play.artist)? {
+        PlaySignalMode::PerPlay => Some(PlaySignal::Play {
+            song_hash: play.song_hash.clone(),
+            amount_owed: play.amount_owed,
+            played_at: play.played_at,
+        }),
+        PlaySignalMode::PerMinute => owed
+            .signal_play(&play.artist, play.amount_owed, play.played_at, SIGNAL_WINDOW_MICROS)
+            .and_then(aggregate_signal),
+    };
 
-    for play in plays {
-        total_amount += play.amount_owed;
-        play_count += 1;
-        *by_artist.entry(play.artist.to_string()).or_insert(0) += play.amount_owed;
+    if let Some(signal) = signal {
+        send_play_signal(&play.artist, signal);
     }
 
-    Ok(BalanceOwed {
-        total_amount,
-        play_count,
-        by_artist: by_artist.into_iter().collect(),
+    Ok(())
+}
+
+/// Aggregate signal for PerMinute artists, reporting plays counted by
+/// `OwedBalance::signal_play` or flushed
+fn aggregate_signal(plays: UnsignalledPlays) -> Option<PlaySignal> {
+    Some(PlaySignal::Aggregate {
+        play_count: plays.play_count,
+        amount_owed: plays.amount,
+        window_start: plays.first_played_at?,
+        window_end: plays.last_played_at?,
     })
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct BalanceOwed {
-    pub total_amount: u64,
-    pub play_count: u64,
-    pub by_artist: Vec<(String, u64)>,
+fn send_play_signal(artist: &AgentPubKey, signal: PlaySignal) {
+    // Best effort: an offline artist must not block the play
+    let _ = send_remote_signal(signal, vec![artist.clone()]);
 }
 
-/// Create a settlement batch for an artist
+/// Report plays still waiting for a per-minute signal to their artists
+///
+/// Plays after the first in a window are reported with the next window's
+/// first play, or when they're settled. Clients call this periodically
+/// (e.g. every minute) so the end of a session isn't held back until then.
+/// Returns how many artists were signalled.
 #[hdk_extern]
-pub fn create_settlement_batch(artist: AgentPubKey) -> ExternResult<ActionHash> {
-    let plays = get_my_unsettled_plays(())?;
-
-    // Filter plays for this artist
-    let artist_plays: Vec<PlayRecord> = plays
-        .into_iter()
-        .filter(|p| p.artist == artist)
+pub fn flush_play_signals(_: ()) -> ExternResult<u32> {
+    let now = sys_time()?;
+    let mut owed = current_owed_balance()?;
+    let artists: Vec<AgentPubKey> = owed
+        .by_artist
+        .iter()
+        .filter(|a| a.unsignalled.play_count > 0)
+        .map(|a| a.artist.clone())
         .collect();
+    if artists.is_empty() {
+        return Ok(0);
+    }
 
-    if artist_plays.is_empty() {
-        return Err(wasm_error!(WasmErrorInner::Guest(
-            "No unsettled plays for this artist".to_string()
-        )));
+    for artist in &artists {
+        if let Some(signal) = owed.flush_signal(artist, now).and_then(aggregate_signal) {
+            send_play_signal(artist, signal);
+        }
     }
+    save_owed_balance(&mut owed)?;
+    Ok(artists.len() as u32)
+}
 
-    // Calculate totals
-    let play_count = artist_plays.len() as u64;
-    let total_amount: u64 = artist_plays.iter().map(|p| p.amount_owed).sum();
+fn presence_links(song_hash: &ActionHash) -> ExternResult<Vec<Link>> {
+    get_links(
+        GetLinksInputBuilder::try_new(song_hash.clone(), LinkTypes::SongToListeners)?.build(),
+    )
+}
 
-    // Collect play hashes (we need to get them from links)
-    let my_agent = agent_info()?.agent_initial_pubkey;
-    let listener_path = Path::from(format!("listener_plays/{}", my_agent));
-    let links = get_links(
-        GetLinksInputBuilder::try_new(listener_path.path_entry_hash()?, LinkTypes::ListenerToPlays)?
-            .build(),
-    )?;
+/// Whether a presence link made at `at` is live at `now`
+fn presence_is_live(at: Timestamp, now: Timestamp) -> bool {
+    at.as_micros() > now.as_micros() - PRESENCE_TTL_MICROS
+        && at.as_micros() <= now.as_micros() + PRESENCE_MAX_SKEW_MICROS
+}
 
-    let mut play_hashes = Vec::new();
-    for link in links {
-        if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
-                if let Some(play) = record
-                    .entry()
-                    .to_app_option::<PlayRecord>()
-                    .map_err(|e| wasm_error!(e))?
-                {
-                    if !play.settled && play.artist == artist {
-                        play_hashes.push(action_hash);
-                    }
-                }
-            }
-        }
-    }
+/// Listeners with a live presence link on a song
+fn live_listeners(song_hash: &ActionHash, now: Timestamp) -> ExternResult<Vec<AgentPubKey>> {
+    let mut listeners: Vec<AgentPubKey> = presence_links(song_hash)?
+        .into_iter()
+        .filter(|link| presence_is_live(link.timestamp, now))
+        .filter_map(|link| link.target.into_agent_pub_key())
+        .collect();
+    listeners.sort();
+    listeners.dedup();
+    Ok(listeners)
+}
 
-    // Create merkle root (simplified - just hash all play hashes together)
-    let merkle_root = compute_merkle_root(&play_hashes);
+/// Announce that I'm playing a song
+///
+/// Replaces my presence link on the song, which counts as live for 90
+/// seconds, and sends a `NowPlaying` signal to the artist, their followers
+/// and the song's other live listeners. Clients should repeat it while
+/// playback continues.
+#[hdk_extern]
+pub fn broadcast_now_playing(song_hash: ActionHash) -> ExternResult<()> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?;
 
-    let batch = SettlementBatch {
-        artist: artist.clone(),
-        play_count,
-        total_amount,
-        play_hashes: play_hashes.clone(),
-        merkle_root,
-        created_at: sys_time()?,
-        status: SettlementStatus::Pending,
-        tx_hash: None,
-    };
+    let record = get(song_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest("Song not found".to_string())
+    ))?;
+    let song: catalog_integrity::Song = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Record is not a song".to_string()
+        )))?;
 
-    let batch_hash = create_entry(&EntryTypes::SettlementBatch(batch))?;
+    let mut recipients = vec![song.artist.clone()];
+    recipients.extend(get_followers(&song.artist)?);
+    recipients.extend(live_listeners(&song_hash, now)?);
+    recipients.sort();
+    recipients.dedup();
+    recipients.retain(|agent| *agent != my_agent);
 
-    // Link batch to artist
-    let artist_settlements_path = Path::from(format!("settlements/{}", artist));
-    artist_settlements_path.ensure()?;
+    // One presence link per listener and song: drop my previous heartbeat
+    for link in presence_links(&song_hash)? {
+        if link.author == my_agent {
+            delete_link(link.create_link_hash)?;
+        }
+    }
     create_link(
-        artist_settlements_path.path_entry_hash()?,
-        batch_hash.clone(),
-        LinkTypes::ArtistToSettlements,
+        song_hash.clone(),
+        my_agent.clone(),
+        LinkTypes::SongToListeners,
         (),
     )?;
 
-    // Link plays to settlement
-    for play_hash in play_hashes {
-        create_link(
-            play_hash,
-            batch_hash.clone(),
-            LinkTypes::PlayToSettlement,
-            (),
-        )?;
-    }
+    let signal = PlaySignal::NowPlaying {
+        song_hash,
+        listener: my_agent,
+        heartbeat_at: now,
+    };
+    // Best effort: offline recipients just miss this heartbeat
+    let _ = send_remote_signal(signal, recipients);
+
+    Ok(())
+}
 
-    Ok(batch_hash)
+/// Count of listeners playing a song right now: those whose presence link
+/// is under 90 seconds old
+#[hdk_extern]
+pub fn get_live_listeners(song_hash: ActionHash) -> ExternResult<u32> {
+    Ok(live_listeners(&song_hash, sys_time()?)?.len() as u32)
 }
 
-/// Compute a simple merkle root from action hashes
-fn compute_merkle_root(hashes: &[ActionHash]) -> Vec<u8> {
-    use hdk::prelude::hash_keccak256;
+/// The part of a catalog `Song` the plays zome needs
+#[derive(Serialize, Deserialize, Debug)]
+struct CatalogSong {
+    artist: AgentPubKey,
+    #[serde(default)]
+    duration_seconds: u32,
+    #[serde(default)]
+    strategy_id: String,
+    #[serde(default)]
+    min_play_override: Option<u32>,
+}
 
-    if hashes.is_empty() {
-        return vec![0u8; 32];
+/// A song via the catalog zome
+fn catalog_song(song_hash: &ActionHash) -> ExternResult<Option<CatalogSong>> {
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("catalog"),
+        FunctionName::from("get_song"),
+        None,
+        song_hash.clone(),
+    )? {
+        ZomeCallResponse::Ok(result) => result.decode().map_err(|e| wasm_error!(e)),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Failed to get song {}: {:?}",
+            song_hash, other
+        )))),
     }
+}
 
-    let mut current: Vec<Vec<u8>> = hashes
-        .iter()
-        .map(|h| h.get_raw_39().to_vec())
-        .collect();
-
-    while current.len() > 1 {
-        let mut next = Vec::new();
-        for chunk in current.chunks(2) {
-            let combined = if chunk.len() == 2 {
-                [chunk[0].as_slice(), chunk[1].as_slice()].concat()
-            } else {
-                [chunk[0].as_slice(), chunk[0].as_slice()].concat()
-            };
-            next.push(hash_keccak256(combined).expect("hash").to_vec());
+/// An artist's followers via the catalog zome; empty if unavailable
+fn get_followers(artist: &AgentPubKey) -> ExternResult<Vec<AgentPubKey>> {
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("catalog"),
+        FunctionName::from("get_followers"),
+        None,
+        artist.clone(),
+    )? {
+        ZomeCallResponse::Ok(result) => result.decode().map_err(|e| wasm_error!(e)),
+        other => {
+            debug!("Failed to get followers for {}: {:?}", artist, other);
+            Ok(Vec::new())
         }
-        current = next;
     }
+}
 
-    current.into_iter().next().unwrap_or_else(|| vec![0u8; 32])
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecordPlayInput {
+    pub song_hash: ActionHash,
+    pub duration_listened: u32,
+    /// Also produce a signed PlayAttestation
+    #[serde(default)]
+    pub with_receipt: bool,
 }
 
-/// Get pending settlements for an artist
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecordPlayOutput {
+    pub play_hash: ActionHash,
+    pub receipt: Option<PlayAttestation>,
+    /// CGC accrued for this play (gift economy only)
+    pub reward: Option<RewardAccrual>,
+    /// TEND paid for this play (time barter only)
+    pub tend_charged: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AmendPlayInput {
+    /// The play's original create, as returned by `record_play`
+    pub play_hash: ActionHash,
+    pub duration_listened: u32,
+}
+
+/// Correct how much of one of my plays was listened
+///
+/// Allowed until `amend_grace_secs` after the play was recorded, before it
+/// becomes settleable. The amount is re-priced for the new duration the
+/// way `record_play` prices it, except that no remainder is carried in or
+/// out: under `AccumulateRemainder` the amended amount is floored. Returns
+/// the amended play.
 #[hdk_extern]
-pub fn get_pending_settlements(artist: AgentPubKey) -> ExternResult<Vec<SettlementBatch>> {
-    let artist_settlements_path = Path::from(format!("settlements/{}", artist));
-    let links = get_links(
-        GetLinksInputBuilder::try_new(
-            artist_settlements_path.path_entry_hash()?,
-            LinkTypes::ArtistToSettlements,
-        )?
-        .build(),
-    )?;
+pub fn amend_play(input: AmendPlayInput) -> ExternResult<PlayRecord> {
+    let (version, play) = latest_play_version(input.play_hash)?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Play not found".to_string())))?;
 
-    let mut pending = Vec::new();
-    for link in links {
-        if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(record) = get(action_hash, GetOptions::default())? {
-                if let Some(batch) = record
-                    .entry()
-                    .to_app_option::<SettlementBatch>()
-                    .map_err(|e| wasm_error!(e))?
-                {
-                    if batch.status == SettlementStatus::Pending {
-                        pending.push(batch);
-                    }
-                }
-            }
+    let properties = plays_properties()?;
+    let config = properties.strategy_config(&play.strategy_id);
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let self_play = catalog_song(&play.song_hash)?.is_some_and(|song| song.artist == my_agent);
+    let repeat_listen = config.repeat_listen_bonus_bps.is_some()
+        && is_full_listen(input.duration_listened, play.song_duration)
+        && has_full_listen(&play.song_hash, play.played_at)?;
+    let amount_owed = match self_play && properties.self_plays == SelfPlayPolicy::Unpaid {
+        true => 0,
+        false => {
+            let nanowei = play_amount_nanowei(
+                properties.base_rate_wei,
+                &play.strategy_id,
+                input.duration_listened,
+                play.song_duration,
+                play.min_play_override,
+                completion_bonus_bps(
+                    &config,
+                    input.duration_listened,
+                    play.song_duration,
+                    play.min_play_override,
+                    repeat_listen,
+                ),
+            );
+            properties.earnings_rounding.charge(nanowei, 0).0
         }
+    };
+    let amended = PlayRecord {
+        duration_listened: input.duration_listened,
+        amount_owed,
+        ..play.clone()
+    };
+
+    // Fail with validation's reason rather than a rejected commit
+    let elapsed_micros = sys_time()?.as_micros() - play.played_at.as_micros();
+    check_play_amendment(&play, &amended, elapsed_micros, properties.amend_grace_secs)
+        .and_then(|()| check_self_play(&amended, self_play, properties.self_plays))
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    // Bootstrap the owed counter before the amendment lands, as record_play does
+    let mut owed = if amount_owed != play.amount_owed {
+        Some(current_owed_balance()?)
+    } else {
+        None
+    };
+
+    update_entry(version, &EntryTypes::PlayRecord(amended.clone()))?;
+
+    if let Some(owed) = owed.as_mut() {
+        let before = owed.total_amount;
+        owed.amend_play(&play.artist, play.amount_owed, amount_owed);
+        save_owed_balance(owed)?;
+        nudge_settlement(owed, before, properties.settlement_nudge_wei)?;
     }
 
-    Ok(pending)
+    Ok(amended)
 }
 
-/// Get play statistics for a song (for artists)
+/// Record CGC earned for a qualifying gift-economy play
+fn accrue_reward(
+    listener: AgentPubKey,
+    play_hash: ActionHash,
+    play: &PlayRecord,
+    cgc_amount: u64,
+) -> ExternResult<RewardAccrual> {
+    let reward = RewardAccrual {
+        listener: listener.clone(),
+        song_hash: play.song_hash.clone(),
+        play_hash,
+        cgc_amount,
+        accrued_at: play.played_at,
+    };
+
+    let reward_hash = create_entry(&EntryTypes::RewardAccrual(reward.clone()))?;
+
+    let rewards_path = Path::from(format!("listener_rewards/{}", listener));
+    rewards_path.ensure()?;
+    create_link(
+        rewards_path.path_entry_hash()?,
+        reward_hash,
+        LinkTypes::ListenerToRewards,
+        (),
+    )?;
+
+    Ok(reward)
+}
+
+/// CGC accrued by a listener
 #[derive(Serialize, Deserialize, Debug)]
-pub struct SongStats {
-    pub total_plays: u64,
-    pub total_earnings: u64,
-    pub unique_listeners: u64,
-    pub avg_completion: f64,
+pub struct RewardSummary {
+    pub total_cgc: u64,
+    pub reward_count: u64,
 }
 
+/// Get my accrued CGC rewards
 #[hdk_extern]
-pub fn get_song_stats(song_hash: ActionHash) -> ExternResult<SongStats> {
+pub fn get_my_rewards(_: ()) -> ExternResult<RewardSummary> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let rewards_path = Path::from(format!("listener_rewards/{}", my_agent));
     let links = get_links(
-        GetLinksInputBuilder::try_new(song_hash, LinkTypes::SongToPlays)?.build(),
+        GetLinksInputBuilder::try_new(rewards_path.path_entry_hash()?, LinkTypes::ListenerToRewards)?
+            .build(),
     )?;
 
-    let mut total_plays: u64 = 0;
-    let mut total_earnings: u64 = 0;
-    let mut listeners: std::collections::HashSet<String> = std::collections::HashSet::new();
-    let mut total_completion: f64 = 0.0;
-
+    let mut summary = RewardSummary {
+        total_cgc: 0,
+        reward_count: 0,
+    };
     for link in links {
         if let Some(action_hash) = link.target.into_action_hash() {
             if let Some(record) = get(action_hash, GetOptions::default())? {
-                if let Some(play) = record
+                if let Some(reward) = record
                     .entry()
-                    .to_app_option::<PlayRecord>()
+                    .to_app_option::<RewardAccrual>()
                     .map_err(|e| wasm_error!(e))?
                 {
-                    total_plays += 1;
-                    total_earnings += play.amount_owed;
-
-                    // Get the author of this play record for unique listener count
-                    listeners.insert(record.action().author().to_string());
-
-                    if play.song_duration > 0 {
-                        total_completion +=
-                            play.duration_listened as f64 / play.song_duration as f64;
-                    }
+                    summary.total_cgc += reward.cgc_amount;
+                    summary.reward_count += 1;
                 }
             }
         }
     }
 
-    let avg_completion = if total_plays > 0 {
-        total_completion / total_plays as f64
-    } else {
-        0.0
+    Ok(summary)
+}
+
+/// Sign the canonical play payload and store it as an attestation
+fn create_play_receipt(
+    listener: AgentPubKey,
+    play_hash: ActionHash,
+    play: &PlayRecord,
+) -> ExternResult<PlayAttestation> {
+    let mut attestation = PlayAttestation {
+        play_hash,
+        song_hash: play.song_hash.clone(),
+        artist: play.artist.clone(),
+        amount_owed: play.amount_owed,
+        listener_signature: Vec::new(),
     };
 
-    Ok(SongStats {
-        total_plays,
-        total_earnings,
-        unique_listeners: listeners.len() as u64,
-        avg_completion,
-    })
+    let signature = sign(listener, attestation.payload())?;
+    attestation.listener_signature = signature.0.to_vec();
+
+    let attestation_hash = create_entry(&EntryTypes::PlayAttestation(attestation.clone()))?;
+
+    // Auditors list a song's receipts with get_attestations_for_song
+    create_link(
+        play.song_hash.clone(),
+        attestation_hash,
+        LinkTypes::SongToAttestations,
+        (),
+    )?;
+
+    Ok(attestation)
+}
+
+/// Whether `listener` signed the attestation's payload
+fn attestation_signed_by(
+    attestation: &PlayAttestation,
+    listener: AgentPubKey,
+) -> ExternResult<bool> {
+    match attestation.signature() {
+        Some(signature) => verify_signature(listener, signature, attestation.payload()),
+        None => Ok(false),
+    }
+}
+
+/// Verify a play receipt: signature is the listener's and the play exists
+#[hdk_extern]
+pub fn verify_play_receipt(attestation: PlayAttestation) -> ExternResult<bool> {
+    let record = match get(attestation.play_hash.clone(), GetOptions::default())? {
+        Some(record) => record,
+        None => return Ok(false),
+    };
+
+    let play = match read_play_record(&record) {
+        Ok(play) => play,
+        Err(_) => return Ok(false),
+    };
+
+    if !attestation.matches_play(&play) {
+        return Ok(false);
+    }
+
+    // The listener is whoever authored the play record
+    let listener = record.action().author().clone();
+    attestation_signed_by(&attestation, listener)
+}
+
+/// Default page size for `get_attestations_for_song`
+const DEFAULT_ATTESTATIONS_PAGE_SIZE: usize = 50;
+
+/// Largest page `get_attestations_for_song` will return
+const MAX_ATTESTATIONS_PAGE_SIZE: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetSongAttestationsInput {
+    pub song_hash: ActionHash,
+    /// None starts from the oldest attestation
+    pub cursor: Option<PageCursor>,
+    /// Defaults to 50, capped at 200
+    pub limit: Option<usize>,
+}
+
+/// A play attestation with the outcome of checking it
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditedAttestation {
+    pub attestation_hash: ActionHash,
+    pub attestation: PlayAttestation,
+    /// Author of the attested play (the attestation's author if the play
+    /// can't be found)
+    pub listener: AgentPubKey,
+    /// The signature is the listener's over the attestation payload
+    pub signature_valid: bool,
+    /// The attestation's song, artist and amount match the play as recorded
+    pub matches_play: bool,
+    /// The play's latest version (amendments and settlement), if found
+    pub play: Option<PlayRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SongAttestationsPage {
+    pub attestations: Vec<AuditedAttestation>,
+    /// None once the last page has been returned
+    pub next_cursor: Option<PageCursor>,
+}
+
+/// Page through a song's play attestations, oldest first, verifying each
+///
+/// An audit trail separating listens the listener cryptographically
+/// attested from ones that were merely recorded.
+#[hdk_extern]
+pub fn get_attestations_for_song(
+    input: GetSongAttestationsInput,
+) -> ExternResult<SongAttestationsPage> {
+    let limit = page_limit(
+        input.limit,
+        DEFAULT_ATTESTATIONS_PAGE_SIZE,
+        MAX_ATTESTATIONS_PAGE_SIZE,
+    );
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(input.song_hash, LinkTypes::SongToAttestations)?.build(),
+    )?;
+    let (page, next_cursor) = paginate_links(links, input.cursor.as_ref(), limit);
+
+    let mut attestations = Vec::with_capacity(page.len());
+    for link in &page {
+        let attestation_hash = match link.target.clone().into_action_hash() {
+            Some(hash) => hash,
+            None => continue,
+        };
+        let record = match get(attestation_hash.clone(), GetOptions::default())? {
+            Some(record) => record,
+            None => continue,
+        };
+        let attestation = match record
+            .entry()
+            .to_app_option::<PlayAttestation>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            Some(attestation) => attestation,
+            None => continue,
+        };
+        let author = record.action().author().clone();
+        attestations.push(audit_attestation(attestation_hash, author, attestation)?);
+    }
+
+    Ok(SongAttestationsPage {
+        attestations,
+        next_cursor,
+    })
+}
+
+/// Check an attestation against its play and the play author's key
+fn audit_attestation(
+    attestation_hash: ActionHash,
+    author: AgentPubKey,
+    attestation: PlayAttestation,
+) -> ExternResult<AuditedAttestation> {
+    let details = match get_details(attestation.play_hash.clone(), GetOptions::default())? {
+        Some(Details::Record(details)) => Some(details),
+        _ => None,
+    };
+
+    // The signature covers the play as recorded, not later versions
+    let (listener, matches_play) = match &details {
+        Some(details) => (
+            details.record.action().author().clone(),
+            read_play_record(&details.record)
+                .map(|play| attestation.matches_play(&play))
+                .unwrap_or(false),
+        ),
+        None => (author, false),
+    };
+    let play = match details {
+        Some(details) => latest_play_from_details(Details::Record(details))?.map(|(_, play)| play),
+        None => None,
+    };
+    let signature_valid = attestation_signed_by(&attestation, listener.clone())?;
+
+    Ok(AuditedAttestation {
+        attestation_hash,
+        attestation,
+        listener,
+        signature_valid,
+        matches_play,
+        play,
+    })
+}
+
+/// Whether I played a song to the end before `before` (scans my source chain)
+fn has_full_listen(song_hash: &ActionHash, before: Timestamp) -> ExternResult<bool> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::PlayRecord.try_into()?)
+            .include_entries(true),
+    )?;
+
+    for record in records {
+        let play = read_play_record(&record)?;
+        if &play.song_hash == song_hash
+            && play.played_at < before
+            && is_full_listen(play.duration_listened, play.song_duration)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Effective per-full-play rate for a strategy
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayRate {
+    pub strategy_id: String,
+    pub multiplier: f64,
+    /// Amount owed for one full play (in wei)
+    pub rate_wei: u64,
+    /// Intended fiat price for one full play (millionths of a unit)
+    pub fiat_price_micros: u64,
+    pub fiat_currency: String,
+}
+
+/// Get the effective per-full-play rate for a strategy
+#[hdk_extern]
+pub fn get_play_rate(strategy_id: String) -> ExternResult<PlayRate> {
+    let properties = plays_properties()?;
+    let multiplier = strategy_multiplier(&strategy_id);
+
+    Ok(PlayRate {
+        strategy_id,
+        multiplier,
+        rate_wei: (properties.base_rate_wei as f64 * multiplier) as u64,
+        fiat_price_micros: (properties.fiat_price_micros as f64 * multiplier) as u64,
+        fiat_currency: properties.fiat_currency,
+    })
+}
+
+/// Input for previewing what a play of a song would cost
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PreviewPlayInput {
+    pub song_hash: ActionHash,
+    /// Hypothetical share of the song listened (basis points, 10000 = all)
+    pub completion_bps: u32,
+}
+
+/// What a play would be charged, priced like `record_play`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayPreview {
+    pub song_hash: ActionHash,
+    pub strategy_id: String,
+    pub song_duration: u32,
+    pub duration_listened: u32,
+    /// Whether the play would reach the listen threshold
+    pub qualifies: bool,
+    /// Amount owed (in wei), including completion bonuses
+    pub amount_wei: u64,
+}
+
+/// Preview the charge for listening to part of a song
+///
+/// Uses the same pricing as `record_play`, including completion-bonus
+/// tiers. Repeat-listen bonuses and carried remainders depend on the
+/// listener's history and are left out. Gift and time-barter songs, and
+/// plays below the threshold, preview as 0. Returns None if the song
+/// doesn't exist.
+#[hdk_extern]
+pub fn preview_play_amount(input: PreviewPlayInput) -> ExternResult<Option<PlayPreview>> {
+    let song = match catalog_song(&input.song_hash)? {
+        Some(song) => song,
+        None => return Ok(None),
+    };
+    let completion_bps = input.completion_bps.min(10_000) as u64;
+    let duration_listened = (song.duration_seconds as u64 * completion_bps / 10_000) as u32;
+
+    let properties = plays_properties()?;
+    let config = properties.strategy_config(&song.strategy_id);
+    let nanowei = play_amount_nanowei(
+        properties.base_rate_wei,
+        &song.strategy_id,
+        duration_listened,
+        song.duration_seconds,
+        song.min_play_override,
+        completion_bonus_bps(
+            &config,
+            duration_listened,
+            song.duration_seconds,
+            song.min_play_override,
+            false,
+        ),
+    );
+    let (amount_wei, _) = properties.earnings_rounding.charge(nanowei, 0);
+
+    Ok(Some(PlayPreview {
+        song_hash: input.song_hash,
+        qualifies: is_qualifying_play(
+            duration_listened,
+            song.duration_seconds,
+            song.min_play_override,
+        ),
+        strategy_id: song.strategy_id,
+        song_duration: song.duration_seconds,
+        duration_listened,
+        amount_wei,
+    }))
+}
+
+/// Protocol fee a strategy's settlements pay
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StrategyFeeRate {
+    pub strategy_id: String,
+    /// Configured fee (basis points)
+    pub protocol_fee_bps: u32,
+    /// Fee actually deducted; 0 while no platform agent is set
+    pub effective_fee_bps: u32,
+    /// Recipient of the `ProtocolFee` transfer
+    pub platform_agent: Option<AgentPubKey>,
+}
+
+/// Get the protocol fee deducted from a strategy's settlements
+#[hdk_extern]
+pub fn get_strategy_fee(strategy_id: String) -> ExternResult<StrategyFeeRate> {
+    let properties = plays_properties()?;
+
+    Ok(StrategyFeeRate {
+        protocol_fee_bps: properties.strategy_config(&strategy_id).protocol_fee_bps,
+        effective_fee_bps: properties.effective_fee_bps(&strategy_id),
+        platform_agent: properties.platform_agent,
+        strategy_id,
+    })
+}
+
+/// Decode a PlayRecord of any schema version, upgraded to the current one
+///
+/// Every query reads plays through here so field additions stay
+/// non-breaking for entries already on listeners' chains.
+fn read_play_record(record: &Record) -> ExternResult<PlayRecord> {
+    record
+        .entry()
+        .to_app_option::<PlayRecord>()
+        .map_err(|e| wasm_error!(e))?
+        .map(PlayRecord::upgraded)
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Record is not a PlayRecord".to_string())))
+}
+
+/// The create action a play descends from
+///
+/// Amending and settling a play update its entry, so links and counts key
+/// on the original create to treat a play as the same play throughout.
+/// Links target creates, so a record read through one is at most a
+/// direct update of its create.
+fn canonical_play_hash(record: &Record) -> ActionHash {
+    match record.action() {
+        Action::Update(update) => update.original_action_address.clone(),
+        _ => record.action_address().clone(),
+    }
+}
+
+/// A play's canonical hash and latest version, following its amendments
+/// and settlement
+fn latest_play_from_details(details: Details) -> ExternResult<Option<(ActionHash, PlayRecord)>> {
+    let details = match details {
+        Details::Record(details) => details,
+        _ => return Ok(None),
+    };
+    let play_hash = canonical_play_hash(&details.record);
+
+    Ok(latest_record(details)?
+        .map(|record| read_play_record(&record))
+        .transpose()?
+        .map(|play| (play_hash, play)))
+}
+
+/// The newest record in a chain of updates: a play's amendments and
+/// settlement, or a song's earnings freezes
+///
+/// Each update is made to the version before it, so this follows the
+/// newest update of each version until one has none.
+fn latest_record(mut details: RecordDetails) -> ExternResult<Option<Record>> {
+    loop {
+        let next = match details
+            .updates
+            .iter()
+            .max_by_key(|update| update.action().timestamp())
+        {
+            Some(update) => update.action_address().clone(),
+            None => return Ok(Some(details.record)),
+        };
+        details = match get_details(next, GetOptions::default())? {
+            Some(Details::Record(next)) => next,
+            _ => return Ok(None),
+        };
+    }
+}
+
+/// A play's latest version and the action that wrote it
+fn latest_play_version(play_hash: ActionHash) -> ExternResult<Option<(ActionHash, PlayRecord)>> {
+    let details = match get_details(play_hash, GetOptions::default())? {
+        Some(Details::Record(details)) => details,
+        _ => return Ok(None),
+    };
+    match latest_record(details)? {
+        Some(record) => Ok(Some((record.action_address().clone(), read_play_record(&record)?))),
+        None => Ok(None),
+    }
+}
+
+/// A song's latest version and the action that wrote it
+fn latest_song_version(
+    song_hash: ActionHash,
+) -> ExternResult<Option<(ActionHash, catalog_integrity::Song)>> {
+    let details = match get_details(song_hash, GetOptions::default())? {
+        Some(Details::Record(details)) => details,
+        _ => return Ok(None),
+    };
+    let record = match latest_record(details)? {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+    let song = record
+        .entry()
+        .to_app_option::<catalog_integrity::Song>()
+        .map_err(|e| wasm_error!(e))?;
+    Ok(song.map(|song| (record.action_address().clone(), song)))
+}
+
+fn latest_play(play_hash: ActionHash) -> ExternResult<Option<(ActionHash, PlayRecord)>> {
+    match get_details(play_hash, GetOptions::default())? {
+        Some(details) => latest_play_from_details(details),
+        None => Ok(None),
+    }
+}
+
+fn listener_plays_path(agent: &AgentPubKey) -> Path {
+    Path::from(format!("listener_plays/{}", agent))
+}
+
+/// Get my unsettled plays along with their action hashes
+fn get_my_unsettled_plays_with_hashes() -> ExternResult<Vec<(ActionHash, PlayRecord)>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            listener_plays_path(&my_agent).path_entry_hash()?,
+            LinkTypes::ListenerToPlays,
+        )?
+        .build(),
+    )?;
+
+    let mut unsettled = Vec::new();
+    for link in links {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some((play_hash, play)) = latest_play(action_hash)? {
+                if !play.settled {
+                    unsettled.push((play_hash, play));
+                }
+            }
+        }
+    }
+
+    Ok(unsettled)
+}
+
+/// Get my unsettled plays
+///
+/// Walks every play I've recorded; listeners with long histories should
+/// page through `get_my_unsettled_plays_page` instead.
+#[hdk_extern]
+pub fn get_my_unsettled_plays(_: ()) -> ExternResult<Vec<PlayRecord>> {
+    Ok(get_my_unsettled_plays_with_hashes()?
+        .into_iter()
+        .map(|(_, play)| play)
+        .collect())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetUnsettledPlaysPageInput {
+    /// None starts from my oldest play
+    pub cursor: Option<PageCursor>,
+    /// Defaults to 100, capped at 500
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnsettledPlay {
+    pub play_hash: ActionHash,
+    pub play: PlayRecord,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnsettledPlaysPage {
+    pub plays: Vec<UnsettledPlay>,
+    /// None once the last page has been returned
+    pub next_cursor: Option<PageCursor>,
+}
+
+/// Page through my unsettled plays, oldest first
+///
+/// Each page examines up to `limit` plays, so it may hold fewer unsettled
+/// plays than that; keep going until `next_cursor` is None.
+#[hdk_extern]
+pub fn get_my_unsettled_plays_page(
+    input: GetUnsettledPlaysPageInput,
+) -> ExternResult<UnsettledPlaysPage> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let limit = page_limit(input.limit, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE);
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            listener_plays_path(&my_agent).path_entry_hash()?,
+            LinkTypes::ListenerToPlays,
+        )?
+        .build(),
+    )?;
+    let (page, next_cursor) = paginate_links(links, input.cursor.as_ref(), limit);
+
+    let inputs: Vec<GetInput> = page
+        .iter()
+        .filter_map(|link| link.target.clone().into_action_hash())
+        .map(|hash| GetInput::new(hash.into(), GetOptions::default()))
+        .collect();
+    let details = HDK.with(|h| h.borrow().get_details(inputs))?;
+
+    let mut plays = Vec::new();
+    for details in details.into_iter().flatten() {
+        if let Some((play_hash, play)) = latest_play_from_details(details)? {
+            if !play.settled {
+                plays.push(UnsettledPlay { play_hash, play });
+            }
+        }
+    }
+
+    Ok(UnsettledPlaysPage { plays, next_cursor })
+}
+
+fn listener_owed_path(agent: &AgentPubKey) -> Path {
+    Path::from(format!("listener_owed/{}", agent))
+}
+
+fn earnings_remainder_path(listener: &AgentPubKey, artist: &AgentPubKey) -> Path {
+    Path::from(format!("earnings_remainder/{}/{}", listener, artist))
+}
+
+/// Nanowei carried from a listener's last play of an artist (0 if none)
+fn earnings_remainder(listener: &AgentPubKey, artist: &AgentPubKey) -> ExternResult<u64> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            earnings_remainder_path(listener, artist).path_entry_hash()?,
+            LinkTypes::PairToEarningsRemainder,
+        )?
+        .build(),
+    )?;
+
+    let latest = links.into_iter().max_by_key(|l| l.timestamp);
+    if let Some(action_hash) = latest.and_then(|link| link.target.into_action_hash()) {
+        if let Some(record) = get(action_hash, GetOptions::default())? {
+            let remainder = record
+                .entry()
+                .to_app_option::<EarningsRemainder>()
+                .map_err(|e| wasm_error!(e))?;
+            return Ok(remainder.map_or(0, |r| r.nanowei));
+        }
+    }
+
+    Ok(0)
+}
+
+fn save_earnings_remainder(
+    listener: &AgentPubKey,
+    artist: &AgentPubKey,
+    nanowei: u64,
+) -> ExternResult<()> {
+    let action_hash = create_entry(&EntryTypes::EarningsRemainder(EarningsRemainder {
+        listener: listener.clone(),
+        artist: artist.clone(),
+        nanowei,
+        updated_at: sys_time()?,
+    }))?;
+    replace_snapshot_link(
+        earnings_remainder_path(listener, artist),
+        action_hash,
+        LinkTypes::PairToEarningsRemainder,
+    )
+}
+
+/// My owed-balance counter, built from a full walk of my plays the first
+/// time it's needed
+fn current_owed_balance() -> ExternResult<OwedBalance> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    if let Some(owed) = latest_owed_balance(&my_agent)? {
+        return Ok(owed);
+    }
+
+    let mut owed = OwedBalance::empty(my_agent, sys_time()?);
+    for (_, play) in get_my_unsettled_plays_with_hashes()? {
+        if play.amount_owed > 0 {
+            owed.add_play(&play.artist, play.amount_owed);
+        }
+    }
+    save_owed_balance(&mut owed)?;
+    Ok(owed)
+}
+
+/// Latest counter snapshot for a listener, if any
+fn latest_owed_balance(listener: &AgentPubKey) -> ExternResult<Option<OwedBalance>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            listener_owed_path(listener).path_entry_hash()?,
+            LinkTypes::ListenerToOwedBalance,
+        )?
+        .build(),
+    )?;
+
+    let latest = links.into_iter().max_by_key(|l| l.timestamp);
+    if let Some(link) = latest {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(action_hash, GetOptions::default())? {
+                return record
+                    .entry()
+                    .to_app_option::<OwedBalance>()
+                    .map_err(|e| wasm_error!(e));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Write a new counter snapshot and make it the latest
+fn save_owed_balance(owed: &mut OwedBalance) -> ExternResult<()> {
+    owed.updated_at = sys_time()?;
+    let action_hash = create_entry(&EntryTypes::OwedBalance(owed.clone()))?;
+    replace_snapshot_link(
+        listener_owed_path(&owed.listener),
+        action_hash,
+        LinkTypes::ListenerToOwedBalance,
+    )
+}
+
+/// Point a snapshot path at its new snapshot, deleting the links to the
+/// ones it supersedes
+///
+/// Keeps reads to one live link however many times the snapshot is saved.
+fn replace_snapshot_link(
+    path: Path,
+    snapshot: ActionHash,
+    link_type: LinkTypes,
+) -> ExternResult<()> {
+    path.ensure()?;
+    let base = path.path_entry_hash()?;
+    let superseded = get_links(GetLinksInputBuilder::try_new(base.clone(), link_type)?.build())?;
+    create_link(base, snapshot, link_type, ())?;
+    for link in superseded {
+        delete_link(link.create_link_hash)?;
+    }
+    Ok(())
+}
+
+/// Get total amount I owe (unsettled paying plays)
+///
+/// Read from a counter maintained by `record_play` and settlement
+/// batching rather than re-walking every play.
+#[hdk_extern]
+pub fn get_my_balance_owed(_: ()) -> ExternResult<BalanceOwed> {
+    let owed = current_owed_balance()?;
+
+    Ok(BalanceOwed {
+        total_amount: owed.total_amount,
+        play_count: owed.play_count,
+        by_artist: owed
+            .by_artist
+            .into_iter()
+            .map(|a| (a.artist.to_string(), a.amount))
+            .collect(),
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BalanceOwed {
+    pub total_amount: u64,
+    pub play_count: u64,
+    pub by_artist: Vec<(String, u64)>,
+}
+
+/// Outcome of a settlement batch request
+#[derive(Serialize, Deserialize, Debug)]
+pub enum BatchOutcome {
+    /// A batch was created and paid
+    Created {
+        batch_hash: ActionHash,
+        play_count: u64,
+        total_amount: u64,
+        /// The `PlaySettlement` transfer paying the artist
+        transfer_hash: ActionHash,
+    },
+    /// No batch was created
+    Skipped { reason: BatchSkipReason },
+}
+
+/// Why a settlement batch was not created
+#[derive(Serialize, Deserialize, Debug)]
+pub enum BatchSkipReason {
+    /// No unsettled plays for this artist
+    NoUnsettledPlays,
+    /// All unsettled plays are under zero-payout strategies (e.g. gift)
+    ZeroPayout,
+    /// No strategy reached its play/amount threshold or max age yet
+    BelowThreshold { play_count: u64, total_amount: u64 },
+    /// Every paying play can still be amended (`amend_grace_secs`)
+    InGracePeriod { play_count: u64 },
+    /// Every settleable play is of a song whose earnings are frozen
+    EarningsFrozen { play_count: u64 },
+    /// My listener balance can't pay the batch and its protocol fee
+    InsufficientBalance { required: u64, available: u64 },
+}
+
+/// Preview of the batch `create_settlement_batch` would create right now
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SettlementEstimate {
+    pub play_count: u64,
+    /// Net of the protocol fee
+    pub total_amount: u64,
+    pub gross_amount: u64,
+    pub protocol_fee: u64,
+    pub fee_breakdown: Vec<StrategyFee>,
+    pub play_hashes: Vec<ActionHash>,
+    pub estimated_merkle_root: Vec<u8>,
+    pub merkle_algorithm: MerkleAlgorithm,
+    /// Batches needed to settle every selected play within
+    /// `max_batch_plays`; this estimate covers the first
+    pub sub_batches: u64,
+    /// Set (with empty totals) when no batch would be created
+    pub skipped: Option<BatchSkipReason>,
+}
+
+/// Preview a settlement batch for an artist without writing anything
+#[hdk_extern]
+pub fn estimate_settlement(artist: AgentPubKey) -> ExternResult<SettlementEstimate> {
+    Ok(match plan_settlement(&artist)? {
+        Ok(estimates) => estimates
+            .into_iter()
+            .next()
+            .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Empty settlement plan".to_string())))?,
+        Err(reason) => SettlementEstimate {
+            play_count: 0,
+            total_amount: 0,
+            gross_amount: 0,
+            protocol_fee: 0,
+            fee_breakdown: Vec::new(),
+            play_hashes: Vec::new(),
+            estimated_merkle_root: Vec::new(),
+            merkle_algorithm: plays_properties()?.merkle_algorithm,
+            sub_batches: 0,
+            skipped: Some(reason),
+        },
+    })
+}
+
+/// Create a settlement batch for an artist
+///
+/// Plays are grouped by strategy and only groups that satisfy their
+/// strategy's `StrategyConfig` (min plays and amount, or max age) are
+/// included. Zero-payout plays never produce a batch. At most
+/// `max_batch_plays` of the oldest selected plays go into the batch; use
+/// `create_bounded_settlement_batches` to settle the rest in one call.
+#[hdk_extern]
+pub fn create_settlement_batch(artist: AgentPubKey) -> ExternResult<BatchOutcome> {
+    let estimate = match plan_settlement(&artist)? {
+        Ok(estimates) => estimates.into_iter().next(),
+        Err(reason) => return Ok(BatchOutcome::Skipped { reason }),
+    };
+    match estimate {
+        Some(estimate) => {
+            if let Some(reason) = check_can_pay(estimate.gross_amount)? {
+                return Ok(BatchOutcome::Skipped { reason });
+            }
+            write_settlement_batch(artist, estimate)
+        }
+        None => Ok(BatchOutcome::Skipped {
+            reason: BatchSkipReason::NoUnsettledPlays,
+        }),
+    }
+}
+
+/// Create every settlement batch an artist is due, split so none holds
+/// more than `max_batch_plays` plays
+///
+/// Each sub-batch has its own merkle root and fee breakdown and is linked
+/// to the artist like any other batch; no play is in more than one.
+/// Returns the created batch hashes, oldest plays first (empty when
+/// nothing is due). Fails without creating any if my balance can't pay
+/// them all.
+#[hdk_extern]
+pub fn create_bounded_settlement_batches(artist: AgentPubKey) -> ExternResult<Vec<ActionHash>> {
+    let estimates = match plan_settlement(&artist)? {
+        Ok(estimates) => estimates,
+        Err(_) => return Ok(Vec::new()),
+    };
+    require_can_pay(estimates.iter().map(|e| e.gross_amount).sum())?;
+
+    let mut batch_hashes = Vec::with_capacity(estimates.len());
+    for estimate in estimates {
+        if let BatchOutcome::Created { batch_hash, .. } =
+            write_settlement_batch(artist.clone(), estimate)?
+        {
+            batch_hashes.push(batch_hash);
+        }
+    }
+    Ok(batch_hashes)
+}
+
+/// A batch created by `settle_all_debts`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArtistSettlement {
+    pub artist: AgentPubKey,
+    pub batch_hash: ActionHash,
+    pub play_count: u64,
+    pub total_amount: u64,
+    pub transfer_hash: ActionHash,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SettleAllOutput {
+    pub batches: Vec<ArtistSettlement>,
+    pub play_count: u64,
+    pub total_amount: u64,
+}
+
+/// Settle everything I owe, creating one batch per artist (more when an
+/// artist is owed for over `max_batch_plays` plays)
+///
+/// Unlike `create_settlement_batch`, strategy thresholds and max ages are
+/// ignored so every outstanding debt is covered (e.g. before closing an
+/// account or at period end). Artists owed nothing are skipped. Fails
+/// without creating any batch if my balance can't pay them all.
+#[hdk_extern]
+pub fn settle_all_debts(_: ()) -> ExternResult<SettleAllOutput> {
+    // Keyed by the artist's string form for a stable batch order
+    let mut by_artist: std::collections::BTreeMap<String, (AgentPubKey, Vec<(ActionHash, PlayRecord)>)> =
+        std::collections::BTreeMap::new();
+    for (hash, play) in get_my_unsettled_plays_with_hashes()? {
+        by_artist
+            .entry(play.artist.to_string())
+            .or_insert_with(|| (play.artist.clone(), Vec::new()))
+            .1
+            .push((hash, play));
+    }
+
+    let mut output = SettleAllOutput {
+        batches: Vec::new(),
+        play_count: 0,
+        total_amount: 0,
+    };
+
+    let mut planned = Vec::new();
+    for (artist, plays) in by_artist.into_values() {
+        if let Ok(estimates) = select_settlement_plays(plays, true)? {
+            planned.extend(estimates.into_iter().map(|estimate| (artist.clone(), estimate)));
+        }
+    }
+    require_can_pay(planned.iter().map(|(_, e)| e.gross_amount).sum())?;
+
+    for (artist, estimate) in planned {
+        if let BatchOutcome::Created {
+            batch_hash,
+            play_count,
+            total_amount,
+            transfer_hash,
+        } = write_settlement_batch(artist.clone(), estimate)?
+        {
+            output.play_count += play_count;
+            output.total_amount += total_amount;
+            output.batches.push(ArtistSettlement {
+                artist,
+                batch_hash,
+                play_count,
+                total_amount,
+                transfer_hash,
+            });
+        }
+    }
+
+    Ok(output)
+}
+
+/// Create a batch entry for the planned plays, index it and pay it
+///
+/// The artist's share and the protocol fee are transferred from my
+/// listener account in the same zome call, so if either transfer fails
+/// (e.g. `InsufficientBalance`) the call fails and no batch is written.
+fn write_settlement_batch(
+    artist: AgentPubKey,
+    estimate: SettlementEstimate,
+) -> ExternResult<BatchOutcome> {
+    let play_count = estimate.play_count;
+    let total_amount = estimate.total_amount;
+    let play_hashes = estimate.play_hashes;
+
+    let protocol_fee = estimate.protocol_fee;
+
+    // Batched plays no longer count towards what I owe; report any still
+    // waiting for a per-minute signal first
+    let mut owed = current_owed_balance()?;
+    if let Some(signal) = owed.flush_signal(&artist, sys_time()?).and_then(aggregate_signal) {
+        send_play_signal(&artist, signal);
+    }
+    owed.remove_settled(&artist, play_count, estimate.gross_amount);
+    save_owed_balance(&mut owed)?;
+
+    // The batch settles each play as it was priced: its latest amendment
+    let mut play_versions = Vec::with_capacity(play_hashes.len());
+    let mut song_hashes = Vec::new();
+    for play_hash in &play_hashes {
+        play_versions.push(match latest_play_version(play_hash.clone())? {
+            Some((version, play)) => {
+                if !song_hashes.contains(&play.song_hash) {
+                    song_hashes.push(play.song_hash);
+                }
+                version
+            }
+            None => play_hash.clone(),
+        });
+    }
+
+    // Cite each song as checked, so validation can see none is frozen
+    let mut song_versions = Vec::with_capacity(song_hashes.len());
+    for song_hash in song_hashes {
+        let (version, song) = latest_song_version(song_hash.clone())?.ok_or_else(|| {
+            wasm_error!(WasmErrorInner::Guest(format!("Song {} not found", song_hash)))
+        })?;
+        if song.earnings_frozen {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Song {} has frozen earnings",
+                song_hash
+            ))));
+        }
+        song_versions.push(version);
+    }
+
+    let batch = SettlementBatch {
+        artist: artist.clone(),
+        play_count,
+        total_amount,
+        gross_amount: estimate.gross_amount,
+        protocol_fee,
+        fee_breakdown: estimate.fee_breakdown,
+        play_hashes,
+        play_versions,
+        song_versions,
+        merkle_root: estimate.estimated_merkle_root,
+        merkle_algorithm: estimate.merkle_algorithm,
+        settlement_target: plays_properties()?.settlement_target,
+        created_at: sys_time()?,
+        status: SettlementStatus::Pending,
+        tx_hash: None,
+    };
+
+    let batch_hash = create_entry(&EntryTypes::SettlementBatch(batch.clone()))?;
+
+    let transfer_hash = pay_settlement(&artist, total_amount, play_count, &batch_hash)?;
+    if protocol_fee > 0 {
+        if let Some(platform) = plays_properties()?.platform_agent {
+            record_protocol_fee(platform, protocol_fee, &batch_hash)?;
+        }
+    }
+
+    // Link batch to artist
+    let artist_settlements_path = Path::from(format!("settlements/{}", artist));
+    artist_settlements_path.ensure()?;
+    create_link(
+        artist_settlements_path.path_entry_hash()?,
+        batch_hash.clone(),
+        LinkTypes::ArtistToSettlements,
+        (),
+    )?;
+
+    // Add to the network-wide worklist until it leaves Pending
+    let pending_path = Path::from(ALL_PENDING_SETTLEMENTS);
+    pending_path.ensure()?;
+    create_link(
+        pending_path.path_entry_hash()?,
+        batch_hash.clone(),
+        LinkTypes::AllPendingSettlements,
+        (),
+    )?;
+
+    settle_batch_plays(&batch_hash, &batch)?;
+
+    Ok(BatchOutcome::Created {
+        batch_hash,
+        play_count,
+        total_amount,
+        transfer_hash,
+    })
+}
+
+/// My listener balance, via the balances zome (0 without an account)
+fn my_listener_balance() -> ExternResult<u64> {
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("balances"),
+        FunctionName::from("get_my_listener_balance"),
+        None,
+        (),
+    )? {
+        ZomeCallResponse::Ok(result) => {
+            let account: Option<ListenerAccount> = result.decode().map_err(|e| wasm_error!(e))?;
+            Ok(account.map_or(0, |account| account.balance))
+        }
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Failed to get listener balance: {:?}",
+            other
+        )))),
+    }
+}
+
+/// Why I can't pay `required` right now, if I can't
+fn check_can_pay(required: u64) -> ExternResult<Option<BatchSkipReason>> {
+    let available = my_listener_balance()?;
+    Ok((available < required).then_some(BatchSkipReason::InsufficientBalance { required, available }))
+}
+
+/// Fail unless I can pay `required`, before anything is written
+fn require_can_pay(required: u64) -> ExternResult<()> {
+    match check_can_pay(required)? {
+        Some(reason) => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot settle: {:?}",
+            reason
+        )))),
+        None => Ok(()),
+    }
+}
+
+/// Mirror of `balances::ExecuteTransferInput`
+#[derive(Serialize, Deserialize, Debug)]
+struct ExecuteTransferInput {
+    from: AgentPubKey,
+    to: AgentPubKey,
+    amount: u64,
+    reason: TransferReason,
+    reference: Option<ActionHash>,
+    play_count: u64,
+}
+
+/// Transfer via the balances zome, returning the transfer's hash
+fn balances_transfer(input: ExecuteTransferInput, what: &str) -> ExternResult<ActionHash> {
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("balances"),
+        FunctionName::from("execute_transfer"),
+        None,
+        input,
+    )? {
+        ZomeCallResponse::Ok(result) => result.decode().map_err(|e| wasm_error!(e)),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Failed to {}: {:?}",
+            what, other
+        )))),
+    }
+}
+
+/// Pay an artist a batch's net total from my listener account
+///
+/// The batch hash is the transfer reference, so a retried call is a no-op.
+fn pay_settlement(
+    artist: &AgentPubKey,
+    amount: u64,
+    play_count: u64,
+    batch_hash: &ActionHash,
+) -> ExternResult<ActionHash> {
+    let input = ExecuteTransferInput {
+        from: agent_info()?.agent_initial_pubkey,
+        to: artist.clone(),
+        amount,
+        reason: TransferReason::PlaySettlement,
+        reference: Some(batch_hash.clone()),
+        play_count,
+    };
+    balances_transfer(input, "pay settlement")
+}
+
+/// Pay a batch's protocol fee to the platform agent via the balances zome
+///
+/// The batch hash is the transfer reference, so a retried call is a no-op.
+fn record_protocol_fee(
+    platform: AgentPubKey,
+    amount: u64,
+    batch_hash: &ActionHash,
+) -> ExternResult<()> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    if my_agent == platform {
+        return Ok(());
+    }
+
+    let input = ExecuteTransferInput {
+        from: my_agent,
+        to: platform,
+        amount,
+        reason: TransferReason::ProtocolFee,
+        reference: Some(batch_hash.clone()),
+        play_count: 0,
+    };
+    balances_transfer(input, "record protocol fee")?;
+    Ok(())
+}
+
+/// Select the plays an artist's next batch would settle
+///
+/// Shared by `create_settlement_batch` and `estimate_settlement` so a
+/// preview always matches the real batch.
+fn plan_settlement(
+    artist: &AgentPubKey,
+) -> ExternResult<Result<Vec<SettlementEstimate>, BatchSkipReason>> {
+    let artist_plays: Vec<(ActionHash, PlayRecord)> = get_my_unsettled_plays_with_hashes()?
+        .into_iter()
+        .filter(|(_, p)| &p.artist == artist)
+        .collect();
+
+    select_settlement_plays(artist_plays, false)
+}
+
+/// Pick which of one artist's unsettled plays to settle
+///
+/// With `ignore_thresholds`, every paying play is selected regardless of
+/// its strategy's batching policy. The selection is split, oldest plays
+/// first, into batches of at most `max_batch_plays`.
+fn select_settlement_plays(
+    artist_plays: Vec<(ActionHash, PlayRecord)>,
+    ignore_thresholds: bool,
+) -> ExternResult<Result<Vec<SettlementEstimate>, BatchSkipReason>> {
+    if artist_plays.is_empty() {
+        return Ok(Err(BatchSkipReason::NoUnsettledPlays));
+    }
+
+    // Skip strategies with zero payout
+    let paying_plays: Vec<(ActionHash, PlayRecord)> = artist_plays
+        .into_iter()
+        .filter(|(_, p)| p.amount_owed > 0)
+        .collect();
+
+    if paying_plays.is_empty() {
+        return Ok(Err(BatchSkipReason::ZeroPayout));
+    }
+
+    // Plays that can still be amended aren't settleable yet
+    let properties = plays_properties()?;
+    let now_micros = sys_time()?.as_micros();
+    let grace_micros = properties.amend_grace_secs as i64 * 1_000_000;
+    let (settleable, in_grace): (Vec<_>, Vec<_>) = paying_plays
+        .into_iter()
+        .partition(|(_, p)| now_micros - p.played_at.as_micros() >= grace_micros);
+
+    if settleable.is_empty() {
+        return Ok(Err(BatchSkipReason::InGracePeriod {
+            play_count: in_grace.len() as u64,
+        }));
+    }
+
+    // Disputed songs' plays stay unsettled until they're unfrozen
+    let mut frozen_songs = HashSet::new();
+    let song_hashes: HashSet<ActionHash> =
+        settleable.iter().map(|(_, p)| p.song_hash.clone()).collect();
+    for song_hash in song_hashes {
+        if let Some((_, song)) = latest_song_version(song_hash.clone())? {
+            if song.earnings_frozen {
+                frozen_songs.insert(song_hash);
+            }
+        }
+    }
+    let (settleable, frozen): (Vec<_>, Vec<_>) = settleable
+        .into_iter()
+        .partition(|(_, p)| !frozen_songs.contains(&p.song_hash));
+
+    if settleable.is_empty() {
+        return Ok(Err(BatchSkipReason::EarningsFrozen {
+            play_count: frozen.len() as u64,
+        }));
+    }
+
+    // Group by strategy and apply each strategy's policy
+    let mut by_strategy: std::collections::BTreeMap<String, Vec<(ActionHash, PlayRecord)>> =
+        std::collections::BTreeMap::new();
+    for (hash, play) in settleable {
+        by_strategy
+            .entry(play.strategy_id.clone())
+            .or_default()
+            .push((hash, play));
+    }
+
+    let mut pending_count: u64 = 0;
+    let mut pending_amount: u64 = 0;
+    let mut selected: Vec<(ActionHash, PlayRecord)> = Vec::new();
+    let mut fee_rates: BTreeMap<String, u32> = BTreeMap::new();
+
+    for (strategy_id, plays) in by_strategy {
+        let config = properties.strategy_config(&strategy_id);
+        let count = plays.len() as u64;
+        let amount: u64 = plays.iter().map(|(_, p)| p.amount_owed).sum();
+        let oldest_micros = plays
+            .iter()
+            .map(|(_, p)| p.played_at.as_micros())
+            .min()
+            .unwrap_or(now_micros);
+
+        let meets_threshold = count >= config.min_plays && amount >= config.min_total_amount;
+        let too_old = config
+            .max_age_secs
+            .map(|max_age| now_micros - oldest_micros >= max_age as i64 * 1_000_000)
+            .unwrap_or(false);
+
+        if ignore_thresholds || meets_threshold || too_old {
+            fee_rates.insert(strategy_id.clone(), properties.effective_fee_bps(&strategy_id));
+            selected.extend(plays);
+        } else {
+            pending_count += count;
+            pending_amount += amount;
+        }
+    }
+
+    if selected.is_empty() {
+        return Ok(Err(BatchSkipReason::BelowThreshold {
+            play_count: pending_count,
+            total_amount: pending_amount,
+        }));
+    }
+
+    selected.sort_by_key(|(_, play)| play.played_at);
+    let batch_size = match properties.max_batch_plays {
+        0 => selected.len(),
+        max => max as usize,
+    };
+    let mut estimates: Vec<SettlementEstimate> = selected
+        .chunks(batch_size)
+        .map(|plays| settlement_estimate(plays, &fee_rates, properties.merkle_algorithm))
+        .collect();
+    let sub_batches = estimates.len() as u64;
+    for estimate in &mut estimates {
+        estimate.sub_batches = sub_batches;
+    }
+
+    Ok(Ok(estimates))
+}
+
+/// Totals, per-strategy fees and merkle root for one batch of plays
+fn settlement_estimate(
+    plays: &[(ActionHash, PlayRecord)],
+    fee_rates: &BTreeMap<String, u32>,
+    merkle_algorithm: MerkleAlgorithm,
+) -> SettlementEstimate {
+    let mut gross_by_strategy: BTreeMap<&str, u64> = BTreeMap::new();
+    for (_, play) in plays {
+        *gross_by_strategy.entry(play.strategy_id.as_str()).or_default() += play.amount_owed;
+    }
+    let fee_breakdown: Vec<StrategyFee> = gross_by_strategy
+        .into_iter()
+        .map(|(strategy_id, gross_amount)| {
+            let fee_bps = fee_rates.get(strategy_id).copied().unwrap_or(0);
+            StrategyFee {
+                strategy_id: strategy_id.to_string(),
+                gross_amount,
+                fee_bps,
+                fee: protocol_fee(gross_amount, fee_bps),
+            }
+        })
+        .collect();
+
+    // Calculate totals
+    let gross_amount: u64 = fee_breakdown.iter().map(|f| f.gross_amount).sum();
+    let protocol_fee: u64 = fee_breakdown.iter().map(|f| f.fee).sum();
+    let play_hashes: Vec<ActionHash> = plays.iter().map(|(hash, _)| hash.clone()).collect();
+
+    // Root in the construction the settlement contract verifies
+    let merkle_root = merkle_algorithm.root(&play_hashes);
+
+    SettlementEstimate {
+        play_count: plays.len() as u64,
+        total_amount: gross_amount - protocol_fee,
+        gross_amount,
+        protocol_fee,
+        fee_breakdown,
+        play_hashes,
+        estimated_merkle_root: merkle_root,
+        merkle_algorithm,
+        sub_batches: 1,
+        skipped: None,
+    }
+}
+
+/// Anchor linking every batch that is still Pending
+const ALL_PENDING_SETTLEMENTS: &str = "all_pending_settlements";
+
+/// Latest version of a settlement batch, following status updates
+fn get_latest_settlement(batch_hash: ActionHash) -> ExternResult<Option<SettlementBatch>> {
+    Ok(latest_settlement_version(batch_hash)?.map(|(_, batch)| batch))
+}
+
+/// A batch's latest version and the action that wrote it
+fn latest_settlement_version(
+    batch_hash: ActionHash,
+) -> ExternResult<Option<(ActionHash, SettlementBatch)>> {
+    let details = match get_details(batch_hash, GetOptions::default())? {
+        Some(Details::Record(details)) => details,
+        _ => return Ok(None),
+    };
+    let record = match latest_record(details)? {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+    let batch = record
+        .entry()
+        .to_app_option::<SettlementBatch>()
+        .map_err(|e| wasm_error!(e))?;
+    Ok(batch.map(|batch| (record.action_address().clone(), batch)))
+}
+
+/// Link a batch's plays to it and mark them settled
+///
+/// The batch lists plays by their original creates, and the settling
+/// update is made to the version it priced. The create stays the play's
+/// identity, so its SongToPlays and ListenerToPlays links are left as they
+/// are. Plays this batch already settled are skipped, so marking can be
+/// retried. Returns how many plays were marked.
+fn settle_batch_plays(batch_hash: &ActionHash, batch: &SettlementBatch) -> ExternResult<u32> {
+    let mut marked = 0;
+    for (index, play_hash) in batch.play_hashes.iter().enumerate() {
+        let (latest, play) = match latest_play_version(play_hash.clone())? {
+            Some(version) => version,
+            None => continue,
+        };
+        if play.settled {
+            if play.settlement_hash.as_ref() != Some(batch_hash) {
+                return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                    "Play {} is already settled by another batch",
+                    play_hash
+                ))));
+            }
+            continue;
+        }
+        if Some(&latest) != batch.play_version(index) {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Play {} changed since the batch priced it",
+                play_hash
+            ))));
+        }
+
+        create_link(
+            play_hash.clone(),
+            batch_hash.clone(),
+            LinkTypes::PlayToSettlement,
+            (),
+        )?;
+        update_entry(
+            latest,
+            &EntryTypes::PlayRecord(PlayRecord {
+                settled: true,
+                settlement_hash: Some(batch_hash.clone()),
+                ..play
+            }),
+        )?;
+        marked += 1;
+    }
+    Ok(marked)
+}
+
+/// Mark every play in one of my settlement batches as settled by it
+///
+/// Batch creation already does this; call it to finish a batch whose
+/// plays weren't all marked. Returns how many plays were marked.
+#[hdk_extern]
+pub fn mark_plays_settled(batch_hash: ActionHash) -> ExternResult<u32> {
+    let batch = get_latest_settlement(batch_hash.clone())?.ok_or_else(|| {
+        wasm_error!(WasmErrorInner::Guest("Settlement batch not found".to_string()))
+    })?;
+    settle_batch_plays(&batch_hash, &batch)
+}
+
+/// Get a settlement batch by hash, including status updates
+#[hdk_extern]
+pub fn get_settlement(batch_hash: ActionHash) -> ExternResult<Option<SettlementBatch>> {
+    get_latest_settlement(batch_hash)
+}
+
+/// Input for looking up a play's merkle proof in a settlement batch
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SettlementProofInput {
+    pub batch_hash: ActionHash,
+    pub play_hash: ActionHash,
+}
+
+/// Proof that a play is included in a settlement batch's merkle root
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SettlementProof {
+    pub merkle_algorithm: MerkleAlgorithm,
+    pub merkle_root: Vec<u8>,
+    pub leaf: Vec<u8>,
+    /// Position of the play in the batch (orders `Sequential` pairs)
+    pub index: u64,
+    /// Sibling hashes from the leaf up to the root
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// Merkle proof for a play in a settlement batch, built with the batch's
+/// own algorithm so it verifies against the root submitted on-chain
+#[hdk_extern]
+pub fn get_settlement_proof(input: SettlementProofInput) -> ExternResult<Option<SettlementProof>> {
+    Ok(get_latest_settlement(input.batch_hash)?
+        .and_then(|batch| settlement_proof(&batch, &input.play_hash)))
+}
+
+/// Inclusion proof for a play against a batch's root (None if not listed)
+fn settlement_proof(batch: &SettlementBatch, play_hash: &ActionHash) -> Option<SettlementProof> {
+    let index = batch.play_hashes.iter().position(|h| h == play_hash)?;
+    let algorithm = batch.merkle_algorithm;
+
+    algorithm.proof(&batch.play_hashes, index).map(|proof| SettlementProof {
+        merkle_algorithm: algorithm,
+        merkle_root: batch.merkle_root.clone(),
+        leaf: algorithm.leaf(play_hash),
+        index: index as u64,
+        proof,
+    })
+}
+
+/// A play's inclusion proof to check against a root
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifyProofInput {
+    /// Tree construction the root was built with
+    #[serde(default)]
+    pub merkle_algorithm: MerkleAlgorithm,
+    pub merkle_root: Vec<u8>,
+    pub play_hash: ActionHash,
+    /// Position of the play in the batch (only `Sequential` uses it)
+    #[serde(default)]
+    pub index: u64,
+    /// Sibling hashes from the leaf up to the root
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// Recompute the root from a play and its proof and compare, using the
+/// same leaf hashing and pair ordering as settlement roots and proofs
+pub fn check_merkle_proof(input: &VerifyProofInput) -> bool {
+    let index = match usize::try_from(input.index) {
+        Ok(index) => index,
+        Err(_) => return false,
+    };
+    input
+        .merkle_algorithm
+        .verify(&input.merkle_root, &input.play_hash, index, &input.proof)
+}
+
+/// Check a settlement proof off-chain before submitting it, or to debug
+/// a proof the contract rejects
+#[hdk_extern]
+pub fn verify_merkle_proof(input: VerifyProofInput) -> ExternResult<bool> {
+    Ok(check_merkle_proof(&input))
+}
+
+/// Everything needed to check that a play was paid for on-chain
+///
+/// Recompute `proof.merkle_root` from `play_hash` with `proof` (see
+/// `verify_merkle_proof`), then check the settlement transaction `tx_hash`
+/// submitted that root.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayReceipt {
+    /// The play's original create
+    pub play_hash: ActionHash,
+    /// Latest version, as settled
+    pub play: PlayRecord,
+    pub batch_hash: ActionHash,
+    pub artist: AgentPubKey,
+    pub batch_status: SettlementStatus,
+    /// Settlement transaction, once submitted
+    pub tx_hash: Option<String>,
+    pub proof: SettlementProof,
+}
+
+/// Receipt tying a settled play to its batch and on-chain settlement
+///
+/// Takes the play's create hash (as returned by `record_play`). Returns
+/// None until the play is settled by a batch.
+#[hdk_extern]
+pub fn get_play_receipt(play_hash: ActionHash) -> ExternResult<Option<PlayReceipt>> {
+    let (play_hash, play) = match latest_play(play_hash)? {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+    let batch_hash = match (&play.settled, &play.settlement_hash) {
+        (true, Some(batch_hash)) => batch_hash.clone(),
+        _ => return Ok(None),
+    };
+    let batch = match get_latest_settlement(batch_hash.clone())? {
+        Some(batch) => batch,
+        None => return Ok(None),
+    };
+    let proof = settlement_proof(&batch, &play_hash).ok_or_else(|| {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Play {} is not in its settlement batch",
+            play_hash
+        )))
+    })?;
+
+    Ok(Some(PlayReceipt {
+        play_hash,
+        play,
+        batch_hash,
+        artist: batch.artist,
+        batch_status: batch.status,
+        tx_hash: batch.tx_hash,
+        proof,
+    }))
+}
+
+/// Get pending settlements for an artist
+#[hdk_extern]
+pub fn get_pending_settlements(artist: AgentPubKey) -> ExternResult<Vec<SettlementBatch>> {
+    let artist_settlements_path = Path::from(format!("settlements/{}", artist));
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            artist_settlements_path.path_entry_hash()?,
+            LinkTypes::ArtistToSettlements,
+        )?
+        .build(),
+    )?;
+
+    let mut pending = Vec::new();
+    for link in links {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some(batch) = get_latest_settlement(action_hash)? {
+                if batch.status == SettlementStatus::Pending {
+                    pending.push(batch);
+                }
+            }
+        }
+    }
+
+    Ok(pending)
+}
+
+/// A pending batch and the hash to settle it by
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PendingSettlement {
+    pub batch_hash: ActionHash,
+    pub batch: SettlementBatch,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAllPendingSettlementsInput {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Get pending settlement batches across all artists (settlement driver worklist)
+#[hdk_extern]
+pub fn get_all_pending_settlements(
+    input: GetAllPendingSettlementsInput,
+) -> ExternResult<Vec<PendingSettlement>> {
+    let pending_path = Path::from(ALL_PENDING_SETTLEMENTS);
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            pending_path.path_entry_hash()?,
+            LinkTypes::AllPendingSettlements,
+        )?
+        .build(),
+    )?;
+
+    // Page over pending batches only; a link can outlive its batch's
+    // Pending status if the worklist cleanup failed
+    let wanted = input.offset.saturating_add(input.limit);
+    let mut pending = Vec::new();
+    for link in links {
+        if pending.len() >= wanted {
+            break;
+        }
+        if let Some(batch_hash) = link.target.into_action_hash() {
+            if let Some(batch) = get_latest_settlement(batch_hash.clone())? {
+                if batch.status == SettlementStatus::Pending {
+                    pending.push(PendingSettlement { batch_hash, batch });
+                }
+            }
+        }
+    }
+
+    Ok(pending.into_iter().skip(input.offset).collect())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateSettlementStatusInput {
+    /// Original batch hash (as returned by `create_settlement_batch`)
+    pub batch_hash: ActionHash,
+    pub status: SettlementStatus,
+    pub tx_hash: Option<String>,
+}
+
+/// Move a settlement batch to a new status (its listener or a
+/// `settlement_agents` driver only)
+///
+/// The update is made to the batch's latest version, and validation only
+/// allows Pending -> Submitted -> Confirmed/Failed (Failed may be
+/// resubmitted). Batches leaving Pending are dropped from the
+/// all_pending_settlements worklist.
+#[hdk_extern]
+pub fn update_settlement_status(input: UpdateSettlementStatusInput) -> ExternResult<ActionHash> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let batch_author = get(input.batch_hash.clone(), GetOptions::default())?
+        .map(|record| record.action().author().clone())
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Settlement not found".to_string())))?;
+    if my_agent != batch_author && !plays_properties()?.settlement_agents.contains(&my_agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the batch's listener or a settlement agent can update its status".to_string()
+        )));
+    }
+
+    let (latest_hash, original) = latest_settlement_version(input.batch_hash.clone())?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Settlement not found".to_string())))?;
+    let mut batch = original.clone();
+    batch.status = input.status;
+    if input.tx_hash.is_some() {
+        batch.tx_hash = input.tx_hash;
+    }
+    check_settlement_status_change(&original, &batch)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    let new_hash = update_entry(latest_hash, &EntryTypes::SettlementBatch(batch.clone()))?;
+
+    if batch.status != SettlementStatus::Pending {
+        let pending_path = Path::from(ALL_PENDING_SETTLEMENTS);
+        let links = get_links(
+            GetLinksInputBuilder::try_new(
+                pending_path.path_entry_hash()?,
+                LinkTypes::AllPendingSettlements,
+            )?
+            .build(),
+        )?;
+        for link in links {
+            if link.target.clone().into_action_hash().as_ref() == Some(&input.batch_hash) {
+                delete_link(link.create_link_hash)?;
+            }
+        }
+    }
+
+    Ok(new_hash)
+}
+
+/// Get play statistics for a song (for artists)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SongStats {
+    pub total_plays: u64,
+    pub total_earnings: u64,
+    /// Unique listeners (exact or HyperLogLog estimate)
+    pub unique_listeners: u64,
+    /// Whether `unique_listeners` is an exact count
+    pub unique_listeners_exact: bool,
+    /// Relative standard error of `unique_listeners` (0 when exact)
+    pub unique_listeners_error: f64,
+    /// Unique listeners whose best play reached `reach_completion_bps`,
+    /// counted like `unique_listeners`
+    pub completed_unique_listeners: u64,
+    pub avg_completion: f64,
+}
+
+/// Latest stats snapshot the song's artist published, if any
+fn latest_stats_snapshot(
+    song_hash: &ActionHash,
+    artist: &AgentPubKey,
+) -> ExternResult<Option<SongStatsSnapshot>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(song_hash.clone(), LinkTypes::SongToStats)?.build(),
+    )?;
+
+    let latest = links
+        .into_iter()
+        .filter(|link| &link.author == artist)
+        .max_by_key(|l| l.timestamp);
+    if let Some(link) = latest {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(action_hash, GetOptions::default())? {
+                return record
+                    .entry()
+                    .to_app_option::<SongStatsSnapshot>()
+                    .map_err(|e| wasm_error!(e));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether a SongToPlays link adds a play to the stats
+///
+/// Only links to a play's original create count, once each; a stale link
+/// to a settlement update or a repeated link would count the play again.
+fn counts_as_new_play(
+    counted: &mut HashSet<ActionHash>,
+    target: &ActionHash,
+    canonical: ActionHash,
+) -> bool {
+    &canonical == target && counted.insert(canonical)
+}
+
+/// Salted listener key: the raw agent key never enters the sketch
+fn listener_key(song_hash: &ActionHash, listener: &AgentPubKey) -> ExternResult<[u8; 32]> {
+    let hash = hash_keccak256([song_hash.get_raw_39(), listener.get_raw_39()].concat())?;
+    hash.as_slice()
+        .try_into()
+        .map_err(|_| wasm_error!(WasmErrorInner::Guest("Invalid keccak256 length".to_string())))
+}
+
+/// keccak256 of a set of links' hashes, independent of order
+fn links_digest<'a>(links: impl Iterator<Item = &'a Link>) -> ExternResult<Vec<u8>> {
+    let mut hashes: Vec<&[u8]> = links.map(|link| link.create_link_hash.get_raw_39()).collect();
+    hashes.sort_unstable();
+    hash_keccak256(hashes.concat())
+}
+
+/// A song's stats: the artist's latest snapshot with the plays linked
+/// since folded in, and whether anything was folded
+///
+/// The snapshot is only built on if the links up to its `counted_through`
+/// are exactly the ones it folded; otherwise (a play linked late with an
+/// older timestamp, a deleted link, a snapshot from before the digest)
+/// every play is recounted.
+fn fold_song_stats(song_hash: &ActionHash) -> ExternResult<(SongStatsSnapshot, u64)> {
+    let properties = plays_properties()?;
+    let exact_threshold = properties.exact_listener_threshold;
+    let artist = latest_song_version(song_hash.clone())?
+        .map(|(_, song)| song.artist)
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Song not found".to_string())))?;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(song_hash.clone(), LinkTypes::SongToPlays)?.build(),
+    )?;
+
+    // Snapshots without completed reach are recounted from the first play
+    let mut snapshot = match latest_stats_snapshot(song_hash, &artist)? {
+        Some(snapshot) if snapshot.completed_listeners.is_some() => {
+            let counted = links
+                .iter()
+                .filter(|link| snapshot.counted_through.map_or(false, |t| link.timestamp <= t));
+            if links_digest(counted)? == snapshot.folded_links_digest {
+                snapshot
+            } else {
+                SongStatsSnapshot::empty(song_hash.clone(), sys_time()?)
+            }
+        }
+        _ => SongStatsSnapshot::empty(song_hash.clone(), sys_time()?),
+    };
+
+    // Only fetch plays linked since the snapshot
+    let mut folded: u64 = 0;
+    let mut counted = HashSet::new();
+    for link in &links {
+        if snapshot.counted_through.map_or(false, |t| link.timestamp <= t) {
+            continue;
+        }
+
+        if let Some(action_hash) = link.target.clone().into_action_hash() {
+            if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
+                if counts_as_new_play(&mut counted, &action_hash, canonical_play_hash(&record)) {
+                    let play = read_play_record(&record)?;
+                    let key = listener_key(song_hash, record.action().author())?;
+                    snapshot.fold_play(
+                        &play,
+                        key,
+                        exact_threshold,
+                        properties.reach_completion_bps,
+                    );
+                    folded += 1;
+                }
+            }
+        }
+    }
+
+    // Every link is now folded in
+    if let Some(newest) = links.iter().map(|link| link.timestamp).max() {
+        snapshot.counted_through = Some(newest);
+    }
+    snapshot.folded_links_digest = links_digest(links.iter())?;
+
+    Ok((snapshot, folded))
+}
+
+/// Get play statistics for a song
+///
+/// Read-only: plays linked since the artist's last `refresh_song_stats`
+/// are folded in memory and nothing is written.
+#[hdk_extern]
+pub fn get_song_stats(song_hash: ActionHash) -> ExternResult<SongStats> {
+    let (snapshot, _) = fold_song_stats(&song_hash)?;
+    Ok(song_stats(&snapshot))
+}
+
+fn song_stats(snapshot: &SongStatsSnapshot) -> SongStats {
+    let avg_completion = if snapshot.total_plays > 0 {
+        snapshot.total_completion / snapshot.total_plays as f64
+    } else {
+        0.0
+    };
+
+    SongStats {
+        total_plays: snapshot.total_plays,
+        total_earnings: snapshot.total_earnings,
+        unique_listeners: snapshot.listeners.estimate(),
+        unique_listeners_exact: snapshot.listeners.is_exact(),
+        unique_listeners_error: snapshot.listeners.error_bound(),
+        completed_unique_listeners: snapshot
+            .completed_listeners
+            .as_ref()
+            .map_or(0, ListenerSketch::estimate),
+        avg_completion,
+    }
+}
+
+/// Persist my song's stats so later reads start from here (artist only)
+///
+/// Publishes a snapshot only when plays were folded in since the last one.
+#[hdk_extern]
+pub fn refresh_song_stats(song_hash: ActionHash) -> ExternResult<SongStats> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let artist = latest_song_version(song_hash.clone())?.map(|(_, song)| song.artist);
+    if artist.as_ref() != Some(&my_agent) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the song's artist can publish its stats".to_string()
+        )));
+    }
+
+    let (mut snapshot, folded) = fold_song_stats(&song_hash)?;
+    if folded > 0 {
+        snapshot.updated_at = sys_time()?;
+        let snapshot_hash = create_entry(&EntryTypes::SongStatsSnapshot(snapshot.clone()))?;
+        create_link(song_hash, snapshot_hash, LinkTypes::SongToStats, ())?;
+    }
+
+    Ok(song_stats(&snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_RATE: u64 = 1_000_000;
+
+    fn play_hashes(count: u8) -> Vec<ActionHash> {
+        (0..count).map(|i| ActionHash::from_raw_36(vec![i; 36])).collect()
+    }
+
+    /// What a client sends back after `get_settlement_proof`
+    fn proof_input(algorithm: MerkleAlgorithm, hashes: &[ActionHash], index: usize) -> VerifyProofInput {
+        VerifyProofInput {
+            merkle_algorithm: algorithm,
+            merkle_root: algorithm.root(hashes),
+            play_hash: hashes[index].clone(),
+            index: index as u64,
+            proof: algorithm.proof(hashes, index).unwrap(),
+        }
+    }
+
+    #[test]
+    fn generated_proofs_round_trip() {
+        for algorithm in [MerkleAlgorithm::SortedPairs, MerkleAlgorithm::Sequential] {
+            for count in 1..=9 {
+                let hashes = play_hashes(count);
+                for index in 0..hashes.len() {
+                    assert!(check_merkle_proof(&proof_input(algorithm, &hashes, index)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn proofs_fail_on_any_mismatch() {
+        for algorithm in [MerkleAlgorithm::SortedPairs, MerkleAlgorithm::Sequential] {
+            let hashes = play_hashes(5);
+
+            let mut other_play = proof_input(algorithm, &hashes, 1);
+            other_play.play_hash = ActionHash::from_raw_36(vec![200; 36]);
+            assert!(!check_merkle_proof(&other_play));
+
+            let mut tampered = proof_input(algorithm, &hashes, 1);
+            tampered.proof[0][0] ^= 1;
+            assert!(!check_merkle_proof(&tampered));
+
+            let mut other_root = proof_input(algorithm, &hashes, 1);
+            other_root.merkle_root = algorithm.root(&play_hashes(4));
+            assert!(!check_merkle_proof(&other_root));
+        }
+
+        // The algorithms' roots aren't interchangeable
+        let mut wrong_algorithm = proof_input(MerkleAlgorithm::SortedPairs, &play_hashes(5), 2);
+        wrong_algorithm.merkle_algorithm = MerkleAlgorithm::Sequential;
+        assert!(!check_merkle_proof(&wrong_algorithm));
+    }
+
+    #[test]
+    fn only_sequential_proofs_depend_on_the_index() {
+        let hashes = play_hashes(6);
+
+        // OpenZeppelin sorts each pair, so positions don't matter
+        let mut sorted = proof_input(MerkleAlgorithm::SortedPairs, &hashes, 3);
+        sorted.index = 0;
+        assert!(check_merkle_proof(&sorted));
+
+        let mut sequential = proof_input(MerkleAlgorithm::Sequential, &hashes, 3);
+        sequential.index = 2;
+        assert!(!check_merkle_proof(&sequential));
+    }
+
+    /// 90s song: threshold is 30s, 50% completion is 45s
+    const SONG_SECS: u32 = 90;
+
+    #[test]
+    fn below_threshold_is_free() {
+        for strategy in ["pay_per_stream", "patronage", "premium"] {
+            assert_eq!(calculate_play_amount(BASE_RATE, strategy, 29, SONG_SECS, None, 0), 0);
+        }
+    }
+
+    #[test]
+    fn threshold_boundary_charges_nothing() {
+        for strategy in ["pay_per_stream", "patronage", "premium", "gift"] {
+            assert_eq!(calculate_play_amount(BASE_RATE, strategy, 30, SONG_SECS, None, 0), 0);
+        }
+    }
+
+    #[test]
+    fn half_listen_is_linear_from_threshold() {
+        // (45 - 30) / (90 - 30) = 25% of the full rate
+        assert_eq!(calculate_play_amount(BASE_RATE, "pay_per_stream", 45, SONG_SECS, None, 0), 250_000);
+        assert_eq!(calculate_play_amount(BASE_RATE, "patronage", 45, SONG_SECS, None, 0), 375_000);
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 45, SONG_SECS, None, 0), 500_000);
+        assert_eq!(calculate_play_amount(BASE_RATE, "gift", 45, SONG_SECS, None, 0), 0);
+    }
+
+    #[test]
+    fn full_listen_charges_full_rate() {
+        assert_eq!(calculate_play_amount(BASE_RATE, "pay_per_stream", 90, SONG_SECS, None, 0), 1_000_000);
+        assert_eq!(calculate_play_amount(BASE_RATE, "patronage", 90, SONG_SECS, None, 0), 1_500_000);
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 90, SONG_SECS, None, 0), 2_000_000);
+        assert_eq!(calculate_play_amount(BASE_RATE, "gift", 90, SONG_SECS, None, 0), 0);
+        // Listening past the end (seeks, loops) doesn't overcharge
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 120, SONG_SECS, None, 0), 2_000_000);
+    }
+
+    #[test]
+    fn bonus_scales_the_completion_amount() {
+        // +10% on a full listen
+        assert_eq!(
+            calculate_play_amount(BASE_RATE, "pay_per_stream", 90, SONG_SECS, None, 1_000),
+            1_100_000
+        );
+        // No bonus can make a non-qualifying play paid
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 29, SONG_SECS, None, 1_000), 0);
+    }
+
+    #[test]
+    fn accumulated_remainders_add_up_to_the_exact_total() {
+        // 110s song, threshold 30s: 40s listened is 1/8 of a 999 wei rate,
+        // 124.875 wei
+        let nanowei = play_amount_nanowei(999, "pay_per_stream", 40, 110, None, 0);
+        assert_eq!(nanowei, 124_875_000_000);
+
+        let plays = 8_000;
+        let rounding = EarningsRounding::AccumulateRemainder;
+        let mut carried = 0;
+        let mut accumulated = 0;
+        let mut floored = 0;
+        for _ in 0..plays {
+            let (amount, remainder) = rounding.charge(nanowei, carried);
+            accumulated += amount;
+            carried = remainder;
+            floored += calculate_play_amount(999, "pay_per_stream", 40, 110, None, 0);
+        }
+        // 8000 * 124.875
+        assert_eq!(accumulated, 999_000);
+        assert_eq!(carried, 0);
+        assert_eq!(floored, 992_000);
+    }
+
+    #[test]
+    fn rounding_policies_handle_the_fraction() {
+        // 14285.714... wei
+        let nanowei = play_amount_nanowei(BASE_RATE, "pay_per_stream", 31, 100, None, 0);
+        assert_eq!(EarningsRounding::Floor.charge(nanowei, 0), (14_285, 0));
+        assert_eq!(EarningsRounding::Round.charge(nanowei, 0), (14_286, 0));
+        // Only accumulation uses or changes the carry
+        assert_eq!(EarningsRounding::Round.charge(nanowei, 300_000_000), (14_286, 300_000_000));
+        assert_eq!(
+            EarningsRounding::AccumulateRemainder.charge(nanowei, 300_000_000),
+            (14_286, 14_285_714)
+        );
+        // Whole-wei prices carry nothing
+        assert_eq!(
+            EarningsRounding::AccumulateRemainder.charge(250_000 * NANOWEI_PER_WEI as u128, 0),
+            (250_000, 0)
+        );
+    }
+
+    #[test]
+    fn short_song_threshold_is_half_the_song() {
+        // 40s song: threshold is 20s
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 19, 40, None, 0), 0);
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 30, 40, None, 0), 1_000_000);
+    }
+
+    #[test]
+    fn interlude_override_sets_the_threshold() {
+        // 20s interlude with a 10s override
+        let charge = |listened| {
+            calculate_play_amount(BASE_RATE, "pay_per_stream", listened, 20, Some(10), 0)
+        };
+        assert_eq!(charge(9), 0);
+        assert_eq!(charge(10), 0);
+        // (15 - 10) / (20 - 10) = 50% of the full rate
+        assert_eq!(charge(15), 500_000);
+        assert_eq!(charge(20), 1_000_000);
+    }
+
+    #[test]
+    fn override_replaces_the_default_threshold() {
+        // 90s song: 20s qualifies with a 10s override, not with the default 30s
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 20, SONG_SECS, None, 0), 0);
+        // (20 - 10) / (90 - 10) = 12.5% of the 2x rate
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 20, SONG_SECS, Some(10), 0), 250_000);
+        // An override of the whole song pays only for full listens
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 89, SONG_SECS, Some(90), 0), 0);
+        assert_eq!(calculate_play_amount(BASE_RATE, "premium", 90, SONG_SECS, Some(90), 0), 2_000_000);
+    }
+
+    #[test]
+    fn settled_play_is_counted_once() {
+        let song_hash = ActionHash::from_raw_36(vec![1; 36]);
+        let play = PlayRecord {
+            schema_version: PLAY_RECORD_SCHEMA_VERSION,
+            song_hash: song_hash.clone(),
+            artist: AgentPubKey::from_raw_36(vec![2; 36]),
+            played_at: Timestamp::from_micros(0),
+            duration_listened: SONG_SECS,
+            song_duration: SONG_SECS,
+            min_play_override: None,
+            song_version: None,
+            strategy_id: "pay_per_stream".to_string(),
+            amount_owed: BASE_RATE,
+            settled: false,
+            settlement_hash: None,
+        };
+
+        // Recorded as `created`, then settled by the `settled` update; a
+        // stale link to the update and a repeated link to the create
+        // must not count it again
+        let created = ActionHash::from_raw_36(vec![3; 36]);
+        let settled = ActionHash::from_raw_36(vec![4; 36]);
+        let links = [
+            (created.clone(), created.clone()),
+            (settled, created.clone()),
+            (created.clone(), created),
+        ];
+
+        let mut snapshot = SongStatsSnapshot::empty(song_hash, Timestamp::from_micros(0));
+        let mut counted = HashSet::new();
+        for (target, canonical) in links {
+            if counts_as_new_play(&mut counted, &target, canonical) {
+                snapshot.fold_play(&play, [0; 32], 1_000, 8_000);
+            }
+        }
+        assert_eq!(snapshot.total_plays, 1);
+        assert_eq!(snapshot.total_earnings, BASE_RATE);
+    }
 }
@@ -6,11 +6,22 @@
 
 use hdi::prelude::*;
 
+/// Current PlayRecord schema version
+///
+/// - v0: original layout (no `schema_version` field)
+/// - v1: adds `schema_version`
+/// - v2: adds `min_play_override`
+/// - v3: adds `song_version`
+pub const PLAY_RECORD_SCHEMA_VERSION: u8 = 3;
+
 /// Play record - stored on listener's source chain (FREE!)
 /// This is the magic of Holochain - each play is just a local entry.
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
 pub struct PlayRecord {
+    /// Entry schema version (missing on v0 entries)
+    #[serde(default)]
+    pub schema_version: u8,
     /// Song action hash (reference to catalog)
     pub song_hash: ActionHash,
     /// Artist's agent public key (for aggregation)
@@ -21,6 +32,12 @@ pub struct PlayRecord {
     pub duration_listened: u32,
     /// Total song duration (for completion percentage)
     pub song_duration: u32,
+    /// The song's play threshold override at play time (seconds)
+    #[serde(default)]
+    pub min_play_override: Option<u32>,
+    /// Song version `min_play_override` was read from (None before v3)
+    #[serde(default)]
+    pub song_version: Option<ActionHash>,
     /// Strategy that was active at play time
     pub strategy_id: String,
     /// Calculated micro-payment amount (in wei equivalent)
@@ -31,6 +48,20 @@ pub struct PlayRecord {
     pub settlement_hash: Option<ActionHash>,
 }
 
+impl PlayRecord {
+    /// Upgrade an entry of any prior schema version in memory
+    ///
+    /// New fields must be `#[serde(default)]` so older entries still
+    /// deserialize; any non-default backfill belongs here.
+    pub fn upgraded(mut self) -> Self {
+        // v0 -> v1: nothing to backfill
+        // v1 -> v2: no override, so the default threshold applies
+        // v2 -> v3: the song version wasn't recorded
+        self.schema_version = PLAY_RECORD_SCHEMA_VERSION;
+        self
+    }
+}
+
 /// Play attestation - signed by listener, can be verified
 /// Used when plays need to be proven to others
 #[hdk_entry_helper]
@@ -48,6 +79,40 @@ pub struct PlayAttestation {
     pub listener_signature: Vec<u8>,
 }
 
+/// Canonical payload the listener signs for a PlayAttestation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayReceiptPayload {
+    pub play_hash: ActionHash,
+    pub song_hash: ActionHash,
+    pub artist: AgentPubKey,
+    pub amount_owed: u64,
+}
+
+impl PlayAttestation {
+    /// The payload covered by `listener_signature`
+    pub fn payload(&self) -> PlayReceiptPayload {
+        PlayReceiptPayload {
+            play_hash: self.play_hash.clone(),
+            song_hash: self.song_hash.clone(),
+            artist: self.artist.clone(),
+            amount_owed: self.amount_owed,
+        }
+    }
+
+    /// Decode the listener signature (None if malformed)
+    pub fn signature(&self) -> Option<Signature> {
+        let bytes: [u8; 64] = self.listener_signature.as_slice().try_into().ok()?;
+        Some(Signature(bytes))
+    }
+
+    /// Whether the attestation fields match the play it refers to
+    pub fn matches_play(&self, play: &PlayRecord) -> bool {
+        self.song_hash == play.song_hash
+            && self.artist == play.artist
+            && self.amount_owed == play.amount_owed
+    }
+}
+
 /// Settlement batch - aggregates many plays for efficient on-chain settlement
 /// This is what actually touches the blockchain - batched for efficiency
 #[hdk_entry_helper]
@@ -57,12 +122,43 @@ pub struct SettlementBatch {
     pub artist: AgentPubKey,
     /// Total plays in this batch
     pub play_count: u64,
-    /// Total amount to settle (in wei)
+    /// Total amount to settle to the artist, net of the protocol fee (in wei)
     pub total_amount: u64,
-    /// Play record hashes included
+    /// Amount owed for the plays before the protocol fee (in wei)
+    #[serde(default)]
+    pub gross_amount: u64,
+    /// Protocol fee deducted from this batch (in wei)
+    #[serde(default)]
+    pub protocol_fee: u64,
+    /// Protocol fee per strategy
+    #[serde(default)]
+    pub fee_breakdown: Vec<StrategyFee>,
+    /// Play record hashes included (each play's original create)
     pub play_hashes: Vec<ActionHash>,
+    /// Version of each play the batch priced, by position in `play_hashes`:
+    /// its latest amendment, or the create itself. Empty on batches from
+    /// before plays could be amended, which priced the creates
+    #[serde(default)]
+    pub play_versions: Vec<ActionHash>,
+    /// Latest version of each song with plays in the batch, as checked for
+    /// an earnings freeze when the batch was made
+    ///
+    /// Validation checks each cited version is one of the song's and isn't
+    /// frozen, but can't check it is still the latest: whether a later
+    /// update exists isn't deterministic. That the latest version is
+    /// cited is enforced by the coordinator (`create_settlement_batch`)
+    /// only, so a settler running modified code could cite an older,
+    /// unfrozen version.
+    #[serde(default)]
+    pub song_versions: Vec<ActionHash>,
     /// Merkle root of play hashes (for efficient verification)
     pub merkle_root: Vec<u8>,
+    /// Tree construction behind `merkle_root` (Sequential before it was recorded)
+    #[serde(default = "MerkleAlgorithm::legacy")]
+    pub merkle_algorithm: MerkleAlgorithm,
+    /// Chain and contract the batch settles on (Gnosis before it was recorded)
+    #[serde(default)]
+    pub settlement_target: SettlementTarget,
     /// When this batch was created
     pub created_at: Timestamp,
     /// On-chain settlement status
@@ -71,6 +167,501 @@ pub struct SettlementBatch {
     pub tx_hash: Option<String>,
 }
 
+/// Protocol fee charged on one strategy's share of a settlement batch
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StrategyFee {
+    pub strategy_id: String,
+    /// Owed for this strategy's plays before the fee (in wei)
+    pub gross_amount: u64,
+    pub fee_bps: u32,
+    pub fee: u64,
+}
+
+/// Protocol fee on an amount at a rate in basis points, rounded down
+pub fn protocol_fee(amount: u64, fee_bps: u32) -> u64 {
+    (amount as u128 * fee_bps.min(10_000) as u128 / 10_000) as u64
+}
+
+/// Keccak-256, as used by the settlement contract
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+    Keccak256::digest(data).into()
+}
+
+/// Chain family a settlement is submitted to; each expects its own
+/// signature format from the settlement contract
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementChain {
+    #[default]
+    Gnosis,
+    Optimism,
+    Base,
+}
+
+/// Where a batch's payout is settled on-chain
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SettlementTarget {
+    pub chain: SettlementChain,
+    /// EIP-155 chain id the settlement transaction is signed for
+    pub chain_id: u64,
+    /// Settlement contract that verifies the batch (0x-prefixed address),
+    /// or empty until one is deployed
+    #[serde(default)]
+    pub verifier: String,
+}
+
+impl Default for SettlementTarget {
+    /// Gnosis Chain, where every batch settled before targets were recorded
+    fn default() -> Self {
+        Self {
+            chain: SettlementChain::Gnosis,
+            chain_id: 100,
+            verifier: String::new(),
+        }
+    }
+}
+
+impl SettlementTarget {
+    /// A target needs a chain id, and any verifier must be an address
+    pub fn check(&self) -> Result<(), String> {
+        if self.chain_id == 0 {
+            return Err("Settlement target needs a chain id".to_string());
+        }
+        let is_address = self
+            .verifier
+            .strip_prefix("0x")
+            .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+        if !self.verifier.is_empty() && !is_address {
+            return Err("Settlement verifier must be a 0x-prefixed address".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Merkle tree construction for a settlement batch. Must match the
+/// verifier in the deployed settlement contract, or proofs won't verify.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MerkleAlgorithm {
+    /// OpenZeppelin `MerkleProof`: leaves are the keccak256 of each play
+    /// hash, pairs are hashed smaller-first, and a node without a sibling
+    /// moves up a level unchanged
+    #[default]
+    SortedPairs,
+    /// Original scheme: raw play hashes as leaves, pairs hashed left to
+    /// right, and a node without a sibling hashed with itself
+    Sequential,
+}
+
+impl MerkleAlgorithm {
+    /// Algorithm of batches created before it was recorded on the batch
+    pub fn legacy() -> Self {
+        Self::Sequential
+    }
+
+    /// Leaf for a play hash
+    pub fn leaf(self, play_hash: &ActionHash) -> Vec<u8> {
+        match self {
+            Self::SortedPairs => keccak256(play_hash.get_raw_39()).to_vec(),
+            Self::Sequential => play_hash.get_raw_39().to_vec(),
+        }
+    }
+
+    fn parent(self, left: &[u8], right: &[u8]) -> Vec<u8> {
+        let (first, second) = match self {
+            Self::SortedPairs if right < left => (right, left),
+            _ => (left, right),
+        };
+        keccak256(&[first, second].concat()).to_vec()
+    }
+
+    /// Every level of the tree, leaves first and the root last
+    fn levels(self, play_hashes: &[ActionHash]) -> Vec<Vec<Vec<u8>>> {
+        let mut levels = vec![play_hashes.iter().map(|h| self.leaf(h)).collect::<Vec<_>>()];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match (pair, self) {
+                    ([left, right], _) => self.parent(left, right),
+                    ([lone], Self::SortedPairs) => lone.clone(),
+                    ([lone], Self::Sequential) => self.parent(lone, lone),
+                    _ => unreachable!("chunks(2) yields one or two nodes"),
+                })
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    /// Merkle root of a batch's play hashes (32 zero bytes if empty)
+    pub fn root(self, play_hashes: &[ActionHash]) -> Vec<u8> {
+        if play_hashes.is_empty() {
+            return vec![0u8; 32];
+        }
+        self.levels(play_hashes).pop().and_then(|mut root| root.pop()).unwrap_or_default()
+    }
+
+    /// Sibling hashes from the leaf at `index` up to the root, or None if
+    /// `index` is out of range
+    pub fn proof(self, play_hashes: &[ActionHash], index: usize) -> Option<Vec<Vec<u8>>> {
+        if index >= play_hashes.len() {
+            return None;
+        }
+        let levels = self.levels(play_hashes);
+        let mut proof = Vec::new();
+        let mut position = index;
+        for level in &levels[..levels.len() - 1] {
+            match (level.get(position ^ 1), self) {
+                (Some(sibling), _) => proof.push(sibling.clone()),
+                (None, Self::Sequential) => proof.push(level[position].clone()),
+                // Carried up unchanged; nothing to hash with
+                (None, Self::SortedPairs) => {}
+            }
+            position /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Check a proof from [`Self::proof`] against a root
+    pub fn verify(self, root: &[u8], play_hash: &ActionHash, index: usize, proof: &[Vec<u8>]) -> bool {
+        let mut node = self.leaf(play_hash);
+        let mut position = index;
+        for sibling in proof {
+            node = if position % 2 == 0 {
+                self.parent(&node, sibling)
+            } else {
+                self.parent(sibling, &node)
+            };
+            position /= 2;
+        }
+        node == root
+    }
+}
+
+/// Check a batch's fee accounting: each strategy is charged the rate the
+/// DNA properties set for it and its fee matches that rate, the breakdown
+/// sums to the batch totals, and fee plus net equals gross
+pub fn check_fee_accounting(
+    batch: &SettlementBatch,
+    properties: &PlaysProperties,
+) -> Result<(), String> {
+    let mut gross: u64 = 0;
+    let mut fees: u64 = 0;
+    for entry in &batch.fee_breakdown {
+        if entry.fee_bps != properties.effective_fee_bps(&entry.strategy_id) {
+            return Err(format!(
+                "Protocol fee rate for strategy {} does not match the DNA properties",
+                entry.strategy_id
+            ));
+        }
+        if entry.fee != protocol_fee(entry.gross_amount, entry.fee_bps) {
+            return Err(format!(
+                "Protocol fee for strategy {} does not match its rate",
+                entry.strategy_id
+            ));
+        }
+        gross = gross
+            .checked_add(entry.gross_amount)
+            .ok_or_else(|| "Fee breakdown overflows".to_string())?;
+        fees = fees
+            .checked_add(entry.fee)
+            .ok_or_else(|| "Fee breakdown overflows".to_string())?;
+    }
+
+    if gross != batch.gross_amount || fees != batch.protocol_fee {
+        return Err("Fee breakdown must sum to the batch totals".to_string());
+    }
+
+    if batch.total_amount.checked_add(batch.protocol_fee) != Some(batch.gross_amount) {
+        return Err("Protocol fee plus net amount must equal the gross amount".to_string());
+    }
+
+    Ok(())
+}
+
+/// A batch may hold at most `max_batch_plays` plays (0 for no limit)
+pub fn check_batch_size(play_count: u64, max_batch_plays: u64) -> Result<(), String> {
+    if max_batch_plays > 0 && play_count > max_batch_plays {
+        return Err(format!(
+            "Settlement batches can hold at most {} plays",
+            max_batch_plays
+        ));
+    }
+    Ok(())
+}
+
+/// Each play may appear in a batch only once; a repeated hash would be
+/// paid twice while `play_count` still matches
+pub fn check_unique_play_hashes(batch: &SettlementBatch) -> Result<(), String> {
+    let mut seen = std::collections::BTreeSet::new();
+    for hash in &batch.play_hashes {
+        if !seen.insert(hash) {
+            return Err(format!("Play {} appears more than once in the batch", hash));
+        }
+    }
+    Ok(())
+}
+
+/// A batch's Merkle root must be built with the configured algorithm over
+/// exactly its play hashes, or the settlement contract would check proofs
+/// against plays the batch doesn't hold
+pub fn check_merkle_root(
+    batch: &SettlementBatch,
+    properties: &PlaysProperties,
+) -> Result<(), String> {
+    if batch.merkle_algorithm != properties.merkle_algorithm {
+        return Err("Merkle algorithm must match the merkle_algorithm DNA property".to_string());
+    }
+    if batch.merkle_root != batch.merkle_algorithm.root(&batch.play_hashes) {
+        return Err("Merkle root must be the root of the batch's play hashes".to_string());
+    }
+    Ok(())
+}
+
+/// A batch may only settle its author's own plays of the batch's artist,
+/// and each strategy's plays must add up to its fee breakdown entry
+///
+/// `plays` pairs each play in the batch with the agent who recorded it.
+pub fn check_batch_plays(
+    batch: &SettlementBatch,
+    settler: &AgentPubKey,
+    plays: &[(AgentPubKey, PlayRecord)],
+) -> Result<(), String> {
+    let mut owed: std::collections::BTreeMap<&str, u64> = std::collections::BTreeMap::new();
+    for (author, play) in plays {
+        if author != settler {
+            return Err("Batches can only settle plays recorded by their author".to_string());
+        }
+        if play.artist != batch.artist {
+            return Err("Every play in a batch must be for the batch's artist".to_string());
+        }
+        let amount = owed.entry(play.strategy_id.as_str()).or_default();
+        *amount = amount
+            .checked_add(play.amount_owed)
+            .ok_or_else(|| "Play amounts overflow".to_string())?;
+    }
+
+    let breakdown: std::collections::BTreeMap<&str, u64> = batch
+        .fee_breakdown
+        .iter()
+        .map(|f| (f.strategy_id.as_str(), f.gross_amount))
+        .collect();
+    if breakdown.len() != batch.fee_breakdown.len() {
+        return Err("Fee breakdown lists a strategy more than once".to_string());
+    }
+    if owed != breakdown {
+        return Err("Play amounts must match the fee breakdown".to_string());
+    }
+
+    Ok(())
+}
+
+/// A play is settled once, by a batch, with an update that changes nothing
+/// but its settlement fields
+pub fn check_play_settlement(original: &PlayRecord, updated: &PlayRecord) -> Result<(), String> {
+    if original.settled {
+        return Err("Play is already settled".to_string());
+    }
+    if !updated.settled || updated.settlement_hash.is_none() {
+        return Err("Play updates must mark the play settled by a batch".to_string());
+    }
+
+    let unsettled = PlayRecord {
+        settled: false,
+        settlement_hash: None,
+        ..updated.clone().upgraded()
+    };
+    if unsettled != original.clone().upgraded() {
+        return Err("Settling a play cannot change its other fields".to_string());
+    }
+
+    Ok(())
+}
+
+/// A play can only be settled by a batch for its artist that lists it
+///
+/// `play_hash` is the play's original create: batches keep referencing
+/// creates, so a play's identity survives its own updates. `version` is
+/// the action being updated, which must be the version the batch priced.
+pub fn check_settled_by_batch(
+    batch: &SettlementBatch,
+    play_hash: &ActionHash,
+    version: &ActionHash,
+    play: &PlayRecord,
+) -> Result<(), String> {
+    let index = batch
+        .play_hashes
+        .iter()
+        .position(|h| h == play_hash)
+        .ok_or_else(|| "Settlement batch does not include this play".to_string())?;
+    if batch.play_version(index) != Some(version) {
+        return Err("Settlement batch priced a different version of this play".to_string());
+    }
+    if batch.artist != play.artist {
+        return Err("Settlement batch is for a different artist".to_string());
+    }
+    Ok(())
+}
+
+impl SettlementBatch {
+    /// The version of the play at `index` this batch priced and settles
+    pub fn play_version(&self, index: usize) -> Option<&ActionHash> {
+        match self.play_versions.is_empty() {
+            true => self.play_hashes.get(index),
+            false => self.play_versions.get(index),
+        }
+    }
+}
+
+/// What plays of an artist's own songs may owe, so artists can't farm
+/// earnings or inflate stats from their own balance
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfPlayPolicy {
+    /// Recorded, but owing nothing
+    #[default]
+    Unpaid,
+    /// Paid like anyone else's (e.g. for testing)
+    Paid,
+    /// Not recorded at all
+    Rejected,
+}
+
+/// Check a play (or amendment) against the self-play policy, where
+/// `self_play` is whether its author is the song's artist
+pub fn check_self_play(
+    play: &PlayRecord,
+    self_play: bool,
+    policy: SelfPlayPolicy,
+) -> Result<(), String> {
+    if !self_play {
+        return Ok(());
+    }
+    match policy {
+        SelfPlayPolicy::Paid => Ok(()),
+        SelfPlayPolicy::Unpaid if play.amount_owed == 0 => Ok(()),
+        SelfPlayPolicy::Unpaid => Err("Plays of your own songs owe nothing".to_string()),
+        SelfPlayPolicy::Rejected => Err("Artists cannot play their own songs".to_string()),
+    }
+}
+
+/// Check a play's pricing inputs are the song's own, as of the version it
+/// cites
+///
+/// The listener writes the play, so its artist, strategy, length and
+/// threshold must be copied from the song rather than trusted; otherwise a
+/// play could claim the free gift strategy, or a one-second song, to farm
+/// rewards on a song it never pays for.
+pub fn check_play_matches_song(
+    play: &PlayRecord,
+    song: &catalog_integrity::Song,
+) -> Result<(), String> {
+    if play.artist != song.artist {
+        return Err("Play artist must match the song's artist".to_string());
+    }
+    if play.strategy_id != song.strategy_id {
+        return Err("Play strategy must match the song's strategy".to_string());
+    }
+    if play.song_duration != song.duration_seconds {
+        return Err("Play song duration must match the song's duration".to_string());
+    }
+    if play.min_play_override != song.min_play_override {
+        return Err("Play threshold must match the song's min_play_override".to_string());
+    }
+    Ok(())
+}
+
+/// A play can be amended until `grace_secs` after it was recorded, to
+/// correct what was listened before the amount is locked by settlement
+///
+/// Only the duration and amount can change. The duration may drop, or rise
+/// up to the song's length; the amount may only drop with the duration and
+/// must be zero once the play no longer qualifies. Plays paid when they
+/// were recorded (gift rewards, TEND) can't be amended.
+pub fn check_play_amendment(
+    original: &PlayRecord,
+    amended: &PlayRecord,
+    elapsed_micros: i64,
+    grace_secs: u64,
+) -> Result<(), String> {
+    if original.settled {
+        return Err("Settled plays cannot be amended".to_string());
+    }
+    if amended.settlement_hash.is_some() {
+        return Err("Amendments cannot set a settlement batch".to_string());
+    }
+    if elapsed_micros > grace_secs as i64 * 1_000_000 {
+        return Err("The play's amendment window has closed".to_string());
+    }
+    if original.strategy_id == GIFT_STRATEGY_ID || original.strategy_id == TIME_BARTER_STRATEGY_ID {
+        return Err("Plays paid when recorded cannot be amended".to_string());
+    }
+
+    let unchanged = PlayRecord {
+        duration_listened: original.duration_listened,
+        amount_owed: original.amount_owed,
+        ..amended.clone().upgraded()
+    };
+    if unchanged != original.clone().upgraded() {
+        return Err("Amendments can only change the duration and amount".to_string());
+    }
+
+    if amended.duration_listened > original.duration_listened
+        && amended.duration_listened > amended.song_duration
+    {
+        return Err("Duration can only be corrected up to the song's length".to_string());
+    }
+    if amended.amount_owed < original.amount_owed
+        && amended.duration_listened >= original.duration_listened
+    {
+        return Err("The amount can only drop with the duration".to_string());
+    }
+    if amended.amount_owed > 0
+        && !is_qualifying_play(amended.duration_listened, amended.song_duration, amended.min_play_override)
+    {
+        return Err("Plays below the listen threshold owe nothing".to_string());
+    }
+
+    Ok(())
+}
+
+/// Plays can only be batched once they can no longer be amended
+pub fn check_plays_past_grace(
+    created_at: Timestamp,
+    plays: &[(AgentPubKey, PlayRecord)],
+    grace_secs: u64,
+) -> Result<(), String> {
+    let grace_micros = grace_secs as i64 * 1_000_000;
+    if plays
+        .iter()
+        .any(|(_, play)| created_at.as_micros() - play.played_at.as_micros() < grace_micros)
+    {
+        return Err("Batches can only settle plays past their amendment window".to_string());
+    }
+    Ok(())
+}
+
+/// Plays of songs with frozen earnings can't be settled
+///
+/// `songs` maps each song (by create hash) to whether the version the batch
+/// cites is frozen; every play's song must be cited.
+pub fn check_songs_unfrozen(
+    plays: &[(AgentPubKey, PlayRecord)],
+    songs: &std::collections::BTreeMap<ActionHash, bool>,
+) -> Result<(), String> {
+    for (_, play) in plays {
+        match songs.get(&play.song_hash) {
+            Some(false) => {}
+            Some(true) => {
+                return Err("Settlement batch includes a song with frozen earnings".to_string())
+            }
+            None => return Err("Settlement batch must cite the version of each song".to_string()),
+        }
+    }
+    Ok(())
+}
+
 /// Settlement status
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
@@ -85,104 +676,2204 @@ pub enum SettlementStatus {
     Failed,
 }
 
-/// Link types for plays
-#[hdk_link_types]
-pub enum LinkTypes {
-    /// Listener -> Their play records
-    ListenerToPlays,
-    /// Song -> Play records
-    SongToPlays,
-    /// Artist -> Settlement batches
-    ArtistToSettlements,
-    /// Play -> Settlement batch
-    PlayToSettlement,
+/// Check a settlement batch update only moves it along its lifecycle
+///
+/// Pending -> Submitted -> Confirmed or Failed, and Failed -> Submitted to
+/// resubmit. Submitting records the transaction hash, which then stays
+/// fixed until a resubmission; nothing else about the batch can change.
+pub fn check_settlement_status_change(
+    original: &SettlementBatch,
+    updated: &SettlementBatch,
+) -> Result<(), String> {
+    use SettlementStatus::*;
+    match (&original.status, &updated.status) {
+        (Pending, Submitted) | (Failed, Submitted) => {
+            if updated.tx_hash.as_deref().map_or(true, str::is_empty) {
+                return Err("Submitting a settlement must record its transaction hash".to_string());
+            }
+        }
+        (Submitted, Confirmed) | (Submitted, Failed) => {
+            if updated.tx_hash != original.tx_hash {
+                return Err("A submitted settlement's transaction hash cannot change".to_string());
+            }
+        }
+        (from, to) => {
+            return Err(format!("Settlement cannot move from {:?} to {:?}", from, to));
+        }
+    }
+    let unchanged = SettlementBatch {
+        status: updated.status.clone(),
+        tx_hash: updated.tx_hash.clone(),
+        ..original.clone()
+    };
+    if updated != &unchanged {
+        return Err("Settlement updates can only change the status and tx hash".to_string());
+    }
+    Ok(())
 }
 
-/// Entry types
-#[hdk_entry_types]
-#[unit_enum(UnitEntryTypes)]
-pub enum EntryTypes {
-    PlayRecord(PlayRecord),
-    PlayAttestation(PlayAttestation),
-    SettlementBatch(SettlementBatch),
+/// How an artist wants to be notified of paying plays
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PlaySignalMode {
+    /// One signal per paying play
+    PerPlay,
+    /// At most one aggregated signal per listener per minute
+    PerMinute,
 }
 
-/// Validation
-#[hdk_extern]
-pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
-    match op.flattened::<EntryTypes, LinkTypes>()? {
-        FlatOp::StoreEntry(store_entry) => match store_entry {
-            OpEntry::CreateEntry { app_entry, action } => match app_entry {
-                EntryTypes::PlayRecord(play) => validate_create_play(play, action),
-                EntryTypes::PlayAttestation(attestation) => {
-                    validate_create_attestation(attestation, action)
-                }
-                EntryTypes::SettlementBatch(batch) => validate_create_settlement(batch, action),
-            },
-            _ => Ok(ValidateCallbackResult::Valid),
-        },
-        _ => Ok(ValidateCallbackResult::Valid),
+/// Strategy whose plays are free and earn the listener CGC instead
+pub const GIFT_STRATEGY_ID: &str = "gift";
+
+/// Strategy whose plays are paid in TEND (time credits) instead of wei
+pub const TIME_BARTER_STRATEGY_ID: &str = "time_barter";
+
+/// TEND owed for a time-barter play: listened seconds at `tend_per_minute`,
+/// nothing if the play doesn't qualify
+pub fn tend_play_amount(
+    duration_listened: u32,
+    song_duration: u32,
+    min_play_override: Option<u32>,
+    tend_per_minute: u64,
+) -> u64 {
+    if !is_qualifying_play(duration_listened, song_duration, min_play_override) {
+        return 0;
     }
+    duration_listened.min(song_duration) as u64 * tend_per_minute / 60
 }
 
-fn validate_create_play(play: PlayRecord, _action: Create) -> ExternResult<ValidateCallbackResult> {
-    // Duration listened cannot exceed song duration
-    if play.duration_listened > play.song_duration {
-        return Ok(ValidateCallbackResult::Invalid(
-            "Duration listened cannot exceed song duration".to_string(),
-        ));
+/// Seconds of listening that always qualify a play
+pub const MIN_LISTEN_SECS: u32 = 30;
+
+/// Seconds a listener must reach for a play to count: the song's
+/// override if it has one, otherwise 30 seconds or half the song,
+/// whichever comes first
+pub fn listen_threshold(song_duration: u32, min_play_override: Option<u32>) -> u32 {
+    match min_play_override {
+        Some(secs) => secs.min(song_duration),
+        None => MIN_LISTEN_SECS.min(song_duration / 2),
     }
+}
 
-    // Plays cannot be pre-settled
-    if play.settled {
-        return Ok(ValidateCallbackResult::Invalid(
-            "New plays must have settled=false".to_string(),
-        ));
+/// Whether a play reached the listen threshold
+pub fn is_qualifying_play(
+    duration_listened: u32,
+    song_duration: u32,
+    min_play_override: Option<u32>,
+) -> bool {
+    song_duration > 0
+        && duration_listened.min(song_duration) >= listen_threshold(song_duration, min_play_override)
+}
+
+/// Strategy multiplier applied to the base rate
+pub fn strategy_multiplier(strategy_id: &str) -> f64 {
+    match strategy_id {
+        "premium" => 2.0,
+        "patronage" => 1.5,
+        "gift" => 0.0, // Gift economy = free
+        "time_barter" => 0.0, // Paid in TEND, not wei
+        "pay_per_stream" => 1.0,
+        _ => 1.0,
     }
+}
 
-    // Play must reference a song
-    if play.song_hash.as_ref().is_empty() {
-        return Ok(ValidateCallbackResult::Invalid(
-            "Play must reference a song".to_string(),
-        ));
+/// Calculate payment amount based on strategy
+///
+/// A play qualifies once the listener reaches the threshold: the song's
+/// `min_play_override` if set, otherwise 30 seconds or 50% of the song,
+/// whichever comes first. Below it nothing is owed. From the threshold to
+/// the end of the song the charge rises linearly from 0 to the full rate
+/// (`base_rate * multiplier`), so a listener who skips just after
+/// qualifying pays almost nothing:
+///
+/// ```text
+/// amount = rate * (listened - threshold) / (duration - threshold)
+/// ```
+///
+/// Plays and previews (`preview_play_amount`) are both priced here, so a
+/// preview can't drift from the charge. Fractions of a wei are dropped;
+/// see `play_amount_nanowei` for the exact price.
+pub fn calculate_play_amount(
+    base_rate: u64,
+    strategy_id: &str,
+    duration_listened: u32,
+    song_duration: u32,
+    min_play_override: Option<u32>,
+    bonus_bps: u32,
+) -> u64 {
+    let nanowei = play_amount_nanowei(
+        base_rate,
+        strategy_id,
+        duration_listened,
+        song_duration,
+        min_play_override,
+        bonus_bps,
+    );
+    (nanowei / NANOWEI_PER_WEI as u128) as u64
+}
+
+/// Billionths of a wei: the unit exact play prices and carried
+/// remainders are kept in
+pub const NANOWEI_PER_WEI: u64 = 1_000_000_000;
+
+/// Exact price of a play in nanowei, priced as `calculate_play_amount`
+/// describes but before rounding to whole wei
+pub fn play_amount_nanowei(
+    base_rate: u64,
+    strategy_id: &str,
+    duration_listened: u32,
+    song_duration: u32,
+    min_play_override: Option<u32>,
+    bonus_bps: u32,
+) -> u128 {
+    if !is_qualifying_play(duration_listened, song_duration, min_play_override) {
+        return 0;
     }
 
-    Ok(ValidateCallbackResult::Valid)
+    let listened = duration_listened.min(song_duration);
+    let threshold = listen_threshold(song_duration, min_play_override);
+
+    let multiplier_bps = (strategy_multiplier(strategy_id) * 10_000.0).round() as u128;
+    let rate = base_rate as u128 * multiplier_bps * (NANOWEI_PER_WEI as u128 / 10_000);
+    // An override of the whole song only pays for full listens
+    let amount = match threshold == song_duration {
+        true => rate,
+        false => rate * (listened - threshold) as u128 / (song_duration - threshold) as u128,
+    };
+    amount * (10_000 + bonus_bps as u128) / 10_000
 }
 
-fn validate_create_attestation(
-    _attestation: PlayAttestation,
-    _action: Create,
-) -> ExternResult<ValidateCallbackResult> {
-    // Attestation signature verification would happen here
-    // For now, accept all attestations
-    Ok(ValidateCallbackResult::Valid)
+/// What happens to the fraction of a wei in a play's exact price
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EarningsRounding {
+    /// Dropped, so artists lose up to a wei per play
+    #[default]
+    Floor,
+    /// Rounded to the nearest wei, halves up
+    Round,
+    /// Carried forward per listener and artist (`EarningsRemainder`) and
+    /// added to their next play, so charges add up to the exact total
+    AccumulateRemainder,
 }
 
-fn validate_create_settlement(
-    batch: SettlementBatch,
-    _action: Create,
-) -> ExternResult<ValidateCallbackResult> {
-    // Settlement must have plays
-    if batch.play_count == 0 {
-        return Ok(ValidateCallbackResult::Invalid(
-            "Settlement batch must contain at least one play".to_string(),
-        ));
+impl EarningsRounding {
+    /// Whole wei to charge for an exact price, and the remainder (nanowei)
+    /// to carry to the pair's next play. `carried` is the remainder from
+    /// their last play and only counts when accumulating.
+    pub fn charge(self, nanowei: u128, carried: u64) -> (u64, u64) {
+        let per_wei = NANOWEI_PER_WEI as u128;
+        match self {
+            Self::Floor => ((nanowei / per_wei) as u64, carried),
+            Self::Round => (((nanowei + per_wei / 2) / per_wei) as u64, carried),
+            Self::AccumulateRemainder => {
+                let total = nanowei + carried as u128;
+                ((total / per_wei) as u64, (total % per_wei) as u64)
+            }
+        }
     }
+}
 
-    // Play count must match hashes
-    if batch.play_count as usize != batch.play_hashes.len() {
-        return Ok(ValidateCallbackResult::Invalid(
-            "Play count must match number of play hashes".to_string(),
-        ));
+/// Fraction of a wei a listener's plays of an artist have priced but not
+/// yet charged, under `EarningsRounding::AccumulateRemainder`
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct EarningsRemainder {
+    pub listener: AgentPubKey,
+    pub artist: AgentPubKey,
+    /// Always less than one wei
+    pub nanowei: u64,
+    pub updated_at: Timestamp,
+}
+
+/// CGC earned by a listener for a qualifying gift-economy play
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct RewardAccrual {
+    /// Listener earning the reward
+    pub listener: AgentPubKey,
+    /// Song that was played
+    pub song_hash: ActionHash,
+    /// Play record that earned the reward
+    pub play_hash: ActionHash,
+    /// CGC earned (smallest unit)
+    pub cgc_amount: u64,
+    pub accrued_at: Timestamp,
+}
+
+/// Artist's play-signal preference
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct PlaySignalPreference {
+    pub artist: AgentPubKey,
+    pub mode: PlaySignalMode,
+}
+
+/// HyperLogLog precision (2^10 = 1024 registers, ~3.25% std error)
+pub const HLL_PRECISION: u32 = 10;
+
+/// Unique-listener set commitment
+///
+/// Listeners are stored as salted hashes (keccak256 of song + agent),
+/// never as raw agent keys. Small sets are exact; large sets switch to
+/// a HyperLogLog sketch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ListenerSketch {
+    /// Exact set of salted listener hashes
+    Exact(Vec<[u8; 32]>),
+    /// HyperLogLog registers
+    HyperLogLog(Vec<u8>),
+}
+
+impl ListenerSketch {
+    /// Add a salted listener hash, upgrading to HLL past `exact_threshold`
+    pub fn insert(&mut self, key: [u8; 32], exact_threshold: u64) {
+        match self {
+            ListenerSketch::Exact(keys) => {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+                if keys.len() as u64 > exact_threshold {
+                    let mut registers = vec![0u8; 1 << HLL_PRECISION];
+                    for k in keys.iter() {
+                        hll_insert(&mut registers, k);
+                    }
+                    *self = ListenerSketch::HyperLogLog(registers);
+                }
+            }
+            ListenerSketch::HyperLogLog(registers) => hll_insert(registers, &key),
+        }
     }
 
-    // New settlements must be pending
-    if batch.status != SettlementStatus::Pending {
-        return Ok(ValidateCallbackResult::Invalid(
-            "New settlements must have Pending status".to_string(),
-        ));
+    /// Estimated unique listener count
+    pub fn estimate(&self) -> u64 {
+        match self {
+            ListenerSketch::Exact(keys) => keys.len() as u64,
+            ListenerSketch::HyperLogLog(registers) => {
+                let m = registers.len() as f64;
+                let alpha = 0.7213 / (1.0 + 1.079 / m);
+                let sum: f64 = registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+                let raw = alpha * m * m / sum;
+
+                // Small-range correction (linear counting)
+                let zeros = registers.iter().filter(|r| **r == 0).count() as f64;
+                if raw <= 2.5 * m && zeros > 0.0 {
+                    (m * (m / zeros).ln()).round() as u64
+                } else {
+                    raw.round() as u64
+                }
+            }
+        }
     }
 
-    Ok(ValidateCallbackResult::Valid)
+    /// Relative standard error of `estimate` (0 when exact)
+    pub fn error_bound(&self) -> f64 {
+        match self {
+            ListenerSketch::Exact(_) => 0.0,
+            ListenerSketch::HyperLogLog(registers) => 1.04 / (registers.len() as f64).sqrt(),
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, ListenerSketch::Exact(_))
+    }
+}
+
+impl Default for ListenerSketch {
+    fn default() -> Self {
+        ListenerSketch::Exact(Vec::new())
+    }
+}
+
+fn hll_insert(registers: &mut [u8], key: &[u8; 32]) {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&key[..8]);
+    let hash = u64::from_be_bytes(bytes);
+
+    let index = (hash >> (64 - HLL_PRECISION)) as usize;
+    let rest = hash << HLL_PRECISION;
+    let rank = (rest.leading_zeros().min(64 - HLL_PRECISION) + 1) as u8;
+
+    if rank > registers[index] {
+        registers[index] = rank;
+    }
+}
+
+/// Running play statistics for a song
+/// Folded forward from new SongToPlays links instead of rescanning.
+/// Only the song's artist writes snapshots (`refresh_song_stats`).
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct SongStatsSnapshot {
+    pub song_hash: ActionHash,
+    pub total_plays: u64,
+    pub total_earnings: u64,
+    /// Sum of completion ratios (for average completion)
+    pub total_completion: f64,
+    pub listeners: ListenerSketch,
+    /// Listeners with a play reaching `reach_completion_bps`. None on
+    /// snapshots from before reach was tracked; those are recounted.
+    #[serde(default)]
+    pub completed_listeners: Option<ListenerSketch>,
+    /// Timestamp of the newest SongToPlays link folded in
+    pub counted_through: Option<Timestamp>,
+    /// keccak256 of the sorted hashes of every SongToPlays link folded in.
+    /// A reader whose links up to `counted_through` hash differently (a
+    /// play that arrived late, or a snapshot from before this field)
+    /// recounts instead of trusting the totals.
+    #[serde(default)]
+    pub folded_links_digest: Vec<u8>,
+    pub updated_at: Timestamp,
+}
+
+impl SongStatsSnapshot {
+    pub fn empty(song_hash: ActionHash, now: Timestamp) -> Self {
+        Self {
+            song_hash,
+            total_plays: 0,
+            total_earnings: 0,
+            total_completion: 0.0,
+            listeners: ListenerSketch::default(),
+            completed_listeners: Some(ListenerSketch::default()),
+            counted_through: None,
+            folded_links_digest: Vec::new(),
+            updated_at: now,
+        }
+    }
+
+    /// Fold one play into the running totals
+    ///
+    /// The listener counts toward completed reach if this play reaches
+    /// `reach_completion_bps`; the sketch dedupes, so a listener counts
+    /// once if their best play does.
+    pub fn fold_play(
+        &mut self,
+        play: &PlayRecord,
+        listener_key: [u8; 32],
+        exact_threshold: u64,
+        reach_completion_bps: u32,
+    ) {
+        self.total_plays += 1;
+        self.total_earnings += play.amount_owed;
+        if play.song_duration > 0 {
+            self.total_completion += play.duration_listened as f64 / play.song_duration as f64;
+        }
+        self.listeners.insert(listener_key, exact_threshold);
+        if reaches_completion(play.duration_listened, play.song_duration, reach_completion_bps) {
+            self.completed_listeners
+                .get_or_insert_with(ListenerSketch::default)
+                .insert(listener_key, exact_threshold);
+        }
+    }
+}
+
+/// Running total of what a listener owes, maintained by `record_play`
+/// and settlement batching so balance queries don't rescan every play
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct OwedBalance {
+    pub listener: AgentPubKey,
+    /// Total owed across all artists (in wei)
+    pub total_amount: u64,
+    pub play_count: u64,
+    pub by_artist: Vec<ArtistOwed>,
+    pub updated_at: Timestamp,
+}
+
+/// Amount owed to one artist
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArtistOwed {
+    pub artist: AgentPubKey,
+    pub amount: u64,
+    pub play_count: u64,
+    /// When the last per-minute play signal to this artist was sent
+    #[serde(default)]
+    pub signalled_at: Option<Timestamp>,
+    /// Paying plays since then, for the next per-minute signal
+    #[serde(default)]
+    pub unsignalled: UnsignalledPlays,
+}
+
+/// Paying plays to one artist not yet reported in a per-minute signal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct UnsignalledPlays {
+    pub play_count: u64,
+    pub amount: u64,
+    pub first_played_at: Option<Timestamp>,
+    pub last_played_at: Option<Timestamp>,
+}
+
+impl OwedBalance {
+    pub fn empty(listener: AgentPubKey, now: Timestamp) -> Self {
+        Self {
+            listener,
+            total_amount: 0,
+            play_count: 0,
+            by_artist: Vec::new(),
+            updated_at: now,
+        }
+    }
+
+    /// Count a new unsettled play
+    pub fn add_play(&mut self, artist: &AgentPubKey, amount: u64) {
+        self.total_amount += amount;
+        self.play_count += 1;
+        match self.by_artist.iter_mut().find(|a| &a.artist == artist) {
+            Some(owed) => {
+                owed.amount += amount;
+                owed.play_count += 1;
+            }
+            None => self.by_artist.push(ArtistOwed {
+                artist: artist.clone(),
+                amount,
+                play_count: 1,
+                signalled_at: None,
+                unsignalled: UnsignalledPlays::default(),
+            }),
+        }
+    }
+
+    /// Count a paying play (already added) toward a per-minute signal
+    ///
+    /// The first play in each `window_micros` window returns the plays to
+    /// report: every one since the previous signal, itself included. Later
+    /// plays in the same window wait for the next window or a flush.
+    pub fn signal_play(
+        &mut self,
+        artist: &AgentPubKey,
+        amount: u64,
+        played_at: Timestamp,
+        window_micros: i64,
+    ) -> Option<UnsignalledPlays> {
+        let owed = self.by_artist.iter_mut().find(|a| &a.artist == artist)?;
+        let pending = &mut owed.unsignalled;
+        pending.play_count += 1;
+        pending.amount += amount;
+        pending.first_played_at.get_or_insert(played_at);
+        pending.last_played_at = Some(played_at);
+
+        let window = |t: Timestamp| t.as_micros().div_euclid(window_micros);
+        if owed.signalled_at.map_or(false, |at| window(at) == window(played_at)) {
+            return None;
+        }
+        owed.signalled_at = Some(played_at);
+        Some(std::mem::take(&mut owed.unsignalled))
+    }
+
+    /// Take the plays waiting to be reported to an artist, if any
+    pub fn flush_signal(
+        &mut self,
+        artist: &AgentPubKey,
+        now: Timestamp,
+    ) -> Option<UnsignalledPlays> {
+        let owed = self.by_artist.iter_mut().find(|a| &a.artist == artist)?;
+        if owed.unsignalled.play_count == 0 {
+            return None;
+        }
+        owed.signalled_at = Some(now);
+        Some(std::mem::take(&mut owed.unsignalled))
+    }
+
+    /// Remove plays that were batched for settlement
+    pub fn remove_settled(&mut self, artist: &AgentPubKey, play_count: u64, amount: u64) {
+        self.total_amount = self.total_amount.saturating_sub(amount);
+        self.play_count = self.play_count.saturating_sub(play_count);
+        if let Some(owed) = self.by_artist.iter_mut().find(|a| &a.artist == artist) {
+            owed.amount = owed.amount.saturating_sub(amount);
+            owed.play_count = owed.play_count.saturating_sub(play_count);
+        }
+        self.by_artist.retain(|a| a.play_count > 0);
+    }
+
+    /// Whether a change from `before` took the total owed up to `threshold`
+    ///
+    /// True only on the crossing, so a listener is prompted once per build-up
+    /// rather than on every play past it.
+    pub fn crossed_threshold(&self, before: u64, threshold: u64) -> bool {
+        threshold > 0 && before < threshold && self.total_amount >= threshold
+    }
+
+    /// Re-count an unsettled play whose amount was amended
+    pub fn amend_play(&mut self, artist: &AgentPubKey, old_amount: u64, new_amount: u64) {
+        if old_amount > 0 {
+            self.remove_settled(artist, 1, old_amount);
+        }
+        if new_amount > 0 {
+            self.add_play(artist, new_amount);
+        }
+    }
+}
+
+/// DNA properties controlling play pricing
+/// Lets the platform adjust rates without a zome redeploy.
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PlaysProperties {
+    /// Payment for one full play at 1x multiplier (in wei)
+    pub base_rate_wei: u64,
+    /// Intended fiat price per full play (millionths of a unit, 1000 = $0.001)
+    pub fiat_price_micros: u64,
+    /// Fiat currency code for display
+    pub fiat_currency: String,
+    /// Per-strategy settlement policies
+    pub strategies: Vec<StrategyConfig>,
+    /// Unique listeners tracked exactly up to this count, then estimated
+    pub exact_listener_threshold: u64,
+    /// Completion (basis points) a listener's best play must reach to count
+    /// toward a song's `completed_unique_listeners`
+    pub reach_completion_bps: u32,
+    /// CGC (smallest unit) a listener earns per qualifying gift-economy play
+    pub gift_reward_cgc: u64,
+    /// Agent that receives protocol fees (no fee is charged when unset)
+    pub platform_agent: Option<AgentPubKey>,
+    /// TEND charged per minute listened on time-barter plays
+    pub tend_per_minute: u64,
+    /// Most plays one settlement batch may hold (0 for no limit). Each
+    /// play adds its hash to the on-chain calldata, so this keeps a
+    /// batch's submission within block gas limits.
+    pub max_batch_plays: u64,
+    /// Merkle construction the deployed settlement contract verifies
+    pub merkle_algorithm: MerkleAlgorithm,
+    /// Seconds after recording a play can be amended (`amend_play`); plays
+    /// only become settleable once it has passed
+    pub amend_grace_secs: u64,
+    /// Owed total (in wei) at which a listener is prompted to settle (0 never)
+    pub settlement_nudge_wei: u64,
+    /// How plays of an artist's own songs are treated
+    pub self_plays: SelfPlayPolicy,
+    /// Chain and contract new batches settle on
+    pub settlement_target: SettlementTarget,
+    /// How fractions of a wei in play prices are handled
+    pub earnings_rounding: EarningsRounding,
+    /// Settlement drivers allowed to report any batch's on-chain status
+    /// (the batch's listener can always report their own)
+    pub settlement_agents: Vec<AgentPubKey>,
+}
+
+impl PlaysProperties {
+    /// Policy for a strategy (default policy if not configured)
+    pub fn strategy_config(&self, strategy_id: &str) -> StrategyConfig {
+        self.strategies
+            .iter()
+            .find(|s| s.strategy_id == strategy_id)
+            .cloned()
+            .unwrap_or_else(|| StrategyConfig {
+                strategy_id: strategy_id.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Fee actually deducted from a strategy's settlements (basis points)
+    ///
+    /// Fees only apply when there is a platform agent to receive them.
+    pub fn effective_fee_bps(&self, strategy_id: &str) -> u32 {
+        match self.platform_agent {
+            Some(_) => self.strategy_config(strategy_id).protocol_fee_bps,
+            None => 0,
+        }
+    }
+}
+
+/// Settlement batching policy for one strategy
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct StrategyConfig {
+    pub strategy_id: String,
+    /// Minimum unsettled plays before a batch is created
+    pub min_plays: u64,
+    /// Minimum total owed (in wei) before a batch is created
+    pub min_total_amount: u64,
+    /// Force settlement once the oldest unsettled play is this old (seconds)
+    pub max_age_secs: Option<u64>,
+    /// Protocol fee deducted from this strategy's settlements (basis points)
+    pub protocol_fee_bps: u32,
+    /// Extra pay for high-completion plays; the highest tier reached applies
+    pub completion_bonuses: Vec<CompletionBonus>,
+    /// Extra pay when a listener fully plays a song they've fully played
+    /// before (basis points). None skips the source-chain scan entirely
+    pub repeat_listen_bonus_bps: Option<u32>,
+}
+
+/// A completion-bonus tier, e.g. +10% (1000 bps) at >= 90% (9000 bps)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CompletionBonus {
+    /// Completion needed for this tier (basis points of the song)
+    pub min_completion_bps: u32,
+    pub bonus_bps: u32,
+}
+
+/// Whether a play reached the end of the song
+pub fn is_full_listen(duration_listened: u32, song_duration: u32) -> bool {
+    song_duration > 0 && duration_listened >= song_duration
+}
+
+/// Whether a listen reaches a completion threshold (basis points, inclusive)
+pub fn reaches_completion(
+    duration_listened: u32,
+    song_duration: u32,
+    completion_bps: u32,
+) -> bool {
+    song_duration > 0
+        && duration_listened.min(song_duration) as u64 * 10_000
+            >= completion_bps as u64 * song_duration as u64
+}
+
+/// Total bonus (basis points) a play earns under a strategy's tiers
+///
+/// Tier boundaries are inclusive. The repeat bonus stacks on top of the
+/// completion tier and only applies to full listens.
+pub fn completion_bonus_bps(
+    config: &StrategyConfig,
+    duration_listened: u32,
+    song_duration: u32,
+    min_play_override: Option<u32>,
+    repeat_listen: bool,
+) -> u32 {
+    if !is_qualifying_play(duration_listened, song_duration, min_play_override) {
+        return 0;
+    }
+
+    let listened = duration_listened.min(song_duration) as u64;
+    let completion_bps = listened * 10_000 / song_duration as u64;
+    let tier_bps = config
+        .completion_bonuses
+        .iter()
+        .filter(|tier| completion_bps >= tier.min_completion_bps as u64)
+        .map(|tier| tier.bonus_bps)
+        .max()
+        .unwrap_or(0);
+
+    let repeat_bps = match config.repeat_listen_bonus_bps {
+        Some(bps) if repeat_listen && is_full_listen(duration_listened, song_duration) => bps,
+        _ => 0,
+    };
+
+    tier_bps.saturating_add(repeat_bps)
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self {
+            strategy_id: String::new(),
+            min_plays: 1,
+            min_total_amount: 0,
+            max_age_secs: None,
+            protocol_fee_bps: 100,
+            completion_bonuses: Vec::new(),
+            repeat_listen_bonus_bps: None,
+        }
+    }
+}
+
+impl Default for PlaysProperties {
+    fn default() -> Self {
+        Self {
+            // 0.0004 xDAI (~$0.001 at $0.40/xDAI)
+            base_rate_wei: 400_000_000_000_000,
+            fiat_price_micros: 1_000,
+            fiat_currency: "USD".to_string(),
+            strategies: Vec::new(),
+            exact_listener_threshold: 1_000,
+            reach_completion_bps: 8_000,
+            // 0.01 CGC (18 decimals)
+            gift_reward_cgc: 10_000_000_000_000_000,
+            platform_agent: None,
+            // One TEND per second listened
+            tend_per_minute: 60,
+            max_batch_plays: 500,
+            merkle_algorithm: MerkleAlgorithm::SortedPairs,
+            amend_grace_secs: 300,
+            // 0.1 xDAI, 250 full plays at the default rate
+            settlement_nudge_wei: 100_000_000_000_000_000,
+            self_plays: SelfPlayPolicy::Unpaid,
+            settlement_target: SettlementTarget::default(),
+            earnings_rounding: EarningsRounding::Floor,
+            settlement_agents: Vec::new(),
+        }
+    }
+}
+
+/// Parse plays settings from DNA properties; defaults if none are set
+///
+/// Malformed properties are an error rather than the defaults, so a typo in
+/// a fee or threshold can't go unnoticed.
+pub fn parse_plays_properties(properties: SerializedBytes) -> Result<PlaysProperties, String> {
+    if properties_unset(&properties) {
+        return Ok(PlaysProperties::default());
+    }
+    PlaysProperties::try_from(properties)
+        .map_err(|e| format!("Malformed plays DNA properties: {}", e))
+}
+
+/// Read plays settings from DNA properties
+pub fn plays_properties() -> ExternResult<PlaysProperties> {
+    parse_plays_properties(dna_info()?.modifiers.properties)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))
+}
+
+/// Whether the DNA manifest sets no properties (empty, or msgpack nil)
+fn properties_unset(properties: &SerializedBytes) -> bool {
+    matches!(properties.bytes().as_slice(), [] | [0xc0])
+}
+
+/// Link types for plays
+#[hdk_link_types]
+pub enum LinkTypes {
+    /// Listener -> Their play records
+    ListenerToPlays,
+    /// Song -> Play records
+    SongToPlays,
+    /// Artist -> Settlement batches
+    ArtistToSettlements,
+    /// Play -> Settlement batch
+    PlayToSettlement,
+    /// Song -> Stats snapshots
+    SongToStats,
+    /// Artist -> Play-signal preference
+    ArtistToSignalPreference,
+    /// all_pending_settlements anchor -> Pending settlement batches
+    AllPendingSettlements,
+    /// Listener -> CGC reward accruals
+    ListenerToRewards,
+    /// Listener -> OwedBalance counter snapshot; superseded links are
+    /// deleted, leaving the latest
+    ListenerToOwedBalance,
+    /// Song -> Play attestations (receipts)
+    SongToAttestations,
+    /// Listener/artist pair -> EarningsRemainder snapshot; superseded links
+    /// are deleted, leaving the latest
+    PairToEarningsRemainder,
+    /// Song -> Listener heartbeating it; live until the link is older than
+    /// the presence TTL
+    SongToListeners,
+}
+
+/// Entry types
+#[hdk_entry_types]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    PlayRecord(PlayRecord),
+    PlayAttestation(PlayAttestation),
+    SettlementBatch(SettlementBatch),
+    SongStatsSnapshot(SongStatsSnapshot),
+    PlaySignalPreference(PlaySignalPreference),
+    RewardAccrual(RewardAccrual),
+    OwedBalance(OwedBalance),
+    EarningsRemainder(EarningsRemainder),
+}
+
+/// Validation
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(store_entry) => match store_entry {
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::PlayRecord(play),
+                action,
+                original_action_hash,
+                original_entry_hash: _,
+            } => validate_update_play(play, action, original_action_hash),
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::SettlementBatch(batch),
+                action,
+                original_action_hash,
+                original_entry_hash: _,
+            } => validate_update_settlement(batch, action, original_action_hash),
+            OpEntry::CreateEntry { app_entry, action } => match app_entry {
+                EntryTypes::PlayRecord(play) => validate_create_play(play, action),
+                EntryTypes::PlayAttestation(attestation) => {
+                    validate_create_attestation(attestation, action)
+                }
+                EntryTypes::SettlementBatch(batch) => validate_create_settlement(batch, action),
+                EntryTypes::SongStatsSnapshot(snapshot) => {
+                    validate_create_stats_snapshot(snapshot, action)
+                }
+                EntryTypes::PlaySignalPreference(preference) => {
+                    validate_create_signal_preference(preference, action)
+                }
+                EntryTypes::RewardAccrual(reward) => validate_create_reward(reward, action),
+                EntryTypes::OwedBalance(balance) => {
+                    // Listeners keep their own counter
+                    if balance.listener != action.author {
+                        return Ok(ValidateCallbackResult::Invalid(
+                            "Owed balance listener must match action author".to_string(),
+                        ));
+                    }
+                    Ok(ValidateCallbackResult::Valid)
+                }
+                EntryTypes::EarningsRemainder(remainder) => {
+                    if remainder.listener != action.author {
+                        return Ok(ValidateCallbackResult::Invalid(
+                            "Earnings remainder listener must match action author".to_string(),
+                        ));
+                    }
+                    // Whole wei are charged on the next play, not carried
+                    if remainder.nanowei >= NANOWEI_PER_WEI {
+                        return Ok(ValidateCallbackResult::Invalid(
+                            "Earnings remainder must be less than one wei".to_string(),
+                        ));
+                    }
+                    Ok(ValidateCallbackResult::Valid)
+                }
+            },
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::SongToStats,
+            base_address,
+            target_address,
+            tag: _,
+            action,
+        } => validate_stats_link(base_address, target_address, action),
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::SongToListeners,
+            base_address: _,
+            target_address,
+            tag: _,
+            action,
+        } => {
+            // Listeners only announce themselves
+            if target_address != AnyLinkableHash::from(action.author) {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Presence links must point to their author".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+        FlatOp::RegisterDeleteLink {
+            link_type: LinkTypes::SongToListeners,
+            original_action,
+            action,
+            ..
+        } => {
+            if action.author != original_action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the listener can remove their presence".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+        FlatOp::RegisterDeleteLink {
+            link_type: LinkTypes::ListenerToOwedBalance | LinkTypes::PairToEarningsRemainder,
+            original_action,
+            action,
+            ..
+        } => {
+            // Readers take the live link, so nobody else may remove it
+            if action.author != original_action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the listener can remove their snapshots".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// The artist of the catalog song at `song_hash`, if it is one
+fn song_artist(song_hash: ActionHash) -> ExternResult<Option<AgentPubKey>> {
+    Ok(must_get_valid_record(song_hash)?
+        .entry()
+        .to_app_option::<catalog_integrity::Song>()
+        .map_err(|e| wasm_error!(e))?
+        .map(|song| song.artist))
+}
+
+fn validate_create_stats_snapshot(
+    snapshot: SongStatsSnapshot,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    // Readers take the latest snapshot, so only the artist may publish one
+    if song_artist(snapshot.song_hash)?.as_ref() != Some(&action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the song's artist can publish its stats".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_stats_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Stats links must point to a snapshot".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(target)?;
+    let snapshot = match record
+        .entry()
+        .to_app_option::<SongStatsSnapshot>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(snapshot) => snapshot,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Stats links must point to a snapshot".to_string(),
+            ))
+        }
+    };
+    if record.action().author() != &action.author
+        || base_address.into_action_hash().as_ref() != Some(&snapshot.song_hash)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Stats links must be made by the snapshot's author from its song".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_create_play(play: PlayRecord, action: Create) -> ExternResult<ValidateCallbackResult> {
+    // New plays must use the current schema
+    if play.schema_version != PLAY_RECORD_SCHEMA_VERSION {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "New plays must use schema version {}",
+            PLAY_RECORD_SCHEMA_VERSION
+        )));
+    }
+
+    // Duration listened cannot exceed song duration
+    if play.duration_listened > play.song_duration {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Duration listened cannot exceed song duration".to_string(),
+        ));
+    }
+
+    // Plays cannot be pre-settled
+    if play.settled {
+        return Ok(ValidateCallbackResult::Invalid(
+            "New plays must have settled=false".to_string(),
+        ));
+    }
+
+    // Play must reference a song
+    if play.song_hash.as_ref().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Play must reference a song".to_string(),
+        ));
+    }
+
+    // The artist, pricing and listen threshold are the song's, as of the
+    // version the play cites
+    let song_version = match &play.song_version {
+        Some(song_version) => song_version.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Play must cite the song version it was recorded against".to_string(),
+            ))
+        }
+    };
+    if original_create_hash(song_version.clone())? != play.song_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Play song version must be a version of its song".to_string(),
+        ));
+    }
+    let song = must_get_valid_record(song_version)?
+        .entry()
+        .to_app_option::<catalog_integrity::Song>()
+        .map_err(|e| wasm_error!(e))?;
+    let song = match song {
+        Some(song) => song,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Play song version is not a song".to_string(),
+            ))
+        }
+    };
+    if let Err(reason) = check_play_matches_song(&play, &song) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    validate_self_play(&play, &action.author)
+}
+
+/// Check a play (or amendment) by `author` against the self-play policy,
+/// comparing with the artist on the song itself rather than the play's
+/// claimed `artist`
+fn validate_self_play(
+    play: &PlayRecord,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let song = must_get_valid_record(play.song_hash.clone())?
+        .entry()
+        .to_app_option::<catalog_integrity::Song>()
+        .map_err(|e| wasm_error!(e))?;
+    let song = match song {
+        Some(song) => song,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Play must reference a song".to_string(),
+            ))
+        }
+    };
+
+    let properties = plays_properties()?;
+    match check_self_play(play, &song.artist == author, properties.self_plays) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(reason) => Ok(ValidateCallbackResult::Invalid(reason)),
+    }
+}
+
+fn validate_update_play(
+    play: PlayRecord,
+    action: Update,
+    original_action_hash: ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let record = must_get_valid_record(original_action_hash)?;
+    let original = match record
+        .entry()
+        .to_app_option::<PlayRecord>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not a play record".to_string(),
+            ))
+        }
+    };
+
+    // Plays live on the listener's chain; only they amend or settle them
+    if record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the listener can update their plays".to_string(),
+        ));
+    }
+
+    let play_hash = original_create_hash(original_action_hash.clone())?;
+    if !play.settled {
+        let recorded_at = must_get_action(play_hash)?.action().timestamp();
+        let properties = plays_properties()?;
+        if let Err(reason) = check_play_amendment(
+            &original,
+            &play,
+            action.timestamp.as_micros() - recorded_at.as_micros(),
+            properties.amend_grace_secs,
+        ) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+        return validate_self_play(&play, &action.author);
+    }
+
+    if let Err(reason) = check_play_settlement(&original, &play) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // The batch must exist, list the play by its original create and have
+    // priced the version being settled
+    let batch_hash = match play.settlement_hash.clone() {
+        Some(batch_hash) => batch_hash,
+        None => return Ok(ValidateCallbackResult::Invalid("Settled play has no batch".to_string())),
+    };
+    let batch = match must_get_valid_record(batch_hash)?
+        .entry()
+        .to_app_option::<SettlementBatch>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(batch) => batch,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Settlement hash must reference a settlement batch".to_string(),
+            ))
+        }
+    };
+    if let Err(reason) = check_settled_by_batch(&batch, &play_hash, &original_action_hash, &play) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // A fork of the update chain could otherwise settle the play twice
+    if let Some(earlier) = prior_settlement(&action.author, &action.prev_action, &play_hash)? {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Play was already settled by update {}",
+            earlier
+        )));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// An earlier settled version of the play created at `play_hash`, on the
+/// author's chain up to and including `until`
+///
+/// Versions can only follow the play's create, so the walk stops there.
+fn prior_settlement(
+    author: &AgentPubKey,
+    until: &ActionHash,
+    play_hash: &ActionHash,
+) -> ExternResult<Option<ActionHash>> {
+    let play_def = ScopedEntryDefIndex::try_from(UnitEntryTypes::PlayRecord)?;
+    let filter = ChainFilter::new(until.clone()).until(play_hash.clone());
+    let activity = must_get_agent_activity(author.clone(), filter)?;
+
+    for item in activity {
+        if let Action::Update(update) = &item.action.hashed.content {
+            let is_play = matches!(
+                &update.entry_type,
+                EntryType::App(def)
+                    if def.zome_index == play_def.zome_index
+                        && def.entry_index == play_def.zome_type
+            );
+            if !is_play {
+                continue;
+            }
+            let entry = must_get_entry(update.entry_hash.clone())?;
+            if !matches!(PlayRecord::try_from(entry.content), Ok(play) if play.settled) {
+                continue;
+            }
+            if &original_create_hash(update.original_action_address.clone())? == play_hash {
+                return Ok(Some(item.action.hashed.hash.clone()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn validate_update_settlement(
+    batch: SettlementBatch,
+    action: Update,
+    original_action_hash: ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let original = match must_get_valid_record(original_action_hash.clone())?
+        .entry()
+        .to_app_option::<SettlementBatch>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(original) => original,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Original entry is not a settlement batch".to_string(),
+            ))
+        }
+    };
+
+    // The listener who made the batch, or a configured settlement driver
+    let batch_author = must_get_action(original_create_hash(original_action_hash)?)?
+        .action()
+        .author()
+        .clone();
+    let properties = plays_properties()?;
+    if action.author != batch_author && !properties.settlement_agents.contains(&action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the batch's listener or a settlement agent can update its status".to_string(),
+        ));
+    }
+
+    if let Err(reason) = check_settlement_status_change(&original, &batch) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// The create action a chain of updates descends from
+fn original_create_hash(mut action_hash: ActionHash) -> ExternResult<ActionHash> {
+    loop {
+        match must_get_action(action_hash.clone())?.action() {
+            Action::Update(update) => action_hash = update.original_action_address.clone(),
+            _ => return Ok(action_hash),
+        }
+    }
+}
+
+fn validate_create_attestation(
+    attestation: PlayAttestation,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    // Only the listener who recorded the play can attest to it
+    let play_record = must_get_valid_record(attestation.play_hash.clone())?;
+    if play_record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Attestation must be created by the listener who recorded the play".to_string(),
+        ));
+    }
+
+    let play = match play_record
+        .entry()
+        .to_app_option::<PlayRecord>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(play) => play.upgraded(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Attestation must reference a play record".to_string(),
+            ))
+        }
+    };
+
+    if !attestation.matches_play(&play) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Attestation does not match the referenced play".to_string(),
+        ));
+    }
+
+    let signature = match attestation.signature() {
+        Some(signature) => signature,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Listener signature must be 64 bytes".to_string(),
+            ))
+        }
+    };
+
+    if !verify_signature(action.author, signature, attestation.payload())? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Invalid listener signature".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_create_signal_preference(
+    preference: PlaySignalPreference,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    // Artists set their own preference
+    if preference.artist != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Signal preference artist must match the action author".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn validate_create_reward(
+    reward: RewardAccrual,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    // Listeners accrue their own rewards
+    if reward.listener != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Reward listener must match the action author".to_string(),
+        ));
+    }
+
+    let play_record = must_get_valid_record(reward.play_hash.clone())?;
+    if play_record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Rewards can only accrue for the listener's own plays".to_string(),
+        ));
+    }
+
+    let play = match play_record
+        .entry()
+        .to_app_option::<PlayRecord>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(play) => play.upgraded(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Reward must reference a play record".to_string(),
+            ))
+        }
+    };
+
+    if play.strategy_id != GIFT_STRATEGY_ID || play.song_hash != reward.song_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Rewards only accrue for gift-economy plays of the same song".to_string(),
+        ));
+    }
+
+    // Skipped plays earn nothing, so replaying intros can't farm CGC
+    if !is_qualifying_play(play.duration_listened, play.song_duration, play.min_play_override) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Rewards only accrue for qualifying plays".to_string(),
+        ));
+    }
+
+    let properties = plays_properties()?;
+    if reward.cgc_amount != properties.gift_reward_cgc {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Reward amount must match the configured per-play rate".to_string(),
+        ));
+    }
+
+    // One reward per play
+    let earlier = rewards_since(&action.author, &action.prev_action, &reward.play_hash)?;
+    if earlier.iter().any(|earlier| earlier.play_hash == reward.play_hash) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "This play has already earned its reward".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Rewards accrued on an author's chain from `since` up to and including
+/// `until`
+///
+/// A reward can only follow its play, so the walk stops at the play.
+fn rewards_since(
+    author: &AgentPubKey,
+    until: &ActionHash,
+    since: &ActionHash,
+) -> ExternResult<Vec<RewardAccrual>> {
+    let reward_def = ScopedEntryDefIndex::try_from(UnitEntryTypes::RewardAccrual)?;
+    let filter = ChainFilter::new(until.clone()).until(since.clone());
+    let activity = must_get_agent_activity(author.clone(), filter)?;
+
+    let mut rewards = Vec::new();
+    for item in activity {
+        if let Action::Create(create) = &item.action.hashed.content {
+            let is_reward = matches!(
+                &create.entry_type,
+                EntryType::App(def)
+                    if def.zome_index == reward_def.zome_index
+                        && def.entry_index == reward_def.zome_type
+            );
+            if is_reward {
+                let entry = must_get_entry(create.entry_hash.clone())?;
+                if let Ok(reward) = RewardAccrual::try_from(entry.content) {
+                    rewards.push(reward);
+                }
+            }
+        }
+    }
+    Ok(rewards)
+}
+
+fn validate_create_settlement(
+    batch: SettlementBatch,
+    action: Create,
+) -> ExternResult<ValidateCallbackResult> {
+    // Settlement must have plays
+    if batch.play_count == 0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Settlement batch must contain at least one play".to_string(),
+        ));
+    }
+
+    // Play count must match hashes
+    if batch.play_count as usize != batch.play_hashes.len() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Play count must match number of play hashes".to_string(),
+        ));
+    }
+
+    // Larger batches can't be submitted within the gas limit
+    let properties = plays_properties()?;
+    if let Err(reason) = check_batch_size(batch.play_count, properties.max_batch_plays) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // The payout must be submittable somewhere, and only where this DNA settles
+    if let Err(reason) = batch.settlement_target.check() {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if batch.settlement_target != properties.settlement_target {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Settlement target must match the settlement_target DNA property".to_string(),
+        ));
+    }
+
+    // New settlements must be pending
+    if batch.status != SettlementStatus::Pending {
+        return Ok(ValidateCallbackResult::Invalid(
+            "New settlements must have Pending status".to_string(),
+        ));
+    }
+
+    // No play can be counted twice
+    if let Err(reason) = check_unique_play_hashes(&batch) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // Proofs must verify against exactly these plays
+    if let Err(reason) = check_merkle_root(&batch, &properties) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // The protocol fee can't lose or create value
+    if let Err(reason) = check_fee_accounting(&batch, &properties) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // Amended plays are priced as amended; versions pair up with plays
+    if !batch.play_versions.is_empty() && batch.play_versions.len() != batch.play_hashes.len() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Play versions must match the play hashes".to_string(),
+        ));
+    }
+
+    // The plays, as priced, must be the settler's own and add up to the batch
+    let mut plays = Vec::with_capacity(batch.play_hashes.len());
+    for (index, play_hash) in batch.play_hashes.iter().enumerate() {
+        let version = batch.play_version(index).cloned().unwrap_or_else(|| play_hash.clone());
+        if &original_create_hash(version.clone())? != play_hash {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Play versions must descend from their play hashes".to_string(),
+            ));
+        }
+        let record = must_get_valid_record(version)?;
+        let play = match record
+            .entry()
+            .to_app_option::<PlayRecord>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            Some(play) => play.upgraded(),
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Settlement play hashes must reference play records".to_string(),
+                ))
+            }
+        };
+        plays.push((record.action().author().clone(), play));
+    }
+    if let Err(reason) = check_batch_plays(&batch, &action.author, &plays) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if let Err(reason) = check_plays_past_grace(batch.created_at, &plays, properties.amend_grace_secs) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // Disputed songs' earnings stay held, as of the versions cited (only
+    // the coordinator makes sure they are the latest; see `song_versions`)
+    let mut songs = std::collections::BTreeMap::new();
+    for version in &batch.song_versions {
+        let song = must_get_valid_record(version.clone())?
+            .entry()
+            .to_app_option::<catalog_integrity::Song>()
+            .map_err(|e| wasm_error!(e))?;
+        let frozen = match song {
+            Some(song) => song.earnings_frozen,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Settlement song versions must reference songs".to_string(),
+                ))
+            }
+        };
+        songs.insert(original_create_hash(version.clone())?, frozen);
+    }
+    if let Err(reason) = check_songs_unfrozen(&plays, &songs) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(total_amount: u64, protocol_fee: u64, fee_breakdown: Vec<StrategyFee>) -> SettlementBatch {
+        SettlementBatch {
+            artist: AgentPubKey::from_raw_36(vec![1; 36]),
+            play_count: 1,
+            total_amount,
+            gross_amount: fee_breakdown.iter().map(|f| f.gross_amount).sum(),
+            protocol_fee,
+            fee_breakdown,
+            play_hashes: vec![ActionHash::from_raw_36(vec![2; 36])],
+            play_versions: Vec::new(),
+            song_versions: Vec::new(),
+            merkle_root: Vec::new(),
+            merkle_algorithm: MerkleAlgorithm::SortedPairs,
+            settlement_target: SettlementTarget::default(),
+            created_at: Timestamp::from_micros(0),
+            status: SettlementStatus::Pending,
+            tx_hash: None,
+        }
+    }
+
+    fn fee(strategy_id: &str, gross_amount: u64, fee_bps: u32) -> StrategyFee {
+        StrategyFee {
+            strategy_id: strategy_id.to_string(),
+            gross_amount,
+            fee_bps,
+            fee: protocol_fee(gross_amount, fee_bps),
+        }
+    }
+
+    fn bonus_config() -> StrategyConfig {
+        StrategyConfig {
+            completion_bonuses: vec![
+                CompletionBonus {
+                    min_completion_bps: 9_000,
+                    bonus_bps: 1_000,
+                },
+                CompletionBonus {
+                    min_completion_bps: 10_000,
+                    bonus_bps: 1_500,
+                },
+            ],
+            repeat_listen_bonus_bps: Some(2_500),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn completed_reach_counts_listeners_whose_best_play_qualifies() {
+        let artist = AgentPubKey::from_raw_36(vec![2; 36]);
+        let listen = |seconds: u32| PlayRecord {
+            duration_listened: seconds,
+            ..play(&artist, "pay_per_stream", 0)
+        };
+        assert!(reaches_completion(72, 90, 8_000));
+        assert!(!reaches_completion(71, 90, 8_000));
+        assert!(!reaches_completion(0, 0, 0));
+
+        let mut snapshot = SongStatsSnapshot::empty(
+            ActionHash::from_raw_36(vec![3; 36]),
+            Timestamp::from_micros(0),
+        );
+        // A skipper, and a listener who skipped once then finished
+        snapshot.fold_play(&listen(10), [1; 32], 1_000, 8_000);
+        snapshot.fold_play(&listen(10), [2; 32], 1_000, 8_000);
+        snapshot.fold_play(&listen(90), [2; 32], 1_000, 8_000);
+        snapshot.fold_play(&listen(85), [2; 32], 1_000, 8_000);
+
+        assert_eq!(snapshot.listeners.estimate(), 2);
+        assert_eq!(snapshot.completed_listeners.as_ref().map(ListenerSketch::estimate), Some(1));
+    }
+
+    #[test]
+    fn completion_bonus_applies_exactly_at_boundaries() {
+        let config = bonus_config();
+        // 200s song: 90% is 180s
+        assert_eq!(completion_bonus_bps(&config, 179, 200, None, false), 0);
+        assert_eq!(completion_bonus_bps(&config, 180, 200, None, false), 1_000);
+        assert_eq!(completion_bonus_bps(&config, 199, 200, None, false), 1_000);
+        assert_eq!(completion_bonus_bps(&config, 200, 200, None, false), 1_500);
+        // Listening past the end doesn't reach a higher tier
+        assert_eq!(completion_bonus_bps(&config, 400, 200, None, false), 1_500);
+    }
+
+    #[test]
+    fn repeat_bonus_needs_a_full_listen_and_the_flag() {
+        let config = bonus_config();
+        assert_eq!(completion_bonus_bps(&config, 199, 200, None, true), 1_000);
+        assert_eq!(completion_bonus_bps(&config, 200, 200, None, true), 4_000);
+
+        let no_repeat = StrategyConfig {
+            repeat_listen_bonus_bps: None,
+            ..bonus_config()
+        };
+        assert_eq!(completion_bonus_bps(&no_repeat, 200, 200, None, true), 1_500);
+    }
+
+    #[test]
+    fn no_bonus_below_the_listen_threshold_or_without_tiers() {
+        assert_eq!(completion_bonus_bps(&bonus_config(), 29, 200, None, true), 0);
+        assert_eq!(completion_bonus_bps(&StrategyConfig::default(), 200, 200, None, true), 0);
+    }
+
+    #[test]
+    fn owed_balance_tracks_plays_and_settlements() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let artist_a = AgentPubKey::from_raw_36(vec![2; 36]);
+        let artist_b = AgentPubKey::from_raw_36(vec![3; 36]);
+
+        let mut owed = OwedBalance::empty(listener, Timestamp::from_micros(0));
+        owed.add_play(&artist_a, 100);
+        owed.add_play(&artist_a, 50);
+        owed.add_play(&artist_b, 30);
+        assert_eq!(owed.total_amount, 180);
+        assert_eq!(owed.play_count, 3);
+        assert_eq!(owed.by_artist.len(), 2);
+
+        owed.remove_settled(&artist_a, 2, 150);
+        assert_eq!(owed.total_amount, 30);
+        assert_eq!(owed.play_count, 1);
+        assert_eq!(owed.by_artist.len(), 1);
+        assert_eq!(owed.by_artist[0].artist, artist_b);
+
+        // Crossing the nudge threshold, not staying past it
+        let before = owed.total_amount;
+        owed.add_play(&artist_b, 70);
+        assert!(owed.crossed_threshold(before, 100));
+        let before = owed.total_amount;
+        owed.add_play(&artist_b, 10);
+        assert!(!owed.crossed_threshold(before, 100));
+        assert!(!owed.crossed_threshold(0, 0));
+        owed.remove_settled(&artist_b, 2, 80);
+
+        // Amended down, then to nothing
+        owed.amend_play(&artist_b, 30, 20);
+        assert_eq!(owed.total_amount, 20);
+        assert_eq!(owed.play_count, 1);
+        owed.amend_play(&artist_b, 20, 0);
+        assert_eq!(owed.total_amount, 0);
+        assert_eq!(owed.play_count, 0);
+        assert!(owed.by_artist.is_empty());
+    }
+
+    #[test]
+    fn per_minute_signals_count_plays_until_reported() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let artist = AgentPubKey::from_raw_36(vec![2; 36]);
+        let minute = 60 * 1_000_000;
+        let at = |secs: i64| Timestamp::from_micros(secs * 1_000_000);
+        let mut owed = OwedBalance::empty(listener, at(0));
+
+        // The first play of a window reports itself
+        owed.add_play(&artist, 10);
+        let first = owed.signal_play(&artist, 10, at(5), minute).unwrap();
+        assert_eq!((first.play_count, first.amount), (1, 10));
+
+        // Later ones in the window wait
+        owed.add_play(&artist, 20);
+        assert!(owed.signal_play(&artist, 20, at(20), minute).is_none());
+        owed.add_play(&artist, 30);
+        assert!(owed.signal_play(&artist, 30, at(40), minute).is_none());
+
+        // And are reported with the next window's first play
+        owed.add_play(&artist, 40);
+        let next = owed.signal_play(&artist, 40, at(70), minute).unwrap();
+        assert_eq!((next.play_count, next.amount), (3, 90));
+        assert_eq!(next.first_played_at, Some(at(20)));
+        assert_eq!(next.last_played_at, Some(at(70)));
+
+        // Or by a flush, e.g. before settling
+        owed.add_play(&artist, 50);
+        assert!(owed.signal_play(&artist, 50, at(80), minute).is_none());
+        let flushed = owed.flush_signal(&artist, at(90)).unwrap();
+        assert_eq!((flushed.play_count, flushed.amount), (1, 50));
+        assert!(owed.flush_signal(&artist, at(95)).is_none());
+    }
+
+    #[test]
+    fn fee_is_configured_per_strategy_and_needs_a_platform_agent() {
+        let mut properties = PlaysProperties {
+            strategies: vec![StrategyConfig {
+                strategy_id: "premium".to_string(),
+                protocol_fee_bps: 250,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(properties.effective_fee_bps("premium"), 0);
+
+        properties.platform_agent = Some(AgentPubKey::from_raw_36(vec![9; 36]));
+        assert_eq!(properties.effective_fee_bps("premium"), 250);
+        // Unconfigured strategies get the default 1%
+        assert_eq!(properties.effective_fee_bps("gift"), 100);
+    }
+
+    #[test]
+    fn protocol_fee_rounds_down_and_caps_at_gross() {
+        assert_eq!(protocol_fee(1_000_000, 100), 10_000);
+        assert_eq!(protocol_fee(99, 100), 0);
+        assert_eq!(protocol_fee(1_000, 20_000), 1_000);
+        assert_eq!(protocol_fee(u64::MAX, 10_000), u64::MAX);
+    }
+
+    /// 1% by default and 2.5% on premium, paid to a platform agent
+    fn fee_properties() -> PlaysProperties {
+        PlaysProperties {
+            platform_agent: Some(AgentPubKey::from_raw_36(vec![8; 36])),
+            strategies: vec![StrategyConfig {
+                strategy_id: "premium".to_string(),
+                protocol_fee_bps: 250,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn balanced_batch_passes() {
+        // 1% on 1M plus 2.5% on 400k
+        let properties = fee_properties();
+        let breakdown = vec![fee("standard", 1_000_000, 100), fee("premium", 400_000, 250)];
+        assert!(check_fee_accounting(&batch(1_380_000, 20_000, breakdown), &properties).is_ok());
+
+        // Batches without a fee settle the full amount
+        assert!(check_fee_accounting(&batch(0, 0, Vec::new()), &properties).is_ok());
+        let unpaid = PlaysProperties::default();
+        let breakdown = vec![fee("standard", 500, 0)];
+        assert!(check_fee_accounting(&batch(500, 0, breakdown), &unpaid).is_ok());
+    }
+
+    #[test]
+    fn value_cannot_be_lost_or_created() {
+        let properties = fee_properties();
+        let check = |total, charged, breakdown| {
+            check_fee_accounting(&batch(total, charged, breakdown), &properties)
+        };
+        let breakdown = vec![fee("standard", 1_000_000, 100)];
+        // Net too low: fee + net < gross
+        assert!(check(980_000, 10_000, breakdown.clone()).is_err());
+        // Net too high: fee + net > gross
+        assert!(check(1_000_000, 10_000, breakdown.clone()).is_err());
+        // Batch fee disagrees with the breakdown
+        assert!(check(995_000, 5_000, breakdown).is_err());
+    }
+
+    #[test]
+    fn strategy_rate_must_be_the_configured_one() {
+        let properties = fee_properties();
+        // A settler can't pick a lower rate, or skip the fee
+        let discounted = vec![fee("premium", 400_000, 100)];
+        assert!(check_fee_accounting(&batch(396_000, 4_000, discounted), &properties).is_err());
+        let skipped = vec![fee("standard", 500, 0)];
+        assert!(check_fee_accounting(&batch(500, 0, skipped), &properties).is_err());
+    }
+
+    #[test]
+    fn merkle_root_must_cover_the_batch_plays() {
+        let properties = PlaysProperties::default();
+        let mut settled = batch(1_000, 0, Vec::new());
+        assert!(check_merkle_root(&settled, &properties).is_err());
+
+        settled.merkle_root = MerkleAlgorithm::SortedPairs.root(&settled.play_hashes);
+        assert!(check_merkle_root(&settled, &properties).is_ok());
+
+        // A root over other plays, or built another way, doesn't stand
+        let other = vec![ActionHash::from_raw_36(vec![5; 36])];
+        let mut foreign = settled.clone();
+        foreign.merkle_root = MerkleAlgorithm::SortedPairs.root(&other);
+        assert!(check_merkle_root(&foreign, &properties).is_err());
+        let mut legacy = settled;
+        legacy.merkle_algorithm = MerkleAlgorithm::Sequential;
+        legacy.merkle_root = MerkleAlgorithm::Sequential.root(&legacy.play_hashes);
+        assert!(check_merkle_root(&legacy, &properties).is_err());
+    }
+
+    fn play(artist: &AgentPubKey, strategy_id: &str, amount_owed: u64) -> PlayRecord {
+        PlayRecord {
+            schema_version: PLAY_RECORD_SCHEMA_VERSION,
+            song_hash: ActionHash::from_raw_36(vec![3; 36]),
+            artist: artist.clone(),
+            played_at: Timestamp::from_micros(0),
+            duration_listened: 90,
+            song_duration: 90,
+            min_play_override: None,
+            song_version: None,
+            strategy_id: strategy_id.to_string(),
+            amount_owed,
+            settled: false,
+            settlement_hash: None,
+        }
+    }
+
+    #[test]
+    fn play_must_carry_its_songs_pricing() {
+        let artist = AgentPubKey::from_raw_36(vec![1; 36]);
+        let song = catalog_integrity::Song {
+            song_hash: "song".to_string(),
+            title: "Song".to_string(),
+            artist: artist.clone(),
+            ipfs_cid: "bafysong".to_string(),
+            cover_cid: None,
+            duration_seconds: 90,
+            genres: Vec::new(),
+            strategy_id: "pay_per_stream".to_string(),
+            released_at: Timestamp::from_micros(0),
+            metadata: String::new(),
+            min_play_override: None,
+            isrc: None,
+            earnings_frozen: false,
+            earnings_freeze: None,
+        };
+        let honest = play(&artist, "pay_per_stream", 10);
+        assert_eq!(check_play_matches_song(&honest, &song), Ok(()));
+
+        // Skip-farming: a paid song claimed as a one-second gift
+        let farmed = PlayRecord {
+            strategy_id: GIFT_STRATEGY_ID.to_string(),
+            song_duration: 1,
+            duration_listened: 1,
+            amount_owed: 0,
+            ..honest.clone()
+        };
+        assert!(check_play_matches_song(&farmed, &song).is_err());
+        let shortened = PlayRecord { song_duration: 1, duration_listened: 1, ..honest.clone() };
+        assert!(check_play_matches_song(&shortened, &song).is_err());
+        let redirected = play(&AgentPubKey::from_raw_36(vec![2; 36]), "pay_per_stream", 10);
+        assert!(check_play_matches_song(&redirected, &song).is_err());
+    }
+
+    #[test]
+    fn settlement_targets_need_a_chain_and_an_address() {
+        let base = SettlementTarget {
+            chain: SettlementChain::Base,
+            chain_id: 8453,
+            verifier: format!("0x{}", "aB".repeat(20)),
+        };
+        assert!(base.check().is_ok());
+        // Not deployed yet
+        assert!(SettlementTarget::default().check().is_ok());
+
+        assert!(SettlementTarget { chain_id: 0, ..base.clone() }.check().is_err());
+        let short = SettlementTarget { verifier: "0x1234".to_string(), ..base.clone() };
+        assert!(short.check().is_err());
+        let unprefixed = SettlementTarget { verifier: "ab".repeat(20), ..base };
+        assert!(unprefixed.check().is_err());
+    }
+
+    #[test]
+    fn self_plays_follow_the_policy() {
+        let artist = AgentPubKey::from_raw_36(vec![1; 36]);
+        let paying = play(&artist, "standard", 1_000);
+        let free = play(&artist, "standard", 0);
+
+        // By default the play is kept but can't earn anything
+        assert!(check_self_play(&paying, true, SelfPlayPolicy::default()).is_err());
+        assert!(check_self_play(&free, true, SelfPlayPolicy::default()).is_ok());
+
+        assert!(check_self_play(&paying, true, SelfPlayPolicy::Paid).is_ok());
+        assert!(check_self_play(&free, true, SelfPlayPolicy::Rejected).is_err());
+
+        // Anyone else's plays are unaffected
+        for policy in [SelfPlayPolicy::Unpaid, SelfPlayPolicy::Paid, SelfPlayPolicy::Rejected] {
+            assert!(check_self_play(&paying, false, policy).is_ok());
+        }
+    }
+
+    #[test]
+    fn repeated_play_hash_is_rejected() {
+        let mut repeated = batch(1_000, 0, vec![fee("standard", 1_000, 0)]);
+        let hash = repeated.play_hashes[0].clone();
+        repeated.play_hashes.push(hash);
+        repeated.play_count = 2;
+        assert!(check_unique_play_hashes(&repeated).is_err());
+
+        let mut distinct = repeated.clone();
+        distinct.play_hashes[1] = ActionHash::from_raw_36(vec![4; 36]);
+        assert!(check_unique_play_hashes(&distinct).is_ok());
+    }
+
+    #[test]
+    fn batch_plays_must_be_the_settlers_and_add_up() {
+        let settler = AgentPubKey::from_raw_36(vec![5; 36]);
+        let other = AgentPubKey::from_raw_36(vec![6; 36]);
+        let b = batch(1_000, 0, vec![fee("standard", 1_000, 0)]);
+        let artist = b.artist.clone();
+
+        let plays = vec![
+            (settler.clone(), play(&artist, "standard", 600)),
+            (settler.clone(), play(&artist, "standard", 400)),
+        ];
+        assert!(check_batch_plays(&b, &settler, &plays).is_ok());
+
+        // Someone else's play
+        let stolen = vec![(other.clone(), play(&artist, "standard", 1_000))];
+        assert!(check_batch_plays(&b, &settler, &stolen).is_err());
+
+        // A play for a different artist
+        let misdirected = vec![(settler.clone(), play(&other, "standard", 1_000))];
+        assert!(check_batch_plays(&b, &settler, &misdirected).is_err());
+
+        // Breakdown claims more than the plays owe
+        let short = vec![(settler.clone(), play(&artist, "standard", 600))];
+        assert!(check_batch_plays(&b, &settler, &short).is_err());
+    }
+
+    #[test]
+    fn settling_a_play_changes_only_the_settlement_fields() {
+        let artist = AgentPubKey::from_raw_36(vec![5; 36]);
+        let original = play(&artist, "standard", 600);
+        let settled = PlayRecord {
+            settled: true,
+            settlement_hash: Some(ActionHash::from_raw_36(vec![7; 36])),
+            ..original.clone()
+        };
+        assert!(check_play_settlement(&original, &settled).is_ok());
+
+        // Older entries upgrade before comparing
+        let v1 = PlayRecord {
+            schema_version: 1,
+            ..original.clone()
+        };
+        assert!(check_play_settlement(&v1, &settled).is_ok());
+
+        // Settled twice, not settled, or amount rewritten
+        assert!(check_play_settlement(&settled, &settled).is_err());
+        assert!(check_play_settlement(&original, &original).is_err());
+        let rewritten = PlayRecord {
+            amount_owed: 1,
+            ..settled.clone()
+        };
+        assert!(check_play_settlement(&original, &rewritten).is_err());
+    }
+
+    #[test]
+    fn settled_flag_only_moves_from_false_to_true_with_a_batch() {
+        let artist = AgentPubKey::from_raw_36(vec![5; 36]);
+        let original = play(&artist, "standard", 600);
+
+        // Flag without a batch, or a batch without the flag
+        let flag_only = PlayRecord {
+            settled: true,
+            ..original.clone()
+        };
+        assert!(check_play_settlement(&original, &flag_only).is_err());
+        let hash_only = PlayRecord {
+            settlement_hash: Some(ActionHash::from_raw_36(vec![7; 36])),
+            ..original.clone()
+        };
+        assert!(check_play_settlement(&original, &hash_only).is_err());
+
+        // Un-settling, or moving to another batch
+        let settled = PlayRecord {
+            settled: true,
+            settlement_hash: Some(ActionHash::from_raw_36(vec![7; 36])),
+            ..original.clone()
+        };
+        assert!(check_play_settlement(&settled, &original).is_err());
+        let moved = PlayRecord {
+            settlement_hash: Some(ActionHash::from_raw_36(vec![8; 36])),
+            ..settled.clone()
+        };
+        assert!(check_play_settlement(&settled, &moved).is_err());
+    }
+
+    #[test]
+    fn play_is_settled_only_by_a_batch_listing_it() {
+        let artist = AgentPubKey::from_raw_36(vec![5; 36]);
+        let other = AgentPubKey::from_raw_36(vec![6; 36]);
+        let play_hash = ActionHash::from_raw_36(vec![10; 36]);
+        let mut b = batch(594, 6, vec![fee("standard", 600, 100)]);
+        b.artist = artist.clone();
+        b.play_hashes = vec![play_hash.clone()];
+
+        let listed = play(&artist, "standard", 600);
+        assert!(check_settled_by_batch(&b, &play_hash, &play_hash, &listed).is_ok());
+        // Not listed (e.g. referenced by an update's hash rather than the create)
+        let update_hash = ActionHash::from_raw_36(vec![11; 36]);
+        assert!(check_settled_by_batch(&b, &update_hash, &update_hash, &listed).is_err());
+        // Listed, but the batch pays someone else
+        assert!(check_settled_by_batch(&b, &play_hash, &play_hash, &play(&other, "standard", 600)).is_err());
+    }
+
+    #[test]
+    fn settlement_must_update_the_version_the_batch_priced() {
+        let artist = AgentPubKey::from_raw_36(vec![5; 36]);
+        let play_hash = ActionHash::from_raw_36(vec![10; 36]);
+        let amended_hash = ActionHash::from_raw_36(vec![11; 36]);
+        let mut b = batch(594, 6, vec![fee("standard", 600, 100)]);
+        b.artist = artist.clone();
+        b.play_hashes = vec![play_hash.clone()];
+        b.play_versions = vec![amended_hash.clone()];
+
+        let amended = play(&artist, "standard", 600);
+        assert!(check_settled_by_batch(&b, &play_hash, &amended_hash, &amended).is_ok());
+        // Settling the create would skip the amendment the batch priced
+        assert!(check_settled_by_batch(&b, &play_hash, &play_hash, &amended).is_err());
+    }
+
+    fn amendable_play() -> PlayRecord {
+        PlayRecord {
+            duration_listened: 200,
+            song_duration: 200,
+            ..play(&AgentPubKey::from_raw_36(vec![5; 36]), "standard", 1_000)
+        }
+    }
+
+    #[test]
+    fn amendments_can_reduce_or_correct_within_the_song() {
+        let original = amendable_play();
+        let reduced = PlayRecord {
+            duration_listened: 120,
+            amount_owed: 500,
+            ..original.clone()
+        };
+        assert!(check_play_amendment(&original, &reduced, 1_000_000, 300).is_ok());
+        // Back up to the full song
+        assert!(check_play_amendment(&reduced, &original, 1_000_000, 300).is_ok());
+
+        // Past the song's end
+        let overlong = PlayRecord {
+            duration_listened: 201,
+            ..reduced.clone()
+        };
+        assert!(check_play_amendment(&reduced, &overlong, 1_000_000, 300).is_err());
+        // Cheaper without listening less
+        let discounted = PlayRecord {
+            amount_owed: 10,
+            ..original.clone()
+        };
+        assert!(check_play_amendment(&original, &discounted, 1_000_000, 300).is_err());
+        // Below the threshold but still charged
+        let skipped = PlayRecord {
+            duration_listened: 10,
+            amount_owed: 100,
+            ..original.clone()
+        };
+        assert!(check_play_amendment(&original, &skipped, 1_000_000, 300).is_err());
+        let skipped_free = PlayRecord {
+            amount_owed: 0,
+            ..skipped
+        };
+        assert!(check_play_amendment(&original, &skipped_free, 1_000_000, 300).is_ok());
+    }
+
+    #[test]
+    fn amendments_are_limited_to_the_grace_period_and_other_fields_are_fixed() {
+        let original = amendable_play();
+        let reduced = PlayRecord {
+            duration_listened: 120,
+            amount_owed: 500,
+            ..original.clone()
+        };
+        assert!(check_play_amendment(&original, &reduced, 300_000_000, 300).is_ok());
+        assert!(check_play_amendment(&original, &reduced, 300_000_001, 300).is_err());
+
+        let settled = PlayRecord {
+            settled: true,
+            settlement_hash: Some(ActionHash::from_raw_36(vec![7; 36])),
+            ..original.clone()
+        };
+        assert!(check_play_amendment(&settled, &reduced, 0, 300).is_err());
+
+        let restrategised = PlayRecord {
+            strategy_id: "premium".to_string(),
+            ..reduced.clone()
+        };
+        assert!(check_play_amendment(&original, &restrategised, 0, 300).is_err());
+
+        let barter = PlayRecord {
+            strategy_id: TIME_BARTER_STRATEGY_ID.to_string(),
+            amount_owed: 0,
+            ..original
+        };
+        let barter_reduced = PlayRecord {
+            duration_listened: 120,
+            ..barter.clone()
+        };
+        assert!(check_play_amendment(&barter, &barter_reduced, 0, 300).is_err());
+    }
+
+    #[test]
+    fn batched_plays_must_be_past_the_grace_period() {
+        let listener = AgentPubKey::from_raw_36(vec![4; 36]);
+        let plays = vec![(listener, amendable_play())];
+        // The test play was recorded at 0
+        assert!(check_plays_past_grace(Timestamp::from_micros(300_000_000), &plays, 300).is_ok());
+        assert!(check_plays_past_grace(Timestamp::from_micros(299_999_999), &plays, 300).is_err());
+        assert!(check_plays_past_grace(Timestamp::from_micros(0), &plays, 0).is_ok());
+    }
+
+    #[test]
+    fn batch_size_is_capped_unless_unlimited() {
+        assert!(check_batch_size(500, 500).is_ok());
+        assert!(check_batch_size(501, 500).is_err());
+        assert!(check_batch_size(100_000, 0).is_ok());
+    }
+
+    #[test]
+    fn strategy_fee_must_match_its_rate() {
+        let mut wrong = fee("standard", 1_000_000, 100);
+        wrong.fee = 1;
+        assert!(check_fee_accounting(&batch(999_999, 1, vec![wrong]), &fee_properties()).is_err());
+    }
+
+    fn play_hashes(count: u8) -> Vec<ActionHash> {
+        (0..count).map(|i| ActionHash::from_raw_36(vec![i; 36])).collect()
+    }
+
+    /// OpenZeppelin `MerkleProof.verify` (processProof + _hashPair)
+    fn oz_verify(proof: &[Vec<u8>], root: &[u8], leaf: &[u8]) -> bool {
+        let mut computed = leaf.to_vec();
+        for node in proof {
+            computed = if computed < *node {
+                keccak256(&[computed.as_slice(), node].concat()).to_vec()
+            } else {
+                keccak256(&[node.as_slice(), computed.as_slice()].concat()).to_vec()
+            };
+        }
+        computed == root
+    }
+
+    #[test]
+    fn keccak256_matches_known_digest() {
+        assert_eq!(
+            keccak256(b"").to_vec(),
+            vec![
+                0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7,
+                0x03, 0xc0, 0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04,
+                0x5d, 0x85, 0xa4, 0x70,
+            ]
+        );
+    }
+
+    #[test]
+    fn sorted_pairs_proofs_verify_with_openzeppelin() {
+        let algorithm = MerkleAlgorithm::SortedPairs;
+        for count in 1..=9 {
+            let hashes = play_hashes(count);
+            let root = algorithm.root(&hashes);
+            for (index, hash) in hashes.iter().enumerate() {
+                let proof = algorithm.proof(&hashes, index).unwrap();
+                assert!(oz_verify(&proof, &root, &algorithm.leaf(hash)));
+                assert!(algorithm.verify(&root, hash, index, &proof));
+            }
+
+            // A play outside the batch doesn't verify
+            let outsider = ActionHash::from_raw_36(vec![200; 36]);
+            let proof = algorithm.proof(&hashes, 0).unwrap();
+            assert!(!oz_verify(&proof, &root, &algorithm.leaf(&outsider)));
+        }
+        assert!(algorithm.proof(&play_hashes(3), 3).is_none());
+    }
+
+    #[test]
+    fn sequential_keeps_original_construction() {
+        let algorithm = MerkleAlgorithm::Sequential;
+        let hashes = play_hashes(3);
+        let raw: Vec<&[u8]> = hashes.iter().map(|h| h.get_raw_39()).collect();
+        let ab = keccak256(&[raw[0], raw[1]].concat());
+        let cc = keccak256(&[raw[2], raw[2]].concat());
+        assert_eq!(algorithm.root(&hashes), keccak256(&[ab, cc].concat()).to_vec());
+
+        for count in 1..=9 {
+            let hashes = play_hashes(count);
+            let root = algorithm.root(&hashes);
+            for (index, hash) in hashes.iter().enumerate() {
+                let proof = algorithm.proof(&hashes, index).unwrap();
+                assert!(algorithm.verify(&root, hash, index, &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn frozen_songs_cannot_be_settled() {
+        let artist = AgentPubKey::from_raw_36(vec![1; 36]);
+        let plays = vec![(artist.clone(), play(&artist, "standard", 1_000))];
+        let song_hash = plays[0].1.song_hash.clone();
+
+        let mut songs = std::collections::BTreeMap::new();
+        assert!(check_songs_unfrozen(&plays, &songs).is_err());
+        songs.insert(song_hash.clone(), false);
+        assert!(check_songs_unfrozen(&plays, &songs).is_ok());
+        songs.insert(song_hash, true);
+        assert!(check_songs_unfrozen(&plays, &songs).is_err());
+    }
+
+    #[test]
+    fn settlement_status_only_moves_along_its_lifecycle() {
+        use SettlementStatus::*;
+        let pending = batch(990, 10, vec![fee("pay_per_stream", 1_000, 100)]);
+        let with = |status: SettlementStatus, tx_hash: Option<&str>| SettlementBatch {
+            status,
+            tx_hash: tx_hash.map(str::to_string),
+            ..pending.clone()
+        };
+        let check = check_settlement_status_change;
+        let submitted = with(Submitted, Some("0xabc"));
+
+        assert_eq!(check(&pending, &submitted), Ok(()));
+        assert!(check(&pending, &with(Submitted, None)).is_err());
+        // Straight to Confirmed, or confirmed with a different transaction
+        assert!(check(&pending, &with(Confirmed, Some("0xabc"))).is_err());
+        assert_eq!(check(&submitted, &with(Confirmed, Some("0xabc"))), Ok(()));
+        assert!(check(&submitted, &with(Confirmed, Some("0xdef"))).is_err());
+
+        // Failed batches can be resubmitted, confirmed ones can't move
+        let failed = with(Failed, Some("0xabc"));
+        assert_eq!(check(&submitted, &failed), Ok(()));
+        assert_eq!(check(&failed, &with(Submitted, Some("0xdef"))), Ok(()));
+        assert!(check(&with(Confirmed, Some("0xabc")), &failed).is_err());
+
+        // Nothing else may change
+        let mut repriced = with(Submitted, Some("0xabc"));
+        repriced.total_amount += 1;
+        assert!(check(&pending, &repriced).is_err());
+    }
 }
@@ -3,12 +3,24 @@
 //! Implements the trust layer for Mycelix Music:
 //! - Web-of-trust artist verification
 //! - CDN node reputation management
+//! - Listener reputation from running listening aggregates
 //! - Byzantine behavior detection and reporting
 //! - Integration point for Mycelix-Core PoGQ
 
 use hdk::prelude::*;
+use std::collections::BTreeMap;
 use trust_integrity::*;
 
+/// Fail init if the DNA properties are malformed
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    if let Err(reason) = parse_trust_properties(dna_info()?.modifiers.properties) {
+        error!("{}", reason);
+        return Ok(InitCallbackResult::Fail(reason));
+    }
+    Ok(InitCallbackResult::Pass)
+}
+
 /// Create a trust claim (vouch for another agent)
 #[hdk_extern]
 pub fn create_trust_claim(input: CreateTrustClaimInput) -> ExternResult<ActionHash> {
@@ -38,7 +50,7 @@ pub fn create_trust_claim(input: CreateTrustClaimInput) -> ExternResult<ActionHa
     )?;
 
     // Link to recipient
-    let to_path = Path::from(format!("claims_received/{}", input.to));
+    let to_path = claims_received_path(&input.to);
     to_path.ensure()?;
     create_link(
         to_path.path_entry_hash()?,
@@ -65,7 +77,17 @@ pub struct CreateTrustClaimInput {
 /// Get trust claims about an agent
 #[hdk_extern]
 pub fn get_trust_claims(agent: AgentPubKey) -> ExternResult<Vec<TrustClaim>> {
-    let to_path = Path::from(format!("claims_received/{}", agent));
+    Ok(received_claims(&agent)?
+        .into_iter()
+        .map(|(_, claim)| claim)
+        .filter(|claim| claim.active)
+        .collect())
+}
+
+/// Latest version of every trust claim about an agent, revoked ones
+/// included, with the action that wrote it
+fn received_claims(agent: &AgentPubKey) -> ExternResult<Vec<(ActionHash, TrustClaim)>> {
+    let to_path = claims_received_path(agent);
     let links = get_links(
         GetLinksInputBuilder::try_new(to_path.path_entry_hash()?, LinkTypes::AgentToClaimsReceived)?
             .build(),
@@ -74,15 +96,17 @@ pub fn get_trust_claims(agent: AgentPubKey) -> ExternResult<Vec<TrustClaim>> {
     let mut claims = Vec::new();
     for link in links {
         if let Some(action_hash) = link.target.into_action_hash() {
-            if let Some(record) = get(action_hash, GetOptions::default())? {
+            let details = match get_details(action_hash, GetOptions::default())? {
+                Some(Details::Record(details)) => details,
+                _ => continue,
+            };
+            if let Some(record) = latest_record(details)? {
                 if let Some(claim) = record
                     .entry()
                     .to_app_option::<TrustClaim>()
                     .map_err(|e| wasm_error!(e))?
                 {
-                    if claim.active {
-                        claims.push(claim);
-                    }
+                    claims.push((record.action_address().clone(), claim));
                 }
             }
         }
@@ -91,27 +115,39 @@ pub fn get_trust_claims(agent: AgentPubKey) -> ExternResult<Vec<TrustClaim>> {
     Ok(claims)
 }
 
+/// Follow a record's updates to its newest version
+fn latest_record(mut details: RecordDetails) -> ExternResult<Option<Record>> {
+    loop {
+        let next = match details
+            .updates
+            .iter()
+            .max_by_key(|update| update.action().timestamp())
+        {
+            Some(update) => update.action_address().clone(),
+            None => return Ok(Some(details.record)),
+        };
+        details = match get_details(next, GetOptions::default())? {
+            Some(Details::Record(next)) => next,
+            _ => return Ok(None),
+        };
+    }
+}
+
 /// Compute and store verification status
+///
+/// The status cites every claim it was computed from, so validation can
+/// recompute it.
 fn recompute_verification(agent: AgentPubKey) -> ExternResult<()> {
-    let claims = get_trust_claims(agent.clone())?;
+    let claims = received_claims(&agent)?;
 
-    // Calculate trust score
-    let vouch_count = claims.len() as u32;
-    let total_confidence: u32 = claims.iter().map(|c| c.confidence_bps).sum();
-    let trust_score = if vouch_count > 0 {
-        total_confidence / vouch_count
-    } else {
-        0
-    };
+    // Calculate trust score and tier from meaningful vouches only
+    let (trust_score, tier, vouch_count) = compute_verification(
+        claims.iter().map(|(_, claim)| claim),
+        trust_properties()?.min_vouch_confidence_bps,
+    );
 
-    // Determine tier
-    let tier = if vouch_count >= 10 && trust_score >= 800 {
-        VerificationTier::Trusted
-    } else if vouch_count >= 3 {
-        VerificationTier::CommunityVerified
-    } else {
-        VerificationTier::Unverified
-    };
+    let previous_tier = get_verification_status(agent.clone())?.map(|status| status.tier);
+    let tier_changed = previous_tier.as_ref() != Some(&tier);
 
     let status = VerificationStatus {
         artist: agent.clone(),
@@ -119,6 +155,7 @@ fn recompute_verification(agent: AgentPubKey) -> ExternResult<()> {
         tier,
         vouch_count,
         computed_at: sys_time()?,
+        claims: claims.into_iter().map(|(hash, _)| hash).collect(),
     };
 
     let action_hash = create_entry(&EntryTypes::VerificationStatus(status))?;
@@ -128,11 +165,45 @@ fn recompute_verification(agent: AgentPubKey) -> ExternResult<()> {
     status_path.ensure()?;
     create_link(
         status_path.path_entry_hash()?,
-        action_hash,
+        action_hash.clone(),
         LinkTypes::AgentToVerification,
         (),
     )?;
 
+    if tier_changed {
+        propagate_verification(agent, action_hash)?;
+    }
+
+    Ok(())
+}
+
+/// Push a new verification status to the artist's catalog profile
+///
+/// The profile lives on the artist's own chain, so this asks their cell
+/// to apply it. An unreachable artist isn't an error: the catalog picks up
+/// the latest status the next time they save their profile.
+fn propagate_verification(agent: AgentPubKey, status_hash: ActionHash) -> ExternResult<()> {
+    let response = if agent == agent_info()?.agent_initial_pubkey {
+        call(
+            CallTargetCell::Local,
+            ZomeName::from("catalog"),
+            FunctionName::from("apply_verification"),
+            None,
+            status_hash,
+        )?
+    } else {
+        call_remote(
+            agent.clone(),
+            ZomeName::from("catalog"),
+            FunctionName::from("apply_verification"),
+            None,
+            status_hash,
+        )?
+    };
+
+    if !matches!(response, ZomeCallResponse::Ok(_)) {
+        debug!("Verification for {} not applied yet: {:?}", agent, response);
+    }
     Ok(())
 }
 
@@ -159,12 +230,85 @@ pub fn get_verification_status(agent: AgentPubKey) -> ExternResult<Option<Verifi
     Ok(None)
 }
 
+/// Action hash of an agent's latest verification status
+///
+/// Lets the catalog cite the status as evidence for a verified profile.
+#[hdk_extern]
+pub fn get_verification_status_hash(agent: AgentPubKey) -> ExternResult<Option<ActionHash>> {
+    let status_path = Path::from(format!("verification/{}", agent));
+    let links = get_links(
+        GetLinksInputBuilder::try_new(status_path.path_entry_hash()?, LinkTypes::AgentToVerification)?
+            .build(),
+    )?;
+
+    Ok(links.last().and_then(|link| link.target.clone().into_action_hash()))
+}
+
+/// Get verification statuses for many agents in one call
+///
+/// Link reads and record fetches are each issued as a single batched host
+/// call. Agents without a status are left out of the map.
+#[hdk_extern]
+pub fn get_verification_statuses(
+    agents: Vec<AgentPubKey>,
+) -> ExternResult<std::collections::HashMap<AgentPubKey, VerificationStatus>> {
+    let mut statuses = std::collections::HashMap::new();
+    if agents.is_empty() {
+        return Ok(statuses);
+    }
+
+    let link_inputs = agents
+        .iter()
+        .map(|agent| {
+            let status_path = Path::from(format!("verification/{}", agent));
+            Ok(GetLinksInputBuilder::try_new(
+                status_path.path_entry_hash()?,
+                LinkTypes::AgentToVerification,
+            )?
+            .build())
+        })
+        .collect::<ExternResult<Vec<GetLinksInput>>>()?;
+    let links_per_agent = HDK.with(|h| h.borrow().get_links(link_inputs))?;
+
+    // Latest status link per agent, matching get_verification_status
+    let mut latest: Vec<(AgentPubKey, ActionHash)> = Vec::new();
+    for (agent, links) in agents.into_iter().zip(links_per_agent) {
+        if let Some(action_hash) = links.last().and_then(|l| l.target.clone().into_action_hash()) {
+            latest.push((agent, action_hash));
+        }
+    }
+    if latest.is_empty() {
+        return Ok(statuses);
+    }
+
+    let get_inputs: Vec<GetInput> = latest
+        .iter()
+        .map(|(_, hash)| GetInput::new(hash.clone().into(), GetOptions::default()))
+        .collect();
+    let records = HDK.with(|h| h.borrow().get(get_inputs))?;
+
+    for ((agent, _), record) in latest.into_iter().zip(records) {
+        if let Some(record) = record {
+            if let Some(status) = record
+                .entry()
+                .to_app_option::<VerificationStatus>()
+                .map_err(|e| wasm_error!(e))?
+            {
+                statuses.insert(agent, status);
+            }
+        }
+    }
+
+    Ok(statuses)
+}
+
 /// Register as a CDN node
 #[hdk_extern]
 pub fn register_cdn_node(input: RegisterCdnNodeInput) -> ExternResult<ActionHash> {
     let my_agent = agent_info()?.agent_initial_pubkey;
 
     let reputation = CdnNodeReputation {
+        schema_version: CDN_REPUTATION_SCHEMA_VERSION,
         node: my_agent.clone(),
         eth_address: input.eth_address,
         ipfs_peer_id: input.ipfs_peer_id,
@@ -178,6 +322,9 @@ pub fn register_cdn_node(input: RegisterCdnNodeInput) -> ExternResult<ActionHash
         last_active: sys_time()?,
         stake_amount: input.stake_amount,
         slash_count: 0,
+        weighted_successes: 0,
+        weighted_failures: 0,
+        gateway_url: input.gateway_url,
     };
 
     let action_hash = create_entry(&EntryTypes::CdnNodeReputation(reputation))?;
@@ -211,6 +358,9 @@ pub struct RegisterCdnNodeInput {
     pub ipfs_peer_id: String,
     pub region: String,
     pub stake_amount: u64,
+    /// HTTP gateway the node serves content from (e.g. `https://node.example/ipfs`)
+    #[serde(default)]
+    pub gateway_url: Option<String>,
 }
 
 /// Get CDN node reputation
@@ -225,10 +375,7 @@ pub fn get_cdn_reputation(node: AgentPubKey) -> ExternResult<Option<CdnNodeReput
     if let Some(link) = links.last() {
         if let Some(action_hash) = link.target.clone().into_action_hash() {
             if let Some(record) = get(action_hash, GetOptions::default())? {
-                return Ok(record
-                    .entry()
-                    .to_app_option()
-                    .map_err(|e| wasm_error!(e))?);
+                return read_cdn_reputation(&record);
             }
         }
     }
@@ -236,6 +383,15 @@ pub fn get_cdn_reputation(node: AgentPubKey) -> ExternResult<Option<CdnNodeReput
     Ok(None)
 }
 
+/// Decode a CdnNodeReputation of any schema version, upgraded to the current one
+fn read_cdn_reputation(record: &Record) -> ExternResult<Option<CdnNodeReputation>> {
+    Ok(record
+        .entry()
+        .to_app_option::<CdnNodeReputation>()
+        .map_err(|e| wasm_error!(e))?
+        .map(CdnNodeReputation::upgraded))
+}
+
 /// Get all CDN nodes (for routing)
 #[hdk_extern]
 pub fn get_all_cdn_nodes(_: ()) -> ExternResult<Vec<CdnNodeReputation>> {
@@ -249,11 +405,7 @@ pub fn get_all_cdn_nodes(_: ()) -> ExternResult<Vec<CdnNodeReputation>> {
     for link in links {
         if let Some(action_hash) = link.target.into_action_hash() {
             if let Some(record) = get(action_hash, GetOptions::default())? {
-                if let Some(rep) = record
-                    .entry()
-                    .to_app_option::<CdnNodeReputation>()
-                    .map_err(|e| wasm_error!(e))?
-                {
+                if let Some(rep) = read_cdn_reputation(&record)? {
                     nodes.push(rep);
                 }
             }
@@ -267,21 +419,122 @@ pub fn get_all_cdn_nodes(_: ()) -> ExternResult<Vec<CdnNodeReputation>> {
 #[hdk_extern]
 pub fn submit_quality_report(input: SubmitQualityReportInput) -> ExternResult<ActionHash> {
     let my_agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?;
+    let listener_weight = reporter_weight(listener_score(&my_agent, now)?);
+    let report = quality_report(&my_agent, input, now);
+    let mut tally = QualityTally::default();
+    tally.add(report.success, report.latency_ms, report.weight() * listener_weight);
+    let node = report.node.clone();
+
+    let action_hash = store_quality_report(report)?;
+
+    // Update CDN node reputation based on report
+    update_cdn_reputation(node, &tally)?;
+
+    Ok(action_hash)
+}
+
+/// Outcome of one report in a batch
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QualityReportResult {
+    /// Set when the report was stored
+    pub action_hash: Option<ActionHash>,
+    /// Why the report was rejected
+    pub error: Option<String>,
+}
+
+/// Submit a session's quality reports at once
+///
+/// Each report is checked on its own and invalid ones are skipped (see
+/// the per-item results). Valid reports are stored, then each node gets
+/// a single reputation update covering all of its reports.
+#[hdk_extern]
+pub fn submit_quality_reports_batch(
+    inputs: Vec<SubmitQualityReportInput>,
+) -> ExternResult<Vec<QualityReportResult>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let now = sys_time()?;
+    let listener_weight = reporter_weight(listener_score(&my_agent, now)?);
+
+    // Reports stored earlier in the batch count as prior for nonce reuse
+    let mut prior = my_quality_reports()?;
+    let mut results = Vec::with_capacity(inputs.len());
+    let mut tallies: BTreeMap<AgentPubKey, QualityTally> = BTreeMap::new();
+    for input in inputs {
+        let report = quality_report(&my_agent, input, now);
+        if let Err(reason) = check_quality_report(&report, &my_agent, now, &prior)? {
+            results.push(QualityReportResult {
+                action_hash: None,
+                error: Some(reason),
+            });
+            continue;
+        }
+
+        tallies
+            .entry(report.node.clone())
+            .or_default()
+            .add(report.success, report.latency_ms, report.weight() * listener_weight);
+        prior.push(report.clone());
+        results.push(QualityReportResult {
+            action_hash: Some(store_quality_report(report)?),
+            error: None,
+        });
+    }
+
+    for (node, tally) in tallies {
+        update_cdn_reputation(node, &tally)?;
+    }
+
+    Ok(results)
+}
+
+/// Signed quality reports I have made
+fn my_quality_reports() -> ExternResult<Vec<ServiceQualityReport>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::ServiceQualityReport.try_into()?)
+            .include_entries(true),
+    )?;
+
+    let mut reports = Vec::new();
+    for record in records {
+        if let Some(report) = record
+            .entry()
+            .to_app_option::<ServiceQualityReport>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            if report.challenge.is_some() {
+                reports.push(report);
+            }
+        }
+    }
+    Ok(reports)
+}
 
-    let report = ServiceQualityReport {
-        reporter: my_agent.clone(),
-        node: input.node.clone(),
+fn quality_report(
+    reporter: &AgentPubKey,
+    input: SubmitQualityReportInput,
+    reported_at: Timestamp,
+) -> ServiceQualityReport {
+    ServiceQualityReport {
+        reporter: reporter.clone(),
+        node: input.node,
         song_hash: input.song_hash,
         latency_ms: input.latency_ms,
         success: input.success,
         error_code: input.error_code,
-        reported_at: sys_time()?,
-    };
+        reported_at,
+        challenge: input.challenge,
+        node_signature: input.node_signature,
+    }
+}
 
+/// Store a report and link it to the reporter
+fn store_quality_report(report: ServiceQualityReport) -> ExternResult<ActionHash> {
+    let reports_path = Path::from(format!("quality_reports/{}", report.reporter));
     let action_hash = create_entry(&EntryTypes::ServiceQualityReport(report))?;
 
     // Link to reporter
-    let reports_path = Path::from(format!("quality_reports/{}", my_agent));
     reports_path.ensure()?;
     create_link(
         reports_path.path_entry_hash()?,
@@ -290,9 +543,6 @@ pub fn submit_quality_report(input: SubmitQualityReportInput) -> ExternResult<Ac
         (),
     )?;
 
-    // Update CDN node reputation based on report
-    update_cdn_reputation(input.node, input.success, input.latency_ms)?;
-
     Ok(action_hash)
 }
 
@@ -303,10 +553,39 @@ pub struct SubmitQualityReportInput {
     pub latency_ms: u32,
     pub success: bool,
     pub error_code: Option<String>,
+    /// Challenge the node signed with the result (see `sign_service_challenge`)
+    #[serde(default)]
+    pub challenge: Option<ServiceChallenge>,
+    #[serde(default)]
+    pub node_signature: Option<Vec<u8>>,
+}
+
+/// Sign a listener's service result (called by the serving CDN node)
+///
+/// Listeners include the signature in their quality report as proof the
+/// node actually served them, and with what outcome.
+#[hdk_extern]
+pub fn sign_service_challenge(result: SignedServiceResult) -> ExternResult<Vec<u8>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    if result.node != my_agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Can only sign results for requests this node served".into()
+        )));
+    }
+    if result.reporter == my_agent {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Cannot sign a result for a report on self".into()
+        )));
+    }
+    Ok(sign(my_agent, result)?.0.to_vec())
 }
 
-/// Update CDN reputation based on service report
-fn update_cdn_reputation(node: AgentPubKey, success: bool, latency_ms: u32) -> ExternResult<()> {
+/// Update CDN reputation from a tally of service reports
+///
+/// Report weights come from `ServiceQualityReport::weight`
+/// (`SIGNED_REPORT_WEIGHT` for node-signed reports, 1 otherwise) times the
+/// reporter's `reporter_weight`; uptime and latency are weighted averages.
+fn update_cdn_reputation(node: AgentPubKey, tally: &QualityTally) -> ExternResult<()> {
     let node_path = Path::from(format!("cdn_node/{}", node));
     let links = get_links(
         GetLinksInputBuilder::try_new(node_path.path_entry_hash()?, LinkTypes::NodeToReputation)?
@@ -316,37 +595,13 @@ fn update_cdn_reputation(node: AgentPubKey, success: bool, latency_ms: u32) -> E
     if let Some(link) = links.last() {
         if let Some(action_hash) = link.target.clone().into_action_hash() {
             if let Some(record) = get(action_hash.clone(), GetOptions::default())? {
-                if let Some(mut rep) = record
-                    .entry()
-                    .to_app_option::<CdnNodeReputation>()
-                    .map_err(|e| wasm_error!(e))?
-                {
+                if let Some(mut rep) = read_cdn_reputation(&record)? {
                     // Update stats
-                    if success {
-                        rep.successful_requests += 1;
-                        // Rolling average for latency
-                        let total_requests = rep.successful_requests + rep.failed_requests;
-                        rep.avg_latency_ms = ((rep.avg_latency_ms as u64 * (total_requests - 1)
-                            + latency_ms as u64)
-                            / total_requests) as u32;
-                    } else {
-                        rep.failed_requests += 1;
-                    }
-
-                    // Recalculate uptime
-                    let total = rep.successful_requests + rep.failed_requests;
-                    rep.uptime_bps = ((rep.successful_requests as f64 / total as f64) * 1000.0) as u32;
+                    rep.apply_tally(tally);
 
                     // Simple PoGQ score based on uptime and latency
                     let uptime_factor = rep.uptime_bps as f64 / 1000.0;
-                    let latency_factor = if rep.avg_latency_ms < 100 {
-                        1.0
-                    } else if rep.avg_latency_ms < 500 {
-                        0.8
-                    } else {
-                        0.5
-                    };
-                    rep.pogq_score = uptime_factor * latency_factor;
+                    rep.pogq_score = uptime_factor * latency_factor(rep.avg_latency_ms);
 
                     rep.last_active = sys_time()?;
 
@@ -369,23 +624,65 @@ fn update_cdn_reputation(node: AgentPubKey, success: bool, latency_ms: u32) -> E
     Ok(())
 }
 
+/// Score multiplier for a given latency
+fn latency_factor(latency_ms: u32) -> f64 {
+    if latency_ms < 100 {
+        1.0
+    } else if latency_ms < 500 {
+        0.8
+    } else {
+        0.5
+    }
+}
+
 /// Report Byzantine behavior
+///
+/// Play-farming accusations (fake plays, Sybil identities) are limited to
+/// high-trust listeners so fresh accounts can't mass-report each other.
+/// Each reporter may report an agent once per `byzantine_report_cooldown_secs`
+/// and never repeat a report.
 #[hdk_extern]
 pub fn report_byzantine_behavior(input: ReportByzantineInput) -> ExternResult<ActionHash> {
     let my_agent = agent_info()?.agent_initial_pubkey;
 
+    let farming_claim = matches!(
+        input.behavior_type,
+        ByzantineBehavior::FakePlayClaims | ByzantineBehavior::SybilAttack
+    );
+    if farming_claim && !is_high_trust_listener(my_agent.clone())? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only high-trust listeners can report fake plays or Sybil attacks".to_string()
+        )));
+    }
+
+    let now = sys_time()?;
     let report = ByzantineReport {
         reporter: my_agent,
         accused: input.accused,
         behavior_type: input.behavior_type,
         evidence: input.evidence,
         severity: input.severity,
-        reported_at: sys_time()?,
+        reported_at: now,
         status: ReportStatus::Pending,
     };
 
+    // Validation enforces this too; checking first gives a clear error
+    let cooldown_secs = trust_properties()?.byzantine_report_cooldown_secs;
+    check_report_cooldown(&report, now, &my_byzantine_reports()?, cooldown_secs)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+
+    let accused_path = accused_reports_path(&report.accused);
     let action_hash = create_entry(&EntryTypes::ByzantineReport(report))?;
 
+    // Index by the accused for dedup and quorum counting
+    accused_path.ensure()?;
+    create_link(
+        accused_path.path_entry_hash()?,
+        action_hash.clone(),
+        LinkTypes::AccusedToReports,
+        (),
+    )?;
+
     // Link to byzantine reports anchor
     let reports_path = Path::from("byzantine_reports");
     reports_path.ensure()?;
@@ -407,6 +704,222 @@ pub struct ReportByzantineInput {
     pub severity: u8,
 }
 
+/// Reports I have made, with the time each was created
+fn my_byzantine_reports() -> ExternResult<Vec<(Timestamp, ByzantineReport)>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::ByzantineReport.try_into()?)
+            .include_entries(true),
+    )?;
+
+    let mut reports = Vec::new();
+    for record in records {
+        if let Some(report) = record
+            .entry()
+            .to_app_option::<ByzantineReport>()
+            .map_err(|e| wasm_error!(e))?
+        {
+            reports.push((record.action().timestamp(), report));
+        }
+    }
+    Ok(reports)
+}
+
+/// Byzantine reports filed against an agent
+#[hdk_extern]
+pub fn get_reports_against(accused: AgentPubKey) -> ExternResult<Vec<ByzantineReport>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            accused_reports_path(&accused).path_entry_hash()?,
+            LinkTypes::AccusedToReports,
+        )?
+        .build(),
+    )?;
+
+    let mut reports = Vec::new();
+    for link in links {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(action_hash, GetOptions::default())? {
+                if let Some(report) = record
+                    .entry()
+                    .to_app_option::<ByzantineReport>()
+                    .map_err(|e| wasm_error!(e))?
+                {
+                    reports.push(report);
+                }
+            }
+        }
+    }
+    Ok(reports)
+}
+
+/// Whether enough distinct agents have reported an agent to act on it
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReportQuorum {
+    pub accused: AgentPubKey,
+    pub report_count: u32,
+    /// Reporters behind standing (not dismissed) reports
+    pub distinct_reporters: u32,
+    pub quorum: u32,
+    pub reached: bool,
+}
+
+/// Count an agent's reports toward the `byzantine_report_quorum`
+///
+/// Only distinct reporters count, so one agent repeating themselves can't
+/// manufacture a quorum.
+#[hdk_extern]
+pub fn get_report_quorum(accused: AgentPubKey) -> ExternResult<ReportQuorum> {
+    let reports = get_reports_against(accused.clone())?;
+    let quorum = trust_properties()?.byzantine_report_quorum;
+    let distinct_reporters = distinct_reporters(&reports);
+
+    Ok(ReportQuorum {
+        accused,
+        report_count: reports.len() as u32,
+        distinct_reporters,
+        quorum,
+        reached: distinct_reporters >= quorum,
+    })
+}
+
+fn listener_reputation_path(agent: &AgentPubKey) -> Path {
+    Path::from(format!("listener_reputation/{}", agent))
+}
+
+/// Latest version of a listener's aggregates with its action hash
+///
+/// Versions form an update chain from the linked create.
+fn latest_listener_reputation(
+    agent: &AgentPubKey,
+) -> ExternResult<Option<(ActionHash, ListenerReputation)>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(
+            listener_reputation_path(agent).path_entry_hash()?,
+            LinkTypes::ListenerToReputation,
+        )?
+        .build(),
+    )?;
+
+    let latest = links.into_iter().max_by_key(|link| link.timestamp);
+    if let Some(action_hash) = latest.and_then(|link| link.target.into_action_hash()) {
+        if let Some(Details::Record(details)) = get_details(action_hash, GetOptions::default())? {
+            if let Some(record) = latest_record(details)? {
+                return Ok(record
+                    .entry()
+                    .to_app_option::<ListenerReputation>()
+                    .map_err(|e| wasm_error!(e))?
+                    .map(|reputation| (record.action_address().clone(), reputation)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// A listener's reputation score (0 before their first play)
+fn listener_score(agent: &AgentPubKey, now: Timestamp) -> ExternResult<u32> {
+    Ok(latest_listener_reputation(agent)?
+        .map(|(_, reputation)| reputation.score(now))
+        .unwrap_or(0))
+}
+
+/// Fold one of my plays into my listener reputation
+///
+/// Called by the plays zome on each play with the play's create hash;
+/// one write per play keeps `get_listener_reputation` from ever scanning
+/// play history. Validation recomputes each version from the play it
+/// cites.
+#[hdk_extern]
+pub fn record_listener_play(play_hash: ActionHash) -> ExternResult<ActionHash> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let record = get(play_hash.clone(), GetOptions::default())?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Play not found".to_string())))?;
+    let play: ListenedPlay = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or_else(|| wasm_error!(WasmErrorInner::Guest("Invalid play".to_string())))?;
+    let played_at = record.action().timestamp();
+
+    let (previous, mut reputation) = match latest_listener_reputation(&my_agent)? {
+        Some((action_hash, reputation)) => (Some(action_hash), reputation),
+        None => (None, ListenerReputation::new(my_agent.clone(), played_at)),
+    };
+    reputation.add_play(&play.artist, play.duration_listened, play.song_duration, played_at);
+    reputation.last_play = Some(play_hash);
+
+    let entry = EntryTypes::ListenerReputation(reputation);
+    match previous {
+        Some(previous) => update_entry(previous, &entry),
+        None => {
+            let path = listener_reputation_path(&my_agent);
+            path.ensure()?;
+            let action_hash = create_entry(&entry)?;
+            create_link(
+                path.path_entry_hash()?,
+                action_hash.clone(),
+                LinkTypes::ListenerToReputation,
+                (),
+            )?;
+            Ok(action_hash)
+        }
+    }
+}
+
+/// A listener's reputation as seen by others
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ListenerReputationSummary {
+    pub listener: AgentPubKey,
+    /// 0-1000; see `ListenerReputation::score`
+    pub score: u32,
+    /// Whether `score` reaches the `high_trust_listener_score` property
+    pub high_trust: bool,
+    pub play_count: u64,
+    pub avg_completion_bps: u64,
+    /// Distinct artists played (counted up to `LISTENER_DIVERSE_ARTISTS`)
+    pub distinct_artists: u32,
+    /// None until the listener's first play
+    pub first_play_at: Option<Timestamp>,
+}
+
+/// Get a listener's reputation from their listening history
+#[hdk_extern]
+pub fn get_listener_reputation(agent: AgentPubKey) -> ExternResult<ListenerReputationSummary> {
+    let now = sys_time()?;
+    let threshold = trust_properties()?.high_trust_listener_score;
+
+    Ok(match latest_listener_reputation(&agent)? {
+        Some((_, reputation)) => {
+            let score = reputation.score(now);
+            ListenerReputationSummary {
+                listener: agent,
+                score,
+                high_trust: score >= threshold,
+                play_count: reputation.play_count,
+                avg_completion_bps: reputation.avg_completion_bps(),
+                distinct_artists: reputation.artists.len() as u32,
+                first_play_at: Some(reputation.first_play_at),
+            }
+        }
+        None => ListenerReputationSummary {
+            listener: agent,
+            score: 0,
+            high_trust: false,
+            play_count: 0,
+            avg_completion_bps: 0,
+            distinct_artists: 0,
+            first_play_at: None,
+        },
+    })
+}
+
+/// Whether a listener's reputation unlocks high-trust-only features
+#[hdk_extern]
+pub fn is_high_trust_listener(agent: AgentPubKey) -> ExternResult<bool> {
+    Ok(get_listener_reputation(agent)?.high_trust)
+}
+
 /// Get best CDN nodes for a region (for content routing)
 #[hdk_extern]
 pub fn get_best_nodes_for_region(region: String) -> ExternResult<Vec<CdnNodeReputation>> {
@@ -428,6 +941,113 @@ pub fn get_best_nodes_for_region(region: String) -> ExternResult<Vec<CdnNodeRepu
     Ok(regional_nodes.into_iter().take(5).collect())
 }
 
+/// Number of my most recent quality reports used for personalized ranking
+const PERSONAL_REPORT_WINDOW: usize = 50;
+
+/// Weight of the listener's own latency vs the global PoGQ score
+const PERSONAL_LATENCY_WEIGHT: f64 = 0.6;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetPersonalizedNodesInput {
+    /// Region used when I have no quality reports yet ("global" if unset)
+    pub fallback_region: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// CDN node ranked for a specific listener
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RankedCdnNode {
+    pub node: CdnNodeReputation,
+    /// Global PoGQ score
+    pub global_score: f64,
+    /// Average latency I observed (None if I never used this node)
+    pub observed_latency_ms: Option<u32>,
+    /// Number of my reports behind `observed_latency_ms`
+    pub observed_reports: u32,
+    /// Blend of my observed latency and the global score
+    pub personalized_score: f64,
+}
+
+/// Get CDN nodes ranked by the latency I have observed, blended with PoGQ
+#[hdk_extern]
+pub fn get_best_nodes_for_me(input: GetPersonalizedNodesInput) -> ExternResult<Vec<RankedCdnNode>> {
+    let my_agent = agent_info()?.agent_initial_pubkey;
+    let limit = input.limit.unwrap_or(5);
+
+    // Aggregate my recent successful reports per node
+    let reports_path = Path::from(format!("quality_reports/{}", my_agent));
+    let links = get_links(
+        GetLinksInputBuilder::try_new(reports_path.path_entry_hash()?, LinkTypes::AgentToReports)?
+            .build(),
+    )?;
+
+    let mut observed: std::collections::HashMap<AgentPubKey, (u64, u32)> =
+        std::collections::HashMap::new();
+    for link in links.into_iter().rev().take(PERSONAL_REPORT_WINDOW) {
+        if let Some(action_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(action_hash, GetOptions::default())? {
+                if let Some(report) = record
+                    .entry()
+                    .to_app_option::<ServiceQualityReport>()
+                    .map_err(|e| wasm_error!(e))?
+                {
+                    if report.success {
+                        let entry = observed.entry(report.node).or_insert((0, 0));
+                        entry.0 += report.latency_ms as u64;
+                        entry.1 += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // No history: fall back to region-based ranking
+    if observed.is_empty() {
+        let region = input.fallback_region.unwrap_or_else(|| "global".to_string());
+        return Ok(get_best_nodes_for_region(region)?
+            .into_iter()
+            .take(limit)
+            .map(|node| RankedCdnNode {
+                global_score: node.pogq_score,
+                personalized_score: node.pogq_score,
+                observed_latency_ms: None,
+                observed_reports: 0,
+                node,
+            })
+            .collect());
+    }
+
+    let mut ranked: Vec<RankedCdnNode> = get_all_cdn_nodes(())?
+        .into_iter()
+        .map(|node| {
+            let (observed_latency_ms, observed_reports) = match observed.get(&node.node) {
+                Some((total, count)) => (Some((total / *count as u64) as u32), *count),
+                None => (None, 0),
+            };
+            // Nodes I haven't used are judged by their global latency
+            let latency = observed_latency_ms.unwrap_or(node.avg_latency_ms);
+            let personalized_score = PERSONAL_LATENCY_WEIGHT * latency_factor(latency)
+                + (1.0 - PERSONAL_LATENCY_WEIGHT) * node.pogq_score;
+
+            RankedCdnNode {
+                global_score: node.pogq_score,
+                observed_latency_ms,
+                observed_reports,
+                personalized_score,
+                node,
+            }
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.personalized_score
+            .partial_cmp(&a.personalized_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(ranked.into_iter().take(limit).collect())
+}
+
 /// Get my trust claims (made by me)
 #[hdk_extern]
 pub fn get_my_trust_claims(_: ()) -> ExternResult<Vec<TrustClaim>> {
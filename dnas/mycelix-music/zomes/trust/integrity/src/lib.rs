@@ -3,9 +3,11 @@
 //! Implements Multi-Agent Trust Logic (MATL) for Mycelix Music.
 //! - Artist verification through web-of-trust
 //! - CDN node reputation scoring
+//! - Listener reputation from listening history
 //! - Byzantine detection integration from Mycelix-Core
 
 use hdi::prelude::*;
+use std::collections::BTreeMap;
 
 /// Trust claim - one agent vouches for another
 #[hdk_entry_helper]
@@ -61,6 +63,10 @@ pub struct VerificationStatus {
     pub vouch_count: u32,
     /// Last computed timestamp
     pub computed_at: Timestamp,
+    /// Latest versions of the trust claims the status was computed from,
+    /// revoked ones included
+    #[serde(default)]
+    pub claims: Vec<ActionHash>,
 }
 
 /// Verification tiers
@@ -79,10 +85,178 @@ pub enum VerificationTier {
     FoundingArtist,
 }
 
+/// Vouches needed for CommunityVerified
+pub const COMMUNITY_VOUCHES: u32 = 3;
+
+/// Vouches needed for Trusted
+pub const TRUSTED_VOUCHES: u32 = 10;
+
+/// Average confidence needed for Trusted (basis points)
+pub const TRUSTED_MIN_SCORE: u32 = 800;
+
+/// Length of the claim rate-limit window (microseconds)
+pub const CLAIM_WINDOW_MICROS: i64 = 24 * 60 * 60 * 1_000_000;
+
+/// DNA properties controlling web-of-trust verification
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TrustProperties {
+    /// Claims below this confidence don't count as vouches (basis points)
+    pub min_vouch_confidence_bps: u32,
+    /// Trust claims one agent may create per 24h
+    pub max_claims_per_day: u32,
+    /// Listener reputation score (0-1000) that counts as high trust
+    pub high_trust_listener_score: u32,
+    /// Seconds before a reporter may report the same agent again (0 for none)
+    pub byzantine_report_cooldown_secs: u64,
+    /// Distinct reporters needed before reports against an agent count
+    pub byzantine_report_quorum: u32,
+}
+
+impl Default for TrustProperties {
+    fn default() -> Self {
+        Self {
+            min_vouch_confidence_bps: 300,
+            max_claims_per_day: 20,
+            high_trust_listener_score: 600,
+            byzantine_report_cooldown_secs: 24 * 60 * 60,
+            byzantine_report_quorum: 3,
+        }
+    }
+}
+
+/// Parse trust settings from DNA properties; defaults if none are set
+///
+/// Malformed properties are an error rather than the defaults, so a typo in
+/// a threshold or quorum can't go unnoticed.
+pub fn parse_trust_properties(properties: SerializedBytes) -> Result<TrustProperties, String> {
+    if properties_unset(&properties) {
+        return Ok(TrustProperties::default());
+    }
+    TrustProperties::try_from(properties)
+        .map_err(|e| format!("Malformed trust DNA properties: {}", e))
+}
+
+/// Read trust settings from DNA properties
+pub fn trust_properties() -> ExternResult<TrustProperties> {
+    parse_trust_properties(dna_info()?.modifiers.properties)
+        .map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))
+}
+
+/// Whether the DNA manifest sets no properties (empty, or msgpack nil)
+fn properties_unset(properties: &SerializedBytes) -> bool {
+    matches!(properties.bytes().as_slice(), [] | [0xc0])
+}
+
+/// Trust score, tier and vouch count from an agent's claims
+///
+/// Each voucher counts once, by their most recent active claim, so one
+/// agent can't promote another by vouching repeatedly. Only vouches at or
+/// above `min_confidence_bps` are tallied, so a pile of
+/// near-zero-confidence vouches can't promote an agent either.
+pub fn compute_verification<'a>(
+    claims: impl IntoIterator<Item = &'a TrustClaim>,
+    min_confidence_bps: u32,
+) -> (u32, VerificationTier, u32) {
+    let mut latest: BTreeMap<&AgentPubKey, &TrustClaim> = BTreeMap::new();
+    for claim in claims.into_iter().filter(|claim| claim.active) {
+        let newer = latest
+            .get(&claim.from)
+            .map_or(true, |kept| claim.created_at > kept.created_at);
+        if newer {
+            latest.insert(&claim.from, claim);
+        }
+    }
+    let counted: Vec<u32> = latest
+        .values()
+        .map(|claim| claim.confidence_bps)
+        .filter(|&c| c >= min_confidence_bps)
+        .collect();
+
+    let vouch_count = counted.len() as u32;
+    let trust_score = if vouch_count > 0 {
+        counted.iter().sum::<u32>() / vouch_count
+    } else {
+        0
+    };
+
+    let tier = if vouch_count >= TRUSTED_VOUCHES && trust_score >= TRUSTED_MIN_SCORE {
+        VerificationTier::Trusted
+    } else if vouch_count >= COMMUNITY_VOUCHES {
+        VerificationTier::CommunityVerified
+    } else {
+        VerificationTier::Unverified
+    };
+
+    (trust_score, tier, vouch_count)
+}
+
+/// Check a verification status against the claims it cites, given with
+/// the agent who wrote each
+///
+/// The status must be written by one of those claimants, every claim must
+/// be about the status's artist, and the score, tier and vouch count must
+/// be what the active claims add up to, one vouch per claimant.
+pub fn check_verification_status(
+    status: &VerificationStatus,
+    author: &AgentPubKey,
+    claims: &[(AgentPubKey, TrustClaim)],
+    min_confidence_bps: u32,
+) -> Result<(), String> {
+    if claims
+        .iter()
+        .any(|(writer, claim)| writer != &claim.from || claim.to != status.artist)
+    {
+        return Err("Verification status must cite claims about its artist".to_string());
+    }
+    if !claims.iter().any(|(_, claim)| &claim.from == author) {
+        return Err("Verification status must be written by one of its vouchers".to_string());
+    }
+
+    let (trust_score, tier, vouch_count) =
+        compute_verification(claims.iter().map(|(_, claim)| claim), min_confidence_bps);
+    if status.trust_score != trust_score || status.tier != tier || status.vouch_count != vouch_count
+    {
+        return Err("Verification status does not match its cited claims".to_string());
+    }
+    Ok(())
+}
+
+/// Path indexing the trust claims made about an agent
+pub fn claims_received_path(agent: &AgentPubKey) -> Path {
+    Path::from(format!("claims_received/{}", agent))
+}
+
+/// Whether another claim at `now` would exceed the daily claim limit,
+/// given the author's earlier claim timestamps
+pub fn exceeds_daily_claim_limit(prior: &[Timestamp], now: Timestamp, max_per_day: u32) -> bool {
+    let in_window = prior
+        .iter()
+        .filter(|t| now.as_micros() - t.as_micros() < CLAIM_WINDOW_MICROS)
+        .count();
+    in_window >= max_per_day as usize
+}
+
+/// Current CdnNodeReputation schema version
+///
+/// - v0: original layout (no `schema_version` field)
+/// - v1: adds `schema_version`
+/// - v2: adds `weighted_successes` / `weighted_failures`
+pub const CDN_REPUTATION_SCHEMA_VERSION: u8 = 2;
+
+/// Weight of a node-signed quality report relative to an unsigned one
+pub const SIGNED_REPORT_WEIGHT: u64 = 10;
+
+/// How long a node's signed service challenge stays usable (microseconds)
+pub const CHALLENGE_WINDOW_MICROS: i64 = 60 * 60 * 1_000_000;
+
 /// CDN node reputation
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
 pub struct CdnNodeReputation {
+    /// Entry schema version (missing on v0 entries)
+    #[serde(default)]
+    pub schema_version: u8,
     /// Node's agent pub key
     pub node: AgentPubKey,
     /// Ethereum address (for staking/rewards)
@@ -109,6 +283,209 @@ pub struct CdnNodeReputation {
     pub stake_amount: u64,
     /// Slashing events
     pub slash_count: u32,
+    /// Report weight behind successful requests (signed reports count more)
+    #[serde(default)]
+    pub weighted_successes: u64,
+    /// Report weight behind failed requests
+    #[serde(default)]
+    pub weighted_failures: u64,
+    /// HTTP gateway serving `/ipfs/{cid}`, for server-side streaming
+    #[serde(default)]
+    pub gateway_url: Option<String>,
+}
+
+impl CdnNodeReputation {
+    /// Upgrade an entry of any prior schema version in memory
+    pub fn upgraded(mut self) -> Self {
+        // v0 -> v1: nothing to backfill
+        // v1 -> v2: earlier reports were all unsigned (weight 1)
+        if self.schema_version < 2 {
+            self.weighted_successes = self.successful_requests;
+            self.weighted_failures = self.failed_requests;
+        }
+        self.schema_version = CDN_REPUTATION_SCHEMA_VERSION;
+        self
+    }
+
+    /// Fold a tally of reports into the counters, the weighted latency
+    /// average and uptime
+    pub fn apply_tally(&mut self, tally: &QualityTally) {
+        if tally.weighted_successes > 0 {
+            let prior_weight = self.weighted_successes;
+            self.weighted_successes += tally.weighted_successes;
+            self.avg_latency_ms = ((self.avg_latency_ms as u64 * prior_weight
+                + tally.weighted_latency_ms)
+                / self.weighted_successes) as u32;
+        }
+        self.weighted_failures += tally.weighted_failures;
+        self.successful_requests += tally.successes;
+        self.failed_requests += tally.failures;
+
+        let total = self.weighted_successes + self.weighted_failures;
+        if total > 0 {
+            self.uptime_bps = ((self.weighted_successes as f64 / total as f64) * 1000.0) as u32;
+        }
+    }
+}
+
+/// Quality reports for one node summed, so any number of them updates
+/// its reputation in one write
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct QualityTally {
+    pub successes: u64,
+    pub failures: u64,
+    pub weighted_successes: u64,
+    pub weighted_failures: u64,
+    /// Sum of latency times weight over successful reports
+    pub weighted_latency_ms: u64,
+}
+
+impl QualityTally {
+    /// Count one report
+    pub fn add(&mut self, success: bool, latency_ms: u32, weight: u64) {
+        if success {
+            self.successes += 1;
+            self.weighted_successes += weight;
+            self.weighted_latency_ms += latency_ms as u64 * weight;
+        } else {
+            self.failures += 1;
+            self.weighted_failures += weight;
+        }
+    }
+}
+
+/// Plays a listener needs before their reputation counts in full
+pub const LISTENER_FULL_CONFIDENCE_PLAYS: u64 = 20;
+
+/// Days of listening history that earn the full account-age score
+pub const LISTENER_MATURE_DAYS: i64 = 90;
+
+/// Distinct artists that earn the full diversity score (and the most
+/// `ListenerReputation` tracks)
+pub const LISTENER_DIVERSE_ARTISTS: usize = 20;
+
+const DAY_MICROS: i64 = 24 * 60 * 60 * 1_000_000;
+
+/// Running listening aggregates behind a listener's reputation
+///
+/// Kept on the listener's chain and folded forward on each play, so a
+/// score never rescans play history. Each version folds in exactly one
+/// play, which it cites.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct ListenerReputation {
+    pub listener: AgentPubKey,
+    pub play_count: u64,
+    /// Sum of per-play completion (basis points, at most 10000 per play)
+    pub total_completion_bps: u64,
+    /// First distinct artists played, up to `LISTENER_DIVERSE_ARTISTS`
+    pub artists: Vec<AgentPubKey>,
+    pub first_play_at: Timestamp,
+    pub updated_at: Timestamp,
+    /// Create hash of the play this version folded in
+    #[serde(default)]
+    pub last_play: Option<ActionHash>,
+}
+
+/// Mirror of the fields of a plays `PlayRecord` that listener
+/// reputation is derived from
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct ListenedPlay {
+    pub artist: AgentPubKey,
+    pub duration_listened: u32,
+    pub song_duration: u32,
+}
+
+/// Integrity zome whose `PlayRecord`s listener reputation cites
+pub const PLAYS_INTEGRITY_ZOME: &str = "plays_integrity";
+
+/// Check a reputation version against the one before it (None for the
+/// first) and the play it cites, made at `played_at`
+///
+/// The version must be the previous aggregates with exactly that play
+/// folded in, and plays of the listener's own songs don't count.
+pub fn check_listener_play(
+    previous: Option<&ListenerReputation>,
+    updated: &ListenerReputation,
+    play: &ListenedPlay,
+    played_at: Timestamp,
+) -> Result<(), String> {
+    if play.artist == updated.listener {
+        return Err("Plays of your own songs don't count toward reputation".to_string());
+    }
+
+    let mut expected = previous
+        .cloned()
+        .unwrap_or_else(|| ListenerReputation::new(updated.listener.clone(), played_at));
+    expected.add_play(&play.artist, play.duration_listened, play.song_duration, played_at);
+    expected.last_play = updated.last_play.clone();
+    if &expected != updated {
+        return Err("Listener reputation must fold in exactly the play it cites".to_string());
+    }
+    Ok(())
+}
+
+impl ListenerReputation {
+    pub fn new(listener: AgentPubKey, now: Timestamp) -> Self {
+        Self {
+            listener,
+            play_count: 0,
+            total_completion_bps: 0,
+            artists: Vec::new(),
+            first_play_at: now,
+            updated_at: now,
+            last_play: None,
+        }
+    }
+
+    /// Fold one play into the aggregates
+    pub fn add_play(
+        &mut self,
+        artist: &AgentPubKey,
+        duration_listened: u32,
+        song_duration: u32,
+        at: Timestamp,
+    ) {
+        self.play_count += 1;
+        if song_duration > 0 {
+            self.total_completion_bps +=
+                (duration_listened as u64 * 10_000 / song_duration as u64).min(10_000);
+        }
+        if self.artists.len() < LISTENER_DIVERSE_ARTISTS && !self.artists.contains(artist) {
+            self.artists.push(artist.clone());
+        }
+        self.updated_at = at;
+    }
+
+    /// Average completion per play (basis points)
+    pub fn avg_completion_bps(&self) -> u64 {
+        if self.play_count == 0 {
+            0
+        } else {
+            self.total_completion_bps / self.play_count
+        }
+    }
+
+    /// Reputation score (0-1000) as of `now`
+    ///
+    /// Completion is worth up to 400, account age up to 300 and artist
+    /// diversity up to 300. The total is scaled down until the listener
+    /// has `LISTENER_FULL_CONFIDENCE_PLAYS` plays.
+    pub fn score(&self, now: Timestamp) -> u32 {
+        let completion = self.avg_completion_bps() * 400 / 10_000;
+        let age_days = ((now.as_micros() - self.first_play_at.as_micros()) / DAY_MICROS)
+            .clamp(0, LISTENER_MATURE_DAYS);
+        let age = age_days as u64 * 300 / LISTENER_MATURE_DAYS as u64;
+        let diversity = self.artists.len() as u64 * 300 / LISTENER_DIVERSE_ARTISTS as u64;
+        let confidence = self.play_count.min(LISTENER_FULL_CONFIDENCE_PLAYS);
+        ((completion + age + diversity) * confidence / LISTENER_FULL_CONFIDENCE_PLAYS) as u32
+    }
+}
+
+/// Multiplier (1-5) a reporter's listener reputation applies to the
+/// weight of their quality reports
+pub fn reporter_weight(listener_score: u32) -> u64 {
+    1 + listener_score.min(1000) as u64 / 250
 }
 
 /// Service quality report (for CDN nodes)
@@ -129,6 +506,53 @@ pub struct ServiceQualityReport {
     pub error_code: Option<String>,
     /// Timestamp
     pub reported_at: Timestamp,
+    /// Challenge the node signed when serving (signed reports only)
+    #[serde(default)]
+    pub challenge: Option<ServiceChallenge>,
+    /// Node's signature over the report's `SignedServiceResult`, proving
+    /// the interaction happened and how it went
+    #[serde(default)]
+    pub node_signature: Option<Vec<u8>>,
+}
+
+/// Challenge a listener issues when requesting content from a CDN node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServiceChallenge {
+    pub song_hash: ActionHash,
+    /// Random bytes chosen by the listener
+    pub nonce: Vec<u8>,
+    pub issued_at: Timestamp,
+}
+
+/// What a CDN node signs for a listener: the challenge, who served whom,
+/// and whether the request succeeded
+///
+/// Binding the parties and the outcome means a signature can't be
+/// replayed by another listener, against another node, or to turn a
+/// failure into a success.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedServiceResult {
+    pub node: AgentPubKey,
+    pub reporter: AgentPubKey,
+    pub challenge: ServiceChallenge,
+    pub success: bool,
+}
+
+impl ServiceQualityReport {
+    /// Decode the node signature (None if absent or malformed)
+    pub fn signature(&self) -> Option<Signature> {
+        let bytes: [u8; 64] = self.node_signature.as_deref()?.try_into().ok()?;
+        Some(Signature(bytes))
+    }
+
+    /// Weight this report carries in the node's reputation
+    pub fn weight(&self) -> u64 {
+        if self.node_signature.is_some() {
+            SIGNED_REPORT_WEIGHT
+        } else {
+            1
+        }
+    }
 }
 
 /// Byzantine behavior report
@@ -151,6 +575,51 @@ pub struct ByzantineReport {
     pub status: ReportStatus,
 }
 
+/// Index path for the Byzantine reports against an agent
+pub fn accused_reports_path(accused: &AgentPubKey) -> Path {
+    Path::from(format!("byzantine_reports/{}", accused))
+}
+
+/// Check a new report against the reporter's earlier reports, given with
+/// the time each was made
+///
+/// A reporter may report an agent again only after the cooldown, and never
+/// with the same behavior and evidence, so one agent can't harass another
+/// or pad a quorum by repeating themselves.
+pub fn check_report_cooldown(
+    report: &ByzantineReport,
+    at: Timestamp,
+    prior: &[(Timestamp, ByzantineReport)],
+    cooldown_secs: u64,
+) -> Result<(), String> {
+    let cooldown_micros = (cooldown_secs as i64).saturating_mul(1_000_000);
+    for (reported_at, earlier) in prior.iter().filter(|(_, r)| r.accused == report.accused) {
+        if earlier.behavior_type == report.behavior_type && earlier.evidence == report.evidence {
+            return Err("This report has already been made".to_string());
+        }
+        if at.as_micros() - reported_at.as_micros() < cooldown_micros {
+            return Err(format!(
+                "You can report the same agent at most once every {} seconds",
+                cooldown_secs
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Distinct reporters behind the reports that are still standing
+/// (dismissed reports don't count)
+pub fn distinct_reporters(reports: &[ByzantineReport]) -> u32 {
+    let mut reporters: Vec<&AgentPubKey> = reports
+        .iter()
+        .filter(|report| report.status != ReportStatus::Dismissed)
+        .map(|report| &report.reporter)
+        .collect();
+    reporters.sort();
+    reporters.dedup();
+    reporters.len() as u32
+}
+
 /// Types of Byzantine behavior
 #[hdk_entry_helper]
 #[derive(Clone, PartialEq)]
@@ -198,6 +667,10 @@ pub enum LinkTypes {
     AgentToReports,
     /// Byzantine reports anchor
     ByzantineReports,
+    /// listener_reputation/{agent} -> Listener reputation versions
+    ListenerToReputation,
+    /// byzantine_reports/{accused} -> Reports against them
+    AccusedToReports,
 }
 
 /// Entry types
@@ -209,6 +682,7 @@ pub enum EntryTypes {
     CdnNodeReputation(CdnNodeReputation),
     ServiceQualityReport(ServiceQualityReport),
     ByzantineReport(ByzantineReport),
+    ListenerReputation(ListenerReputation),
 }
 
 /// Validation
@@ -218,15 +692,36 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
         FlatOp::StoreEntry(store_entry) => match store_entry {
             OpEntry::CreateEntry { app_entry, action } => match app_entry {
                 EntryTypes::TrustClaim(claim) => validate_trust_claim(claim, action),
-                EntryTypes::VerificationStatus(_) => Ok(ValidateCallbackResult::Valid),
+                EntryTypes::VerificationStatus(status) => {
+                    validate_verification_status(&status, &action.author)
+                }
                 EntryTypes::CdnNodeReputation(rep) => validate_cdn_reputation(rep, action),
                 EntryTypes::ServiceQualityReport(report) => {
                     validate_quality_report(report, action)
                 }
                 EntryTypes::ByzantineReport(report) => validate_byzantine_report(report, action),
+                EntryTypes::ListenerReputation(reputation) => {
+                    validate_listener_reputation(&reputation, &action.author, None)
+                }
             },
+            OpEntry::UpdateEntry {
+                app_entry: EntryTypes::ListenerReputation(reputation),
+                action,
+                ..
+            } => validate_listener_reputation(
+                &reputation,
+                &action.author,
+                Some(&action.original_action_address),
+            ),
             _ => Ok(ValidateCallbackResult::Valid),
         },
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::AgentToClaimsReceived,
+            base_address,
+            target_address,
+            tag: _,
+            action,
+        } => validate_claim_received_link(base_address, target_address, action),
         _ => Ok(ValidateCallbackResult::Valid),
     }
 }
@@ -253,9 +748,115 @@ fn validate_trust_claim(claim: TrustClaim, action: Create) -> ExternResult<Valid
         ));
     }
 
+    // Limit vouch-spamming
+    let max_per_day = trust_properties()?.max_claims_per_day;
+    let prior = prior_claim_times(&action.author, &action.prev_action)?;
+    if exceeds_daily_claim_limit(&prior, action.timestamp, max_per_day) {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "At most {} trust claims per day",
+            max_per_day
+        )));
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
 
+/// Claims are indexed under their subject's path, by the claimant
+fn validate_claim_received_link(
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    action: CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    let target = match target_address.into_action_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Received claim links must point to a trust claim".to_string(),
+            ))
+        }
+    };
+    let record = must_get_valid_record(target)?;
+    let claim = match record
+        .entry()
+        .to_app_option::<TrustClaim>()
+        .map_err(|e| wasm_error!(e))?
+    {
+        Some(claim) => claim,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Received claim links must point to a trust claim".to_string(),
+            ))
+        }
+    };
+    if claim.from != action.author
+        || AnyLinkableHash::from(claims_received_path(&claim.to).path_entry_hash()?)
+            != base_address
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Received claim links must be made by the claimant from the subject's path"
+                .to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Timestamps of trust claims created earlier on an author's chain
+fn prior_claim_times(author: &AgentPubKey, until: &ActionHash) -> ExternResult<Vec<Timestamp>> {
+    let claim_def = ScopedEntryDefIndex::try_from(UnitEntryTypes::TrustClaim)?;
+    let activity = must_get_agent_activity(author.clone(), ChainFilter::new(until.clone()))?;
+
+    Ok(activity
+        .into_iter()
+        .filter_map(|item| match &item.action.hashed.content {
+            Action::Create(create) => match &create.entry_type {
+                EntryType::App(def)
+                    if def.zome_index == claim_def.zome_index
+                        && def.entry_index == claim_def.zome_type =>
+                {
+                    Some(create.timestamp)
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect())
+}
+
+/// Statuses must be backed by the claims they cite
+fn validate_verification_status(
+    status: &VerificationStatus,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let mut cited = status.claims.clone();
+    cited.sort();
+    cited.dedup();
+    if cited.len() != status.claims.len() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Verification status cites a claim more than once".to_string(),
+        ));
+    }
+
+    let mut claims = Vec::new();
+    for claim_hash in &status.claims {
+        let record = must_get_valid_record(claim_hash.clone())?;
+        let claim = match record.entry().to_app_option::<TrustClaim>() {
+            Ok(Some(claim)) => claim,
+            _ => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Verification status must cite trust claims".to_string(),
+                ))
+            }
+        };
+        claims.push((record.action().author().clone(), claim));
+    }
+
+    let min_confidence_bps = trust_properties()?.min_vouch_confidence_bps;
+    Ok(match check_verification_status(status, author, &claims, min_confidence_bps) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
 fn validate_cdn_reputation(
     rep: CdnNodeReputation,
     action: Create,
@@ -277,25 +878,215 @@ fn validate_cdn_reputation(
     Ok(ValidateCallbackResult::Valid)
 }
 
+/// Listeners keep their own aggregates, folding in one of their own plays
+/// per version
+fn validate_listener_reputation(
+    reputation: &ListenerReputation,
+    author: &AgentPubKey,
+    previous_hash: Option<&ActionHash>,
+) -> ExternResult<ValidateCallbackResult> {
+    if &reputation.listener != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Listener reputation must be kept by the listener".to_string(),
+        ));
+    }
+
+    let play_hash = match &reputation.last_play {
+        Some(play_hash) => play_hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Listener reputation must cite the play it folds in".to_string(),
+            ))
+        }
+    };
+    let play_record = must_get_valid_record(play_hash.clone())?;
+    let play = match read_listened_play(&play_record, author)? {
+        Some(play) => play,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Listener reputation must cite one of the listener's plays".to_string(),
+            ))
+        }
+    };
+
+    let previous = match previous_hash {
+        Some(previous_hash) => {
+            let record = must_get_valid_record(previous_hash.clone())?;
+            let previous = match record.entry().to_app_option::<ListenerReputation>() {
+                Ok(Some(previous)) => previous,
+                _ => {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "Listener reputation must update a listener reputation".to_string(),
+                    ))
+                }
+            };
+            // Plays are folded in chain order, so none is counted twice
+            if let Some(previous_play) = &previous.last_play {
+                let previous_seq = must_get_action(previous_play.clone())?.action().action_seq();
+                if play_record.action().action_seq() <= previous_seq {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "Listener reputation must fold in a later play".to_string(),
+                    ));
+                }
+            }
+            Some(previous)
+        }
+        None => None,
+    };
+
+    let played_at = play_record.action().timestamp();
+    Ok(match check_listener_play(previous.as_ref(), reputation, &play, played_at) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// Decode a plays `PlayRecord` created by `listener` (None if the record
+/// is anything else)
+fn read_listened_play(
+    record: &Record,
+    listener: &AgentPubKey,
+) -> ExternResult<Option<ListenedPlay>> {
+    let create = match record.action() {
+        Action::Create(create) if &create.author == listener => create,
+        _ => return Ok(None),
+    };
+    let zome_index = match &create.entry_type {
+        EntryType::App(def) => def.zome_index,
+        _ => return Ok(None),
+    };
+    let from_plays = dna_info()?
+        .zome_names
+        .get(zome_index.0 as usize)
+        .map_or(false, |name| name.0 == PLAYS_INTEGRITY_ZOME);
+    if !from_plays {
+        return Ok(None);
+    }
+    Ok(record.entry().to_app_option::<ListenedPlay>().ok().flatten())
+}
+
 fn validate_quality_report(
     report: ServiceQualityReport,
     action: Create,
 ) -> ExternResult<ValidateCallbackResult> {
+    let prior = if report.challenge.is_some() {
+        prior_quality_reports(&action.author, &action.prev_action)?
+    } else {
+        Vec::new()
+    };
+    Ok(match check_quality_report(&report, &action.author, action.timestamp, &prior)? {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// Check a quality report authored by `author` at `at`, given the
+/// author's earlier reports
+///
+/// Shared by validation and batch submission, which rejects bad reports
+/// individually instead of failing the whole batch.
+pub fn check_quality_report(
+    report: &ServiceQualityReport,
+    author: &AgentPubKey,
+    at: Timestamp,
+    prior: &[ServiceQualityReport],
+) -> ExternResult<Result<(), String>> {
     // Reporter must match author
-    if report.reporter != action.author {
-        return Ok(ValidateCallbackResult::Invalid(
-            "Reporter must match action author".to_string(),
-        ));
+    if &report.reporter != author {
+        return Ok(Err("Reporter must match action author".to_string()));
     }
 
     // Cannot report self
     if report.reporter == report.node {
-        return Ok(ValidateCallbackResult::Invalid(
-            "Cannot report on self".to_string(),
-        ));
+        return Ok(Err("Cannot report on self".to_string()));
     }
 
-    Ok(ValidateCallbackResult::Valid)
+    // Signed reports must carry a fresh challenge for this song, signed by the node
+    match (&report.challenge, &report.node_signature) {
+        (None, None) => Ok(Ok(())),
+        (Some(challenge), Some(_)) => {
+            if challenge.song_hash != report.song_hash {
+                return Ok(Err("Service challenge must be for the reported song".to_string()));
+            }
+            if challenge.nonce.is_empty() {
+                return Ok(Err("Service challenge must include a nonce".to_string()));
+            }
+            let age = at.as_micros() - challenge.issued_at.as_micros();
+            if !(0..=CHALLENGE_WINDOW_MICROS).contains(&age) {
+                return Ok(Err("Service challenge has expired".to_string()));
+            }
+            if let Err(reason) = check_challenge_unused(report, prior) {
+                return Ok(Err(reason));
+            }
+
+            let signature = match report.signature() {
+                Some(signature) => signature,
+                None => return Ok(Err("Node signature must be 64 bytes".to_string())),
+            };
+            let signed = SignedServiceResult {
+                node: report.node.clone(),
+                reporter: report.reporter.clone(),
+                challenge: challenge.clone(),
+                success: report.success,
+            };
+            if !verify_signature(report.node.clone(), signature, signed)? {
+                return Ok(Err("Invalid node signature".to_string()));
+            }
+
+            Ok(Ok(()))
+        }
+        _ => Ok(Err(
+            "A service challenge and node signature must be submitted together".to_string(),
+        )),
+    }
+}
+
+/// A node's signature counts once: a signed report may not reuse a nonce
+/// the reporter already cited in a signed report on the same node
+pub fn check_challenge_unused(
+    report: &ServiceQualityReport,
+    prior: &[ServiceQualityReport],
+) -> Result<(), String> {
+    let nonce = match &report.challenge {
+        Some(challenge) => &challenge.nonce,
+        None => return Ok(()),
+    };
+    let reused = prior.iter().any(|earlier| {
+        earlier.node == report.node
+            && earlier.challenge.as_ref().map_or(false, |c| &c.nonce == nonce)
+    });
+    if reused {
+        return Err("Service challenge nonce has already been used for this node".to_string());
+    }
+    Ok(())
+}
+
+/// Quality reports created earlier on an author's chain
+fn prior_quality_reports(
+    author: &AgentPubKey,
+    until: &ActionHash,
+) -> ExternResult<Vec<ServiceQualityReport>> {
+    let report_def = ScopedEntryDefIndex::try_from(UnitEntryTypes::ServiceQualityReport)?;
+    let activity = must_get_agent_activity(author.clone(), ChainFilter::new(until.clone()))?;
+
+    let mut reports = Vec::new();
+    for item in activity {
+        if let Action::Create(create) = &item.action.hashed.content {
+            let is_report = matches!(
+                &create.entry_type,
+                EntryType::App(def)
+                    if def.zome_index == report_def.zome_index
+                        && def.entry_index == report_def.zome_type
+            );
+            if is_report {
+                let entry = must_get_entry(create.entry_hash.clone())?;
+                if let Ok(report) = ServiceQualityReport::try_from(entry.content) {
+                    reports.push(report);
+                }
+            }
+        }
+    }
+    Ok(reports)
 }
 
 fn validate_byzantine_report(
@@ -330,5 +1121,375 @@ fn validate_byzantine_report(
         ));
     }
 
+    // No repeats against the same agent within the cooldown
+    let cooldown_secs = trust_properties()?.byzantine_report_cooldown_secs;
+    let prior = prior_reports(&action.author, &action.prev_action)?;
+    if let Err(reason) = check_report_cooldown(&report, action.timestamp, &prior, cooldown_secs) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
+
+/// Byzantine reports created earlier on an author's chain, with the time
+/// each was created
+fn prior_reports(
+    author: &AgentPubKey,
+    until: &ActionHash,
+) -> ExternResult<Vec<(Timestamp, ByzantineReport)>> {
+    let report_def = ScopedEntryDefIndex::try_from(UnitEntryTypes::ByzantineReport)?;
+    let activity = must_get_agent_activity(author.clone(), ChainFilter::new(until.clone()))?;
+
+    let mut reports = Vec::new();
+    for item in activity {
+        if let Action::Create(create) = &item.action.hashed.content {
+            let is_report = matches!(
+                &create.entry_type,
+                EntryType::App(def)
+                    if def.zome_index == report_def.zome_index
+                        && def.entry_index == report_def.zome_type
+            );
+            if is_report {
+                let entry = must_get_entry(create.entry_hash.clone())?;
+                if let Ok(report) = ByzantineReport::try_from(entry.content) {
+                    reports.push((create.timestamp, report));
+                }
+            }
+        }
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn below_threshold_claims_do_not_promote() {
+        // Hundreds of 1-bps vouches count for nothing
+        let claims = vouch_claims(&[1; 200]);
+        let (score, tier, vouches) = compute_verification(&claims, 300);
+        assert_eq!(vouches, 0);
+        assert_eq!(score, 0);
+        assert_eq!(tier, VerificationTier::Unverified);
+    }
+
+    #[test]
+    fn only_meaningful_claims_are_tallied() {
+        // Two real vouches plus spam: still below CommunityVerified
+        let claims = vouch_claims(&[900, 800, 10, 10, 10, 10]);
+        let (score, tier, vouches) = compute_verification(&claims, 300);
+        assert_eq!(vouches, 2);
+        assert_eq!(score, 850);
+        assert_eq!(tier, VerificationTier::Unverified);
+
+        let (_, tier, vouches) = compute_verification(&vouch_claims(&[900, 800, 300]), 300);
+        assert_eq!(vouches, 3);
+        assert_eq!(tier, VerificationTier::CommunityVerified);
+    }
+
+    #[test]
+    fn spam_does_not_dilute_trusted_score() {
+        let mut confidences = vec![900; 10];
+        confidences.extend([1; 50]);
+        let (score, tier, vouches) = compute_verification(&vouch_claims(&confidences), 300);
+        assert_eq!(vouches, 10);
+        assert_eq!(score, 900);
+        assert_eq!(tier, VerificationTier::Trusted);
+    }
+
+    #[test]
+    fn each_voucher_counts_once() {
+        // One agent vouching three times is one vouch, at their latest confidence
+        let mut claims = vouch_claims(&[900, 900, 900]);
+        for (i, claim) in claims.iter_mut().enumerate() {
+            claim.from = AgentPubKey::from_raw_36(vec![1; 36]);
+            claim.created_at = Timestamp::from_micros(i as i64);
+        }
+        claims[2].confidence_bps = 500;
+        let (score, tier, vouches) = compute_verification(&claims, 300);
+        assert_eq!(vouches, 1);
+        assert_eq!(score, 500);
+        assert_eq!(tier, VerificationTier::Unverified);
+
+        // A revoked latest claim falls back to their earlier active one
+        claims[2].active = false;
+        let (score, _, vouches) = compute_verification(&claims, 300);
+        assert_eq!((score, vouches), (900, 1));
+    }
+
+    /// One active claim about agent 0 per confidence, each from its own voucher
+    fn vouch_claims(confidences: &[u32]) -> Vec<TrustClaim> {
+        confidences
+            .iter()
+            .enumerate()
+            .map(|(i, &confidence)| claim(i as u8 + 1, 0, confidence).1)
+            .collect()
+    }
+
+    fn claim(from: u8, to: u8, confidence_bps: u32) -> (AgentPubKey, TrustClaim) {
+        let from = AgentPubKey::from_raw_36(vec![from; 36]);
+        let claim = TrustClaim {
+            from: from.clone(),
+            to: AgentPubKey::from_raw_36(vec![to; 36]),
+            claim_type: TrustClaimType::IdentityVerification,
+            confidence_bps,
+            evidence: None,
+            created_at: Timestamp::from_micros(0),
+            expires_at: None,
+            active: true,
+        };
+        (from, claim)
+    }
+
+    #[test]
+    fn verification_status_must_match_its_claims() {
+        let artist = AgentPubKey::from_raw_36(vec![9; 36]);
+        let claims = vec![claim(1, 9, 900), claim(2, 9, 800), claim(3, 9, 700)];
+        let status = VerificationStatus {
+            artist: artist.clone(),
+            trust_score: 800,
+            tier: VerificationTier::CommunityVerified,
+            vouch_count: 3,
+            computed_at: Timestamp::from_micros(0),
+            claims: Vec::new(),
+        };
+        let voucher = AgentPubKey::from_raw_36(vec![1; 36]);
+        assert!(check_verification_status(&status, &voucher, &claims, 300).is_ok());
+
+        // Only a voucher may write it, and never for a higher tier
+        assert!(check_verification_status(&status, &artist, &claims, 300).is_err());
+        let mut inflated = status.clone();
+        inflated.tier = VerificationTier::Trusted;
+        assert!(check_verification_status(&inflated, &voucher, &claims, 300).is_err());
+
+        // A revoked claim no longer counts
+        let mut revoked = claims.clone();
+        revoked[2].1.active = false;
+        assert!(check_verification_status(&status, &voucher, &revoked, 300).is_err());
+
+        // Claims about someone else, or rewritten by another agent, don't count
+        let mut foreign = claims.clone();
+        foreign[2] = claim(3, 8, 700);
+        assert!(check_verification_status(&status, &voucher, &foreign, 300).is_err());
+        let mut forged = claims;
+        forged[2].0 = artist;
+        assert!(check_verification_status(&status, &voucher, &forged, 300).is_err());
+    }
+
+    fn reputation() -> CdnNodeReputation {
+        CdnNodeReputation {
+            schema_version: CDN_REPUTATION_SCHEMA_VERSION,
+            node: AgentPubKey::from_raw_36(vec![1; 36]),
+            eth_address: String::new(),
+            ipfs_peer_id: String::new(),
+            region: "eu".to_string(),
+            bytes_served: 0,
+            successful_requests: 1,
+            failed_requests: 0,
+            avg_latency_ms: 100,
+            uptime_bps: 1000,
+            pogq_score: 1.0,
+            last_active: Timestamp::from_micros(0),
+            stake_amount: 0,
+            slash_count: 0,
+            weighted_successes: 1,
+            weighted_failures: 0,
+            gateway_url: None,
+        }
+    }
+
+    #[test]
+    fn batched_reports_update_reputation_once() {
+        let mut tally = QualityTally::default();
+        tally.add(true, 200, 1);
+        tally.add(true, 50, SIGNED_REPORT_WEIGHT);
+        tally.add(false, 0, 1);
+        assert_eq!(tally.successes, 2);
+        assert_eq!(tally.failures, 1);
+
+        let mut rep = reputation();
+        rep.apply_tally(&tally);
+        assert_eq!(rep.successful_requests, 3);
+        assert_eq!(rep.failed_requests, 1);
+        assert_eq!(rep.weighted_successes, 12);
+        assert_eq!(rep.weighted_failures, 1);
+        // (100 * 1 + 200 * 1 + 50 * 10) / 12
+        assert_eq!(rep.avg_latency_ms, 66);
+        // 12 of 13 weighted reports succeeded
+        assert_eq!(rep.uptime_bps, 923);
+    }
+
+    #[test]
+    fn failures_only_leave_latency_alone() {
+        let mut tally = QualityTally::default();
+        tally.add(false, 900, 1);
+
+        let mut rep = reputation();
+        rep.apply_tally(&tally);
+        assert_eq!(rep.avg_latency_ms, 100);
+        assert_eq!(rep.uptime_bps, 500);
+    }
+
+    #[test]
+    fn daily_claim_limit() {
+        let now = Timestamp::from_micros(10 * CLAIM_WINDOW_MICROS);
+        let recent: Vec<Timestamp> = (1..=3)
+            .map(|i| Timestamp::from_micros(now.as_micros() - i * 1_000_000))
+            .collect();
+        assert!(exceeds_daily_claim_limit(&recent, now, 3));
+        assert!(!exceeds_daily_claim_limit(&recent, now, 4));
+
+        // Claims older than a day don't count
+        let old = vec![Timestamp::from_micros(now.as_micros() - CLAIM_WINDOW_MICROS); 5];
+        assert!(!exceeds_daily_claim_limit(&old, now, 1));
+    }
+
+    fn signed_report(node: u8, nonce: u8) -> ServiceQualityReport {
+        ServiceQualityReport {
+            reporter: AgentPubKey::from_raw_36(vec![1; 36]),
+            node: AgentPubKey::from_raw_36(vec![node; 36]),
+            song_hash: ActionHash::from_raw_36(vec![7; 36]),
+            latency_ms: 120,
+            success: true,
+            error_code: None,
+            reported_at: Timestamp::from_micros(0),
+            challenge: Some(ServiceChallenge {
+                song_hash: ActionHash::from_raw_36(vec![7; 36]),
+                nonce: vec![nonce; 16],
+                issued_at: Timestamp::from_micros(0),
+            }),
+            node_signature: Some(vec![0; 64]),
+        }
+    }
+
+    #[test]
+    fn challenge_nonce_counts_once_per_node() {
+        let prior = vec![signed_report(2, 1)];
+        assert!(check_challenge_unused(&signed_report(2, 1), &prior).is_err());
+        assert!(check_challenge_unused(&signed_report(2, 2), &prior).is_ok());
+        assert!(check_challenge_unused(&signed_report(3, 1), &prior).is_ok());
+    }
+
+    fn byzantine_report(reporter: u8, accused: u8, evidence: &str) -> ByzantineReport {
+        ByzantineReport {
+            reporter: AgentPubKey::from_raw_36(vec![reporter; 36]),
+            accused: AgentPubKey::from_raw_36(vec![accused; 36]),
+            behavior_type: ByzantineBehavior::ContentCorruption,
+            evidence: evidence.to_string(),
+            severity: 50,
+            reported_at: Timestamp::from_micros(0),
+            status: ReportStatus::Pending,
+        }
+    }
+
+    #[test]
+    fn second_report_within_cooldown_is_rejected() {
+        let day = 24 * 60 * 60;
+        let first_at = Timestamp::from_micros(0);
+        let prior = vec![(first_at, byzantine_report(1, 2, "bad chunk at 0x10"))];
+
+        let again = byzantine_report(1, 2, "bad chunk at 0x20");
+        let an_hour_later = Timestamp::from_micros(3600 * 1_000_000);
+        assert!(check_report_cooldown(&again, an_hour_later, &prior, day).is_err());
+        // Someone else may still be reported
+        let other = byzantine_report(1, 3, "bad chunk at 0x20");
+        assert!(check_report_cooldown(&other, an_hour_later, &prior, day).is_ok());
+
+        let next_day = Timestamp::from_micros(day as i64 * 1_000_000);
+        assert!(check_report_cooldown(&again, next_day, &prior, day).is_ok());
+        // The identical report is never accepted twice
+        let repeat = byzantine_report(1, 2, "bad chunk at 0x10");
+        assert!(check_report_cooldown(&repeat, next_day, &prior, day).is_err());
+        assert!(check_report_cooldown(&repeat, next_day, &prior, 0).is_err());
+    }
+
+    #[test]
+    fn quorum_counts_distinct_reporters() {
+        let mut reports = vec![
+            byzantine_report(1, 9, "a"),
+            byzantine_report(1, 9, "b"),
+            byzantine_report(1, 9, "c"),
+            byzantine_report(2, 9, "a"),
+        ];
+        assert_eq!(distinct_reporters(&reports), 2);
+
+        let mut dismissed = byzantine_report(3, 9, "a");
+        dismissed.status = ReportStatus::Dismissed;
+        reports.push(dismissed);
+        assert_eq!(distinct_reporters(&reports), 2);
+    }
+
+    #[test]
+    fn listener_score_rewards_completion_age_and_diversity() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let start = Timestamp::from_micros(0);
+
+        // 20 full listens across 20 artists, 90 days on: full marks
+        let mut seasoned = ListenerReputation::new(listener.clone(), start);
+        for i in 0..20u8 {
+            seasoned.add_play(&AgentPubKey::from_raw_36(vec![i + 2; 36]), 180, 180, start);
+        }
+        let later = Timestamp::from_micros(LISTENER_MATURE_DAYS * DAY_MICROS);
+        assert_eq!(seasoned.score(later), 1000);
+        assert_eq!(reporter_weight(seasoned.score(later)), 5);
+
+        // A fresh account looping one artist's songs at the threshold
+        let mut farmer = ListenerReputation::new(listener, start);
+        let artist = AgentPubKey::from_raw_36(vec![2; 36]);
+        for _ in 0..500 {
+            farmer.add_play(&artist, 31, 180, start);
+        }
+        assert_eq!(farmer.artists.len(), 1);
+        // 17% completion (68) plus one artist (15)
+        assert_eq!(farmer.score(start), 83);
+        assert_eq!(reporter_weight(farmer.score(start)), 1);
+    }
+
+    #[test]
+    fn reputation_versions_fold_in_exactly_their_play() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let artist = AgentPubKey::from_raw_36(vec![2; 36]);
+        let play = ListenedPlay {
+            artist: artist.clone(),
+            duration_listened: 90,
+            song_duration: 180,
+        };
+        let at = Timestamp::from_micros(5);
+
+        let mut first = ListenerReputation::new(listener.clone(), at);
+        first.add_play(&artist, 90, 180, at);
+        first.last_play = Some(ActionHash::from_raw_36(vec![7; 36]));
+        assert!(check_listener_play(None, &first, &play, at).is_ok());
+
+        let mut next = first.clone();
+        next.add_play(&artist, 90, 180, at);
+        assert!(check_listener_play(Some(&first), &next, &play, at).is_ok());
+
+        // Self-asserted aggregates are rejected
+        let mut inflated = next.clone();
+        inflated.play_count = 500;
+        assert!(check_listener_play(Some(&first), &inflated, &play, at).is_err());
+        let mut backdated = first.clone();
+        backdated.first_play_at = Timestamp::from_micros(0);
+        assert!(check_listener_play(None, &backdated, &play, at).is_err());
+
+        // Plays of your own songs don't count
+        let own = ListenedPlay { artist: listener, ..play };
+        assert!(check_listener_play(None, &first, &own, at).is_err());
+    }
+
+    #[test]
+    fn few_plays_scale_the_listener_score_down() {
+        let listener = AgentPubKey::from_raw_36(vec![1; 36]);
+        let start = Timestamp::from_micros(0);
+        let mut newcomer = ListenerReputation::new(listener, start);
+        assert_eq!(newcomer.score(start), 0);
+
+        // 5 of 20 plays: a quarter of 400 + 75
+        for i in 0..5u8 {
+            newcomer.add_play(&AgentPubKey::from_raw_36(vec![i + 2; 36]), 200, 200, start);
+        }
+        assert_eq!(newcomer.score(start), 118);
+    }
+}